@@ -0,0 +1,35 @@
+// Arma una torre con el builder y la guarda como PNG sin abrir ventana:
+//
+//     cargo run --example api_usage -- torre.png
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::{DioramaBuilder, Renderer, Vec3};
+
+const FLOORS: usize = 6;
+const BLOCK: f32 = 0.25;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let output = std::env::args().nth(1).unwrap_or_else(|| "torre.png".to_string());
+
+    // Base de césped con un pilar de obsidiana coronado por lava
+    let mut builder = DioramaBuilder::new()
+        .add_cube_in(Vec3::new(-1.0, -0.25, -1.0), Vec3::new(1.0, 0.0, 1.0), "grass", "base")
+        .camera(Vec3::new(2.2, 1.4, 3.0), Vec3::new(0.0, 0.7, 0.0))
+        .day_time(0.3);
+
+    for floor in 0..FLOORS {
+        let y = floor as f32 * BLOCK;
+        // Los pisos se angostan un poco hacia arriba
+        let half = BLOCK * (1.0 - floor as f32 * 0.08);
+        builder = builder.add_cube(Vec3::new(-half, y, -half), Vec3::new(half, y + BLOCK, half), "obsidian");
+    }
+    let top = FLOORS as f32 * BLOCK;
+    builder = builder.add_cube(Vec3::new(-0.1, top, -0.1), Vec3::new(0.1, top + 0.1, 0.1), "lava");
+    // Las esferas van en la misma lista que los cubos
+    builder = builder.add_sphere(Vec3::new(0.6, 0.18, 0.55), 0.18, "lava");
+
+    let scene = builder.build()?;
+    let image = Renderer::new(RenderSettings::default()).size(400, 300).render(&scene, &scene.camera);
+    image.save(&output)?;
+    println!("Torre guardada en {}", output);
+    Ok(())
+}
@@ -0,0 +1,58 @@
+// Compara el tiempo de render recorriendo la lista entera de objetos y con el
+// árbol de cajas, sobre una grilla de unos cientos de cubos:
+//
+//     cargo run --release --example bvh_benchmark -- 20
+use std::time::{Duration, Instant};
+
+use raycasting_diorama::bvh;
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::renderer::{render, sky_color};
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::{DioramaBuilder, Vec3};
+
+const BLOCK: f32 = 0.1;
+const RUNS: u32 = 3;
+
+// Mejor tiempo de unos cuantos renders, para que no pese la primera pasada
+fn time_render(scene: &Scene, settings: &RenderSettings) -> Duration {
+    let mut framebuffer = Framebuffer::new(400, 300);
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            render(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, sky_color(&scene.lights), settings);
+            start.elapsed()
+        })
+        .min()
+        .expect("hay al menos un render")
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let side: usize = std::env::args().nth(1).and_then(|text| text.parse().ok()).unwrap_or(20);
+
+    // Columnas de alturas distintas, así las sombras no caen todas en el mismo lugar
+    let half = side as f32 * BLOCK * 0.5;
+    let mut builder = DioramaBuilder::new()
+        .camera(Vec3::new(half * 1.6, half * 1.2, half * 2.0), Vec3::new(0.0, 0.0, 0.0))
+        .day_time(0.3);
+    for x in 0..side {
+        for z in 0..side {
+            let height = BLOCK * (1 + (x * 7 + z * 3) % 5) as f32;
+            let min = Vec3::new(x as f32 * BLOCK - half, 0.0, z as f32 * BLOCK - half);
+            let material = if (x + z) % 2 == 0 { "grass" } else { "obsidian" };
+            builder = builder.add_cube(min, min + Vec3::new(BLOCK, height, BLOCK), material);
+        }
+    }
+    let scene = builder.build()?;
+
+    let mut settings = RenderSettings { bias: scene.default_bias(), ..RenderSettings::default() };
+    let linear = time_render(&scene, &settings);
+    bvh::refresh(&mut settings.bvh, &scene.objects);
+    let traced = time_render(&scene, &settings);
+
+    println!("{} cubos", scene.objects.len());
+    println!("lista: {:.1} ms", linear.as_secs_f64() * 1000.0);
+    println!("árbol: {:.1} ms", traced.as_secs_f64() * 1000.0);
+    println!("{:.1}x más rápido", linear.as_secs_f64() / traced.as_secs_f64());
+    Ok(())
+}
@@ -0,0 +1,86 @@
+// Mide lo que cuestan las texturas: consultas sueltas a la textura de pasto
+// contra las mismas consultas a través de `DynamicImage::get_pixel`, que era
+// como se leían antes, lo que cuesta clonarla, y el frame del diorama
+// integrado con y sin texturas:
+//
+//     cargo run --release --example texture_benchmark
+use image::{GenericImageView, Rgba};
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use raycasting_diorama::color::Color;
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::renderer::{render, sky_color};
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::texture::Texture;
+
+const RUNS: u32 = 5;
+const LOOKUPS: u32 = 4_000_000;
+
+// Mejor tiempo de unas cuantas pasadas, para que no pese la primera
+fn best_of(mut run: impl FnMut()) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            run();
+            start.elapsed()
+        })
+        .min()
+        .expect("hay al menos una pasada")
+}
+
+// Coordenadas repartidas por toda la imagen sin seguir las filas
+fn uv(i: u32) -> (f32, f32) {
+    ((i as f32 * 0.618_034).fract(), (i as f32 * 0.414_213).fract())
+}
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let path = "assets/grass.jpg";
+    let texture = Texture::new(path)?;
+    let image = image::open(path)?;
+    let (width, height) = image.dimensions();
+
+    let flat = best_of(|| {
+        for i in 0..LOOKUPS {
+            let (u, v) = uv(i);
+            black_box(texture.get_color(u, v));
+        }
+    });
+    let dynamic = best_of(|| {
+        for i in 0..LOOKUPS {
+            let (u, v) = uv(i);
+            // Las mismas cuentas que hace `get_color`
+            let x = (((u % 1.0) * width as f32) as u32).min(width - 1);
+            let y = (((1.0 - (v % 1.0)) * height as f32) as u32).min(height - 1);
+            let Rgba([r, g, b, _a]) = image.get_pixel(x, y);
+            black_box(Color::new(r, g, b));
+        }
+    });
+    let clones = 10_000;
+    let shared = best_of(|| (0..clones).for_each(|_| drop(black_box(texture.clone()))));
+    let copied = best_of(|| (0..clones).for_each(|_| drop(black_box(image.clone()))));
+
+    let scene = Scene::diorama();
+    let mut untextured = Scene::diorama();
+    for object in untextured.objects.iter_mut() {
+        object.material_mut().texture = None;
+    }
+    let frame = |scene: &Scene| {
+        let settings = RenderSettings { bias: scene.default_bias(), ..RenderSettings::default() };
+        let mut framebuffer = Framebuffer::new(800, 600);
+        best_of(|| {
+            render(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, sky_color(&scene.lights), &settings);
+        })
+    };
+    let (textured, plain) = (frame(&scene), frame(&untextured));
+
+    let per_lookup = |time: Duration| time.as_secs_f64() * 1e9 / LOOKUPS as f64;
+    println!("{} consultas a {} ({}x{})", LOOKUPS, path, width, height);
+    println!("búfer RGBA8: {:.1} ns por consulta", per_lookup(flat));
+    println!("DynamicImage::get_pixel: {:.1} ns por consulta", per_lookup(dynamic));
+    let per_clone = |time: Duration| time.as_secs_f64() * 1e9 / clones as f64;
+    println!("clonar: {:.0} ns la textura, {:.0} ns la DynamicImage", per_clone(shared), per_clone(copied));
+    println!("frame de 800x600 del diorama: {:.1} ms con texturas, {:.1} ms sin ellas", textured.as_secs_f64() * 1000.0, plain.as_secs_f64() * 1000.0);
+    Ok(())
+}
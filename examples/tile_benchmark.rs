@@ -0,0 +1,43 @@
+// Compara el frame de 800x600 del diorama integrado con teselas de distintos
+// tamaños; las de un píxel reparten cada píxel como una tarea aparte:
+//
+//     cargo run --release --example tile_benchmark
+use std::time::{Duration, Instant};
+
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::renderer::{render, sky_color};
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+
+const RUNS: u32 = 5;
+const TILE_SIZES: [usize; 5] = [1, 8, 16, 32, 64];
+
+// Mejor tiempo de unos cuantos renders, para que no pese la primera pasada
+fn time_render(scene: &Scene, settings: &RenderSettings) -> Duration {
+    let mut framebuffer = Framebuffer::new(800, 600);
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            render(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, sky_color(&scene.lights), settings);
+            start.elapsed()
+        })
+        .min()
+        .expect("hay al menos un render")
+}
+
+fn main() {
+    let scene = Scene::diorama();
+    println!("{} hilos", rayon::current_num_threads());
+    let times: Vec<Duration> = TILE_SIZES
+        .iter()
+        .map(|&tile_size| time_render(&scene, &RenderSettings { bias: scene.default_bias(), tile_size, ..RenderSettings::default() }))
+        .collect();
+    for (tile_size, time) in TILE_SIZES.into_iter().zip(&times) {
+        println!(
+            "teselas de {:>2}: {:.1} ms ({:.2}x)",
+            tile_size,
+            time.as_secs_f64() * 1000.0,
+            times[0].as_secs_f64() / time.as_secs_f64()
+        );
+    }
+}
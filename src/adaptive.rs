@@ -0,0 +1,217 @@
+use rayon::prelude::*;
+
+use crate::color::Color;
+use crate::profile;
+
+// Resultado de trazar un rayo primario: lo necesario para decidir si dos
+// muestras vecinas pertenecen a la misma superficie
+#[derive(Debug, Clone, Copy)]
+pub struct Sample {
+    pub color: Color,
+    pub alpha: u8,
+    pub object: Option<usize>,
+    pub depth: f32,
+    // Brillo que aporta al halo de los objetos emisivos (negro si no aporta)
+    pub glow: Color,
+}
+
+impl Default for Sample {
+    fn default() -> Self {
+        Sample {
+            color: Color::black(),
+            alpha: 0,
+            object: None,
+            depth: f32::INFINITY,
+            glow: Color::black(),
+        }
+    }
+}
+
+// Muestreo adaptativo: se trazan las esquinas de cada bloque de pantalla y
+// solo se subdivide donde difieren; el resto se rellena interpolando. Un
+// detalle más pequeño que un bloque y que no toque ninguna esquina puede
+// perderse, así que el umbral y el tamaño de bloque controlan el error.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct AdaptiveSampling {
+    // Lado del bloque inicial en píxeles (se redondea a potencia de 2)
+    pub tile_size: usize,
+    // Diferencia máxima por canal (0-255) entre esquinas para interpolar
+    pub threshold: f32,
+}
+
+impl Default for AdaptiveSampling {
+    fn default() -> Self {
+        AdaptiveSampling {
+            tile_size: 16,
+            threshold: 8.0,
+        }
+    }
+}
+
+// Diferencia relativa de profundidad a partir de la cual hay discontinuidad
+const DEPTH_TOLERANCE: f32 = 0.05;
+
+fn channel_spread(corners: &[Sample; 4], channel: fn(&Color) -> u8) -> f32 {
+    let values = corners.iter().map(|sample| channel(&sample.color));
+    let max = values.clone().max().unwrap_or(0);
+    let min = values.min().unwrap_or(0);
+    (max - min) as f32
+}
+
+impl AdaptiveSampling {
+    fn similar(&self, corners: &[Sample; 4]) -> bool {
+        if corners.iter().any(|sample| sample.object != corners[0].object) {
+            return false;
+        }
+
+        if corners[0].object.is_some() {
+            let near = corners.iter().map(|s| s.depth).fold(f32::INFINITY, f32::min);
+            let far = corners.iter().map(|s| s.depth).fold(0.0, f32::max);
+            if far - near > near * DEPTH_TOLERANCE {
+                return false;
+            }
+        }
+
+        channel_spread(corners, Color::red) <= self.threshold
+            && channel_spread(corners, Color::green) <= self.threshold
+            && channel_spread(corners, Color::blue) <= self.threshold
+    }
+
+    // Devuelve las muestras de toda la imagen y cuántos rayos primarios se trazaron
+    pub fn render<F>(&self, width: usize, height: usize, trace: &F) -> (Vec<Sample>, usize)
+    where
+        F: Fn(usize, usize) -> Sample + Sync,
+    {
+        let size = self.tile_size.max(1).next_power_of_two();
+        let tiles: Vec<(usize, usize)> = (0..height.div_ceil(size))
+            .flat_map(|ty| (0..width.div_ceil(size)).map(move |tx| (tx * size, ty * size)))
+            .collect();
+
+        let results: Vec<(usize, usize, Vec<Sample>, usize)> = tiles
+            .par_iter()
+            .enumerate()
+            .map(|(index, &(x0, y0))| {
+                let _chunk = profile::chunk("bloque", Some(index));
+                let mut tile = TileState {
+                    origin: (x0, y0),
+                    size,
+                    image: (width, height),
+                    samples: vec![Sample::default(); size * size],
+                    rays: 0,
+                };
+                let corners = [
+                    tile.trace(trace, x0, y0),
+                    tile.trace(trace, x0 + size, y0),
+                    tile.trace(trace, x0, y0 + size),
+                    tile.trace(trace, x0 + size, y0 + size),
+                ];
+                self.subdivide(&mut tile, trace, x0, y0, size, corners);
+                (x0, y0, tile.samples, tile.rays)
+            })
+            .collect();
+
+        let mut samples = vec![Sample::default(); width * height];
+        let mut rays = 0;
+        for (x0, y0, tile_samples, tile_rays) in results {
+            rays += tile_rays;
+            for ty in 0..size.min(height - y0) {
+                for tx in 0..size.min(width - x0) {
+                    samples[(y0 + ty) * width + x0 + tx] = tile_samples[ty * size + tx];
+                }
+            }
+        }
+        (samples, rays)
+    }
+
+    fn subdivide<F>(&self, tile: &mut TileState, trace: &F, x: usize, y: usize, size: usize, corners: [Sample; 4])
+    where
+        F: Fn(usize, usize) -> Sample + Sync,
+    {
+        if size == 1 {
+            tile.write(x, y, corners[0]);
+            return;
+        }
+
+        if self.similar(&corners) {
+            tile.interpolate(x, y, size, &corners);
+            return;
+        }
+
+        let half = size / 2;
+        let top = tile.trace(trace, x + half, y);
+        let left = tile.trace(trace, x, y + half);
+        let center = tile.trace(trace, x + half, y + half);
+        let right = tile.trace(trace, x + size, y + half);
+        let bottom = tile.trace(trace, x + half, y + size);
+
+        self.subdivide(tile, trace, x, y, half, [corners[0], top, left, center]);
+        self.subdivide(tile, trace, x + half, y, half, [top, corners[1], center, right]);
+        self.subdivide(tile, trace, x, y + half, half, [left, center, corners[2], bottom]);
+        self.subdivide(tile, trace, x + half, y + half, half, [center, right, bottom, corners[3]]);
+    }
+}
+
+struct TileState {
+    origin: (usize, usize),
+    size: usize,
+    image: (usize, usize),
+    samples: Vec<Sample>,
+    rays: usize,
+}
+
+impl TileState {
+    // Las esquinas fuera de la imagen se ajustan al borde
+    fn trace<F>(&mut self, trace: &F, x: usize, y: usize) -> Sample
+    where
+        F: Fn(usize, usize) -> Sample + Sync,
+    {
+        self.rays += 1;
+        trace(x.min(self.image.0 - 1), y.min(self.image.1 - 1))
+    }
+
+    fn write(&mut self, x: usize, y: usize, sample: Sample) {
+        let (tx, ty) = (x - self.origin.0, y - self.origin.1);
+        if tx < self.size && ty < self.size {
+            self.samples[ty * self.size + tx] = sample;
+        }
+    }
+
+    fn interpolate(&mut self, x: usize, y: usize, size: usize, corners: &[Sample; 4]) {
+        for py in 0..size {
+            for px in 0..size {
+                let fx = px as f32 / size as f32;
+                let fy = py as f32 / size as f32;
+                // Los fallos tienen profundidad infinita y no se interpolan
+                let depth = if corners[0].object.is_some() {
+                    let top_depth = corners[0].depth * (1.0 - fx) + corners[1].depth * fx;
+                    let bottom_depth = corners[2].depth * (1.0 - fx) + corners[3].depth * fx;
+                    top_depth * (1.0 - fy) + bottom_depth * fy
+                } else {
+                    f32::INFINITY
+                };
+                self.write(
+                    x + px,
+                    y + py,
+                    Sample {
+                        color: bilinear(corners.map(|corner| corner.color), fx, fy),
+                        alpha: corners[0].alpha,
+                        object: corners[0].object,
+                        depth,
+                        glow: bilinear(corners.map(|corner| corner.glow), fx, fy),
+                    },
+                );
+            }
+        }
+    }
+}
+
+// Interpola en punto flotante por canal para que las zonas planas no pierdan
+// un nivel por el truncado de Color * f32
+fn bilinear(corners: [Color; 4], fx: f32, fy: f32) -> Color {
+    let channel = |get: fn(&Color) -> u8| {
+        let top = get(&corners[0]) as f32 * (1.0 - fx) + get(&corners[1]) as f32 * fx;
+        let bottom = get(&corners[2]) as f32 * (1.0 - fx) + get(&corners[3]) as f32 * fx;
+        (top * (1.0 - fy) + bottom * fy).round().clamp(0.0, 255.0) as u8
+    };
+    Color::new(channel(Color::red), channel(Color::green), channel(Color::blue))
+}
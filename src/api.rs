@@ -0,0 +1,224 @@
+use image::{RgbImage, RgbaImage};
+use nalgebra_glm::Vec3;
+use std::collections::HashMap;
+
+use crate::camera::Camera;
+use crate::caustics;
+use crate::cornerao;
+use crate::cube::Cube;
+use crate::framebuffer::Framebuffer;
+use crate::light::{SceneLight, DAY_DURATION};
+use crate::material::Material;
+use crate::object::SceneObject;
+use crate::plane::Plane;
+use crate::registry::default_materials;
+use crate::renderer::{render, sky_color};
+use crate::scene::{default_camera, default_sun, Scene, SceneError};
+use crate::settings::RenderSettings;
+use crate::sphere::Sphere;
+
+enum PendingShape {
+    Cube { min: Vec3, max: Vec3 },
+    Sphere { center: Vec3, radius: f32 },
+    Plane { point: Vec3, normal: Vec3, tile: f32 },
+}
+
+// Objeto pendiente de resolver su material al construir la escena
+struct PendingObject {
+    shape: PendingShape,
+    material: String,
+    group: String,
+}
+
+// Construye escenas desde código. Los materiales se piden por nombre y salen
+// del registro del diorama (obsidian, purple, grass, rock, lava) o de los que
+// se agreguen con `material`:
+//
+//     let scene = DioramaBuilder::new()
+//         .add_cube(Vec3::new(0.0, 0.0, 0.0), Vec3::new(1.0, 1.0, 1.0), "obsidian")
+//         .day_time(0.3)
+//         .build()?;
+pub struct DioramaBuilder {
+    materials: HashMap<String, Material>,
+    objects: Vec<PendingObject>,
+    lights: Vec<SceneLight>,
+    camera: Camera,
+    day_time: Option<f32>,
+}
+
+impl Default for DioramaBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl DioramaBuilder {
+    pub fn new() -> Self {
+        DioramaBuilder {
+            materials: default_materials(),
+            objects: Vec::new(),
+            lights: Vec::new(),
+            camera: default_camera(),
+            day_time: None,
+        }
+    }
+
+    // Registra un material propio o reemplaza uno del registro
+    pub fn material(mut self, name: &str, material: Material) -> Self {
+        self.materials.insert(name.to_string(), material.named(name));
+        self
+    }
+
+    pub fn add_cube(self, min: Vec3, max: Vec3, material: &str) -> Self {
+        self.add_cube_in(min, max, material, "")
+    }
+
+    // Como `add_cube`, dentro de un grupo para el enlace de luces
+    pub fn add_cube_in(mut self, min: Vec3, max: Vec3, material: &str, group: &str) -> Self {
+        self.objects.push(PendingObject {
+            shape: PendingShape::Cube { min, max },
+            material: material.to_string(),
+            group: group.to_string(),
+        });
+        self
+    }
+
+    pub fn add_sphere(self, center: Vec3, radius: f32, material: &str) -> Self {
+        self.add_sphere_in(center, radius, material, "")
+    }
+
+    pub fn add_sphere_in(mut self, center: Vec3, radius: f32, material: &str, group: &str) -> Self {
+        self.objects.push(PendingObject {
+            shape: PendingShape::Sphere { center, radius },
+            material: material.to_string(),
+            group: group.to_string(),
+        });
+        self
+    }
+
+    // Plano infinito por `point` con la textura repetida cada `tile` unidades
+    // según X y Z
+    pub fn add_plane(self, point: Vec3, normal: Vec3, tile: f32, material: &str) -> Self {
+        self.add_plane_in(point, normal, tile, material, "")
+    }
+
+    pub fn add_plane_in(mut self, point: Vec3, normal: Vec3, tile: f32, material: &str, group: &str) -> Self {
+        self.objects.push(PendingObject {
+            shape: PendingShape::Plane { point, normal, tile },
+            material: material.to_string(),
+            group: group.to_string(),
+        });
+        self
+    }
+
+    pub fn add_light(mut self, light: SceneLight) -> Self {
+        self.lights.push(light);
+        self
+    }
+
+    pub fn camera(mut self, eye: Vec3, center: Vec3) -> Self {
+        self.camera = Camera::new(eye, center, Vec3::new(0.0, 1.0, 0.0));
+        self
+    }
+
+    // Momento del ciclo día/noche para los soles, como fracción del día
+    // (0.25 es el mediodía)
+    pub fn day_time(mut self, fraction: f32) -> Self {
+        self.day_time = Some(fraction.rem_euclid(1.0));
+        self
+    }
+
+    // Sin luces se usa el sol del diorama. Falla si algún objeto pide un
+    // material que no está registrado.
+    pub fn build(self) -> Result<Scene, SceneError> {
+        let objects = self
+            .objects
+            .iter()
+            .map(|object| {
+                let material = self
+                    .materials
+                    .get(&object.material)
+                    .ok_or_else(|| SceneError::UnknownMaterial(object.material.clone()))?;
+                Ok(match object.shape {
+                    PendingShape::Cube { min, max } => SceneObject::Cube(Cube::new(min, max, material).in_group(&object.group)),
+                    PendingShape::Sphere { center, radius } => {
+                        SceneObject::Sphere(Sphere::new(center, radius, material).in_group(&object.group))
+                    }
+                    PendingShape::Plane { point, normal, tile } => {
+                        SceneObject::Plane(Plane::new(point, normal, material).with_tile(tile).in_group(&object.group))
+                    }
+                })
+            })
+            .collect::<Result<Vec<_>, SceneError>>()?;
+
+        let mut lights = self.lights;
+        if lights.is_empty() {
+            lights.push(default_sun());
+        }
+        if let Some(fraction) = self.day_time {
+            for light in lights.iter_mut().filter(|light| light.is_sun) {
+                light.set_time(fraction * DAY_DURATION);
+            }
+        }
+
+        Ok(Scene::new(objects, self.materials, lights, self.camera))
+    }
+}
+
+// Render de un frame a una imagen en memoria, sin ventana
+pub struct Renderer {
+    settings: RenderSettings,
+    width: usize,
+    height: usize,
+    bias: Option<f32>,
+}
+
+impl Renderer {
+    pub fn new(settings: RenderSettings) -> Self {
+        Renderer {
+            settings,
+            width: 400,
+            height: 300,
+            bias: None,
+        }
+    }
+
+    pub fn size(mut self, width: usize, height: usize) -> Self {
+        self.width = width;
+        self.height = height;
+        self
+    }
+
+    // Fija el sesgo de origen; por defecto se calcula desde el tamaño de cada escena
+    pub fn bias(mut self, bias: f32) -> Self {
+        self.bias = Some(bias);
+        self
+    }
+
+    fn render_framebuffer(&self, scene: &Scene, camera: &Camera) -> Framebuffer {
+        let mut settings = RenderSettings {
+            bias: self.bias.unwrap_or_else(|| scene.default_bias()),
+            ..self.settings.clone()
+        };
+        cornerao::refresh(&mut settings.corner_ao, &scene.objects, scene.corner_ao);
+        let time = settings.caustics.map_or(0.0, |caustics| caustics.time);
+        caustics::refresh(&mut settings.caustics, &scene.objects, time);
+        settings.bvh = Some(scene.bvh());
+        let mut framebuffer = Framebuffer::new(self.width, self.height);
+        render(&mut framebuffer, &scene.objects, camera, &scene.lights, sky_color(&scene.lights), &settings);
+        framebuffer
+    }
+
+    pub fn render(&self, scene: &Scene, camera: &Camera) -> RgbImage {
+        let framebuffer = self.render_framebuffer(scene, camera);
+        RgbImage::from_raw(self.width as u32, self.height as u32, framebuffer.to_rgb_bytes())
+            .expect("el búfer tiene el tamaño de la imagen")
+    }
+
+    // Con alfa: los píxeles sin geometría quedan transparentes
+    pub fn render_rgba(&self, scene: &Scene, camera: &Camera) -> RgbaImage {
+        let framebuffer = self.render_framebuffer(scene, camera);
+        RgbaImage::from_raw(self.width as u32, self.height as u32, framebuffer.to_rgba_bytes())
+            .expect("el búfer tiene el tamaño de la imagen")
+    }
+}
@@ -0,0 +1,1257 @@
+use minifb::{Key, MouseButton, MouseMode, Window, WindowOptions};
+use nalgebra_glm::Vec3;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use crate::adaptive::AdaptiveSampling;
+use crate::budget::{render_budgeted, BudgetStats, LEVELS};
+use crate::camera::{Camera, CameraMode, Projection};
+use crate::caustics;
+use crate::chunks::ChunkSettings;
+use crate::cli::Args;
+use crate::color::{Color, Palette};
+use crate::config::{self, RenderPrefs, Settings, ViewPrefs, WindowPrefs};
+use crate::controls::{
+    apply_frame, click_minimap, crop_of, drag_corners, edit_lights, input_camera, light_line, play_rail, probe_frame, regenerate,
+    save_edited_lights, update_crop, update_probe, update_selection, update_split, View,
+};
+use crate::cornerao;
+use crate::export::{export_gltf, export_rail, save_capture};
+use crate::framebuffer::Framebuffer;
+use crate::generate::GenParams;
+use crate::gizmo::Gizmo;
+use crate::input::{InputEvent, InputState};
+use crate::irradiance::{self, ENTRIES_PER_FRAME};
+use crate::light::{SceneLight, DAY_DURATION};
+use crate::lightedit::LightEditor;
+use crate::minimap::Minimap;
+use crate::motion::{render_motion_blur, FrameMotion};
+use crate::object::SceneObject;
+use crate::offline::OfflineRender;
+use crate::overlay;
+use crate::probe::ProbePath;
+use crate::profile::{self, FrameProfile, Span};
+use crate::rail::CameraRail;
+use crate::ray_intersect::Intersect;
+use crate::renderer::{pick, probe_pixel, render, render_reprojected, sky_color, RenderStats};
+use crate::redraw::Redraw;
+use crate::reprojection::Reprojection;
+use crate::scene::{LoadReport, Scene, SceneError, DEFAULT_SCENE};
+use crate::settings::{AmbientOcclusion, Bloom, Crop, GodRays, Preset, RenderSettings, ToneMapping};
+use crate::shadowmap;
+use crate::sky;
+use crate::skybox::Skybox;
+use crate::split::{SplitView, ViewCost};
+use crate::texture::{Texture, TextureFilter};
+use crate::timeline::{Action, DayClock, FrameRecord, Recorder, Replay};
+use crate::tools::load_scene;
+use crate::velocity::{MotionVectors, VIEW_SCALE};
+
+// Lado del framebuffer de la ventana, que se escala al tamaño de la ventana
+const FRAMEBUFFER_WIDTH: usize = 400;
+const FRAMEBUFFER_HEIGHT: usize = 300;
+
+// Rayos primarios del render de alta resolución que se trazan por frame de la
+// ventana, de a filas enteras
+const OFFLINE_RAYS_PER_FRAME: usize = 200_000;
+
+// Todo lo que arma `run` antes de elegir entre el render sin ventana y la
+// ventana: la escena, sus luces y las opciones, lo que se ve encima y la
+// sesión que se reproduce
+struct Session {
+    scene: Scene,
+    // Archivo del que salió la escena, para abrirla de nuevo en la próxima sesión
+    scene_path: Option<String>,
+    lights: Vec<SceneLight>,
+    camera: Camera,
+    settings: RenderSettings,
+    view: View,
+    minimap: Minimap,
+    selected: Option<usize>,
+    gizmo: Gizmo,
+    reprojection: Option<Reprojection>,
+    probe: Option<ProbePath>,
+    // Instante del recorrido de cámara mientras se recorre
+    rail_time: Option<f32>,
+    // Segundos de las texturas animadas: suman los pasos de los frames, así
+    // una sesión grabada las reproduce igual
+    scene_time: f32,
+    clock: DayClock,
+    generator: Option<(u64, GenParams)>,
+    replay: Option<Replay>,
+    stats_report: String,
+    // Preferencias guardadas, de dónde se leyeron y si se usan o solo se
+    // guarda la ventana al salir
+    saved: Settings,
+    settings_path: Option<PathBuf>,
+    restore: bool,
+}
+
+// Corre el programa con los argumentos ya leídos: exporta, renderiza una
+// imagen o abre la ventana. Devuelve el código de salida del proceso
+pub fn run(args: Args) -> i32 {
+    // Las preferencias guardadas solo valen para la ventana: los renders sin
+    // ventana no dependen de lo que se tocó en la última sesión, y las
+    // sesiones grabadas o reproducidas arrancan de las opciones por defecto
+    // para que la reproducción dé lo mismo con y sin ventana. Las teclas y la
+    // geometría de la ventana se usan siempre que hay ventana.
+    let interactive = args.output.is_none() && args.export_gltf.is_none() && args.rail_frames.is_none();
+    let settings_path = config::settings_path();
+    let saved = if interactive || args.reset_settings {
+        config::load_settings(settings_path.as_deref(), args.reset_settings)
+    } else {
+        Settings::default()
+    };
+    let restore = interactive && args.record.is_none() && args.replay.is_none();
+
+    if args.split && args.crop.is_some() {
+        eprintln!("El recorte no se puede usar con la vista dividida");
+        return 2;
+    }
+    let mut settings = match render_settings(&args, restore.then_some(&saved.render)) {
+        Ok(settings) => settings,
+        Err(code) => return code,
+    };
+
+    // El primer argumento opcional es un archivo de escena (.ron); sin él la
+    // ventana vuelve a abrir la escena de la última sesión
+    let last_scene = saved.last_scene.clone().filter(|_| restore && args.scene.is_none() && args.generate.is_none());
+    let (mut scene, scene_path, load_report) = match open_scene(&args, last_scene) {
+        Ok(opened) => opened,
+        Err(code) => return code,
+    };
+    // Sin ventana para CI, una textura que falta es un error: la imagen
+    // saldría con el damero y nadie lo vería
+    if args.headless && !load_report.is_clean() {
+        eprintln!("Faltan recursos de la escena; no se renderiza");
+        return 1;
+    }
+    let culled = scene.cull_buried();
+    if culled > 0 {
+        println!("Objetos enterrados sin trazar: {}", culled);
+    }
+    if args.merge {
+        println!("Cubos fusionados: {} objetos menos", scene.merge_cubes());
+    }
+
+    // La ventana traza su framebuffer; los modos sin ventana, la imagen de `--width` x `--height`
+    let (render_width, render_height) = if interactive { (FRAMEBUFFER_WIDTH, FRAMEBUFFER_HEIGHT) } else { (args.width, args.height) };
+    let stats_report =
+        scene.stats().report(render_width, render_height, scene.lights.len(), settings.max_depth, settings.samples_per_pixel);
+    println!("{}", stats_report);
+    settings.bias = args.bias.unwrap_or_else(|| scene.default_bias());
+    stream_chunks(&mut scene, args.chunks.as_ref());
+    if let Some(half_height) = args.ortho {
+        scene.camera.projection = Projection::Orthographic { half_height };
+    }
+
+    let mut replay = None;
+    if let Some(path) = &args.replay {
+        match Replay::load(path) {
+            Ok(loaded) => {
+                println!("Reproduciendo {} frames de {}", loaded.len(), path);
+                replay = Some(loaded);
+            }
+            Err(e) => {
+                eprintln!("Error al cargar la sesión {}: {}", path, e);
+                return 1;
+            }
+        }
+    }
+
+    let mut lights = scene.lights.clone();
+    if let Some(fraction) = args.day_time {
+        for light in lights.iter_mut().filter(|light| light.is_sun) {
+            light.set_time(fraction * DAY_DURATION);
+        }
+    }
+
+    scene.animate(args.scene_time);
+
+    if let Some(path) = &args.export_gltf {
+        return if export_gltf(path, &scene, &lights, &settings) { 0 } else { 1 };
+    }
+    if let Some(dir) = &args.rail_frames {
+        return if export_rail(dir, &mut scene, &mut lights, &mut settings, &args) { 0 } else { 1 };
+    }
+    let view_prefs = if restore { saved.view.clone() } else { ViewPrefs::default() };
+    let view = View {
+        show_stats: view_prefs.show_stats,
+        show_gizmo: args.gizmo || view_prefs.show_gizmo,
+        show_minimap: args.minimap || view_prefs.show_minimap,
+        show_motion: false,
+        load_report: load_report.lines(),
+        palette: args.palette.unwrap_or(view_prefs.palette),
+        light_editor: LightEditor::default(),
+        mouse_sensitivity: args.mouse_sensitivity.unwrap_or(view_prefs.mouse_sensitivity),
+        zoom_range: args.zoom_range.unwrap_or_else(|| scene.zoom_range()),
+    };
+    let session = Session {
+        scene_path,
+        lights,
+        camera: scene.camera.clone(),
+        settings,
+        view,
+        minimap: Minimap::default(),
+        selected: None,
+        gizmo: Gizmo {
+            spacing: args.grid_spacing,
+            ..Gizmo::default()
+        },
+        reprojection: (args.reproject || args.orbit_demo).then(Reprojection::default),
+        probe: None,
+        rail_time: None,
+        scene_time: scene.time(),
+        clock: DayClock::new(args.freeze_sun),
+        generator: args.generate,
+        replay,
+        stats_report,
+        saved,
+        settings_path,
+        restore,
+        scene,
+    };
+
+    match &args.output {
+        Some(output) => render_image(session, &args, output),
+        None => run_window(session, &args),
+    }
+}
+
+// Opciones de render de la línea de comandos, encima de las de la última
+// sesión si se restauran. Un fondo o un cielo que no se puede leer termina el
+// programa con el código que se devuelve
+fn render_settings(args: &Args, saved: Option<&RenderPrefs>) -> Result<RenderSettings, i32> {
+    let mut settings = RenderSettings {
+        background_fit: args.background_fit,
+        near: args.near,
+        cut_color: args.cut_color,
+        shadow_color: args.shadow_color,
+        transparent: args.transparent,
+        crop: args.crop,
+        ..RenderSettings::default()
+    };
+    if let Some(saved) = saved {
+        saved.apply(&mut settings);
+    }
+    // Lo que se pide en la línea de comandos se aplica encima de lo guardado
+    if let Some(preset) = args.preset {
+        settings.apply_preset(preset);
+    }
+    // Las opciones de calidad sueltas se aplican encima del preset
+    if args.adaptive {
+        settings.adaptive = Some(AdaptiveSampling::default());
+    }
+    if args.half_res {
+        settings.half_res = true;
+    }
+    if args.no_gamma {
+        settings.gamma_correct = false;
+    }
+    if let Some(tone_mapping) = args.tone_mapping {
+        settings.tone_mapping = tone_mapping;
+    }
+    if let Some(exposure) = args.exposure {
+        settings.exposure = exposure;
+    }
+    if let Some(samples) = args.samples {
+        settings.samples_per_pixel = samples;
+    }
+    if args.god_rays {
+        settings.god_rays = Some(GodRays::default());
+    }
+    if args.bloom {
+        settings.bloom = Some(Bloom::default());
+    }
+    if args.ambient_occlusion {
+        settings.ambient_occlusion = Some(AmbientOcclusion::default());
+    }
+    if let Some(filter) = args.texture_filter {
+        settings.texture_filter = Some(filter);
+    }
+    if let Some(radius) = args.halo_radius {
+        settings.halo_radius = radius;
+    }
+    if let Some(quality) = args.shadow_quality {
+        settings.shadow_quality = quality;
+    }
+    if args.shadow_map.is_some() {
+        settings.shadow_map = args.shadow_map;
+    }
+    if let Some(path) = &args.background {
+        match Texture::new(path) {
+            Ok(texture) => settings.background = Some(texture),
+            Err(e) => {
+                eprintln!("Error al cargar el fondo {}: {}", path, e);
+                return Err(1);
+            }
+        }
+    }
+    if let Some(path) = &args.skybox {
+        match Skybox::load(path) {
+            Ok(skybox) => settings.skybox = Some(skybox),
+            Err(e) => {
+                eprintln!("Error al cargar el cielo {}: {}", path, e);
+                return Err(1);
+            }
+        }
+    }
+    Ok(settings)
+}
+
+// La escena que se abre: la de la línea de comandos, la de la última sesión,
+// una generada o la de siempre, con el archivo del que salió y lo que faltó al
+// cargarla. Si no se puede abrir la que se pidió, devuelve el código de salida
+fn open_scene(args: &Args, last_scene: Option<String>) -> Result<(Scene, Option<String>, LoadReport), i32> {
+    let opened = match (&args.scene, last_scene) {
+        (Some(path), _) => match load_scene(path) {
+            Ok((scene, report)) => (scene, Some(path.clone()), report),
+            Err(e) => {
+                eprintln!("Error al cargar la escena {}: {}", path, e);
+                return Err(1);
+            }
+        },
+        (None, Some(path)) => match load_scene(&path) {
+            Ok((scene, report)) => {
+                println!("Escena de la última sesión: {}", path);
+                (scene, Some(path), report)
+            }
+            Err(e) => {
+                eprintln!("Error al cargar la escena de la última sesión {}: {}", path, e);
+                (Scene::diorama(), None, LoadReport::default())
+            }
+        },
+        (None, None) => match &args.generate {
+            Some((seed, params)) => {
+                println!("Escena generada: {}", params.scene_entry(*seed));
+                (Scene::generate(*seed, params), None, LoadReport::default())
+            }
+            // La escena de siempre sale del archivo si está, así se puede
+            // retocar sin recompilar; si no, la integrada es la misma
+            None => match load_scene(DEFAULT_SCENE) {
+                Ok((scene, report)) => (scene, Some(DEFAULT_SCENE.to_string()), report),
+                Err(SceneError::Io(e)) if e.kind() == std::io::ErrorKind::NotFound => (Scene::diorama(), None, LoadReport::default()),
+                Err(e) if args.headless => {
+                    eprintln!("Error al cargar la escena {}: {}", DEFAULT_SCENE, e);
+                    return Err(1);
+                }
+                Err(e) => {
+                    eprintln!("Error al cargar la escena {}: {}; se usa la integrada", DEFAULT_SCENE, e);
+                    (Scene::diorama(), None, LoadReport::default())
+                }
+            },
+        },
+    };
+    Ok(opened)
+}
+
+// Render sin ventana: un solo frame guardado como PNG. Si hay una sesión
+// grabada, se aplica completa antes de renderizar el estado final.
+fn render_image(session: Session, args: &Args, output: &str) -> i32 {
+    let Session {
+        mut scene,
+        mut lights,
+        mut camera,
+        mut settings,
+        mut view,
+        mut minimap,
+        mut selected,
+        gizmo,
+        mut reprojection,
+        mut probe,
+        mut rail_time,
+        mut scene_time,
+        mut clock,
+        mut generator,
+        mut replay,
+        ..
+    } = session;
+    if let Some(crop) = args.crop.filter(|crop| crop.clamped(args.width, args.height).is_none()) {
+        eprintln!("El recorte en ({}, {}) está fuera de la imagen de {}x{}", crop.x, crop.y, args.width, args.height);
+        return 2;
+    }
+    let mut framebuffer = Framebuffer::new(args.width, args.height);
+    let mut split = args.split.then(|| SplitView::new(scene.camera.clone(), args.width, args.height));
+    // Estado antes del último frame de la sesión, para el desenfoque y los
+    // vectores de movimiento
+    let mut before_last: Option<(Camera, Vec<SceneLight>)> = None;
+    for frame in replay.iter_mut().flatten() {
+        if args.shutter > 0.0 || args.motion_vectors.is_some() {
+            before_last = Some((camera.clone(), lights.clone()));
+        }
+        update_split(&frame, &mut split, &mut settings, &scene.camera, args.width, args.height);
+        let mut discarded = None;
+        let frame_camera = input_camera(&frame, &mut camera, &mut split, &mut discarded);
+        apply_frame(&frame, frame_camera, &mut lights, &mut view, &mut settings, &mut reprojection, &mut clock, args.orbit_demo);
+        // Al reproducir una sesión las luces editadas no se guardan
+        edit_lights(&frame, &mut view.light_editor, &mut lights, frame_camera);
+        play_rail(&frame, &scene.rail, &scene.objects, &mut rail_time, &mut camera);
+        if split.is_none() {
+            update_crop(&frame, &mut settings, args.width, args.height);
+        }
+        if regenerate(&frame, &mut generator, &mut scene, &mut lights, &mut settings, args.bias, args.merge) {
+            probe = None;
+            rail_time = None;
+            selected = None;
+            view.light_editor.forget();
+            if reprojection.is_some() {
+                reprojection = Some(Reprojection::default());
+            }
+            stream_chunks(&mut scene, args.chunks.as_ref());
+        }
+        expose_for_near(&mut scene, &settings);
+        if scene.update_chunks(&camera, args.width as f32 / args.height as f32) {
+            selected = None;
+        }
+        scene_time += frame.delta_time;
+        scene.animate(scene_time);
+        let (mut frame, target) = probe_frame(&frame, split.as_ref());
+        let target_framebuffer = split.as_ref().map_or(&framebuffer, |split| &split.views[target]);
+        if target == 0 {
+            click_minimap(&mut frame, &view, &minimap, target_framebuffer, &scene.objects, &mut camera);
+        }
+        let target_camera = split.as_ref().map_or(&camera, |split| split.camera_of(target, &camera));
+        update_probe(&frame, &mut probe, target_framebuffer, &scene.objects, target_camera, &lights, &settings);
+        update_selection(&frame, &mut selected, target_framebuffer, &scene.objects, target_camera, &settings);
+    }
+    expose_for_near(&mut scene, &settings);
+    scene.update_chunks(&camera, args.width as f32 / args.height as f32);
+    let objects = &scene.objects;
+    let rail = rail_time.is_none().then_some(&scene.rail);
+    shadowmap::refresh(&mut lights, objects, settings.shadow_map.as_ref());
+    cornerao::refresh(&mut settings.corner_ao, objects, args.corner_ao || scene.corner_ao);
+    caustics::refresh(&mut settings.caustics, objects, scene_time);
+    settings.bvh = Some(scene.bvh());
+    sky::refresh(&mut settings.sky, &mut lights, args.physical_sky.then_some(scene.turbidity));
+    irradiance::warm_up(&mut framebuffer, objects, &camera, &lights, &mut settings, args.indirect);
+    if let Some((x, y)) = args.probe {
+        if x >= args.width || y >= args.height {
+            eprintln!("La sonda ({}, {}) está fuera de la imagen de {}x{}", x, y, args.width, args.height);
+            return 2;
+        }
+        // Con la vista dividida la sonda se lanza en la vista en que cae
+        let (x, target_framebuffer, target_camera) = match &split {
+            Some(split) => {
+                let (target, u) = split.view_at((x as f32 + 0.5) / args.width as f32);
+                let view_width = split.views[target].width;
+                (((u * view_width as f32) as usize).min(view_width - 1), &split.views[target], split.camera_of(target, &camera))
+            }
+            None => (x, &framebuffer, &camera),
+        };
+        let path = probe_pixel(x, y, target_framebuffer, objects, target_camera, &lights, &settings);
+        path.print();
+        selected = pick(x, y, target_framebuffer, objects, target_camera, &settings).map(|(_, index)| index);
+        probe = Some(path);
+    }
+
+    profile::begin_frame(false, args.trace.is_some());
+    // La vista dividida se traza tal cual, sin desenfoque ni presupuesto
+    let render_stats = if let Some(split) = split.as_mut() {
+        split.render(objects, &camera, &lights, sky_color(&lights), &settings, None);
+        split.total()
+    } else if args.shutter > 0.0 {
+        let motion = match &before_last {
+            Some((start_camera, start_lights)) => FrameMotion::new((start_camera, start_lights), (&camera, &lights)),
+            None => FrameMotion::ending_at(&camera, &lights, 1.0 / args.fps),
+        };
+        if !motion.is_static() {
+            println!("Desenfoque de movimiento: obturador {} con {} muestras", args.shutter, args.shutter_samples);
+        }
+        render_motion_blur(&mut framebuffer, objects, &motion, args.shutter, args.shutter_samples, &settings)
+    } else if let Some(millis) = args.budget.filter(|_| settings.crop.is_none()) {
+        let budget = Duration::from_secs_f32(millis / 1000.0);
+        let stats = render_budgeted(&mut framebuffer, objects, &camera, &lights, sky_color(&lights), &settings, budget);
+        println!(
+            "Presupuesto {} ms: nivel {} de {} ({}) en {:.1} ms",
+            millis,
+            stats.level + 1,
+            LEVELS.len(),
+            stats.describe(),
+            stats.elapsed.as_secs_f32() * 1000.0
+        );
+        stats.stats
+    } else {
+        render(&mut framebuffer, objects, &camera, &lights, sky_color(&lights), &settings)
+    };
+    println!("Rayos primarios: {}", render_stats.primary_rays);
+    if let Some(path) = &args.motion_vectors {
+        // Desde el frame anterior de la reproducción; sin reproducción la
+        // cámara no se movió. Con la vista dividida, los de la vista principal
+        let previous = before_last.as_ref().map_or(&camera, |(previous, _)| previous);
+        let traced = split.as_ref().map_or(&framebuffer, |split| &split.views[0]);
+        if let Err(e) = MotionVectors::compute(traced, &camera, previous).save_exr(path) {
+            eprintln!("Error al guardar {}: {}", path, e);
+            return 1;
+        }
+        println!("Vectores de movimiento guardados en {}", path);
+    }
+    match split.as_mut() {
+        Some(split) => {
+            for (index, cost) in split.costs.iter().enumerate() {
+                println!("{}", view_cost_line(split, index, cost));
+            }
+            for index in 0..2 {
+                let view_camera = if index == 0 { &camera } else { &split.camera };
+                let map = (index == 0).then_some(&mut minimap);
+                draw_view_overlays(&mut split.views[index], view_camera, &view, &gizmo, probe.as_ref(), &lights, map, objects, selected, rail);
+            }
+            split.compose(&mut framebuffer);
+        }
+        None => draw_view_overlays(&mut framebuffer, &camera, &view, &gizmo, probe.as_ref(), &lights, Some(&mut minimap), objects, selected, rail),
+    }
+
+    // Con recorte se guarda solo lo que se trazó
+    if let Some(crop) = settings.crop.and_then(|crop| crop.clamped(args.width, args.height)) {
+        framebuffer = framebuffer.region(crop.x, crop.y, crop.width, crop.height);
+    }
+    let saved = {
+        let _present = profile::phase(Span::Present);
+        if args.transparent {
+            framebuffer.save_png_rgba(output)
+        } else {
+            framebuffer.save_png(output)
+        }
+    };
+    let frame_profile = profile::end_frame();
+    if let Some(path) = &args.trace {
+        write_trace(path, &frame_profile);
+    }
+    if let Err(e) = saved {
+        eprintln!("Error al guardar {}: {}", output, e);
+        return 1;
+    }
+    println!("Imagen guardada en {}", output);
+    0
+}
+
+// La ventana: lee la entrada de cada frame (o la de la sesión que se
+// reproduce), renderiza y al cerrarse guarda las preferencias
+fn run_window(session: Session, args: &Args) -> i32 {
+    let Session {
+        mut scene,
+        scene_path,
+        mut lights,
+        mut camera,
+        mut settings,
+        mut view,
+        mut minimap,
+        mut selected,
+        gizmo,
+        mut reprojection,
+        mut probe,
+        mut rail_time,
+        mut scene_time,
+        mut clock,
+        mut generator,
+        mut replay,
+        mut stats_report,
+        mut saved,
+        settings_path,
+        restore,
+    } = session;
+    let (framebuffer_width, framebuffer_height) = (FRAMEBUFFER_WIDTH, FRAMEBUFFER_HEIGHT);
+    let frame_delay = Duration::from_millis(16);
+
+    let mut recorder = None;
+    if let Some(path) = &args.record {
+        match Recorder::create(path) {
+            Ok(created) => recorder = Some(created),
+            Err(e) => {
+                eprintln!("Error al crear la grabación {}: {}", path, e);
+                return 1;
+            }
+        }
+    }
+
+    let mut framebuffer = Framebuffer::new(framebuffer_width, framebuffer_height);
+    let (bindings, ignored) = saved.bindings();
+    if !ignored.is_empty() {
+        eprintln!("Teclas desconocidas en las preferencias: {}", ignored.join(", "));
+    }
+    let geometry = saved.window;
+    let mut window = Window::new(
+        WINDOW_TITLE,
+        geometry.width.max(1),
+        geometry.height.max(1),
+        WindowOptions {
+            resize: true,
+            ..WindowOptions::default()
+        },
+    ).unwrap();
+
+    // La ventana vuelve a donde estaba en la última sesión
+    window.set_position(geometry.x, geometry.y);
+    window.update();
+
+    let mut last_update = std::time::Instant::now();
+    let mut input = WindowInput::default();
+    let mut split = args.split.then(|| SplitView::new(scene.camera.clone(), framebuffer_width, framebuffer_height));
+    // Último render sin lo que se dibuja encima: con recorte, lo de afuera sale de acá
+    let mut rendered: Vec<Color> = Vec::new();
+    let mut rendered_views: [Vec<Color>; 2] = Default::default();
+    // Hay que volver a trazar la escena; si nada cambió desde el último render
+    // se lo vuelve a mostrar tal cual, con lo que va encima dibujado de nuevo
+    let mut dirty = true;
+    let mut redraw = Redraw::new(&camera, &lights);
+    let mut render_stats = RenderStats::default();
+    let mut budget_stats: Option<BudgetStats> = None;
+    // Cámara del frame anterior, para la vista de movimiento
+    let mut last_camera = camera.clone();
+    // Campo de visión que muestra el título, que lo agrega al cambiar, y el
+    // título puesto
+    let mut shown_fov = camera.fov;
+    let mut fov_label = String::new();
+    let mut shown_title = WINDOW_TITLE.to_string();
+    // Render de alta resolución de F11 mientras se traza
+    let mut offline: Option<OfflineRender> = None;
+    let mut last_profile = FrameProfile::default();
+    let trace_path = args.trace.as_deref().unwrap_or("traza.json");
+    let budget = args.budget.map(|millis| Duration::from_secs_f32(millis / 1000.0));
+    let camera_actions = [
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::OrbitLeft,
+        Action::OrbitRight,
+        Action::OrbitUp,
+        Action::OrbitDown,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+    ];
+
+    while window.is_open() {
+        let frame_start = std::time::Instant::now();
+
+        // La entrada viene de la sesión grabada o del teclado; el paso de tiempo
+        // del ciclo día/noche sale del mismo registro para que sea determinista
+        let frame = match replay.as_mut() {
+            Some(replay) => match replay.next() {
+                Some(frame) => frame,
+                None => break,
+            },
+            None => {
+                let now = std::time::Instant::now();
+                let delta_time = (now - last_update).as_secs_f32();
+                last_update = now;
+                let mut frame = poll_window(&window, &bindings, delta_time, &mut input);
+                if let Some((split, (mouse_x, _))) = split.as_ref().zip(window.get_mouse_pos(MouseMode::Discard)) {
+                    frame.view = split.view_at(mouse_x / window.get_size().0 as f32).0;
+                }
+                frame
+            }
+        };
+
+        if let Some(recorder) = recorder.as_mut() {
+            if let Err(e) = recorder.record(&frame) {
+                eprintln!("Error al grabar la sesión: {}", e);
+            }
+        }
+
+        if frame.is_held(Action::Quit) {
+            break;
+        }
+
+        // El perfilado solo mide mientras el panel está visible o se captura
+        // una traza con F6
+        profile::begin_frame(view.show_stats, frame.was_pressed(Action::CaptureTrace));
+
+        update_split(&frame, &mut split, &mut settings, &scene.camera, framebuffer_width, framebuffer_height);
+        let mut discarded = None;
+        let frame_camera = input_camera(&frame, &mut camera, &mut split, &mut discarded);
+        apply_frame(&frame, frame_camera, &mut lights, &mut view, &mut settings, &mut reprojection, &mut clock, args.orbit_demo);
+        if edit_lights(&frame, &mut view.light_editor, &mut lights, frame_camera) {
+            save_edited_lights(scene_path.as_deref().filter(|_| generator.is_none()), &lights);
+        }
+        play_rail(&frame, &scene.rail, &scene.objects, &mut rail_time, &mut camera);
+        if split.is_none() {
+            update_crop(&frame, &mut settings, framebuffer_width, framebuffer_height);
+        }
+        if regenerate(&frame, &mut generator, &mut scene, &mut lights, &mut settings, args.bias, args.merge) {
+            // El historial, la sonda, la selección, lo que se deshace y el
+            // recorrido eran de la escena anterior
+            probe = None;
+            rail_time = None;
+            selected = None;
+            view.light_editor.forget();
+            if reprojection.is_some() {
+                reprojection = Some(Reprojection::default());
+            }
+            stats_report =
+                scene.stats().report(framebuffer_width, framebuffer_height, scene.lights.len(), settings.max_depth, settings.samples_per_pixel);
+            stream_chunks(&mut scene, args.chunks.as_ref());
+            dirty = true;
+        }
+        expose_for_near(&mut scene, &settings);
+        // Los índices de los objetos cambian con el conjunto de trozos
+        if scene.update_chunks(&camera, framebuffer_width as f32 / framebuffer_height as f32) {
+            selected = None;
+            dirty = true;
+            if reprojection.is_some() {
+                reprojection = Some(Reprojection::default());
+            }
+        }
+        scene_time += frame.delta_time;
+        dirty |= scene.animate(scene_time);
+        let objects = &scene.objects;
+        let rail = rail_time.is_none().then_some(&scene.rail);
+        {
+            // Construir un mapa cuenta como tiempo de sombras del frame
+            let _shadows = profile::phase(Span::Shadow);
+            shadowmap::refresh(&mut lights, objects, settings.shadow_map.as_ref());
+        }
+        cornerao::refresh(&mut settings.corner_ao, objects, args.corner_ao || scene.corner_ao);
+        // Las cáusticas se mueven solas, como las animaciones
+        dirty |= caustics::refresh(&mut settings.caustics, objects, scene_time);
+        settings.bvh = Some(scene.bvh());
+        sky::refresh(&mut settings.sky, &mut lights, args.physical_sky.then_some(scene.turbidity));
+        irradiance::refresh(&mut settings, objects, &lights, args.indirect, ENTRIES_PER_FRAME);
+        let (mut frame, target) = probe_frame(&frame, split.as_ref());
+        let target_framebuffer = split.as_ref().map_or(&framebuffer, |split| &split.views[target]);
+        if target == 0 {
+            click_minimap(&mut frame, &view, &minimap, target_framebuffer, objects, &mut camera);
+        }
+        let target_camera = split.as_ref().map_or(&camera, |split| split.camera_of(target, &camera));
+        update_probe(&frame, &mut probe, target_framebuffer, objects, target_camera, &lights, &settings);
+        update_selection(&frame, &mut selected, target_framebuffer, objects, target_camera, &settings);
+
+        // F11 arranca el render de alta resolución con la cámara, las luces y
+        // los ajustes de ahora, y otra vez lo cancela. Cada frame traza unas
+        // filas más, así la ventana sigue respondiendo mientras tanto
+        if frame.was_pressed(Action::BeautyShot) {
+            offline = match offline {
+                Some(_) => {
+                    println!("Render de alta resolución cancelado");
+                    None
+                }
+                None => Some(OfflineRender::new(objects, &camera, &lights, &settings, &args.beauty)),
+            };
+        }
+        if offline.as_mut().is_some_and(|shot| shot.step(OFFLINE_RAYS_PER_FRAME)) {
+            if let Some(shot) = offline.take() {
+                save_capture(&shot.framebuffer, "render", settings.transparent);
+            }
+        }
+
+        // El título muestra el campo de visión desde que cambia y el avance
+        // del render de alta resolución
+        if camera.fov != shown_fov {
+            shown_fov = camera.fov;
+            fov_label = format!(" - FOV {:.0}°", camera.fov.to_degrees());
+        }
+        let progress = offline.as_ref().map_or(String::new(), |shot| {
+            format!(" - render {}x{} {:.0}%", shot.framebuffer.width, shot.framebuffer.height, shot.progress() * 100.0)
+        });
+        let title = format!("{}{}{}", WINDOW_TITLE, fov_label, progress);
+        if title != shown_title {
+            window.set_title(&title);
+            shown_title = title;
+        }
+
+        // La cámara y las luces se comparan con las del último render, así el
+        // recorrido de cámara, la demostración de órbita, el sol mientras
+        // corre el ciclo y el editor de luces cuentan aunque no haya entrada.
+        // Cualquier tecla, la rueda o un clic pueden cambiar además las
+        // opciones; sin entrada cambian también la luz indirecta mientras se
+        // completa y los cuadros de las texturas animadas
+        let filling = settings.irradiance.as_ref().is_some_and(|cache| cache.pending() > 0);
+        dirty |= !frame.is_idle() || redraw.changed(&camera, &lights) || filling;
+
+        // Si algo cambió dibuja los objetos con el nuevo color del cielo; con
+        // presupuesto se refina mientras quede tiempo y no se usa la
+        // reproyección. Con recorte se parte del último render, sin el panel
+        // ni lo demás que se dibujó encima, y no hay presupuesto. La vista
+        // dividida tampoco usa el presupuesto
+        let redrawn = dirty;
+        if dirty {
+            if settings.crop.is_some() && rendered.len() == framebuffer.buffer.len() {
+                framebuffer.buffer.copy_from_slice(&rendered);
+            }
+            let sky = sky_color(&lights);
+            budget_stats = None;
+            render_stats = match (budget.filter(|_| settings.crop.is_none() && split.is_none()), reprojection.as_mut(), split.as_mut()) {
+                (Some(budget), _, _) => {
+                    let stats = render_budgeted(&mut framebuffer, objects, &camera, &lights, sky, &settings, budget);
+                    budget_stats = Some(stats);
+                    stats.stats
+                }
+                (None, reprojection, Some(split)) => {
+                    split.render(objects, &camera, &lights, sky, &settings, reprojection);
+                    split.total()
+                }
+                (None, Some(reprojection), None) => render_reprojected(&mut framebuffer, objects, &camera, &lights, sky, &settings, reprojection),
+                (None, None, None) => render(&mut framebuffer, objects, &camera, &lights, sky, &settings),
+            };
+            rendered.clone_from(&framebuffer.buffer);
+            if let Some(split) = &split {
+                for (saved, view) in rendered_views.iter_mut().zip(&split.views) {
+                    saved.clone_from(&view.buffer);
+                }
+            }
+            redraw.rendered(&camera, &lights);
+            dirty = false;
+        } else {
+            framebuffer.buffer.copy_from_slice(&rendered);
+            if let Some(split) = split.as_mut() {
+                for (view, saved) in split.views.iter_mut().zip(&rendered_views) {
+                    view.buffer.copy_from_slice(saved);
+                }
+            }
+        }
+        // La vista de movimiento reemplaza al render, fuera de la vista dividida
+        if view.show_motion && split.is_none() {
+            MotionVectors::compute(&framebuffer, &camera, &last_camera).visualize(&mut framebuffer, VIEW_SCALE);
+        }
+        last_camera.clone_from(&camera);
+
+        let present = profile::phase(Span::Present);
+        if let Some(crop) = &settings.crop {
+            dim_outside(&mut framebuffer, crop);
+        }
+        if let Some(dragging) = input.crop_start.filter(|_| split.is_none()).and_then(|start| {
+            let (window_width, window_height) = window.get_size();
+            let (x, y) = window.get_mouse_pos(MouseMode::Clamp)?;
+            let end = (x / window_width as f32, y / window_height as f32);
+            crop_of(drag_corners(start, end), framebuffer_width, framebuffer_height)
+        }) {
+            draw_outline(&mut framebuffer, &dragging, Color::white());
+        }
+        match split.as_mut() {
+            Some(split) => {
+                for index in 0..2 {
+                    let view_camera = if index == 0 { &camera } else { &split.camera };
+                    let map = (index == 0).then_some(&mut minimap);
+                    draw_view_overlays(&mut split.views[index], view_camera, &view, &gizmo, probe.as_ref(), &lights, map, objects, selected, rail);
+                }
+                split.compose(&mut framebuffer);
+            }
+            None => draw_view_overlays(&mut framebuffer, &camera, &view, &gizmo, probe.as_ref(), &lights, Some(&mut minimap), objects, selected, rail),
+        }
+
+        // El tooltip solo se dibuja encima del frame; se oculta mientras la
+        // cámara se mueve o el botón está presionado
+        let camera_moving = input.mouse.is_down(MouseButton::Left)
+            || input.mouse.is_down(MouseButton::Middle)
+            || camera_actions.iter().any(|&action| frame.is_held(action));
+        if !camera_moving {
+            let (window_width, window_height) = window.get_size();
+            if let Some((mouse_x, mouse_y)) = window.get_mouse_pos(MouseMode::Discard) {
+                let u = mouse_x / window_width as f32;
+                let x = ((u * framebuffer_width as f32) as usize).min(framebuffer_width - 1);
+                let y = ((mouse_y * framebuffer_height as f32 / window_height as f32) as usize).min(framebuffer_height - 1);
+                // Con la vista dividida se busca en la vista bajo el mouse
+                let (target, target_u) = split.as_ref().map_or((0, u), |split| split.view_at(u));
+                let target_framebuffer = split.as_ref().map_or(&framebuffer, |split| &split.views[target]);
+                let target_camera = split.as_ref().map_or(&camera, |split| split.camera_of(target, &camera));
+                let target_x = ((target_u * target_framebuffer.width as f32) as usize).min(target_framebuffer.width - 1);
+                let over_minimap = view.show_minimap
+                    && target == 0
+                    && minimap.world_at(target_framebuffer.width, objects, target_camera, target_x as f32, y as f32).is_some();
+                let hovered = if over_minimap {
+                    None
+                } else {
+                    pick(target_x, y, target_framebuffer, objects, target_camera, &settings)
+                };
+                if let Some((intersect, index)) = hovered {
+                    let lines = hover_lines(&intersect, index, &objects[index]);
+                    overlay::draw_tooltip(&mut framebuffer, x, y, &lines);
+                }
+            }
+        }
+
+        if view.show_stats {
+            let mut lines: Vec<String> = stats_report.lines().map(str::to_string).collect();
+            lines.push(format!("Preset: {}", settings.preset().map_or("Custom", Preset::label)));
+            lines.push(format!("Rebotes: {}", settings.max_depth));
+            if camera.mode == CameraMode::Fly {
+                lines.push("Cámara: vuelo libre".to_string());
+            }
+            if let Projection::Orthographic { half_height } = camera.projection {
+                lines.push(format!("Proyección: ortográfica, {:.2} de alto", 2.0 * half_height));
+            }
+            if view.palette != Palette::Default {
+                lines.push(format!("Paleta: {}", view.palette.name()));
+            }
+            if view.light_editor.active {
+                lines.push(light_line(&view.light_editor, &lights));
+            }
+            let pixels = match (&split, settings.crop) {
+                (Some(split), _) => split.pixels(),
+                (None, Some(crop)) => crop.width * crop.height,
+                (None, None) => framebuffer_width * framebuffer_height,
+            };
+            if let Some(crop) = settings.crop {
+                lines.push(format!("Recorte: {}x{} en ({}, {})", crop.width, crop.height, crop.x, crop.y));
+            }
+            lines.push(format!(
+                "Rayos primarios: {} ({:.0}%){}",
+                render_stats.primary_rays,
+                100.0 * render_stats.primary_rays as f32 / pixels as f32,
+                if settings.adaptive.is_some() { " adaptativo" } else { "" }
+            ));
+            if let Some(split) = &split {
+                for (index, cost) in split.costs.iter().enumerate() {
+                    lines.push(view_cost_line(split, index, cost));
+                }
+            }
+            if let Some(stats) = budget_stats {
+                lines.push(format!(
+                    "Presupuesto {:.0} ms: nivel {} de {} ({}) en {:.1} ms",
+                    budget.unwrap_or_default().as_secs_f32() * 1000.0,
+                    stats.level + 1,
+                    LEVELS.len(),
+                    stats.describe(),
+                    stats.elapsed.as_secs_f32() * 1000.0
+                ));
+            } else if reprojection.is_some() {
+                lines.push(format!(
+                    "Reproyectados: {} de {} ({:.0}%)",
+                    render_stats.reused_pixels,
+                    render_stats.reusable_pixels,
+                    100.0 * render_stats.reused_pixels as f32 / render_stats.reusable_pixels.max(1) as f32
+                ));
+            }
+            lines.push(format!(
+                "Filtro de textura: {}",
+                settings.texture_filter.map_or("por material".to_string(), TextureFilter::name)
+            ));
+            lines.push(format!(
+                "Sombras y reflejos: {}",
+                if settings.half_res { "media resolución" } else { "resolución completa" }
+            ));
+            if settings.samples_per_pixel > 1 {
+                lines.push(format!("Muestras por píxel: {}", settings.samples_per_pixel));
+            }
+            if settings.exposure != 1.0 || settings.tone_mapping != ToneMapping::Clamp {
+                lines.push(format!("Tonos: {}, exposición {:+.1} pasos", settings.tone_mapping.name(), settings.exposure.log2()));
+            }
+            if settings.god_rays.is_some() {
+                lines.push("Rayos de sol: activos".to_string());
+            }
+            if let Some(bloom) = settings.bloom {
+                lines.push(format!("Resplandor: desde luminancia {:.2}, fuerza {:.2}", bloom.threshold, bloom.strength));
+            }
+            if let Some(occlusion) = settings.ambient_occlusion {
+                lines.push(format!("Oclusión ambiental: {} rayos hasta {:.2}", occlusion.samples, occlusion.radius));
+            }
+            if let Some(counts) = scene.chunk_counts() {
+                lines.push(format!(
+                    "Trozos: {} completos, {} aproximados, {} sin trazar",
+                    counts.full, counts.proxy, counts.hidden
+                ));
+            }
+            if let Some(map) = settings.shadow_map {
+                lines.push(format!("Sombras del sol: mapa de {}x{} por cara", map.resolution, map.resolution));
+            }
+            if settings.near > 0.0 {
+                lines.push(format!("Plano cercano: {:.2}", settings.near));
+            }
+            if let Some(cache) = &settings.irradiance {
+                lines.push(format!("Luz indirecta: {} entradas, {} pendientes", cache.len(), cache.pending()));
+            }
+            if !redrawn {
+                lines.push("Sin cambios: se muestra el último render".to_string());
+            }
+            if clock.paused {
+                lines.push("Ciclo día/noche: en pausa".to_string());
+            }
+            if clock.owed() > 0.0 {
+                lines.push(format!("Sol detenido: {:.1} s de atraso", clock.owed()));
+            }
+            overlay::draw_panel(&mut framebuffer, 2, 2, &lines);
+
+            // Desglose del frame anterior, que ya incluye su presentación
+            let (_, panel_height) = overlay::panel_size(&lines);
+            let title = format!("Frame {:.1} ms, CPU por tramo:", last_profile.wall.as_secs_f32() * 1000.0);
+            overlay::draw_breakdown(&mut framebuffer, 2, 4 + panel_height, &title, &profile_entries(&last_profile, view.palette));
+        }
+
+        // Aviso de texturas que faltan, abajo hasta que se cierra
+        if !view.load_report.is_empty() {
+            let mut lines = view.load_report.clone();
+            lines.push("Enter cierra este aviso".to_string());
+            let (_, panel_height) = overlay::panel_size(&lines);
+            overlay::draw_panel(&mut framebuffer, 2, framebuffer_height.saturating_sub(panel_height + 2), &lines);
+        }
+
+        // F12 guarda lo que se ve, con los paneles y las ayudas
+        if frame.was_pressed(Action::Screenshot) {
+            save_capture(&framebuffer, "diorama", false);
+        }
+
+        // Actualiza la ventana con el contenido del framebuffer
+        window
+            .update_with_buffer(&framebuffer.to_argb_u32(), framebuffer_width, framebuffer_height)
+            .unwrap();
+        drop(present);
+
+        last_profile = profile::end_frame();
+        if last_profile.has_trace() {
+            write_trace(trace_path, &last_profile);
+        }
+
+        // Al reproducir se respeta el ritmo original salvo con --fast
+        let delay = match &replay {
+            Some(_) if args.fast => Duration::ZERO,
+            Some(_) => Duration::from_secs_f32(frame.delta_time).saturating_sub(frame_start.elapsed()),
+            None => frame_delay,
+        };
+        std::thread::sleep(delay);
+    }
+
+    if let Some(recorder) = recorder {
+        if let Err(e) = recorder.finish() {
+            eprintln!("Error al guardar la sesión: {}", e);
+        }
+    }
+
+    if let Some(path) = &settings_path {
+        let (x, y) = window.get_position();
+        let (width, height) = window.get_size();
+        saved.window = WindowPrefs { x, y, width, height };
+        if restore {
+            saved.render = RenderPrefs::of(&settings);
+            saved.view = ViewPrefs {
+                show_stats: view.show_stats,
+                show_gizmo: view.show_gizmo,
+                show_minimap: view.show_minimap,
+                palette: view.palette,
+                mouse_sensitivity: saved.view.mouse_sensitivity,
+            };
+            saved.last_scene = scene_path.filter(|_| generator.is_none());
+        }
+        if let Err(e) = saved.save(path) {
+            eprintln!("Error al guardar las preferencias {}: {}", path.display(), e);
+        }
+    }
+    0
+}
+
+// Texto del tooltip para el objeto bajo el cursor
+fn hover_lines(intersect: &Intersect, index: usize, object: &SceneObject) -> Vec<String> {
+    let material = &object.material().name;
+    let name = if material.is_empty() { "sin nombre" } else { material };
+    let mut lines = vec![format!("Objeto #{}", index)];
+    if !object.name().is_empty() {
+        lines.push(format!("Nombre: {}", object.name()));
+    }
+    if !object.group().is_empty() {
+        lines.push(format!("Grupo: {}", object.group()));
+    }
+    lines.push(format!("Material: {}", name));
+    match object {
+        SceneObject::Cube(cube) => {
+            lines.push(format!("Min: ({:.3}, {:.3}, {:.3})", cube.min.x, cube.min.y, cube.min.z));
+            lines.push(format!("Max: ({:.3}, {:.3}, {:.3})", cube.max.x, cube.max.y, cube.max.z));
+        }
+        SceneObject::Sphere(sphere) => {
+            lines.push(format!("Centro: ({:.3}, {:.3}, {:.3})", sphere.center.x, sphere.center.y, sphere.center.z));
+            lines.push(format!("Radio: {:.3}", sphere.radius));
+        }
+        SceneObject::Plane(plane) => {
+            lines.push(format!("Punto: ({:.3}, {:.3}, {:.3})", plane.point.x, plane.point.y, plane.point.z));
+            lines.push(format!("Normal: ({:.3}, {:.3}, {:.3})", plane.normal.x, plane.normal.y, plane.normal.z));
+        }
+    }
+    lines.push(format!("Distancia: {:.3}", intersect.distance));
+    lines
+}
+
+// Entrada de la ventana entre frames: las acciones de las teclas asignadas,
+// los botones del mouse, dónde empezó el arrastre del recorte, dónde estaba
+// el mouse en el frame anterior y cuánto se movió desde que bajó el botón
+// izquierdo
+#[derive(Default)]
+struct WindowInput {
+    actions: InputState<Action>,
+    mouse: InputState<MouseButton>,
+    crop_start: Option<(f32, f32)>,
+    last_mouse: Option<(f32, f32)>,
+    dragged: f32,
+}
+
+const WINDOW_TITLE: &str = "Rust Graphics - Raytracer Example";
+
+// Píxeles que puede moverse el mouse con el botón izquierdo abajo y seguir
+// contando como un clic en lugar de un arrastre
+const CLICK_SLOP: f32 = 3.0;
+
+// Lee el teclado y el ratón y los traduce a las acciones del frame según las
+// teclas asignadas en las preferencias. Las acciones que no se mantienen se
+// disparan solo en el frame en que baja alguna de sus teclas. Un clic
+// izquierdo (al soltar el botón sin haberlo arrastrado) lanza una sonda en ese
+// punto de la pantalla, arrastrar con el izquierdo orbita la cámara, con el
+// del medio la desplaza y con el derecho marca un recorte, que se aplica al
+// soltarlo.
+fn poll_window(window: &Window, bindings: &[(Key, Action)], delta_time: f32, input: &mut WindowInput) -> FrameRecord {
+    let keys = window.get_keys();
+    input.actions.update(bindings.iter().filter(|(key, _)| keys.contains(key)).map(|&(_, action)| action));
+    input
+        .mouse
+        .update([MouseButton::Left, MouseButton::Middle, MouseButton::Right].into_iter().filter(|&button| window.get_mouse_down(button)));
+
+    // Lo que se movió el mouse desde el frame anterior, que solo cuenta para
+    // los botones que ya estaban abajo
+    let pointer = window.get_mouse_pos(MouseMode::Pass);
+    let moved = input.last_mouse.zip(pointer).map_or([0.0; 2], |((x0, y0), (x1, y1))| [x1 - x0, y1 - y0]);
+    input.last_mouse = pointer;
+    let drag = |button: MouseButton| {
+        if input.mouse.is_down(button) && !input.mouse.was_pressed(button) { moved } else { [0.0; 2] }
+    };
+    let (orbit, pan) = (drag(MouseButton::Left), drag(MouseButton::Middle));
+    if input.mouse.was_pressed(MouseButton::Left) {
+        input.dragged = 0.0;
+    }
+    input.dragged += orbit[0].abs() + orbit[1].abs();
+    let clicking = input.dragged <= CLICK_SLOP;
+
+    let (window_width, window_height) = window.get_size();
+    let probe = window
+        .get_mouse_pos(MouseMode::Discard)
+        .filter(|_| input.mouse.was_released(MouseButton::Left) && clicking)
+        .map(|(x, y)| (x / window_width as f32, y / window_height as f32));
+
+    let mouse = window
+        .get_mouse_pos(MouseMode::Clamp)
+        .map(|(x, y)| (x / window_width as f32, y / window_height as f32));
+    if input.mouse.was_pressed(MouseButton::Right) {
+        input.crop_start = mouse;
+    }
+    let crop = if input.mouse.was_released(MouseButton::Right) {
+        input.crop_start.take().zip(mouse).map(|(start, end)| drag_corners(start, end))
+    } else {
+        None
+    };
+
+    let events = input.actions.events();
+    FrameRecord {
+        delta_time,
+        held: events
+            .iter()
+            .filter_map(|event| match *event {
+                InputEvent::Pressed(action) | InputEvent::Held(action) if action.is_held() => Some(action),
+                _ => None,
+            })
+            .collect(),
+        pressed: events
+            .iter()
+            .filter_map(|event| match *event {
+                InputEvent::Pressed(action) if !action.is_held() => Some(action),
+                _ => None,
+            })
+            .collect(),
+        scroll: window.get_scroll_wheel().map_or(0.0, |(_, y)| y),
+        probe,
+        crop,
+        // La vista bajo el mouse la pone el bucle, que sabe si está dividida
+        view: 0,
+        // Un arrastre corto todavía puede ser un clic
+        orbit: if clicking { [0.0; 2] } else { orbit },
+        pan,
+    }
+}
+
+// Oscurece un poco lo que queda fuera del recorte, que no se volvió a trazar
+fn dim_outside(framebuffer: &mut Framebuffer, crop: &Crop) {
+    const DIM: f32 = 0.3;
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let (right, bottom) = (crop.x + crop.width, crop.y + crop.height);
+    overlay::fill_rect(framebuffer, 0, 0, width, crop.y, Color::black(), DIM);
+    overlay::fill_rect(framebuffer, 0, bottom, width, height - bottom, Color::black(), DIM);
+    overlay::fill_rect(framebuffer, 0, crop.y, crop.x, crop.height, Color::black(), DIM);
+    overlay::fill_rect(framebuffer, right, crop.y, width - right, crop.height, Color::black(), DIM);
+}
+
+// Contorno del recorte que se está arrastrando
+fn draw_outline(framebuffer: &mut Framebuffer, crop: &Crop, color: Color) {
+    let (left, top) = (crop.x as f32, crop.y as f32);
+    let (right, bottom) = ((crop.x + crop.width - 1) as f32, (crop.y + crop.height - 1) as f32);
+    overlay::draw_line(framebuffer, (left, top), (right, top), color);
+    overlay::draw_line(framebuffer, (right, top), (right, bottom), color);
+    overlay::draw_line(framebuffer, (right, bottom), (left, bottom), color);
+    overlay::draw_line(framebuffer, (left, bottom), (left, top), color);
+}
+
+// Costo de una vista dividida, para el panel y la salida estándar
+fn view_cost_line(split: &SplitView, index: usize, cost: &ViewCost) -> String {
+    let name = match index {
+        0 => "izquierda",
+        _ if split.locked => "derecha (fija)",
+        _ => "derecha",
+    };
+    format!("Vista {}: {} rayos primarios en {:.1} ms", name, cost.stats.primary_rays, cost.elapsed.as_secs_f32() * 1000.0)
+}
+
+// Lo que se dibuja encima de una vista con su cámara; el minimapa solo se
+// pasa para la primera. El recorrido de cámara se dibuja con los ejes y la
+// cuadrícula, salvo mientras se recorre
+#[allow(clippy::too_many_arguments)]
+fn draw_view_overlays(
+    framebuffer: &mut Framebuffer,
+    camera: &Camera,
+    view: &View,
+    gizmo: &Gizmo,
+    probe: Option<&ProbePath>,
+    lights: &[SceneLight],
+    minimap: Option<&mut Minimap>,
+    objects: &[SceneObject],
+    selected: Option<usize>,
+    rail: Option<&CameraRail>,
+) {
+    if let Some(rail) = rail.filter(|_| view.show_gizmo) {
+        let keys: Vec<Vec3> = rail.keys().iter().map(|key| key.eye).collect();
+        gizmo.draw_path(framebuffer, camera, camera.perspective_scale(), &rail.path(), &keys, view.palette);
+    }
+    if view.show_gizmo {
+        gizmo.draw(framebuffer, camera, camera.perspective_scale(), view.palette);
+    }
+    if let Some(path) = probe {
+        path.draw(framebuffer, camera, camera.perspective_scale(), view.palette);
+    }
+    if view.light_editor.active {
+        for (index, light) in lights.iter().enumerate() {
+            let chosen = index == view.light_editor.selected;
+            gizmo.draw_light(framebuffer, camera, camera.perspective_scale(), light, chosen, view.palette);
+        }
+    }
+    if let Some(minimap) = minimap.filter(|_| view.show_minimap) {
+        minimap.draw(framebuffer, objects, camera, selected, view.palette);
+    }
+}
+
+// El plano cercano puede dejar ver el interior de un sólido, así que con él
+// los objetos enterrados vuelven al trazado
+pub(crate) fn expose_for_near(scene: &mut Scene, settings: &RenderSettings) {
+    if settings.near > 0.0 && !scene.buried.is_empty() {
+        let restored = scene.restore_buried();
+        println!("Plano cercano activo: {} objetos enterrados vuelven al trazado", restored);
+    }
+}
+
+// Con `--chunks` la escena pasa a trazarse por trozos; va después de sacar
+// las estadísticas y el sesgo, que miden la escena entera
+fn stream_chunks(scene: &mut Scene, chunks: Option<&ChunkSettings>) {
+    if let Some(chunks) = chunks {
+        let count = scene.stream_chunks(chunks);
+        println!("Escena en {} trozos de {} bloques por lado", count, chunks.size);
+    }
+}
+
+// Entradas del desglose del panel: milisegundos de cada tramo sumados sobre
+// todos los hilos
+fn profile_entries(profile: &FrameProfile, palette: Palette) -> Vec<(String, f32, Color)> {
+    Span::ALL
+        .iter()
+        .map(|&span| {
+            let millis = profile.get(span).as_secs_f32() * 1000.0;
+            (format!("{} {:.1} ms", span.name(), millis), millis, span.color(palette))
+        })
+        .collect()
+}
+
+fn write_trace(path: &str, profile: &FrameProfile) {
+    match std::fs::write(path, profile.chrome_trace()) {
+        Ok(()) => println!("Traza guardada en {}", path),
+        Err(e) => eprintln!("Error al guardar la traza {}: {}", path, e),
+    }
+}
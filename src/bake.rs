@@ -0,0 +1,241 @@
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+use std::collections::HashMap;
+
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::decal::Face;
+use crate::light::SceneLight;
+use crate::material::Material;
+use crate::object::SceneObject;
+use crate::ray_intersect::Intersect;
+use crate::renderer::{cast_tinted_shadow, closest_hit, sky_color};
+use crate::settings::{ShadowQuality, DEFAULT_BIAS};
+use crate::texture::TextureSource;
+
+// Horneado de la iluminación en un color por cara, para exportar el diorama
+// como un modelo estático. Cada cara se muestrea en una cuadrícula de puntos,
+// sin contar los que quedan tapados por otro cubo opaco. En cada punto se suma
+// la luz directa de las luces que afectan al grupo, con la misma sombra que el
+// render, y una luz ambiente atenuada por oclusión ambiental: la fracción de
+// rayos del hemisferio que escapan sin chocar con nada cerca. Los materiales
+// que reflejan suman además el cielo con esa misma atenuación, que es lo que
+// más se ve en ellos en el render.
+
+// Giro entre rayos consecutivos del hemisferio (ángulo áureo)
+pub(crate) const GOLDEN_ANGLE: f32 = 2.399_963;
+const MAX_SAMPLES: usize = 64;
+
+#[derive(Debug, Clone)]
+pub struct BakeSettings {
+    // Separación entre puntos de la cuadrícula de cada cara, con al menos dos
+    // y a lo sumo `MAX_SAMPLES` por lado
+    pub spacing: f32,
+    // Rayos de oclusión ambiental por punto
+    pub ao_rays: usize,
+    // Distancia hasta la que un impacto ocluye
+    pub ao_distance: f32,
+    // Intensidad de la luz ambiente, que es blanca
+    pub ambient: f32,
+    pub bias: f32,
+    pub shadow_quality: ShadowQuality,
+    // Segundo de la escena cuyo cuadro se toma de las texturas animadas
+    pub time: f32,
+    // Lado de la imagen en la que se hornean los patrones y las animaciones
+    // antes de promediarlos
+    pub texels: u32,
+}
+
+impl Default for BakeSettings {
+    fn default() -> Self {
+        BakeSettings {
+            spacing: 0.05,
+            ao_rays: 32,
+            ao_distance: 0.5,
+            ambient: 0.6,
+            bias: DEFAULT_BIAS,
+            shadow_quality: ShadowQuality::Hard,
+            time: 0.0,
+            texels: 256,
+        }
+    }
+}
+
+// Cara de un cubo con su color horneado. Las esquinas van en sentido
+// antihorario vistas desde afuera.
+#[derive(Debug, Clone)]
+pub struct BakedFace {
+    pub corners: [Vec3; 4],
+    pub normal: Vec3,
+    pub color: Color,
+    // Opacidad, 1 salvo en los materiales transparentes
+    pub alpha: f32,
+}
+
+// Las seis caras de un cubo: eje, sentido y los dos ejes que la recorren
+fn faces(cube: &Cube) -> [(Vec3, [Vec3; 4]); 6] {
+    let corner = |axis: usize, side: f32, u: f32, v: f32| {
+        let mut point = Vec3::zeros();
+        let (axis_u, axis_v) = ((axis + 1) % 3, (axis + 2) % 3);
+        point[axis] = if side > 0.0 { cube.max[axis] } else { cube.min[axis] };
+        point[axis_u] = cube.min[axis_u] + (cube.max[axis_u] - cube.min[axis_u]) * u;
+        point[axis_v] = cube.min[axis_v] + (cube.max[axis_v] - cube.min[axis_v]) * v;
+        point
+    };
+    std::array::from_fn(|index| {
+        let (axis, side) = (index / 2, if index % 2 == 0 { 1.0 } else { -1.0 });
+        let mut normal = Vec3::zeros();
+        normal[axis] = side;
+        // u × v apunta hacia +axis; del lado negativo se invierte el recorrido
+        let mut corners = [(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 1.0)].map(|(u, v)| corner(axis, side, u, v));
+        if side < 0.0 {
+            corners.reverse();
+        }
+        (normal, corners)
+    })
+}
+
+// Direcciones del hemisferio de `normal` repartidas en espiral con densidad
+// proporcional al coseno, así el promedio ya queda ponderado. `spin` gira la
+// espiral alrededor de la normal
+pub(crate) fn hemisphere(normal: &Vec3, rays: usize, spin: f32) -> impl Iterator<Item = Vec3> + '_ {
+    let helper = if normal.x.abs() < 0.9 { Vec3::new(1.0, 0.0, 0.0) } else { Vec3::new(0.0, 1.0, 0.0) };
+    let tangent = normal.cross(&helper).normalize();
+    let bitangent = normal.cross(&tangent);
+    (0..rays).map(move |k| {
+        let radius = ((k as f32 + 0.5) / rays as f32).sqrt();
+        let angle = spin + k as f32 * GOLDEN_ANGLE;
+        tangent * (radius * angle.cos()) + bitangent * (radius * angle.sin()) + normal * (1.0 - radius * radius).sqrt()
+    })
+}
+
+// Fracción del hemisferio que no ve geometría a menos de `ao_distance`
+fn ambient_visibility(point: &Vec3, normal: &Vec3, objects: &[SceneObject], settings: &BakeSettings) -> f32 {
+    let origin = point + normal * settings.bias;
+    let open = hemisphere(normal, settings.ao_rays, 0.0)
+        .filter(|direction| {
+            closest_hit(&origin, direction, objects, None, settings.bias, false)
+                .is_none_or(|(hit, _)| hit.distance > settings.ao_distance)
+        })
+        .count();
+    open as f32 / settings.ao_rays.max(1) as f32
+}
+
+// Color propio de un material sin luz; las texturas se reducen a su promedio
+fn albedo(material: &Material, averages: &HashMap<String, Color>) -> Color {
+    match &material.texture {
+        Some(texture) => averages.get(texture.path()).copied().unwrap_or(material.color),
+        None => material.color,
+    }
+}
+
+fn bake_face(
+    cube: &Cube,
+    normal: Vec3,
+    corners: [Vec3; 4],
+    objects: &[SceneObject],
+    lights: &[SceneLight],
+    albedo: Color,
+    settings: &BakeSettings,
+) -> BakedFace {
+    let sky = sky_color(lights);
+    let channels = |color: Color| [color.red(), color.green(), color.blue()].map(|c| c as f32 / 255.0);
+    let (albedo, sky) = (channels(albedo), channels(sky));
+    let material = cube.face_material(Face::from_normal(&normal));
+    let reflectivity = material.reflectivity;
+
+    let (edge_u, edge_v) = (corners[1] - corners[0], corners[3] - corners[0]);
+    let count = |edge: &Vec3| ((edge.magnitude() / settings.spacing).ceil() as usize).clamp(2, MAX_SAMPLES);
+    let (samples_u, samples_v) = (count(&edge_u), count(&edge_v));
+
+    // Un punto justo afuera de la cara que cae dentro de otro cubo opaco no
+    // se ve desde ningún lado
+    let covered = |point: &Vec3| {
+        let outside = point + normal * (2.0 * settings.bias);
+        objects.iter().any(|other| {
+            !other.as_cube().is_some_and(|other| std::ptr::eq(other, cube))
+                && !other.material().is_transparent()
+                && !other.material().shadow_catcher
+                && other.contains(&outside)
+        })
+    };
+
+    let mut sum = [0.0; 3];
+    let mut visible = 0;
+    for i in 0..samples_u {
+        for j in 0..samples_v {
+            let (u, v) = ((i as f32 + 0.5) / samples_u as f32, (j as f32 + 0.5) / samples_v as f32);
+            let point = corners[0] + edge_u * u + edge_v * v;
+            if covered(&point) {
+                continue;
+            }
+            visible += 1;
+            let intersect = Intersect::new(point, normal, 0.0, Material::black());
+
+            let visibility = ambient_visibility(&point, &normal, objects, settings);
+            let mut light = [settings.ambient * visibility; 3];
+            for source in lights.iter().filter(|light| light.affects(&cube.group)) {
+                let (direction, distance) = source.toward(&point);
+                let diffuse = normal.dot(&direction).max(0.0);
+                if diffuse <= 0.0 {
+                    continue;
+                }
+                let shadow = cast_tinted_shadow(&intersect, source, objects, None, settings.bias, settings.shadow_quality);
+                let amount = diffuse * source.intensity * source.attenuation(distance) * source.cone(&point);
+                for ((channel, value), shadow) in light.iter_mut().zip(channels(source.color)).zip(shadow) {
+                    *channel += value * (amount * (1.0 - shadow));
+                }
+            }
+            for channel in 0..3 {
+                sum[channel] += albedo[channel] * light[channel] + sky[channel] * reflectivity * visibility;
+            }
+        }
+    }
+
+    // Una cara tapada por completo queda con la luz ambiente sin ocluir
+    let [r, g, b] = if visible > 0 {
+        sum.map(|channel| channel / visible as f32)
+    } else {
+        [0, 1, 2].map(|channel| albedo[channel] * settings.ambient + sky[channel] * reflectivity)
+    }
+    .map(|channel| (channel * 255.0).round().clamp(0.0, 255.0) as u8);
+    BakedFace {
+        corners,
+        normal,
+        color: Color::new(r, g, b) + material.average_emission(),
+        alpha: 1.0 - material.transparency.clamp(0.0, 1.0),
+    }
+}
+
+// Hornea las caras de todos los cubos visibles. Los receptores de sombras no
+// tienen superficie propia y se omiten, y las esferas no tienen caras planas
+// que hornear, aunque sí tapan la luz de los cubos.
+pub fn bake(objects: &[SceneObject], lights: &[SceneLight], settings: &BakeSettings) -> Vec<BakedFace> {
+    let mut averages = HashMap::new();
+    for material in objects.iter().flat_map(SceneObject::materials) {
+        let Some(texture) = &material.texture else {
+            continue;
+        };
+        // Los patrones y las animaciones pasan por una imagen (ver
+        // `TextureSource`); una imagen se promedia tal cual
+        let (size, time) = (settings.texels, settings.time);
+        averages.entry(texture.path().to_string()).or_insert_with(|| match (&material.animation, texture.pattern()) {
+            (Some(animation), _) => animation.bake(size, size, time).average_color(),
+            (None, Some(pattern)) => pattern.bake(size, size, time).average_color(),
+            (None, None) => texture.average_color(),
+        });
+    }
+
+    objects
+        .par_iter()
+        .filter_map(SceneObject::as_cube)
+        .filter(|cube| !cube.material.shadow_catcher)
+        .flat_map_iter(|cube| {
+            let averages = &averages;
+            faces(cube).into_iter().map(move |(normal, corners)| {
+                let albedo = albedo(cube.face_material(Face::from_normal(&normal)), averages);
+                bake_face(cube, normal, corners, objects, lights, albedo, settings)
+            })
+        })
+        .collect()
+}
@@ -0,0 +1,97 @@
+use crate::color::LinearColor;
+use crate::framebuffer::Framebuffer;
+use crate::settings::Bloom;
+
+// Resplandor de lo más brillante de la imagen, como el que deja una lente: la
+// luz de los píxeles que pasan el umbral de `Bloom::threshold` se separa, se
+// difumina y se vuelve a sumar en luz lineal, así el sol, los reflejos y los
+// emisores claros sangran sobre lo que tienen alrededor. A diferencia del halo
+// trabaja sobre la imagen terminada y no sobre los materiales. El desenfoque se
+// hace a media resolución con tres pasadas de caja, parecidas a una gaussiana,
+// así que el costo no depende del radio.
+
+// Pasadas de caja de cada eje
+const PASSES: usize = 3;
+
+fn luminance(color: LinearColor) -> f32 {
+    0.2126 * color.r + 0.7152 * color.g + 0.0722 * color.b
+}
+
+pub fn apply_bloom(framebuffer: &mut Framebuffer, bloom: &Bloom) {
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    if width == 0 || height == 0 || bloom.strength <= 0.0 {
+        return;
+    }
+    let threshold = bloom.threshold.clamp(0.0, 0.999);
+
+    // Lo que pasa el umbral, promediado en celdas de 2x2
+    let (grid_width, grid_height) = (width.div_ceil(2), height.div_ceil(2));
+    let mut glow = vec![[0.0f32; 3]; grid_width * grid_height];
+    let mut counts = vec![0.0f32; grid_width * grid_height];
+    for (index, pixel) in framebuffer.buffer.iter().enumerate() {
+        let color = LinearColor::from_srgb(*pixel);
+        let brightness = luminance(color);
+        let cell = (index / width / 2) * grid_width + index % width / 2;
+        counts[cell] += 1.0;
+        if brightness <= threshold {
+            continue;
+        }
+        // Solo el exceso sobre el umbral, con el tono del píxel
+        let excess = (brightness - threshold) / (1.0 - threshold) / brightness;
+        for (sum, channel) in glow[cell].iter_mut().zip([color.r, color.g, color.b]) {
+            *sum += channel * excess;
+        }
+    }
+    if glow.iter().all(|cell| cell.iter().all(|&channel| channel == 0.0)) {
+        return;
+    }
+    for (cell, count) in glow.iter_mut().zip(counts) {
+        *cell = cell.map(|channel| channel / count);
+    }
+
+    // Cada caja mide un tercio del radio, que se da en fracciones de la altura
+    let radius = ((bloom.radius * grid_height as f32) / PASSES as f32).round().max(1.0) as usize;
+    for _ in 0..PASSES {
+        glow = box_blur(&glow, grid_width, grid_height, radius, true);
+        glow = box_blur(&glow, grid_width, grid_height, radius, false);
+    }
+
+    // Vuelta a resolución completa, interpolando entre los centros de las celdas
+    let glow_at = |x: usize, y: usize| {
+        let u = ((x as f32 + 0.5) / 2.0 - 0.5).clamp(0.0, (grid_width - 1) as f32);
+        let v = ((y as f32 + 0.5) / 2.0 - 0.5).clamp(0.0, (grid_height - 1) as f32);
+        let (u0, v0) = (u as usize, v as usize);
+        let (u1, v1) = ((u0 + 1).min(grid_width - 1), (v0 + 1).min(grid_height - 1));
+        let (tu, tv) = (u.fract(), v.fract());
+        let texel = |u: usize, v: usize| glow[v * grid_width + u];
+        let row = |v: usize| [0, 1, 2].map(|c| texel(u0, v)[c] * (1.0 - tu) + texel(u1, v)[c] * tu);
+        let (top, bottom) = (row(v0), row(v1));
+        [0, 1, 2].map(|c| top[c] * (1.0 - tv) + bottom[c] * tv)
+    };
+    for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+        let [r, g, b] = glow_at(index % width, index / width);
+        *pixel = (LinearColor::from_srgb(*pixel) + LinearColor::new(r, g, b) * bloom.strength).to_srgb();
+    }
+}
+
+// Promedio de una ventana de `2 * radius + 1` celdas en un eje, con sumas
+// acumuladas; fuera de la imagen cuenta como negro, así los bordes no brillan
+// de más
+fn box_blur(input: &[[f32; 3]], width: usize, height: usize, radius: usize, horizontal: bool) -> Vec<[f32; 3]> {
+    let (length, lines) = if horizontal { (width, height) } else { (height, width) };
+    let index = |line: usize, at: usize| if horizontal { line * width + at } else { at * width + line };
+    let window = (2 * radius + 1) as f32;
+    let mut output = vec![[0.0; 3]; input.len()];
+    let mut prefix = vec![[0.0f32; 3]; length + 1];
+    for line in 0..lines {
+        for at in 0..length {
+            let value = input[index(line, at)];
+            prefix[at + 1] = [0, 1, 2].map(|c| prefix[at][c] + value[c]);
+        }
+        for at in 0..length {
+            let (start, end) = (at.saturating_sub(radius), (at + radius + 1).min(length));
+            output[index(line, at)] = [0, 1, 2].map(|c| (prefix[end][c] - prefix[start][c]) / window);
+        }
+    }
+    output
+}
@@ -0,0 +1,86 @@
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::object::SceneObject;
+use crate::framebuffer::Framebuffer;
+use crate::light::SceneLight;
+use crate::renderer::{render_until, RenderStats};
+use crate::settings::RenderSettings;
+
+// Render con tiempo acotado: se traza primero una pasada gruesa y después
+// pasadas cada vez más finas mientras quede tiempo, y se presenta la última
+// que terminó. Cada pasada se traza en su propio framebuffer y se descarta si
+// el plazo la corta a mitad, así la imagen presentada siempre está completa.
+// La primera pasada se traza entera aunque se pase del plazo.
+
+// Escala de resolución y muestras por lado de cada nivel de refinamiento
+pub const LEVELS: [(f32, usize); 5] = [(0.125, 1), (0.25, 1), (0.5, 1), (1.0, 1), (1.0, 2)];
+
+#[derive(Debug, Clone, Copy, Default)]
+pub struct BudgetStats {
+    // Nivel de `LEVELS` que se presentó
+    pub level: usize,
+    pub stats: RenderStats,
+    // Tiempo total, contando las pasadas descartadas
+    pub elapsed: Duration,
+}
+
+impl BudgetStats {
+    // Resolución del nivel presentado para el panel, como "1/4" o "1/1 con 2x2"
+    pub fn describe(&self) -> String {
+        let (scale, samples) = LEVELS[self.level];
+        let fraction = format!("1/{}", (1.0 / scale).round());
+        if samples > 1 {
+            format!("{} con {}x{}", fraction, samples, samples)
+        } else {
+            fraction
+        }
+    }
+}
+
+#[allow(clippy::too_many_arguments)]
+pub fn render_budgeted(
+    framebuffer: &mut Framebuffer,
+    objects: &[SceneObject],
+    camera: &Camera,
+    lights: &[SceneLight],
+    sky_color: Color,
+    settings: &RenderSettings,
+    budget: Duration,
+) -> BudgetStats {
+    let start = Instant::now();
+    let deadline = start + budget;
+    let mut presented: Option<(usize, Framebuffer, RenderStats)> = None;
+    // Lo que tardó la última pasada por píxel trazado, para no empezar una que no entra
+    let mut cost_per_pixel: Option<Duration> = None;
+    for (level, &(scale, samples)) in LEVELS.iter().enumerate() {
+        let size = |length: usize| ((length as f32 * scale).round() as usize).max(1) * samples;
+        let mut pass = Framebuffer::new(size(framebuffer.width), size(framebuffer.height));
+        let pixels = (pass.width * pass.height) as u32;
+        let pass_start = Instant::now();
+        if cost_per_pixel.is_some_and(|cost| pass_start + cost * pixels > deadline) {
+            break;
+        }
+
+        // La resolución la fija el nivel, y el halo se achica con ella. Cada
+        // pasada es una imagen entera, así que no se recorta
+        let pass_settings = RenderSettings {
+            render_scale: 1.0,
+            crop: None,
+            halo_radius: (settings.halo_radius as f32 * scale * samples as f32).round() as usize,
+            ..settings.clone()
+        };
+        let pass_deadline = presented.is_some().then_some(deadline);
+        let Some(stats) = render_until(&mut pass, objects, camera, lights, sky_color, &pass_settings, pass_deadline) else {
+            break;
+        };
+        cost_per_pixel = Some(pass_start.elapsed() / pixels.max(1));
+        let pass = if samples > 1 { pass.downsample(samples, settings.transparent) } else { pass };
+        presented = Some((level, pass, stats));
+    }
+
+    let (level, pass, stats) = presented.expect("la primera pasada siempre termina");
+    pass.upscale_into(framebuffer);
+    BudgetStats { level, stats, elapsed: start.elapsed() }
+}
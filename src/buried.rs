@@ -0,0 +1,164 @@
+use nalgebra_glm::Vec3;
+use std::collections::HashMap;
+
+use crate::cube::Cube;
+use crate::object::SceneObject;
+
+// Detección de cubos enterrados: los que tienen las seis caras tapadas por
+// cubos opacos y a los que ningún rayo puede llegar. Las construcciones de
+// vóxeles importadas están llenas de ellos. Se trabaja sobre una grilla cuya
+// celda es la arista más corta de los cubos opacos; los cubos que no caen
+// justo sobre la grilla nunca se consideran enterrados ni tapan a otros, y
+// las demás formas tampoco.
+
+// Un cubo que ocupa más celdas que esto no se registra, para que una losa
+// enorme en una escena de bloques chicos no llene la grilla
+const MAX_CELLS: i64 = 1 << 16;
+// Tolerancia de alineación con la grilla, en fracción de celda
+pub(crate) const ALIGNMENT: f32 = 1e-3;
+
+pub(crate) type Cell = [i32; 3];
+
+// Celdas de la grilla ocupadas por cubos opacos, con cuántos cubos cubren cada
+// una para poder sacar cubos sin reconstruirla
+pub struct Occupancy {
+    origin: Vec3,
+    cell: f32,
+    cells: HashMap<Cell, u32>,
+}
+
+// Los rayos atraviesan los transparentes y los receptores de sombras
+pub(crate) fn is_opaque(cube: &Cube) -> bool {
+    !cube.material.is_transparent() && !cube.material.shadow_catcher
+}
+
+impl Occupancy {
+    // None si no hay cubos opacos
+    pub fn new<'a>(objects: impl Iterator<Item = &'a SceneObject> + Clone) -> Option<Self> {
+        let opaque = objects.filter_map(SceneObject::as_cube).filter(|cube| is_opaque(cube));
+        let cell = opaque
+            .clone()
+            .map(|cube| {
+                let size = cube.max - cube.min;
+                size.x.min(size.y).min(size.z)
+            })
+            .filter(|&edge| edge > 0.0)
+            .fold(f32::INFINITY, f32::min);
+        if !cell.is_finite() {
+            return None;
+        }
+        let origin = opaque.clone().fold(Vec3::repeat(f32::INFINITY), |origin, cube| origin.inf(&cube.min));
+        Some(Occupancy::with_grid(opaque, origin, cell))
+    }
+
+    // Grilla dada por una esquina y el lado de la celda
+    pub(crate) fn with_grid<'a>(cubes: impl Iterator<Item = &'a Cube>, origin: Vec3, cell: f32) -> Self {
+        let mut occupancy = Occupancy {
+            origin,
+            cell,
+            cells: HashMap::new(),
+        };
+        for cube in cubes {
+            occupancy.add_cube(cube);
+        }
+        occupancy
+    }
+
+    // Celdas [min, max) que ocupa el cubo, si está alineado con la grilla
+    fn cell_box(&self, cube: &Cube) -> Option<(Cell, Cell)> {
+        let to_cell = |value: f32, axis: usize| {
+            let position = (value - self.origin[axis]) / self.cell;
+            let rounded = position.round();
+            ((position - rounded).abs() <= ALIGNMENT).then_some(rounded as i32)
+        };
+        let mut min = [0; 3];
+        let mut max = [0; 3];
+        for axis in 0..3 {
+            min[axis] = to_cell(cube.min[axis], axis)?;
+            max[axis] = to_cell(cube.max[axis], axis)?;
+            if max[axis] <= min[axis] {
+                return None;
+            }
+        }
+        let count: i64 = (0..3).map(|axis| (max[axis] - min[axis]) as i64).product();
+        (count <= MAX_CELLS).then_some((min, max))
+    }
+
+    fn cells_in(min: Cell, max: Cell) -> impl Iterator<Item = Cell> {
+        (min[0]..max[0]).flat_map(move |x| (min[1]..max[1]).flat_map(move |y| (min[2]..max[2]).map(move |z| [x, y, z])))
+    }
+
+    // Posición de un punto en celdas desde el origen de la grilla
+    pub(crate) fn to_grid(&self, point: &Vec3) -> Vec3 {
+        (point - self.origin) / self.cell
+    }
+
+    pub(crate) fn is_occupied(&self, cell: Cell) -> bool {
+        self.cells.contains_key(&cell)
+    }
+
+    pub fn add(&mut self, object: &SceneObject) {
+        if let Some(cube) = object.as_cube() {
+            self.add_cube(cube);
+        }
+    }
+
+    fn add_cube(&mut self, cube: &Cube) {
+        if let Some((min, max)) = self.cell_box(cube).filter(|_| is_opaque(cube)) {
+            for cell in Self::cells_in(min, max) {
+                *self.cells.entry(cell).or_insert(0) += 1;
+            }
+        }
+    }
+
+    pub fn remove(&mut self, object: &SceneObject) {
+        let Some(cube) = object.as_cube() else {
+            return;
+        };
+        if let Some((min, max)) = self.cell_box(cube).filter(|_| is_opaque(cube)) {
+            for cell in Self::cells_in(min, max) {
+                if let Some(count) = self.cells.get_mut(&cell) {
+                    *count -= 1;
+                    if *count == 0 {
+                        self.cells.remove(&cell);
+                    }
+                }
+            }
+        }
+    }
+
+    // Un cubo está enterrado si todas las celdas pegadas a sus caras están
+    // ocupadas por cubos opacos
+    pub fn is_buried(&self, object: &SceneObject) -> bool {
+        let Some((min, max)) = object.as_cube().and_then(|cube| self.cell_box(cube)) else {
+            return false;
+        };
+        (0..3).all(|axis| {
+            [min[axis] - 1, max[axis]].into_iter().all(|layer| {
+                let (mut from, mut to) = (min, max);
+                from[axis] = layer;
+                to[axis] = layer + 1;
+                Self::cells_in(from, to).all(|cell| self.is_occupied(cell))
+            })
+        })
+    }
+}
+
+// Grilla de bloques de una construcción: una esquina y el lado de la celda,
+// que es la arista más repetida entre los cubos opacos, así unas pocas piezas
+// sueltas de otro tamaño no achican la grilla. None si no hay cubos opacos
+pub(crate) fn block_grid<'a>(cubes: impl Iterator<Item = &'a Cube>) -> Option<(Vec3, f32)> {
+    let edge = |cube: &Cube| {
+        let size = cube.max - cube.min;
+        size.x.min(size.y).min(size.z)
+    };
+    let mut counts: HashMap<u32, (usize, Vec3)> = HashMap::new();
+    for cube in cubes.filter(|cube| is_opaque(cube) && edge(cube) > 0.0) {
+        counts.entry(edge(cube).to_bits()).or_insert((0, cube.min)).0 += 1;
+    }
+    // A igual cantidad gana la arista más corta, para no depender del hash
+    counts
+        .into_iter()
+        .max_by(|(a, (count_a, _)), (b, (count_b, _))| count_a.cmp(count_b).then(f32::from_bits(*b).total_cmp(&f32::from_bits(*a))))
+        .map(|(cell, (_, origin))| (origin, f32::from_bits(cell)))
+}
@@ -0,0 +1,632 @@
+use nalgebra_glm::Vec3;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::object::SceneObject;
+use crate::ray_intersect::{Intersect, RayIntersect};
+
+// Jerarquía de volúmenes envolventes sobre las cajas de los objetos, pensada
+// para editar la escena sin reconstruirla: mover un objeto ajusta su hoja y
+// las uniones de sus ancestros, y agregar o quitar uno inserta o saca una hoja
+// eligiendo el hermano que menos agranda el árbol. Cada edición empeora un
+// poco el árbol, así que se compara su costo con el de la última
+// construcción completa y solo se reconstruye cuando se degradó demasiado.
+//
+// Las hojas guardan el índice del objeto en la lista de la escena; `remove`
+// corre los índices siguientes igual que `Vec::remove`.
+
+const NONE: usize = usize::MAX;
+// Costo relativo a la última construcción a partir del cual conviene reconstruir
+const REBUILD_THRESHOLD: f32 = 1.5;
+// Margen relativo con el que `TraceBvh` agranda las cajas, para que una caja
+// no descarte por redondeo un impacto justo en el borde de su objeto
+const TRACE_MARGIN: f32 = 1e-5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Aabb {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Aabb {
+    pub fn of(object: &SceneObject) -> Self {
+        object.bounds()
+    }
+
+    pub fn union(&self, other: &Aabb) -> Aabb {
+        Aabb {
+            min: self.min.inf(&other.min),
+            max: self.max.sup(&other.max),
+        }
+    }
+
+    // Mitad del área de la superficie; alcanza para comparar costos
+    pub fn area(&self) -> f32 {
+        let size = self.max - self.min;
+        size.x * size.y + size.y * size.z + size.z * size.x
+    }
+
+    // Si se tocan o se superponen
+    pub fn overlaps(&self, other: &Aabb) -> bool {
+        (0..3).all(|axis| self.min[axis] <= other.max[axis] && other.min[axis] <= self.max[axis])
+    }
+
+    // Distancia desde un punto hasta la caja; 0 si está dentro
+    pub fn distance_to(&self, point: &Vec3) -> f32 {
+        (self.min - point).sup(&(point - self.max)).sup(&Vec3::zeros()).magnitude()
+    }
+
+    fn padded(&self, margin: f32) -> Aabb {
+        let size = self.min.abs().sup(&self.max.abs()).max() + 1.0;
+        let pad = Vec3::repeat(size * margin);
+        Aabb { min: self.min - pad, max: self.max + pad }
+    }
+
+    fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    // Distancia de entrada del rayo a la caja si la corta antes de `t_max`
+    fn hit(&self, origin: &Vec3, inverse_direction: &Vec3, t_max: f32) -> Option<f32> {
+        let mut near: f32 = 0.0;
+        let mut far = t_max;
+        for axis in 0..3 {
+            let t0 = (self.min[axis] - origin[axis]) * inverse_direction[axis];
+            let t1 = (self.max[axis] - origin[axis]) * inverse_direction[axis];
+            let (t0, t1) = if t0 <= t1 { (t0, t1) } else { (t1, t0) };
+            // Un NaN (rayo paralelo justo sobre una cara) no descarta la caja
+            near = if t0 > near { t0 } else { near };
+            far = if t1 < far { t1 } else { far };
+        }
+        (near <= far).then_some(near)
+    }
+}
+
+#[derive(Debug, Clone)]
+struct Node {
+    bounds: Aabb,
+    parent: usize,
+    // NONE en las hojas
+    children: [usize; 2],
+    // Índice del objeto en las hojas
+    object: usize,
+}
+
+impl Node {
+    fn is_leaf(&self) -> bool {
+        self.children[0] == NONE
+    }
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Bvh {
+    nodes: Vec<Node>,
+    // Nodos sin usar que se reciclan al insertar
+    free: Vec<usize>,
+    root: Option<usize>,
+    // Hoja de cada objeto
+    leaves: Vec<usize>,
+    // Costo del árbol justo después de la última construcción completa
+    built_cost: f32,
+}
+
+impl Bvh {
+    pub fn build(objects: &[SceneObject]) -> Self {
+        let mut bvh = Bvh::default();
+        bvh.rebuild(&objects.iter().map(Aabb::of).collect::<Vec<_>>());
+        bvh
+    }
+
+    // Construcción completa partiendo por la mediana del eje más largo de los
+    // centros; `bounds` es la caja de cada objeto en orden
+    pub fn rebuild(&mut self, bounds: &[Aabb]) {
+        self.nodes.clear();
+        self.free.clear();
+        self.leaves = vec![NONE; bounds.len()];
+        let mut order: Vec<usize> = (0..bounds.len()).collect();
+        self.root = (!order.is_empty()).then(|| self.build_range(&mut order, bounds, NONE));
+        self.built_cost = self.cost();
+    }
+
+    fn build_range(&mut self, order: &mut [usize], bounds: &[Aabb], parent: usize) -> usize {
+        let id = self.nodes.len();
+        if let [object] = *order {
+            self.nodes.push(Node { bounds: bounds[object], parent, children: [NONE; 2], object });
+            self.leaves[object] = id;
+            return id;
+        }
+
+        let (low, high) = order.iter().fold((Vec3::repeat(f32::INFINITY), Vec3::repeat(f32::NEG_INFINITY)), |(low, high), &object| {
+            let center = bounds[object].center();
+            (low.inf(&center), high.sup(&center))
+        });
+        let extent = high - low;
+        let axis = if extent.x >= extent.y && extent.x >= extent.z { 0 } else if extent.y >= extent.z { 1 } else { 2 };
+        let middle = order.len() / 2;
+        order.select_nth_unstable_by(middle, |&a, &b| bounds[a].center()[axis].total_cmp(&bounds[b].center()[axis]));
+
+        self.nodes.push(Node { bounds: bounds[order[0]], parent, children: [NONE; 2], object: NONE });
+        let (left, right) = order.split_at_mut(middle);
+        let left = self.build_range(left, bounds, id);
+        let right = self.build_range(right, bounds, id);
+        self.nodes[id].children = [left, right];
+        self.nodes[id].bounds = self.nodes[left].bounds.union(&self.nodes[right].bounds);
+        id
+    }
+
+    pub fn len(&self) -> usize {
+        self.leaves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.leaves.is_empty()
+    }
+
+    // Suma de las áreas de los nodos internos relativa a la raíz: el número
+    // esperado de cajas internas que prueba un rayo que cruza la escena
+    pub fn cost(&self) -> f32 {
+        let Some(root) = self.root else {
+            return 0.0;
+        };
+        let root_area = self.nodes[root].bounds.area().max(f32::MIN_POSITIVE);
+        let mut total = 0.0;
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id];
+            if !node.is_leaf() {
+                total += node.bounds.area();
+                stack.extend(node.children);
+            }
+        }
+        total / root_area
+    }
+
+    // Costo actual respecto del de la última construcción (1 recién construido)
+    pub fn degradation(&self) -> f32 {
+        if self.built_cost > 0.0 { self.cost() / self.built_cost } else { 1.0 }
+    }
+
+    pub fn needs_rebuild(&self) -> bool {
+        self.degradation() > REBUILD_THRESHOLD
+    }
+
+    fn allocate(&mut self, node: Node) -> usize {
+        match self.free.pop() {
+            Some(id) => {
+                self.nodes[id] = node;
+                id
+            }
+            None => {
+                self.nodes.push(node);
+                self.nodes.len() - 1
+            }
+        }
+    }
+
+    // Recalcula las uniones desde `id` hasta la raíz, o hasta un ancestro que
+    // no cambia
+    fn refit_from(&mut self, mut id: usize) {
+        while id != NONE {
+            let [left, right] = self.nodes[id].children;
+            let bounds = self.nodes[left].bounds.union(&self.nodes[right].bounds);
+            if self.nodes[id].bounds == bounds {
+                return;
+            }
+            self.nodes[id].bounds = bounds;
+            id = self.nodes[id].parent;
+        }
+    }
+
+    // Agrega un objeto al final de la lista
+    pub fn insert(&mut self, bounds: Aabb) {
+        let object = self.leaves.len();
+        let leaf = self.allocate(Node { bounds, parent: NONE, children: [NONE; 2], object });
+        self.leaves.push(leaf);
+        let Some(root) = self.root else {
+            self.root = Some(leaf);
+            return;
+        };
+
+        // Baja por el hijo cuya caja crece menos al sumarle la nueva
+        let mut sibling = root;
+        while !self.nodes[sibling].is_leaf() {
+            let growth = |child: usize| {
+                let child = &self.nodes[child].bounds;
+                child.union(&bounds).area() - child.area()
+            };
+            let [left, right] = self.nodes[sibling].children;
+            sibling = if growth(left) <= growth(right) { left } else { right };
+        }
+
+        let parent = self.nodes[sibling].parent;
+        let joined = self.nodes[sibling].bounds.union(&bounds);
+        let branch = self.allocate(Node { bounds: joined, parent, children: [sibling, leaf], object: NONE });
+        self.nodes[sibling].parent = branch;
+        self.nodes[leaf].parent = branch;
+        if parent == NONE {
+            self.root = Some(branch);
+        } else {
+            let slot = self.nodes[parent].children.iter().position(|&child| child == sibling).expect("el hermano cuelga de su padre");
+            self.nodes[parent].children[slot] = branch;
+            self.refit_from(parent);
+        }
+    }
+
+    // Quita el objeto `object`; los siguientes bajan un índice
+    pub fn remove(&mut self, object: usize) {
+        let leaf = self.leaves.remove(object);
+        for &later in &self.leaves[object..] {
+            self.nodes[later].object -= 1;
+        }
+        self.free.push(leaf);
+
+        let parent = self.nodes[leaf].parent;
+        if parent == NONE {
+            self.root = None;
+            return;
+        }
+        // El hermano ocupa el lugar del padre
+        let [left, right] = self.nodes[parent].children;
+        let sibling = if left == leaf { right } else { left };
+        let grandparent = self.nodes[parent].parent;
+        self.nodes[sibling].parent = grandparent;
+        self.free.push(parent);
+        if grandparent == NONE {
+            self.root = Some(sibling);
+        } else {
+            let slot = self.nodes[grandparent].children.iter().position(|&child| child == parent).expect("el padre cuelga del abuelo");
+            self.nodes[grandparent].children[slot] = sibling;
+            self.refit_from(grandparent);
+        }
+    }
+
+    // Cambia la caja de un objeto que se movió o cambió de tamaño
+    pub fn refit(&mut self, object: usize, bounds: Aabb) {
+        let leaf = self.leaves[object];
+        self.nodes[leaf].bounds = bounds;
+        let parent = self.nodes[leaf].parent;
+        if parent != NONE {
+            self.refit_from(parent);
+        }
+    }
+
+    // Objetos cuya caja corta el rayo antes de `t_max`, de los nodos más
+    // cercanos a los más lejanos. `visit` devuelve la nueva distancia máxima,
+    // así una búsqueda del más cercano poda lo que queda detrás del impacto.
+    pub fn traverse(&self, origin: &Vec3, direction: &Vec3, mut t_max: f32, mut visit: impl FnMut(usize) -> f32) {
+        let Some(root) = self.root else {
+            return;
+        };
+        let inverse_direction = direction.map(|component| 1.0 / component);
+        if self.nodes[root].bounds.hit(origin, &inverse_direction, t_max).is_none() {
+            return;
+        }
+        let mut stack = vec![root];
+        while let Some(id) = stack.pop() {
+            let node = &self.nodes[id];
+            if node.is_leaf() {
+                t_max = t_max.min(visit(node.object));
+                continue;
+            }
+            let hits = node.children.map(|child| self.nodes[child].bounds.hit(origin, &inverse_direction, t_max).map(|t| (t, child)));
+            match hits {
+                [Some(a), Some(b)] => {
+                    // El más cercano queda arriba de la pila
+                    let (near, far) = if a.0 <= b.0 { (a, b) } else { (b, a) };
+                    stack.push(far.1);
+                    stack.push(near.1);
+                }
+                [Some((_, child)), None] | [None, Some((_, child))] => stack.push(child),
+                [None, None] => {}
+            }
+        }
+    }
+
+    // Objetos cuya caja toca o se superpone con `bounds`, sin orden fijo. Solo
+    // baja por los nodos que tocan la caja: O(log n + k) para k resultados en
+    // un árbol equilibrado
+    pub fn overlapping(&self, bounds: Aabb) -> impl Iterator<Item = usize> + '_ {
+        let mut stack: Vec<usize> = self.root.into_iter().collect();
+        std::iter::from_fn(move || {
+            while let Some(id) = stack.pop() {
+                let node = &self.nodes[id];
+                if !node.bounds.overlaps(&bounds) {
+                    continue;
+                }
+                if node.is_leaf() {
+                    return Some(node.object);
+                }
+                stack.extend(node.children);
+            }
+            None
+        })
+    }
+
+    // Objetos cuya caja queda a lo sumo a `bound` del punto, de los nodos más
+    // cercanos a los más lejanos. Como en `traverse`, `visit` devuelve la
+    // nueva cota, así una búsqueda del más cercano poda lo que queda más lejos
+    pub fn traverse_near(&self, point: &Vec3, mut bound: f32, mut visit: impl FnMut(usize) -> f32) {
+        let Some(root) = self.root else {
+            return;
+        };
+        let mut stack = vec![(self.nodes[root].bounds.distance_to(point), root)];
+        while let Some((distance, id)) = stack.pop() {
+            if distance > bound {
+                continue;
+            }
+            let node = &self.nodes[id];
+            if node.is_leaf() {
+                bound = bound.min(visit(node.object));
+                continue;
+            }
+            let [a, b] = node.children.map(|child| (self.nodes[child].bounds.distance_to(point), child));
+            // El más cercano queda arriba de la pila
+            let (near, far) = if a.0 <= b.0 { (a, b) } else { (b, a) };
+            stack.push(far);
+            stack.push(near);
+        }
+    }
+
+    // Como el `closest_hit` del renderer: el impacto más cercano a partir de
+    // `t_min`, con los receptores de sombras solo si `catchers` es true. Los
+    // objetos sin caja (ver `SceneObject::is_bounded`) no se alcanzan
+    pub fn closest_hit(
+        &self,
+        objects: &[SceneObject],
+        origin: &Vec3,
+        direction: &Vec3,
+        t_min: f32,
+        catchers: bool,
+    ) -> Option<(Intersect, usize)> {
+        let mut closest: Option<(Intersect, usize)> = None;
+        self.traverse(origin, direction, f32::INFINITY, |index| {
+            let object = &objects[index];
+            if !object.material().shadow_catcher || catchers {
+                let hit = object.ray_intersect(origin, direction);
+                let best = closest.as_ref().map_or(f32::INFINITY, |(best, _)| best.distance);
+                if hit.is_intersecting && hit.distance >= t_min && hit.distance < best {
+                    closest = Some((hit, index));
+                }
+            }
+            closest.as_ref().map_or(f32::INFINITY, |(best, _)| best.distance)
+        });
+        closest
+    }
+}
+
+// Lo que usa el renderer para trazar los rayos: un árbol sobre los objetos con
+// caja y aparte la lista de los que no tienen (los planos), que se prueban
+// siempre. Da exactamente lo mismo que recorrer la lista entera, empates
+// incluidos, así que se puede prender o apagar sin que cambie la imagen. La
+// escena lo mantiene con `insert`, `refit` y `remove` al editar objetos
+// sueltos, como al `Bvh` que lleva adentro, y `refresh` lo rehace cuando
+// cambian las cajas de una lista que nadie mantiene.
+#[derive(Debug, Clone)]
+pub struct TraceBvh {
+    bvh: Bvh,
+    // Índice en la lista de la escena de cada objeto del árbol
+    bounded: Vec<usize>,
+    unbounded: Vec<usize>,
+    // Con qué se construyó, para saber cuándo rehacerlo
+    scene_key: u64,
+}
+
+// Huella de las cajas de los objetos; los materiales se miran al trazar
+fn scene_key(objects: &[SceneObject]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    objects.len().hash(&mut hasher);
+    for object in objects {
+        let bounds = object.bounds();
+        for value in bounds.min.iter().chain(bounds.max.iter()) {
+            value.to_bits().hash(&mut hasher);
+        }
+        object.is_bounded().hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+// Si un impacto a `distance` del objeto `index` le gana al mejor hasta ahora:
+// el más cercano y, a igual distancia, el primero de la lista
+fn better<T>(best: &Option<(f32, T, usize)>, distance: f32, index: usize) -> bool {
+    best.as_ref().is_none_or(|&(best, _, best_index)| distance < best || (distance == best && index < best_index))
+}
+
+impl TraceBvh {
+    pub fn new(objects: &[SceneObject]) -> Self {
+        let (bounded, unbounded): (Vec<usize>, Vec<usize>) = (0..objects.len()).partition(|&index| objects[index].is_bounded());
+        let mut bvh = Bvh::default();
+        bvh.rebuild(&bounded.iter().map(|&index| objects[index].bounds().padded(TRACE_MARGIN)).collect::<Vec<_>>());
+        TraceBvh { bvh, bounded, unbounded, scene_key: scene_key(objects) }
+    }
+
+    // Si se construyó o se mantuvo con estos mismos objetos
+    pub fn is_current(&self, objects: &[SceneObject]) -> bool {
+        self.scene_key == scene_key(objects)
+    }
+
+    // Suma al árbol el último objeto de `objects`, recién agregado al final
+    pub fn insert(&mut self, objects: &[SceneObject]) {
+        let index = objects.len() - 1;
+        let object = &objects[index];
+        if object.is_bounded() {
+            self.bvh.insert(object.bounds().padded(TRACE_MARGIN));
+            self.bounded.push(index);
+        } else {
+            self.unbounded.push(index);
+        }
+        self.maintain(objects);
+    }
+
+    // Ajusta la caja del objeto `index`, que se movió o cambió de tamaño
+    pub fn refit(&mut self, objects: &[SceneObject], index: usize) {
+        if let Ok(leaf) = self.bounded.binary_search(&index) {
+            self.bvh.refit(leaf, objects[index].bounds().padded(TRACE_MARGIN));
+        }
+        self.maintain(objects);
+    }
+
+    // Saca el objeto `index`; `objects` es la lista ya sin él, con los
+    // siguientes un índice más abajo
+    pub fn remove(&mut self, objects: &[SceneObject], index: usize) {
+        match self.bounded.binary_search(&index) {
+            Ok(leaf) => {
+                self.bvh.remove(leaf);
+                self.bounded.remove(leaf);
+            }
+            Err(_) => self.unbounded.retain(|&other| other != index),
+        }
+        for other in self.bounded.iter_mut().chain(&mut self.unbounded) {
+            if *other > index {
+                *other -= 1;
+            }
+        }
+        self.maintain(objects);
+    }
+
+    // Después de una edición: reconstruye si el árbol se degradó demasiado y
+    // si no anota la lista con la que quedó al día
+    fn maintain(&mut self, objects: &[SceneObject]) {
+        if self.bvh.needs_rebuild() {
+            *self = TraceBvh::new(objects);
+        } else {
+            self.scene_key = scene_key(objects);
+        }
+    }
+
+    // Costo actual respecto del de la última construcción (ver `Bvh::degradation`)
+    pub fn degradation(&self) -> f32 {
+        self.bvh.degradation()
+    }
+
+    // Objetos de la lista con la que se construyó
+    pub fn len(&self) -> usize {
+        self.bounded.len() + self.unbounded.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // El objeto de menor distancia según `test`, que devuelve la distancia y lo
+    // que se quiere guardar del impacto, o None si el objeto no cuenta; el
+    // recorte cercano del renderer lo usa para decidir él qué cuenta
+    pub fn closest_by<T>(&self, origin: &Vec3, direction: &Vec3, test: impl FnMut(usize) -> Option<(f32, T)>) -> Option<(T, usize)> {
+        self.closest_within(origin, direction, f32::INFINITY, test)
+    }
+
+    // Como `closest_by` pero sin mirar lo que queda más allá de `t_max`
+    fn closest_within<T>(&self, origin: &Vec3, direction: &Vec3, t_max: f32, mut test: impl FnMut(usize) -> Option<(f32, T)>) -> Option<(T, usize)> {
+        let mut best: Option<(f32, T, usize)> = None;
+        self.bvh.traverse(origin, direction, t_max, |leaf| {
+            let index = self.bounded[leaf];
+            if let Some((distance, value)) = test(index) {
+                if better(&best, distance, index) {
+                    best = Some((distance, value, index));
+                }
+            }
+            best.as_ref().map_or(t_max, |&(distance, _, _)| distance)
+        });
+        for &index in &self.unbounded {
+            if let Some((distance, value)) = test(index) {
+                if better(&best, distance, index) {
+                    best = Some((distance, value, index));
+                }
+            }
+        }
+        best.map(|(_, value, index)| (value, index))
+    }
+
+    // Impacto más cercano del rayo con cualquier objeto, receptores de sombras incluidos
+    pub fn intersect(&self, objects: &[SceneObject], origin: &Vec3, direction: &Vec3) -> Option<Intersect> {
+        self.closest_hit(objects, origin, direction, 0.0, true).map(|(intersect, _)| intersect)
+    }
+
+    // Como el `closest_hit` del renderer: el impacto más cercano a partir de
+    // `t_min`, con los receptores de sombras solo si `catchers` es true
+    pub fn closest_hit(
+        &self,
+        objects: &[SceneObject],
+        origin: &Vec3,
+        direction: &Vec3,
+        t_min: f32,
+        catchers: bool,
+    ) -> Option<(Intersect, usize)> {
+        self.closest_by(origin, direction, |index| {
+            let object = &objects[index];
+            if object.material().shadow_catcher && !catchers {
+                return None;
+            }
+            let hit = object.ray_intersect(origin, direction);
+            (hit.is_intersecting && hit.distance >= t_min).then_some((hit.distance, hit))
+        })
+    }
+
+    // Objetos de la lista cuya caja real toca `bounds`, sin orden fijo. Los
+    // que no tienen caja se devuelven siempre: decidir si de verdad cortan la
+    // caja queda para quien pregunta
+    pub fn overlapping<'a>(&'a self, objects: &'a [SceneObject], bounds: Aabb) -> impl Iterator<Item = usize> + 'a {
+        let bounded = self.bvh.overlapping(bounds).map(|leaf| self.bounded[leaf]);
+        bounded.filter(move |&index| objects[index].bounds().overlaps(&bounds)).chain(self.unbounded.iter().copied())
+    }
+
+    // El objeto de menor `distance` al punto, que no puede ser menor que la
+    // distancia a la caja del objeto; a igual distancia, el primero de la lista
+    pub fn nearest(&self, point: &Vec3, distance: impl Fn(usize) -> f32) -> Option<(usize, f32)> {
+        let mut best: Option<(f32, (), usize)> = None;
+        self.bvh.traverse_near(point, f32::INFINITY, |leaf| {
+            let index = self.bounded[leaf];
+            let distance = distance(index);
+            if better(&best, distance, index) {
+                best = Some((distance, (), index));
+            }
+            best.as_ref().map_or(f32::INFINITY, |&(distance, _, _)| distance)
+        });
+        for &index in &self.unbounded {
+            let distance = distance(index);
+            if better(&best, distance, index) {
+                best = Some((distance, (), index));
+            }
+        }
+        best.map(|(distance, _, index)| (index, distance))
+    }
+
+    // Índices de los objetos cuya caja corta el rayo antes de `t_max` y de los
+    // que no tienen caja, que se visitan siempre al final. Como en
+    // `Bvh::traverse`, `visit` devuelve la nueva distancia máxima
+    pub fn traverse(&self, origin: &Vec3, direction: &Vec3, t_max: f32, mut visit: impl FnMut(usize) -> f32) {
+        self.bvh.traverse(origin, direction, t_max, |leaf| visit(self.bounded[leaf]));
+        for &index in &self.unbounded {
+            visit(index);
+        }
+    }
+
+    // El impacto más cercano a `origin` entre los objetos que acepten `casts`
+    // y corten el rayo entre `t_min` y `t_max`; a igual distancia, el primero
+    // de la lista, como en un recorrido lineal
+    pub fn nearest_blocker(
+        &self,
+        objects: &[SceneObject],
+        origin: &Vec3,
+        direction: &Vec3,
+        t_min: f32,
+        t_max: f32,
+        casts: impl Fn(&SceneObject) -> bool,
+    ) -> Option<(Intersect, usize)> {
+        self.closest_within(origin, direction, t_max, |index| {
+            let object = &objects[index];
+            if !casts(object) {
+                return None;
+            }
+            let hit = object.ray_intersect(origin, direction);
+            (hit.is_intersecting && hit.distance >= t_min && hit.distance < t_max).then_some((hit.distance, hit))
+        })
+    }
+}
+
+// Mantiene el árbol al día con los objetos: lo rehace si cambiaron sus cajas
+pub fn refresh(current: &mut Option<Arc<TraceBvh>>, objects: &[SceneObject]) {
+    if current.as_ref().is_none_or(|bvh| bvh.scene_key != scene_key(objects)) {
+        *current = Some(Arc::new(TraceBvh::new(objects)));
+    }
+}
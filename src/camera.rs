@@ -1,10 +1,43 @@
 use nalgebra_glm::Vec3;
 use std::f32::consts::PI;
 
+use crate::renderer::FOV;
+
+// How the window input drives the camera: orbiting around `center`, or
+// flying with the eye and turning the view around it
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum CameraMode {
+    #[default]
+    Orbit,
+    Fly,
+}
+
+// How primary rays leave the camera: all from the eye, spread by the field of
+// view, or parallel to the view direction from an image plane through the
+// eye that reaches `half_height` world units above and below the center
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum Projection {
+    #[default]
+    Perspective,
+    Orthographic { half_height: f32 },
+}
+
+// Pitch limit shared by orbiting and turning, short of the poles so the view
+// never flips over
+const MAX_PITCH: f32 = PI / 2.0 - 0.1;
+
+// Narrowest and widest vertical field of view `widen` allows, in radians
+pub const MIN_FOV: f32 = 20.0 * PI / 180.0;
+pub const MAX_FOV: f32 = 120.0 * PI / 180.0;
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Camera {
     pub eye: Vec3,    // Camera position in world space
     pub center: Vec3, // Point the camera is looking at
     pub up: Vec3,     // Up vector
+    pub fov: f32,     // Vertical field of view in radians
+    pub mode: CameraMode,
+    pub projection: Projection,
 }
 
 impl Camera {
@@ -13,13 +46,60 @@ impl Camera {
             eye,
             center,
             up,
+            fov: FOV,
+            mode: CameraMode::Orbit,
+            projection: Projection::Perspective,
         }
     }
 
-    pub fn basis_change(&self, vector: &Vec3) -> Vec3 {
+    // Half-height of the image plane at unit distance, the factor that turns
+    // the field of view into screen coordinates
+    pub fn perspective_scale(&self) -> f32 {
+        (self.fov * 0.5).tan()
+    }
+
+    // Orthographic projection that frames what the perspective one shows at
+    // the distance of the center, so switching keeps the subject the same size
+    pub fn matching_orthographic(&self) -> Projection {
+        let distance = (self.center - self.eye).magnitude();
+        Projection::Orthographic { half_height: distance * self.perspective_scale() }
+    }
+
+    // Distance from the origin of the primary ray that reaches `point` to the
+    // point: from the eye in perspective, from the image plane in orthographic
+    pub fn ray_distance(&self, point: &Vec3) -> f32 {
+        match self.projection {
+            Projection::Perspective => (point - self.eye).magnitude(),
+            Projection::Orthographic { .. } => (point - self.eye).dot(&self.basis().2),
+        }
+    }
+
+    // World size of a pixel of an image `height` pixels tall, `distance` away
+    // along its primary ray
+    pub fn pixel_footprint(&self, distance: f32, height: usize) -> f32 {
+        match self.projection {
+            Projection::Perspective => distance * 2.0 * self.perspective_scale() / height as f32,
+            Projection::Orthographic { half_height } => 2.0 * half_height / height as f32,
+        }
+    }
+
+    // Changes the vertical field of view by `delta` radians (negative narrows
+    // it), within [MIN_FOV, MAX_FOV]. The horizontal one follows from the
+    // aspect ratio of the image
+    pub fn widen(&mut self, delta: f32) {
+        self.fov = (self.fov + delta).clamp(MIN_FOV, MAX_FOV);
+    }
+
+    // Returns the (right, up, forward) orthonormal basis of the camera
+    pub fn basis(&self) -> (Vec3, Vec3, Vec3) {
         let forward = (self.center - self.eye).normalize();
         let right = forward.cross(&self.up).normalize();
         let up = right.cross(&forward).normalize();
+        (right, up, forward)
+    }
+
+    pub fn basis_change(&self, vector: &Vec3) -> Vec3 {
+        let (right, up, forward) = self.basis();
 
         let rotated = 
         vector.x * right +
@@ -29,6 +109,81 @@ impl Camera {
         rotated.normalize()
     }
 
+    // Projects a world space point to normalized screen coordinates in [-1, 1],
+    // the inverse of the primary ray generation in `render`. Returns None for
+    // points behind the camera. Orthographic cameras ignore `perspective_scale`.
+    pub fn project(&self, point: &Vec3, aspect_ratio: f32, perspective_scale: f32) -> Option<(f32, f32)> {
+        let (right, up, forward) = self.basis();
+        let relative = point - self.eye;
+        let depth = relative.dot(&forward);
+        if depth <= 0.0 {
+            return None;
+        }
+
+        // Half-width and half-height of the visible image at that depth
+        let (half_width, half_height) = match self.projection {
+            Projection::Perspective => (depth * aspect_ratio * perspective_scale, depth * perspective_scale),
+            Projection::Orthographic { half_height } => (half_height * aspect_ratio, half_height),
+        };
+        let screen_x = relative.dot(&right) / half_width;
+        let screen_y = relative.dot(&up) / half_height;
+        Some((screen_x, screen_y))
+    }
+
+    // Moves the eye along the current view direction so the bounding sphere
+    // of the box fills the vertical field of view
+    pub fn frame_bounds(&mut self, min: &Vec3, max: &Vec3) {
+        let (_, _, forward) = self.basis();
+        let center = (min + max) * 0.5;
+        let radius = (max - min).magnitude() * 0.5;
+        let distance = radius / (self.fov * 0.5).sin();
+
+        self.center = center;
+        self.eye = center - forward * distance;
+    }
+
+    // Scales the eye-center distance by `factor` (below 1 zooms in) along the
+    // view direction, keeping it within [min_distance, max_distance] so the eye
+    // never reaches or crosses the center
+    pub fn zoom(&mut self, factor: f32, min_distance: f32, max_distance: f32) {
+        let offset = self.eye - self.center;
+        let distance = offset.magnitude();
+        if distance <= 0.0 {
+            return;
+        }
+        let target = (distance * factor).min(max_distance).max(min_distance);
+        self.eye = self.center + offset * (target / distance);
+        // The orthographic image has no perspective to bring things closer, so
+        // it shrinks with the distance instead
+        if let Projection::Orthographic { half_height } = &mut self.projection {
+            *half_height *= target / distance;
+        }
+    }
+
+    // Moves the eye and the center together by `offset`, given along the
+    // camera's right vector, its `up` vector and the view direction
+    pub fn move_relative(&mut self, offset: Vec3) {
+        let (right, _, forward) = self.basis();
+        let shift = right * offset.x + self.up * offset.y + forward * offset.z;
+        self.eye += shift;
+        self.center += shift;
+    }
+
+    // Turns the view direction around the eye, the inverse of `orbit`: a
+    // positive yaw turns right and a positive pitch looks up. The center stays
+    // at the same distance in front of the eye
+    pub fn rotate(&mut self, delta_yaw: f32, delta_pitch: f32) {
+        let view = self.center - self.eye;
+        let distance = view.magnitude();
+        if distance <= 0.0 {
+            return;
+        }
+        let yaw = view.z.atan2(view.x) + delta_yaw;
+        let horizontal = (view.x * view.x + view.z * view.z).sqrt();
+        let pitch = (view.y.atan2(horizontal) + delta_pitch).clamp(-MAX_PITCH, MAX_PITCH);
+        self.center = self.eye + Vec3::new(yaw.cos() * pitch.cos(), pitch.sin(), yaw.sin() * pitch.cos()) * distance;
+    }
+
     pub fn orbit(&mut self, delta_yaw: f32, delta_pitch: f32) {
         // Calculate the vector from the center to the eye (radius vector) and measure the distance
         let radius_vector = self.eye - self.center;
@@ -50,7 +205,7 @@ impl Camera {
         // Keep yaw in range [0, 2π] for consistency
         let new_yaw = (current_yaw + delta_yaw) % (2.0 * PI);
         // Clamp pitch to slightly less than [-π/2, π/2] to prevent gimbal lock
-        let new_pitch = (current_pitch + delta_pitch).clamp(-PI / 2.0 + 0.1, PI / 2.0 - 0.1);
+        let new_pitch = (current_pitch + delta_pitch).clamp(-MAX_PITCH, MAX_PITCH);
 
         // Calculate new eye position
         // We use spherical coordinates to cartesian conversion:
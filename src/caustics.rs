@@ -0,0 +1,154 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::TAU;
+
+use crate::bvh::TraceBvh;
+use crate::object::SceneObject;
+use crate::ray_intersect::Intersect;
+use crate::renderer::closest_hit;
+use crate::scene::WORLD_UP;
+use crate::settings::Caustics;
+
+// Cáusticas aproximadas del agua: las ondas de la superficie juntan la luz del
+// sol en líneas brillantes que bailan en el fondo. En lugar de seguir la luz
+// refractada, un punto que tiene encima un material con `caustics` (lo busca
+// un rayo hacia arriba) multiplica el sol por un dibujo calculado en X y Z del
+// mundo: dos capas de celdas de Voronoi, con las líneas brillantes en los
+// bordes entre celdas, que se desplazan con el tiempo de las olas. Con más
+// agua encima el dibujo se agranda y se suaviza. Solo cambia la luz que ya
+// llega según la sombra y se debilita con ella, así que no ilumina lo que
+// algo opaco tapa, y se apaga con el sol bajo el horizonte. Cuesta uno o dos
+// rayos por impacto iluminado por el sol mientras la escena tenga agua.
+
+// Lado de las celdas a ras de la superficie, en unidades del mundo, y cuánto
+// crece por cada unidad de profundidad
+const CELL: f32 = 0.3;
+const CELL_PER_DEPTH: f32 = 0.35;
+// Ancho de las líneas brillantes, en fracciones de celda
+const LINE_WIDTH: f32 = 0.12;
+// Factor del sol entre las líneas y en el centro de una línea, elegidos para
+// que el promedio quede cerca de 1 y el fondo no gane ni pierda luz
+const DARK: f32 = 0.77;
+const BRIGHT: f32 = 2.5;
+// Cuánto se apaga el contraste por cada unidad de profundidad
+const DEPTH_FADE: f32 = 0.4;
+// Radio y velocidad angular (por segundo) del círculo en que se mece cada
+// punto de la grilla, y velocidad a la que se desplaza cada capa
+const SWAY: f32 = 0.18;
+const SWAY_SPEED: f32 = 1.3;
+const DRIFT: [(f32, f32); 2] = [(0.06, 0.035), (-0.045, 0.05)];
+// Profundidad hasta la que se busca la superficie con el cielo abierto encima
+const MAX_DEPTH: f32 = 16.0;
+// Seno de la elevación del sol a partir del cual las cáusticas se ven enteras
+const SUN_FADE: f32 = 0.15;
+
+fn smoothstep(low: f32, high: f32, x: f32) -> f32 {
+    let t = ((x - low) / (high - low)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Tres valores pseudoaleatorios en [0, 1) para una celda de la grilla
+fn hash(x: i32, z: i32, seed: u32) -> [f32; 3] {
+    let mut state = (x as u32 as u64) << 32 | z as u32 as u64;
+    state ^= (seed as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    [0, 1, 2].map(|_| {
+        // splitmix64
+        state = state.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut value = state;
+        value = (value ^ (value >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        value = (value ^ (value >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        value ^= value >> 31;
+        (value >> 40) as f32 / (1u64 << 24) as f32
+    })
+}
+
+// Distancia, en celdas, al borde entre las dos celdas más cercanas (la
+// segunda distancia menos la primera) de una grilla con un punto por celda
+fn voronoi_edge(x: f32, z: f32, time: f32, seed: u32) -> f32 {
+    let (cell_x, cell_z) = (x.floor() as i32, z.floor() as i32);
+    let (mut first, mut second) = (f32::MAX, f32::MAX);
+    for (dx, dz) in (-1..=1).flat_map(|dx| (-1..=1).map(move |dz| (dx, dz))) {
+        let (neighbor_x, neighbor_z) = (cell_x + dx, cell_z + dz);
+        let [jitter_x, jitter_z, phase] = hash(neighbor_x, neighbor_z, seed);
+        let angle = time * SWAY_SPEED + phase * TAU;
+        let point_x = neighbor_x as f32 + 0.2 + 0.6 * jitter_x + SWAY * angle.cos();
+        let point_z = neighbor_z as f32 + 0.2 + 0.6 * jitter_z + SWAY * angle.sin();
+        let distance = ((point_x - x).powi(2) + (point_z - z).powi(2)).sqrt();
+        if distance < first {
+            second = first;
+            first = distance;
+        } else if distance < second {
+            second = distance;
+        }
+    }
+    second - first
+}
+
+// Factor por el que se multiplica el sol en (x, z) del mundo con `depth`
+// unidades de agua encima, a los `time` segundos de las olas: entre `DARK` y
+// `BRIGHT` a ras de la superficie y cada vez más cerca de 1 más abajo
+pub fn pattern(x: f32, z: f32, time: f32, depth: f32) -> f32 {
+    let depth = depth.max(0.0);
+    let cell = CELL + CELL_PER_DEPTH * depth;
+    // La segunda capa es más fina y va en otra dirección; juntas no dejan
+    // ver la grilla
+    let layer = |index: usize, scale: f32| {
+        let (drift_x, drift_z) = DRIFT[index];
+        let size = cell * scale;
+        let edge = voronoi_edge((x + drift_x * time) / size, (z + drift_z * time) / size, time, index as u32);
+        1.0 - smoothstep(0.0, LINE_WIDTH, edge)
+    };
+    let lines = 0.6 * layer(0, 1.0) + 0.4 * layer(1, 0.63);
+    let contrast = 1.0 / (1.0 + DEPTH_FADE * depth);
+    1.0 + (DARK + (BRIGHT - DARK) * lines - 1.0) * contrast
+}
+
+// Unidades de agua sobre `point`, hasta la superficie de un material con
+// `caustics`; None si no está debajo del agua. Desde adentro del agua el rayo
+// hacia arriba no ve la cara por la que saldría, así que la superficie se
+// busca bajando desde lo primero que haya arriba
+pub fn water_depth(point: &Vec3, objects: &[SceneObject], bvh: Option<&TraceBvh>, bias: f32) -> Option<f32> {
+    let up = WORLD_UP;
+    let origin = point + up * bias;
+    let above = closest_hit(&origin, &up, objects, bvh, bias, false);
+    if let Some((hit, _)) = above.as_ref().filter(|(hit, _)| hit.material.caustics) {
+        return Some(hit.distance);
+    }
+    let reach = above.map_or(MAX_DEPTH, |(hit, _)| hit.distance);
+    let (hit, _) = closest_hit(&(origin + up * reach), &-up, objects, bvh, bias, false)?;
+    (hit.material.caustics && hit.distance < reach).then_some(reach - hit.distance)
+}
+
+// Factor del sol que llega por `toward_sun` a un impacto: el dibujo si está
+// debajo del agua y 1 si no. Las líneas necesitan el sol directo, así que el
+// dibujo se pesa por `reach`, la fracción que deja pasar la sombra, además de
+// `strength` y la altura del sol. La superficie del agua misma no recibe
+// cáusticas
+#[allow(clippy::too_many_arguments)]
+pub fn sun_factor(
+    intersect: &Intersect,
+    toward_sun: &Vec3,
+    reach: f32,
+    objects: &[SceneObject],
+    bvh: Option<&TraceBvh>,
+    bias: f32,
+    caustics: &Caustics,
+) -> f32 {
+    let weight = caustics.strength * reach.clamp(0.0, 1.0) * smoothstep(0.0, SUN_FADE, toward_sun.dot(&WORLD_UP));
+    if weight <= 0.0 || intersect.material.caustics {
+        return 1.0;
+    }
+    let Some(depth) = water_depth(&intersect.point, objects, bvh, bias) else {
+        return 1.0;
+    };
+    let point = intersect.point;
+    (1.0 + (pattern(point.x, point.z, caustics.time, depth) - 1.0) * weight).max(0.0)
+}
+
+// Deja las cáusticas al tiempo `time` de las olas si algún objeto tiene un
+// material de agua, o las quita si ninguno lo tiene. Devuelve si quedaron
+// activas, ya que entonces el fondo cambia en cada frame
+pub fn refresh(current: &mut Option<Caustics>, objects: &[SceneObject], time: f32) -> bool {
+    let water = objects.iter().flat_map(SceneObject::materials).any(|material| material.caustics);
+    *current = water.then(|| Caustics { time, ..current.unwrap_or_default() });
+    water
+}
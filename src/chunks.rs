@@ -0,0 +1,310 @@
+use nalgebra_glm::Vec3;
+use std::collections::HashMap;
+
+use crate::buried::{block_grid, is_opaque};
+use crate::bvh::Aabb;
+use crate::camera::{Camera, Projection};
+use crate::cube::Cube;
+use crate::merge::merge_cubes;
+use crate::object::SceneObject;
+
+// Escena partida en trozos para construcciones de cientos de miles de
+// bloques, que no se pueden trazar enteras. Los objetos se reparten por su
+// centro en trozos de `size` bloques por lado sobre la grilla de
+// `buried::block_grid`, y cada trozo guarda sus cubos fusionados (ver
+// `merge`) y un sustituto de bloques más grandes. En cada frame se elige qué
+// se traza: completos los trozos a menos de `radius` bloques de la cámara,
+// empezando por los más cercanos mientras entren en `max_cubes`; con su
+// sustituto los que quedan dentro del campo de visión o del radio, y nada los
+// demás. Los trozos lejanos que no se ven no proyectan sombras ni aparecen en
+// los reflejos, y los que cruzan el radio cambian de golpe de sustituto a
+// completos. Los objetos sin caja, como un plano de suelo, no caen en ningún
+// trozo y se trazan siempre.
+
+// Bloques de la grilla por lado de cada bloque del sustituto
+const PROXY_STEP: f32 = 4.0;
+// Un cubo que cubre más bloques del sustituto que esto queda entero en el
+// sustituto, para no partir en pedazos una losa enorme
+const MAX_PROXY_SPLIT: usize = 64;
+
+// Lado de los trozos y radio en bloques, y cuántos cubos completos se trazan
+// como máximo
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ChunkSettings {
+    pub size: usize,
+    pub radius: f32,
+    pub max_cubes: usize,
+}
+
+impl Default for ChunkSettings {
+    fn default() -> Self {
+        ChunkSettings {
+            size: 16,
+            radius: 48.0,
+            max_cubes: 4096,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Detail {
+    Full,
+    Proxy,
+    Hidden,
+}
+
+struct Chunk {
+    bounds: Aabb,
+    objects: Vec<SceneObject>,
+    proxy: Vec<SceneObject>,
+}
+
+// Cuántos trozos se trazan completos, cuántos con su sustituto y cuántos no se trazan
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct ChunkCounts {
+    pub full: usize,
+    pub proxy: usize,
+    pub hidden: usize,
+}
+
+pub struct ChunkStream {
+    settings: ChunkSettings,
+    origin: Vec3,
+    // Lado de un bloque
+    cell: f32,
+    chunks: Vec<Chunk>,
+    keys: HashMap<[i32; 3], usize>,
+    // Detalle con el que se trazó cada trozo en la última actualización
+    details: Vec<Detail>,
+    unbounded: Vec<SceneObject>,
+}
+
+impl Chunk {
+    fn new(objects: Vec<SceneObject>, origin: Vec3, step: f32) -> Self {
+        let objects = merge_cubes(objects);
+        let bounds = objects
+            .iter()
+            .map(Aabb::of)
+            .reduce(|bounds, other| bounds.union(&other))
+            .expect("los trozos tienen al menos un objeto");
+        let proxy = proxy_of(&objects, origin, step);
+        Chunk { bounds, objects, proxy }
+    }
+}
+
+// Volumen que cubre cada objeto, por índice, dentro de un bloque del sustituto
+type Votes = Vec<(f32, usize)>;
+
+// Sustituto de un trozo: un cubo por cada bloque de `step` de lado que toca
+// algún cubo opaco, ajustado a lo que los cubos ocupan dentro de él y con el
+// material y el grupo que más volumen cubren ahí. Los transparentes, los
+// receptores de sombras y las formas que no son cubos pasan tal cual, porque
+// juntarlos con los opacos cambia lo que se ve a través de ellos o su silueta
+fn proxy_of(objects: &[SceneObject], origin: Vec3, step: f32) -> Vec<SceneObject> {
+    let mut blocks: HashMap<[i32; 3], (Aabb, Votes)> = HashMap::new();
+    let mut proxy = Vec::new();
+    for (index, object) in objects.iter().enumerate() {
+        let Some(cube) = object.as_cube().filter(|cube| is_opaque(cube)) else {
+            proxy.push(object.clone());
+            continue;
+        };
+        let from = ((cube.min - origin) / step).map(|value| value.floor() as i32);
+        let to = ((cube.max - origin) / step).map(|value| value.ceil() as i32);
+        let count: usize = (0..3).map(|axis| (to[axis] - from[axis]).max(1) as usize).product();
+        if count > MAX_PROXY_SPLIT {
+            proxy.push(object.clone());
+            continue;
+        }
+        for x in from.x..to.x.max(from.x + 1) {
+            for y in from.y..to.y.max(from.y + 1) {
+                for z in from.z..to.z.max(from.z + 1) {
+                    let corner = origin + Vec3::new(x as f32, y as f32, z as f32) * step;
+                    let min = cube.min.sup(&corner);
+                    let max = cube.max.inf(&(corner + Vec3::repeat(step)));
+                    let size = max - min;
+                    if size.min() <= 0.0 {
+                        continue;
+                    }
+                    let part = Aabb { min, max };
+                    let (bounds, votes) = blocks.entry([x, y, z]).or_insert((part, Vec::new()));
+                    *bounds = bounds.union(&part);
+                    votes.push((size.x * size.y * size.z, index));
+                }
+            }
+        }
+    }
+
+    // En el orden de los bloques, para que el resultado no dependa del hash
+    let mut blocks: Vec<_> = blocks.into_iter().collect();
+    blocks.sort_by_key(|(key, _)| *key);
+    for (_, (bounds, votes)) in blocks {
+        let mut totals: Vec<(f32, usize)> = Vec::new();
+        for (volume, index) in votes {
+            let (material, group) = (&objects[index].material().name, objects[index].group());
+            match totals
+                .iter_mut()
+                .find(|(_, other)| objects[*other].material().name == *material && objects[*other].group() == group)
+            {
+                Some((total, _)) => *total += volume,
+                None => totals.push((volume, index)),
+            }
+        }
+        let (_, index) = totals.into_iter().fold((0.0, 0), |best, total| if total.0 > best.0 { total } else { best });
+        let source = &objects[index];
+        let mut cube = Cube::new(bounds.min, bounds.max, source.material()).in_group(source.group());
+        // La textura se repite con el tamaño de celda del cubo del que sale
+        if let Some(source) = source.as_cube() {
+            let cell = (source.max - source.min).component_div(&source.uv_repeat);
+            cube.uv_repeat = (bounds.max - bounds.min).component_div(&cell);
+        }
+        proxy.push(SceneObject::Cube(cube));
+    }
+    merge_cubes(proxy)
+}
+
+// Si alguna parte de la caja puede caer dentro del campo de visión: la caja
+// queda afuera solo si sus ocho esquinas están del mismo lado de uno de los
+// planos del frustum. Con la cámara ortográfica los planos de los costados
+// son paralelos y la vista no se abre con la profundidad
+fn in_view(bounds: &Aabb, camera: &Camera, aspect_ratio: f32, perspective_scale: f32) -> bool {
+    let (right, up, forward) = camera.basis();
+    let (scale, opens) = match camera.projection {
+        Projection::Perspective => (perspective_scale, true),
+        Projection::Orthographic { half_height } => (half_height, false),
+    };
+    // Cada esquina con la profundidad y el alcance de la vista a esa profundidad
+    let corners: Vec<(f32, f32, f32, f32)> = (0..8)
+        .map(|corner| {
+            let pick = |axis: usize| if corner & (1 << axis) == 0 { bounds.min[axis] } else { bounds.max[axis] };
+            let relative = Vec3::new(pick(0), pick(1), pick(2)) - camera.eye;
+            let depth = relative.dot(&forward);
+            (relative.dot(&right), relative.dot(&up), depth, if opens { depth } else { 1.0 })
+        })
+        .collect();
+    let (scale_x, scale_y) = (aspect_ratio * scale, scale);
+    let outside = |test: fn(f32, f32, f32, f32, f32, f32) -> bool| {
+        corners.iter().all(|&(x, y, depth, reach)| test(x, y, depth, reach, scale_x, scale_y))
+    };
+    !(outside(|_, _, depth, _, _, _| depth <= 0.0)
+        || outside(|x, _, _, reach, scale_x, _| x > reach * scale_x)
+        || outside(|x, _, _, reach, scale_x, _| x < -reach * scale_x)
+        || outside(|_, y, _, reach, _, scale_y| y > reach * scale_y)
+        || outside(|_, y, _, reach, _, scale_y| y < -reach * scale_y))
+}
+
+impl ChunkStream {
+    // Sin cubos opacos la grilla es de una unidad
+    pub fn new(objects: Vec<SceneObject>, settings: &ChunkSettings) -> Self {
+        let (origin, cell) = block_grid(objects.iter().filter_map(SceneObject::as_cube)).unwrap_or((Vec3::zeros(), 1.0));
+        let mut stream = ChunkStream {
+            settings: ChunkSettings { size: settings.size.max(1), ..*settings },
+            origin,
+            cell,
+            chunks: Vec::new(),
+            keys: HashMap::new(),
+            details: Vec::new(),
+            unbounded: Vec::new(),
+        };
+        stream.insert(objects);
+        stream
+    }
+
+    pub fn len(&self) -> usize {
+        self.chunks.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.chunks.is_empty()
+    }
+
+    // Agrega objetos a sus trozos; los trozos que cambian se rearman y el
+    // próximo `update` vuelve a armar el conjunto activo
+    pub fn insert(&mut self, objects: Vec<SceneObject>) {
+        let side = self.cell * self.settings.size as f32;
+        let (bounded, mut unbounded): (Vec<SceneObject>, Vec<SceneObject>) =
+            objects.into_iter().partition(SceneObject::is_bounded);
+        self.unbounded.append(&mut unbounded);
+        let mut keyed: Vec<([i32; 3], SceneObject)> = bounded
+            .into_iter()
+            .map(|object| {
+                let center = (object.center() - self.origin) / side;
+                ([center.x.floor() as i32, center.y.floor() as i32, center.z.floor() as i32], object)
+            })
+            .collect();
+        // Ordenados por trozo; el orden es estable, así cada trozo conserva el de los objetos
+        keyed.sort_by_key(|(key, _)| *key);
+        let mut added: Vec<([i32; 3], Vec<SceneObject>)> = Vec::new();
+        for (key, object) in keyed {
+            match added.last_mut() {
+                Some((last, group)) if *last == key => group.push(object),
+                _ => added.push((key, vec![object])),
+            }
+        }
+
+        let step = self.cell * PROXY_STEP;
+        for (key, mut objects) in added {
+            match self.keys.get(&key) {
+                Some(&index) => {
+                    objects.append(&mut self.chunks[index].objects);
+                    self.chunks[index] = Chunk::new(objects, self.origin, step);
+                }
+                None => {
+                    self.keys.insert(key, self.chunks.len());
+                    self.chunks.push(Chunk::new(objects, self.origin, step));
+                }
+            }
+        }
+        self.details.clear();
+    }
+
+    // Elige el detalle de cada trozo para la cámara y devuelve si cambió
+    pub fn update(&mut self, camera: &Camera, aspect_ratio: f32) -> bool {
+        let perspective_scale = camera.perspective_scale();
+        let radius = self.settings.radius * self.cell;
+        let mut order: Vec<(f32, usize)> =
+            self.chunks.iter().enumerate().map(|(index, chunk)| (chunk.bounds.distance_to(&camera.eye), index)).collect();
+        order.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+
+        let mut details = vec![Detail::Hidden; self.chunks.len()];
+        let mut budget = self.settings.max_cubes;
+        for (distance, index) in order {
+            let chunk = &self.chunks[index];
+            details[index] = if distance <= radius && chunk.objects.len() <= budget {
+                budget -= chunk.objects.len();
+                Detail::Full
+            } else if distance <= radius || in_view(&chunk.bounds, camera, aspect_ratio, perspective_scale) {
+                Detail::Proxy
+            } else {
+                Detail::Hidden
+            };
+        }
+
+        let changed = details != self.details;
+        self.details = details;
+        changed
+    }
+
+    // Objetos que se trazan según la última actualización
+    pub fn objects(&self) -> Vec<SceneObject> {
+        self.chunks
+            .iter()
+            .zip(&self.details)
+            .flat_map(|(chunk, detail)| match detail {
+                Detail::Full => chunk.objects.as_slice(),
+                Detail::Proxy => chunk.proxy.as_slice(),
+                Detail::Hidden => &[],
+            })
+            .chain(&self.unbounded)
+            .cloned()
+            .collect()
+    }
+
+    pub fn counts(&self) -> ChunkCounts {
+        let count = |detail: Detail| self.details.iter().filter(|&&other| other == detail).count();
+        ChunkCounts {
+            full: count(Detail::Full),
+            proxy: count(Detail::Proxy),
+            hidden: count(Detail::Hidden),
+        }
+    }
+}
@@ -0,0 +1,565 @@
+use crate::chunks::ChunkSettings;
+use crate::color::{Color, Palette};
+use crate::generate::GenParams;
+use crate::offline::OfflineSettings;
+use crate::settings::{BackgroundFit, Crop, Preset, ShadowMapSettings, ShadowQuality, ToneMapping};
+use crate::texture::TextureFilter;
+
+pub const USAGE: &str = "Uso: Raycasting_diorama [escena.ron] [--headless] [--output imagen.png] [--width N] [--height N]
+                          [--background placa.png] [--background-fit cover|letterbox] [--transparent]
+                          [--record sesion.log] [--replay sesion.log] [--fast] [--adaptive] [--samples N]
+                          [--probe X,Y] [--near T] [--cut-color RRGGBB]
+                          [--thumbnails carpeta/] [--thumbnail-size N] [--halo-radius N]
+                          [--bias B] [--shadow-color RRGGBB]
+                          [--texture-filter nearest|bilinear|aniso[:N]] [--trace traza.json]
+                          [--reproject] [--orbit-demo] [--half-res]
+                          [--preset fast|balanced|quality|cinematic] [--gizmo] [--grid-spacing S] [--minimap]
+                          [--palette default|viridis|cividis|okabe_ito] [--god-rays] [--bloom] [--ambient-occlusion]
+                          [--day-time F | --time-of-day F] [--scene-time S] [--export-gltf modelo.glb] [--shadows hard|sdf[:K]|area[:N]]
+                          [--shutter F] [--shutter-samples N] [--fps F] [--shadow-map N[:PCF[:SESGO]]]
+                          [--budget MS] [--merge] [--corner-ao] [--chunks RADIO[:LADO[:MAX]]]
+                          [--reset-settings] [--crop X,Y,ANCHO,ALTO] [--split] [--sky gradient|physical]
+                          [--skybox cielo.png|cielo_{}.png]
+                          [--rail-frames carpeta/] [--indirect] [--no-gamma] [--motion-vectors movimiento.exr] [--freeze-sun]
+                          [--tone-map clamp|reinhard|aces] [--exposure F]
+                          [--mouse-sensitivity S] [--zoom-range MIN,MAX] [--ortho S]
+                          [--beauty ANCHOxALTO[:MUESTRAS[:REBOTES]]]
+                          [--generate [seed=N] [size=N] [relief=N] [lava_chance=F] [rock_piles=N]]
+       Raycasting_diorama --diff antes.ron despues.ron [--diff-image imagen.png]";
+
+// Argumentos de línea de comandos. Con `--output` se renderiza un solo frame
+// sin abrir la ventana y se guarda como PNG.
+#[derive(Debug, Clone)]
+pub struct Args {
+    pub scene: Option<String>,
+    pub output: Option<String>,
+    // Render sin ventana para CI: pide `--output` y cualquier recurso que
+    // falte termina con error en lugar de usar el damero
+    pub headless: bool,
+    pub width: usize,
+    pub height: usize,
+    pub background: Option<String>,
+    pub background_fit: BackgroundFit,
+    // Guarda un PNG RGBA donde el fondo queda transparente
+    pub transparent: bool,
+    // Graba la entrada de cada frame en un archivo de sesión
+    pub record: Option<String>,
+    // Reproduce una sesión grabada; con `--output` renderiza solo el estado final
+    pub replay: Option<String>,
+    // Reproduce sin esperar el ritmo original
+    pub fast: bool,
+    // Muestreo adaptativo de los rayos primarios
+    pub adaptive: bool,
+    // Rayos primarios por píxel para suavizar los bordes
+    pub samples: Option<u32>,
+    // Píxel del que se traza y dibuja el camino del rayo al renderizar con `--output`
+    pub probe: Option<(usize, usize)>,
+    // Recorte cercano de los rayos primarios y color de la sección cortada
+    pub near: f32,
+    pub cut_color: Option<Color>,
+    // Genera `nombre.thumb.png` para cada escena .ron de la carpeta y termina
+    pub thumbnails: Option<String>,
+    pub thumbnail_size: usize,
+    // Radio del halo de los materiales emisivos; 0 lo desactiva
+    pub halo_radius: Option<usize>,
+    // Sesgo de origen de los rayos secundarios; None lo calcula desde el tamaño de la escena
+    pub bias: Option<f32>,
+    // Color de las sombras que recogen los materiales `shadow_catcher`
+    pub shadow_color: Color,
+    // Filtro de textura para todos los materiales en lugar del de cada uno
+    pub texture_filter: Option<TextureFilter>,
+    // Archivo de la traza de Chrome: sin ventana se captura el único frame y
+    // en la ventana el frame en que se presiona F6
+    pub trace: Option<String>,
+    // Sin ventana guarda también los vectores de movimiento del frame como
+    // OpenEXR (ver `velocity`)
+    pub motion_vectors: Option<String>,
+    // Reusa el color del frame anterior en los píxeles que siguen viendo el
+    // mismo punto; `--orbit-demo` la activa y hace girar la cámara sola con el
+    // sol detenido
+    pub reproject: bool,
+    pub orbit_demo: bool,
+    // Detiene el sol mientras se mueve la cámara; al soltarla se pone al día
+    // de a poco (ver `timeline::DayClock`)
+    pub freeze_sun: bool,
+    // Radianes que gira la órbita por píxel arrastrado con el mouse; None usa
+    // el de las preferencias
+    pub mouse_sensitivity: Option<f32>,
+    // Distancias mínima y máxima de la cámara al centro con el zoom; None usa
+    // las de `Scene::zoom_range`
+    pub zoom_range: Option<(f32, f32)>,
+    // Empieza con la cámara ortográfica, con la vista de `ortho` unidades de
+    // alto a cada lado del centro
+    pub ortho: Option<f32>,
+    // Tamaño y calidad del render de alta resolución de F11
+    pub beauty: OfflineSettings,
+    // Sombras y reflejos a media resolución
+    pub half_res: bool,
+    // Ilumina sobre los valores sRGB guardados, sin pasar a luz lineal
+    pub no_gamma: bool,
+    // Mapeo de tonos y exposición; None deja los guardados o los de por defecto
+    pub tone_mapping: Option<ToneMapping>,
+    pub exposure: Option<f32>,
+    // Rayos de sol en posproceso
+    pub god_rays: bool,
+    // Resplandor de lo más brillante en posproceso
+    pub bloom: bool,
+    // Oclusión ambiental en los rayos primarios
+    pub ambient_occlusion: bool,
+    // Preset de calidad; las opciones sueltas de calidad se aplican encima
+    pub preset: Option<Preset>,
+    // Ejes y cuadrícula del plano y = 0 encima del render, y la separación de sus líneas
+    pub gizmo: bool,
+    pub grid_spacing: f32,
+    // Empieza con el minimapa visible
+    pub minimap: bool,
+    // Colores de las vistas de depuración y de la interfaz; None usa la de
+    // las preferencias guardadas
+    pub palette: Option<Palette>,
+    // Momento del ciclo día/noche al arrancar, como fracción del día (0.25 es
+    // el mediodía)
+    pub day_time: Option<f32>,
+    // Segundo de las texturas animadas al arrancar; el render con `--output`
+    // y el modelo de `--export-gltf` salen de ese cuadro
+    pub scene_time: f32,
+    // Hornea la iluminación en colores por cara, exporta la escena como glTF
+    // binario y termina
+    pub export_gltf: Option<String>,
+    // Sombras duras o con penumbra de campo de distancias; None usa las de
+    // las preferencias guardadas
+    pub shadow_quality: Option<ShadowQuality>,
+    // Sombras del sol desde un mapa de sombras de N celdas por lado, con el
+    // radio del PCF y el sesgo en celdas
+    pub shadow_map: Option<ShadowMapSettings>,
+    // Desenfoque de movimiento con `--output`: fracción del frame con el
+    // obturador abierto (0 lo desactiva), renders que se promedian y cuadros
+    // por segundo que fijan la duración del frame cuando no hay sesión grabada
+    pub shutter: f32,
+    pub shutter_samples: usize,
+    pub fps: f32,
+    // Milisegundos por frame del render con tiempo acotado (ver `budget`)
+    pub budget: Option<f32>,
+    // Fusiona las filas de cubos iguales en cajas más largas al cargar la escena
+    pub merge: bool,
+    // Oscurece las esquinas entre bloques aunque la escena no lo pida
+    pub corner_ao: bool,
+    // Traza por trozos según la cámara (ver `chunks`)
+    pub chunks: Option<ChunkSettings>,
+    // Vuelve las preferencias guardadas a las de por defecto
+    pub reset_settings: bool,
+    // Compara dos escenas y termina; el código de salida indica si difieren
+    pub diff: Option<(String, String)>,
+    pub diff_image: Option<String>,
+    // Semilla y parámetros del diorama al azar que reemplaza a la escena
+    pub generate: Option<(u64, GenParams)>,
+    // Rectángulo en píxeles al que se limita el trazado; con `--output` se
+    // guarda solo ese pedazo
+    pub crop: Option<Crop>,
+    // Empieza con la vista dividida
+    pub split: bool,
+    // Cielo analítico en lugar del degradado (ver `sky`)
+    pub physical_sky: bool,
+    // Imagen de cielo alrededor de la escena (ver `skybox`)
+    pub skybox: Option<String>,
+    // Suma un rebote de luz difusa entre superficies (ver `irradiance`)
+    pub indirect: bool,
+    // Renderiza el recorrido de cámara de la escena cuadro por cuadro en esa
+    // carpeta, a `--fps` cuadros por segundo, y termina
+    pub rail_frames: Option<String>,
+}
+
+impl Default for Args {
+    fn default() -> Self {
+        Args {
+            scene: None,
+            output: None,
+            headless: false,
+            width: 400,
+            height: 300,
+            background: None,
+            background_fit: BackgroundFit::Cover,
+            transparent: false,
+            record: None,
+            replay: None,
+            fast: false,
+            adaptive: false,
+            samples: None,
+            probe: None,
+            near: 0.0,
+            cut_color: None,
+            thumbnails: None,
+            thumbnail_size: 128,
+            halo_radius: None,
+            bias: None,
+            shadow_color: Color::black(),
+            texture_filter: None,
+            trace: None,
+            motion_vectors: None,
+            reproject: false,
+            orbit_demo: false,
+            freeze_sun: false,
+            mouse_sensitivity: None,
+            zoom_range: None,
+            ortho: None,
+            beauty: OfflineSettings::default(),
+            half_res: false,
+            no_gamma: false,
+            tone_mapping: None,
+            exposure: None,
+            god_rays: false,
+            bloom: false,
+            ambient_occlusion: false,
+            preset: None,
+            gizmo: false,
+            minimap: false,
+            palette: None,
+            grid_spacing: 1.0,
+            day_time: None,
+            scene_time: 0.0,
+            export_gltf: None,
+            shadow_quality: None,
+            shadow_map: None,
+            shutter: 0.0,
+            shutter_samples: 8,
+            fps: 24.0,
+            budget: None,
+            merge: false,
+            corner_ao: false,
+            chunks: None,
+            reset_settings: false,
+            diff: None,
+            diff_image: None,
+            generate: None,
+            crop: None,
+            physical_sky: false,
+            skybox: None,
+            indirect: false,
+            split: false,
+            rail_frames: None,
+        }
+    }
+}
+
+fn value(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<String, String> {
+    args.next().ok_or_else(|| format!("falta el valor de {}", flag))
+}
+
+fn hex_color(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<Color, String> {
+    let text = value(args, flag)?;
+    u32::from_str_radix(text.trim_start_matches('#'), 16)
+        .map(Color::from_hex)
+        .map_err(|_| format!("valor inválido para {}: {}", flag, text))
+}
+
+fn number(args: &mut impl Iterator<Item = String>, flag: &str) -> Result<usize, String> {
+    let text = value(args, flag)?;
+    match text.parse() {
+        Ok(n) if n > 0 => Ok(n),
+        _ => Err(format!("valor inválido para {}: {}", flag, text)),
+    }
+}
+
+pub fn parse(args: impl Iterator<Item = String>) -> Result<Args, String> {
+    let mut parsed = Args::default();
+    let mut args = args.peekable();
+
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--output" => parsed.output = Some(value(&mut args, &arg)?),
+            "--headless" => parsed.headless = true,
+            "--width" => parsed.width = number(&mut args, &arg)?,
+            "--height" => parsed.height = number(&mut args, &arg)?,
+            "--transparent" => parsed.transparent = true,
+            "--record" => parsed.record = Some(value(&mut args, &arg)?),
+            "--replay" => parsed.replay = Some(value(&mut args, &arg)?),
+            "--fast" => parsed.fast = true,
+            "--adaptive" => parsed.adaptive = true,
+            "--samples" => parsed.samples = Some(number(&mut args, &arg)? as u32),
+            "--probe" => {
+                let text = value(&mut args, &arg)?;
+                let pixel = text.split_once(',').and_then(|(x, y)| Some((x.parse().ok()?, y.parse().ok()?)));
+                parsed.probe = Some(pixel.ok_or_else(|| format!("valor inválido para {}: {}", arg, text))?);
+            }
+            "--crop" => {
+                let text = value(&mut args, &arg)?;
+                let values: Result<Vec<usize>, _> = text.split(',').map(str::parse).collect();
+                parsed.crop = match values.as_deref() {
+                    Ok(&[x, y, width, height]) if width > 0 && height > 0 => Some(Crop { x, y, width, height }),
+                    _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                };
+            }
+            "--near" => {
+                let text = value(&mut args, &arg)?;
+                parsed.near = match text.parse::<f32>() {
+                    Ok(near) if near >= 0.0 => near,
+                    _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                };
+            }
+            "--cut-color" => parsed.cut_color = Some(hex_color(&mut args, &arg)?),
+            "--shadow-color" => parsed.shadow_color = hex_color(&mut args, &arg)?,
+            "--thumbnails" => parsed.thumbnails = Some(value(&mut args, &arg)?),
+            "--thumbnail-size" => parsed.thumbnail_size = number(&mut args, &arg)?,
+            "--halo-radius" => {
+                let text = value(&mut args, &arg)?;
+                parsed.halo_radius = Some(text.parse().map_err(|_| format!("valor inválido para {}: {}", arg, text))?);
+            }
+            "--tone-map" => {
+                let text = value(&mut args, &arg)?;
+                parsed.tone_mapping = Some(ToneMapping::from_name(&text).ok_or_else(|| format!("mapeo de tonos desconocido: {}", text))?);
+            }
+            "--exposure" => {
+                let text = value(&mut args, &arg)?;
+                parsed.exposure = match text.parse::<f32>() {
+                    Ok(exposure) if exposure > 0.0 => Some(exposure),
+                    _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                };
+            }
+            "--bias" => {
+                let text = value(&mut args, &arg)?;
+                parsed.bias = match text.parse::<f32>() {
+                    Ok(bias) if bias > 0.0 => Some(bias),
+                    _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                };
+            }
+            "--texture-filter" => {
+                let text = value(&mut args, &arg)?;
+                parsed.texture_filter = Some(match text.split_once(':') {
+                    None if text == "nearest" => TextureFilter::Nearest,
+                    None if text == "bilinear" => TextureFilter::Bilinear,
+                    None if text == "aniso" => TextureFilter::Aniso { max_samples: 4 },
+                    Some(("aniso", samples)) => match samples.parse() {
+                        Ok(max_samples) if max_samples > 0 => TextureFilter::Aniso { max_samples },
+                        _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                    },
+                    _ => return Err(format!("filtro de textura desconocido: {}", text)),
+                });
+            }
+            "--shadows" => {
+                let text = value(&mut args, &arg)?;
+                parsed.shadow_quality = Some(match text.split_once(':') {
+                    None if text == "hard" => ShadowQuality::Hard,
+                    None if text == "sdf" => ShadowQuality::SdfSoft { k: 8.0 },
+                    Some(("sdf", k)) => match k.parse::<f32>() {
+                        Ok(k) if k > 0.0 => ShadowQuality::SdfSoft { k },
+                        _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                    },
+                    None if text == "area" => ShadowQuality::Area { samples: 16 },
+                    Some(("area", samples)) => match samples.parse::<u32>() {
+                        Ok(samples) if samples > 0 => ShadowQuality::Area { samples },
+                        _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                    },
+                    _ => return Err(format!("tipo de sombras desconocido: {}", text)),
+                });
+            }
+            "--shadow-map" => {
+                let text = value(&mut args, &arg)?;
+                let invalid = || format!("valor inválido para {}: {}", arg, text);
+                let mut parts = text.split(':');
+                let mut map = ShadowMapSettings::default();
+                map.resolution = match parts.next().map(str::parse::<usize>) {
+                    Some(Ok(resolution)) if resolution > 0 => resolution,
+                    _ => return Err(invalid()),
+                };
+                if let Some(radius) = parts.next() {
+                    map.pcf_radius = radius.parse().map_err(|_| invalid())?;
+                }
+                if let Some(bias) = parts.next() {
+                    map.bias_texels = match bias.parse::<f32>() {
+                        Ok(bias) if bias >= 0.0 => bias,
+                        _ => return Err(invalid()),
+                    };
+                }
+                if parts.next().is_some() {
+                    return Err(invalid());
+                }
+                parsed.shadow_map = Some(map);
+            }
+            "--trace" => parsed.trace = Some(value(&mut args, &arg)?),
+            "--motion-vectors" => parsed.motion_vectors = Some(value(&mut args, &arg)?),
+            "--reproject" => parsed.reproject = true,
+            "--orbit-demo" => parsed.orbit_demo = true,
+            "--freeze-sun" => parsed.freeze_sun = true,
+            "--half-res" => parsed.half_res = true,
+            "--no-gamma" => parsed.no_gamma = true,
+            "--god-rays" => parsed.god_rays = true,
+            "--bloom" => parsed.bloom = true,
+            "--ambient-occlusion" => parsed.ambient_occlusion = true,
+            "--gizmo" => parsed.gizmo = true,
+            "--minimap" => parsed.minimap = true,
+            "--split" => parsed.split = true,
+            "--rail-frames" => parsed.rail_frames = Some(value(&mut args, &arg)?),
+            "--merge" => parsed.merge = true,
+            "--corner-ao" => parsed.corner_ao = true,
+            "--indirect" => parsed.indirect = true,
+            "--sky" => {
+                let text = value(&mut args, &arg)?;
+                parsed.physical_sky = match text.as_str() {
+                    "gradient" => false,
+                    "physical" => true,
+                    _ => return Err(format!("cielo desconocido: {}", text)),
+                };
+            }
+            "--skybox" => parsed.skybox = Some(value(&mut args, &arg)?),
+            "--chunks" => {
+                let text = value(&mut args, &arg)?;
+                let invalid = || format!("valor inválido para {}: {}", arg, text);
+                let mut parts = text.split(':');
+                let radius = match parts.next().map(str::parse::<f32>) {
+                    Some(Ok(radius)) if radius >= 0.0 => radius,
+                    _ => return Err(invalid()),
+                };
+                let mut chunks = ChunkSettings { radius, ..ChunkSettings::default() };
+                if let Some(size) = parts.next() {
+                    chunks.size = match size.parse::<usize>() {
+                        Ok(size) if size > 0 => size,
+                        _ => return Err(invalid()),
+                    };
+                }
+                if let Some(max_cubes) = parts.next() {
+                    chunks.max_cubes = max_cubes.parse().map_err(|_| invalid())?;
+                }
+                if parts.next().is_some() {
+                    return Err(invalid());
+                }
+                parsed.chunks = Some(chunks);
+            }
+            "--reset-settings" => parsed.reset_settings = true,
+            "--palette" => {
+                let text = value(&mut args, &arg)?;
+                parsed.palette = Some(Palette::from_name(&text).ok_or_else(|| format!("valor inválido para {}: {}", arg, text))?);
+            }
+            "--mouse-sensitivity" => {
+                let text = value(&mut args, &arg)?;
+                parsed.mouse_sensitivity = match text.parse::<f32>() {
+                    Ok(sensitivity) if sensitivity > 0.0 => Some(sensitivity),
+                    _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                };
+            }
+            "--zoom-range" => {
+                let text = value(&mut args, &arg)?;
+                let range = text.split_once(',').and_then(|(min, max)| Some((min.parse::<f32>().ok()?, max.parse::<f32>().ok()?)));
+                parsed.zoom_range = match range {
+                    Some((min, max)) if min > 0.0 && min <= max => Some((min, max)),
+                    _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                };
+            }
+            "--ortho" => {
+                let text = value(&mut args, &arg)?;
+                parsed.ortho = match text.parse::<f32>() {
+                    Ok(half_height) if half_height > 0.0 => Some(half_height),
+                    _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                };
+            }
+            "--beauty" => {
+                let text = value(&mut args, &arg)?;
+                let invalid = || format!("valor inválido para {}: {}", arg, text);
+                let mut parts = text.split(':');
+                let size = parts.next().and_then(|size| size.split_once('x'));
+                let (width, height) = match size.map(|(width, height)| (width.parse::<usize>(), height.parse::<usize>())) {
+                    Some((Ok(width), Ok(height))) if width > 0 && height > 0 => (width, height),
+                    _ => return Err(invalid()),
+                };
+                let mut beauty = OfflineSettings { width, height, ..OfflineSettings::default() };
+                if let Some(samples) = parts.next() {
+                    beauty.samples = match samples.parse::<u32>() {
+                        Ok(samples) if samples > 0 => Some(samples),
+                        _ => return Err(invalid()),
+                    };
+                }
+                if let Some(depth) = parts.next() {
+                    beauty.max_depth = Some(depth.parse().map_err(|_| invalid())?);
+                }
+                if parts.next().is_some() {
+                    return Err(invalid());
+                }
+                parsed.beauty = beauty;
+            }
+            "--grid-spacing" => {
+                let text = value(&mut args, &arg)?;
+                parsed.grid_spacing = match text.parse::<f32>() {
+                    Ok(spacing) if spacing > 0.0 => spacing,
+                    _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                };
+            }
+            "--day-time" | "--time-of-day" => {
+                let text = value(&mut args, &arg)?;
+                parsed.day_time = match text.parse::<f32>() {
+                    Ok(fraction) if fraction.is_finite() => Some(fraction.rem_euclid(1.0)),
+                    _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                };
+            }
+            "--scene-time" => {
+                let text = value(&mut args, &arg)?;
+                parsed.scene_time = match text.parse::<f32>() {
+                    Ok(time) if time >= 0.0 && time.is_finite() => time,
+                    _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                };
+            }
+            "--export-gltf" => parsed.export_gltf = Some(value(&mut args, &arg)?),
+            "--shutter" => {
+                let text = value(&mut args, &arg)?;
+                parsed.shutter = match text.parse::<f32>() {
+                    Ok(shutter) if (0.0..=1.0).contains(&shutter) => shutter,
+                    _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                };
+            }
+            "--shutter-samples" => parsed.shutter_samples = number(&mut args, &arg)?,
+            "--fps" => {
+                let text = value(&mut args, &arg)?;
+                parsed.fps = match text.parse::<f32>() {
+                    Ok(fps) if fps > 0.0 && fps.is_finite() => fps,
+                    _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                };
+            }
+            "--budget" => {
+                let text = value(&mut args, &arg)?;
+                parsed.budget = match text.parse::<f32>() {
+                    Ok(millis) if millis > 0.0 && millis.is_finite() => Some(millis),
+                    _ => return Err(format!("valor inválido para {}: {}", arg, text)),
+                };
+            }
+            "--preset" => {
+                let text = value(&mut args, &arg)?;
+                parsed.preset = Some(Preset::from_name(&text).ok_or_else(|| format!("preset desconocido: {}", text))?);
+            }
+            "--generate" => {
+                // Toma los `clave=valor` que siguen; los que faltan quedan por defecto
+                let (mut seed, mut params) = (0, GenParams::default());
+                while let Some(pair) = args.next_if(|next| !next.starts_with("--") && next.contains('=')) {
+                    let invalid = || format!("valor inválido para {}: {}", arg, pair);
+                    let (key, text) = pair.split_once('=').ok_or_else(invalid)?;
+                    match key {
+                        "seed" => seed = text.parse().map_err(|_| invalid())?,
+                        "size" => params.size = text.parse().map_err(|_| invalid())?,
+                        "relief" => params.relief = text.parse().map_err(|_| invalid())?,
+                        "lava_chance" => params.lava_chance = text.parse().map_err(|_| invalid())?,
+                        "rock_piles" => params.rock_piles = text.parse().map_err(|_| invalid())?,
+                        _ => return Err(format!("parámetro desconocido para {}: {}", arg, key)),
+                    }
+                }
+                parsed.generate = Some((seed, params));
+            }
+            "--diff" => parsed.diff = Some((value(&mut args, &arg)?, value(&mut args, &arg)?)),
+            "--diff-image" => parsed.diff_image = Some(value(&mut args, &arg)?),
+            "--background" => parsed.background = Some(value(&mut args, &arg)?),
+            "--background-fit" => {
+                parsed.background_fit = match value(&mut args, &arg)?.as_str() {
+                    "cover" => BackgroundFit::Cover,
+                    "letterbox" => BackgroundFit::Letterbox,
+                    other => return Err(format!("ajuste de fondo desconocido: {}", other)),
+                }
+            }
+            flag if flag.starts_with("--") => return Err(format!("opción desconocida: {}", flag)),
+            path if parsed.scene.is_none() => parsed.scene = Some(path.to_string()),
+            extra => return Err(format!("argumento inesperado: {}", extra)),
+        }
+    }
+
+    if parsed.generate.is_some() && parsed.scene.is_some() {
+        return Err("--generate reemplaza al archivo de escena; se usa uno u otro".to_string());
+    }
+    if parsed.headless && parsed.output.is_none() {
+        return Err("--headless necesita --output".to_string());
+    }
+    Ok(parsed)
+}
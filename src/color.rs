@@ -1,6 +1,7 @@
+use serde::{Deserialize, Serialize};
 use std::fmt;
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub struct Color {
     r: u8,
     g: u8,
@@ -42,11 +43,73 @@ impl Color {
     }
 
     // Function to return the color as a hex value
-    pub fn to_hex(&self) -> u32 {
+    pub fn to_hex(self) -> u32 {
         ((self.r as u32) << 16) | ((self.g as u32) << 8) | (self.b as u32)
     }
 }
 
+// sRGB transfer curve, to average or blend colors as light adds up instead of
+// in the stored 8-bit values
+pub fn srgb_to_linear(channel: u8) -> f32 {
+    let c = channel as f32 / 255.0;
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(value: f32) -> u8 {
+    let c = value.clamp(0.0, 1.0);
+    let encoded = if c <= 0.003_130_8 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+    (encoded * 255.0).round() as u8
+}
+
+// Color with float channels for adding up light: the terms don't clip one by
+// one, only when the total is turned back into a `Color`. 1.0 is the
+// brightest a stored channel can show
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct LinearColor {
+    pub r: f32,
+    pub g: f32,
+    pub b: f32,
+}
+
+impl LinearColor {
+    pub const fn new(r: f32, g: f32, b: f32) -> Self {
+        LinearColor { r, g, b }
+    }
+
+    // Decodes the sRGB curve of a stored color
+    pub fn from_srgb(color: Color) -> Self {
+        LinearColor::new(srgb_to_linear(color.r), srgb_to_linear(color.g), srgb_to_linear(color.b))
+    }
+
+    // Encodes with the sRGB curve, clamping each channel to [0, 1]
+    pub fn to_srgb(self) -> Color {
+        Color::new(linear_to_srgb(self.r), linear_to_srgb(self.g), linear_to_srgb(self.b))
+    }
+
+    // The stored values over 255 as they are, without the sRGB curve
+    pub fn from_raw(color: Color) -> Self {
+        LinearColor::new(color.r as f32 / 255.0, color.g as f32 / 255.0, color.b as f32 / 255.0)
+    }
+
+    pub fn to_raw(self) -> Color {
+        let channel = |value: f32| (value.clamp(0.0, 1.0) * 255.0).round() as u8;
+        Color::new(channel(self.r), channel(self.g), channel(self.b))
+    }
+
+    pub fn channels(self) -> [f32; 3] {
+        [self.r, self.g, self.b]
+    }
+
+    // Scales each channel by its own factor
+    pub fn scaled(self, [r, g, b]: [f32; 3]) -> Self {
+        LinearColor::new(self.r * r, self.g * g, self.b * b)
+    }
+}
+
 // Implement addition for Color
 use std::ops::Add;
 
@@ -77,9 +140,139 @@ impl Mul<f32> for Color {
     }
 }
 
+// Implement channel-wise multiplication of two colors, as when a light
+// tints a surface: white leaves the other color unchanged
+impl Mul<Color> for Color {
+    type Output = Color;
+
+    fn mul(self, other: Color) -> Color {
+        let modulate = |a: u8, b: u8| ((a as u16 * b as u16 + 127) / 255) as u8;
+        Color {
+            r: modulate(self.r, other.r),
+            g: modulate(self.g, other.g),
+            b: modulate(self.b, other.b),
+        }
+    }
+}
+
+impl Add for LinearColor {
+    type Output = LinearColor;
+
+    fn add(self, other: LinearColor) -> LinearColor {
+        LinearColor::new(self.r + other.r, self.g + other.g, self.b + other.b)
+    }
+}
+
+impl Mul<f32> for LinearColor {
+    type Output = LinearColor;
+
+    fn mul(self, scalar: f32) -> LinearColor {
+        LinearColor::new(self.r * scalar, self.g * scalar, self.b * scalar)
+    }
+}
+
+impl Mul<LinearColor> for LinearColor {
+    type Output = LinearColor;
+
+    fn mul(self, other: LinearColor) -> LinearColor {
+        self.scaled(other.channels())
+    }
+}
+
 // Implement display formatting for Color
 impl fmt::Display for Color {
     fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
         write!(f, "Color(r: {}, g: {}, b: {})", self.r, self.g, self.b)
     }
 }
+
+// Color ramps and categorical sets for debug views and UI overlays. Every
+// table is a list of evenly spaced stops; `sample` interpolates the ramps and
+// snaps to the nearest entry for the categorical set.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Palette {
+    // The original look: a blue-to-red rainbow ramp and each view's own colors
+    #[default]
+    Default,
+    Viridis,
+    Cividis,
+    // Okabe & Ito, distinguishable with the common forms of color blindness
+    OkabeIto,
+}
+
+const RAINBOW: [u32; 5] = [0x0000ff, 0x00ffff, 0x00ff00, 0xffff00, 0xff0000];
+
+// matplotlib's viridis at t = 0, 0.1, ..., 1
+const VIRIDIS: [u32; 11] = [
+    0x440154, 0x482475, 0x414487, 0x355f8d, 0x2a788e, 0x21918c, 0x22a884, 0x44bf70, 0x7ad151, 0xbddf26, 0xfde725,
+];
+
+// Cividis (Nuñez et al.), tuned to look the same with and without deuteranopia
+const CIVIDIS: [u32; 18] = [
+    0x00204c, 0x002a66, 0x00346e, 0x273f6c, 0x3c4a6b, 0x4c556b, 0x5b5f6d, 0x686a70, 0x757575, 0x838178, 0x928c78,
+    0xa19876, 0xb0a572, 0xc0b16d, 0xd1bf66, 0xe1cc5c, 0xf3db4f, 0xffe945,
+];
+
+// Okabe-Ito without its black, which disappears over the dark panels
+const OKABE_ITO: [u32; 7] = [0xe69f00, 0x56b4e9, 0x009e73, 0xf0e442, 0x0072b2, 0xd55e00, 0xcc79a7];
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [Palette::Default, Palette::Viridis, Palette::Cividis, Palette::OkabeIto];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Palette::Default => "default",
+            Palette::Viridis => "viridis",
+            Palette::Cividis => "cividis",
+            Palette::OkabeIto => "okabe_ito",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Palette> {
+        Palette::ALL.iter().copied().find(|palette| palette.name() == name)
+    }
+
+    pub fn next(self) -> Palette {
+        let index = Palette::ALL.iter().position(|&palette| palette == self).unwrap_or(0);
+        Palette::ALL[(index + 1) % Palette::ALL.len()]
+    }
+
+    fn stops(self) -> &'static [u32] {
+        match self {
+            Palette::Default => &RAINBOW,
+            Palette::Viridis => &VIRIDIS,
+            Palette::Cividis => &CIVIDIS,
+            Palette::OkabeIto => &OKABE_ITO,
+        }
+    }
+
+    // Color at `t` in [0, 1] (clamped); low values are the dark or cold end
+    pub fn sample(self, t: f32) -> Color {
+        let stops = self.stops();
+        let position = if t.is_nan() { 0.0 } else { t.clamp(0.0, 1.0) } * (stops.len() - 1) as f32;
+        if self == Palette::OkabeIto {
+            return Color::from_hex(stops[position.round() as usize]);
+        }
+        let index = (position as usize).min(stops.len() - 2);
+        let (from, to) = (Color::from_hex(stops[index]), Color::from_hex(stops[index + 1]));
+        let fraction = position - index as f32;
+        let channel = |a: u8, b: u8| (a as f32 + (b as f32 - a as f32) * fraction).round() as u8;
+        Color::new(channel(from.r, to.r), channel(from.g, to.g), channel(from.b, to.b))
+    }
+
+    // Color for the `index`-th kind of thing in a view (an axis, a ray type, a
+    // diff status). The default palette keeps the view's own `fallback`; the
+    // ramps skip their darkest quarter and visit it out of order so that
+    // neighbouring indices don't get neighbouring shades.
+    pub fn category(self, index: usize, fallback: Color) -> Color {
+        match self {
+            Palette::Default => fallback,
+            Palette::OkabeIto => Color::from_hex(OKABE_ITO[index % OKABE_ITO.len()]),
+            Palette::Viridis | Palette::Cividis => {
+                let spread = (index * 3 % OKABE_ITO.len()) as f32 / (OKABE_ITO.len() - 1) as f32;
+                self.sample(0.25 + 0.75 * spread)
+            }
+        }
+    }
+}
@@ -0,0 +1,365 @@
+use minifb::Key;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use crate::adaptive::AdaptiveSampling;
+use crate::color::Palette;
+use crate::settings::{AmbientOcclusion, Bloom, GodRays, RenderSettings, ShadowMapSettings, ShadowQuality, ToneMapping};
+use crate::texture::TextureFilter;
+
+use crate::timeline::Action;
+
+// Preferencias de la ventana que se guardan al salir y se leen al arrancar,
+// en `raycasting_diorama/settings.ron` dentro del directorio de configuración
+// de la plataforma. Todos los campos tienen valor por defecto, así el archivo
+// de una versión anterior se completa con lo que le falta y uno con campos que
+// esta versión no conoce se lee igual.
+
+const APP_DIR: &str = "raycasting_diorama";
+const FILE_NAME: &str = "settings.ron";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Settings {
+    pub render: RenderPrefs,
+    pub view: ViewPrefs,
+    // Tecla y nombre de la acción que dispara; una acción puede tener varias
+    // teclas. Los nombres de las teclas son los de `minifb::Key`
+    pub keys: Vec<(String, String)>,
+    // Escena abierta en la última sesión; None para el diorama integrado
+    pub last_scene: Option<String>,
+    pub window: WindowPrefs,
+}
+
+impl Default for Settings {
+    fn default() -> Self {
+        Settings {
+            render: RenderPrefs::default(),
+            view: ViewPrefs::default(),
+            keys: default_keys().iter().map(|&(key, action)| (key_name(key), action.name().to_string())).collect(),
+            last_scene: None,
+            window: WindowPrefs::default(),
+        }
+    }
+}
+
+// Opciones de render que se cambian desde la ventana o la línea de comandos
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct RenderPrefs {
+    pub render_scale: f32,
+    pub samples_per_pixel: u32,
+    pub max_depth: u32,
+    pub adaptive: bool,
+    pub half_res: bool,
+    pub texture_filter: Option<TextureFilter>,
+    pub halo_radius: usize,
+    pub shadow_quality: ShadowQuality,
+    pub shadow_map: Option<ShadowMapSettings>,
+    pub god_rays: Option<GodRays>,
+    pub bloom: Option<Bloom>,
+    pub ambient_occlusion: Option<AmbientOcclusion>,
+    pub tone_mapping: ToneMapping,
+    pub exposure: f32,
+}
+
+impl Default for RenderPrefs {
+    fn default() -> Self {
+        RenderPrefs::of(&RenderSettings::default())
+    }
+}
+
+impl RenderPrefs {
+    pub fn of(settings: &RenderSettings) -> Self {
+        RenderPrefs {
+            render_scale: settings.render_scale,
+            samples_per_pixel: settings.samples_per_pixel,
+            max_depth: settings.max_depth,
+            adaptive: settings.adaptive.is_some(),
+            half_res: settings.half_res,
+            texture_filter: settings.texture_filter,
+            halo_radius: settings.halo_radius,
+            shadow_quality: settings.shadow_quality,
+            shadow_map: settings.shadow_map,
+            god_rays: settings.god_rays,
+            bloom: settings.bloom,
+            ambient_occlusion: settings.ambient_occlusion,
+            tone_mapping: settings.tone_mapping,
+            exposure: settings.exposure,
+        }
+    }
+
+    // Un valor fuera de rango editado a mano deja el de por defecto
+    pub fn apply(&self, settings: &mut RenderSettings) {
+        if self.render_scale > 0.0 && self.render_scale <= 1.0 {
+            settings.render_scale = self.render_scale;
+        }
+        settings.samples_per_pixel = self.samples_per_pixel.max(1);
+        settings.max_depth = self.max_depth;
+        settings.adaptive = self.adaptive.then(AdaptiveSampling::default);
+        settings.half_res = self.half_res;
+        settings.texture_filter = self.texture_filter;
+        settings.halo_radius = self.halo_radius;
+        settings.shadow_quality = self.shadow_quality;
+        settings.shadow_map = self.shadow_map.filter(|map| map.resolution > 0);
+        settings.god_rays = self.god_rays;
+        settings.bloom = self.bloom.filter(|bloom| bloom.strength > 0.0);
+        settings.ambient_occlusion = self.ambient_occlusion.filter(|occlusion| occlusion.samples > 0);
+        settings.tone_mapping = self.tone_mapping;
+        if self.exposure > 0.0 && self.exposure.is_finite() {
+            settings.exposure = self.exposure;
+        }
+    }
+}
+
+// Ayudas visuales visibles al salir
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ViewPrefs {
+    pub show_stats: bool,
+    pub show_gizmo: bool,
+    pub show_minimap: bool,
+    pub palette: Palette,
+    // Radianes que gira la órbita por píxel arrastrado con el mouse; el
+    // desplazamiento mueve la misma fracción de la distancia al centro
+    pub mouse_sensitivity: f32,
+}
+
+impl Default for ViewPrefs {
+    fn default() -> Self {
+        ViewPrefs {
+            show_stats: true,
+            show_gizmo: false,
+            show_minimap: false,
+            palette: Palette::Default,
+            mouse_sensitivity: 0.01,
+        }
+    }
+}
+
+// Posición y tamaño de la ventana en píxeles de pantalla
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct WindowPrefs {
+    pub x: isize,
+    pub y: isize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Default for WindowPrefs {
+    fn default() -> Self {
+        WindowPrefs {
+            x: 500,
+            y: 500,
+            width: 800,
+            height: 600,
+        }
+    }
+}
+
+// Teclas de siempre
+pub fn default_keys() -> Vec<(Key, Action)> {
+    vec![
+        (Key::W, Action::ZoomIn),
+        (Key::S, Action::ZoomOut),
+        (Key::Left, Action::OrbitLeft),
+        (Key::Right, Action::OrbitRight),
+        (Key::Up, Action::OrbitUp),
+        (Key::Down, Action::OrbitDown),
+        (Key::RightBracket, Action::NearIn),
+        (Key::LeftBracket, Action::NearOut),
+        (Key::PageUp, Action::LightUp),
+        (Key::PageDown, Action::LightDown),
+        (Key::Escape, Action::Quit),
+        (Key::F3, Action::ToggleStats),
+        (Key::F4, Action::ToggleAdaptive),
+        (Key::C, Action::ClearProbe),
+        (Key::G, Action::ToggleGizmo),
+        (Key::F5, Action::CycleFilter),
+        (Key::F6, Action::CaptureTrace),
+        (Key::F7, Action::ToggleReprojection),
+        (Key::F8, Action::ToggleHalfRes),
+        (Key::F9, Action::CyclePreset),
+        (Key::N, Action::Regenerate),
+        (Key::M, Action::ToggleMinimap),
+        (Key::P, Action::CyclePalette),
+        (Key::L, Action::ToggleLightEdit),
+        (Key::J, Action::NextLight),
+        (Key::K, Action::CycleLightKind),
+        (Key::Insert, Action::AddLight),
+        (Key::Delete, Action::DeleteLight),
+        (Key::Z, Action::Undo),
+        (Key::R, Action::ToggleGodRays),
+        (Key::X, Action::ClearCrop),
+        (Key::V, Action::ToggleSplit),
+        (Key::B, Action::ToggleViewLock),
+        (Key::T, Action::PlayRail),
+        (Key::Y, Action::ToggleMotionView),
+        (Key::Enter, Action::DismissReport),
+        (Key::O, Action::ToggleAmbientOcclusion),
+        (Key::Equal, Action::DepthUp),
+        (Key::Minus, Action::DepthDown),
+        (Key::NumPadPlus, Action::ExposureUp),
+        (Key::NumPadMinus, Action::ExposureDown),
+        (Key::F10, Action::CycleSamples),
+        (Key::H, Action::PauseCycle),
+        (Key::Tab, Action::ToggleFly),
+        (Key::A, Action::MoveLeft),
+        (Key::D, Action::MoveRight),
+        (Key::Space, Action::MoveUp),
+        (Key::LeftShift, Action::MoveDown),
+        (Key::Comma, Action::FovDown),
+        (Key::Period, Action::FovUp),
+        (Key::Key5, Action::ToggleOrtho),
+        (Key::F12, Action::Screenshot),
+        (Key::F11, Action::BeautyShot),
+    ]
+}
+
+// Teclas que se pueden asignar, por su nombre en `minifb::Key`
+const KEYS: [Key; 106] = [
+    Key::Key0, Key::Key1, Key::Key2, Key::Key3, Key::Key4, Key::Key5, Key::Key6, Key::Key7, Key::Key8, Key::Key9,
+    Key::A, Key::B, Key::C, Key::D, Key::E, Key::F, Key::G, Key::H, Key::I, Key::J, Key::K, Key::L, Key::M,
+    Key::N, Key::O, Key::P, Key::Q, Key::R, Key::S, Key::T, Key::U, Key::V, Key::W, Key::X, Key::Y, Key::Z,
+    Key::F1, Key::F2, Key::F3, Key::F4, Key::F5, Key::F6, Key::F7, Key::F8, Key::F9, Key::F10, Key::F11,
+    Key::F12, Key::F13, Key::F14, Key::F15,
+    Key::Down, Key::Left, Key::Right, Key::Up, Key::Apostrophe, Key::Backquote, Key::Backslash, Key::Comma,
+    Key::Equal, Key::LeftBracket, Key::Minus, Key::Period, Key::RightBracket, Key::Semicolon, Key::Slash,
+    Key::Backspace, Key::Delete, Key::End, Key::Enter, Key::Escape, Key::Home, Key::Insert, Key::Menu,
+    Key::PageDown, Key::PageUp, Key::Pause, Key::Space, Key::Tab, Key::NumLock, Key::CapsLock, Key::ScrollLock,
+    Key::LeftShift, Key::RightShift, Key::LeftCtrl, Key::RightCtrl,
+    Key::NumPad0, Key::NumPad1, Key::NumPad2, Key::NumPad3, Key::NumPad4, Key::NumPad5, Key::NumPad6,
+    Key::NumPad7, Key::NumPad8, Key::NumPad9, Key::NumPadDot, Key::NumPadSlash, Key::NumPadAsterisk,
+    Key::NumPadMinus, Key::NumPadPlus, Key::NumPadEnter, Key::LeftAlt, Key::RightAlt, Key::LeftSuper,
+    Key::RightSuper,
+];
+
+fn key_name(key: Key) -> String {
+    format!("{:?}", key)
+}
+
+fn key_from_name(name: &str) -> Option<Key> {
+    KEYS.iter().copied().find(|&key| key_name(key) == name)
+}
+
+#[derive(Debug)]
+pub enum SettingsError {
+    Io(io::Error),
+    Parse(ron::error::SpannedError),
+}
+
+impl fmt::Display for SettingsError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SettingsError::Io(e) => write!(f, "no se pudo leer el archivo: {}", e),
+            SettingsError::Parse(e) => write!(f, "error de sintaxis: {}", e),
+        }
+    }
+}
+
+// Directorio de configuración de la plataforma: %APPDATA% en Windows,
+// ~/Library/Application Support en macOS y $XDG_CONFIG_HOME o ~/.config en el
+// resto. None si no hay dónde guardar
+pub fn settings_path() -> Option<PathBuf> {
+    let var = |name: &str| std::env::var_os(name).filter(|value| !value.is_empty()).map(PathBuf::from);
+    let dir = if cfg!(target_os = "windows") {
+        var("APPDATA")
+    } else if cfg!(target_os = "macos") {
+        var("HOME").map(|home| home.join("Library").join("Application Support"))
+    } else {
+        var("XDG_CONFIG_HOME").filter(|dir| dir.is_absolute()).or_else(|| var("HOME").map(|home| home.join(".config")))
+    };
+    dir.map(|dir| dir.join(APP_DIR).join(FILE_NAME))
+}
+
+impl Settings {
+    // Sin archivo son las de por defecto
+    pub fn load(path: &Path) -> Result<Settings, SettingsError> {
+        match fs::read_to_string(path) {
+            Ok(source) => ron::from_str(&source).map_err(SettingsError::Parse),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Settings::default()),
+            Err(e) => Err(SettingsError::Io(e)),
+        }
+    }
+
+    // Se escribe aparte y se renombra, así un corte a mitad no deja el archivo roto
+    pub fn save(&self, path: &Path) -> io::Result<()> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let text = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .expect("las preferencias se pueden escribir en RON");
+        let partial = path.with_extension("ron.tmp");
+        fs::write(&partial, text + "\n")?;
+        fs::rename(&partial, path)
+    }
+
+    // Teclas de cada acción. Las entradas con nombres desconocidos se devuelven
+    // aparte, y las acciones sin tecla, como las agregadas en versiones nuevas,
+    // conservan la de por defecto si ninguna otra acción la usa
+    pub fn bindings(&self) -> (Vec<(Key, Action)>, Vec<String>) {
+        let mut bindings = Vec::new();
+        let mut ignored = Vec::new();
+        for (key, action) in &self.keys {
+            match (key_from_name(key), Action::from_name(action)) {
+                (Some(key), Some(action)) => bindings.push((key, action)),
+                _ => ignored.push(format!("{} -> {}", key, action)),
+            }
+        }
+        for (key, action) in default_keys() {
+            if bindings.iter().all(|&(other_key, other_action)| other_key != key && other_action != action) {
+                bindings.push((key, action));
+            }
+        }
+        (bindings, ignored)
+    }
+}
+
+// Renombra un archivo que no se pudo leer a `settings.ron.bad` (o `.bad.1`,
+// `.bad.2`...) para no perder lo que tenía, y devuelve el nombre nuevo
+pub fn set_aside(path: &Path) -> io::Result<PathBuf> {
+    let name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+    let aside = (0..)
+        .map(|n| if n == 0 { format!("{}.bad", name) } else { format!("{}.bad.{}", name, n) })
+        .map(|candidate| path.with_file_name(candidate))
+        .find(|candidate| !candidate.exists())
+        .expect("siempre hay un nombre libre");
+    fs::rename(path, &aside)?;
+    Ok(aside)
+}
+
+// Preferencias guardadas de la sesión anterior. Un archivo ilegible se aparta
+// con otro nombre y se reemplaza por uno nuevo con las de por defecto, igual
+// que con `--reset-settings`; nunca impide arrancar.
+pub fn load_settings(path: Option<&Path>, reset: bool) -> Settings {
+    let Some(path) = path else {
+        return Settings::default();
+    };
+    let loaded = if reset {
+        println!("Preferencias restablecidas en {}", path.display());
+        None
+    } else {
+        match Settings::load(path) {
+            Ok(settings) => Some(settings),
+            Err(e) => {
+                eprintln!("Error al leer las preferencias {}: {}", path.display(), e);
+                match set_aside(path) {
+                    Ok(aside) => eprintln!("Se guardaron aparte en {} y se usan las de por defecto", aside.display()),
+                    Err(e) => eprintln!("No se pudieron apartar: {}", e),
+                }
+                None
+            }
+        }
+    };
+    loaded.unwrap_or_else(|| {
+        let settings = Settings::default();
+        if let Err(e) = settings.save(path) {
+            eprintln!("Error al guardar las preferencias {}: {}", path.display(), e);
+        }
+        settings
+    })
+}
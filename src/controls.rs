@@ -0,0 +1,588 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::adaptive::AdaptiveSampling;
+use crate::camera::{Camera, CameraMode, Projection};
+use crate::color::Palette;
+use crate::framebuffer::Framebuffer;
+use crate::generate::GenParams;
+use crate::light::{LightKind, SceneLight};
+use crate::lightedit::{LightEdit, LightEditor};
+use crate::minimap::Minimap;
+use crate::object::SceneObject;
+use crate::probe::ProbePath;
+use crate::rail::CameraRail;
+use crate::renderer::{pick, probe_pixel};
+use crate::reprojection::Reprojection;
+use crate::scene::{save_lights, Scene};
+use crate::settings::{AmbientOcclusion, Crop, GodRays, Preset, RenderSettings};
+use crate::split::SplitView;
+use crate::texture::TextureFilter;
+use crate::timeline::{Action, DayClock, FrameRecord};
+
+// Lo que se dibuja encima del render
+pub struct View {
+    pub show_stats: bool,
+    pub show_gizmo: bool,
+    pub show_minimap: bool,
+    // Muestra los vectores de movimiento en lugar del render
+    pub show_motion: bool,
+    // Aviso de lo que faltó al cargar la escena, hasta que se cierra
+    pub load_report: Vec<String>,
+    pub palette: Palette,
+    pub light_editor: LightEditor,
+    pub mouse_sensitivity: f32,
+    // Distancias mínima y máxima de la cámara al centro de la órbita
+    pub zoom_range: (f32, f32),
+}
+
+// Aplica la entrada de un frame a la cámara, las luces y la interfaz
+#[allow(clippy::too_many_arguments)]
+pub fn apply_frame(
+    frame: &FrameRecord,
+    camera: &mut Camera,
+    lights: &mut [SceneLight],
+    view: &mut View,
+    settings: &mut RenderSettings,
+    reprojection: &mut Option<Reprojection>,
+    clock: &mut DayClock,
+    orbit_demo: bool,
+) {
+    let rotation_speed = PI / 50.0;
+    let start = camera.clone();
+
+    // Vuelta lenta de la demostración de órbita, en radianes por segundo
+    const ORBIT_DEMO_SPEED: f32 = 0.15;
+
+    // Fracción de la distancia al centro que avanza W o S en cada frame y
+    // factor de cada paso de la rueda; los dos se frenan en `view.zoom_range`
+    const ZOOM_SPEED: f32 = 0.01;
+    const WHEEL_STEP: f32 = 0.9;
+    let (min_distance, max_distance) = view.zoom_range;
+    let flying = camera.mode == CameraMode::Fly;
+
+    // Vuelo libre: el ojo avanza en unidades por segundo y la vista gira en
+    // radianes por segundo, así no depende de los cuadros por segundo
+    const FLY_SPEED: f32 = 1.5;
+    const FLY_TURN_SPEED: f32 = 1.5;
+
+    // Si presionas la tecla W, la cámara se acerca
+    if frame.is_held(Action::ZoomIn) && !flying {
+        camera.zoom(1.0 - ZOOM_SPEED, min_distance, max_distance);
+    }
+
+    // Si presionas la tecla S, la cámara se aleja
+    if frame.is_held(Action::ZoomOut) && !flying {
+        camera.zoom(1.0 + ZOOM_SPEED, min_distance, max_distance);
+    }
+
+    // La rueda acerca o aleja la cámara en la órbita, salvo en el modo de
+    // edición de luces, donde cambia la intensidad de la luz
+    if frame.scroll != 0.0 && !view.light_editor.active && !flying {
+        camera.zoom(WHEEL_STEP.powf(frame.scroll), min_distance, max_distance);
+    }
+
+    // , y . cierran y abren el campo de visión, en grados por segundo
+    const FOV_SPEED: f32 = 30.0;
+    if frame.is_held(Action::FovDown) {
+        camera.widen(-(FOV_SPEED * frame.delta_time).to_radians());
+    }
+    if frame.is_held(Action::FovUp) {
+        camera.widen((FOV_SPEED * frame.delta_time).to_radians());
+    }
+
+    // Tab pasa de la órbita al vuelo libre y vuelve; el centro queda delante
+    // del ojo, así la órbita sigue alrededor de lo que se estaba mirando
+    if frame.was_pressed(Action::ToggleFly) {
+        camera.mode = match camera.mode {
+            CameraMode::Orbit => CameraMode::Fly,
+            CameraMode::Fly => CameraMode::Orbit,
+        };
+    }
+
+    // 5 pasa de la perspectiva a la ortográfica y vuelve. La ortográfica
+    // enmarca lo mismo que se veía a la distancia del centro
+    if frame.was_pressed(Action::ToggleOrtho) {
+        camera.projection = match camera.projection {
+            Projection::Perspective => camera.matching_orthographic(),
+            Projection::Orthographic { .. } => Projection::Perspective,
+        };
+    }
+
+    // F3 muestra u oculta las estadísticas de la escena
+    if frame.was_pressed(Action::ToggleStats) {
+        view.show_stats = !view.show_stats;
+    }
+
+    // G muestra u oculta los ejes y la cuadrícula
+    if frame.was_pressed(Action::ToggleGizmo) {
+        view.show_gizmo = !view.show_gizmo;
+    }
+
+    // Y muestra los vectores de movimiento en lugar del render
+    if frame.was_pressed(Action::ToggleMotionView) {
+        view.show_motion = !view.show_motion;
+    }
+
+    // Enter cierra el aviso de texturas que faltan
+    if frame.was_pressed(Action::DismissReport) {
+        view.load_report.clear();
+    }
+
+    // M muestra u oculta el minimapa
+    if frame.was_pressed(Action::ToggleMinimap) {
+        view.show_minimap = !view.show_minimap;
+    }
+
+    // P pasa a la paleta siguiente para el minimapa, la sonda, los ejes y el panel
+    if frame.was_pressed(Action::CyclePalette) {
+        view.palette = view.palette.next();
+    }
+
+    // ] y [ mueven el plano cercano de los rayos primarios
+    const NEAR_SPEED: f32 = 0.02;
+    if frame.is_held(Action::NearIn) {
+        settings.near += NEAR_SPEED;
+    }
+    if frame.is_held(Action::NearOut) {
+        settings.near = (settings.near - NEAR_SPEED).max(0.0);
+    }
+
+    // F5 recorre los filtros de textura forzados; al final vuelve al de cada material
+    if frame.was_pressed(Action::CycleFilter) {
+        settings.texture_filter = match settings.texture_filter {
+            None => Some(TextureFilter::Nearest),
+            Some(TextureFilter::Nearest) => Some(TextureFilter::Bilinear),
+            Some(TextureFilter::Bilinear) => Some(TextureFilter::Aniso { max_samples: 4 }),
+            Some(TextureFilter::Aniso { .. }) => None,
+        };
+    }
+
+    // F9 pasa al preset siguiente; con opciones cambiadas a mano empieza por el primero
+    if frame.was_pressed(Action::CyclePreset) {
+        settings.apply_preset(settings.preset().map_or(Preset::ALL[0], Preset::next));
+    }
+
+    // F8 alterna entre sombras y reflejos a media resolución y la referencia completa
+    if frame.was_pressed(Action::ToggleHalfRes) {
+        settings.half_res = !settings.half_res;
+    }
+
+    // R activa o desactiva los rayos de sol
+    if frame.was_pressed(Action::ToggleGodRays) {
+        settings.god_rays = match settings.god_rays {
+            Some(_) => None,
+            None => Some(GodRays::default()),
+        };
+    }
+
+    // O activa o desactiva la oclusión ambiental
+    if frame.was_pressed(Action::ToggleAmbientOcclusion) {
+        settings.ambient_occlusion = match settings.ambient_occlusion {
+            Some(_) => None,
+            None => Some(AmbientOcclusion::default()),
+        };
+    }
+
+    // = y - suben y bajan los rebotes de reflexión y refracción
+    const DEPTH_LIMIT: u32 = 8;
+    if frame.was_pressed(Action::DepthUp) {
+        settings.max_depth = (settings.max_depth + 1).min(DEPTH_LIMIT);
+    }
+    if frame.was_pressed(Action::DepthDown) {
+        settings.max_depth = settings.max_depth.saturating_sub(1);
+    }
+
+    // + y - del teclado numérico suben y bajan la exposición de a medio paso
+    const EXPOSURE_STEP: f32 = std::f32::consts::SQRT_2;
+    const EXPOSURE_RANGE: (f32, f32) = (1.0 / 16.0, 16.0);
+    if frame.was_pressed(Action::ExposureUp) {
+        settings.exposure = (settings.exposure * EXPOSURE_STEP).min(EXPOSURE_RANGE.1);
+    }
+    if frame.was_pressed(Action::ExposureDown) {
+        settings.exposure = (settings.exposure / EXPOSURE_STEP).max(EXPOSURE_RANGE.0);
+    }
+
+    // F10 pasa a la siguiente cantidad de muestras por píxel
+    if frame.was_pressed(Action::CycleSamples) {
+        settings.samples_per_pixel = match settings.samples_per_pixel {
+            0 | 1 => 4,
+            2..=4 => 9,
+            5..=9 => 16,
+            _ => 1,
+        };
+    }
+
+    // H pausa o reanuda el ciclo día/noche
+    if frame.was_pressed(Action::PauseCycle) {
+        clock.paused = !clock.paused;
+    }
+
+    // F4 activa o desactiva el muestreo adaptativo
+    if frame.was_pressed(Action::ToggleAdaptive) {
+        settings.adaptive = match settings.adaptive {
+            Some(_) => None,
+            None => Some(AdaptiveSampling::default()),
+        };
+    }
+
+    // F7 activa o desactiva la reproyección temporal
+    if frame.was_pressed(Action::ToggleReprojection) {
+        *reprojection = match reprojection {
+            Some(_) => None,
+            None => Some(Reprojection::default()),
+        };
+    }
+
+    // Controles de órbita de la cámara; en el modo de edición de luces las
+    // flechas mueven la luz seleccionada. En vuelo las flechas giran la vista
+    // y W/A/S/D, Espacio y Shift mueven el ojo
+    let orbiting = !view.light_editor.active;
+    if flying {
+        let axis = |positive: Action, negative: Action| frame.is_held(positive) as i32 as f32 - frame.is_held(negative) as i32 as f32;
+        let turn = FLY_TURN_SPEED * frame.delta_time;
+        if orbiting {
+            camera.rotate(axis(Action::OrbitRight, Action::OrbitLeft) * turn, axis(Action::OrbitUp, Action::OrbitDown) * turn);
+        }
+        let step = Vec3::new(
+            axis(Action::MoveRight, Action::MoveLeft),
+            axis(Action::MoveUp, Action::MoveDown),
+            axis(Action::ZoomIn, Action::ZoomOut),
+        );
+        if step != Vec3::zeros() {
+            camera.move_relative(step * FLY_SPEED * frame.delta_time);
+        }
+    } else {
+        if orbiting && frame.is_held(Action::OrbitLeft) {
+            camera.orbit(rotation_speed, 0.0);
+        }
+        if orbiting && frame.is_held(Action::OrbitRight) {
+            camera.orbit(-rotation_speed, 0.0);
+        }
+        if orbiting && frame.is_held(Action::OrbitUp) {
+            camera.orbit(0.0, -rotation_speed);
+        }
+        if orbiting && frame.is_held(Action::OrbitDown) {
+            camera.orbit(0.0, rotation_speed);
+        }
+    }
+
+    // Arrastrar con el botón izquierdo orbita (o en vuelo gira la vista) como
+    // las flechas y con el del medio desplaza el centro y el ojo juntos en el
+    // plano de la cámara, con lo que se mueve la escena detrás del mouse
+    let [dx, dy] = frame.orbit;
+    if dx != 0.0 || dy != 0.0 {
+        let (yaw, pitch) = (dx * view.mouse_sensitivity, dy * view.mouse_sensitivity);
+        match camera.mode {
+            CameraMode::Orbit => camera.orbit(-yaw, pitch),
+            CameraMode::Fly => camera.rotate(yaw, -pitch),
+        }
+    }
+    let [dx, dy] = frame.pan;
+    if dx != 0.0 || dy != 0.0 {
+        let (right, up, _) = camera.basis();
+        let distance = (camera.center - camera.eye).magnitude();
+        let shift = (up * dy - right * dx) * view.mouse_sensitivity * distance;
+        camera.center += shift;
+        camera.eye += shift;
+    }
+
+    // En la demostración la cámara gira sola y el sol queda quieto, porque
+    // cualquier cambio de luz invalida el historial de la reproyección
+    if orbit_demo {
+        camera.orbit(ORBIT_DEMO_SPEED * frame.delta_time, 0.0);
+        return;
+    }
+
+    // Solo el sol sigue el ciclo día/noche; las demás luces quedan fijas
+    let delta_time = clock.advance(frame.delta_time, *camera != start);
+    for light in lights.iter_mut().filter(|light| light.is_sun) {
+        light.update(delta_time);
+    }
+}
+
+// Modo de edición de luces: L entra y sale, J pasa a la luz siguiente, las
+// flechas y RePág/AvPág la mueven por los ejes del mundo, la rueda cambia su
+// intensidad, K su tipo, Insert agrega una luz en la cámara, Supr borra la
+// seleccionada y Z deshace el último cambio. Devuelve si se salió del modo
+// con cambios, para guardarlos con `save_edited_lights`
+pub fn edit_lights(frame: &FrameRecord, editor: &mut LightEditor, lights: &mut Vec<SceneLight>, camera: &Camera) -> bool {
+    const LIGHT_SPEED: f32 = 0.02;
+    const SCROLL_STEP: f32 = 1.1;
+
+    if frame.was_pressed(Action::NextLight) {
+        if editor.active {
+            editor.select_next(lights);
+        }
+        editor.active = true;
+    }
+    let mut finished = false;
+    if frame.was_pressed(Action::ToggleLightEdit) {
+        editor.active = !editor.active;
+        finished = !editor.active && editor.changed;
+        if finished {
+            editor.changed = false;
+        }
+    }
+    if !editor.active {
+        editor.end_frame();
+        return finished;
+    }
+
+    let moves = [
+        (Action::OrbitLeft, Vec3::new(-1.0, 0.0, 0.0)),
+        (Action::OrbitRight, Vec3::new(1.0, 0.0, 0.0)),
+        (Action::OrbitUp, Vec3::new(0.0, 0.0, -1.0)),
+        (Action::OrbitDown, Vec3::new(0.0, 0.0, 1.0)),
+        (Action::LightUp, Vec3::new(0.0, 1.0, 0.0)),
+        (Action::LightDown, Vec3::new(0.0, -1.0, 0.0)),
+    ];
+    let offset: Vec3 = moves.iter().filter(|(action, _)| frame.is_held(*action)).map(|(_, axis)| axis * LIGHT_SPEED).sum();
+    if offset != Vec3::zeros() {
+        editor.apply(lights, LightEdit::Move(offset));
+    }
+    if frame.scroll != 0.0 {
+        editor.apply(lights, LightEdit::ScaleIntensity(SCROLL_STEP.powf(frame.scroll)));
+    }
+    if frame.was_pressed(Action::CycleLightKind) {
+        editor.apply(lights, LightEdit::CycleKind);
+    }
+    if frame.was_pressed(Action::AddLight) {
+        editor.apply(lights, LightEdit::Add { position: camera.eye, target: camera.center });
+    }
+    if frame.was_pressed(Action::DeleteLight) {
+        editor.apply(lights, LightEdit::DeleteSelected);
+    }
+    if frame.was_pressed(Action::Undo) {
+        editor.undo(lights);
+    }
+    editor.end_frame();
+    false
+}
+
+// Guarda las luces editadas junto al archivo de la escena, que al volver a
+// abrirse las usa (ver `scene::save_lights`). La escena integrada y las
+// generadas no tienen archivo donde guardarlas
+pub fn save_edited_lights(scene_path: Option<&str>, lights: &[SceneLight]) {
+    let Some(scene_path) = scene_path else {
+        eprintln!("Aviso: la escena no tiene archivo; las luces editadas no se guardan");
+        return;
+    };
+    match save_lights(scene_path, lights) {
+        Ok(path) => println!("Luces guardadas en {}", path.display()),
+        Err(e) => eprintln!("Error al guardar las luces de {}: {}", scene_path, e),
+    }
+}
+
+// Línea del panel con la luz seleccionada del modo de edición
+pub fn light_line(editor: &LightEditor, lights: &[SceneLight]) -> String {
+    let Some(light) = editor.selected(lights) else {
+        return "Luces: ninguna (Insert agrega una en la cámara)".to_string();
+    };
+    let kind = match light.kind {
+        LightKind::Spot { angle } => format!("{} de {:.0}°", light.kind.name(), angle),
+        kind => kind.name().to_string(),
+    };
+    let [linear, quadratic] = light.falloff;
+    let falloff = if light.attenuation(1.0) < 1.0 {
+        format!(", atenuación {:.2} / {:.2}", linear, quadratic)
+    } else {
+        String::new()
+    };
+    format!(
+        "Luz {} de {}: {}, intensidad {:.2}{}{}",
+        editor.selected + 1,
+        lights.len(),
+        kind,
+        light.intensity,
+        falloff,
+        if light.is_sun { " (sol: sigue el ciclo)" } else { "" }
+    )
+}
+
+// N cambia el diorama generado por el de la semilla siguiente, con el sol en
+// el mismo momento del día. Devuelve si cambió la escena.
+pub fn regenerate(
+    frame: &FrameRecord,
+    generator: &mut Option<(u64, GenParams)>,
+    scene: &mut Scene,
+    lights: &mut Vec<SceneLight>,
+    settings: &mut RenderSettings,
+    fixed_bias: Option<f32>,
+    merge: bool,
+) -> bool {
+    let Some((seed, params)) = generator.as_mut().filter(|_| frame.was_pressed(Action::Regenerate)) else {
+        return false;
+    };
+    *seed = seed.wrapping_add(1);
+    *scene = Scene::generate(*seed, params);
+    scene.cull_buried();
+    if merge {
+        scene.merge_cubes();
+    }
+    println!("Escena generada: {}", params.scene_entry(*seed));
+
+    let day_time = lights.iter().find(|light| light.is_sun).map(SceneLight::time);
+    *lights = scene.lights.clone();
+    if let Some(time) = day_time {
+        for light in lights.iter_mut().filter(|light| light.is_sun) {
+            light.set_time(time);
+        }
+    }
+    settings.bias = fixed_bias.unwrap_or_else(|| scene.default_bias());
+    true
+}
+
+// Esquinas mínima y máxima del rectángulo entre dos puntos
+pub fn drag_corners(start: (f32, f32), end: (f32, f32)) -> [f32; 4] {
+    [start.0.min(end.0), start.1.min(end.1), start.0.max(end.0), start.1.max(end.1)]
+}
+
+// Píxeles del framebuffer que cubre un rectángulo en coordenadas normalizadas;
+// None si no llega a cubrir uno
+pub fn crop_of([u0, v0, u1, v1]: [f32; 4], width: usize, height: usize) -> Option<Crop> {
+    let column = |u: f32| (u * width as f32).round() as usize;
+    let row = |v: f32| (v * height as f32).round() as usize;
+    let (x, y) = (column(u0), row(v0));
+    let crop = Crop {
+        x,
+        y,
+        width: column(u1).saturating_sub(x),
+        height: row(v1).saturating_sub(y),
+    };
+    crop.clamped(width, height)
+}
+
+// Aplica el recorte que se soltó en el frame o su borrado con X; un arrastre
+// que no cubre ni un píxel deja el recorte como estaba
+pub fn update_crop(frame: &FrameRecord, settings: &mut RenderSettings, width: usize, height: usize) {
+    if frame.was_pressed(Action::ClearCrop) {
+        settings.crop = None;
+    }
+    if let Some(crop) = frame.crop.and_then(|corners| crop_of(corners, width, height)) {
+        settings.crop = Some(crop);
+    }
+}
+
+// Aplica el clic de sonda o el borrado del frame: una sonda nueva reemplaza
+// a la anterior y su registro se imprime por la salida estándar
+pub fn update_probe(
+    frame: &FrameRecord,
+    probe: &mut Option<ProbePath>,
+    framebuffer: &Framebuffer,
+    objects: &[SceneObject],
+    camera: &Camera,
+    lights: &[SceneLight],
+    settings: &RenderSettings,
+) {
+    if frame.was_pressed(Action::ClearProbe) {
+        *probe = None;
+    }
+    if let Some((u, v)) = frame.probe {
+        let x = ((u * framebuffer.width as f32) as usize).min(framebuffer.width - 1);
+        let y = ((v * framebuffer.height as f32) as usize).min(framebuffer.height - 1);
+        let path = probe_pixel(x, y, framebuffer, objects, camera, lights, settings);
+        path.print();
+        *probe = Some(path);
+    }
+}
+
+// La sonda también selecciona el objeto que toca primero, que el minimapa
+// resalta; C borra ambas
+pub fn update_selection(
+    frame: &FrameRecord,
+    selected: &mut Option<usize>,
+    framebuffer: &Framebuffer,
+    objects: &[SceneObject],
+    camera: &Camera,
+    settings: &RenderSettings,
+) {
+    if frame.was_pressed(Action::ClearProbe) {
+        *selected = None;
+    }
+    if let Some((u, v)) = frame.probe {
+        let x = ((u * framebuffer.width as f32) as usize).min(framebuffer.width - 1);
+        let y = ((v * framebuffer.height as f32) as usize).min(framebuffer.height - 1);
+        *selected = pick(x, y, framebuffer, objects, camera, settings).map(|(_, index)| index);
+    }
+}
+
+// Un clic dentro del minimapa lleva el centro de la órbita a ese punto del
+// plano XZ, con la cámara a la misma distancia, en lugar de lanzar una sonda
+pub fn click_minimap(frame: &mut FrameRecord, view: &View, minimap: &Minimap, framebuffer: &Framebuffer, objects: &[SceneObject], camera: &mut Camera) {
+    let Some((u, v)) = frame.probe.filter(|_| view.show_minimap) else {
+        return;
+    };
+    let (x, y) = (u * framebuffer.width as f32, v * framebuffer.height as f32);
+    if let Some((target_x, target_z)) = minimap.world_at(framebuffer.width, objects, camera, x, y) {
+        let offset = Vec3::new(target_x - camera.center.x, 0.0, target_z - camera.center.z);
+        camera.center += offset;
+        camera.eye += offset;
+        frame.probe = None;
+    }
+}
+
+// V divide la pantalla en dos vistas o vuelve a una; la derecha arranca con
+// la cámara de la escena y fija, y B la suelta o la vuelve a fijar. El
+// recorte no se usa con la vista dividida, así que se borra al dividirla
+pub fn update_split(frame: &FrameRecord, split: &mut Option<SplitView>, settings: &mut RenderSettings, scene_camera: &Camera, width: usize, height: usize) {
+    if frame.was_pressed(Action::ToggleSplit) {
+        *split = match split {
+            Some(_) => None,
+            None => {
+                settings.crop = None;
+                Some(SplitView::new(scene_camera.clone(), width, height))
+            }
+        };
+    }
+    if let Some(split) = split.as_mut().filter(|_| frame.was_pressed(Action::ToggleViewLock)) {
+        split.locked = !split.locked;
+    }
+}
+
+// Cámara que recibe la entrada del frame: con la vista dividida, la de la
+// vista bajo el mouse. La derecha fija recibe una copia que se descarta
+pub fn input_camera<'a>(frame: &FrameRecord, camera: &'a mut Camera, split: &'a mut Option<SplitView>, discarded: &'a mut Option<Camera>) -> &'a mut Camera {
+    match split {
+        Some(split) if frame.view == 1 && split.locked => discarded.insert(split.camera.clone()),
+        Some(split) if frame.view == 1 => &mut split.camera,
+        _ => camera,
+    }
+}
+
+// T recorre el recorrido de cámara de la escena desde la primera pose y otra
+// T lo corta. Mientras dura, la cámara principal sigue el recorrido por encima
+// de la entrada de cámara; al terminar queda en la última pose
+pub fn play_rail(frame: &FrameRecord, rail: &CameraRail, objects: &[SceneObject], time: &mut Option<f32>, camera: &mut Camera) {
+    if frame.was_pressed(Action::PlayRail) && !rail.is_empty() {
+        *time = match time {
+            Some(_) => None,
+            None => Some(rail.span().0),
+        };
+    } else if let Some(time) = time.as_mut() {
+        *time += frame.delta_time;
+    }
+    let Some(now) = *time else {
+        return;
+    };
+    if let Some(pose) = rail.at(now, objects) {
+        *camera = Camera { mode: camera.mode, projection: camera.projection, ..pose };
+    }
+    if now >= rail.span().1 {
+        *time = None;
+    }
+}
+
+// Con la vista dividida el clic de sonda pasa a las coordenadas de la vista
+// en que cayó, que también se devuelve
+pub fn probe_frame(frame: &FrameRecord, split: Option<&SplitView>) -> (FrameRecord, usize) {
+    let mut local = frame.clone();
+    match (split, frame.probe) {
+        (Some(split), Some((u, v))) => {
+            let (view, u) = split.view_at(u);
+            local.probe = Some((u, v));
+            (local, view)
+        }
+        _ => (local, 0),
+    }
+}
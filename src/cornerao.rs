@@ -0,0 +1,109 @@
+use nalgebra_glm::Vec3;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::buried::{block_grid, Cell, Occupancy, ALIGNMENT};
+use crate::object::SceneObject;
+
+// Oscurecimiento de las esquinas donde se juntan los bloques, como la
+// iluminación suave de Minecraft. Cada cara se divide en las celdas de una
+// grilla de bloques como la de `buried`; en cada esquina de una celda se
+// miran los tres bloques vecinos de la capa que está delante de la cara (dos
+// de lado y uno en diagonal) y la luz de la esquina baja con cuántos están
+// ocupados. Dentro de la celda se interpola entre las cuatro esquinas según la
+// posición del impacto. No traza rayos: son ocho consultas a la grilla por
+// impacto. Solo tiene sentido en construcciones sobre una grilla, así que se
+// activa por escena; las caras que no caen sobre la grilla quedan sin tocar.
+pub struct CornerOcclusion {
+    occupancy: Occupancy,
+    // Con qué se construyó, para saber cuándo rehacerla
+    scene_key: u64,
+}
+
+// Luz que queda en una esquina según cuántos de sus vecinos están ocupados;
+// con los dos de lado ocupados la esquina está tapada aunque falte el diagonal
+const CORNER_LIGHT: [f32; 4] = [1.0, 0.78, 0.62, 0.5];
+
+impl std::fmt::Debug for CornerOcclusion {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "CornerOcclusion({:016x})", self.scene_key)
+    }
+}
+
+// Huella de lo que ocupa la grilla: cajas de los cubos y si dejan pasar los rayos
+fn scene_key(objects: &[SceneObject]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    let cubes = objects.iter().filter_map(SceneObject::as_cube);
+    cubes.clone().count().hash(&mut hasher);
+    for cube in cubes {
+        for value in cube.min.iter().chain(cube.max.iter()) {
+            value.to_bits().hash(&mut hasher);
+        }
+        cube.material.is_transparent().hash(&mut hasher);
+        cube.material.shadow_catcher.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+impl CornerOcclusion {
+    // La grilla es la de `buried::block_grid`, solo con los cubos. None si no
+    // hay bloques opacos
+    pub fn new(objects: &[SceneObject]) -> Option<Self> {
+        let cubes = objects.iter().filter_map(SceneObject::as_cube);
+        let (origin, cell) = block_grid(cubes.clone())?;
+        Some(CornerOcclusion {
+            occupancy: Occupancy::with_grid(cubes, origin, cell),
+            scene_key: scene_key(objects),
+        })
+    }
+
+    // Factor por el que se multiplica el color en un punto de una cara, de
+    // `CORNER_LIGHT[3]` en un rincón a 1 en una cara despejada
+    pub fn factor(&self, point: &Vec3, normal: &Vec3) -> f32 {
+        let Some(axis) = (0..3).find(|&axis| normal[axis].abs() > 0.99) else {
+            return 1.0;
+        };
+        let grid = self.occupancy.to_grid(point);
+        let plane = grid[axis].round();
+        if (grid[axis] - plane).abs() > ALIGNMENT {
+            return 1.0;
+        }
+
+        // Celda de la capa que está delante de la cara
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let mut front: Cell = [0; 3];
+        front[axis] = if normal[axis] > 0.0 { plane as i32 } else { plane as i32 - 1 };
+        front[a] = grid[a].floor() as i32;
+        front[b] = grid[b].floor() as i32;
+        if self.occupancy.is_occupied(front) {
+            return 1.0;
+        }
+        let occupied = |da: i32, db: i32| {
+            let mut cell = front;
+            cell[a] += da;
+            cell[b] += db;
+            self.occupancy.is_occupied(cell)
+        };
+        let corner = |da: i32, db: i32| {
+            let (side_a, side_b) = (occupied(da, 0), occupied(0, db));
+            let level = if side_a && side_b { 3 } else { side_a as usize + side_b as usize + occupied(da, db) as usize };
+            CORNER_LIGHT[level]
+        };
+
+        let (u, v) = (grid[a] - grid[a].floor(), grid[b] - grid[b].floor());
+        let near = corner(-1, -1) * (1.0 - u) + corner(1, -1) * u;
+        let far = corner(-1, 1) * (1.0 - u) + corner(1, 1) * u;
+        near * (1.0 - v) + far * v
+    }
+}
+
+// Mantiene la grilla al día con los objetos: la rehace si cambiaron y la saca
+// si la escena no usa el oscurecimiento
+pub fn refresh(current: &mut Option<Arc<CornerOcclusion>>, objects: &[SceneObject], enabled: bool) {
+    if !enabled {
+        *current = None;
+    } else if current.as_ref().is_none_or(|occlusion| occlusion.scene_key != scene_key(objects)) {
+        *current = CornerOcclusion::new(objects).map(Arc::new);
+    }
+}
@@ -1,12 +1,20 @@
 use nalgebra_glm::Vec3;
-use crate::Material;
+use crate::decal::{Decal, Face};
+use crate::material::Material;
 use crate::ray_intersect::{RayIntersect, Intersect};
+use crate::texture::Footprint;
 
 
+#[derive(Clone)]
 pub struct Cube {
     pub min: Vec3,
     pub max: Vec3,
     pub material: Material,
+    pub group: String, // Grupo o capa usado por el enlace de luces
+    pub name: String,  // Nombre opcional para identificarlo entre versiones de la escena
+    pub decals: Vec<Decal>, // Calcomanías sobre sus caras, aplicadas en orden
+    pub face_materials: Vec<(Face, Material)>, // Materiales propios de algunas caras; las demás usan `material`
+    pub uv_repeat: Vec3, // Veces que la textura se repite a lo largo de cada eje; una por cubo en las cajas fusionadas
 }
 
 impl Cube {
@@ -16,9 +24,91 @@ impl Cube {
             min,
             max,
             material: material.clone(),  // Clone the material to own it
+            group: String::new(),
+            name: String::new(),
+            decals: Vec::new(),
+            face_materials: Vec::new(),
+            uv_repeat: Vec3::repeat(1.0),
         }
     }
 
+    pub fn in_group(mut self, group: &str) -> Self {
+        self.group = group.to_string();
+        self
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn with_decal(mut self, decal: Decal) -> Self {
+        self.decals.push(decal);
+        self
+    }
+
+    // Cambia el material de una cara, reemplazando el que tuviera
+    pub fn with_face_material(mut self, face: Face, material: &Material) -> Self {
+        self.face_materials.retain(|(other, _)| *other != face);
+        self.face_materials.push((face, material.clone()));
+        self
+    }
+
+    // Bloque como los del pasto: un material arriba, otro abajo y otro en
+    // los cuatro lados
+    pub fn with_face_materials(self, top: &Material, bottom: &Material, sides: &Material) -> Self {
+        [Face::X, Face::NegX, Face::Z, Face::NegZ]
+            .into_iter()
+            .fold(self, |cube, face| cube.with_face_material(face, sides))
+            .with_face_material(Face::Y, top)
+            .with_face_material(Face::NegY, bottom)
+    }
+
+    pub fn face_material(&self, face: Face) -> &Material {
+        self.face_materials
+            .iter()
+            .find(|(other, _)| *other == face)
+            .map_or(&self.material, |(_, material)| material)
+    }
+
+    pub fn contains(&self, point: &Vec3) -> bool {
+        (0..3).all(|axis| point[axis] >= self.min[axis] && point[axis] <= self.max[axis])
+    }
+
+    // Normal de sombreado con las aristas redondeadas en `bevel` unidades: cerca
+    // de una arista la normal de la cara se inclina hacia la de la cara vecina
+    // hasta quedar a medio camino justo sobre la arista. La geometría no cambia.
+    pub fn bevel_normal(&self, point: &Vec3, normal: &Vec3, bevel: f32) -> Vec3 {
+        let mut bent = *normal;
+        for axis in (0..3).filter(|&axis| normal[axis].abs() < 0.5) {
+            let width = bevel.min((self.max[axis] - self.min[axis]) * 0.5);
+            let to_min = point[axis] - self.min[axis];
+            let to_max = self.max[axis] - point[axis];
+            let (distance, side) = if to_min < to_max { (to_min, -1.0) } else { (to_max, 1.0) };
+            if width > 0.0 && distance < width {
+                bent[axis] += side * (1.0 - distance / width);
+            }
+        }
+        bent.normalize()
+    }
+
+    // Distancia hasta la cara por la que sale un rayo que parte de adentro:
+    // la más cercana de las caras hacia las que avanza en cada eje
+    pub fn exit_distance(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        (0..3)
+            .map(|axis| {
+                let face = if direction[axis] > 0.0 { self.max[axis] } else { self.min[axis] };
+                if direction[axis] == 0.0 { f32::INFINITY } else { (face - origin[axis]) / direction[axis] }
+            })
+            .fold(f32::INFINITY, f32::min)
+            .max(0.0)
+    }
+
+    pub fn volume(&self) -> f32 {
+        let size = self.max - self.min;
+        size.x * size.y * size.z
+    }
+
     fn calculate_normal(&self, hit_point: Vec3) -> Vec3 {
         if (hit_point.x - self.min.x).abs() < 1e-4 {
             return Vec3::new(-1.0, 0.0, 0.0);
@@ -37,8 +127,36 @@ impl Cube {
         }
         Vec3::new(0.0, 0.0, 1.0)
     }
-}
 
+    // Coordenadas de textura de un punto de una cara: de 0 a 1 a lo ancho de
+    // la cara, con la orientación de `Face::uv` para que ninguna quede
+    // espejada, y repetidas `uv_repeat` veces por eje
+    fn face_uv(&self, point: &Vec3, face: Face) -> (f32, f32) {
+        let (u, v) = face.uv(self, point);
+        let [(u_axis, _), (v_axis, _)] = face.axes();
+        (u * self.uv_repeat[u_axis], v * self.uv_repeat[v_axis])
+    }
+
+    // Huella del píxel en UV: se alarga con la inclinación entre el rayo y la
+    // normal, en la dirección del rayo proyectada sobre la cara y llevada a
+    // las escalas de u y v
+    fn face_footprint(&self, face: Face, ray_direction: &Vec3) -> Footprint {
+        const MAX_ELONGATION: f32 = 16.0;
+        let normal = face.normal();
+        let cos = ray_direction.dot(&normal).abs().max(1.0 / MAX_ELONGATION);
+        let along = ray_direction - normal * ray_direction.dot(&normal);
+
+        // Mismos ejes que face_uv
+        let [du, dv] = face.axes().map(|(axis, reversed)| {
+            let scale = self.uv_repeat[axis] / (self.max[axis] - self.min[axis]);
+            if reversed { -along[axis] * scale } else { along[axis] * scale }
+        });
+
+        let length = (du * du + dv * dv).sqrt();
+        let axis = if length > 1e-6 { (du / length, dv / length) } else { (1.0, 0.0) };
+        Footprint { axis, elongation: 1.0 / cos }
+    }
+}
 
 impl RayIntersect for Cube {
     fn ray_intersect(&self, ray_origin: &Vec3, ray_dir: &Vec3) -> Intersect {
@@ -82,13 +200,19 @@ impl RayIntersect for Cube {
         }
 
         let intersection_point = ray_origin + ray_dir * tmin;
+        let normal = self.calculate_normal(intersection_point);
+        let face = Face::from_normal(&normal);
 
-        Intersect {
-            point: intersection_point,
-            distance: tmin,
-            normal: self.calculate_normal(intersection_point),
-            material: self.material.clone(),
-            is_intersecting: true,
-        }
+        Intersect::new(intersection_point, normal, tmin, self.face_material(face).clone())
+            .with_uv(self.face_uv(&intersection_point, face), self.face_footprint(face, ray_dir))
+    }
+
+    // Exacta: afuera es la distancia al punto más cercano de la caja y adentro
+    // la distancia a la cara más cercana
+    fn distance(&self, point: &Vec3) -> f32 {
+        let q = (self.min - point).sup(&(point - self.max));
+        let outside = q.sup(&Vec3::zeros()).magnitude();
+        let inside = q.x.max(q.y).max(q.z).min(0.0);
+        outside + inside
     }
 }
\ No newline at end of file
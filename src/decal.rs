@@ -0,0 +1,140 @@
+use nalgebra_glm::Vec3;
+use serde::Deserialize;
+
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::texture::Texture;
+
+// Calcomanías: imágenes estampadas sobre una cara de un cubo sin necesidad de
+// una textura propia. Se mezclan sobre el color difuso de la superficie antes
+// de iluminarla, así reciben la misma luz y sombra que la cara. Las de un
+// mismo cubo se aplican en orden, cada una sobre el resultado de las
+// anteriores.
+
+// Cara de un cubo según el eje de su normal, con los mismos nombres que `UpAxis`
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Face {
+    X,
+    NegX,
+    Y,
+    NegY,
+    Z,
+    NegZ,
+}
+
+impl Face {
+    pub fn normal(self) -> Vec3 {
+        match self {
+            Face::X => Vec3::new(1.0, 0.0, 0.0),
+            Face::NegX => Vec3::new(-1.0, 0.0, 0.0),
+            Face::Y => Vec3::new(0.0, 1.0, 0.0),
+            Face::NegY => Vec3::new(0.0, -1.0, 0.0),
+            Face::Z => Vec3::new(0.0, 0.0, 1.0),
+            Face::NegZ => Vec3::new(0.0, 0.0, -1.0),
+        }
+    }
+
+    // Cara cuya normal es la dada, que tiene que estar alineada con un eje
+    pub fn from_normal(normal: &Vec3) -> Face {
+        let axis = normal.iamax();
+        match (axis, normal[axis] > 0.0) {
+            (0, true) => Face::X,
+            (0, false) => Face::NegX,
+            (1, true) => Face::Y,
+            (1, false) => Face::NegY,
+            (2, true) => Face::Z,
+            _ => Face::NegZ,
+        }
+    }
+
+    // Ejes del mundo a lo largo de los que avanzan u y v sobre la cara, y si
+    // avanzan al revés, para que una imagen se lea derecha vista desde afuera:
+    // en las caras laterales v crece hacia arriba y en la superior hacia -Z,
+    // lejos de la cámara por defecto
+    pub fn axes(self) -> [(usize, bool); 2] {
+        match self {
+            Face::X => [(2, true), (1, false)],
+            Face::NegX => [(2, false), (1, false)],
+            Face::Y => [(0, false), (2, true)],
+            Face::NegY => [(0, false), (2, false)],
+            Face::Z => [(0, false), (1, false)],
+            Face::NegZ => [(0, true), (1, false)],
+        }
+    }
+
+    // Direcciones del mundo en las que crecen u y v sobre la cara; con la
+    // normal forman el marco de los mapas de normales
+    pub fn tangents(self) -> [Vec3; 2] {
+        self.axes().map(|(axis, reversed)| {
+            let mut direction = Vec3::zeros();
+            direction[axis] = if reversed { -1.0 } else { 1.0 };
+            direction
+        })
+    }
+
+    // Coordenadas de 0 a 1 de un punto sobre esta cara del cubo, con los
+    // ejes de `axes`
+    pub fn uv(self, cube: &Cube, point: &Vec3) -> (f32, f32) {
+        let along = |(axis, reversed): (usize, bool)| {
+            let t = (point[axis] - cube.min[axis]) / (cube.max[axis] - cube.min[axis]);
+            if reversed { 1.0 - t } else { t }
+        };
+        let [u, v] = self.axes();
+        (along(u), along(v))
+    }
+}
+
+// Cómo se combina la calcomanía con lo que tiene debajo
+#[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DecalBlend {
+    // Encima, según su alfa
+    #[default]
+    Over,
+    // Oscurece multiplicando, para manchas y suciedad
+    Multiply,
+}
+
+#[derive(Debug, Clone)]
+pub struct Decal {
+    pub face: Face,
+    // Rectángulo (u0, v0, u1, v1) de la cara que cubre la imagen, en las
+    // coordenadas de `Face::uv`
+    pub rect: (f32, f32, f32, f32),
+    pub texture: Texture,
+    pub blend: DecalBlend,
+}
+
+impl Decal {
+    // Posición dentro de la imagen si el punto cae en el rectángulo
+    fn local_uv(&self, cube: &Cube, point: &Vec3) -> Option<(f32, f32)> {
+        let (u, v) = self.face.uv(cube, point);
+        let (u0, v0, u1, v1) = self.rect;
+        let (s, t) = ((u - u0) / (u1 - u0), (v - v0) / (v1 - v0));
+        ((0.0..=1.0).contains(&s) && (0.0..=1.0).contains(&t)).then_some((s, t))
+    }
+}
+
+// Color de la superficie en un punto de la cara con normal `normal` después
+// de aplicar las calcomanías del cubo sobre `base`
+pub fn apply(cube: &Cube, point: &Vec3, normal: &Vec3, base: Color) -> Color {
+    if cube.decals.is_empty() {
+        return base;
+    }
+    let face = Face::from_normal(normal);
+    let mut rgb = [base.red(), base.green(), base.blue()].map(|c| c as f32);
+    for decal in cube.decals.iter().filter(|decal| decal.face == face) {
+        let Some((s, t)) = decal.local_uv(cube, point) else {
+            continue;
+        };
+        let (color, alpha) = decal.texture.sample_rgba(s, t);
+        for (channel, value) in rgb.iter_mut().zip(color) {
+            let stamped = match decal.blend {
+                DecalBlend::Over => value,
+                DecalBlend::Multiply => *channel * value / 255.0,
+            };
+            *channel += (stamped - *channel) * alpha;
+        }
+    }
+    let [r, g, b] = rgb.map(|c| c.round().clamp(0.0, 255.0) as u8);
+    Color::new(r, g, b)
+}
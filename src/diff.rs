@@ -0,0 +1,449 @@
+use nalgebra_glm::Vec3;
+use std::fmt::Write;
+
+use crate::bvh::{self, Aabb};
+use crate::color::{Color, Palette};
+use crate::cornerao;
+use crate::framebuffer::Framebuffer;
+use crate::light::{SceneLight, NOON};
+use crate::material::Material;
+use crate::object::SceneObject;
+use crate::renderer::{render, sky_color};
+use crate::scene::Scene;
+use crate::settings::RenderSettings;
+
+// Diferencia máxima entre dos valores en punto flotante para considerarlos
+// iguales; los archivos de escena se editan a mano y redondean distinto
+pub const DEFAULT_TOLERANCE: f32 = 1e-4;
+
+// Grupo de los objetos eliminados en la visualización: las luces lo excluyen
+// para que los fantasmas no proyecten sombras
+const GHOST_GROUP: &str = "__diff_eliminado";
+
+const ADDED_COLOR: Color = Color::from_hex(0x30d040);
+const REMOVED_COLOR: Color = Color::from_hex(0xff3030);
+
+// Un campo que cambió, ya formateado para el reporte
+#[derive(Debug, Clone, PartialEq)]
+pub struct FieldChange {
+    pub field: &'static str,
+    pub before: String,
+    pub after: String,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    // Los índices son los de la escena donde existe el objeto o la luz
+    ObjectAdded { index: usize, label: String },
+    ObjectRemoved { index: usize, label: String },
+    ObjectModified { before: usize, after: usize, label: String, fields: Vec<FieldChange> },
+    MaterialAdded(String),
+    MaterialRemoved(String),
+    MaterialModified { name: String, fields: Vec<FieldChange> },
+    LightAdded(usize),
+    LightRemoved(usize),
+    LightModified { index: usize, fields: Vec<FieldChange> },
+    Camera(Vec<FieldChange>),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct SceneDiff {
+    pub changes: Vec<Change>,
+}
+
+fn close(a: f32, b: f32, tolerance: f32) -> bool {
+    (a - b).abs() <= tolerance
+}
+
+fn close_vec(a: &Vec3, b: &Vec3, tolerance: f32) -> bool {
+    (0..3).all(|axis| close(a[axis], b[axis], tolerance))
+}
+
+fn format_vec(v: &Vec3) -> String {
+    format!("({:.3}, {:.3}, {:.3})", v.x, v.y, v.z)
+}
+
+fn format_color(color: &Color) -> String {
+    format!("#{:02x}{:02x}{:02x}", color.red(), color.green(), color.blue())
+}
+
+// Acumula los campos distintos de un par de elementos emparejados
+struct Fields {
+    tolerance: f32,
+    changes: Vec<FieldChange>,
+}
+
+impl Fields {
+    fn new(tolerance: f32) -> Self {
+        Fields { tolerance, changes: Vec::new() }
+    }
+
+    fn push(&mut self, field: &'static str, before: String, after: String) {
+        self.changes.push(FieldChange { field, before, after });
+    }
+
+    fn text<T: PartialEq + ToString>(&mut self, field: &'static str, before: T, after: T) {
+        if before != after {
+            self.push(field, before.to_string(), after.to_string());
+        }
+    }
+
+    fn number(&mut self, field: &'static str, before: f32, after: f32) {
+        if !close(before, after, self.tolerance) {
+            self.push(field, format!("{:.3}", before), format!("{:.3}", after));
+        }
+    }
+
+    fn vector(&mut self, field: &'static str, before: &Vec3, after: &Vec3) {
+        if !close_vec(before, after, self.tolerance) {
+            self.push(field, format_vec(before), format_vec(after));
+        }
+    }
+
+    fn color(&mut self, field: &'static str, before: &Color, after: &Color) {
+        if before != after {
+            self.push(field, format_color(before), format_color(after));
+        }
+    }
+}
+
+fn label(index: usize, object: &SceneObject) -> String {
+    let mut label = format!("#{}", index);
+    if !object.name().is_empty() {
+        let _ = write!(label, " \"{}\"", object.name());
+    }
+    let _ = write!(label, " {}", object.material().name);
+    if !object.group().is_empty() {
+        let _ = write!(label, " ({})", object.group());
+    }
+    match object {
+        SceneObject::Cube(cube) => {
+            let _ = write!(label, " {}..{}", format_vec(&cube.min), format_vec(&cube.max));
+        }
+        SceneObject::Sphere(sphere) => {
+            let _ = write!(label, " esfera {} r {}", format_vec(&sphere.center), sphere.radius);
+        }
+        SceneObject::Plane(plane) => {
+            let _ = write!(label, " plano {} n {}", format_vec(&plane.point), format_vec(&plane.normal));
+        }
+    }
+    label
+}
+
+fn object_fields(before: &SceneObject, after: &SceneObject, tolerance: f32) -> Vec<FieldChange> {
+    let mut fields = Fields::new(tolerance);
+    fields.text("nombre", before.name(), after.name());
+    fields.text("forma", before.kind(), after.kind());
+    match (before, after) {
+        (SceneObject::Sphere(a), SceneObject::Sphere(b)) => {
+            fields.vector("centro", &a.center, &b.center);
+            fields.number("radio", a.radius, b.radius);
+        }
+        (SceneObject::Plane(a), SceneObject::Plane(b)) => {
+            fields.vector("punto", &a.point, &b.point);
+            fields.vector("normal", &a.normal, &b.normal);
+            fields.number("mosaico", a.tile, b.tile);
+        }
+        _ => {
+            let (a, b) = (before.bounds(), after.bounds());
+            fields.vector("min", &a.min, &b.min);
+            fields.vector("max", &a.max, &b.max);
+        }
+    }
+    fields.text("material", before.material().name.as_str(), after.material().name.as_str());
+    fields.text("grupo", before.group(), after.group());
+    fields.changes
+}
+
+fn material_fields(before: &Material, after: &Material, tolerance: f32) -> Vec<FieldChange> {
+    let texture = |material: &Material| material.texture.as_ref().map_or("ninguna", |texture| texture.path()).to_string();
+    let mut fields = Fields::new(tolerance);
+    fields.color("color", &before.color, &after.color);
+    fields.text("textura", texture(before), texture(after));
+    let animation = |material: &Material| {
+        material.animation.as_ref().map_or("ninguna".to_string(), |animation| format!("{} cuadros a {}", animation.frames().len(), animation.fps))
+    };
+    fields.text("animacion", animation(before), animation(after));
+    let normal_map = |material: &Material| material.normal_map.as_ref().map_or("ninguno", |texture| texture.path()).to_string();
+    fields.text("mapa de normales", normal_map(before), normal_map(after));
+    fields.number("brillo", before.shininess, after.shininess);
+    fields.number("difuso", before.diffuse, after.diffuse);
+    fields.number("especular", before.specular, after.specular);
+    fields.number("reflectividad", before.reflectivity, after.reflectivity);
+    fields.number("transparencia", before.transparency, after.transparency);
+    fields.number("refraccion", before.refractive_index, after.refractive_index);
+    fields.color("emision", &before.emission, &after.emission);
+    let emission_map = |material: &Material| material.emission_map.as_ref().map_or("ninguno", |texture| texture.path()).to_string();
+    fields.text("mapa de emision", emission_map(before), emission_map(after));
+    fields.number("intensidad de emision", before.emission_strength, after.emission_strength);
+    fields.text("reflexion planar", before.planar_reflection, after.planar_reflection);
+    fields.number("halo", before.halo, after.halo);
+    fields.text("receptor de sombras", before.shadow_catcher, after.shadow_catcher);
+    fields.text("recibe sombras", before.receives_shadows, after.receives_shadows);
+    fields.text("sin iluminar", before.unlit, after.unlit);
+    fields.text("filtro", before.filter.name(), after.filter.name());
+    fields.number("bisel", before.edge_bevel, after.edge_bevel);
+    fields.text("coordenadas", format!("{:?}", before.uv_mode), format!("{:?}", after.uv_mode));
+    fields.number("escala de textura", before.texture_scale, after.texture_scale);
+    fields.text("fresnel", before.use_fresnel, after.use_fresnel);
+    fields.color("absorcion", &before.absorption, &after.absorption);
+    fields.number("densidad", before.absorption_density, after.absorption_density);
+    fields.text("causticas", before.caustics, after.caustics);
+    fields.changes
+}
+
+fn light_fields(before: &SceneLight, after: &SceneLight, tolerance: f32) -> Vec<FieldChange> {
+    let mut fields = Fields::new(tolerance);
+    fields.vector("posicion", &before.position, &after.position);
+    fields.color("color", &before.color, &after.color);
+    fields.number("intensidad", before.intensity, after.intensity);
+    fields.text("sol", before.is_sun, after.is_sun);
+    fields.text("tipo", format!("{:?}", before.kind), format!("{:?}", after.kind));
+    fields.vector("objetivo", &before.target, &after.target);
+    fields.text("incluye", before.include.join(","), after.include.join(","));
+    fields.text("excluye", before.exclude.join(","), after.exclude.join(","));
+    fields.number("atenuacion lineal", before.falloff[0], after.falloff[0]);
+    fields.number("atenuacion cuadratica", before.falloff[1], after.falloff[1]);
+    fields.number("radio", before.radius, after.radius);
+    fields.changes
+}
+
+type Matcher<'a> = &'a dyn Fn(&SceneObject, &SceneObject) -> bool;
+
+// Empareja los objetos de las dos versiones: primero por nombre, después los
+// que ocupan la misma caja, los que mantienen el centro y por último los del
+// mismo material y grupo cuyas cajas se solapan (un bloque estirado o movido
+// un poco). Lo que queda suelto se cuenta como añadido o eliminado.
+fn match_objects(before: &[SceneObject], after: &[SceneObject], tolerance: f32) -> Vec<(usize, usize)> {
+    let mut used_before = vec![false; before.len()];
+    let mut used_after = vec![false; after.len()];
+    let mut pairs = Vec::new();
+
+    let overlap = |a: &Aabb, b: &Aabb| (0..3).all(|axis| a.min[axis] < b.max[axis] && b.min[axis] < a.max[axis]);
+    let passes: [Matcher; 4] = [
+        &|a, b| !a.name().is_empty() && a.name() == b.name(),
+        &|a, b| {
+            let (a, b) = (a.bounds(), b.bounds());
+            close_vec(&a.min, &b.min, tolerance) && close_vec(&a.max, &b.max, tolerance)
+        },
+        &|a, b| close_vec(&a.center(), &b.center(), tolerance),
+        &|a, b| a.material().name == b.material().name && a.group() == b.group() && overlap(&a.bounds(), &b.bounds()),
+    ];
+
+    for matches in passes {
+        for (i, a) in before.iter().enumerate() {
+            if used_before[i] {
+                continue;
+            }
+            let found = after
+                .iter()
+                .enumerate()
+                .position(|(j, b)| !used_after[j] && matches(a, b));
+            if let Some(j) = found {
+                used_before[i] = true;
+                used_after[j] = true;
+                pairs.push((i, j));
+            }
+        }
+    }
+
+    pairs.sort();
+    pairs
+}
+
+// Compara dos escenas. Las luces se emparejan por orden y los materiales por
+// nombre; los flotantes se comparan con `tolerance`.
+pub fn diff_scenes(before: &Scene, after: &Scene, tolerance: f32) -> SceneDiff {
+    let mut changes = Vec::new();
+
+    let pairs = match_objects(&before.objects, &after.objects, tolerance);
+    for &(i, j) in &pairs {
+        let fields = object_fields(&before.objects[i], &after.objects[j], tolerance);
+        if !fields.is_empty() {
+            let label = label(j, &after.objects[j]);
+            changes.push(Change::ObjectModified { before: i, after: j, label, fields });
+        }
+    }
+    for (i, object) in before.objects.iter().enumerate() {
+        if !pairs.iter().any(|&(a, _)| a == i) {
+            changes.push(Change::ObjectRemoved { index: i, label: label(i, object) });
+        }
+    }
+    for (j, object) in after.objects.iter().enumerate() {
+        if !pairs.iter().any(|&(_, b)| b == j) {
+            changes.push(Change::ObjectAdded { index: j, label: label(j, object) });
+        }
+    }
+
+    let mut names: Vec<&String> = before.materials.keys().chain(after.materials.keys()).collect();
+    names.sort();
+    names.dedup();
+    for name in names {
+        match (before.materials.get(name), after.materials.get(name)) {
+            (Some(a), Some(b)) => {
+                let fields = material_fields(a, b, tolerance);
+                if !fields.is_empty() {
+                    changes.push(Change::MaterialModified { name: name.clone(), fields });
+                }
+            }
+            (Some(_), None) => changes.push(Change::MaterialRemoved(name.clone())),
+            (None, Some(_)) => changes.push(Change::MaterialAdded(name.clone())),
+            (None, None) => {}
+        }
+    }
+
+    let light_count = before.lights.len().max(after.lights.len());
+    for index in 0..light_count {
+        match (before.lights.get(index), after.lights.get(index)) {
+            (Some(a), Some(b)) => {
+                let fields = light_fields(a, b, tolerance);
+                if !fields.is_empty() {
+                    changes.push(Change::LightModified { index, fields });
+                }
+            }
+            (Some(_), None) => changes.push(Change::LightRemoved(index)),
+            (None, Some(_)) => changes.push(Change::LightAdded(index)),
+            (None, None) => {}
+        }
+    }
+
+    let mut camera = Fields::new(tolerance);
+    camera.vector("eye", &before.camera.eye, &after.camera.eye);
+    camera.vector("center", &before.camera.center, &after.camera.center);
+    camera.vector("up", &before.camera.up, &after.camera.up);
+    if !camera.changes.is_empty() {
+        changes.push(Change::Camera(camera.changes));
+    }
+
+    SceneDiff { changes }
+}
+
+fn write_fields(report: &mut String, fields: &[FieldChange]) {
+    for field in fields {
+        let _ = writeln!(report, "      {}: {} -> {}", field.field, field.before, field.after);
+    }
+}
+
+impl SceneDiff {
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    // Índices en la escena nueva de los objetos añadidos
+    pub fn added_objects(&self) -> Vec<usize> {
+        self.changes
+            .iter()
+            .filter_map(|change| match change {
+                Change::ObjectAdded { index, .. } => Some(*index),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Índices en la escena original de los objetos eliminados
+    pub fn removed_objects(&self) -> Vec<usize> {
+        self.changes
+            .iter()
+            .filter_map(|change| match change {
+                Change::ObjectRemoved { index, .. } => Some(*index),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // Reporte de texto: `+` añadido, `-` eliminado y `~` modificado, agrupado
+    // por sección
+    pub fn report(&self) -> String {
+        if self.is_empty() {
+            return "Sin diferencias".to_string();
+        }
+
+        let mut sections: [(&str, String); 4] = [
+            ("Objetos", String::new()),
+            ("Materiales", String::new()),
+            ("Luces", String::new()),
+            ("Cámara", String::new()),
+        ];
+        for change in &self.changes {
+            let (section, text) = match change {
+                Change::ObjectAdded { label, .. } => (0, format!("  + {}\n", label)),
+                Change::ObjectRemoved { label, .. } => (0, format!("  - {}\n", label)),
+                Change::ObjectModified { before, label, fields, .. } => {
+                    let mut text = format!("  ~ {} (antes #{})\n", label, before);
+                    write_fields(&mut text, fields);
+                    (0, text)
+                }
+                Change::MaterialAdded(name) => (1, format!("  + {}\n", name)),
+                Change::MaterialRemoved(name) => (1, format!("  - {}\n", name)),
+                Change::MaterialModified { name, fields } => {
+                    let mut text = format!("  ~ {}\n", name);
+                    write_fields(&mut text, fields);
+                    (1, text)
+                }
+                Change::LightAdded(index) => (2, format!("  + #{}\n", index)),
+                Change::LightRemoved(index) => (2, format!("  - #{}\n", index)),
+                Change::LightModified { index, fields } => {
+                    let mut text = format!("  ~ #{}\n", index);
+                    write_fields(&mut text, fields);
+                    (2, text)
+                }
+                Change::Camera(fields) => {
+                    let mut text = String::new();
+                    write_fields(&mut text, fields);
+                    (3, text)
+                }
+            };
+            sections[section].1.push_str(&text);
+        }
+
+        let mut report = String::new();
+        for (title, body) in &sections {
+            if !body.is_empty() {
+                let _ = write!(report, "{}:\n{}", title, body);
+            }
+        }
+        let _ = write!(report, "{} diferencias", self.changes.len());
+        report
+    }
+}
+
+// Render combinado: la escena nueva vista desde su cámara, con los objetos
+// añadidos teñidos de verde y los eliminados como fantasmas rojos
+// semitransparentes que no proyectan sombra (azul y naranja con las paletas
+// para daltonismo). El sol queda al mediodía como en las miniaturas.
+pub fn render_diff(before: &Scene, after: &Scene, diff: &SceneDiff, width: usize, height: usize, palette: Palette) -> Framebuffer {
+    let (added_color, removed_color) = (palette.category(1, ADDED_COLOR), palette.category(0, REMOVED_COLOR));
+    let mut objects = after.objects.clone();
+    for index in diff.added_objects() {
+        let material = objects[index].material_mut();
+        material.texture = None;
+        material.emission_map = None;
+        material.color = added_color;
+        material.emission = added_color * 0.6;
+    }
+    for index in diff.removed_objects() {
+        let mut ghost = before.objects[index].clone().in_group(GHOST_GROUP);
+        let mut material = Material::with_emission(removed_color, 0.0, [0.3, 0.0, 0.0, 0.6], 1.0, removed_color);
+        material.name = ghost.material().name.clone();
+        *ghost.material_mut() = material;
+        objects.push(ghost);
+    }
+
+    let mut lights = after.lights.clone();
+    for light in &mut lights {
+        light.exclude.push(GHOST_GROUP.to_string());
+        if light.is_sun {
+            light.set_time(NOON);
+        }
+    }
+
+    let mut settings = RenderSettings {
+        bias: after.default_bias(),
+        ..RenderSettings::default()
+    };
+    cornerao::refresh(&mut settings.corner_ao, &objects, after.corner_ao);
+    bvh::refresh(&mut settings.bvh, &objects);
+    let mut framebuffer = Framebuffer::new(width, height);
+    render(&mut framebuffer, &objects, &after.camera, &lights, sky_color(&lights), &settings);
+    framebuffer
+}
@@ -0,0 +1,149 @@
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::app::expose_for_near;
+use crate::bake::{bake, BakeSettings};
+use crate::camera::Camera;
+use crate::caustics;
+use crate::cli::Args;
+use crate::cornerao;
+use crate::framebuffer::Framebuffer;
+use crate::gltf::write_glb;
+use crate::irradiance;
+use crate::light::SceneLight;
+use crate::object::SceneObject;
+use crate::renderer::{render, sky_color};
+use crate::scene::Scene;
+use crate::settings::RenderSettings;
+use crate::shadowmap;
+use crate::sky;
+
+// Hornea la luz de la escena con `lights` y la guarda en `path` como glTF
+// binario. El modelo no se mueve: cada animación queda en el cuadro que se ve
+// (ver `Scene::time`). Devuelve si se guardó
+pub fn export_gltf(path: &str, scene: &Scene, lights: &[SceneLight], settings: &RenderSettings) -> bool {
+    let bake_settings = BakeSettings {
+        bias: settings.bias,
+        shadow_quality: settings.shadow_quality,
+        time: scene.time(),
+        ..BakeSettings::default()
+    };
+    let animated: BTreeSet<_> = scene
+        .objects
+        .iter()
+        .flat_map(SceneObject::materials)
+        .filter(|material| material.animation.is_some())
+        .map(|material| material.name.as_str())
+        .collect();
+    if !animated.is_empty() {
+        let names: Vec<_> = animated.into_iter().collect();
+        eprintln!("Aviso: las animaciones de {} se exportan en el cuadro del segundo {}", names.join(", "), bake_settings.time);
+    }
+    let faces = bake(&scene.objects, lights, &bake_settings);
+    match write_glb(path, &faces) {
+        Ok(()) => {
+            println!("Modelo exportado en {} ({} caras)", path, faces.len());
+            true
+        }
+        Err(e) => {
+            eprintln!("Error al exportar {}: {}", path, e);
+            false
+        }
+    }
+}
+
+// Renderiza el recorrido de cámara de la escena en `dir` como
+// `cuadro_0000.png`, `cuadro_0001.png`... a `fps` cuadros por segundo. La
+// cámara de cada cuadro sale de su índice y las luces quedan como al arrancar,
+// así volver a exportar da las mismas imágenes. Devuelve si se guardaron todos
+pub fn export_rail(
+    dir: &str,
+    scene: &mut Scene,
+    lights: &mut [SceneLight],
+    settings: &mut RenderSettings,
+    args: &Args,
+) -> bool {
+    if scene.rail.is_empty() {
+        eprintln!("La escena no tiene recorrido de cámara (`rail`)");
+        return false;
+    }
+    if let Err(e) = std::fs::create_dir_all(dir) {
+        eprintln!("Error al crear la carpeta {}: {}", dir, e);
+        return false;
+    }
+    let frames = scene.rail.frame_count(args.fps);
+    let mut framebuffer = Framebuffer::new(args.width, args.height);
+    for index in 0..frames {
+        let time = scene.rail.frame_time(index, args.fps);
+        let Some(pose) = scene.rail.at(time, &scene.objects) else {
+            break;
+        };
+        let camera = Camera { projection: scene.camera.projection, ..pose };
+        expose_for_near(scene, settings);
+        scene.update_chunks(&camera, args.width as f32 / args.height as f32);
+        let objects = &scene.objects;
+        shadowmap::refresh(lights, objects, settings.shadow_map.as_ref());
+        cornerao::refresh(&mut settings.corner_ao, objects, args.corner_ao || scene.corner_ao);
+        caustics::refresh(&mut settings.caustics, objects, time);
+        settings.bvh = Some(scene.bvh());
+        sky::refresh(&mut settings.sky, lights, args.physical_sky.then_some(scene.turbidity));
+        irradiance::warm_up(&mut framebuffer, objects, &camera, lights, settings, args.indirect);
+        render(&mut framebuffer, objects, &camera, lights, sky_color(lights), settings);
+
+        let path = Path::new(dir).join(format!("cuadro_{:04}.png", index));
+        let saved = if args.transparent {
+            framebuffer.save_png_rgba(&path.to_string_lossy())
+        } else {
+            framebuffer.save_png(&path.to_string_lossy())
+        };
+        if let Err(e) = saved {
+            eprintln!("Error al guardar {}: {}", path.display(), e);
+            return false;
+        }
+    }
+    println!("Recorrido exportado en {}: {} cuadros a {} por segundo", dir, frames, args.fps);
+    true
+}
+
+// Carpeta de las capturas de F12 y los renders de F11
+pub const SCREENSHOT_DIR: &str = "screenshots";
+
+// Guarda la imagen en `screenshots/<prefijo>_<fecha>.png`, con alfa si
+// `rgba`, creando la carpeta si falta. Dos capturas en el mismo segundo no se
+// pisan.
+pub fn save_capture(framebuffer: &Framebuffer, prefix: &str, rgba: bool) {
+    if let Err(e) = std::fs::create_dir_all(SCREENSHOT_DIR) {
+        eprintln!("Error al crear la carpeta {}: {}", SCREENSHOT_DIR, e);
+        return;
+    }
+    let stamp = timestamp(SystemTime::now());
+    let path = (1..)
+        .map(|copy| match copy {
+            1 => format!("{}/{}_{}.png", SCREENSHOT_DIR, prefix, stamp),
+            _ => format!("{}/{}_{}_{}.png", SCREENSHOT_DIR, prefix, stamp, copy),
+        })
+        .find(|path| !Path::new(path).exists())
+        .unwrap();
+    let saved = if rgba { framebuffer.save_png_rgba(&path) } else { framebuffer.save_png(&path) };
+    match saved {
+        Ok(()) => println!("Captura guardada en {}", path),
+        Err(e) => eprintln!("Error al guardar la captura {}: {}", path, e),
+    }
+}
+
+// Fecha y hora UTC de `time` como AAAA-MM-DD_HH-MM-SS, con el paso de días
+// desde 1970 a fecha civil de Howard Hinnant
+pub fn timestamp(time: SystemTime) -> String {
+    let seconds = time.duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+    let (days, rest) = (seconds / 86_400, seconds % 86_400);
+    let shifted = days + 719_468;
+    let (era, day_of_era) = (shifted / 146_097, shifted % 146_097);
+    let year_of_era = (day_of_era - day_of_era / 1460 + day_of_era / 36_524 - day_of_era / 146_096) / 365;
+    let day_of_year = day_of_era - (365 * year_of_era + year_of_era / 4 - year_of_era / 100);
+    let month_index = (5 * day_of_year + 2) / 153;
+    let day = day_of_year - (153 * month_index + 2) / 5 + 1;
+    let month = if month_index < 10 { month_index + 3 } else { month_index - 9 };
+    let year = era * 400 + year_of_era + u64::from(month <= 2);
+    format!("{:04}-{:02}-{:02}_{:02}-{:02}-{:02}", year, month, day, rest / 3600, rest / 60 % 60, rest % 60)
+}
@@ -1,9 +1,63 @@
+use crate::color::{linear_to_srgb, srgb_to_linear, Color};
+
+// Formatos de píxel a los que se puede convertir el framebuffer. El
+// framebuffer guarda colores sin empaquetar, así cada uno pide el formato que
+// espera en lugar de reinterpretar valores u32 empaquetados.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    // Un u32 por píxel como 0x00RRGGBB (lo que espera minifb), con el orden
+    // de bytes de la máquina al escribirlo como bytes
+    Argb32,
+    // Cuatro bytes por píxel en orden R, G, B, A (PNG, canvas de wasm)
+    Rgba8,
+    // Tres bytes por píxel en orden R, G, B (PNG sin alfa)
+    Rgb8,
+}
+
+impl PixelFormat {
+    pub fn bytes_per_pixel(self) -> usize {
+        match self {
+            PixelFormat::Argb32 | PixelFormat::Rgba8 => 4,
+            PixelFormat::Rgb8 => 3,
+        }
+    }
+
+    // Los formatos sin canal alfa descartan `alpha`
+    pub fn encode(self, color: Color, alpha: u8, out: &mut Vec<u8>) {
+        match self {
+            PixelFormat::Argb32 => out.extend_from_slice(&color.to_hex().to_ne_bytes()),
+            PixelFormat::Rgba8 => out.extend_from_slice(&[color.red(), color.green(), color.blue(), alpha]),
+            PixelFormat::Rgb8 => out.extend_from_slice(&[color.red(), color.green(), color.blue()]),
+        }
+    }
+
+    // Lee un píxel y su alfa (opaco en los formatos sin alfa); `bytes` tiene
+    // que tener al menos `bytes_per_pixel` valores
+    pub fn decode(self, bytes: &[u8]) -> (Color, u8) {
+        match self {
+            PixelFormat::Argb32 => (Color::from_hex(u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])), 255),
+            PixelFormat::Rgba8 => (Color::new(bytes[0], bytes[1], bytes[2]), bytes[3]),
+            PixelFormat::Rgb8 => (Color::new(bytes[0], bytes[1], bytes[2]), 255),
+        }
+    }
+}
+
+// `alpha` guarda cuánto cubre la geometría cada píxel: 0 donde el rayo
+// primario no impactó nada y 255 donde impactó un objeto. El alfa es directo
+// (no premultiplicado): `buffer` guarda el color sombreado entero aun en los
+// píxeles cubiertos a medias, así las exportaciones RGBA se componen con la
+// mezcla de siempre, `src * a + dst * (1 - a)`. `depth` es la distancia del
+// ojo al impacto primario de cada píxel (infinita donde no impactó nada),
+// así lo que se dibuja encima en coordenadas del mundo queda tapado por la
+// geometría.
 pub struct Framebuffer {
     pub width: usize,
     pub height: usize,
-    pub buffer: Vec<u32>,
-    background_color: u32,
-    current_color: u32,
+    pub buffer: Vec<Color>,
+    pub alpha: Vec<u8>,
+    pub depth: Vec<f32>,
+    background_color: Color,
+    current_color: Color,
 }
 
 impl Framebuffer {
@@ -11,12 +65,24 @@ impl Framebuffer {
         Framebuffer {
             width,
             height,
-            buffer: vec![0; width * height],
-            background_color: 0x000000,
-            current_color: 0xFFFFFF,
+            buffer: vec![Color::black(); width * height],
+            alpha: vec![255; width * height],
+            depth: vec![f32::INFINITY; width * height],
+            background_color: Color::black(),
+            current_color: Color::white(),
         }
     }
 
+    // Rearma un framebuffer a partir de píxeles en el formato dado
+    pub fn from_pixels(width: usize, height: usize, bytes: &[u8], format: PixelFormat) -> Self {
+        let mut framebuffer = Framebuffer::new(width, height);
+        let pixels = framebuffer.buffer.iter_mut().zip(framebuffer.alpha.iter_mut());
+        for ((pixel, alpha), chunk) in pixels.zip(bytes.chunks_exact(format.bytes_per_pixel())) {
+            (*pixel, *alpha) = format.decode(chunk);
+        }
+        framebuffer
+    }
+
     pub fn clear(&mut self) {
         for pixel in self.buffer.iter_mut() {
             *pixel = self.background_color;
@@ -29,11 +95,149 @@ impl Framebuffer {
         }
     }
 
-    pub fn set_background_color(&mut self, color: u32) {
+    pub fn set_alpha(&mut self, x: usize, y: usize, alpha: u8) {
+        if x < self.width && y < self.height {
+            self.alpha[y * self.width + x] = alpha;
+        }
+    }
+
+    pub fn get_pixel(&self, x: usize, y: usize) -> Color {
+        self.buffer[y * self.width + x]
+    }
+
+    // Reduce por un factor entero promediando cada bloque; resuelve los
+    // renders sobremuestreados. Como el sobremuestreo por píxel del renderer,
+    // los colores se promedian en luz lineal. Con el fondo `transparent`
+    // además se pesan por su alfa, así las muestras de cielo que quedan
+    // detrás no se meten en los bordes de lo impactado; un render opaco
+    // muestra su cielo, así que ahí todas las muestras cuentan igual. El alfa
+    // es el promedio simple (la cobertura) y los colores quedan directos, sin
+    // premultiplicar
+    pub fn downsample(&self, factor: usize, transparent: bool) -> Framebuffer {
+        let width = self.width / factor;
+        let height = self.height / factor;
+        let mut result = Framebuffer::new(width, height);
+        let samples = (factor * factor) as f32;
+
+        for y in 0..height {
+            for x in 0..width {
+                let (mut color, mut weight, mut alpha) = ([0.0f32; 3], 0.0, 0.0);
+                let mut depth = f32::INFINITY;
+                for sy in 0..factor {
+                    for sx in 0..factor {
+                        let index = (y * factor + sy) * self.width + x * factor + sx;
+                        let pixel = self.buffer[index];
+                        let coverage = if transparent { self.alpha[index] as f32 / 255.0 } else { 1.0 };
+                        for (sum, channel) in color.iter_mut().zip([pixel.red(), pixel.green(), pixel.blue()]) {
+                            *sum += srgb_to_linear(channel) * coverage;
+                        }
+                        weight += coverage;
+                        alpha += self.alpha[index] as f32;
+                        depth = depth.min(self.depth[index]);
+                    }
+                }
+                let [r, g, b] = color.map(|sum| linear_to_srgb(if weight > 0.0 { sum / weight } else { 0.0 }));
+                let index = y * width + x;
+                result.buffer[index] = Color::new(r, g, b);
+                result.alpha[index] = (alpha / samples).round() as u8;
+                result.depth[index] = depth;
+            }
+        }
+
+        result
+    }
+
+    // Estira colores, cobertura y profundidad al vecino más cercano sobre
+    // `target`, que conserva su tamaño; para los renders a escala reducida
+    pub fn upscale_into(&self, target: &mut Framebuffer) {
+        for y in 0..target.height {
+            let source_y = y * self.height / target.height;
+            for x in 0..target.width {
+                let source = source_y * self.width + x * self.width / target.width;
+                let index = y * target.width + x;
+                target.buffer[index] = self.buffer[source];
+                target.alpha[index] = self.alpha[source];
+                target.depth[index] = self.depth[source];
+            }
+        }
+    }
+
+    // Copia del rectángulo de `width` x `height` cuyo píxel de arriba a la
+    // izquierda es (`x`, `y`), que tiene que caer dentro del framebuffer
+    pub fn region(&self, x: usize, y: usize, width: usize, height: usize) -> Framebuffer {
+        let mut result = Framebuffer::new(width, height);
+        for row in 0..height {
+            let source = (y + row) * self.width + x;
+            let target = row * width;
+            result.buffer[target..target + width].copy_from_slice(&self.buffer[source..source + width]);
+            result.alpha[target..target + width].copy_from_slice(&self.alpha[source..source + width]);
+            result.depth[target..target + width].copy_from_slice(&self.depth[source..source + width]);
+        }
+        result
+    }
+
+    // Copia `source` con su píxel de arriba a la izquierda en (`x`, `y`); lo
+    // que cae afuera se descarta
+    pub fn paste(&mut self, source: &Framebuffer, x: usize, y: usize) {
+        let width = source.width.min(self.width.saturating_sub(x));
+        for row in 0..source.height.min(self.height.saturating_sub(y)) {
+            let from = row * source.width;
+            let to = (y + row) * self.width + x;
+            self.buffer[to..to + width].copy_from_slice(&source.buffer[from..from + width]);
+            self.alpha[to..to + width].copy_from_slice(&source.alpha[from..from + width]);
+            self.depth[to..to + width].copy_from_slice(&source.depth[from..from + width]);
+        }
+    }
+
+    pub fn set_background_color(&mut self, color: Color) {
         self.background_color = color;
     }
 
-    pub fn set_current_color(&mut self, color: u32) {
+    pub fn set_current_color(&mut self, color: Color) {
         self.current_color = color;
     }
-}
\ No newline at end of file
+
+    // Valores 0x00RRGGBB empaquetados para mostrar con minifb
+    pub fn to_argb_u32(&self) -> Vec<u32> {
+        self.buffer.iter().map(|color| color.to_hex()).collect()
+    }
+
+    pub fn to_rgba_bytes(&self) -> Vec<u8> {
+        self.to_bytes(PixelFormat::Rgba8)
+    }
+
+    pub fn to_rgb_bytes(&self) -> Vec<u8> {
+        self.to_bytes(PixelFormat::Rgb8)
+    }
+
+    pub fn to_bytes(&self, format: PixelFormat) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.buffer.len() * format.bytes_per_pixel());
+        for (&color, &alpha) in self.buffer.iter().zip(&self.alpha) {
+            format.encode(color, alpha, &mut bytes);
+        }
+        bytes
+    }
+
+    // Guarda el framebuffer como PNG RGB. Los bytes salen en orden R, G, B
+    // explícito, así el resultado no depende del orden de bytes de la máquina.
+    pub fn save_png(&self, path: &str) -> Result<(), image::ImageError> {
+        image::save_buffer(
+            path,
+            &self.to_rgb_bytes(),
+            self.width as u32,
+            self.height as u32,
+            image::ExtendedColorType::Rgb8,
+        )
+    }
+
+    // Como `save_png`, pero guarda la cobertura como canal alfa directo
+    pub fn save_png_rgba(&self, path: &str) -> Result<(), image::ImageError> {
+        image::save_buffer(
+            path,
+            &self.to_rgba_bytes(),
+            self.width as u32,
+            self.height as u32,
+            image::ExtendedColorType::Rgba8,
+        )
+    }
+}
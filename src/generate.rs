@@ -0,0 +1,277 @@
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+
+use crate::api::DioramaBuilder;
+use crate::scene::Scene;
+
+// Generador de dioramas al azar, para probar el rendimiento con escenas de
+// distinto tamaño: una base de césped con relieve de ruido, un estanque de
+// lava, un portal, unas gradas y montones de rocas, todo con los materiales
+// del registro. La misma semilla con los mismos parámetros da siempre la misma
+// escena, así que alcanza con guardar ambos en el archivo de escena.
+
+// Lado de una celda de la cuadrícula y alto de un escalón del relieve
+const CELL: f32 = 0.125;
+// Celdas entre los puntos de la cuadrícula del ruido
+const NOISE_SPACING: f32 = 6.0;
+// Intentos para ubicar cada pieza antes de darse por vencido
+const PLACEMENT_TRIES: usize = 64;
+
+// Parámetros del generador, en el mismo formato que el bloque `generate` de un
+// archivo de escena
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GenParams {
+    // Celdas por lado de la base
+    pub size: usize,
+    // Altura máxima del relieve en escalones
+    pub relief: usize,
+    // Probabilidad de que haya un estanque de lava
+    pub lava_chance: f32,
+    pub rock_piles: usize,
+}
+
+impl Default for GenParams {
+    fn default() -> Self {
+        GenParams {
+            size: 24,
+            relief: 4,
+            lava_chance: 0.75,
+            rock_piles: 6,
+        }
+    }
+}
+
+impl GenParams {
+    // Bloque para pegar en un archivo de escena y volver a obtener esta escena
+    pub fn scene_entry(&self, seed: u64) -> String {
+        let params = ron::to_string(self).expect("los parámetros se pueden escribir en RON");
+        format!("generate: Some((seed: {}, params: {}))", seed, params)
+    }
+}
+
+// SplitMix64: rápido, sin dependencias y con la misma secuencia en cualquier
+// plataforma
+struct Rng(u64);
+
+impl Rng {
+    fn next_u64(&mut self) -> u64 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        z ^ (z >> 31)
+    }
+
+    // De 0 a 1 sin incluir el 1
+    fn unit(&mut self) -> f32 {
+        (self.next_u64() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    // Entero en `low..high`
+    fn range(&mut self, low: usize, high: usize) -> usize {
+        low + (self.next_u64() % (high - low).max(1) as u64) as usize
+    }
+}
+
+// Ruido de valores: alturas al azar en una cuadrícula gruesa interpoladas
+// suavemente entre sus puntos
+struct ValueNoise {
+    width: usize,
+    values: Vec<f32>,
+}
+
+impl ValueNoise {
+    fn new(rng: &mut Rng, size: usize) -> Self {
+        let width = (size as f32 / NOISE_SPACING).ceil() as usize + 2;
+        ValueNoise { width, values: (0..width * width).map(|_| rng.unit()).collect() }
+    }
+
+    fn at(&self, x: f32, z: f32) -> f32 {
+        let (gx, gz) = (x / NOISE_SPACING, z / NOISE_SPACING);
+        let (x0, z0) = (gx.floor() as usize, gz.floor() as usize);
+        let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+        let (tx, tz) = (smooth(gx.fract()), smooth(gz.fract()));
+        let value = |x: usize, z: usize| self.values[z * self.width + x];
+        let top = value(x0, z0) + (value(x0 + 1, z0) - value(x0, z0)) * tx;
+        let bottom = value(x0, z0 + 1) + (value(x0 + 1, z0 + 1) - value(x0, z0 + 1)) * tx;
+        top + (bottom - top) * tz
+    }
+}
+
+// Contenido de cada celda de la base mientras se arma la escena
+struct Grid {
+    size: usize,
+    // Escalones de césped de cada columna
+    heights: Vec<usize>,
+    lava: Vec<bool>,
+    // Celdas ya ocupadas por una pieza
+    taken: Vec<bool>,
+}
+
+impl Grid {
+    fn index(&self, x: usize, z: usize) -> usize {
+        z * self.size + x
+    }
+
+    // Rectángulo de `width` x `depth` celdas libre y sin lava, o None si se sale
+    fn free(&self, x: usize, z: usize, width: usize, depth: usize) -> Option<Vec<usize>> {
+        if x + width > self.size || z + depth > self.size {
+            return None;
+        }
+        let cells: Vec<usize> = (z..z + depth).flat_map(|z| (x..x + width).map(move |x| z * self.size + x)).collect();
+        cells.iter().all(|&cell| !self.taken[cell] && !self.lava[cell]).then_some(cells)
+    }
+
+    // Busca un rectángulo libre, lo aplana a la altura de su columna más alta
+    // y lo marca como ocupado. Devuelve su esquina y esa altura
+    fn claim(&mut self, rng: &mut Rng, width: usize, depth: usize) -> Option<(usize, usize, usize)> {
+        for _ in 0..PLACEMENT_TRIES {
+            let (x, z) = (rng.range(0, self.size), rng.range(0, self.size));
+            let Some(cells) = self.free(x, z, width, depth) else {
+                continue;
+            };
+            let level = cells.iter().map(|&cell| self.heights[cell]).max().unwrap_or(1);
+            for cell in cells {
+                self.heights[cell] = level;
+                self.taken[cell] = true;
+            }
+            return Some((x, z, level));
+        }
+        None
+    }
+
+    // Esquina mínima de una celda a la altura de `level` escalones
+    fn corner(&self, x: usize, z: usize, level: f32) -> Vec3 {
+        let half = self.size as f32 * CELL * 0.5;
+        Vec3::new(x as f32 * CELL - half, level * CELL, z as f32 * CELL - half)
+    }
+}
+
+// Diorama al azar para la semilla dada
+pub fn generate(seed: u64, params: &GenParams) -> Scene {
+    let size = params.size.max(8);
+    let relief = params.relief.max(1);
+    let mut rng = Rng(seed);
+    let noise = ValueNoise::new(&mut rng, size);
+
+    let mut grid = Grid {
+        size,
+        heights: Vec::with_capacity(size * size),
+        lava: vec![false; size * size],
+        taken: vec![false; size * size],
+    };
+    for z in 0..size {
+        for x in 0..size {
+            grid.heights.push(1 + (noise.at(x as f32, z as f32) * relief as f32) as usize);
+        }
+    }
+
+    // El estanque es un círculo hundido a la altura de su columna más baja
+    if rng.unit() < params.lava_chance {
+        let radius = rng.range(2, (size / 6).max(3)) as f32;
+        let (cx, cz) = (rng.range(0, size) as f32, rng.range(0, size) as f32);
+        let inside: Vec<usize> = (0..size * size)
+            .filter(|&cell| {
+                let (x, z) = ((cell % size) as f32, (cell / size) as f32);
+                (x - cx).powi(2) + (z - cz).powi(2) <= radius * radius
+            })
+            .collect();
+        let floor = inside.iter().map(|&cell| grid.heights[cell]).min().unwrap_or(1);
+        for cell in inside {
+            grid.heights[cell] = floor;
+            grid.lava[cell] = true;
+        }
+    }
+
+    let mut builder = DioramaBuilder::new();
+
+    // Portal de 4 x 5 celdas de frente y una de fondo, a lo largo de X o de Z
+    let along_x = rng.unit() < 0.5;
+    let (width, depth) = if along_x { (4, 1) } else { (1, 4) };
+    if let Some((x, z, level)) = grid.claim(&mut rng, width, depth) {
+        let origin = grid.corner(x, z, level as f32);
+        let block = |from: (f32, f32), to: (f32, f32)| {
+            let (a, b) = if along_x {
+                (Vec3::new(from.0, from.1, 0.0), Vec3::new(to.0, to.1, 1.0))
+            } else {
+                (Vec3::new(0.0, from.1, from.0), Vec3::new(1.0, to.1, to.0))
+            };
+            (origin + a * CELL, origin + b * CELL)
+        };
+        for (from, to, material) in [
+            ((0.0, 0.0), (4.0, 1.0), "obsidian"),
+            ((0.0, 4.0), (4.0, 5.0), "obsidian"),
+            ((0.0, 1.0), (1.0, 4.0), "obsidian"),
+            ((3.0, 1.0), (4.0, 4.0), "obsidian"),
+            ((1.0, 1.0), (3.0, 4.0), "purple"),
+        ] {
+            let (min, max) = block(from, to);
+            builder = builder.add_cube_in(min, max, material, "portal");
+        }
+    }
+
+    // Gradas que suben hacia el centro de la base, cada escalón sobre el anterior
+    let steps = rng.range(3, 6);
+    let facing = rng.range(0, 4);
+    let (width, depth) = if facing < 2 { (3, steps) } else { (steps, 3) };
+    if let Some((x, z, level)) = grid.claim(&mut rng, width, depth) {
+        for step in 0..steps {
+            // Celdas de la huella que cubre este escalón, a lo largo del recorrido
+            let (from, to) = if facing.is_multiple_of(2) { (step, steps) } else { (0, steps - step) };
+            let (min, max) = if facing < 2 {
+                (grid.corner(x, z + from, level as f32), grid.corner(x + 3, z + to, 0.0))
+            } else {
+                (grid.corner(x + from, z, level as f32), grid.corner(x + to, z + 3, 0.0))
+            };
+            let top = (level as f32 + (step + 1) as f32 * 0.5) * CELL;
+            builder = builder.add_cube_in(min, Vec3::new(max.x, top, max.z), "stone", "gradas");
+        }
+    }
+
+    // Montones de rocas: cada piedra más chica que la de abajo
+    for _ in 0..params.rock_piles {
+        let Some((x, z, level)) = grid.claim(&mut rng, 1, 1) else {
+            break;
+        };
+        let center = grid.corner(x, z, level as f32) + Vec3::new(CELL * 0.5, 0.0, CELL * 0.5);
+        let mut bottom = center.y;
+        let mut half = CELL * (0.35 + rng.unit() * 0.15);
+        for _ in 0..rng.range(1, 4) {
+            let height = half * (1.0 + rng.unit());
+            let offset = Vec3::new((rng.unit() - 0.5) * half * 0.5, 0.0, (rng.unit() - 0.5) * half * 0.5);
+            let min = Vec3::new(center.x - half, bottom, center.z - half) + offset;
+            let max = Vec3::new(center.x + half, bottom + height, center.z + half) + offset;
+            builder = builder.add_cube_in(min, max, "rock", "rocas");
+            bottom += height;
+            half *= 0.7;
+        }
+    }
+
+    // Base: una caja por tramo de columnas contiguas de la misma altura en cada
+    // fila, con la lava encima del césped en las celdas del estanque
+    for z in 0..size {
+        let mut x = 0;
+        while x < size {
+            let cell = grid.index(x, z);
+            let same = |other: usize| grid.heights[other] == grid.heights[cell] && grid.lava[other] == grid.lava[cell];
+            let mut end = x + 1;
+            while end < size && same(grid.index(end, z)) {
+                end += 1;
+            }
+            let level = grid.heights[cell] as f32;
+            let (min, max) = (grid.corner(x, z, -2.0), grid.corner(end, z + 1, level));
+            builder = builder.add_cube_in(min, max, "grass", "base");
+            if grid.lava[cell] {
+                builder = builder.add_cube_in(grid.corner(x, z, level), grid.corner(end, z + 1, level + 0.75), "lava", "base");
+            }
+            x = end;
+        }
+    }
+
+    let extent = size as f32 * CELL;
+    builder
+        .camera(Vec3::new(0.0, extent * 0.6, extent * 1.6), Vec3::new(0.0, relief as f32 * CELL * 0.5, 0.0))
+        .build()
+        .expect("el generador solo usa materiales del registro")
+}
@@ -0,0 +1,210 @@
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::color::{Color, Palette};
+use crate::framebuffer::Framebuffer;
+use crate::light::{LightKind, SceneLight};
+use crate::overlay;
+
+// Guía de orientación para armar escenas: los ejes del mundo desde el origen
+// (X rojo, Y verde, Z azul) y una cuadrícula sobre el plano y = 0. Se dibuja
+// encima del render en espacio de pantalla, así no proyecta sombras, y cada
+// píxel de línea se compara con la profundidad del impacto primario para
+// quedar oculto detrás de la geometría.
+#[derive(Debug, Clone)]
+pub struct Gizmo {
+    // Separación entre líneas de la cuadrícula
+    pub spacing: f32,
+    // Líneas a cada lado del origen; también fija el largo de los ejes
+    pub cells: usize,
+    // Distancia a la cámara a la que las líneas terminan de desvanecerse
+    pub fade_distance: f32,
+}
+
+impl Default for Gizmo {
+    fn default() -> Self {
+        Gizmo {
+            spacing: 1.0,
+            cells: 10,
+            fade_distance: 20.0,
+        }
+    }
+}
+
+const NEAR_PLANE: f32 = 0.01;
+const GRID_COLOR: Color = Color::new(200, 200, 200);
+const GRID_ALPHA: f32 = 0.4;
+const AXIS_ALPHA: f32 = 0.9;
+// Margen relativo de la prueba de profundidad, para que la cuadrícula se vea
+// sobre las caras que están justo en y = 0
+const DEPTH_SLACK: f32 = 1e-3;
+// Medio lado de la cruz de una luz con intensidad 1, en unidades de la escena
+const LIGHT_MARKER: f32 = 0.08;
+// Largo de la flecha de una luz direccional
+const DIRECTION_LENGTH: f32 = 0.5;
+const SELECTED_LIGHT_COLOR: Color = Color::new(255, 230, 0);
+const PATH_COLOR: Color = Color::new(255, 140, 0);
+
+impl Gizmo {
+    pub fn draw(&self, framebuffer: &mut Framebuffer, camera: &Camera, perspective_scale: f32, palette: Palette) {
+        let extent = self.spacing * self.cells as f32;
+        let cells = self.cells as i32;
+        for i in -cells..=cells {
+            let offset = i as f32 * self.spacing;
+            let lines = [
+                (Vec3::new(-extent, 0.0, offset), Vec3::new(extent, 0.0, offset)),
+                (Vec3::new(offset, 0.0, -extent), Vec3::new(offset, 0.0, extent)),
+            ];
+            for (start, end) in lines {
+                self.draw_segment(framebuffer, camera, perspective_scale, start, end, GRID_COLOR, GRID_ALPHA);
+            }
+        }
+
+        // Los ejes van encima de la cuadrícula
+        let axes = [
+            (Vec3::new(1.0, 0.0, 0.0), palette.category(5, Color::new(255, 60, 60))),
+            (Vec3::new(0.0, 1.0, 0.0), palette.category(2, Color::new(60, 255, 60))),
+            (Vec3::new(0.0, 0.0, 1.0), palette.category(4, Color::new(80, 120, 255))),
+        ];
+        for (axis, color) in axes {
+            self.draw_segment(framebuffer, camera, perspective_scale, Vec3::zeros(), axis * extent, color, AXIS_ALPHA);
+        }
+    }
+
+    // Marcador de una luz para el modo de edición, encima de todo porque las
+    // luces suelen quedar dentro o detrás de la geometría: una cruz de su color
+    // que crece con la intensidad, la dirección de las direccionales, el cono
+    // de los focos y un recuadro alrededor de la seleccionada
+    pub fn draw_light(
+        &self,
+        framebuffer: &mut Framebuffer,
+        camera: &Camera,
+        perspective_scale: f32,
+        light: &SceneLight,
+        selected: bool,
+        palette: Palette,
+    ) {
+        let highlight = palette.category(3, SELECTED_LIGHT_COLOR);
+        let mut line = |start: Vec3, end: Vec3, color: Color| {
+            if let Some((a, b)) = clip_to_screen(framebuffer, camera, perspective_scale, start, end) {
+                overlay::draw_line(framebuffer, a, b, color);
+            }
+        };
+        let size = LIGHT_MARKER * light.intensity.max(0.0).sqrt().clamp(0.5, 3.0);
+        for axis in [Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)] {
+            line(light.position - axis * size, light.position + axis * size, light.color);
+        }
+
+        let aim = (light.target - light.position).try_normalize(1e-6);
+        let color = if selected { highlight } else { GRID_COLOR };
+        match (light.kind, aim) {
+            (LightKind::Directional, Some(aim)) => {
+                let tip = light.position + aim * DIRECTION_LENGTH;
+                line(light.position, tip, color);
+                // Rayos paralelos a los lados de la flecha
+                let side = aim.cross(&Vec3::new(0.0, 1.0, 0.0)).try_normalize(1e-6).unwrap_or(Vec3::new(1.0, 0.0, 0.0)) * size;
+                line(light.position + side, tip + side, color);
+                line(light.position - side, tip - side, color);
+            }
+            (LightKind::Spot { angle }, Some(aim)) => {
+                let length = (light.target - light.position).magnitude();
+                let radius = length * angle.to_radians().tan().min(10.0);
+                let side = aim.cross(&Vec3::new(0.0, 1.0, 0.0)).try_normalize(1e-6).unwrap_or(Vec3::new(1.0, 0.0, 0.0));
+                let up = side.cross(&aim);
+                for edge in [side, -side, up, -up] {
+                    line(light.position, light.target + edge * radius, color);
+                }
+            }
+            _ => {}
+        }
+
+        if selected {
+            let aspect_ratio = framebuffer.width as f32 / framebuffer.height as f32;
+            if let Some((x, y)) = camera.project(&light.position, aspect_ratio, perspective_scale) {
+                let (x, y) = ((x + 1.0) * 0.5 * framebuffer.width as f32, (1.0 - y) * 0.5 * framebuffer.height as f32);
+                let r = 6.0;
+                for (from, to) in [((-r, -r), (r, -r)), ((r, -r), (r, r)), ((r, r), (-r, r)), ((-r, r), (-r, -r))] {
+                    overlay::draw_line(framebuffer, (x + from.0, y + from.1), (x + to.0, y + to.1), highlight);
+                }
+            }
+        }
+    }
+
+    // Recorrido de cámara como una línea quebrada por `path`, con una cruz en
+    // cada pose de `keys`; como la cuadrícula, queda oculto detrás de la geometría
+    pub fn draw_path(&self, framebuffer: &mut Framebuffer, camera: &Camera, perspective_scale: f32, path: &[Vec3], keys: &[Vec3], palette: Palette) {
+        let color = palette.category(1, PATH_COLOR);
+        for pair in path.windows(2) {
+            self.draw_segment(framebuffer, camera, perspective_scale, pair[0], pair[1], color, AXIS_ALPHA);
+        }
+        for key in keys {
+            for axis in [Vec3::new(1.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0), Vec3::new(0.0, 0.0, 1.0)] {
+                self.draw_segment(framebuffer, camera, perspective_scale, key - axis * LIGHT_MARKER, key + axis * LIGHT_MARKER, color, AXIS_ALPHA);
+            }
+        }
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn draw_segment(
+        &self,
+        framebuffer: &mut Framebuffer,
+        camera: &Camera,
+        perspective_scale: f32,
+        start: Vec3,
+        end: Vec3,
+        color: Color,
+        alpha: f32,
+    ) {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let (_, _, forward) = camera.basis();
+        let Some((start, end)) = clip_near(camera, start, end) else {
+            return;
+        };
+        let Some((a, b)) = clip_to_screen(framebuffer, camera, perspective_scale, start, end) else {
+            return;
+        };
+
+        let near = (start - camera.eye).dot(&forward);
+        let far = (end - camera.eye).dot(&forward);
+        overlay::line_pixels(width, height, a, b, |x, y, t| {
+            // En pantalla lo que varía linealmente es la inversa de la profundidad
+            let s = (t / far) / ((1.0 - t) / near + t / far);
+            let point = start + (end - start) * s;
+            let distance = (point - camera.eye).magnitude();
+            if distance > framebuffer.depth[y * width + x] * (1.0 + DEPTH_SLACK) {
+                return;
+            }
+            let fade = (1.0 - distance / self.fade_distance).clamp(0.0, 1.0);
+            overlay::blend_pixel(framebuffer, x, y, color, alpha * fade);
+        });
+    }
+}
+
+// Recorta el tramo contra el plano cercano de la cámara
+fn clip_near(camera: &Camera, start: Vec3, end: Vec3) -> Option<(Vec3, Vec3)> {
+    let (_, _, forward) = camera.basis();
+    let depth_start = (start - camera.eye).dot(&forward) - NEAR_PLANE;
+    let depth_end = (end - camera.eye).dot(&forward) - NEAR_PLANE;
+    if depth_start <= 0.0 && depth_end <= 0.0 {
+        return None;
+    }
+    let cut = start + (end - start) * (depth_start / (depth_start - depth_end));
+    Some((if depth_start > 0.0 { start } else { cut }, if depth_end > 0.0 { end } else { cut }))
+}
+
+// Extremos en píxeles del tramo recortado contra el plano cercano
+fn clip_to_screen(
+    framebuffer: &Framebuffer,
+    camera: &Camera,
+    perspective_scale: f32,
+    start: Vec3,
+    end: Vec3,
+) -> Option<((f32, f32), (f32, f32))> {
+    let (width, height) = (framebuffer.width as f32, framebuffer.height as f32);
+    let (start, end) = clip_near(camera, start, end)?;
+    let to_screen = |point: &Vec3| {
+        let (screen_x, screen_y) = camera.project(point, width / height, perspective_scale)?;
+        Some(((screen_x + 1.0) * 0.5 * width, (1.0 - screen_y) * 0.5 * height))
+    };
+    Some((to_screen(&start)?, to_screen(&end)?))
+}
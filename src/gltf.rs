@@ -0,0 +1,130 @@
+use std::fmt::Write as _;
+use std::io;
+
+use crate::bake::BakedFace;
+use crate::color::srgb_to_linear;
+
+// Exportación a glTF binario (.glb). Todas las caras van en una sola malla de
+// triángulos con el color horneado como color de vértice; las cuatro esquinas
+// de una cara comparten color, así cada cubo queda en 24 vértices y 12
+// triángulos. El material es sin iluminación (KHR_materials_unlit) para que
+// el visor muestre la luz horneada tal cual en lugar de volver a iluminarla.
+
+const GLB_MAGIC: u32 = 0x4654_6c67; // "glTF"
+const CHUNK_JSON: u32 = 0x4e4f_534a;
+const CHUNK_BIN: u32 = 0x004e_4942;
+const TARGET_ARRAY_BUFFER: u32 = 34962;
+const TARGET_ELEMENT_ARRAY_BUFFER: u32 = 34963;
+const COMPONENT_FLOAT: u32 = 5126;
+const COMPONENT_UNSIGNED_INT: u32 = 5125;
+
+fn push_f32s(buffer: &mut Vec<u8>, values: &[f32]) {
+    for value in values {
+        buffer.extend_from_slice(&value.to_le_bytes());
+    }
+}
+
+fn padded(mut bytes: Vec<u8>, fill: u8) -> Vec<u8> {
+    while !bytes.len().is_multiple_of(4) {
+        bytes.push(fill);
+    }
+    bytes
+}
+
+// Archivo .glb completo con las caras dadas
+pub fn glb_bytes(faces: &[BakedFace]) -> Vec<u8> {
+    let vertex_count = faces.len() * 4;
+    let mut positions = Vec::with_capacity(vertex_count * 12);
+    let mut normals = Vec::with_capacity(vertex_count * 12);
+    let mut colors = Vec::with_capacity(vertex_count * 16);
+    let mut indices = Vec::with_capacity(faces.len() * 24);
+    let mut min = [f32::INFINITY; 3];
+    let mut max = [f32::NEG_INFINITY; 3];
+
+    for (index, face) in faces.iter().enumerate() {
+        // Los colores de vértice de glTF son lineales y los del render están en sRGB
+        let color = [
+            srgb_to_linear(face.color.red()),
+            srgb_to_linear(face.color.green()),
+            srgb_to_linear(face.color.blue()),
+            face.alpha,
+        ];
+        for corner in &face.corners {
+            push_f32s(&mut positions, &[corner.x, corner.y, corner.z]);
+            push_f32s(&mut normals, &[face.normal.x, face.normal.y, face.normal.z]);
+            push_f32s(&mut colors, &color);
+            for axis in 0..3 {
+                min[axis] = min[axis].min(corner[axis]);
+                max[axis] = max[axis].max(corner[axis]);
+            }
+        }
+        let first = (index * 4) as u32;
+        for offset in [0, 1, 2, 0, 2, 3] {
+            indices.extend_from_slice(&(first + offset).to_le_bytes());
+        }
+    }
+
+    let translucent = faces.iter().any(|face| face.alpha < 1.0);
+    let views = [
+        (&positions, TARGET_ARRAY_BUFFER),
+        (&normals, TARGET_ARRAY_BUFFER),
+        (&colors, TARGET_ARRAY_BUFFER),
+        (&indices, TARGET_ELEMENT_ARRAY_BUFFER),
+    ];
+    let mut binary = Vec::new();
+    let mut buffer_views = Vec::new();
+    for (bytes, target) in views {
+        buffer_views.push(format!(
+            r#"{{"buffer":0,"byteOffset":{},"byteLength":{},"target":{}}}"#,
+            binary.len(),
+            bytes.len(),
+            target
+        ));
+        binary.extend_from_slice(bytes);
+    }
+
+    let mut json = String::new();
+    let _ = write!(
+        json,
+        concat!(
+            r#"{{"asset":{{"version":"2.0","generator":"Raycasting_diorama"}},"#,
+            r#""extensionsUsed":["KHR_materials_unlit"],"#,
+            r#""scene":0,"scenes":[{{"nodes":[0]}}],"nodes":[{{"mesh":0,"name":"diorama"}}],"#,
+            r#""meshes":[{{"primitives":[{{"attributes":{{"POSITION":0,"NORMAL":1,"COLOR_0":2}},"indices":3,"material":0}}]}}],"#,
+            r#""materials":[{{"pbrMetallicRoughness":{{"baseColorFactor":[1,1,1,1],"metallicFactor":0,"roughnessFactor":1}},"#,
+            r#""alphaMode":"{}","extensions":{{"KHR_materials_unlit":{{}}}}}}],"#,
+            r#""accessors":["#,
+            r#"{{"bufferView":0,"componentType":{},"count":{},"type":"VEC3","min":[{},{},{}],"max":[{},{},{}]}},"#,
+            r#"{{"bufferView":1,"componentType":{},"count":{},"type":"VEC3"}},"#,
+            r#"{{"bufferView":2,"componentType":{},"count":{},"type":"VEC4"}},"#,
+            r#"{{"bufferView":3,"componentType":{},"count":{},"type":"SCALAR"}}],"#,
+            r#""bufferViews":[{}],"buffers":[{{"byteLength":{}}}]}}"#,
+        ),
+        if translucent { "BLEND" } else { "OPAQUE" },
+        COMPONENT_FLOAT, vertex_count, min[0], min[1], min[2], max[0], max[1], max[2],
+        COMPONENT_FLOAT, vertex_count,
+        COMPONENT_FLOAT, vertex_count,
+        COMPONENT_UNSIGNED_INT, faces.len() * 6,
+        buffer_views.join(","),
+        binary.len(),
+    );
+
+    let json = padded(json.into_bytes(), b' ');
+    let binary = padded(binary, 0);
+    let total = 12 + 8 + json.len() + 8 + binary.len();
+
+    let mut glb = Vec::with_capacity(total);
+    for word in [GLB_MAGIC, 2, total as u32, json.len() as u32, CHUNK_JSON] {
+        glb.extend_from_slice(&word.to_le_bytes());
+    }
+    glb.extend_from_slice(&json);
+    for word in [binary.len() as u32, CHUNK_BIN] {
+        glb.extend_from_slice(&word.to_le_bytes());
+    }
+    glb.extend_from_slice(&binary);
+    glb
+}
+
+pub fn write_glb(path: &str, faces: &[BakedFace]) -> io::Result<()> {
+    std::fs::write(path, glb_bytes(faces))
+}
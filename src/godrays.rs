@@ -0,0 +1,127 @@
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::light::SceneLight;
+use crate::settings::GodRays;
+
+// Rayos de sol en espacio de pantalla: los píxeles donde el rayo primario
+// llegó al cielo cerca del sol forman una máscara, y cada píxel suma muestras
+// de esa máscara a lo largo de la línea hacia la posición del sol en pantalla,
+// cada una con menos peso que la anterior. Donde la geometría tapa el cielo,
+// como el marco del portal, la máscara se corta y quedan los haces que pasan
+// por la abertura. El resultado se tiñe con el color del sol y se suma al
+// framebuffer, así que no traza rayos y cuesta `samples` lecturas por cada
+// celda de `DOWNSAMPLE` x `DOWNSAMPLE` píxeles.
+
+// Elevación del sol, en radianes, a partir de la cual ya no hay rayos: son
+// más fuertes con el sol sobre el horizonte
+const FADE_ELEVATION: f32 = 0.6;
+// Bajo el horizonte se apagan en este ángulo en lugar de cortarse de golpe
+const BELOW_HORIZON: f32 = 0.1;
+// Fuera de la pantalla se apagan a lo largo de esta distancia, en fracciones
+// de la altura de la imagen
+const OFFSCREEN_FADE: f32 = 0.5;
+// Píxeles por lado de cada celda en la que se calculan los haces
+const DOWNSAMPLE: usize = 4;
+
+fn smoothstep(low: f32, high: f32, x: f32) -> f32 {
+    let t = ((x - low) / (high - low)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
+
+// Fuerza de los rayos según la altura del sol vista desde el centro de la órbita
+fn horizon_factor(sun: &SceneLight, camera: &Camera) -> f32 {
+    let (direction, _) = sun.toward(&camera.center);
+    let elevation = direction.y.clamp(-1.0, 1.0).asin();
+    smoothstep(-BELOW_HORIZON, 0.0, elevation) * (1.0 - smoothstep(0.0, FADE_ELEVATION, elevation))
+}
+
+pub fn apply_god_rays(framebuffer: &mut Framebuffer, camera: &Camera, lights: &[SceneLight], perspective_scale: f32, god_rays: &GodRays) {
+    let Some(sun) = lights.iter().find(|light| light.is_sun) else {
+        return;
+    };
+    let horizon = horizon_factor(sun, camera);
+    if horizon <= 0.0 || god_rays.samples == 0 {
+        return;
+    }
+
+    // Un sol direccional está infinitamente lejos en su dirección
+    let (direction, distance) = sun.toward(&camera.eye);
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let aspect_ratio = width as f32 / height as f32;
+    let Some((screen_x, screen_y)) = camera.project(&(camera.eye + direction * distance.min(1e4)), aspect_ratio, perspective_scale) else {
+        return;
+    };
+    // Posición del sol en píxeles, que puede caer fuera de la imagen
+    let sun_x = (screen_x + 1.0) * 0.5 * width as f32;
+    let sun_y = (1.0 - screen_y) * 0.5 * height as f32;
+    let outside = (sun_x.clamp(0.0, width as f32) - sun_x).hypot(sun_y.clamp(0.0, height as f32) - sun_y);
+    let offscreen = 1.0 - (outside / (OFFSCREEN_FADE * height as f32)).min(1.0);
+    let strength = god_rays.strength * horizon * offscreen * sun.intensity.min(1.0);
+    if strength <= 0.0 {
+        return;
+    }
+
+    // Los haces varían despacio, así que se calculan en una cuadrícula de
+    // `DOWNSAMPLE` píxeles por lado y se interpolan al sumarlos. La máscara de
+    // cada celda es la fracción de cielo que se ve en ella, con más peso
+    // cuanto más cerca del sol
+    let (grid_width, grid_height) = (width.div_ceil(DOWNSAMPLE), height.div_ceil(DOWNSAMPLE));
+    let cell = DOWNSAMPLE as f32;
+    let radius = god_rays.radius * height as f32;
+    let mut mask = vec![0.0; grid_width * grid_height];
+    for (index, depth) in framebuffer.depth.iter().enumerate() {
+        if depth.is_infinite() {
+            let (x, y) = (index % width, index / width);
+            mask[(y / DOWNSAMPLE) * grid_width + x / DOWNSAMPLE] += 1.0;
+        }
+    }
+    for (index, value) in mask.iter_mut().enumerate() {
+        let (x, y) = (((index % grid_width) as f32 + 0.5) * cell, ((index / grid_width) as f32 + 0.5) * cell);
+        let distance = (x - sun_x).hypot(y - sun_y) / radius;
+        *value *= (-distance * distance).exp() / (cell * cell);
+    }
+
+    let samples = god_rays.samples;
+    let (sun_u, sun_v) = (sun_x / cell, sun_y / cell);
+    let shafts: Vec<f32> = (0..grid_width * grid_height)
+        .into_par_iter()
+        .map(|index| {
+            let (x, y) = ((index % grid_width) as f32 + 0.5, (index / grid_width) as f32 + 0.5);
+            // Paso hacia el sol: las muestras cubren la fracción `length` del camino
+            let step = ((sun_u - x) * god_rays.length / samples as f32, (sun_v - y) * god_rays.length / samples as f32);
+            let (mut u, mut v) = (x, y);
+            let (mut weight, mut sum) = (1.0, 0.0);
+            for _ in 0..samples {
+                u += step.0;
+                v += step.1;
+                if u < 0.0 || v < 0.0 || u >= grid_width as f32 || v >= grid_height as f32 {
+                    break;
+                }
+                sum += mask[v as usize * grid_width + u as usize] * weight;
+                weight *= god_rays.decay;
+            }
+            sum / samples as f32
+        })
+        .collect();
+
+    // Interpolación bilineal entre los centros de las celdas
+    let shaft_at = |x: usize, y: usize| {
+        let u = ((x as f32 + 0.5) / cell - 0.5).clamp(0.0, (grid_width - 1) as f32);
+        let v = ((y as f32 + 0.5) / cell - 0.5).clamp(0.0, (grid_height - 1) as f32);
+        let (u0, v0) = (u as usize, v as usize);
+        let (u1, v1) = ((u0 + 1).min(grid_width - 1), (v0 + 1).min(grid_height - 1));
+        let (tu, tv) = (u.fract(), v.fract());
+        let row = |v: usize| shafts[v * grid_width + u0] * (1.0 - tu) + shafts[v * grid_width + u1] * tu;
+        row(v0) * (1.0 - tv) + row(v1) * tv
+    };
+
+    let tint = Vec3::new(sun.color.red() as f32, sun.color.green() as f32, sun.color.blue() as f32);
+    for (index, pixel) in framebuffer.buffer.iter_mut().enumerate() {
+        let added = tint * (shaft_at(index % width, index / width) * strength);
+        *pixel = *pixel + Color::new(added.x.min(255.0) as u8, added.y.min(255.0) as u8, added.z.min(255.0) as u8);
+    }
+}
@@ -0,0 +1,155 @@
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+
+use crate::color::Color;
+
+// Sombras y reflejos a media resolución. Una primera pasada traza el rayo
+// primario de un píxel de cada bloque de 2x2 y guarda la sombra de cada luz y
+// el color reflejado en ese impacto; al sombrear a resolución completa cada
+// píxel los toma de las muestras vecinas con pesos bilaterales, que descartan
+// las que están en otra cara o a otra profundidad. Donde las muestras válidas
+// no coinciden (el borde de una sombra o de un reflejo) el píxel se traza
+// completo, así los bordes duros siguen nítidos.
+
+// Diferencia relativa de profundidad a partir de la cual una muestra no cuenta
+const DEPTH_TOLERANCE: f32 = 0.05;
+// Coseno mínimo entre la normal del píxel y la de la muestra
+const MIN_NORMAL_DOT: f32 = 0.95;
+// Desacuerdo entre muestras a partir del cual se traza a resolución completa
+const SHADOW_EDGE: f32 = 0.2;
+const REFLECTION_EDGE: u8 = 32;
+
+// Lo que la primera pasada guarda de un impacto; las sombras van aparte
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Guide {
+    pub depth: f32,
+    pub normal: Vec3,
+    // None si el material no refleja o usa un reflejo planar
+    pub reflection: Option<Color>,
+}
+
+pub(crate) struct HalfResolution {
+    width: usize,
+    height: usize,
+    lights: usize,
+    guides: Vec<Option<Guide>>,
+    // Intensidad de sombra de cada luz por muestra; negativa si no se calculó
+    shadows: Vec<f32>,
+}
+
+impl HalfResolution {
+    // `sample(x, y, shadows)` traza el píxel (x, y) de resolución completa y
+    // llena la sombra de las luces que necesita
+    pub fn render<F>(width: usize, height: usize, lights: usize, sample: F) -> Self
+    where
+        F: Fn(usize, usize, &mut [f32]) -> Option<Guide> + Sync,
+    {
+        let (half_width, half_height) = (width.div_ceil(2), height.div_ceil(2));
+        let mut guides = vec![None; half_width * half_height];
+        let mut shadows = vec![-1.0; half_width * half_height * lights];
+
+        guides
+            .par_iter_mut()
+            .zip(shadows.par_chunks_mut(lights.max(1)))
+            .enumerate()
+            .for_each(|(index, (guide, shadows))| {
+                *guide = sample(2 * (index % half_width), 2 * (index / half_width), shadows);
+            });
+
+        HalfResolution {
+            width: half_width,
+            height: half_height,
+            lights,
+            guides,
+            shadows,
+        }
+    }
+
+    // Muestras vecinas del píxel (x, y) con sus pesos; None si ninguna ve la
+    // misma superficie
+    pub fn upsample(&self, x: usize, y: usize, depth: f32, normal: &Vec3) -> Option<Upsample<'_>> {
+        // Los píxeles pares coinciden con una muestra; los impares quedan a
+        // mitad de camino entre dos
+        let axis = |p: usize, size: usize| {
+            let low = p / 2;
+            if p % 2 == 1 && low + 1 < size {
+                [(low, 0.5), (low + 1, 0.5)]
+            } else {
+                [(low.min(size - 1), 1.0), (0, 0.0)]
+            }
+        };
+
+        let mut taps = [(0, 0.0); 4];
+        let mut total = 0.0;
+        for (i, ((tx, wx), (ty, wy))) in axis(x, self.width)
+            .into_iter()
+            .flat_map(|tx| axis(y, self.height).into_iter().map(move |ty| (tx, ty)))
+            .enumerate()
+        {
+            let index = ty * self.width + tx;
+            let Some(guide) = self.guides[index].filter(|_| wx * wy > 0.0) else {
+                continue;
+            };
+            let depth_error = (guide.depth - depth).abs() / (DEPTH_TOLERANCE * depth);
+            if guide.normal.dot(normal) < MIN_NORMAL_DOT || depth_error >= 1.0 {
+                continue;
+            }
+            let weight = wx * wy * (1.0 - depth_error);
+            taps[i] = (index, weight);
+            total += weight;
+        }
+
+        (total > 1e-4).then_some(Upsample { half: self, taps })
+    }
+}
+
+// Muestras de media resolución que aporta un píxel, sin normalizar
+pub(crate) struct Upsample<'a> {
+    half: &'a HalfResolution,
+    taps: [(usize, f32); 4],
+}
+
+impl Upsample<'_> {
+    // Sombra de la luz `light`, o None si hay que trazarla
+    pub fn shadow(&self, light: usize) -> Option<f32> {
+        let (mut sum, mut total) = (0.0, 0.0);
+        let (mut low, mut high) = (f32::INFINITY, f32::NEG_INFINITY);
+        for &(index, weight) in self.taps.iter().filter(|(_, weight)| *weight > 0.0) {
+            let shadow = self.half.shadows[index * self.half.lights + light];
+            if shadow < 0.0 {
+                return None;
+            }
+            sum += shadow * weight;
+            total += weight;
+            low = low.min(shadow);
+            high = high.max(shadow);
+        }
+        (total > 0.0 && high - low <= SHADOW_EDGE).then(|| sum / total)
+    }
+
+    // Color reflejado, o None si hay que trazarlo
+    pub fn reflection(&self) -> Option<Color> {
+        let mut sum = [0.0; 3];
+        let mut total = 0.0;
+        let mut first: Option<Color> = None;
+        for &(index, weight) in self.taps.iter().filter(|(_, weight)| *weight > 0.0) {
+            let color = self.half.guides[index]?.reflection?;
+            let reference = *first.get_or_insert(color);
+            let differs = |a: u8, b: u8| a.abs_diff(b) > REFLECTION_EDGE;
+            if differs(color.red(), reference.red())
+                || differs(color.green(), reference.green())
+                || differs(color.blue(), reference.blue())
+            {
+                return None;
+            }
+            for (channel, value) in sum.iter_mut().zip([color.red(), color.green(), color.blue()]) {
+                *channel += value as f32 * weight;
+            }
+            total += weight;
+        }
+        (total > 0.0).then(|| {
+            let [r, g, b] = sum.map(|channel| (channel / total).round() as u8);
+            Color::new(r, g, b)
+        })
+    }
+}
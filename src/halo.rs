@@ -0,0 +1,80 @@
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+// Halo en espacio de pantalla para los materiales con `halo > 0`. El pase
+// primario deja en `glow` el brillo de cada píxel; aquí se dilata para que un
+// emisor de un píxel de ancho también deje marca, se difumina y se suma al
+// framebuffer. Funciona aunque no haya bloom porque no depende del brillo final.
+pub fn apply_halo(framebuffer: &mut Framebuffer, glow: &[Color], radius: usize) {
+    if radius == 0 || glow.iter().all(|color| color.to_hex() == 0) {
+        return;
+    }
+
+    let (width, height) = (framebuffer.width, framebuffer.height);
+    let mut channels: Vec<[f32; 3]> = glow
+        .iter()
+        .map(|color| [color.red() as f32, color.green() as f32, color.blue() as f32])
+        .collect();
+
+    let dilate_radius = dilate_radius(radius);
+    channels = filter(&channels, width, height, dilate_radius, true, max_of);
+    channels = filter(&channels, width, height, dilate_radius, false, max_of);
+    channels = filter(&channels, width, height, radius, true, mean_of);
+    channels = filter(&channels, width, height, radius, false, mean_of);
+
+    for (pixel, [r, g, b]) in framebuffer.buffer.iter_mut().zip(channels) {
+        let halo = Color::new(r.min(255.0) as u8, g.min(255.0) as u8, b.min(255.0) as u8);
+        *pixel = *pixel + halo;
+    }
+}
+
+fn dilate_radius(radius: usize) -> usize {
+    (radius / 2).max(1)
+}
+
+// Hasta cuántos píxeles de un píxel con brillo llega su halo
+pub fn halo_reach(radius: usize) -> usize {
+    if radius == 0 {
+        0
+    } else {
+        radius + dilate_radius(radius)
+    }
+}
+
+fn max_of(window: &[[f32; 3]]) -> [f32; 3] {
+    window.iter().fold([0.0; 3], |acc, c| [acc[0].max(c[0]), acc[1].max(c[1]), acc[2].max(c[2])])
+}
+
+fn mean_of(window: &[[f32; 3]]) -> [f32; 3] {
+    let sum = window.iter().fold([0.0; 3], |acc, c| [acc[0] + c[0], acc[1] + c[1], acc[2] + c[2]]);
+    let n = window.len() as f32;
+    [sum[0] / n, sum[1] / n, sum[2] / n]
+}
+
+// Filtro separable de una pasada: horizontal o vertical, con la ventana
+// recortada a los bordes de la imagen
+fn filter(
+    input: &[[f32; 3]],
+    width: usize,
+    height: usize,
+    radius: usize,
+    horizontal: bool,
+    reduce: impl Fn(&[[f32; 3]]) -> [f32; 3],
+) -> Vec<[f32; 3]> {
+    let mut output = vec![[0.0; 3]; input.len()];
+    let mut window = Vec::with_capacity(2 * radius + 1);
+
+    for y in 0..height {
+        for x in 0..width {
+            window.clear();
+            let (center, limit) = if horizontal { (x, width) } else { (y, height) };
+            for i in center.saturating_sub(radius)..(center + radius + 1).min(limit) {
+                let index = if horizontal { y * width + i } else { i * width + x };
+                window.push(input[index]);
+            }
+            output[y * width + x] = reduce(&window);
+        }
+    }
+
+    output
+}
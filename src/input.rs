@@ -0,0 +1,84 @@
+// Estado de entrada entre frames. Cada backend (la ventana de minifb, una
+// sesión reproducida u otro que venga) solo dice qué está abajo en el frame, y
+// `update` lo compara con el frame anterior para sacar los eventos: `Pressed`
+// en el frame en que algo baja, `Held` en los siguientes mientras siga abajo y
+// `Released` en el frame en que sube. Así las acciones que se disparan una vez
+// por pulsación no se repiten mientras se mantiene la tecla, venga de donde
+// venga. Sirve para cualquier tipo que se pueda comparar: teclas, botones del
+// mouse o acciones ya traducidas.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputEvent<T> {
+    Pressed(T),
+    Held(T),
+    Released(T),
+}
+
+#[derive(Debug, Clone)]
+pub struct InputState<T> {
+    // Lo que estaba abajo al final del último `update`, sin repetidos
+    down: Vec<T>,
+    events: Vec<InputEvent<T>>,
+}
+
+impl<T> Default for InputState<T> {
+    fn default() -> Self {
+        InputState {
+            down: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+}
+
+impl<T: Copy + PartialEq> InputState<T> {
+    pub fn new() -> Self {
+        InputState::default()
+    }
+
+    // Pasa al frame siguiente con lo que está abajo ahora y devuelve sus
+    // eventos: primero los de lo que está abajo, en el orden dado, y después
+    // los de lo que se soltó
+    pub fn update(&mut self, down: impl IntoIterator<Item = T>) -> &[InputEvent<T>] {
+        let mut current: Vec<T> = Vec::new();
+        for input in down {
+            if !current.contains(&input) {
+                current.push(input);
+            }
+        }
+
+        self.events.clear();
+        for &input in &current {
+            self.events.push(if self.down.contains(&input) {
+                InputEvent::Held(input)
+            } else {
+                InputEvent::Pressed(input)
+            });
+        }
+        for &input in &self.down {
+            if !current.contains(&input) {
+                self.events.push(InputEvent::Released(input));
+            }
+        }
+        self.down = current;
+        &self.events
+    }
+
+    // Eventos del último `update`
+    pub fn events(&self) -> &[InputEvent<T>] {
+        &self.events
+    }
+
+    // Si bajó en este frame
+    pub fn was_pressed(&self, input: T) -> bool {
+        self.events.contains(&InputEvent::Pressed(input))
+    }
+
+    // Si subió en este frame
+    pub fn was_released(&self, input: T) -> bool {
+        self.events.contains(&InputEvent::Released(input))
+    }
+
+    // Si está abajo, haya bajado en este frame o antes
+    pub fn is_down(&self, input: T) -> bool {
+        self.down.contains(&input)
+    }
+}
@@ -0,0 +1,252 @@
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+use crate::light::SceneLight;
+use crate::object::SceneObject;
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::renderer::{gather_irradiance, render, sky_color, with_current_bvh};
+use crate::settings::RenderSettings;
+
+// Caché de irradiancia para un rebote de luz difusa: el césped tiñe de verde
+// la roca que tiene al lado. Trazar el hemisferio en cada impacto es muy caro,
+// así que la luz indirecta se mide en puntos sueltos de la escena (una entrada
+// por celda de una grilla y por orientación de la cara) y al sombrear se
+// interpola entre las entradas cercanas que miran hacia el mismo lado. Cada
+// entrada promedia unos rayos repartidos por el hemisferio con peso coseno,
+// sombreados con la luz directa, así que hay un solo rebote.
+//
+// Las entradas se crean donde hacen falta: un impacto sin entradas cerca usa
+// el ambiente y deja pedida la de su celda, que `refresh` calcula antes del
+// frame siguiente, unas pocas por frame. Cualquier cambio de objetos o luces
+// vacía la caché, así que con el sol del ciclo día/noche en marcha nunca se
+// llena; sirve con el sol quieto (`--day-time` con `--output`, o mientras se
+// mueve solo la cámara). Los materiales `unlit` no la reciben.
+pub struct IrradianceCache {
+    // Lado de las celdas, que también es el radio de interpolación
+    spacing: f32,
+    entries: HashMap<Cell, Vec<Entry>>,
+    count: usize,
+    // Puntos sin entradas cerca, uno por celda y orientación
+    pending: Mutex<HashMap<(Cell, Facing), (Vec3, Vec3)>>,
+    // Con qué se calcularon las entradas, para saber cuándo vaciarla
+    scene_key: u64,
+}
+
+type Cell = [i32; 3];
+// Normal redondeada: distingue las caras de un mismo bloque
+type Facing = [i8; 3];
+
+#[derive(Debug, Clone, Copy)]
+struct Entry {
+    point: Vec3,
+    normal: Vec3,
+    facing: Facing,
+    irradiance: [f32; 3],
+}
+
+// Rayos por entrada
+pub const SAMPLES: usize = 32;
+// Celdas a lo largo de la diagonal de la escena
+const CELLS_PER_DIAGONAL: f32 = 64.0;
+// Entradas que se calculan por frame en la ventana
+pub const ENTRIES_PER_FRAME: usize = 512;
+// Entradas más lejos que esto (en celdas) o que miran hacia otro lado no se
+// interpolan
+const RADIUS: f32 = 2.0;
+const MIN_NORMAL_DOT: f32 = 0.7;
+// Irradiancia donde no hay entradas cerca, como fracción del color del cielo;
+// los rayos de una entrada que salen al cielo suman lo mismo
+pub const AMBIENT: f32 = 0.15;
+
+impl std::fmt::Debug for IrradianceCache {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "IrradianceCache({} entradas, {:016x})", self.count, self.scene_key)
+    }
+}
+
+impl Clone for IrradianceCache {
+    fn clone(&self) -> Self {
+        IrradianceCache {
+            spacing: self.spacing,
+            entries: self.entries.clone(),
+            count: self.count,
+            pending: Mutex::new(self.pending.lock().expect("los pedidos no quedan a medias").clone()),
+            scene_key: self.scene_key,
+        }
+    }
+}
+
+// Huella de lo que cambia la luz indirecta: las cajas y los materiales de los
+// objetos y las luces
+fn scene_key(objects: &[SceneObject], lights: &[SceneLight]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    objects.len().hash(&mut hasher);
+    for object in objects {
+        let bounds = object.bounds();
+        for value in bounds.min.iter().chain(bounds.max.iter()) {
+            value.to_bits().hash(&mut hasher);
+        }
+        object.material().name.hash(&mut hasher);
+    }
+    lights.len().hash(&mut hasher);
+    for light in lights {
+        for value in light.position.iter().chain(light.target.iter()) {
+            value.to_bits().hash(&mut hasher);
+        }
+        light.color.to_hex().hash(&mut hasher);
+        light.intensity.to_bits().hash(&mut hasher);
+        light.falloff.map(f32::to_bits).hash(&mut hasher);
+        light.radius.to_bits().hash(&mut hasher);
+        format!("{:?}", light.kind).hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+fn facing(normal: &Vec3) -> Facing {
+    let round = |value: f32| (value * 2.0).round() as i8;
+    [round(normal.x), round(normal.y), round(normal.z)]
+}
+
+impl IrradianceCache {
+    fn new(objects: &[SceneObject], scene_key: u64) -> Self {
+        let bounds = objects.iter().filter(|object| object.is_bounded()).map(SceneObject::bounds);
+        let diagonal = bounds
+            .reduce(|a, b| a.union(&b))
+            .map_or(0.0, |bounds| (bounds.max - bounds.min).magnitude());
+        IrradianceCache {
+            spacing: if diagonal > 0.0 { diagonal / CELLS_PER_DIAGONAL } else { 1.0 / CELLS_PER_DIAGONAL },
+            entries: HashMap::new(),
+            count: 0,
+            pending: Mutex::new(HashMap::new()),
+            scene_key,
+        }
+    }
+
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    // Entradas pedidas que todavía no se calcularon
+    pub fn pending(&self) -> usize {
+        self.pending.lock().expect("los pedidos no quedan a medias").len()
+    }
+
+    fn cell(&self, point: &Vec3) -> Cell {
+        let cell = point / self.spacing;
+        [cell.x.floor() as i32, cell.y.floor() as i32, cell.z.floor() as i32]
+    }
+
+    // Irradiancia por canal (1 es el blanco) que llega a un punto con esa
+    // normal. Sin entradas cerca devuelve `ambient` y pide la de su celda
+    pub fn irradiance(&self, point: &Vec3, normal: &Vec3, ambient: [f32; 3]) -> [f32; 3] {
+        let [x, y, z] = self.cell(point);
+        let reach = RADIUS.ceil() as i32;
+        let radius = RADIUS * self.spacing;
+        let mut sum = [0.0; 3];
+        let mut total = 0.0;
+        let mut own_cell = false;
+        let facing = facing(normal);
+        for dz in -reach..=reach {
+            for dy in -reach..=reach {
+                for dx in -reach..=reach {
+                    let cell = [x + dx, y + dy, z + dz];
+                    for entry in self.entries.get(&cell).into_iter().flatten() {
+                        own_cell |= (dx, dy, dz) == (0, 0, 0) && entry.facing == facing;
+                        let alignment = entry.normal.dot(normal);
+                        let distance = (entry.point - point).magnitude();
+                        if alignment < MIN_NORMAL_DOT || distance >= radius {
+                            continue;
+                        }
+                        let weight = (1.0 - distance / radius) * alignment;
+                        for (channel, value) in sum.iter_mut().zip(entry.irradiance) {
+                            *channel += value * weight;
+                        }
+                        total += weight;
+                    }
+                }
+            }
+        }
+
+        if !own_cell {
+            // Gana el punto menor, así el pedido no depende del orden de los hilos
+            let mut pending = self.pending.lock().expect("los pedidos no quedan a medias");
+            let request = pending.entry(([x, y, z], facing)).or_insert((*point, *normal));
+            if point.as_slice() < request.0.as_slice() {
+                *request = (*point, *normal);
+            }
+        }
+        if total > 0.0 {
+            return sum.map(|channel| channel / total);
+        }
+        ambient
+    }
+}
+
+// Mantiene la caché al día: la vacía si cambiaron los objetos o las luces y
+// calcula hasta `budget` de las entradas pedidas, en orden de celda. La saca
+// si no se usa
+pub fn refresh(settings: &mut RenderSettings, objects: &[SceneObject], lights: &[SceneLight], enabled: bool, budget: usize) {
+    if !enabled {
+        settings.irradiance = None;
+        return;
+    }
+    let key = scene_key(objects, lights);
+    let mut cache = match settings.irradiance.take() {
+        Some(cache) if cache.scene_key == key => cache,
+        _ => Arc::new(IrradianceCache::new(objects, key)),
+    };
+
+    let cache_mut = Arc::make_mut(&mut cache);
+    let pending = cache_mut.pending.get_mut().expect("los pedidos no quedan a medias");
+    let mut requests: Vec<_> = pending.drain().collect();
+    requests.sort_by_key(|&(key, _)| key);
+    let later = requests.split_off(requests.len().min(budget));
+    pending.extend(later);
+
+    // Los rayos de las entradas se sombrean sin la caché: un solo rebote
+    let sky = sky_color(lights);
+    let traced = with_current_bvh(settings, objects);
+    let computed: Vec<(Cell, Entry)> = requests
+        .par_iter()
+        .map(|&((cell, facing), (point, normal))| {
+            let seed = {
+                let mut hasher = DefaultHasher::new();
+                (cell, facing).hash(&mut hasher);
+                hasher.finish()
+            };
+            let irradiance = gather_irradiance(&point, &normal, objects, lights, sky, &traced, seed);
+            (cell, Entry { point, normal, facing, irradiance })
+        })
+        .collect();
+    cache_mut.count += computed.len();
+    for (cell, entry) in computed {
+        cache_mut.entries.entry(cell).or_default().push(entry);
+    }
+    settings.irradiance = Some(cache);
+}
+
+// Llena la caché para un render sin ventana: un render previo pide las
+// entradas de todo lo que ve la cámara y se calculan todas antes del definitivo
+pub fn warm_up(
+    framebuffer: &mut Framebuffer,
+    objects: &[SceneObject],
+    camera: &Camera,
+    lights: &[SceneLight],
+    settings: &mut RenderSettings,
+    enabled: bool,
+) {
+    refresh(settings, objects, lights, enabled, usize::MAX);
+    if settings.irradiance.is_some() {
+        render(framebuffer, objects, camera, lights, sky_color(lights), settings);
+        refresh(settings, objects, lights, enabled, usize::MAX);
+    }
+}
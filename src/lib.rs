@@ -0,0 +1,70 @@
+pub mod adaptive;
+pub mod api;
+pub mod app;
+pub mod bake;
+pub mod bloom;
+pub mod budget;
+pub mod bvh;
+pub mod buried;
+pub mod camera;
+pub mod caustics;
+pub mod chunks;
+pub mod cli;
+pub mod color;
+pub mod config;
+pub mod controls;
+pub mod cornerao;
+pub mod cube;
+pub mod decal;
+pub mod diff;
+pub mod export;
+pub mod framebuffer;
+pub mod generate;
+pub mod gizmo;
+pub mod gltf;
+pub mod godrays;
+pub mod halfres;
+pub mod halo;
+pub mod input;
+pub mod irradiance;
+pub mod light;
+pub mod lightedit;
+pub mod material;
+// Los preajustes de materiales también se encuentran como `materials::presets`
+pub mod materials {
+    pub use crate::presets;
+}
+pub mod merge;
+pub mod minimap;
+pub mod motion;
+pub mod object;
+pub mod offline;
+pub mod overlay;
+pub mod plane;
+pub mod probe;
+pub mod profile;
+pub mod query;
+pub mod rail;
+pub mod presets;
+pub mod ray_intersect;
+pub mod redraw;
+pub mod registry;
+pub mod renderer;
+pub mod reprojection;
+pub mod scene;
+pub mod sdf;
+pub mod settings;
+pub mod shadowmap;
+pub mod sky;
+pub mod skybox;
+pub mod sphere;
+pub mod split;
+pub mod texture;
+pub mod thumbnail;
+pub mod timeline;
+pub mod tools;
+pub mod velocity;
+
+// API para usar el renderer desde otro programa
+pub use api::{DioramaBuilder, Renderer};
+pub use nalgebra_glm::Vec3;
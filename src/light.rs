@@ -1,18 +1,231 @@
 use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+use std::f32::consts::PI;
+use std::sync::Arc;
+
 use crate::color::Color;
+use crate::shadowmap::ShadowMap;
+
+pub const DAY_DURATION: f32 = 10.0; // Duración del día en segundos
+pub const NOON: f32 = DAY_DURATION / 4.0; // Momento del ciclo con el sol en lo más alto
+
+// Distancia a la que se traza la sombra de una luz direccional, lejos de
+// cualquier escena
+pub(crate) const DIRECTIONAL_DISTANCE: f32 = 1e4;
+// Un sol direccional recorre el cielo de este (+X) a oeste pasando por el
+// cenit, inclinado hacia -Z como el puntual; su `position` queda a esta
+// distancia de `target` en esa dirección, para el marcador y el color del cielo
+const SUN_TILT: f32 = 0.35;
+const SUN_DISTANCE: f32 = 2.0;
+// Fracción de la apertura de un foco que queda con intensidad completa; el
+// resto se apaga suavemente hacia el borde del cono
+const SPOT_FULL: f32 = 0.85;
+
+// Cómo llega la luz a la escena. Las direccionales y los focos apuntan de
+// `position` a `target`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum LightKind {
+    #[default]
+    Point,
+    // Rayos paralelos en la dirección de `position` a `target`, iguales en
+    // toda la escena y sin distancia a la luz
+    Directional,
+    // Cono con vértice en `position` y eje hacia `target`; `angle` es la
+    // apertura en grados desde el eje
+    Spot { angle: f32 },
+}
+
+impl LightKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            LightKind::Point => "puntual",
+            LightKind::Directional => "direccional",
+            LightKind::Spot { .. } => "foco",
+        }
+    }
+
+    // Tipo siguiente para el modo de edición de luces
+    pub fn next(self) -> LightKind {
+        match self {
+            LightKind::Point => LightKind::Directional,
+            LightKind::Directional => LightKind::Spot { angle: 30.0 },
+            LightKind::Spot { .. } => LightKind::Point,
+        }
+    }
+}
 
-pub struct Light {
+// Luz de la escena; el sol además sigue el ciclo día/noche
+#[derive(Debug, Clone)]
+pub struct SceneLight {
     pub position: Vec3,
     pub color: Color,
     pub intensity: f32,
+    pub is_sun: bool,
+    pub kind: LightKind,
+    // Punto al que apuntan las direccionales y los focos
+    pub target: Vec3,
+    // Enlace de luces: si `include` no está vacío la luz solo afecta a esos
+    // grupos; `exclude` la quita de los grupos listados
+    pub include: Vec<String>,
+    pub exclude: Vec<String>,
+    // Coeficientes lineal y cuadrático de la atenuación con la distancia: la
+    // intensidad se divide por 1 + k1·d + k2·d². En cero la luz llega igual a
+    // toda la escena; el sol y las direccionales nunca se atenúan
+    pub falloff: [f32; 2],
+    // Radio del disco que emite la luz, de frente al punto sombreado. En cero
+    // es puntual; con sombras `Area` da penumbra. Las direccionales lo ignoran
+    pub radius: f32,
+    // Mapa de sombras en uso, que mantiene `shadowmap::refresh`
+    pub shadow_map: Option<Arc<ShadowMap>>,
+    time: f32,
 }
 
-impl Light {
+// Dos luces son iguales si iluminan igual: el mapa de sombras sale de lo
+// demás y no cuenta
+impl PartialEq for SceneLight {
+    fn eq(&self, other: &Self) -> bool {
+        self.position == other.position
+            && self.color == other.color
+            && self.intensity == other.intensity
+            && self.is_sun == other.is_sun
+            && self.kind == other.kind
+            && self.target == other.target
+            && self.include == other.include
+            && self.exclude == other.exclude
+            && self.falloff == other.falloff
+            && self.radius == other.radius
+            && self.time == other.time
+    }
+}
+
+impl SceneLight {
     pub fn new(position: Vec3, color: Color, intensity: f32) -> Self {
-        Light {
+        Self {
             position,
             color,
             intensity,
+            is_sun: false,
+            kind: LightKind::Point,
+            target: Vec3::zeros(),
+            include: Vec::new(),
+            exclude: Vec::new(),
+            falloff: [0.0, 0.0],
+            radius: 0.0,
+            shadow_map: None,
+            time: 0.0,
+        }
+    }
+
+    pub fn sun(position: Vec3, color: Color, intensity: f32) -> Self {
+        Self {
+            is_sun: true,
+            ..Self::new(position, color, intensity)
         }
     }
-}
\ No newline at end of file
+
+    // Luz local que se apaga con la distancia, como la de la lava
+    pub fn attenuated(position: Vec3, color: Color, intensity: f32, linear: f32, quadratic: f32) -> Self {
+        Self {
+            falloff: [linear, quadratic],
+            ..Self::new(position, color, intensity)
+        }
+    }
+
+    // Indica si la luz está restringida a un subconjunto de grupos
+    pub fn is_linked(&self) -> bool {
+        !self.include.is_empty() || !self.exclude.is_empty()
+    }
+
+    pub fn affects(&self, group: &str) -> bool {
+        (self.include.is_empty() || self.include.iter().any(|g| g == group))
+            && !self.exclude.iter().any(|g| g == group)
+    }
+
+    // Dirección desde `point` hacia la luz y distancia hasta ella
+    pub fn toward(&self, point: &Vec3) -> (Vec3, f32) {
+        match self.kind {
+            LightKind::Directional => {
+                let direction = (self.position - self.target).try_normalize(1e-6).unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+                (direction, DIRECTIONAL_DISTANCE)
+            }
+            LightKind::Point | LightKind::Spot { .. } => {
+                let offset = self.position - point;
+                (offset.normalize(), offset.magnitude())
+            }
+        }
+    }
+
+    // Punto desde el que llega la luz a `point`, hasta donde se traza la sombra
+    pub fn source_for(&self, point: &Vec3) -> Vec3 {
+        let (direction, distance) = self.toward(point);
+        point + direction * distance
+    }
+
+    // Fracción de la intensidad que llega a `point` por el cono de un foco;
+    // 1 para los otros tipos
+    pub fn cone(&self, point: &Vec3) -> f32 {
+        let LightKind::Spot { angle } = self.kind else {
+            return 1.0;
+        };
+        let (Some(axis), Some(to_point)) =
+            ((self.target - self.position).try_normalize(1e-6), (point - self.position).try_normalize(1e-6))
+        else {
+            return 1.0;
+        };
+        let outer = angle.to_radians().cos();
+        let inner = (angle * SPOT_FULL).to_radians().cos();
+        let t = ((axis.dot(&to_point) - outer) / (inner - outer).max(1e-6)).clamp(0.0, 1.0);
+        t * t * (3.0 - 2.0 * t)
+    }
+
+    // Fracción de la intensidad que llega a `distance` de la luz
+    pub fn attenuation(&self, distance: f32) -> f32 {
+        if self.is_sun || self.kind == LightKind::Directional {
+            return 1.0;
+        }
+        let [linear, quadratic] = self.falloff;
+        1.0 / (1.0 + linear * distance + quadratic * distance * distance).max(1e-6)
+    }
+
+    // Instante del ciclo día/noche, en segundos desde el amanecer
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    // Lleva el ciclo al instante dado, por ejemplo `NOON` para un preset de mediodía
+    pub fn set_time(&mut self, time: f32) {
+        self.time = time;
+        self.update(0.0);
+    }
+
+    pub fn update(&mut self, delta_time: f32) {
+        self.time += delta_time;
+        if self.time > DAY_DURATION {
+            self.time -= DAY_DURATION;
+        }
+
+        let angle = 2.0 * PI * (self.time / DAY_DURATION);
+        
+        // Actualiza la posición de la luz. El sol puntual da vueltas en un
+        // círculo chico cerca de la escena; el direccional gira su dirección,
+        // así las sombras barren el suelo como con un sol lejano
+        self.position = if self.kind == LightKind::Directional {
+            self.target + Vec3::new(angle.cos(), angle.sin(), -SUN_TILT).normalize() * SUN_DISTANCE
+        } else {
+            Vec3::new(
+                0.75 * angle.cos(),
+                0.25 + 2.0 * angle.sin(),
+                -2.0
+            )
+        };
+
+        // Actualiza el color y la intensidad de la luz
+        let t = (angle.sin() + 1.0) / 2.0; // Normaliza entre 0 y 1
+        self.color = Color::new(
+            (255.0 * t) as u8,
+            (200.0 * t) as u8,
+            (100.0 * t) as u8
+        );
+        self.intensity = 1.0 + t;
+    }
+}
@@ -0,0 +1,130 @@
+use nalgebra_glm::Vec3;
+
+use crate::color::Color;
+use crate::light::SceneLight;
+
+// Pasos que se pueden deshacer; los más viejos se descartan
+const HISTORY_LIMIT: usize = 64;
+
+// Un cambio a las luces de la escena desde el modo de edición
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LightEdit {
+    // Desplaza la luz seleccionada en ejes del mundo
+    Move(Vec3),
+    // Multiplica la intensidad de la luz seleccionada
+    ScaleIntensity(f32),
+    // Pasa la luz seleccionada al tipo siguiente (puntual, direccional, foco)
+    CycleKind,
+    // Agrega una luz puntual blanca en `position` apuntando a `target` y la selecciona
+    Add { position: Vec3, target: Vec3 },
+    DeleteSelected,
+}
+
+impl LightEdit {
+    // Mover y cambiar la intensidad duran varios frames mientras se mantiene
+    // la tecla o gira la rueda, y cuentan como un solo paso para deshacer
+    fn is_continuous(self) -> bool {
+        matches!(self, LightEdit::Move(_) | LightEdit::ScaleIntensity(_))
+    }
+}
+
+// Estado del modo de edición de luces: la luz seleccionada y el historial para
+// deshacer. Todos los cambios pasan por `apply`, que guarda las luces de antes
+// y avisa si algo cambió. La posición y la intensidad del sol las fija el
+// ciclo día/noche en cada frame, así que se dejan fuera.
+#[derive(Debug, Clone, Default)]
+pub struct LightEditor {
+    pub active: bool,
+    pub selected: usize,
+    history: Vec<(Vec<SceneLight>, usize)>,
+    // Edición continua del frame anterior y si siguió en este
+    continuing: Option<LightEdit>,
+    touched: Option<LightEdit>,
+    // Si hubo cambios desde que se entró al modo
+    pub changed: bool,
+}
+
+impl LightEditor {
+    pub fn selected<'a>(&self, lights: &'a [SceneLight]) -> Option<&'a SceneLight> {
+        lights.get(self.selected).filter(|_| self.active)
+    }
+
+    pub fn select_next(&mut self, lights: &[SceneLight]) {
+        self.selected = if lights.is_empty() { 0 } else { (self.selected + 1) % lights.len() };
+    }
+
+    // Aplica el cambio a la luz seleccionada. Devuelve si las luces cambiaron
+    pub fn apply(&mut self, lights: &mut Vec<SceneLight>, edit: LightEdit) -> bool {
+        let sun = lights.get(self.selected).is_some_and(|light| light.is_sun);
+        let applies = match edit {
+            LightEdit::Add { .. } => true,
+            LightEdit::Move(_) | LightEdit::ScaleIntensity(_) => self.selected < lights.len() && !sun,
+            LightEdit::CycleKind | LightEdit::DeleteSelected => self.selected < lights.len(),
+        };
+        if !applies {
+            return false;
+        }
+
+        let same_gesture = edit.is_continuous()
+            && self.continuing.is_some_and(|previous| std::mem::discriminant(&previous) == std::mem::discriminant(&edit));
+        if !same_gesture {
+            self.history.push((lights.clone(), self.selected));
+            if self.history.len() > HISTORY_LIMIT {
+                self.history.remove(0);
+            }
+        }
+        if edit.is_continuous() {
+            self.touched = Some(edit);
+        }
+
+        match edit {
+            LightEdit::Move(offset) => lights[self.selected].position += offset,
+            LightEdit::ScaleIntensity(factor) => {
+                let light = &mut lights[self.selected];
+                light.intensity = (light.intensity * factor).max(0.0);
+            }
+            LightEdit::CycleKind => {
+                let light = &mut lights[self.selected];
+                light.kind = light.kind.next();
+            }
+            LightEdit::Add { position, target } => {
+                let mut light = SceneLight::new(position, Color::white(), 1.0);
+                light.target = target;
+                self.selected = (self.selected + 1).min(lights.len());
+                lights.insert(self.selected, light);
+            }
+            LightEdit::DeleteSelected => {
+                lights.remove(self.selected);
+                self.selected = self.selected.min(lights.len().saturating_sub(1));
+            }
+        }
+        self.changed = true;
+        true
+    }
+
+    // Vuelve las luces a como estaban antes del último cambio
+    pub fn undo(&mut self, lights: &mut Vec<SceneLight>) -> bool {
+        let Some((previous, selected)) = self.history.pop() else {
+            return false;
+        };
+        *lights = previous;
+        self.selected = selected;
+        self.continuing = None;
+        self.touched = None;
+        true
+    }
+
+    // Cierra el frame: una edición continua que no siguió termina su paso
+    pub fn end_frame(&mut self) {
+        self.continuing = self.touched.take();
+    }
+
+    // Descarta el historial, por ejemplo cuando las luces son de otra escena
+    pub fn forget(&mut self) {
+        self.history.clear();
+        self.continuing = None;
+        self.touched = None;
+        self.selected = 0;
+        self.changed = false;
+    }
+}
@@ -1,75 +1,320 @@
+use std::sync::Arc;
+
 use crate::color::Color;
-use crate::texture::Texture;
+use crate::texture::{AnimatedTexture, Texture, TextureFilter, TextureSource, UvMode};
 
 #[derive(Clone, Debug)]
 pub struct Material {
+    pub name: String, // Nombre con el que se registró en la escena, vacío si no tiene
     pub color: Color,
-    pub texture: Option<Texture>,
+    // Compartida entre los materiales que la cargaron de la misma ruta y
+    // entre las copias de un material, sin duplicar la imagen
+    pub texture: Option<Arc<Texture>>,
+    // Cuadros que van pasando por `texture` con `animate`
+    pub animation: Option<AnimatedTexture>,
+    // Normales en el espacio de la cara (rojo hacia u, verde hacia v y azul
+    // hacia afuera) que inclinan la de sombreado; por ahora solo en los cubos
+    pub normal_map: Option<Texture>,
     pub shininess: f32,
-    pub properties: [f32; 4],
+    // Pesos de cada término del sombreado
+    pub diffuse: f32,
+    pub specular: f32,
+    pub reflectivity: f32,
+    pub transparency: f32,
     pub refractive_index: f32,
     pub emission: Color, // Nueva propiedad para la emisividad
+    // Con mapa de emisión cada punto emite el color del mapa en lugar del
+    // plano de `emission`; la intensidad multiplica uno u otro
+    pub emission_map: Option<Texture>,
+    pub emission_strength: f32,
+    // Reflexión planar: el reflejo se muestrea de un render espejado a menor
+    // resolución en vez de trazar un rayo por píxel (solo superficies planas)
+    pub planar_reflection: bool,
+    // Intensidad del halo en pantalla (0 sin halo); se tiñe con la emisión o,
+    // si el material no emite, con su color visto
+    pub halo: f32,
+    // Receptor de sombras para composición: invisible salvo donde le cae
+    // sombra, que se muestra con el color de sombra y alfa igual a la oclusión.
+    // Los rayos secundarios y de sombra lo ignoran.
+    pub shadow_catcher: bool,
+    // Los materiales `unlit` no muestran sombras; con esto se oscurecen donde
+    // les cae sombra de las luces de su grupo
+    pub receives_shadows: bool,
+    // Ignora las luces: la textura o el color se ven tal cual, más la emisión
+    pub unlit: bool,
+    // Filtrado de la textura; Nearest reproduce el muestreo original
+    pub filter: TextureFilter,
+    // Ancho en unidades del bisel falso de las aristas del cubo: solo inclina
+    // la normal de iluminación y reflejo, las sombras y la silueta no cambian
+    pub edge_bevel: f32,
+    // De dónde salen las coordenadas de la textura; con `World` y `Triplanar`
+    // se proyecta en el mundo con una repetición cada `texture_scale` unidades
+    pub uv_mode: UvMode,
+    pub texture_scale: f32,
+    // Los transparentes reparten reflejo y refracción según el ángulo con la
+    // aproximación de Schlick en vez de con los pesos fijos de
+    // `reflectivity` y `transparency`
+    pub use_fresnel: bool,
+    // Absorción dentro del material (Beer-Lambert): `absorption` es el color
+    // que toma la luz tras recorrer una unidad por dentro y la densidad
+    // multiplica la distancia; con densidad 0 el interior no absorbe nada
+    pub absorption: Color,
+    pub absorption_density: f32,
+    // Superficie de agua: lo que queda debajo recibe el sol con el dibujo de
+    // las cáusticas (ver `caustics`)
+    pub caustics: bool,
 }
 
 impl Material {
+    // `properties` son los pesos en el orden de los archivos de escena:
+    // difuso, especular, reflectividad y transparencia
     pub fn new(color: Color, shininess: f32, properties: [f32; 4], refractive_index: f32) -> Self {
+        let [diffuse, specular, reflectivity, transparency] = properties;
         Material {
             color,
-            texture: None,
             shininess,
-            properties,
+            diffuse,
+            specular,
+            reflectivity,
+            transparency,
             refractive_index,
-            emission: Color::black(), // Por defecto, no emite luz
+            ..Material::black()
         }
     }
  
     // Method to create a black material with default values
     pub fn black() -> Self {
         Material {
+            name: String::new(),
             color: Color::new(0, 0, 0),    // Use integer values for Color
             shininess: 0.0,                 // Default shininess
-            properties: [0.0, 0.0, 0.0, 0.0], // Default properties (all set to 0)
+            diffuse: 0.0,                   // Default weights (all set to 0)
+            specular: 0.0,
+            reflectivity: 0.0,
+            transparency: 0.0,
             refractive_index: 1.0, 
             texture: None,         // Default refractive index (e.g., for air)
+            animation: None,
+            normal_map: None,
             emission: Color::black(), // Por defecto, no emite luz
+            emission_map: None,
+            emission_strength: 1.0,
+            planar_reflection: false,
+            halo: 0.0,
+            shadow_catcher: false,
+            receives_shadows: false,
+            unlit: false,
+            filter: TextureFilter::Nearest,
+            edge_bevel: 0.0,
+            uv_mode: UvMode::Face,
+            texture_scale: 1.0,
+            use_fresnel: false,
+            absorption: Color::white(),
+            absorption_density: 0.0,
+            caustics: false,
         }
     }
 
-    pub fn with_texture(texture: Texture, shininess: f32, properties: [f32; 4], refractive_index: f32) -> Self {
+    // Acepta una imagen, una ya compartida o un `ProceduralTexture`
+    pub fn with_texture(texture: impl Into<Arc<Texture>>, shininess: f32, properties: [f32; 4], refractive_index: f32) -> Self {
         Material {
-            color: Color::white(),
-            texture: Some(texture),
-            shininess,
-            properties,
-            refractive_index,
-            emission: Color::black(), // Por defecto, no emite luz
+            texture: Some(texture.into()),
+            ..Material::new(Color::white(), shininess, properties, refractive_index)
         }
     }
 
     // Nuevo método para crear materiales emisivos
     pub fn with_emission(color: Color, shininess: f32, properties: [f32; 4], refractive_index: f32, emission: Color) -> Self {
         Material {
-            color,
-            texture: None,
-            shininess,
-            properties,
-            refractive_index,
             emission,
+            ..Material::new(color, shininess, properties, refractive_index)
+        }
+    }
+
+    // Material armado campo por campo, que parte de un blanco mate
+    pub fn builder() -> MaterialBuilder {
+        MaterialBuilder {
+            material: Material::new(Color::white(), 1.0, [1.0, 0.0, 0.0, 0.0], 1.0),
+        }
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    // El mismo material con otro color de base
+    pub fn tinted(mut self, color: Color) -> Self {
+        self.color = color;
+        self
+    }
+
+    // El mismo material con una textura animada de base, desde el primer cuadro
+    pub fn animated(self, animation: AnimatedTexture) -> Self {
+        let first = animation.frames()[0].clone();
+        Material { animation: Some(animation), ..self.textured(first) }
+    }
+
+    // Muestra el cuadro de la animación que toca a los `time` segundos; el
+    // mapa de emisión que era la misma imagen que la textura cambia con ella.
+    // Devuelve si cambió el cuadro
+    pub fn animate(&mut self, time: f32) -> bool {
+        let Some(animation) = &self.animation else {
+            return false;
+        };
+        let frame = animation.frame_at(time);
+        let Some(shown) = &self.texture else {
+            return false;
+        };
+        if shown.same_image(frame) {
+            return false;
+        }
+        if self.emission_map.as_ref().is_some_and(|map| map.same_image(shown)) {
+            self.emission_map = Some(frame.clone());
+        }
+        self.texture = Some(Arc::new(frame.clone()));
+        true
+    }
+
+    // Cambia la textura por una imagen de `width` x `height` (ver
+    // `TextureSource::bake`): un patrón deja de calcularse en cada muestra y
+    // una animación queda quieta en el cuadro de los `time` segundos. El mapa
+    // de emisión que era la misma imagen se hornea con ella. Devuelve si se
+    // congeló una animación, para avisarlo
+    pub fn bake_texture(&mut self, width: u32, height: u32, time: f32) -> bool {
+        let baked = match (&self.animation, &self.texture) {
+            (Some(animation), _) => animation.bake(width, height, time),
+            (None, Some(texture)) => texture.bake(width, height, time),
+            (None, None) => return false,
+        };
+        let shown = self.texture.as_deref();
+        if self.emission_map.as_ref().zip(shown).is_some_and(|(map, shown)| map.same_image(shown)) {
+            self.emission_map = Some(baked.clone());
         }
+        self.texture = Some(Arc::new(baked));
+        self.animation.take().is_some()
+    }
+
+    // El mismo material con una textura de base; el color queda en blanco,
+    // como en `with_texture`, para que no tiña lo que deja pasar
+    pub fn textured(mut self, texture: impl Into<Arc<Texture>>) -> Self {
+        self.texture = Some(texture.into());
+        self.color = Color::white();
+        self
     }
 
     // Method to determine if the material is completely diffuse (no shininess)
     pub fn is_diffuse(&self) -> bool {
-        self.properties[1] == 0.0 && self.properties[2] == 0.0
+        self.specular == 0.0 && self.reflectivity == 0.0
     }
 
     // Method to determine if the material is reflective
     pub fn is_reflective(&self) -> bool {
-        self.properties[2] > 0.0
+        self.reflectivity > 0.0
+    }
+
+    // Method to determine if the material emits light
+    pub fn is_emissive(&self) -> bool {
+        let flat = self.emission.red() > 0 || self.emission.green() > 0 || self.emission.blue() > 0;
+        self.emission_strength > 0.0 && (flat || self.emission_map.is_some())
+    }
+
+    // Emisión promedio de la superficie, para quien no la mira punto por punto
+    pub fn average_emission(&self) -> Color {
+        self.emission_map.as_ref().map_or(self.emission, Texture::average_color) * self.emission_strength
     }
 
     // Method to determine if the material is transparent
     pub fn is_transparent(&self) -> bool {
-        self.properties[3] > 0.0
+        self.transparency > 0.0
+    }
+
+    // Fracción de cada canal que sobrevive a `distance` unidades por dentro
+    pub fn transmittance(&self, distance: f32) -> [f32; 3] {
+        let channels = [self.absorption.red(), self.absorption.green(), self.absorption.blue()];
+        channels.map(|channel| {
+            if self.absorption_density <= 0.0 {
+                return 1.0;
+            }
+            // Un canal en 0 se absorbe casi del todo en una unidad, sin infinitos
+            let survives = (channel as f32 / 255.0).max(1.0 / 255.0);
+            survives.powf(self.absorption_density * distance)
+        })
     }
-}
\ No newline at end of file
+}
+// Arma un `Material` por nombre de campo, así no hay que recordar el orden de
+// los pesos. Lo que no se pide queda como en `Material::builder`
+pub struct MaterialBuilder {
+    material: Material,
+}
+
+impl MaterialBuilder {
+    pub fn name(mut self, name: &str) -> Self {
+        self.material.name = name.to_string();
+        self
+    }
+
+    pub fn color(mut self, color: Color) -> Self {
+        self.material.color = color;
+        self
+    }
+
+    pub fn texture(mut self, texture: impl Into<Arc<Texture>>) -> Self {
+        self.material.texture = Some(texture.into());
+        self
+    }
+
+    pub fn normal_map(mut self, normal_map: Texture) -> Self {
+        self.material.normal_map = Some(normal_map);
+        self
+    }
+
+    pub fn shininess(mut self, shininess: f32) -> Self {
+        self.material.shininess = shininess;
+        self
+    }
+
+    pub fn diffuse(mut self, diffuse: f32) -> Self {
+        self.material.diffuse = diffuse;
+        self
+    }
+
+    pub fn specular(mut self, specular: f32) -> Self {
+        self.material.specular = specular;
+        self
+    }
+
+    pub fn reflectivity(mut self, reflectivity: f32) -> Self {
+        self.material.reflectivity = reflectivity;
+        self
+    }
+
+    pub fn transparency(mut self, transparency: f32) -> Self {
+        self.material.transparency = transparency;
+        self
+    }
+
+    pub fn refractive_index(mut self, refractive_index: f32) -> Self {
+        self.material.refractive_index = refractive_index;
+        self
+    }
+
+    pub fn emission(mut self, emission: Color) -> Self {
+        self.material.emission = emission;
+        self
+    }
+
+    pub fn emission_map(mut self, emission_map: Texture) -> Self {
+        self.material.emission_map = Some(emission_map);
+        self
+    }
+
+    pub fn emission_strength(mut self, emission_strength: f32) -> Self {
+        self.material.emission_strength = emission_strength;
+        self
+    }
+
+    pub fn build(self) -> Material {
+        self.material
+    }
+}
@@ -0,0 +1,98 @@
+use std::collections::HashMap;
+
+use crate::cube::Cube;
+use crate::decal::Face;
+use crate::object::SceneObject;
+
+// Fusión voraz de cubos: dos cubos que comparten una cara entera y tienen el
+// mismo material y el mismo grupo se juntan en una sola caja, primero a lo
+// largo de X, después de Z y al final de Y. Las filas de bloques de las
+// construcciones de vóxeles quedan en unas pocas cajas largas y el BVH tiene
+// menos hojas que recorrer.
+//
+// Las coordenadas de textura van de 0 a 1 sobre cada cara de un cubo, así
+// que la caja fusionada suma en `uv_repeat` las repeticiones de los cubos de
+// los que salió: una caja de 4x1 repite la textura en cada celda igual que
+// los cuatro cubos y el render no cambia. Para eso las celdas de una fila
+// tienen que medir lo mismo a lo largo de la fila.
+
+// Solo se fusionan cubos cuyo aspecto no depende de su caja: con nombre (para
+// seguirlos entre versiones de la escena), con calcomanías o con bisel quedan
+// como estaban, y los transparentes también, porque entre dos cubos de vidrio
+// el rayo cruza una cara que en la caja fusionada desaparece. Los materiales
+// sin nombre no se comparan. Los materiales por cara cuentan igual que el del
+// cubo.
+fn can_merge(cube: &Cube) -> bool {
+    cube.name.is_empty()
+        && cube.decals.is_empty()
+        && std::iter::once(&cube.material).chain(cube.face_materials.iter().map(|(_, material)| material)).all(|material| {
+            !material.name.is_empty() && material.edge_bevel <= 0.0 && !material.is_transparent()
+        })
+}
+
+// Cubos que pueden quedar en la misma fila a lo largo de `axis`: mismo
+// material, mismo grupo, la misma sección (con sus repeticiones) en los otros
+// dos ejes y celdas del mismo largo. Las coordenadas se comparan exactas, como
+// quedan en las escenas armadas sobre una grilla. Los materiales por cara van
+// ordenados por cara, así da lo mismo en qué orden se asignaron.
+type RowKey = (String, Vec<(Face, String)>, String, [u32; 7]);
+
+fn row_key(cube: &Cube, axis: usize) -> RowKey {
+    let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+    let (repeat, cell) = (cube.uv_repeat, (cube.max[axis] - cube.min[axis]) / cube.uv_repeat[axis]);
+    let section = [cube.min[a], cube.max[a], cube.min[b], cube.max[b], repeat[a], repeat[b], cell].map(f32::to_bits);
+    let mut faces: Vec<(Face, String)> = cube.face_materials.iter().map(|(face, material)| (*face, material.name.clone())).collect();
+    faces.sort_by_key(|(face, _)| *face as u8);
+    (cube.material.name.clone(), faces, cube.group.clone(), section)
+}
+
+// Junta las cajas consecutivas de cada fila a lo largo de `axis`
+fn merge_along(cubes: Vec<Cube>, axis: usize) -> Vec<Cube> {
+    let mut rows: HashMap<RowKey, Vec<Cube>> = HashMap::new();
+    // Orden de aparición de las filas, para que el resultado no dependa del hash
+    let mut order = Vec::new();
+    for cube in cubes {
+        let key = row_key(&cube, axis);
+        let row = rows.entry(key.clone()).or_default();
+        if row.is_empty() {
+            order.push(key);
+        }
+        row.push(cube);
+    }
+
+    let mut merged = Vec::new();
+    for key in order {
+        let mut row = rows.remove(&key).unwrap_or_default();
+        row.sort_by(|a, b| a.min[axis].total_cmp(&b.min[axis]));
+        let mut row = row.into_iter();
+        let Some(mut current) = row.next() else {
+            continue;
+        };
+        for cube in row {
+            if cube.min[axis] == current.max[axis] {
+                current.max[axis] = cube.max[axis];
+                current.uv_repeat[axis] += cube.uv_repeat[axis];
+            } else {
+                merged.push(std::mem::replace(&mut current, cube));
+            }
+        }
+        merged.push(current);
+    }
+    merged
+}
+
+// Devuelve los objetos con las filas de cubos fusionadas; los que no se
+// pueden fusionar, como las esferas, quedan primero y en el mismo orden
+pub fn merge_cubes(objects: Vec<SceneObject>) -> Vec<SceneObject> {
+    let mut mergeable = Vec::new();
+    let mut kept = Vec::new();
+    for object in objects {
+        match object {
+            SceneObject::Cube(cube) if can_merge(&cube) => mergeable.push(cube),
+            other => kept.push(other),
+        }
+    }
+    let merged = [0, 2, 1].into_iter().fold(mergeable, merge_along);
+    kept.extend(merged.into_iter().map(SceneObject::Cube));
+    kept
+}
@@ -0,0 +1,176 @@
+use nalgebra_glm::Vec3;
+use std::collections::HashMap;
+use std::ops::Range;
+
+use crate::bvh::Aabb;
+use crate::camera::Camera;
+use crate::color::{Color, Palette};
+use crate::framebuffer::Framebuffer;
+use crate::material::Material;
+use crate::object::SceneObject;
+use crate::overlay;
+
+// Vista desde arriba en una esquina del framebuffer para no perder de vista
+// dónde está la cámara en escenas grandes: cada objeto es su caja proyectada
+// sobre el plano XZ con el color de su material, los más altos encima, y la
+// cámara una flecha en la dirección en que mira. Con otra paleta que la de
+// siempre los objetos se pintan por capas de altura con la rampa de la paleta. Se arma con los datos de la
+// escena, sin trazar rayos, así que se puede redibujar en cada frame.
+#[derive(Debug, Clone)]
+pub struct Minimap {
+    // Lado del mapa en píxeles
+    pub size: usize,
+    // Separación de los bordes del framebuffer
+    pub margin: usize,
+    // Color promedio de cada textura, que es caro de calcular
+    texture_colors: HashMap<String, Color>,
+}
+
+impl Default for Minimap {
+    fn default() -> Self {
+        Minimap {
+            size: 96,
+            margin: 2,
+            texture_colors: HashMap::new(),
+        }
+    }
+}
+
+const SELECTED_COLOR: Color = Color::new(255, 230, 0);
+const CAMERA_COLOR: Color = Color::new(255, 255, 255);
+const ARROW_LENGTH: f32 = 10.0;
+
+// Cuadrado de la escena que cubre el mapa y dónde cae en pantalla. Es lo
+// bastante grande para que la cámara quede adentro al orbitar, y no cambia de
+// escala mientras la distancia al centro sea la misma.
+struct Layout {
+    x: usize,
+    y: usize,
+    size: usize,
+    // Esquina mínima (x, z) del cuadrado y píxeles por unidad
+    origin: (f32, f32),
+    scale: f32,
+}
+
+impl Layout {
+    fn new(minimap: &Minimap, framebuffer_width: usize, objects: &[SceneObject], camera: &Camera) -> Option<Layout> {
+        let Aabb { min, max } = objects.iter().filter(|object| object.is_bounded()).map(SceneObject::bounds).reduce(|bounds, other| bounds.union(&other))?;
+        let center = (min + max) * 0.5;
+        let to_eye = Vec3::new(camera.eye.x - center.x, 0.0, camera.eye.z - center.z).magnitude();
+        let half = ((max.x - min.x).max(max.z - min.z) * 0.5).max(to_eye).max(1e-3) * 1.1;
+        let size = minimap.size.min(framebuffer_width);
+        Some(Layout {
+            x: framebuffer_width.saturating_sub(size + minimap.margin),
+            y: minimap.margin,
+            size,
+            origin: (center.x - half, center.z - half),
+            scale: size as f32 / (2.0 * half),
+        })
+    }
+
+    // +X hacia la derecha y +Z hacia abajo, así la cámara por defecto queda
+    // abajo mirando hacia arriba
+    fn to_screen(&self, x: f32, z: f32) -> (f32, f32) {
+        (self.x as f32 + (x - self.origin.0) * self.scale, self.y as f32 + (z - self.origin.1) * self.scale)
+    }
+
+    fn to_world(&self, x: f32, y: f32) -> Option<(f32, f32)> {
+        let (u, v) = (x - self.x as f32, y - self.y as f32);
+        let size = self.size as f32;
+        ((0.0..size).contains(&u) && (0.0..size).contains(&v))
+            .then(|| (self.origin.0 + u / self.scale, self.origin.1 + v / self.scale))
+    }
+
+    // Píxeles que cubre la caja del objeto, recortados al mapa
+    fn footprint(&self, object: &SceneObject) -> (Range<usize>, Range<usize>) {
+        let bounds = object.bounds();
+        let (x0, y0) = self.to_screen(bounds.min.x, bounds.min.z);
+        let (x1, y1) = self.to_screen(bounds.max.x, bounds.max.z);
+        // Al menos un píxel, para que los objetos finos no desaparezcan
+        let clip = |from: f32, to: f32, start: usize| {
+            let end = (start + self.size) as f32;
+            let low = from.floor().clamp(start as f32, end);
+            let high = to.ceil().max(low + 1.0).clamp(low, end);
+            low as usize..high as usize
+        };
+        (clip(x0, x1, self.x), clip(y0, y1, self.y))
+    }
+}
+
+impl Minimap {
+    fn color_of(&mut self, material: &Material) -> Color {
+        match &material.texture {
+            Some(texture) => *self
+                .texture_colors
+                .entry(texture.path().to_string())
+                .or_insert_with(|| texture.average_color()),
+            None => material.color,
+        }
+    }
+
+    pub fn draw(
+        &mut self,
+        framebuffer: &mut Framebuffer,
+        objects: &[SceneObject],
+        camera: &Camera,
+        selected: Option<usize>,
+        palette: Palette,
+    ) {
+        let Some(layout) = Layout::new(self, framebuffer.width, objects, camera) else {
+            return;
+        };
+        overlay::fill_rect(framebuffer, layout.x, layout.y, layout.size, layout.size, Color::black(), 0.6);
+
+        // Desde arriba se ve la cara superior más alta
+        let top = |index: usize| objects[index].bounds().max.y;
+        let mut order: Vec<usize> = (0..objects.len())
+            .filter(|&index| objects[index].is_bounded() && !objects[index].material().shadow_catcher)
+            .collect();
+        order.sort_by(|&a, &b| top(a).total_cmp(&top(b)));
+        let heights = order.first().zip(order.last()).map(|(&low, &high)| (top(low), top(high)));
+        for index in order {
+            let color = match (palette, heights) {
+                (Palette::Default, _) | (_, None) => self.color_of(objects[index].material()),
+                (_, Some((low, high))) => palette.sample((top(index) - low) / (high - low).max(1e-6)),
+            };
+            let (columns, rows) = layout.footprint(&objects[index]);
+            overlay::fill_rect(framebuffer, columns.start, rows.start, columns.len(), rows.len(), color, 1.0);
+        }
+
+        if let Some(object) = selected.and_then(|index| objects.get(index)) {
+            let (columns, rows) = layout.footprint(object);
+            if !columns.is_empty() && !rows.is_empty() {
+                let (x0, y0, x1, y1) = (columns.start as f32, rows.start as f32, (columns.end - 1) as f32, (rows.end - 1) as f32);
+                for (from, to) in [((x0, y0), (x1, y0)), ((x1, y0), (x1, y1)), ((x1, y1), (x0, y1)), ((x0, y1), (x0, y0))] {
+                    overlay::draw_line(framebuffer, from, to, palette.category(3, SELECTED_COLOR));
+                }
+            }
+        }
+
+        // Centro de la órbita como una cruz y la cámara como una flecha
+        let (cx, cy) = layout.to_screen(camera.center.x, camera.center.z);
+        overlay::draw_line(framebuffer, (cx - 2.0, cy), (cx + 2.0, cy), CAMERA_COLOR);
+        overlay::draw_line(framebuffer, (cx, cy - 2.0), (cx, cy + 2.0), CAMERA_COLOR);
+        let tail = layout.to_screen(camera.eye.x, camera.eye.z);
+        let forward = Vec3::new(camera.center.x - camera.eye.x, 0.0, camera.center.z - camera.eye.z);
+        let (dx, dy) = if forward.magnitude() > 1e-6 {
+            let forward = forward.normalize() * ARROW_LENGTH;
+            (forward.x, forward.z)
+        } else {
+            // Mirando justo hacia abajo: la flecha sigue el vector arriba de la cámara
+            let up = Vec3::new(camera.up.x, 0.0, camera.up.z).try_normalize(1e-6).unwrap_or(Vec3::new(0.0, 0.0, -1.0)) * ARROW_LENGTH;
+            (up.x, up.z)
+        };
+        let head = (tail.0 + dx, tail.1 + dy);
+        overlay::draw_line(framebuffer, tail, head, CAMERA_COLOR);
+        for side in [-1.0, 1.0] {
+            let barb = (head.0 - dx * 0.4 - dy * 0.3 * side, head.1 - dy * 0.4 + dx * 0.3 * side);
+            overlay::draw_line(framebuffer, head, barb, CAMERA_COLOR);
+        }
+    }
+
+    // Punto (x, z) de la escena bajo el píxel dado, o None si cae fuera del mapa
+    pub fn world_at(&self, framebuffer_width: usize, objects: &[SceneObject], camera: &Camera, x: f32, y: f32) -> Option<(f32, f32)> {
+        Layout::new(self, framebuffer_width, objects, camera)?.to_world(x, y)
+    }
+}
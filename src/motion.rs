@@ -0,0 +1,178 @@
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::color::{linear_to_srgb, srgb_to_linear, Color};
+use crate::object::SceneObject;
+use crate::framebuffer::Framebuffer;
+use crate::light::{SceneLight, DAY_DURATION};
+use crate::renderer::{render, sky_color, RenderStats};
+use crate::settings::RenderSettings;
+
+// Desenfoque de movimiento para los renders sin ventana. Un frame cubre un
+// intervalo de tiempo en el que la cámara y el sol se mueven; con el obturador
+// abierto durante la fracción final `shutter` de ese intervalo, el frame es el
+// promedio de varios renders en instantes repartidos dentro de esa fracción.
+// Cada instante cae en su propio tramo con un desplazamiento fijo, así dos
+// renders de la misma escena dan la misma imagen.
+//
+// Los colores se promedian en luz lineal, como al sobremuestrear, así una
+// estela clara sobre un fondo oscuro no sale más oscura de lo que es; con el
+// fondo transparente además se pesan por su alfa, así el cielo que queda
+// detrás no se mete en los bordes de lo que se mueve.
+//
+// Las texturas animadas quedan en el cuadro de la escena durante todo el
+// obturador: solo se mueven la cámara y las luces, así la lava o el portal
+// no se desenfocan con su animación.
+
+// Cámara y luces al principio y al final de un frame
+#[derive(Debug, Clone)]
+pub struct FrameMotion {
+    start_camera: Camera,
+    end_camera: Camera,
+    start_lights: Vec<SceneLight>,
+    end_lights: Vec<SceneLight>,
+}
+
+fn lerp(a: &Vec3, b: &Vec3, t: f32) -> Vec3 {
+    a + (b - a) * t
+}
+
+impl FrameMotion {
+    pub fn new(start: (&Camera, &[SceneLight]), end: (&Camera, &[SceneLight])) -> Self {
+        FrameMotion {
+            start_camera: start.0.clone(),
+            end_camera: end.0.clone(),
+            start_lights: start.1.to_vec(),
+            end_lights: end.1.to_vec(),
+        }
+    }
+
+    // Frame de `duration` segundos que termina en el estado dado, con la
+    // cámara quieta y el sol avanzando por el ciclo día/noche como en la
+    // ventana. El sol de un archivo de escena no está sobre su recorrido hasta
+    // el primer frame, así que se lo lleva ahí.
+    pub fn ending_at(camera: &Camera, lights: &[SceneLight], duration: f32) -> Self {
+        let at_offset = |offset: f32| -> Vec<SceneLight> {
+            lights
+                .iter()
+                .map(|light| {
+                    let mut light = light.clone();
+                    if light.is_sun {
+                        light.set_time((light.time() - offset).rem_euclid(DAY_DURATION));
+                    }
+                    light
+                })
+                .collect()
+        };
+        FrameMotion::new((camera, &at_offset(duration)), (camera, &at_offset(0.0)))
+    }
+
+    // Si nada cambia durante el frame, en cuyo caso alcanza con un render
+    pub fn is_static(&self) -> bool {
+        let (a, b) = (&self.start_camera, &self.end_camera);
+        let camera_still = a.eye == b.eye && a.center == b.center && a.up == b.up && a.fov == b.fov;
+        let lights_still = self.start_lights.iter().zip(&self.end_lights).all(|(a, b)| {
+            a.position == b.position && a.color == b.color && a.intensity == b.intensity
+        });
+        camera_still && lights_still
+    }
+
+    // Cámara y luces en la fracción `t` del frame, de 0 (principio) a 1 (final).
+    // El sol sigue su recorrido; la cámara gira alrededor de su centro cuando
+    // éste no cambia, y si no se interpola en línea recta.
+    pub fn at(&self, t: f32) -> (Camera, Vec<SceneLight>) {
+        if t >= 1.0 {
+            return (self.end_camera.clone(), self.end_lights.clone());
+        }
+        let (a, b) = (&self.start_camera, &self.end_camera);
+        let center = lerp(&a.center, &b.center, t);
+        let eye = if a.center == b.center {
+            let (from, to) = (a.eye - a.center, b.eye - b.center);
+            let radius = from.magnitude() + (to.magnitude() - from.magnitude()) * t;
+            let direction = lerp(&from.normalize(), &to.normalize(), t);
+            center + direction.normalize() * radius
+        } else {
+            lerp(&a.eye, &b.eye, t)
+        };
+        let camera = Camera {
+            fov: a.fov + (b.fov - a.fov) * t,
+            ..Camera::new(eye, center, lerp(&a.up, &b.up, t).normalize())
+        };
+
+        let lights = self
+            .start_lights
+            .iter()
+            .zip(&self.end_lights)
+            .map(|(start, end)| {
+                let mut light = end.clone();
+                if start.is_sun && start.time() != end.time() {
+                    let elapsed = (end.time() - start.time()).rem_euclid(DAY_DURATION);
+                    light.set_time(start.time() + elapsed * t);
+                } else {
+                    light.position = lerp(&start.position, &end.position, t);
+                    light.intensity = start.intensity + (end.intensity - start.intensity) * t;
+                }
+                light
+            })
+            .collect();
+        (camera, lights)
+    }
+}
+
+// Desplazamiento dentro de cada tramo, de 0 a 1: la parte fraccionaria de
+// múltiplos del número áureo, que no se amontona
+fn jitter(sample: usize) -> f32 {
+    (0.5 + sample as f32 * 0.618_034).fract()
+}
+
+// Renderiza el frame con el obturador abierto durante la fracción final
+// `shutter` del frame, promediando `samples` renders (uno solo si la escena
+// está quieta o el obturador cerrado)
+pub fn render_motion_blur(
+    framebuffer: &mut Framebuffer,
+    objects: &[SceneObject],
+    motion: &FrameMotion,
+    shutter: f32,
+    samples: usize,
+    settings: &RenderSettings,
+) -> RenderStats {
+    let shutter = shutter.clamp(0.0, 1.0);
+    if motion.is_static() || shutter <= 0.0 || samples <= 1 {
+        let (camera, lights) = motion.at(1.0);
+        return render(framebuffer, objects, &camera, &lights, sky_color(&lights), settings);
+    }
+
+    let pixels = framebuffer.width * framebuffer.height;
+    let mut color = vec![[0.0f32; 3]; pixels];
+    let mut weight = vec![0.0f32; pixels];
+    let mut alpha = vec![0.0f32; pixels];
+    let mut stats = RenderStats::default();
+    for sample in 0..samples {
+        let t = 1.0 - shutter * (sample as f32 + jitter(sample)) / samples as f32;
+        let (camera, lights) = motion.at(t);
+        let mut sample_buffer = Framebuffer::new(framebuffer.width, framebuffer.height);
+        let sample_stats = render(&mut sample_buffer, objects, &camera, &lights, sky_color(&lights), settings);
+        stats.primary_rays += sample_stats.primary_rays;
+
+        for (index, pixel) in sample_buffer.buffer.iter().enumerate() {
+            let coverage = if settings.transparent { sample_buffer.alpha[index] as f32 / 255.0 } else { 1.0 };
+            for (sum, channel) in color[index].iter_mut().zip([pixel.red(), pixel.green(), pixel.blue()]) {
+                *sum += srgb_to_linear(channel) * coverage;
+            }
+            weight[index] += coverage;
+            alpha[index] += sample_buffer.alpha[index] as f32;
+        }
+        // La profundidad es la del instante más cercano al final del frame
+        if sample == 0 {
+            framebuffer.depth.copy_from_slice(&sample_buffer.depth);
+        }
+    }
+
+    for index in 0..pixels {
+        let total = weight[index];
+        let [r, g, b] = color[index].map(|sum| linear_to_srgb(if total > 0.0 { sum / total } else { 0.0 }));
+        framebuffer.buffer[index] = Color::new(r, g, b);
+        framebuffer.alpha[index] = (alpha[index] / samples as f32).round() as u8;
+    }
+    stats
+}
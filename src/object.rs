@@ -0,0 +1,194 @@
+use nalgebra_glm::Vec3;
+
+use crate::bvh::Aabb;
+use crate::cube::Cube;
+use crate::material::Material;
+use crate::plane::Plane;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::sphere::Sphere;
+
+// Objeto de la escena. Casi todo se arma con cajas alineadas con los ejes, y
+// lo que solo tiene sentido para ellas (la grilla de bloques, la fusión, las
+// calcomanías, el bisel, el horneado) mira `as_cube` y deja las demás formas
+// como están. El plano es la única forma sin caja: ver `is_bounded`.
+#[derive(Clone)]
+pub enum SceneObject {
+    Cube(Cube),
+    Sphere(Sphere),
+    Plane(Plane),
+}
+
+impl SceneObject {
+    // Nombre de la forma en las estadísticas y en los archivos de escena
+    pub fn kind(&self) -> &'static str {
+        match self {
+            SceneObject::Cube(_) => "cube",
+            SceneObject::Sphere(_) => "sphere",
+            SceneObject::Plane(_) => "plane",
+        }
+    }
+
+    pub fn material(&self) -> &Material {
+        match self {
+            SceneObject::Cube(cube) => &cube.material,
+            SceneObject::Sphere(sphere) => &sphere.material,
+            SceneObject::Plane(plane) => &plane.material,
+        }
+    }
+
+    // El material del objeto y los de las caras de un cubo que los tenga
+    pub fn materials(&self) -> impl Iterator<Item = &Material> {
+        let faces = self.as_cube().map_or(&[][..], |cube| &cube.face_materials);
+        std::iter::once(self.material()).chain(faces.iter().map(|(_, material)| material))
+    }
+
+    pub fn materials_mut(&mut self) -> impl Iterator<Item = &mut Material> {
+        let (material, faces) = match self {
+            SceneObject::Cube(cube) => (&mut cube.material, &mut cube.face_materials[..]),
+            SceneObject::Sphere(sphere) => (&mut sphere.material, &mut [][..]),
+            SceneObject::Plane(plane) => (&mut plane.material, &mut [][..]),
+        };
+        std::iter::once(material).chain(faces.iter_mut().map(|(_, material)| material))
+    }
+
+    pub fn material_mut(&mut self) -> &mut Material {
+        match self {
+            SceneObject::Cube(cube) => &mut cube.material,
+            SceneObject::Sphere(sphere) => &mut sphere.material,
+            SceneObject::Plane(plane) => &mut plane.material,
+        }
+    }
+
+    pub fn group(&self) -> &str {
+        match self {
+            SceneObject::Cube(cube) => &cube.group,
+            SceneObject::Sphere(sphere) => &sphere.group,
+            SceneObject::Plane(plane) => &plane.group,
+        }
+    }
+
+    pub fn in_group(self, group: &str) -> Self {
+        match self {
+            SceneObject::Cube(cube) => SceneObject::Cube(cube.in_group(group)),
+            SceneObject::Sphere(sphere) => SceneObject::Sphere(sphere.in_group(group)),
+            SceneObject::Plane(plane) => SceneObject::Plane(plane.in_group(group)),
+        }
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            SceneObject::Cube(cube) => &cube.name,
+            SceneObject::Sphere(sphere) => &sphere.name,
+            SceneObject::Plane(plane) => &plane.name,
+        }
+    }
+
+    // Caja alineada con los ejes que lo envuelve. La del plano es solo su
+    // punto, así que lo que use las cajas para ubicar o encuadrar los objetos
+    // tiene que dejar aparte los que no están acotados
+    pub fn bounds(&self) -> Aabb {
+        match self {
+            SceneObject::Cube(cube) => Aabb { min: cube.min, max: cube.max },
+            SceneObject::Sphere(sphere) => {
+                let extent = Vec3::repeat(sphere.radius);
+                Aabb { min: sphere.center - extent, max: sphere.center + extent }
+            }
+            SceneObject::Plane(plane) => Aabb { min: plane.point, max: plane.point },
+        }
+    }
+
+    // Si `bounds` lo envuelve de verdad
+    pub fn is_bounded(&self) -> bool {
+        !matches!(self, SceneObject::Plane(_))
+    }
+
+    pub fn center(&self) -> Vec3 {
+        let bounds = self.bounds();
+        (bounds.min + bounds.max) * 0.5
+    }
+
+    pub fn volume(&self) -> f32 {
+        match self {
+            SceneObject::Cube(cube) => cube.volume(),
+            SceneObject::Sphere(sphere) => sphere.volume(),
+            // No se cuenta: el semiespacio de atrás no tiene fin
+            SceneObject::Plane(_) => 0.0,
+        }
+    }
+
+    pub fn contains(&self, point: &Vec3) -> bool {
+        match self {
+            SceneObject::Cube(cube) => cube.contains(point),
+            SceneObject::Sphere(sphere) => sphere.contains(point),
+            SceneObject::Plane(plane) => plane.contains(point),
+        }
+    }
+
+    // Distancia hasta donde deja el objeto un rayo que parte de adentro; sin
+    // fin si no sale, como al bajar por debajo de un plano
+    pub fn exit_distance(&self, origin: &Vec3, direction: &Vec3) -> f32 {
+        match self {
+            SceneObject::Cube(cube) => cube.exit_distance(origin, direction),
+            // Con el origen adentro la esfera y el plano ya devuelven la salida
+            SceneObject::Sphere(_) | SceneObject::Plane(_) => {
+                let hit = self.ray_intersect(origin, direction);
+                if hit.is_intersecting { hit.distance } else { f32::INFINITY }
+            }
+        }
+    }
+
+    // Lo mueve sin cambiarle el tamaño
+    pub fn translate(&mut self, offset: &Vec3) {
+        match self {
+            SceneObject::Cube(cube) => {
+                cube.min += offset;
+                cube.max += offset;
+            }
+            SceneObject::Sphere(sphere) => sphere.center += offset,
+            SceneObject::Plane(plane) => plane.point += offset,
+        }
+    }
+
+    pub fn as_cube(&self) -> Option<&Cube> {
+        match self {
+            SceneObject::Cube(cube) => Some(cube),
+            SceneObject::Sphere(_) | SceneObject::Plane(_) => None,
+        }
+    }
+}
+
+impl From<Cube> for SceneObject {
+    fn from(cube: Cube) -> Self {
+        SceneObject::Cube(cube)
+    }
+}
+
+impl From<Sphere> for SceneObject {
+    fn from(sphere: Sphere) -> Self {
+        SceneObject::Sphere(sphere)
+    }
+}
+
+impl From<Plane> for SceneObject {
+    fn from(plane: Plane) -> Self {
+        SceneObject::Plane(plane)
+    }
+}
+
+impl RayIntersect for SceneObject {
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect {
+        match self {
+            SceneObject::Cube(cube) => cube.ray_intersect(ray_origin, ray_direction),
+            SceneObject::Sphere(sphere) => sphere.ray_intersect(ray_origin, ray_direction),
+            SceneObject::Plane(plane) => plane.ray_intersect(ray_origin, ray_direction),
+        }
+    }
+
+    fn distance(&self, point: &Vec3) -> f32 {
+        match self {
+            SceneObject::Cube(cube) => cube.distance(point),
+            SceneObject::Sphere(sphere) => sphere.distance(point),
+            SceneObject::Plane(plane) => plane.distance(point),
+        }
+    }
+}
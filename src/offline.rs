@@ -0,0 +1,101 @@
+use crate::camera::Camera;
+use crate::framebuffer::Framebuffer;
+use crate::godrays::apply_god_rays;
+use crate::light::SceneLight;
+use crate::object::SceneObject;
+use crate::renderer::{render, sky_color};
+use crate::settings::{Crop, RenderSettings};
+
+// Tamaño y calidad del render fuera de la ventana. Sin `samples` ni
+// `max_depth` se usan los de la vista interactiva
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct OfflineSettings {
+    pub width: usize,
+    pub height: usize,
+    pub samples: Option<u32>,
+    pub max_depth: Option<u32>,
+}
+
+impl Default for OfflineSettings {
+    fn default() -> Self {
+        OfflineSettings {
+            width: 3840,
+            height: 2160,
+            samples: Some(4),
+            max_depth: None,
+        }
+    }
+}
+
+// Render a otra resolución que el de la ventana, con la escena, la cámara y
+// las luces del momento en que arrancó. Se traza de a franjas de filas con el
+// recorte de los ajustes, así la ventana sigue atendiendo eventos entre una y
+// otra; el halo de cada franja alcanza a las vecinas porque el recorte traza
+// un borde de más. Los rayos de sol miran la imagen entera y se aplican al
+// terminar, y los reflejos planares se trazan como los demás en lugar de
+// rehacer el render espejado en cada franja.
+pub struct OfflineRender {
+    pub framebuffer: Framebuffer,
+    objects: Vec<SceneObject>,
+    camera: Camera,
+    lights: Vec<SceneLight>,
+    settings: RenderSettings,
+    next_row: usize,
+}
+
+impl OfflineRender {
+    pub fn new(objects: &[SceneObject], camera: &Camera, lights: &[SceneLight], settings: &RenderSettings, offline: &OfflineSettings) -> Self {
+        let settings = RenderSettings {
+            samples_per_pixel: offline.samples.unwrap_or(settings.samples_per_pixel),
+            max_depth: offline.max_depth.unwrap_or(settings.max_depth),
+            // El render completo siempre: a escala entera y sin interpolar
+            render_scale: 1.0,
+            adaptive: None,
+            crop: None,
+            ..settings.clone()
+        };
+        let mut objects = objects.to_vec();
+        for object in &mut objects {
+            object.material_mut().planar_reflection = false;
+        }
+        OfflineRender {
+            framebuffer: Framebuffer::new(offline.width.max(1), offline.height.max(1)),
+            objects,
+            camera: camera.clone(),
+            lights: lights.to_vec(),
+            settings,
+            next_row: 0,
+        }
+    }
+
+    // Fracción de las filas ya trazadas
+    pub fn progress(&self) -> f32 {
+        self.next_row as f32 / self.framebuffer.height as f32
+    }
+
+    pub fn is_done(&self) -> bool {
+        self.next_row >= self.framebuffer.height
+    }
+
+    // Traza las filas siguientes, tantas como entren en unos `rays` rayos
+    // primarios y al menos una. Devuelve si la imagen quedó completa
+    pub fn step(&mut self, rays: usize) -> bool {
+        if self.is_done() {
+            return true;
+        }
+        let width = self.framebuffer.width;
+        let rows = (rays / (width * self.settings.samples_per_pixel.max(1) as usize)).max(1);
+        let height = rows.min(self.framebuffer.height - self.next_row);
+        let crop = Crop { x: 0, y: self.next_row, width, height };
+        let settings = RenderSettings { crop: Some(crop), god_rays: None, ..self.settings.clone() };
+        render(&mut self.framebuffer, &self.objects, &self.camera, &self.lights, sky_color(&self.lights), &settings);
+        self.next_row += height;
+
+        if self.is_done() {
+            if let Some(god_rays) = &self.settings.god_rays {
+                apply_god_rays(&mut self.framebuffer, &self.camera, &self.lights, self.camera.perspective_scale(), god_rays);
+            }
+        }
+        self.is_done()
+    }
+}
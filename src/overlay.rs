@@ -0,0 +1,219 @@
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+
+// Fuente de mapa de bits de 3x5 píxeles: cada fila usa los 3 bits bajos
+const GLYPH_WIDTH: usize = 3;
+const GLYPH_HEIGHT: usize = 5;
+const LINE_HEIGHT: usize = GLYPH_HEIGHT + 2;
+
+fn glyph(c: char) -> [u8; GLYPH_HEIGHT] {
+    match c.to_ascii_uppercase() {
+        '0' => [0b111, 0b101, 0b101, 0b101, 0b111],
+        '1' => [0b010, 0b110, 0b010, 0b010, 0b111],
+        '2' => [0b111, 0b001, 0b111, 0b100, 0b111],
+        '3' => [0b111, 0b001, 0b111, 0b001, 0b111],
+        '4' => [0b101, 0b101, 0b111, 0b001, 0b001],
+        '5' => [0b111, 0b100, 0b111, 0b001, 0b111],
+        '6' => [0b111, 0b100, 0b111, 0b101, 0b111],
+        '7' => [0b111, 0b001, 0b001, 0b001, 0b001],
+        '8' => [0b111, 0b101, 0b111, 0b101, 0b111],
+        '9' => [0b111, 0b101, 0b111, 0b001, 0b111],
+        'A' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'B' => [0b110, 0b101, 0b110, 0b101, 0b110],
+        'C' => [0b011, 0b100, 0b100, 0b100, 0b011],
+        'D' => [0b110, 0b101, 0b101, 0b101, 0b110],
+        'E' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        'F' => [0b111, 0b100, 0b110, 0b100, 0b100],
+        'G' => [0b011, 0b100, 0b101, 0b101, 0b011],
+        'H' => [0b101, 0b101, 0b111, 0b101, 0b101],
+        'I' | 'Í' => [0b111, 0b010, 0b010, 0b010, 0b111],
+        'J' => [0b001, 0b001, 0b001, 0b101, 0b010],
+        'K' => [0b101, 0b101, 0b110, 0b101, 0b101],
+        'L' => [0b100, 0b100, 0b100, 0b100, 0b111],
+        'M' => [0b101, 0b111, 0b111, 0b101, 0b101],
+        'N' | 'Ñ' => [0b110, 0b101, 0b101, 0b101, 0b101],
+        'O' | 'Ó' => [0b010, 0b101, 0b101, 0b101, 0b010],
+        'P' => [0b110, 0b101, 0b110, 0b100, 0b100],
+        'Q' => [0b010, 0b101, 0b101, 0b110, 0b011],
+        'R' => [0b110, 0b101, 0b110, 0b101, 0b101],
+        'S' => [0b011, 0b100, 0b010, 0b001, 0b110],
+        'T' => [0b111, 0b010, 0b010, 0b010, 0b010],
+        'U' | 'Ú' => [0b101, 0b101, 0b101, 0b101, 0b111],
+        'V' => [0b101, 0b101, 0b101, 0b101, 0b010],
+        'W' => [0b101, 0b101, 0b111, 0b111, 0b101],
+        'X' => [0b101, 0b101, 0b010, 0b101, 0b101],
+        'Y' => [0b101, 0b101, 0b010, 0b010, 0b010],
+        'Z' => [0b111, 0b001, 0b010, 0b100, 0b111],
+        'Á' => [0b010, 0b101, 0b111, 0b101, 0b101],
+        'É' => [0b111, 0b100, 0b110, 0b100, 0b111],
+        ' ' => [0, 0, 0, 0, 0],
+        '.' => [0b000, 0b000, 0b000, 0b000, 0b010],
+        ',' => [0b000, 0b000, 0b000, 0b010, 0b100],
+        ':' => [0b000, 0b010, 0b000, 0b010, 0b000],
+        ';' => [0b000, 0b010, 0b000, 0b010, 0b100],
+        '-' => [0b000, 0b000, 0b111, 0b000, 0b000],
+        '+' => [0b000, 0b010, 0b111, 0b010, 0b000],
+        '=' => [0b000, 0b111, 0b000, 0b111, 0b000],
+        '_' => [0b000, 0b000, 0b000, 0b000, 0b111],
+        '/' => [0b001, 0b001, 0b010, 0b100, 0b100],
+        '%' => [0b101, 0b001, 0b010, 0b100, 0b101],
+        '(' => [0b010, 0b100, 0b100, 0b100, 0b010],
+        ')' => [0b010, 0b001, 0b001, 0b001, 0b010],
+        '[' => [0b110, 0b100, 0b100, 0b100, 0b110],
+        ']' => [0b011, 0b001, 0b001, 0b001, 0b011],
+        '<' => [0b001, 0b010, 0b100, 0b010, 0b001],
+        '>' => [0b100, 0b010, 0b001, 0b010, 0b100],
+        '#' => [0b101, 0b111, 0b101, 0b111, 0b101],
+        '!' => [0b010, 0b010, 0b010, 0b000, 0b010],
+        '*' => [0b000, 0b101, 0b010, 0b101, 0b000],
+        '|' => [0b010, 0b010, 0b010, 0b010, 0b010],
+        '\'' => [0b010, 0b010, 0b000, 0b000, 0b000],
+        '"' => [0b101, 0b101, 0b000, 0b000, 0b000],
+        _ => [0b111, 0b001, 0b010, 0b000, 0b010],
+    }
+}
+
+// Mezcla un píxel del framebuffer con un color según la opacidad dada
+pub fn blend_pixel(framebuffer: &mut Framebuffer, x: usize, y: usize, color: Color, alpha: f32) {
+    if x >= framebuffer.width || y >= framebuffer.height {
+        return;
+    }
+    let index = y * framebuffer.width + x;
+    let background = framebuffer.buffer[index];
+    framebuffer.buffer[index] = background * (1.0 - alpha) + color * alpha;
+}
+
+pub fn fill_rect(framebuffer: &mut Framebuffer, x: usize, y: usize, width: usize, height: usize, color: Color, alpha: f32) {
+    for py in y..(y + height).min(framebuffer.height) {
+        for px in x..(x + width).min(framebuffer.width) {
+            blend_pixel(framebuffer, px, py, color, alpha);
+        }
+    }
+}
+
+pub fn text_width(text: &str) -> usize {
+    text.chars().count() * (GLYPH_WIDTH + 1)
+}
+
+pub fn draw_text(framebuffer: &mut Framebuffer, x: usize, y: usize, text: &str, color: Color) {
+    for (i, c) in text.chars().enumerate() {
+        let rows = glyph(c);
+        let origin_x = x + i * (GLYPH_WIDTH + 1);
+        for (row, bits) in rows.iter().enumerate() {
+            for column in 0..GLYPH_WIDTH {
+                if bits & (1 << (GLYPH_WIDTH - 1 - column)) != 0 {
+                    blend_pixel(framebuffer, origin_x + column, y + row, color, 1.0);
+                }
+            }
+        }
+    }
+}
+
+pub fn panel_size(lines: &[String]) -> (usize, usize) {
+    let width = lines.iter().map(|line| text_width(line)).max().unwrap_or(0) + 4;
+    let height = lines.len() * LINE_HEIGHT + 2;
+    (width, height)
+}
+
+// Panel semitransparente con una línea de texto por renglón
+pub fn draw_panel(framebuffer: &mut Framebuffer, x: usize, y: usize, lines: &[String]) {
+    let (width, height) = panel_size(lines);
+    fill_rect(framebuffer, x, y, width, height, Color::black(), 0.6);
+
+    for (i, line) in lines.iter().enumerate() {
+        draw_text(framebuffer, x + 2, y + 2 + i * LINE_HEIGHT, line, Color::white());
+    }
+}
+
+// Panel con un título, una barra apilada con la proporción de cada entrada y
+// debajo una línea por entrada con su color y su texto
+pub fn draw_breakdown(framebuffer: &mut Framebuffer, x: usize, y: usize, title: &str, entries: &[(String, f32, Color)]) {
+    const SWATCH: usize = GLYPH_HEIGHT;
+    let mut lines = vec![title.to_string(), String::new()];
+    lines.extend(entries.iter().map(|(label, _, _)| format!("  {}", label)));
+    let (width, _) = panel_size(&lines);
+    draw_panel(framebuffer, x, y, &lines);
+
+    let bar_x = x + 2;
+    let bar_y = y + 2 + LINE_HEIGHT;
+    let bar_width = width - 4;
+    let total: f32 = entries.iter().map(|(_, value, _)| value).sum();
+    if total > 0.0 {
+        let mut start = 0.0;
+        for (_, value, color) in entries {
+            let from = (start / total * bar_width as f32).round() as usize;
+            start += value;
+            let to = (start / total * bar_width as f32).round() as usize;
+            fill_rect(framebuffer, bar_x + from, bar_y, to - from, SWATCH, *color, 1.0);
+        }
+    }
+
+    for (i, (_, _, color)) in entries.iter().enumerate() {
+        fill_rect(framebuffer, x + 2, bar_y + (i + 1) * LINE_HEIGHT, SWATCH, SWATCH, *color, 1.0);
+    }
+}
+
+// Panel junto al cursor: se abre abajo a la derecha y se ajusta a los bordes
+// de la pantalla, pasando al otro lado del cursor si no cabe
+pub fn draw_tooltip(framebuffer: &mut Framebuffer, cursor_x: usize, cursor_y: usize, lines: &[String]) {
+    const OFFSET: usize = 6;
+    let (width, height) = panel_size(lines);
+
+    let place = |cursor: usize, size: usize, limit: usize| {
+        if cursor + OFFSET + size <= limit {
+            cursor + OFFSET
+        } else if cursor >= OFFSET + size {
+            cursor - OFFSET - size
+        } else {
+            limit.saturating_sub(size)
+        }
+    };
+    let x = place(cursor_x, width, framebuffer.width);
+    let y = place(cursor_y, height, framebuffer.height);
+    draw_panel(framebuffer, x, y, lines);
+}
+
+// Línea de un píxel de grosor entre dos puntos en coordenadas de pantalla
+pub fn draw_line(framebuffer: &mut Framebuffer, from: (f32, f32), to: (f32, f32), color: Color) {
+    line_pixels(framebuffer.width, framebuffer.height, from, to, |x, y, _| {
+        blend_pixel(framebuffer, x, y, color, 1.0);
+    });
+}
+
+// Recorre los píxeles de la línea dentro de una pantalla de `width` x `height`
+// y pasa a `plot` cada uno con su posición `t` de 0 a 1 entre `from` y `to`.
+// Se recorta primero al rectángulo visible para no recorrer píxeles de más.
+pub fn line_pixels(width: usize, height: usize, from: (f32, f32), to: (f32, f32), mut plot: impl FnMut(usize, usize, f32)) {
+    let max_x = width as f32 - 1.0;
+    let max_y = height as f32 - 1.0;
+    let (dx, dy) = (to.0 - from.0, to.1 - from.1);
+
+    // Recorte paramétrico de Liang-Barsky
+    let (mut t0, mut t1) = (0.0f32, 1.0f32);
+    for (p, q) in [(-dx, from.0), (dx, max_x - from.0), (-dy, from.1), (dy, max_y - from.1)] {
+        if p == 0.0 {
+            if q < 0.0 {
+                return;
+            }
+        } else {
+            let t = q / p;
+            if p < 0.0 {
+                t0 = t0.max(t);
+            } else {
+                t1 = t1.min(t);
+            }
+        }
+    }
+    if t0 > t1 {
+        return;
+    }
+
+    let (x0, y0) = (from.0 + dx * t0, from.1 + dy * t0);
+    let steps = (dx.abs().max(dy.abs()) * (t1 - t0)).ceil().max(1.0) as usize;
+    for step in 0..=steps {
+        let t = step as f32 / steps as f32 * (t1 - t0);
+        let x = (x0 + dx * t).round() as usize;
+        let y = (y0 + dy * t).round() as usize;
+        plot(x, y, t0 + t);
+    }
+}
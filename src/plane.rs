@@ -0,0 +1,89 @@
+use nalgebra_glm::Vec3;
+
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::texture::Footprint;
+
+// Plano infinito, pensado como suelo bajo el diorama. No tiene una caja que lo
+// envuelva, así que lo que trabaja con cajas (el encuadre, el minimapa, el
+// mapa de sombras, los trozos) lo deja aparte; ver `SceneObject::is_bounded`.
+#[derive(Clone)]
+pub struct Plane {
+    pub point: Vec3,
+    pub normal: Vec3, // Unitaria; el lado de afuera es hacia donde apunta
+    pub material: Material,
+    // Unidades del mundo que abarca una repetición de la textura
+    pub tile: f32,
+    pub group: String, // Grupo o capa usado por el enlace de luces, como en `Cube`
+    pub name: String,
+}
+
+// Inclinación máxima con la que se alarga la huella, como en las caras de los cubos
+const MAX_ELONGATION: f32 = 16.0;
+
+impl Plane {
+    pub fn new(point: Vec3, normal: Vec3, material: &Material) -> Self {
+        Plane {
+            point,
+            normal: normal.try_normalize(1e-6).unwrap_or(Vec3::new(0.0, 1.0, 0.0)),
+            material: material.clone(),
+            tile: 1.0,
+            group: String::new(),
+            name: String::new(),
+        }
+    }
+
+    pub fn with_tile(mut self, tile: f32) -> Self {
+        self.tile = tile;
+        self
+    }
+
+    pub fn in_group(mut self, group: &str) -> Self {
+        self.group = group.to_string();
+        self
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    // Lo que queda detrás del plano cuenta como adentro
+    pub fn contains(&self, point: &Vec3) -> bool {
+        self.distance(point) <= 0.0
+    }
+
+    // Coordenadas planas según X y Z del mundo, una repetición cada `tile`
+    // unidades, así una textura de pasto o piedra se repite sin estirarse
+    pub fn uv(&self, point: &Vec3) -> (f32, f32) {
+        let tile = self.tile.max(1e-6);
+        ((point.x / tile).rem_euclid(1.0), (point.z / tile).rem_euclid(1.0))
+    }
+}
+
+impl RayIntersect for Plane {
+    // Choca desde los dos lados, con la normal siempre hacia afuera
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_dir: &Vec3) -> Intersect {
+        let facing = ray_dir.dot(&self.normal);
+        if facing.abs() < 1e-8 {
+            return Intersect::empty();
+        }
+        let distance = (self.point - ray_origin).dot(&self.normal) / facing;
+        if distance < 0.0 {
+            return Intersect::empty();
+        }
+
+        // La huella se alarga en la dirección del rayo proyectada sobre X y Z
+        let point = ray_origin + ray_dir * distance;
+        let cos = (facing / ray_dir.magnitude()).abs().max(1.0 / MAX_ELONGATION);
+        let along = ray_dir - self.normal * facing;
+        let length = (along.x * along.x + along.z * along.z).sqrt();
+        let axis = if length > 1e-6 { (along.x / length, along.z / length) } else { (1.0, 0.0) };
+        Intersect::new(point, self.normal, distance, self.material.clone())
+            .with_uv(self.uv(&point), Footprint { axis, elongation: 1.0 / cos })
+    }
+
+    fn distance(&self, point: &Vec3) -> f32 {
+        (point - self.point).dot(&self.normal)
+    }
+}
@@ -0,0 +1,136 @@
+//! Materiales afinados listos para usar, con los índices de refracción de
+//! verdad y pesos que no suman más luz de la que llega. También se llega a
+//! ellos como `materials::presets`. Se pueden cambiar de base con
+//! `Material::tinted` o `Material::textured`:
+//!
+//! ```
+//! use raycasting_diorama::color::Color;
+//! use raycasting_diorama::materials::presets;
+//! use raycasting_diorama::texture::Texture;
+//!
+//! let verde = presets::glass().tinted(Color::new(200, 240, 210));
+//! assert_eq!(verde.color, Color::new(200, 240, 210));
+//! assert_eq!(verde.refractive_index, 1.5);
+//!
+//! let piso = presets::polished_stone().textured(Texture::new("assets/obsidian.jpg").unwrap());
+//! assert!(piso.texture.is_some());
+//! ```
+
+use crate::color::Color;
+use crate::material::Material;
+
+/// Vidrio común (n = 1,5): casi todo pasa y refleja más en ángulo rasante
+pub fn glass() -> Material {
+    let mut glass = Material::builder()
+        .name("glass")
+        .color(Color::new(235, 245, 250))
+        .shininess(100.0)
+        .diffuse(0.0)
+        .specular(0.5)
+        .reflectivity(0.1)
+        .transparency(0.9)
+        .refractive_index(1.5)
+        .build();
+    glass.use_fresnel = true;
+    glass
+}
+
+/// Agua (n = 1,33) que se pone azul con la profundidad y proyecta cáusticas
+/// en el fondo
+pub fn water() -> Material {
+    let mut water = Material::builder()
+        .name("water")
+        .color(Color::new(20, 60, 110))
+        .shininess(80.0)
+        .diffuse(0.1)
+        .specular(0.5)
+        .reflectivity(0.1)
+        .transparency(0.85)
+        .refractive_index(1.33)
+        .build();
+    water.use_fresnel = true;
+    water.absorption = Color::new(70, 150, 220);
+    water.absorption_density = 1.5;
+    water.caustics = true;
+    water
+}
+
+/// Espejo: casi todo reflejo y un poco del color propio
+pub fn mirror() -> Material {
+    Material::builder()
+        .name("mirror")
+        .color(Color::new(230, 232, 235))
+        .shininess(500.0)
+        .diffuse(0.05)
+        .specular(0.9)
+        .reflectivity(0.9)
+        .build()
+}
+
+/// Metal cepillado: la mitad reflejo y un brillo ancho
+pub fn metal() -> Material {
+    Material::builder()
+        .name("metal")
+        .color(Color::new(180, 182, 190))
+        .shininess(60.0)
+        .diffuse(0.4)
+        .specular(0.8)
+        .reflectivity(0.5)
+        .build()
+}
+
+/// Piedra pulida, la roca gris del diorama
+pub fn polished_stone() -> Material {
+    Material::builder()
+        .name("polished_stone")
+        .color(Color::new(169, 169, 169))
+        .shininess(100.0)
+        .diffuse(0.6)
+        .specular(0.6)
+        .reflectivity(0.6)
+        .build()
+}
+
+/// Piedra que da luz cálida propia, con un halo suave
+pub fn glowstone() -> Material {
+    let mut glowstone = Material::builder()
+        .name("glowstone")
+        .color(Color::new(250, 215, 140))
+        .shininess(10.0)
+        .diffuse(0.9)
+        .specular(0.1)
+        .emission(Color::new(255, 200, 110))
+        .build();
+    glowstone.halo = 0.5;
+    glowstone
+}
+
+/// Lava semitransparente que emite naranja
+pub fn lava() -> Material {
+    let mut lava = Material::builder()
+        .name("lava")
+        .color(Color::new(255, 110, 20))
+        .shininess(30.0)
+        .diffuse(0.9)
+        .specular(0.3)
+        .transparency(0.5)
+        .emission(Color::new(255, 128, 0))
+        .build();
+    lava.halo = 0.3;
+    lava
+}
+
+/// Todos los preajustes, con el nombre de cada uno:
+///
+/// ```
+/// use raycasting_diorama::materials::presets;
+///
+/// let names: Vec<String> = presets::all().into_iter().map(|material| material.name).collect();
+/// assert!(names.contains(&"water".to_string()));
+/// for material in presets::all() {
+///     assert!(material.reflectivity + material.transparency <= 1.0);
+/// }
+/// ```
+pub fn all() -> Vec<Material> {
+    vec![glass(), water(), mirror(), metal(), polished_stone(), glowstone(), lava()]
+}
@@ -0,0 +1,142 @@
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::color::{Color, Palette};
+use crate::framebuffer::Framebuffer;
+use crate::overlay;
+
+// Largo con el que se dibuja un rayo que no impacta nada
+const MISS_LENGTH: f32 = 20.0;
+// Los tramos se recortan contra este plano antes de proyectarlos
+const NEAR_PLANE: f32 = 0.01;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SegmentKind {
+    Primary,
+    Reflect,
+    Refract,
+    Shadow,
+}
+
+impl SegmentKind {
+    pub fn name(self) -> &'static str {
+        match self {
+            SegmentKind::Primary => "primario",
+            SegmentKind::Reflect => "reflejo",
+            SegmentKind::Refract => "refraccion",
+            SegmentKind::Shadow => "sombra",
+        }
+    }
+
+    fn color(self, palette: Palette) -> Color {
+        match self {
+            SegmentKind::Primary => palette.category(3, Color::new(255, 230, 0)),
+            SegmentKind::Reflect => palette.category(1, Color::new(0, 230, 255)),
+            SegmentKind::Refract => palette.category(6, Color::new(255, 0, 200)),
+            SegmentKind::Shadow => Color::new(200, 200, 200),
+        }
+    }
+}
+
+// Un tramo del camino: `hit` indica si terminó en una superficie (para las
+// sombras, si algo tapó la luz)
+#[derive(Debug, Clone)]
+pub struct Segment {
+    pub kind: SegmentKind,
+    pub start: Vec3,
+    pub end: Vec3,
+    pub hit: bool,
+}
+
+// Camino completo de un rayo de sonda: todos sus tramos y una línea de
+// registro por rebote. El trazado marca con `begin` el tipo del próximo rayo
+// y `cast_ray` lo cierra con `hit` o `miss`.
+#[derive(Debug, Clone)]
+pub struct ProbePath {
+    pub pixel: (usize, usize),
+    pub segments: Vec<Segment>,
+    pub log: Vec<String>,
+    next_kind: SegmentKind,
+}
+
+impl ProbePath {
+    pub fn new(pixel: (usize, usize)) -> Self {
+        ProbePath {
+            pixel,
+            segments: Vec::new(),
+            log: Vec::new(),
+            next_kind: SegmentKind::Primary,
+        }
+    }
+
+    pub fn begin(&mut self, kind: SegmentKind) {
+        self.next_kind = kind;
+    }
+
+    pub fn hit(&mut self, origin: &Vec3, point: &Vec3) {
+        self.segments.push(Segment { kind: self.next_kind, start: *origin, end: *point, hit: true });
+    }
+
+    pub fn miss(&mut self, origin: &Vec3, direction: &Vec3) {
+        let end = origin + direction * MISS_LENGTH;
+        self.segments.push(Segment { kind: self.next_kind, start: *origin, end, hit: false });
+        self.note(format!("  {} sin impacto: cielo", self.next_kind.name()));
+    }
+
+    pub fn shadow(&mut self, point: &Vec3, light: &Vec3, blocked: bool) {
+        self.segments.push(Segment { kind: SegmentKind::Shadow, start: *point, end: *light, hit: blocked });
+    }
+
+    pub fn note(&mut self, line: String) {
+        self.log.push(line);
+    }
+
+    pub fn print(&self) {
+        println!("Sonda en el píxel ({}, {}):", self.pixel.0, self.pixel.1);
+        for line in &self.log {
+            println!("{}", line);
+        }
+    }
+
+    // Dibuja el camino proyectado con la cámara actual: un color por tipo de
+    // tramo, sombras bloqueadas en rojo y un marcador en cada impacto
+    pub fn draw(&self, framebuffer: &mut Framebuffer, camera: &Camera, perspective_scale: f32, palette: Palette) {
+        let width = framebuffer.width as f32;
+        let height = framebuffer.height as f32;
+        let aspect_ratio = width / height;
+        let (_, _, forward) = camera.basis();
+
+        let to_screen = |point: &Vec3| {
+            let (screen_x, screen_y) = camera.project(point, aspect_ratio, perspective_scale)?;
+            Some(((screen_x + 1.0) * 0.5 * width, (1.0 - screen_y) * 0.5 * height))
+        };
+
+        for segment in &self.segments {
+            // Recorta el tramo contra el plano cercano de la cámara
+            let depth_start = (segment.start - camera.eye).dot(&forward) - NEAR_PLANE;
+            let depth_end = (segment.end - camera.eye).dot(&forward) - NEAR_PLANE;
+            if depth_start <= 0.0 && depth_end <= 0.0 {
+                continue;
+            }
+            let lerp = |t: f32| segment.start + (segment.end - segment.start) * t;
+            let start = if depth_start > 0.0 { segment.start } else { lerp(depth_start / (depth_start - depth_end)) };
+            let end = if depth_end > 0.0 { segment.end } else { lerp(depth_start / (depth_start - depth_end)) };
+
+            let (Some(a), Some(b)) = (to_screen(&start), to_screen(&end)) else {
+                continue;
+            };
+
+            let color = if segment.kind == SegmentKind::Shadow && segment.hit {
+                palette.category(5, Color::new(255, 40, 40))
+            } else {
+                segment.kind.color(palette)
+            };
+            overlay::draw_line(framebuffer, a, b, color);
+
+            if segment.hit && segment.kind != SegmentKind::Shadow && depth_end > 0.0 {
+                let (x, y) = (b.0.max(1.0) as usize, b.1.max(1.0) as usize);
+                overlay::fill_rect(framebuffer, x - 1, y - 1, 3, 3, Color::white(), 1.0);
+            }
+        }
+    }
+}
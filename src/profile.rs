@@ -0,0 +1,287 @@
+use std::cell::RefCell;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Mutex, OnceLock};
+use std::time::{Duration, Instant};
+
+use crate::color::{Color, Palette};
+
+// Perfilado ligero por frame. Cada hilo acumula en su estado local el tiempo
+// exclusivo de cada tramo (un tramo anidado pausa al que lo contiene) y al
+// terminar el frame se suman los de todos los hilos de rayon. Desactivado, un
+// tramo cuesta una sola lectura atómica.
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Span {
+    Primary,
+    Shadow,
+    Secondary,
+    Post,
+    Present,
+}
+
+const SPAN_COUNT: usize = 5;
+
+impl Span {
+    pub const ALL: [Span; SPAN_COUNT] = [Span::Primary, Span::Shadow, Span::Secondary, Span::Post, Span::Present];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Span::Primary => "primarios",
+            Span::Shadow => "sombras",
+            Span::Secondary => "reflejo/refraccion",
+            Span::Post => "post",
+            Span::Present => "presentacion",
+        }
+    }
+
+    pub fn color(self, palette: Palette) -> Color {
+        match self {
+            Span::Primary => palette.category(0, Color::from_hex(0xffc020)),
+            Span::Shadow => palette.category(4, Color::from_hex(0x8080a0)),
+            Span::Secondary => palette.category(1, Color::from_hex(0x20c0ff)),
+            Span::Post => palette.category(6, Color::from_hex(0xff40c0)),
+            Span::Present => palette.category(2, Color::from_hex(0x60e060)),
+        }
+    }
+}
+
+static ENABLED: AtomicBool = AtomicBool::new(false);
+static CAPTURE: AtomicBool = AtomicBool::new(false);
+static EPOCH: OnceLock<Instant> = OnceLock::new();
+static FRAME_START: Mutex<Option<Instant>> = Mutex::new(None);
+
+// Un evento completo ("X") del formato de trazas de Chrome
+#[derive(Debug, Clone)]
+struct TraceEvent {
+    name: String,
+    thread: usize,
+    start: Duration,
+    duration: Duration,
+    // Tiempo exclusivo de cada tramo mientras duró el evento
+    breakdown: [Duration; SPAN_COUNT],
+}
+
+#[derive(Default)]
+struct ThreadState {
+    totals: [Duration; SPAN_COUNT],
+    current: Option<(Span, Instant)>,
+    events: Vec<TraceEvent>,
+}
+
+impl ThreadState {
+    // Carga al tramo en curso el tiempo transcurrido hasta `now`
+    fn charge(&mut self, now: Instant) {
+        if let Some((span, start)) = self.current {
+            self.totals[span as usize] += now - start;
+            self.current = Some((span, now));
+        }
+    }
+}
+
+thread_local! {
+    static STATE: RefCell<ThreadState> = RefCell::new(ThreadState::default());
+}
+
+fn thread_index() -> usize {
+    rayon::current_thread_index().map_or(0, |index| index + 1)
+}
+
+// Inicio de un evento de traza pendiente de cerrar
+struct TraceStart {
+    name: String,
+    start: Instant,
+    totals: [Duration; SPAN_COUNT],
+}
+
+impl TraceStart {
+    fn begin(name: String) -> Self {
+        let start = Instant::now();
+        let totals = STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.charge(start);
+            state.totals
+        });
+        TraceStart { name, start, totals }
+    }
+
+    fn finish(self, state: &mut ThreadState, now: Instant) {
+        let epoch = *EPOCH.get_or_init(Instant::now);
+        let mut breakdown = state.totals;
+        for (total, before) in breakdown.iter_mut().zip(self.totals) {
+            *total -= before;
+        }
+        state.events.push(TraceEvent {
+            name: self.name,
+            thread: thread_index(),
+            start: self.start.saturating_duration_since(epoch),
+            duration: now - self.start,
+            breakdown,
+        });
+    }
+}
+
+pub struct SpanGuard {
+    previous: Option<Option<Span>>,
+    trace: Option<TraceStart>,
+}
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        let Some(previous) = self.previous else {
+            return;
+        };
+        let now = Instant::now();
+        let trace = self.trace.take();
+        STATE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.charge(now);
+            state.current = previous.map(|span| (span, now));
+            if let Some(trace) = trace {
+                trace.finish(&mut state, now);
+            }
+        });
+    }
+}
+
+fn enter(span: Span, trace: Option<TraceStart>) -> SpanGuard {
+    let now = Instant::now();
+    let previous = STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.charge(now);
+        state.current.replace((span, now)).map(|(previous, _)| previous)
+    });
+    SpanGuard { previous: Some(previous), trace }
+}
+
+// Mide el tiempo hasta que se suelta el guard y lo carga a `span`
+pub fn span(span: Span) -> SpanGuard {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return SpanGuard { previous: None, trace: None };
+    }
+    enter(span, None)
+}
+
+// Como `span`, pero además deja un evento en la traza capturada. Para fases
+// que se ejecutan una vez por frame.
+pub fn phase(span: Span) -> SpanGuard {
+    if !ENABLED.load(Ordering::Relaxed) {
+        return SpanGuard { previous: None, trace: None };
+    }
+    let trace = CAPTURE.load(Ordering::Relaxed).then(|| TraceStart::begin(span.name().to_string()));
+    enter(span, trace)
+}
+
+// Bloque de trabajo paralelo (una fila o un bloque adaptativo) que solo se
+// registra en la traza, con el desglose de los tramos que contuvo
+pub struct Chunk {
+    trace: Option<TraceStart>,
+}
+
+impl Drop for Chunk {
+    fn drop(&mut self) {
+        if let Some(trace) = self.trace.take() {
+            let now = Instant::now();
+            STATE.with(|state| {
+                let mut state = state.borrow_mut();
+                state.charge(now);
+                trace.finish(&mut state, now);
+            });
+        }
+    }
+}
+
+pub fn chunk(name: &'static str, index: Option<usize>) -> Chunk {
+    let capturing = ENABLED.load(Ordering::Relaxed) && CAPTURE.load(Ordering::Relaxed);
+    let name = || match index {
+        Some(index) => format!("{} {}", name, index),
+        None => name.to_string(),
+    };
+    Chunk {
+        trace: capturing.then(|| TraceStart::begin(name())),
+    }
+}
+
+// Tiempos de un frame sumados sobre todos los hilos
+#[derive(Debug, Clone, Default)]
+pub struct FrameProfile {
+    pub totals: [Duration; SPAN_COUNT],
+    // Tiempo de pared desde `begin_frame`
+    pub wall: Duration,
+    events: Vec<TraceEvent>,
+}
+
+// Activa el perfilado para el frame que empieza; con `capture` además se
+// registran los eventos para exportar la traza
+pub fn begin_frame(enabled: bool, capture: bool) {
+    ENABLED.store(enabled || capture, Ordering::Relaxed);
+    CAPTURE.store(capture, Ordering::Relaxed);
+    EPOCH.get_or_init(Instant::now);
+    *FRAME_START.lock().unwrap() = Some(Instant::now());
+}
+
+fn take_thread_state() -> ThreadState {
+    STATE.with(|state| std::mem::take(&mut *state.borrow_mut()))
+}
+
+// Junta lo acumulado por el hilo actual y por cada hilo de rayon
+pub fn end_frame() -> FrameProfile {
+    let wall = FRAME_START.lock().unwrap().take().map_or(Duration::ZERO, |start| start.elapsed());
+    if !ENABLED.load(Ordering::Relaxed) {
+        return FrameProfile { wall, ..FrameProfile::default() };
+    }
+    let mut states = rayon::broadcast(|_| take_thread_state());
+    states.push(take_thread_state());
+
+    let mut profile = FrameProfile { wall, ..FrameProfile::default() };
+    for state in states {
+        for (total, thread_total) in profile.totals.iter_mut().zip(state.totals) {
+            *total += thread_total;
+        }
+        profile.events.extend(state.events);
+    }
+    profile.events.sort_by_key(|event| (event.thread, event.start));
+    CAPTURE.store(false, Ordering::Relaxed);
+    profile
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+impl FrameProfile {
+    pub fn total(&self) -> Duration {
+        self.totals.iter().sum()
+    }
+
+    pub fn get(&self, span: Span) -> Duration {
+        self.totals[span as usize]
+    }
+
+    pub fn has_trace(&self) -> bool {
+        !self.events.is_empty()
+    }
+
+    // Traza en el formato JSON de Chrome (chrome://tracing o Perfetto)
+    pub fn chrome_trace(&self) -> String {
+        let mut json = String::from("{\"traceEvents\":[\n");
+        for (i, event) in self.events.iter().enumerate() {
+            let args: Vec<String> = Span::ALL
+                .iter()
+                .map(|&span| format!("\"{}_ms\":{:.4}", span.name(), millis(event.breakdown[span as usize])))
+                .collect();
+            let _ = write!(
+                json,
+                "{{\"name\":\"{}\",\"cat\":\"render\",\"ph\":\"X\",\"pid\":1,\"tid\":{},\"ts\":{:.3},\"dur\":{:.3},\"args\":{{{}}}}}",
+                event.name,
+                event.thread,
+                event.start.as_secs_f64() * 1e6,
+                event.duration.as_secs_f64() * 1e6,
+                args.join(",")
+            );
+            json.push_str(if i + 1 < self.events.len() { ",\n" } else { "\n" });
+        }
+        json.push_str("]}\n");
+        json
+    }
+}
@@ -0,0 +1,125 @@
+use nalgebra_glm::Vec3;
+use std::sync::Arc;
+
+use crate::bvh::{Aabb, TraceBvh};
+use crate::light::SceneLight;
+use crate::object::SceneObject;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::renderer::primary_hit;
+use crate::settings::RenderSettings;
+
+// Consultas sobre una lista de objetos, para herramientas externas y para lo
+// que dentro del programa necesita preguntar dónde está cada cosa. Los
+// resultados son índices en la lista. Con un `TraceBvh` de esa misma lista las
+// consultas espaciales recorren el árbol y sin él recorren la lista entera;
+// las respuestas son las mismas, empates incluidos. Costos con el árbol, para
+// n objetos, k resultados y u objetos sin caja (los planos, que se prueban
+// siempre):
+//
+// - `objects_in_aabb`: O(log n + k + u) en un árbol equilibrado; sin árbol O(n)
+// - `nearest_object`: O(log n + u) cuando los objetos están repartidos, O(n)
+//   en el peor caso (muchas cajas a la misma distancia del punto)
+// - `raycast`: el mismo recorrido que los rayos primarios del renderer
+// - `objects_with_material`, `emissive_objects` y `objects_lit_by` no son
+//   espaciales y recorren siempre la lista: O(n)
+//
+// Los iteradores son perezosos: el árbol y la lista se recorren a medida que
+// se piden resultados.
+pub struct SceneQuery<'a> {
+    objects: &'a [SceneObject],
+    bvh: Option<Arc<TraceBvh>>,
+}
+
+// Impacto de `raycast`: el objeto y lo que el sombreado sabría de él
+#[derive(Debug, Clone)]
+pub struct Hit {
+    pub object: usize,
+    pub intersect: Intersect,
+}
+
+impl<'a> SceneQuery<'a> {
+    // Un árbol construido para otra lista se descarta
+    pub fn new(objects: &'a [SceneObject], bvh: Option<Arc<TraceBvh>>) -> Self {
+        let bvh = bvh.filter(|bvh| bvh.is_current(objects));
+        SceneQuery { objects, bvh }
+    }
+
+    // Con el árbol que usa el renderer, si está al día
+    pub fn with_settings(objects: &'a [SceneObject], settings: &RenderSettings) -> Self {
+        SceneQuery::new(objects, settings.bvh.clone())
+    }
+
+    pub fn objects(&self) -> &'a [SceneObject] {
+        self.objects
+    }
+
+    // Objetos cuya caja toca o se superpone con `bounds`, sin orden fijo. Un
+    // plano cuenta si alguna parte de la caja queda sobre él o detrás, del
+    // lado sólido
+    pub fn objects_in_aabb(&self, bounds: Aabb) -> Box<dyn Iterator<Item = usize> + '_> {
+        let objects = self.objects;
+        let crosses = move |index: usize| {
+            let object = &objects[index];
+            if object.is_bounded() {
+                return object.bounds().overlaps(&bounds);
+            }
+            (0..8).any(|corner: usize| {
+                let pick = |axis: usize| if corner >> axis & 1 == 0 { bounds.min[axis] } else { bounds.max[axis] };
+                object.distance(&Vec3::new(pick(0), pick(1), pick(2))) <= 0.0
+            })
+        };
+        match &self.bvh {
+            Some(bvh) => Box::new(bvh.overlapping(objects, bounds).filter(move |&index| crosses(index))),
+            None => Box::new((0..objects.len()).filter(move |&index| crosses(index))),
+        }
+    }
+
+    // Objeto más cercano al punto y su distancia, 0 si el punto está dentro;
+    // a igual distancia, el primero de la lista. None si no hay objetos
+    pub fn nearest_object(&self, point: &Vec3) -> Option<(usize, f32)> {
+        let distance = |index: usize| self.objects[index].distance(point).max(0.0);
+        match &self.bvh {
+            Some(bvh) => bvh.nearest(point, distance),
+            None => (0..self.objects.len())
+                .map(|index| (index, distance(index)))
+                .fold(None, |best: Option<(usize, f32)>, (index, distance)| match best {
+                    Some((_, best_distance)) if best_distance <= distance => best,
+                    _ => Some((index, distance)),
+                }),
+        }
+    }
+
+    pub fn objects_with_material<'n>(&self, name: &'n str) -> impl Iterator<Item = usize> + 'n
+    where
+        'a: 'n,
+    {
+        let objects = self.objects;
+        (0..objects.len()).filter(move |&index| objects[index].material().name == name)
+    }
+
+    pub fn emissive_objects(&self) -> impl Iterator<Item = usize> + 'a {
+        let objects = self.objects;
+        (0..objects.len()).filter(move |&index| objects[index].material().is_emissive())
+    }
+
+    // Objetos a los que llega la luz según su enlace (ver `SceneLight::affects`)
+    pub fn objects_lit_by<'l>(&self, light: &'l SceneLight) -> impl Iterator<Item = usize> + 'l
+    where
+        'a: 'l,
+    {
+        let objects = self.objects;
+        (0..objects.len()).filter(move |&index| light.affects(objects[index].group()))
+    }
+
+    // Primer objeto que corta el rayo, receptores de sombras incluidos
+    pub fn raycast(&self, origin: &Vec3, direction: &Vec3) -> Option<Hit> {
+        self.raycast_clipped(origin, direction, 0.0, false)
+    }
+
+    // Como los rayos primarios: lo que está a menos de `near` se atraviesa y,
+    // con `keep_cut`, un sólido cortado por esa distancia se ve cortado
+    pub fn raycast_clipped(&self, origin: &Vec3, direction: &Vec3, near: f32, keep_cut: bool) -> Option<Hit> {
+        primary_hit(origin, direction, self.objects, self.bvh.as_deref(), near, keep_cut)
+            .map(|(intersect, object, _)| Hit { object, intersect })
+    }
+}
@@ -0,0 +1,168 @@
+use nalgebra_glm::Vec3;
+
+use crate::camera::Camera;
+use crate::object::SceneObject;
+
+// Recorrido de cámara para vuelos cinematográficos. El archivo de escena da
+// poses en instantes fijos (ojo, punto mirado y campo de visión) y entre dos
+// poses el ojo sigue una curva de Catmull-Rom que pasa por todas, la mirada
+// gira de una dirección a la otra por el arco más corto y el campo de visión
+// cambia en línea recta. Cada tramo puede arrancar y frenar suave. La pose
+// depende solo del instante, así el mismo cuadro sale igual en la ventana y
+// al exportar la secuencia.
+
+// Lo que mira una pose
+#[derive(Debug, Clone, PartialEq)]
+pub enum RailTarget {
+    Point(Vec3),
+    // El centro del objeto con ese nombre, que sigue al objeto si se mueve;
+    // `fallback` es su centro al cargar, para cuando no está entre los que se
+    // trazan (enterrado o en un trozo sin trazar)
+    Object { name: String, fallback: Vec3 },
+}
+
+impl RailTarget {
+    fn resolve(&self, objects: &[SceneObject]) -> Vec3 {
+        match self {
+            RailTarget::Point(point) => *point,
+            RailTarget::Object { name, fallback } => {
+                objects.iter().find(|object| object.name() == name).map_or(*fallback, SceneObject::center)
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct RailKey {
+    // Segundos desde el principio del recorrido
+    pub time: f32,
+    pub eye: Vec3,
+    pub target: RailTarget,
+    // Campo de visión vertical en radianes
+    pub fov: f32,
+    // El tramo que empieza en esta pose acelera al salir y frena al llegar
+    pub ease: bool,
+}
+
+// Puntos por tramo de la línea que dibuja el recorrido
+const PATH_SAMPLES: usize = 16;
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct CameraRail {
+    // Ordenadas por instante
+    keys: Vec<RailKey>,
+}
+
+impl CameraRail {
+    pub fn new(mut keys: Vec<RailKey>) -> Self {
+        keys.sort_by(|a, b| a.time.total_cmp(&b.time));
+        CameraRail { keys }
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.keys.is_empty()
+    }
+
+    pub fn keys(&self) -> &[RailKey] {
+        &self.keys
+    }
+
+    // Instantes de la primera y la última pose
+    pub fn span(&self) -> (f32, f32) {
+        match (self.keys.first(), self.keys.last()) {
+            (Some(first), Some(last)) => (first.time, last.time),
+            _ => (0.0, 0.0),
+        }
+    }
+
+    pub fn duration(&self) -> f32 {
+        let (start, end) = self.span();
+        end - start
+    }
+
+    // Cuadros de la secuencia a `fps` cuadros por segundo, con el primero en
+    // la primera pose y el último en o antes de la última
+    pub fn frame_count(&self, fps: f32) -> usize {
+        if self.is_empty() {
+            return 0;
+        }
+        (self.duration() * fps + 1e-3).floor() as usize + 1
+    }
+
+    // Instante del cuadro `index`: sale del índice y no de sumar pasos, así
+    // no acumula error de redondeo
+    pub fn frame_time(&self, index: usize, fps: f32) -> f32 {
+        self.span().0 + index as f32 / fps
+    }
+
+    // Tramo en que cae `time` y la fracción recorrida de ese tramo, ya con la
+    // aceleración y el frenado si los pide
+    fn locate(&self, time: f32) -> Option<(usize, f32)> {
+        let last = self.keys.len().checked_sub(1)?;
+        let segment = self.keys[..last].iter().rposition(|key| key.time <= time).unwrap_or(0);
+        let (a, b) = (&self.keys[segment], &self.keys[(segment + 1).min(last)]);
+        let length = b.time - a.time;
+        let u = if length > 0.0 { ((time - a.time) / length).clamp(0.0, 1.0) } else { 0.0 };
+        Some((segment, if a.ease { u * u * (3.0 - 2.0 * u) } else { u }))
+    }
+
+    fn key(&self, index: isize) -> &RailKey {
+        &self.keys[index.clamp(0, self.keys.len() as isize - 1) as usize]
+    }
+
+    fn eye(&self, segment: usize, u: f32) -> Vec3 {
+        let i = segment as isize;
+        catmull_rom(&self.key(i - 1).eye, &self.key(i).eye, &self.key(i + 1).eye, &self.key(i + 2).eye, u)
+    }
+
+    // Cámara en el instante `time`, que se lleva al intervalo del recorrido
+    pub fn at(&self, time: f32, objects: &[SceneObject]) -> Option<Camera> {
+        let (segment, u) = self.locate(time)?;
+        let (a, b) = (self.key(segment as isize), self.key(segment as isize + 1));
+        let eye = self.eye(segment, u);
+
+        // Dos poses que miran lo mismo lo siguen mirando en todo el tramo
+        let (target_a, target_b) = (a.target.resolve(objects), b.target.resolve(objects));
+        let center = if a.target == b.target {
+            target_a
+        } else {
+            let (to_a, to_b) = (target_a - a.eye, target_b - b.eye);
+            let distance = to_a.magnitude() + (to_b.magnitude() - to_a.magnitude()) * u;
+            eye + slerp(&to_a.normalize(), &to_b.normalize(), u) * distance
+        };
+
+        Some(Camera {
+            fov: a.fov + (b.fov - a.fov) * u,
+            ..Camera::new(eye, center, Vec3::new(0.0, 1.0, 0.0))
+        })
+    }
+
+    // Posiciones del ojo a lo largo del recorrido, para dibujarlo
+    pub fn path(&self) -> Vec<Vec3> {
+        let segments = self.keys.len().saturating_sub(1);
+        if segments == 0 {
+            return self.keys.iter().map(|key| key.eye).collect();
+        }
+        (0..segments)
+            .flat_map(|segment| (0..PATH_SAMPLES).map(move |sample| (segment, sample as f32 / PATH_SAMPLES as f32)))
+            .map(|(segment, u)| self.eye(segment, u))
+            .chain(self.keys.last().map(|key| key.eye))
+            .collect()
+    }
+}
+
+// Catmull-Rom uniforme entre `b` y `c`, con `a` y `d` como vecinos
+fn catmull_rom(a: &Vec3, b: &Vec3, c: &Vec3, d: &Vec3, t: f32) -> Vec3 {
+    let (t2, t3) = (t * t, t * t * t);
+    (b * 2.0 + (c - a) * t + (a * 2.0 - b * 5.0 + c * 4.0 - d) * t2 + (b * 3.0 - a - c * 3.0 + d) * t3) * 0.5
+}
+
+// Giro por el arco más corto entre dos direcciones unitarias; casi alineadas
+// o opuestas basta con mezclarlas
+fn slerp(from: &Vec3, to: &Vec3, t: f32) -> Vec3 {
+    let angle = from.dot(to).clamp(-1.0, 1.0).acos();
+    if angle.sin() < 1e-3 {
+        return (from + (to - from) * t).try_normalize(1e-6).unwrap_or(*from);
+    }
+    (from * ((1.0 - t) * angle).sin() + to * (t * angle).sin()) / angle.sin()
+}
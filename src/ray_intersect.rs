@@ -1,6 +1,10 @@
 use nalgebra_glm::Vec3;
 use crate::material::Material;
+use crate::texture::Footprint;
 
+// Lo que el sombreado necesita del impacto, sea cual sea la forma: además del
+// punto, la normal y el material, las coordenadas de textura del punto y la
+// huella del píxel en ellas, que calcula cada forma a su manera
 #[derive(Debug, Clone)]
 #[allow(dead_code)]
 pub struct Intersect {
@@ -9,9 +13,12 @@ pub struct Intersect {
     pub distance: f32,
     pub is_intersecting: bool,
     pub material: Material,
+    pub uv: (f32, f32),
+    pub footprint: Footprint,
 }
 
 impl Intersect {
+    // Sin coordenadas de textura; las formas las agregan con `with_uv`
     pub fn new(point: Vec3, normal: Vec3, distance: f32, material: Material) -> Self {
         Intersect {
             point,
@@ -19,22 +26,32 @@ impl Intersect {
             distance,
             is_intersecting: true,
             material,
+            uv: (0.0, 0.0),
+            footprint: Footprint::ROUND,
         }
     }
 
+    pub fn with_uv(self, uv: (f32, f32), footprint: Footprint) -> Self {
+        Intersect { uv, footprint, ..self }
+    }
+
     pub fn empty() -> Self {
         Intersect {
             point: Vec3::zeros(),
             normal: Vec3::zeros(),
             material: Material::black(),
             distance: 0.0,
-            is_intersecting: false
+            is_intersecting: false,
+            uv: (0.0, 0.0),
+            footprint: Footprint::ROUND,
         }
     }
 }
 
 pub trait RayIntersect {
   fn ray_intersect(&self, ray_origin: &Vec3, ray_direction: &Vec3) -> Intersect;
+  // Distancia con signo del punto a la superficie: negativa por dentro
+  fn distance(&self, point: &Vec3) -> f32;
 }
 
 
@@ -0,0 +1,32 @@
+use crate::camera::Camera;
+use crate::light::SceneLight;
+
+// Qué se trazó en el último render, para saber si el frame siguiente puede
+// volver a mostrarlo. En lugar de banderas que cada cambio de la cámara o de
+// una luz tenga que poner, se compara con lo que se trazó: sus campos son
+// públicos y los cambian los controles, el editor de luces, el recorrido de
+// cámara y el ciclo día/noche, así que una bandera olvidada deja un frame
+// viejo en pantalla y una comparación no se olvida. Compararlas cuesta unos
+// pocos números por luz, nada al lado de un render.
+#[derive(Debug, Clone)]
+pub struct Redraw {
+    camera: Camera,
+    lights: Vec<SceneLight>,
+}
+
+impl Redraw {
+    pub fn new(camera: &Camera, lights: &[SceneLight]) -> Self {
+        Redraw { camera: camera.clone(), lights: lights.to_vec() }
+    }
+
+    // Si la cámara o alguna luz cambió desde el último render
+    pub fn changed(&self, camera: &Camera, lights: &[SceneLight]) -> bool {
+        *camera != self.camera || lights != self.lights.as_slice()
+    }
+
+    // Anota lo que se acaba de trazar
+    pub fn rendered(&mut self, camera: &Camera, lights: &[SceneLight]) {
+        self.camera.clone_from(camera);
+        self.lights = lights.to_vec();
+    }
+}
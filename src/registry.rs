@@ -0,0 +1,78 @@
+use std::collections::HashMap;
+
+use crate::color::Color;
+use crate::material::Material;
+use crate::presets;
+use crate::texture::{AnimatedTexture, ProceduralTexture, Texture, TextureFilter};
+
+// Texturas del diorama incluidas en el binario, para que la biblioteca funcione
+// sin la carpeta assets/ junto al ejecutable. La ruta solo las identifica.
+const OBSIDIAN: (&[u8], &str) = (include_bytes!("../assets/obsidian.jpg"), "assets/obsidian.jpg");
+const GRASS: (&[u8], &str) = (include_bytes!("../assets/grass.jpg"), "assets/grass.jpg");
+const PURPLE_FRAMES: (&[u8], &str) = (include_bytes!("../assets/purple_frames.jpg"), "assets/purple_frames.jpg");
+const LAVA_FRAMES: (&[u8], &str) = (include_bytes!("../assets/lava_frames.jpg"), "assets/lava_frames.jpg");
+const LAVA_NORMAL: (&[u8], &str) = (include_bytes!("../assets/lava_normal.png"), "assets/lava_normal.png");
+
+// Mismo ruido en el diorama integrado y en scenes/portal.ron
+const STONE_NOISE: ProceduralTexture = ProceduralTexture::Noise {
+    low: Color::new(112, 110, 106),
+    high: Color::new(188, 184, 176),
+    seed: 7,
+    frequency: 4.0,
+};
+
+fn embedded((bytes, path): (&[u8], &str)) -> Texture {
+    Texture::from_bytes(bytes, path).expect("las texturas incluidas son imágenes válidas")
+}
+
+// Materiales del diorama por nombre: obsidian, purple, grass, rock, stone y lava
+pub fn default_materials() -> HashMap<String, Material> {
+    let mut obsidian = Material::with_texture(
+        embedded(OBSIDIAN),
+        10.0,                 // Brillo
+        [0.1, 0.9, 0.1, 0.0], // Propiedades
+        2.0,                  // Índice de refracción
+    ).named("obsidian");
+    obsidian.edge_bevel = 0.04; // Las aristas del marco atrapan un poco de luz
+
+    let mut purple = Material::new(
+        Color::white(),
+        10.0,                 // Brillo
+        [0.1, 0.9, 0.1, 0.0], // Propiedades
+        1.0,                  // Índice de refracción
+    )
+    .animated(AnimatedTexture::from_strip(&embedded(PURPLE_FRAMES), 8.0)) // El remolino baja y ondula
+    .named("purple");
+    purple.halo = 0.8; // El portal brilla más que la costra de lava
+    purple.unlit = true; // El portal se ve con su propio brillo, de día y de noche
+
+    let mut grass = Material::with_texture(
+        embedded(GRASS),
+        10.0,                 // Ajuste el brillo si es necesario
+        [0.8, 0.2, 0.0, 0.0], // Ajusta las propiedades: difuso, especular, reflectividad, transparencia
+        1.0,
+    ).named("grass");
+    grass.filter = TextureFilter::Aniso { max_samples: 4 }; // La base se ve casi siempre en ángulo rasante
+
+    let rock = presets::polished_stone().named("rock");
+
+    // Piedra de las gradas con manchas de ruido, sin archivo de imagen
+    let stone = Material::builder()
+        .name("stone")
+        .texture(STONE_NOISE)
+        .shininess(20.0)
+        .diffuse(0.85)
+        .specular(0.3)
+        .reflectivity(0.1)
+        .build();
+
+    // Las grietas laten de a poco; la costra no cambia, así el relieve sigue valiendo
+    let mut lava = presets::lava().animated(AnimatedTexture::from_strip(&embedded(LAVA_FRAMES), 6.0));
+    lava.normal_map = Some(embedded(LAVA_NORMAL)); // La costra en relieve hace correr los brillos del sol
+    lava.emission_map = lava.texture.as_deref().cloned(); // Brillan las grietas; de noche la costra queda oscura
+
+    [obsidian, purple, grass, rock, stone, lava]
+        .into_iter()
+        .map(|material| (material.name.clone(), material))
+        .collect()
+}
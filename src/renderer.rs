@@ -0,0 +1,1640 @@
+use nalgebra_glm::{Vec3, normalize};
+use std::borrow::Cow;
+use std::collections::hash_map::DefaultHasher;
+use std::f32::consts::{PI, TAU};
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Instant;
+use rayon::prelude::*;
+
+use crate::adaptive::Sample;
+use crate::bake::{hemisphere, GOLDEN_ANGLE};
+use crate::bloom::apply_bloom;
+use crate::bvh::TraceBvh;
+use crate::camera::{Camera, Projection};
+use crate::caustics;
+use crate::color::{linear_to_srgb, srgb_to_linear, Color, LinearColor};
+use crate::cube::Cube;
+use crate::decal::{self, Face};
+use crate::framebuffer::Framebuffer;
+use crate::halfres::{Guide, HalfResolution, Upsample};
+use crate::godrays::apply_god_rays;
+use crate::halo::{apply_halo, halo_reach};
+use crate::light::{LightKind, SceneLight};
+use crate::object::SceneObject;
+use crate::probe::{ProbePath, SegmentKind};
+use crate::profile::{self, Span};
+use crate::query::SceneQuery;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::reprojection::{self, HistoryPixel, Reprojection};
+use crate::scene::WORLD_UP;
+use crate::sdf::{soft_shadow, SceneDistanceField};
+use crate::settings::{AmbientOcclusion, Crop, RenderSettings, ShadowQuality};
+use crate::texture::{Texture, TextureFilter, UvMode};
+
+pub const MAX_RAY_DEPTH: u32 = 3;
+pub const FOV: f32 = PI / 3.0;
+const PLANAR_REFLECTION_DOWNSCALE: usize = 2; // Los reflejos planares se renderizan a 1/2 de resolución
+const SKYBOX_COLOR: Color = Color::new(68, 142, 228);
+const HORIZON_COLOR: Color = Color::new(178, 208, 238); // Bruma del horizonte de día
+
+// Añade estas constantes
+const NIGHT_SKY_COLOR: Color = Color::new(10, 10, 50); // Color del cielo nocturno
+const NIGHT_HORIZON_COLOR: Color = Color::new(30, 32, 76);
+// Radios aparentes en radianes, más grandes que los de verdad para que se
+// vean a la resolución de la ventana
+const SUN_RADIUS: f32 = 0.035;
+const MOON_RADIUS: f32 = 0.025;
+const MOON_COLOR: Color = Color::new(222, 226, 238);
+// Tinte de la imagen de cielo a medianoche; de día se ve tal cual
+const NIGHT_SKYBOX_TINT: Color = Color::new(40, 44, 96);
+const TEXTURE_SHADOW: f32 = 0.6; // Cuánto oscurece una sombra plena a una textura que la recibe
+
+// Punto de partida de un rayo secundario: el del impacto corrido `bias` hacia
+// el lado de la superficie por el que sale el rayo
+pub fn offset_origin(intersect: &Intersect, direction: &Vec3, bias: f32) -> Vec3 {
+    let offset = intersect.normal * bias;
+    if direction.dot(&intersect.normal) < 0.0 {
+        intersect.point - offset
+    } else {
+        intersect.point + offset
+    }
+}
+
+// Dirección reflejada de `incident` en una superficie con normal `normal`
+pub fn reflect(incident: &Vec3, normal: &Vec3) -> Vec3 {
+    incident - 2.0 * incident.dot(normal) * normal
+}
+
+// Dirección refractada al entrar o salir de un material de índice `eta_t`,
+// según de qué lado de la normal llega el rayo; más allá del ángulo crítico
+// devuelve el reflejo
+pub fn refract(incident: &Vec3, normal: &Vec3, eta_t: f32) -> Vec3 {
+    let cosi = -incident.dot(normal).clamp(-1.0, 1.0);
+    
+    let (n_cosi, eta, n_normal);
+
+    if cosi < 0.0 {
+        // Ray is leaving the object
+        n_cosi = -cosi;
+        eta = eta_t;
+        n_normal = -normal;
+    } else {
+        // Ray is entering the object
+        n_cosi = cosi;
+        eta = 1.0 / eta_t;
+        n_normal = *normal;
+    }
+    
+    let k = 1.0 - eta * eta * (1.0 - n_cosi * n_cosi);
+    
+    if k < 0.0 {
+        // Total internal reflection
+        reflect(incident, &n_normal)
+    } else {
+        eta * incident + (eta * n_cosi - k.sqrt()) * n_normal
+    }
+}
+
+// Aproximación de Schlick para la reflectancia de Fresnel
+fn schlick(cos_theta: f32, r0: f32) -> f32 {
+    r0 + (1.0 - r0) * (1.0 - cos_theta.clamp(0.0, 1.0)).powi(5)
+}
+
+// Fracción de la luz que se refleja al cruzar la superficie de un material
+// con índice `eta_t`, con los mismos lados que `refract`: de frente casi toda
+// pasa y en ángulo rasante casi toda se refleja. Al salir del material más
+// allá del ángulo crítico `refract` ya devuelve el reflejo, y aquí vale 1.
+fn fresnel(incident: &Vec3, normal: &Vec3, eta_t: f32) -> f32 {
+    let cosi = -incident.dot(normal).clamp(-1.0, 1.0);
+    let (eta, cos_incident) = if cosi < 0.0 { (eta_t, -cosi) } else { (1.0 / eta_t, cosi) };
+    let sin_transmitted = eta * eta * (1.0 - cos_incident * cos_incident);
+    if sin_transmitted > 1.0 {
+        return 1.0;
+    }
+    // Schlick usa el ángulo del lado del medio menos denso
+    let cos_theta = if eta > 1.0 { (1.0 - sin_transmitted).sqrt() } else { cos_incident };
+    schlick(cos_theta, ((eta - 1.0) / (eta + 1.0)).powi(2))
+}
+
+// Reflejo de una placa plana alineada a un eje, renderizado una vez por frame
+// desde la cámara espejada y muestreado al sombrear la superficie
+pub struct PlanarReflection {
+    object: usize,
+    axis: usize,
+    camera: Camera,
+    width: usize,
+    height: usize,
+    aspect_ratio: f32,
+    perspective_scale: f32,
+    buffer: Vec<LinearColor>,
+}
+
+impl PlanarReflection {
+    // Una placa es plana si su lado más delgado es mucho menor que los otros dos
+    fn flat_axis(cube: &Cube) -> Option<usize> {
+        let size = cube.max - cube.min;
+        let axis = (0..3).min_by(|&a, &b| size[a].total_cmp(&size[b]))?;
+        let thinnest_other = (0..3).filter(|&a| a != axis).map(|a| size[a]).fold(f32::INFINITY, f32::min);
+        if size[axis] * 4.0 < thinnest_other {
+            Some(axis)
+        } else {
+            None
+        }
+    }
+
+    // Eje y altura de la cara de la placa que hace de espejo para `camera`:
+    // la que mira hacia ella. None si la placa no es plana, si la cámara está
+    // a la altura de la placa o si la cara queda fuera de la imagen, ya que
+    // entonces el búfer no se usaría. Si solo algunas esquinas quedan detrás
+    // de la cámara su proyección no sirve y la cara cuenta como visible
+    pub fn mirror_plane(cube: &Cube, camera: &Camera, aspect_ratio: f32, perspective_scale: f32) -> Option<(usize, f32)> {
+        let axis = Self::flat_axis(cube)?;
+        let plane = if camera.eye[axis] > cube.max[axis] {
+            cube.max[axis]
+        } else if camera.eye[axis] < cube.min[axis] {
+            cube.min[axis]
+        } else {
+            return None;
+        };
+
+        let (a, b) = ((axis + 1) % 3, (axis + 2) % 3);
+        let corners = [(cube.min[a], cube.min[b]), (cube.max[a], cube.min[b]), (cube.min[a], cube.max[b]), (cube.max[a], cube.max[b])]
+            .map(|(u, v)| {
+                let mut corner = Vec3::zeros();
+                (corner[axis], corner[a], corner[b]) = (plane, u, v);
+                camera.project(&corner, aspect_ratio, perspective_scale)
+            });
+        if corners.iter().all(Option::is_none) {
+            return None;
+        }
+        if corners.iter().all(Option::is_some) {
+            let (xs, ys): (Vec<f32>, Vec<f32>) = corners.iter().flatten().copied().unzip();
+            let outside = |values: &[f32]| values.iter().all(|&v| v < -1.0) || values.iter().all(|&v| v > 1.0);
+            if outside(&xs) || outside(&ys) {
+                return None;
+            }
+        }
+        Some((axis, plane))
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    fn render(
+        object: usize,
+        objects: &[SceneObject],
+        camera: &Camera,
+        lights: &[SceneLight],
+        sky_color: Color,
+        width: usize,
+        height: usize,
+        perspective_scale: f32,
+        settings: &RenderSettings,
+    ) -> Option<Self> {
+        let cube = objects[object].as_cube()?;
+        let aspect_ratio = width as f32 / height as f32;
+        let (axis, plane) = Self::mirror_plane(cube, camera, aspect_ratio, perspective_scale)?;
+
+        let mirror = |v: Vec3| {
+            let mut mirrored = v;
+            mirrored[axis] = 2.0 * plane - v[axis];
+            mirrored
+        };
+        let mut up = camera.up;
+        up[axis] = -up[axis];
+        let mirrored_camera = Camera { fov: camera.fov, ..Camera::new(mirror(camera.eye), mirror(camera.center), up) };
+
+        let mut buffer = vec![settings.linear(sky_color); width * height];
+
+        buffer
+            .par_iter_mut()
+            .enumerate()
+            .for_each(|(index, pixel)| {
+                let x = (index % width) as f32 + 0.5;
+                let y = (index / width) as f32 + 0.5;
+
+                let screen_x = ((2.0 * x) / width as f32 - 1.0) * aspect_ratio * perspective_scale;
+                let screen_y = (-(2.0 * y) / height as f32 + 1.0) * perspective_scale;
+
+                let direction = mirrored_camera.basis_change(&Vec3::new(screen_x, screen_y, -1.0));
+
+                // Los rayos parten del plano para no ver lo que queda detrás del espejo
+                let t = (plane - mirrored_camera.eye[axis]) / direction[axis];
+                if t > 0.0 {
+                    let origin = mirrored_camera.eye + direction * (t + settings.bias);
+                    *pixel = trace_ray(&origin, &direction, objects, lights, 1, 1.0, sky_color, &[], settings, None);
+                }
+            });
+
+        Some(PlanarReflection {
+            object,
+            axis,
+            camera: mirrored_camera,
+            width,
+            height,
+            aspect_ratio,
+            perspective_scale,
+            buffer,
+        })
+    }
+
+    fn pixel(&self, x: usize, y: usize) -> LinearColor {
+        self.buffer[y.min(self.height - 1) * self.width + x.min(self.width - 1)]
+    }
+
+    // Muestrea el búfer espejado con filtrado bilineal
+    fn sample(&self, point: &Vec3) -> Option<LinearColor> {
+        let (screen_x, screen_y) = self.camera.project(point, self.aspect_ratio, self.perspective_scale)?;
+        let px = ((screen_x + 1.0) * 0.5 * self.width as f32 - 0.5).max(0.0);
+        let py = ((1.0 - screen_y) * 0.5 * self.height as f32 - 0.5).max(0.0);
+        if px >= self.width as f32 || py >= self.height as f32 {
+            return None;
+        }
+
+        let (x0, y0) = (px as usize, py as usize);
+        let (fx, fy) = (px.fract(), py.fract());
+        let top = self.pixel(x0, y0) * (1.0 - fx) + self.pixel(x0 + 1, y0) * fx;
+        let bottom = self.pixel(x0, y0 + 1) * (1.0 - fx) + self.pixel(x0 + 1, y0 + 1) * fx;
+        Some(top * (1.0 - fy) + bottom * fy)
+    }
+}
+
+// Giro de 0 a 2π que sale de los bits de un punto, para que las espirales de
+// muestras cambien de un punto a otro pero no de un frame al siguiente
+fn spin_at(point: &Vec3) -> f32 {
+    let mut hasher = DefaultHasher::new();
+    point.map(f32::to_bits).as_slice().hash(&mut hasher);
+    (hasher.finish() >> 40) as f32 / (1u64 << 24) as f32 * TAU
+}
+
+// Fracción del hemisferio de `normal` que no ve geometría a menos de `radius`
+fn ambient_visibility(
+    intersect: &Intersect,
+    normal: &Vec3,
+    objects: &[SceneObject],
+    settings: &RenderSettings,
+    occlusion: &AmbientOcclusion,
+) -> f32 {
+    let samples = occlusion.samples.max(1);
+    let bvh = settings.bvh.as_deref();
+    let open = hemisphere(normal, samples as usize, spin_at(&intersect.point))
+        .filter(|direction| {
+            let origin = offset_origin(intersect, direction, settings.bias);
+            let blocked = match bvh {
+                Some(bvh) => bvh.nearest_blocker(objects, &origin, direction, settings.bias, occlusion.radius, |_| true).is_some(),
+                None => objects.iter().any(|object| {
+                    let hit = object.ray_intersect(&origin, direction);
+                    hit.is_intersecting && hit.distance >= settings.bias && hit.distance < occlusion.radius
+                }),
+            };
+            !blocked
+        })
+        .count();
+    open as f32 / samples as f32
+}
+
+// Intensidad de la sombra de `light` en el punto, de 0 (iluminado) a 1: el
+// promedio de los canales de `cast_tinted_shadow`
+pub fn cast_shadow(
+    intersect: &Intersect,
+    light: &SceneLight,
+    objects: &[SceneObject],
+    bvh: Option<&TraceBvh>,
+    bias: f32,
+    quality: ShadowQuality,
+) -> f32 {
+    shadow_mean(cast_tinted_shadow(intersect, light, objects, bvh, bias, quality))
+}
+
+// Exacto cuando la sombra es gris, como la de los opacos
+fn shadow_mean(shadow: [f32; 3]) -> f32 {
+    match shadow {
+        [r, g, b] if r == g && g == b => r,
+        [r, g, b] => (r + g + b) / 3.0,
+    }
+}
+
+// Sombra por canal: los objetos transparentes dejan pasar parte de la luz
+// teñida por su color, así un techo de vidrio de colores deja una sombra de
+// colores. El mapa de sombras y las sombras SDF los tratan como opacos
+pub(crate) fn cast_tinted_shadow(
+    intersect: &Intersect,
+    light: &SceneLight,
+    objects: &[SceneObject],
+    bvh: Option<&TraceBvh>,
+    bias: f32,
+    quality: ShadowQuality,
+) -> [f32; 3] {
+    let (light_dir, light_distance) = light.toward(&intersect.point);
+    let source = intersect.point + light_dir * light_distance;
+
+    let _span = profile::span(Span::Shadow);
+    // Con mapa de sombras la prueba es una consulta; fuera del mapa se traza
+    if quality == ShadowQuality::Hard {
+        if let Some(shadow) = light.shadow_map.as_ref().and_then(|map| map.shadow(&intersect.point)) {
+            return [shadow; 3];
+        }
+    }
+    let shadow_ray_origin = offset_origin(intersect, &light_dir, bias);
+
+    // Una luz enlazada solo proyecta sombras de los objetos de sus grupos
+    let linked = light.is_linked();
+    let casts = |object: &SceneObject| (!linked || light.affects(object.group())) && !object.material().shadow_catcher;
+
+    if let ShadowQuality::SdfSoft { k } = quality {
+        let field = SceneDistanceField::around_segment(
+            objects.iter().filter(|object| casts(object)),
+            &shadow_ray_origin,
+            &source,
+            light_distance / k,
+        );
+        return [soft_shadow(&field, &shadow_ray_origin, &source, k, bias * 0.5); 3];
+    }
+
+    // Con r = distancia del punto al oclusor / distancia del punto a la luz,
+    // un oclusor opaco da una sombra de 1 - r²: pegado al punto (r cerca de 0)
+    // la sombra es plena y pegado a la luz (r cerca de 1) casi no hay, como la
+    // penumbra de una luz con tamaño, que crece con la distancia al oclusor y
+    // aclara la sombra. Cuenta el opaco más cercano al punto, sin importar el
+    // orden de la lista ni cuántos haya detrás. Cada transparente que queda
+    // antes de él deja pasar por canal `transparencia * color`, y tapa lo
+    // mismo que un opaco en la fracción restante: la luz que llega es el
+    // producto de 1 - (1 - r²) * (1 - paso) de cada uno por la que deja el
+    // opaco. Se multiplican en orden de distancia, el mismo con el árbol o sin él
+    let hard = |origin: &Vec3, direction: &Vec3, distance: f32| {
+        let mut opaque = distance;
+        let mut transparent: Vec<(f32, usize, [f32; 3])> = Vec::new();
+        let mut test = |index: usize| {
+            let object = &objects[index];
+            if !casts(object) {
+                return opaque;
+            }
+            let hit = object.ray_intersect(origin, direction);
+            if hit.is_intersecting && hit.distance >= bias && hit.distance < opaque {
+                let material = &hit.material;
+                if material.is_transparent() {
+                    let color = [material.color.red(), material.color.green(), material.color.blue()];
+                    let passes = color.map(|channel| material.transparency.min(1.0) * channel as f32 / 255.0);
+                    transparent.push((hit.distance, index, passes));
+                } else {
+                    opaque = hit.distance;
+                }
+            }
+            opaque
+        };
+        match bvh {
+            Some(bvh) => bvh.traverse(origin, direction, distance, &mut test),
+            None => (0..objects.len()).for_each(|index| {
+                test(index);
+            }),
+        }
+
+        let fade = |at: f32| 1.0 - (at / distance).powf(2.0).min(1.0);
+        let opaque_shadow = if opaque < distance { fade(opaque) } else { 0.0 };
+        transparent.retain(|&(at, _, _)| at < opaque);
+        if transparent.is_empty() {
+            return [opaque_shadow; 3];
+        }
+        transparent.sort_by(|a, b| a.0.total_cmp(&b.0).then(a.1.cmp(&b.1)));
+        let mut through = [1.0 - opaque_shadow; 3];
+        for (at, _, passes) in transparent {
+            for (channel, passes) in through.iter_mut().zip(passes) {
+                *channel *= 1.0 - fade(at) * (1.0 - passes);
+            }
+        }
+        through.map(|channel| 1.0 - channel)
+    };
+
+    match quality {
+        ShadowQuality::Area { samples } if samples > 1 && light.radius > 0.0 && light.kind != LightKind::Directional => {
+            // Puntos repartidos en espiral sobre el disco de la luz, de frente
+            // al punto sombreado. El giro de la espiral sale del punto, así el
+            // mismo píxel usa siempre los mismos rayos y la penumbra no titila
+            let spin = spin_at(&intersect.point);
+            let side = light_dir.cross(&Vec3::new(0.0, 1.0, 0.0)).try_normalize(1e-6).unwrap_or(Vec3::new(1.0, 0.0, 0.0));
+            let up = side.cross(&light_dir);
+            let total = (0..samples)
+                .map(|i| {
+                    let radius = light.radius * ((i as f32 + 0.5) / samples as f32).sqrt();
+                    let angle = spin + i as f32 * GOLDEN_ANGLE;
+                    let target = light.position + (side * angle.cos() + up * angle.sin()) * radius;
+                    let offset = target - intersect.point;
+                    let direction = offset.normalize();
+                    hard(&offset_origin(intersect, &direction, bias), &direction, offset.magnitude())
+                })
+                .fold([0.0; 3], |total, shadow| [0, 1, 2].map(|channel| total[channel] + shadow[channel]));
+            total.map(|channel| channel / samples as f32)
+        }
+        _ => hard(&shadow_ray_origin, &light_dir, light_distance),
+    }
+}
+
+// Busca el impacto más cercano a partir de `t_min` y el índice del objeto
+// impactado. Los receptores de sombras solo cuentan si `catchers` es true.
+// Con `bvh` se recorre el árbol en lugar de la lista.
+pub(crate) fn closest_hit(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    objects: &[SceneObject],
+    bvh: Option<&TraceBvh>,
+    t_min: f32,
+    catchers: bool,
+) -> Option<(Intersect, usize)> {
+    if let Some(bvh) = bvh {
+        return bvh.closest_hit(objects, ray_origin, ray_direction, t_min, catchers);
+    }
+    let mut intersect = Intersect::empty();
+    let mut zbuffer = f32::INFINITY;
+    let mut hit_object = 0;
+
+    for (index, object) in objects.iter().enumerate() {
+        if object.material().shadow_catcher && !catchers {
+            continue;
+        }
+        let i = object.ray_intersect(ray_origin, ray_direction);
+        if i.is_intersecting && i.distance >= t_min && i.distance < zbuffer {
+            zbuffer = i.distance;
+            intersect = i;
+            hit_object = index;
+        }
+    }
+
+    if intersect.is_intersecting {
+        Some((intersect, hit_object))
+    } else {
+        None
+    }
+}
+
+// Impacto de un rayo primario con recorte cercano: se ignora lo que queda
+// antes de `near`. Un objeto atravesado por el plano cercano se omite, o con
+// `keep_cut` se devuelve su sección a distancia `near` marcada como cortada.
+// Con near = 0 es exactamente `closest_hit`.
+pub(crate) fn primary_hit(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    objects: &[SceneObject],
+    bvh: Option<&TraceBvh>,
+    near: f32,
+    keep_cut: bool,
+) -> Option<(Intersect, usize, bool)> {
+    if near <= 0.0 {
+        return closest_hit(ray_origin, ray_direction, objects, bvh, 0.0, true).map(|(intersect, index)| (intersect, index, false));
+    }
+
+    let cut_point = ray_origin + ray_direction * near;
+    let candidate = |object: &SceneObject| {
+        let i = object.ray_intersect(ray_origin, ray_direction);
+        if !i.is_intersecting {
+            None
+        } else if i.distance >= near {
+            Some((i, false))
+        } else if keep_cut && object.contains(&cut_point) {
+            Some((Intersect::new(cut_point, -ray_direction, near, object.material().clone()), true))
+        } else {
+            None
+        }
+    };
+
+    if let Some(bvh) = bvh {
+        return bvh
+            .closest_by(ray_origin, ray_direction, |index| candidate(&objects[index]).map(|hit| (hit.0.distance, hit)))
+            .map(|((intersect, cut), index)| (intersect, index, cut));
+    }
+
+    let mut closest: Option<(Intersect, usize, bool)> = None;
+    for (index, object) in objects.iter().enumerate() {
+        let Some((intersect, cut)) = candidate(object) else {
+            continue;
+        };
+        if closest.as_ref().is_none_or(|(best, _, _)| intersect.distance < best.distance) {
+            closest = Some((intersect, index, cut));
+        }
+    }
+
+    closest
+}
+
+// `settings` tal cual si su árbol se construyó con `objects`, o una copia sin
+// árbol si no, así un objeto movido o cambiado por otro sin que cambie la
+// cantidad no queda fuera de los rayos. Se mira una vez al entrar al
+// renderer; de ahí para adentro los rayos usan `settings.bvh` sin preguntar
+pub(crate) fn with_current_bvh<'a>(settings: &'a RenderSettings, objects: &[SceneObject]) -> Cow<'a, RenderSettings> {
+    match &settings.bvh {
+        Some(bvh) if !bvh.is_current(objects) => Cow::Owned(RenderSettings { bvh: None, ..settings.clone() }),
+        _ => Cow::Borrowed(settings),
+    }
+}
+
+// Oclusión que recibe un punto: la sombra más fuerte entre las luces que
+// afectan a su grupo, de 0 (iluminado) a 1
+fn occlusion(intersect: &Intersect, group: &str, objects: &[SceneObject], lights: &[SceneLight], settings: &RenderSettings) -> f32 {
+    lights
+        .iter()
+        .filter(|light| light.affects(group))
+        .map(|light| cast_shadow(intersect, light, objects, settings.bvh.as_deref(), settings.bias, settings.shadow_quality))
+        .fold(0.0, f32::max)
+}
+
+// Irradiancia que llega a un punto desde el hemisferio de su normal, por canal
+// (1 es el blanco): promedia `irradiance::SAMPLES` rayos con peso coseno
+// repartidos con la secuencia de Hammersley, girada por `seed` para que
+// puntos vecinos no muestreen las mismas direcciones. Los impactos se
+// sombrean como el último rebote, así no siguen reflejándose, y el cielo
+// cuenta como el ambiente de las zonas sin entradas
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn gather_irradiance(
+    point: &Vec3,
+    normal: &Vec3,
+    objects: &[SceneObject],
+    lights: &[SceneLight],
+    sky_color: Color,
+    settings: &RenderSettings,
+    seed: u64,
+) -> [f32; 3] {
+    let samples = crate::irradiance::SAMPLES;
+    let tangent = normal.cross(&Vec3::new(0.0, 1.0, 0.0)).try_normalize(1e-6).unwrap_or(Vec3::new(1.0, 0.0, 0.0));
+    let bitangent = normal.cross(&tangent);
+    let rotation = ((seed & 0xffff) as f32 / 65536.0, ((seed >> 16) & 0xffff) as f32 / 65536.0);
+    let origin = point + normal * settings.bias;
+
+    let mut sum = [0.0; 3];
+    for i in 0..samples {
+        let u = (i as f32 / samples as f32 + rotation.0).fract();
+        let v = ((i as u32).reverse_bits() as f32 / 4_294_967_296.0 + rotation.1).fract();
+        // Muestra uniforme del disco proyectada al hemisferio: peso coseno
+        let (radius, angle) = (u.sqrt(), 2.0 * PI * v);
+        let direction = tangent * (radius * angle.cos()) + bitangent * (radius * angle.sin()) + normal * (1.0 - u).sqrt();
+        let hit = closest_hit(&origin, &direction, objects, settings.bvh.as_deref(), settings.bias, false);
+        let (color, weight) = match hit {
+            Some((intersect, hit_object)) => (
+                shade(&intersect, hit_object, &origin, &direction, objects, lights, settings.max_depth, 1.0, sky_color, &[], settings, None, None),
+                1.0,
+            ),
+            None => (settings.linear(sky_toward(settings, sky_color, lights, &direction)), crate::irradiance::AMBIENT),
+        };
+        for (channel, value) in sum.iter_mut().zip(color.channels()) {
+            *channel += value * weight;
+        }
+    }
+    sum.map(|channel| channel / samples as f32)
+}
+
+// Modifica la función cast_ray para usar el color del cielo variable.
+// Con una sonda, cada rayo trazado queda registrado como un tramo del camino.
+#[allow(clippy::too_many_arguments)]
+pub fn cast_ray(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    objects: &[SceneObject],
+    lights: &[SceneLight],
+    depth: u32,
+    throughput: f32,
+    sky_color: Color,
+    planar_reflections: &[PlanarReflection],
+    settings: &RenderSettings,
+    probe: Option<&mut ProbePath>,
+) -> LinearColor {
+    let settings = with_current_bvh(settings, objects);
+    trace_ray(ray_origin, ray_direction, objects, lights, depth, throughput, sky_color, planar_reflections, &settings, probe)
+}
+
+// `cast_ray` con el árbol de `settings` ya revisado, para los rebotes
+#[allow(clippy::too_many_arguments)]
+fn trace_ray(
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    objects: &[SceneObject],
+    lights: &[SceneLight],
+    depth: u32,
+    throughput: f32,
+    sky_color: Color,
+    planar_reflections: &[PlanarReflection],
+    settings: &RenderSettings,
+    mut probe: Option<&mut ProbePath>,
+) -> LinearColor {
+    let _span = profile::span(if depth == 0 { Span::Primary } else { Span::Secondary });
+    if depth > settings.max_depth {
+        if let Some(path) = probe {
+            path.note(format!("  profundidad máxima ({}) alcanzada: cielo", settings.max_depth));
+        }
+        return settings.linear(sky_toward(settings, sky_color, lights, ray_direction));
+    }
+
+    // Los rayos secundarios ya salen desplazados, pero además ignoran impactos
+    // más cercanos que el sesgo
+    let t_min = if depth == 0 { 0.0 } else { settings.bias };
+    match closest_hit(ray_origin, ray_direction, objects, settings.bvh.as_deref(), t_min, false) {
+        Some((intersect, hit_object)) => {
+            if let Some(path) = probe.as_deref_mut() {
+                path.hit(ray_origin, &intersect.point);
+            }
+            shade(
+                &intersect,
+                hit_object,
+                ray_origin,
+                ray_direction,
+                objects,
+                lights,
+                depth,
+                throughput,
+                sky_color,
+                planar_reflections,
+                settings,
+                None,
+                probe,
+            )
+        }
+        None => {
+            if let Some(path) = probe {
+                path.miss(ray_origin, ray_direction);
+            }
+            settings.linear(sky_toward(settings, sky_color, lights, ray_direction))
+        }
+    }
+}
+
+// Cielo que se ve en la dirección de un rayo que no impacta: la imagen de
+// cielo oscurecida según la hora si la hay, o el analítico o el degradado con
+// el color del frame, con el sol y la luna encima. Sobre la imagen no se
+// dibujan porque puede traer los suyos
+fn sky_toward(settings: &RenderSettings, sky_color: Color, lights: &[SceneLight], direction: &Vec3) -> Color {
+    if let Some(skybox) = &settings.skybox {
+        return skybox.color(direction) * mix(NIGHT_SKYBOX_TINT, Color::white(), daylight(lights).clamp(0.0, 1.0));
+    }
+    let sky = match &settings.sky {
+        Some(sky) => sky.radiance(direction),
+        None => gradient_sky(sky_color, lights, direction),
+    };
+    with_sun_and_moon(sky, lights, direction)
+}
+
+// Reflejo planar precalculado que cubre la cara impactada, si lo hay
+fn planar_for<'a>(planar_reflections: &'a [PlanarReflection], hit_object: usize, normal: &Vec3) -> Option<&'a PlanarReflection> {
+    planar_reflections
+        .iter()
+        .find(|planar| planar.object == hit_object && normal[planar.axis].abs() > 0.99)
+}
+
+// Traza el rayo reflejado en un impacto respecto de la normal de sombreado
+// `normal`. Si esa normal lo mandaría dentro de la superficie se refleja en la
+// geométrica.
+#[allow(clippy::too_many_arguments)]
+fn trace_reflection(
+    intersect: &Intersect,
+    normal: &Vec3,
+    ray_direction: &Vec3,
+    objects: &[SceneObject],
+    lights: &[SceneLight],
+    depth: u32,
+    throughput: f32,
+    sky_color: Color,
+    settings: &RenderSettings,
+    probe: Option<&mut ProbePath>,
+) -> LinearColor {
+    let mut reflect_dir = reflect(ray_direction, normal).normalize();
+    if reflect_dir.dot(&intersect.normal) * ray_direction.dot(&intersect.normal) > 0.0 {
+        reflect_dir = reflect(ray_direction, &intersect.normal).normalize();
+    }
+    let reflect_origin = offset_origin(intersect, &reflect_dir, settings.bias);
+    trace_ray(&reflect_origin, &reflect_dir, objects, lights, depth + 1, throughput, sky_color, &[], settings, probe)
+}
+
+// Calcula la luz que se ve en un impacto: iluminación, reflexión y
+// refracción, sin saturar; los rebotes se suman así y solo el píxel se lleva
+// al rango guardado con `RenderSettings::display`. Con `half_res` las sombras
+// y el reflejo salen de la pasada de media resolución cuando sus muestras
+// coinciden.
+#[allow(clippy::too_many_arguments)]
+fn shade(
+    intersect: &Intersect,
+    hit_object: usize,
+    ray_origin: &Vec3,
+    ray_direction: &Vec3,
+    objects: &[SceneObject],
+    lights: &[SceneLight],
+    depth: u32,
+    throughput: f32,
+    sky_color: Color,
+    planar_reflections: &[PlanarReflection],
+    settings: &RenderSettings,
+    half_res: Option<&Upsample>,
+    mut probe: Option<&mut ProbePath>,
+) -> LinearColor {
+    // Añadir la emisión del material al color base
+    let emission = emission_at(intersect, settings);
+
+    if let Some(path) = probe.as_deref_mut() {
+        let material = &intersect.material;
+        path.note(format!(
+            "rebote {}: objeto {} (grupo '{}') en ({:.3}, {:.3}, {:.3}) a distancia {:.3}",
+            depth, hit_object, objects[hit_object].group(),
+            intersect.point.x, intersect.point.y, intersect.point.z, intersect.distance,
+        ));
+        path.note(format!(
+            "  normal ({:.2}, {:.2}, {:.2}), material '{}' #{:06x}{}{} brillo {} ior {}",
+            intersect.normal.x, intersect.normal.y, intersect.normal.z,
+            material.name,
+            material.color.to_hex(),
+            if material.texture.is_some() { " con textura" } else { "" },
+            if material.unlit { " sin iluminar" } else { "" },
+            material.shininess, material.refractive_index,
+        ));
+        path.note(format!(
+            "  pesos: difuso {} especular {} reflejo {} refraccion {}",
+            material.diffuse, material.specular, material.reflectivity, material.transparency,
+        ));
+    }
+
+    let hit = &objects[hit_object];
+    let filter = settings.texture_filter.unwrap_or(intersect.material.filter);
+    let material_color = intersect
+        .material
+        .texture
+        .as_ref()
+        .map_or(intersect.material.color, |texture| sample_surface(texture, intersect, filter));
+    
+    let view_dir = (ray_origin - intersect.point).normalize();
+    let group = hit.group();
+
+    // Normal con la que se ilumina, se refleja y se refracta: la geométrica
+    // salvo en el bisel de las aristas de los cubos y donde el mapa de
+    // normales la inclina
+    let bevel = intersect.material.edge_bevel;
+    let normal = match hit.as_cube() {
+        Some(cube) if bevel > 0.0 => cube.bevel_normal(&intersect.point, &intersect.normal, bevel),
+        _ => intersect.normal,
+    };
+    let normal = match (&intersect.material.normal_map, hit.as_cube()) {
+        (Some(normal_map), Some(_)) if intersect.material.uv_mode == UvMode::Face => {
+            mapped_normal(normal_map, intersect, &normal, filter)
+        }
+        _ => normal,
+    };
+    let beveled = normal != intersect.normal;
+
+    // Determinar si el material tiene una textura
+    let has_texture = intersect.material.texture.is_some();
+
+    // Luz que dejan los bloques vecinos en las esquinas de la cara; no apaga la emisión
+    let corner_light = settings
+        .corner_ao
+        .as_ref()
+        .map_or(1.0, |occlusion| occlusion.factor(&intersect.point, &intersect.normal));
+
+    // Calcular el color base, en el espacio en que se suman las luces
+    // Las calcomanías se mezclan sobre el color difuso, así reciben la misma luz
+    let surface_color = settings.linear(match hit.as_cube() {
+        Some(cube) => decal::apply(cube, &intersect.point, &intersect.normal, material_color),
+        None => material_color,
+    });
+    let base_color = if intersect.material.unlit {
+        // Sin iluminar; con `receives_shadows` se oscurece con la sombra más
+        // fuerte de las luces de su grupo
+        let shadowed = if intersect.material.receives_shadows {
+            let shadow_intensity = lights
+                .iter()
+                .enumerate()
+                .filter(|(_, light)| light.affects(group))
+                .map(|(index, light)| {
+                    half_res
+                        .and_then(|half_res| half_res.shadow(index))
+                        .unwrap_or_else(|| cast_shadow(intersect, light, objects, settings.bvh.as_deref(), settings.bias, settings.shadow_quality))
+                })
+                .fold(0.0, f32::max);
+            if let Some(path) = probe.as_deref_mut() {
+                path.note(format!("  {} sobre la textura: {:.3}", SegmentKind::Shadow.name(), shadow_intensity));
+            }
+            1.0 - TEXTURE_SHADOW * shadow_intensity
+        } else {
+            1.0
+        };
+        // Con el cielo analítico recibe además su luz de cielo
+        let unlit = surface_color * (corner_light * shadowed);
+        let unlit = settings.sky.as_ref().map_or(unlit, |sky| unlit.scaled(sky.skylight()));
+        unlit + emission // Añadir emisión
+    } else {
+        // Iluminar sumando cada luz enlazada al grupo; la textura, si la hay,
+        // hace de color difuso
+        let mut lit = LinearColor::default();
+        for (index, light) in lights.iter().enumerate().filter(|(_, light)| light.affects(group)) {
+            // Intensity of the light hitting the object
+            let (light_dir, light_distance) = light.toward(&intersect.point);
+            let reflect_dir = reflect(&-light_dir, &normal).normalize();
+
+            // La caché de media resolución guarda solo la intensidad, sin tinte
+            let shadow = half_res
+                .and_then(|half_res| half_res.shadow(index))
+                .map(|shadow| [shadow; 3])
+                .unwrap_or_else(|| cast_tinted_shadow(intersect, light, objects, settings.bvh.as_deref(), settings.bias, settings.shadow_quality));
+            if let Some(path) = probe.as_deref_mut() {
+                path.shadow(&intersect.point, &light.source_for(&intersect.point), shadow_mean(shadow) > 0.0);
+                path.note(format!(
+                    "  {}: intensidad de sombra {:.3} ({:.3}, {:.3}, {:.3})",
+                    SegmentKind::Shadow.name(),
+                    shadow_mean(shadow),
+                    shadow[0],
+                    shadow[1],
+                    shadow[2]
+                ));
+            }
+            // Una sombra gris apaga la luz y una teñida filtra su color canal por canal
+            let (shadow_intensity, light_color) = match shadow {
+                [r, g, b] if r == g && g == b => (r, settings.linear(light.color)),
+                shadow => (0.0, settings.linear(light.color).scaled(shadow.map(|channel| 1.0 - channel))),
+            };
+            // Las sombras se prueban hasta la luz misma; la atenuación usa esa misma distancia
+            let mut light_intensity =
+                light.intensity * light.attenuation(light_distance) * light.cone(&intersect.point) * (1.0 - shadow_intensity);
+            // Debajo del agua el sol que pasó la sombra llega con cáusticas
+            if let Some(caustics) = settings.caustics.as_ref().filter(|_| light.is_sun && light_intensity > 0.0) {
+                let reach = 1.0 - shadow_mean(shadow);
+                let factor = caustics::sun_factor(intersect, &light_dir, reach, objects, settings.bvh.as_deref(), settings.bias, caustics);
+                if let Some(path) = probe.as_deref_mut().filter(|_| factor != 1.0) {
+                    path.note(format!("  causticas: el sol x {:.3}", factor));
+                }
+                light_intensity *= factor;
+            }
+
+            let diffuse_intensity = normal.dot(&light_dir).clamp(0.0, 1.0);
+            // El color de la superficie filtrado por el de la luz; el producto
+            // de los pesos se aplica de una vez
+            let diffuse = surface_color * light_color * (intersect.material.diffuse * diffuse_intensity * light_intensity);
+
+            let specular_intensity = view_dir.dot(&reflect_dir).max(0.0).powf(intersect.material.shininess);
+            let specular = light_color * intersect.material.specular * specular_intensity * light_intensity;
+
+            lit = lit + diffuse + specular;
+        }
+
+        // Luz ambiente con el color del cielo o, con la caché de irradiancia,
+        // la que rebota en las superficies cercanas tiñendo con su color. Se
+        // busca del lado del que llega el rayo, por si impactó la cara de
+        // atrás de dos caras que coinciden
+        let sky_light = settings.linear(sky_color);
+        let ambient = match &settings.irradiance {
+            Some(cache) => {
+                let outward = if normal.dot(ray_direction) > 0.0 { -normal } else { normal };
+                cache.irradiance(&intersect.point, &outward, (sky_light * crate::irradiance::AMBIENT).channels())
+            }
+            None => (sky_light * settings.ambient).channels(),
+        };
+        lit = lit + surface_color.scaled(ambient) * intersect.material.diffuse;
+
+        // La oclusión ambiental solo se calcula en los rayos primarios y, como
+        // las esquinas, oscurece la luz pero no la emisión
+        let visibility = match settings.ambient_occlusion {
+            Some(occlusion) if depth == 0 => ambient_visibility(intersect, &normal, objects, settings, &occlusion),
+            _ => 1.0,
+        };
+        if let Some(path) = probe.as_deref_mut().filter(|_| visibility < 1.0) {
+            path.note(format!("  oclusion ambiental: visibilidad {:.3}", visibility));
+        }
+
+        lit * (corner_light * visibility) + emission // Añadir emisión
+    };
+
+    // Con Fresnel el reflejo y la refracción se reparten lo que sumaban sus
+    // pesos según el ángulo; si no, cada uno usa el suyo
+    let material = &intersect.material;
+    let (reflectivity, transparency) = if material.use_fresnel && material.transparency > 0.0 {
+        let reflected = fresnel(ray_direction, &refraction_normal(intersect, &normal, ray_direction), intersect.material.refractive_index);
+        if let Some(path) = probe.as_deref_mut() {
+            path.note(format!("  fresnel: se refleja {:.3}", reflected));
+        }
+        let weight = material.reflectivity + material.transparency;
+        (weight * reflected, weight * (1.0 - reflected))
+    } else {
+        (material.reflectivity, material.transparency)
+    };
+
+    // Un rebote que pasaría la profundidad máxima o que aportaría menos que
+    // el corte no se traza y su peso deja de contar, así que queda el color
+    // base: con `max_depth` 0 los rayos primarios no rebotan
+    let bounces = |weight: f32| weight > 0.0 && depth < settings.max_depth && throughput * weight >= settings.min_throughput;
+    if let Some(path) = probe.as_deref_mut() {
+        for (weight, name) in [(reflectivity, "reflejo"), (transparency, "refraccion")] {
+            if weight > 0.0 && !bounces(weight) {
+                path.note(format!("  {} sin trazar: peso acumulado {:.4}", name, throughput * weight));
+            }
+        }
+    }
+    let mut reflectivity = if bounces(reflectivity) { reflectivity } else { 0.0 };
+    let transparency = if bounces(transparency) { transparency } else { 0.0 };
+
+    // Los espejos con reflejo planar pesan el reflejo con Schlick, tanto al
+    // muestrear el búfer como cuando el punto cae fuera de él y se traza, así
+    // no se nota el borde entre los dos
+    if material.planar_reflection && !(material.use_fresnel && material.transparency > 0.0) {
+        reflectivity = schlick(view_dir.dot(&normal).abs(), reflectivity);
+    }
+
+    // Reflected color
+    let mut reflect_color = LinearColor::default();
+    if reflectivity > 0.0 {
+        // Si la superficie tiene reflejo planar precalculado, se muestrea en lugar de trazar
+        let planar_color = planar_for(planar_reflections, hit_object, &intersect.normal)
+            .and_then(|planar| planar.sample(&intersect.point));
+
+        if let Some(color) = planar_color {
+            reflect_color = color;
+        } else if let Some(color) = half_res.filter(|_| !beveled).and_then(Upsample::reflection) {
+            reflect_color = settings.linear(color);
+        } else {
+            if let Some(path) = probe.as_deref_mut() {
+                path.begin(SegmentKind::Reflect);
+            }
+            reflect_color = trace_reflection(
+                intersect,
+                &normal,
+                ray_direction,
+                objects,
+                lights,
+                depth,
+                throughput * reflectivity,
+                sky_color,
+                settings,
+                probe.as_deref_mut(),
+            );
+        }
+    }
+    
+    // Refracted color
+    let mut refract_color = LinearColor::default();
+    if transparency > 0.0 {
+        let refract_dir = refract(ray_direction, &refraction_normal(intersect, &normal, ray_direction), intersect.material.refractive_index);
+        let refract_origin = offset_origin(intersect, &refract_dir, settings.bias);
+        if let Some(path) = probe.as_deref_mut() {
+            path.begin(SegmentKind::Refract);
+        }
+        // Al entrar, la luz que sale de atrás cruzó el interior hasta la
+        // salida, o hasta lo primero que haya adentro (el fondo de una pileta),
+        // y pierde lo que absorbe el material en ese tramo
+        let inside = (intersect.material.absorption_density > 0.0 && ray_direction.dot(&intersect.normal) < 0.0).then(|| {
+            let exit = objects[hit_object].exit_distance(&refract_origin, &refract_dir);
+            closest_hit(&refract_origin, &refract_dir, objects, settings.bvh.as_deref(), settings.bias, false)
+                .map_or(exit, |(hit, _)| hit.distance.min(exit))
+        });
+        if let (Some(path), Some(inside)) = (probe.as_deref_mut(), inside) {
+            path.note(format!("  absorcion: {:.3} unidades por dentro", inside));
+        }
+        refract_color = trace_ray(
+            &refract_origin,
+            &refract_dir,
+            objects,
+            lights,
+            depth + 1,
+            throughput * transparency,
+            sky_color,
+            &[],
+            settings,
+            probe,
+        );
+        if let Some(inside) = inside {
+            refract_color = refract_color.scaled(intersect.material.transmittance(inside));
+        }
+    }
+    
+    // Combinar los colores
+    if has_texture {
+        base_color * (1.0 - reflectivity - transparency) + (reflect_color * reflectivity) + (refract_color * transparency)
+    } else {
+        base_color + (reflect_color * reflectivity) + (refract_color * transparency)
+    }
+}
+
+// Color de una imagen del material en el punto del impacto, con las
+// coordenadas que pide su `uv_mode`
+fn sample_surface(texture: &Texture, intersect: &Intersect, filter: TextureFilter) -> Color {
+    match intersect.material.uv_mode {
+        UvMode::Face => texture.sample(intersect.uv.0.rem_euclid(1.0), intersect.uv.1.rem_euclid(1.0), filter, intersect.footprint),
+        mode => texture.sample_world(&intersect.point, &intersect.normal, mode, intersect.material.texture_scale, filter),
+    }
+}
+
+// Luz que emite el punto del impacto: la del mapa de emisión si lo hay o el
+// color plano, por la intensidad
+fn emission_at(intersect: &Intersect, settings: &RenderSettings) -> LinearColor {
+    let material = &intersect.material;
+    let emission = match &material.emission_map {
+        Some(map) => sample_surface(map, intersect, settings.texture_filter.unwrap_or(material.filter)),
+        None => material.emission,
+    };
+    settings.linear(emission) * material.emission_strength
+}
+
+// Normal de sombreado inclinada por el mapa de normales de un cubo: el texel
+// pasa de 0..255 a -1..1 y se lleva al marco de la cara, con u y v como
+// tangentes y la normal que llega (quizás biselada) como eje z
+fn mapped_normal(normal_map: &Texture, intersect: &Intersect, normal: &Vec3, filter: TextureFilter) -> Vec3 {
+    let texel = normal_map.sample(intersect.uv.0.rem_euclid(1.0), intersect.uv.1.rem_euclid(1.0), filter, intersect.footprint);
+    let [x, y, z] = [texel.red(), texel.green(), texel.blue()].map(|channel| channel as f32 / 255.0 * 2.0 - 1.0);
+    let [tangent, bitangent] = Face::from_normal(&intersect.normal).tangents();
+    let bent = tangent * x + bitangent * y + normal * z;
+    if bent.magnitude() > 1e-6 { bent.normalize() } else { *normal }
+}
+
+// La refracción usa la normal de sombreado mientras quede del mismo lado que
+// la geométrica respecto del rayo; si no, entrar y salir se confundirían
+fn refraction_normal(intersect: &Intersect, normal: &Vec3, ray_direction: &Vec3) -> Vec3 {
+    if normal.dot(ray_direction) * intersect.normal.dot(ray_direction) > 0.0 { *normal } else { intersect.normal }
+}
+
+// Origen y dirección del rayo primario que pasa por el píxel (x, y)
+pub(crate) fn primary_ray(camera: &Camera, x: usize, y: usize, width: usize, height: usize) -> (Vec3, Vec3) {
+    primary_ray_at(camera, x as f32, y as f32, width, height)
+}
+
+// Como `primary_ray`, en un punto cualquiera de la pantalla en píxeles. En
+// perspectiva todos parten del ojo; en ortográfica salen paralelos a la
+// vista desde un plano que pasa por el ojo
+fn primary_ray_at(camera: &Camera, x: f32, y: f32, width: usize, height: usize) -> (Vec3, Vec3) {
+    let aspect_ratio = width as f32 / height as f32;
+
+    let screen_x = (2.0 * x) / width as f32 - 1.0;
+    let screen_y = -(2.0 * y) / height as f32 + 1.0;
+
+    match camera.projection {
+        Projection::Perspective => {
+            let perspective_scale = camera.perspective_scale();
+            let screen_x = screen_x * aspect_ratio * perspective_scale;
+            let screen_y = screen_y * perspective_scale;
+
+            let ray_direction = normalize(&Vec3::new(screen_x, screen_y, -1.0));
+            (camera.eye, camera.basis_change(&ray_direction))
+        }
+        Projection::Orthographic { half_height } => {
+            let (right, up, forward) = camera.basis();
+            let origin = camera.eye + right * (screen_x * aspect_ratio * half_height) + up * (screen_y * half_height);
+            (origin, forward)
+        }
+    }
+}
+
+// Desplazamiento dentro del píxel de la muestra `index` de `count`: un patrón
+// de Hammersley, estratificado en las dos direcciones y siempre igual, así la
+// imagen quieta no titila. Las muestras rodean el punto por el que pasa el
+// rayo único, para que la imagen no se corra al cambiar la cantidad
+fn subpixel_offset(index: u32, count: u32) -> (f32, f32) {
+    let u = (index as f32 + 0.5) / count as f32;
+    let v = index.reverse_bits() as f32 / 4_294_967_296.0 + 0.5 / count.next_power_of_two() as f32;
+    (u - 0.5, v - 0.5)
+}
+
+// Traza `count` muestras de un píxel con `sample`, que recibe su
+// desplazamiento, y las promedia: el color y el halo en luz lineal, el alfa
+// como cobertura y la profundidad y el objeto de la más cercana. Con fondo
+// transparente cada color pesa según su alfa, así el fondo invisible no tiñe
+// los bordes
+fn supersample(count: u32, transparent: bool, mut sample: impl FnMut(f32, f32) -> Sample) -> Sample {
+    if count <= 1 {
+        return sample(0.0, 0.0);
+    }
+    let linear = |color: Color| [color.red(), color.green(), color.blue()].map(srgb_to_linear);
+    let (mut color, mut glow, mut weight, mut alpha) = ([0.0f32; 3], [0.0f32; 3], 0.0, 0.0);
+    let mut nearest = Sample::default();
+    for index in 0..count {
+        let (dx, dy) = subpixel_offset(index, count);
+        let current = sample(dx, dy);
+        let coverage = if transparent { current.alpha as f32 / 255.0 } else { 1.0 };
+        for (sum, value) in color.iter_mut().zip(linear(current.color)) {
+            *sum += value * coverage;
+        }
+        for (sum, value) in glow.iter_mut().zip(linear(current.glow)) {
+            *sum += value;
+        }
+        weight += coverage;
+        alpha += current.alpha as f32;
+        if current.depth < nearest.depth || index == 0 {
+            nearest = current;
+        }
+    }
+    let encode = |sum: [f32; 3], total: f32| {
+        let [r, g, b] = sum.map(|channel| linear_to_srgb(if total > 0.0 { channel / total } else { 0.0 }));
+        Color::new(r, g, b)
+    };
+    Sample {
+        color: encode(color, weight),
+        alpha: (alpha / count as f32).round() as u8,
+        glow: encode(glow, count as f32),
+        ..nearest
+    }
+}
+
+// Objeto visible en el píxel (x, y): un solo rayo primario, sin sombrear
+pub fn pick(
+    x: usize,
+    y: usize,
+    framebuffer: &Framebuffer,
+    objects: &[SceneObject],
+    camera: &Camera,
+    settings: &RenderSettings,
+) -> Option<(Intersect, usize)> {
+    let (origin, direction) = primary_ray(camera, x, y, framebuffer.width, framebuffer.height);
+    SceneQuery::with_settings(objects, settings)
+        .raycast_clipped(&origin, &direction, settings.near, settings.cut_color.is_some())
+        .map(|hit| (hit.intersect, hit.object))
+}
+
+// Traza un único rayo primario con recursión completa registrando su camino.
+// No usa reflejos planares para que se vea el rebote real.
+pub fn probe_pixel(
+    x: usize,
+    y: usize,
+    framebuffer: &Framebuffer,
+    objects: &[SceneObject],
+    camera: &Camera,
+    lights: &[SceneLight],
+    settings: &RenderSettings,
+) -> ProbePath {
+    let mut path = ProbePath::new((x, y));
+    let (origin, direction) = primary_ray(camera, x, y, framebuffer.width, framebuffer.height);
+    let sky = sky_color(lights);
+    let color = settings.display(cast_ray(&origin, &direction, objects, lights, 0, 1.0, sky, &[], settings, Some(&mut path)));
+    path.note(format!("color final #{:06x}", color.to_hex()));
+    path
+}
+
+// Datos de costo del último frame renderizado
+#[derive(Debug, Clone, Copy, Default)]
+pub struct RenderStats {
+    pub primary_rays: usize,
+    // Píxeles cuyo color salió del frame anterior, de los `reusable_pixels`
+    // que podían salir (impactos en materiales no emisivos)
+    pub reused_pixels: usize,
+    pub reusable_pixels: usize,
+}
+
+// Modifica la función render para pasar el color del cielo
+pub fn render(
+    framebuffer: &mut Framebuffer,
+    objects: &[SceneObject],
+    camera: &Camera,
+    lights: &[SceneLight],
+    sky_color: Color,
+    settings: &RenderSettings,
+) -> RenderStats {
+    render_frame(framebuffer, objects, camera, lights, sky_color, settings, None)
+}
+
+// Como `render`, reusando lo que siga valiendo del frame anterior y guardando
+// este para el siguiente. Con muestreo adaptativo se renderiza como siempre.
+#[allow(clippy::too_many_arguments)]
+pub fn render_reprojected(
+    framebuffer: &mut Framebuffer,
+    objects: &[SceneObject],
+    camera: &Camera,
+    lights: &[SceneLight],
+    sky_color: Color,
+    settings: &RenderSettings,
+    reprojection: &mut Reprojection,
+) -> RenderStats {
+    render_frame(framebuffer, objects, camera, lights, sky_color, settings, Some(reprojection))
+}
+
+// Con `render_scale` menor que 1 se traza en un framebuffer más chico que
+// después se amplía al de destino, salvo que haya recorte
+#[allow(clippy::too_many_arguments)]
+fn render_frame(
+    framebuffer: &mut Framebuffer,
+    objects: &[SceneObject],
+    camera: &Camera,
+    lights: &[SceneLight],
+    sky_color: Color,
+    settings: &RenderSettings,
+    reprojection: Option<&mut Reprojection>,
+) -> RenderStats {
+    let settings = &*with_current_bvh(settings, objects);
+    let finished = "sin plazo el render siempre termina";
+    if settings.render_scale >= 1.0 || settings.crop.is_some() {
+        return render_pixels(framebuffer, objects, camera, lights, sky_color, settings, reprojection, None).expect(finished);
+    }
+
+    let scaled = |size: usize| ((size as f32 * settings.render_scale).round() as usize).max(1);
+    let mut small = Framebuffer::new(scaled(framebuffer.width), scaled(framebuffer.height));
+    let stats = render_pixels(&mut small, objects, camera, lights, sky_color, settings, reprojection, None).expect(finished);
+    small.upscale_into(framebuffer);
+    stats
+}
+
+// Como `render`, pero deja de trazar filas al pasar `deadline` y devuelve None
+// sin tocar el framebuffer, así nunca queda una imagen a medio escribir. Sin
+// plazo siempre termina. Se ignora `render_scale`; el muestreo adaptativo no
+// se interrumpe.
+#[allow(clippy::too_many_arguments)]
+pub(crate) fn render_until(
+    framebuffer: &mut Framebuffer,
+    objects: &[SceneObject],
+    camera: &Camera,
+    lights: &[SceneLight],
+    sky_color: Color,
+    settings: &RenderSettings,
+    deadline: Option<Instant>,
+) -> Option<RenderStats> {
+    render_pixels(framebuffer, objects, camera, lights, sky_color, &with_current_bvh(settings, objects), None, deadline)
+}
+
+#[allow(clippy::too_many_arguments)]
+fn render_pixels(
+    framebuffer: &mut Framebuffer,
+    objects: &[SceneObject],
+    camera: &Camera,
+    lights: &[SceneLight],
+    sky_color: Color,
+    settings: &RenderSettings,
+    reprojection: Option<&mut Reprojection>,
+    deadline: Option<Instant>,
+) -> Option<RenderStats> {
+    let width = framebuffer.width as f32;
+    let height = framebuffer.height as f32;
+    let aspect_ratio = width / height;
+    let perspective_scale = camera.perspective_scale();
+
+    // Píxeles que se trazan: el framebuffer entero o el recorte con un borde
+    // del alcance del halo, para que el de los objetos de al lado llegue a
+    // entrar. Con recorte no se reusa el historial, que es de imágenes enteras
+    let (full_width, full_height) = (framebuffer.width, framebuffer.height);
+    let crop = settings.crop.and_then(|crop| crop.clamped(full_width, full_height));
+    let region = crop
+        .and_then(|crop| crop.expanded(halo_reach(settings.halo_radius)).clamped(full_width, full_height))
+        .unwrap_or(Crop { x: 0, y: 0, width: full_width, height: full_height });
+    let reprojection = match (crop, reprojection) {
+        (Some(_), Some(reprojection)) => {
+            reprojection.clear();
+            None
+        }
+        (_, reprojection) => reprojection,
+    };
+
+    // Renderiza una vez los reflejos planares de los materiales que lo piden.
+    // El render espejado es en perspectiva, así que con la cámara ortográfica
+    // los reflejos se trazan
+    let orthographic = matches!(camera.projection, Projection::Orthographic { .. });
+    let planar_reflections: Vec<PlanarReflection> = objects
+        .iter()
+        .enumerate()
+        .filter(|(_, object)| !orthographic && object.material().planar_reflection && object.material().is_reflective())
+        .filter_map(|(index, _)| {
+            PlanarReflection::render(
+                index,
+                objects,
+                camera,
+                lights,
+                sky_color,
+                framebuffer.width / PLANAR_REFLECTION_DOWNSCALE,
+                framebuffer.height / PLANAR_REFLECTION_DOWNSCALE,
+                perspective_scale,
+                settings,
+            )
+        })
+        .collect();
+
+    // Lo que se ve detrás de la escena en el píxel (x, y), cuyo rayo primario
+    // va en `direction`
+    let background = |x: usize, y: usize, direction: &Vec3| {
+        let u = (x as f32 + 0.5) / width;
+        let v = (y as f32 + 0.5) / height;
+        settings
+            .sample_background(u, v, aspect_ratio)
+            .unwrap_or_else(|| settings.display(settings.linear(sky_toward(settings, sky_color, lights, direction))))
+    };
+
+    // Rayo primario que pasa por el punto (x, y) de la pantalla y lo que
+    // impacta, sin sombrear
+    let primary_at = |x: f32, y: f32| {
+        let ray = primary_ray_at(camera, x, y, framebuffer.width, framebuffer.height);
+        let hit = primary_hit(&ray.0, &ray.1, objects, settings.bvh.as_deref(), settings.near, settings.cut_color.is_some());
+        (ray, hit)
+    };
+    let primary = |x: usize, y: usize| primary_at(x as f32, y as f32);
+
+    // Primera pasada de media resolución: sombras y reflejo de cada impacto
+    // primario que los necesite
+    let half_res = (settings.half_res && crop.is_none()).then(|| {
+        HalfResolution::render(framebuffer.width, framebuffer.height, lights.len(), |x, y, shadows| {
+            let _span = profile::span(Span::Primary);
+            let ((_, direction), hit) = primary(x, y);
+            let (intersect, hit_object) = match hit {
+                Some((intersect, hit_object, false)) if !intersect.material.shadow_catcher => (intersect, hit_object),
+                _ => return None,
+            };
+
+            // Las sombras cuentan en los materiales iluminados y en los que las
+            // reciben sin iluminarse
+            if !intersect.material.unlit || intersect.material.receives_shadows {
+                let group = objects[hit_object].group();
+                for (shadow, light) in shadows.iter_mut().zip(lights) {
+                    if light.affects(group) {
+                        *shadow = cast_shadow(&intersect, light, objects, settings.bvh.as_deref(), settings.bias, settings.shadow_quality);
+                    }
+                }
+            }
+            let reflectivity = intersect.material.reflectivity;
+            let reflects = reflectivity > 0.0
+                && settings.max_depth > 0
+                && planar_for(&planar_reflections, hit_object, &intersect.normal).is_none();
+            let reflection = reflects.then(|| {
+                settings.encode(trace_reflection(&intersect, &intersect.normal, &direction, objects, lights, 0, reflectivity, sky_color, settings, None))
+            });
+
+            Some(Guide {
+                depth: intersect.distance,
+                normal: intersect.normal,
+                reflection,
+            })
+        })
+    });
+
+    // Sombrea el impacto del rayo primario del píxel (x, y)
+    let shade_primary = |x: usize, y: usize, (origin, rotated_direction): &(Vec3, Vec3), hit: Option<(Intersect, usize, bool)>| -> Sample {
+        // Los rayos primarios que no impactan muestran el fondo fijo, si lo hay,
+        // y quedan con alfa 0; los reflejos siguen usando el cielo
+        match hit {
+            // Sección cortada por el plano cercano: color plano
+            Some((intersect, hit_object, true)) => Sample {
+                color: settings.cut_color.unwrap_or(sky_color),
+                alpha: 255,
+                object: Some(hit_object),
+                depth: intersect.distance,
+                glow: Color::black(),
+            },
+            // Receptor de sombras: deja ver el fondo salvo donde recibe sombra.
+            // Con fondo transparente el color queda sin mezclar (alfa directo).
+            Some((intersect, hit_object, false)) if intersect.material.shadow_catcher => {
+                let group = objects[hit_object].group();
+                let amount = occlusion(&intersect, group, objects, lights, settings);
+                let color = if settings.transparent {
+                    settings.shadow_color
+                } else {
+                    background(x, y, rotated_direction) * (1.0 - amount) + settings.shadow_color * amount
+                };
+                Sample {
+                    color,
+                    alpha: (amount * 255.0).round() as u8,
+                    object: Some(hit_object),
+                    depth: intersect.distance,
+                    glow: Color::black(),
+                }
+            }
+            Some((intersect, hit_object, false)) => {
+                let upsample = half_res
+                    .as_ref()
+                    .and_then(|half_res| half_res.upsample(x, y, intersect.distance, &intersect.normal));
+                let color = settings.display(shade(
+                    &intersect,
+                    hit_object,
+                    origin,
+                    rotated_direction,
+                    objects,
+                    lights,
+                    0,
+                    1.0,
+                    sky_color,
+                    &planar_reflections,
+                    settings,
+                    upsample.as_ref(),
+                    None,
+                ));
+
+                // Aporte al halo: la emisión del material o, si no emite, su color visto
+                let material = &intersect.material;
+                let glow = if material.halo <= 0.0 {
+                    Color::black()
+                } else if material.is_emissive() {
+                    settings.display(emission_at(&intersect, settings) * material.halo)
+                } else {
+                    color * material.halo
+                };
+
+                Sample {
+                    color,
+                    alpha: 255,
+                    object: Some(hit_object),
+                    depth: intersect.distance,
+                    glow,
+                }
+            }
+            None => Sample {
+                color: background(x, y, rotated_direction),
+                ..Sample::default()
+            },
+        }
+    };
+
+    // Las coordenadas son dentro de la región
+    let samples_per_pixel = settings.samples_per_pixel.max(1);
+    let trace = |x: usize, y: usize| -> Sample {
+        let _span = profile::span(Span::Primary);
+        let (x, y) = (x + region.x, y + region.y);
+        supersample(samples_per_pixel, settings.transparent, |dx, dy| {
+            let (ray, hit) = primary_at(x as f32 + dx, y as f32 + dy);
+            shade_primary(x, y, &ray, hit)
+        })
+    };
+
+    let frame_chunk = profile::chunk("trazado", None);
+    let (pixel_buffer, primary_rays, (reused_pixels, reusable_pixels)) = match (&settings.adaptive, reprojection) {
+        (Some(adaptive), reprojection) => {
+            // Las muestras interpoladas no sirven como historial
+            if let Some(reprojection) = reprojection {
+                reprojection.clear();
+            }
+            let (pixel_buffer, rays) = adaptive.render(region.width, region.height, &trace);
+            (pixel_buffer, rays * samples_per_pixel as usize, (0, 0))
+        }
+        (None, Some(reprojection)) => {
+            reprojection.prepare(framebuffer.width, framebuffer.height, lights, settings);
+            let history = &*reprojection;
+            let mut pixels = vec![HistoryPixel::fixed(Sample::default()); framebuffer.width * framebuffer.height];
+
+            // Todos los píxeles trazan su rayo primario; solo los que no se
+            // encuentran en el frame anterior se sombrean, con tantas muestras
+            // como pida `samples_per_pixel`, y esa muestra queda en el
+            // historial
+            let shade_fresh = |x: usize, y: usize, ray: &(Vec3, Vec3), hit| {
+                if samples_per_pixel <= 1 {
+                    return shade_primary(x, y, ray, hit);
+                }
+                supersample(samples_per_pixel, settings.transparent, |dx, dy| {
+                    let (ray, hit) = primary_at(x as f32 + dx, y as f32 + dy);
+                    shade_primary(x, y, &ray, hit)
+                })
+            };
+            let counts = pixels
+                .par_chunks_mut(framebuffer.width)
+                .enumerate()
+                .map(|(y, row)| {
+                    let _chunk = profile::chunk("fila", Some(y));
+                    let (mut reused, mut reusable) = (0, 0);
+                    for (x, pixel) in row.iter_mut().enumerate() {
+                        let _span = profile::span(Span::Primary);
+                        let (ray, hit) = primary(x, y);
+                        *pixel = match hit {
+                            Some((intersect, object, false)) if reprojection::reusable(&intersect.material) => {
+                                reusable += 1;
+                                match history.lookup(x, y, &intersect.point, &intersect.normal, object, aspect_ratio) {
+                                    Some(previous) => {
+                                        reused += 1;
+                                        HistoryPixel {
+                                            sample: Sample { depth: intersect.distance, ..previous.sample },
+                                            normal: intersect.normal,
+                                            ..previous
+                                        }
+                                    }
+                                    None => HistoryPixel {
+                                        normal: intersect.normal,
+                                        point: intersect.point,
+                                        reusable: true,
+                                        sample: shade_fresh(x, y, &ray, Some((intersect, object, false))),
+                                    },
+                                }
+                            }
+                            hit => HistoryPixel::fixed(shade_fresh(x, y, &ray, hit)),
+                        };
+                    }
+                    (reused, reusable)
+                })
+                .reduce(|| (0, 0), |a, b| (a.0 + b.0, a.1 + b.1));
+
+            let pixel_buffer: Vec<Sample> = pixels.iter().map(|pixel| pixel.sample).collect();
+            // Los píxeles sombreados de nuevo suman sus muestras de más
+            let rays = pixel_buffer.len() + (pixel_buffer.len() - counts.0) * (samples_per_pixel as usize - 1);
+            reprojection.store(camera, framebuffer.width, framebuffer.height, lights, settings, pixels);
+            (pixel_buffer, rays, counts)
+        }
+        (None, None) => {
+            // Reparte la región en teselas, una por tarea, y traza cada una
+            // fila por fila, así los rayos vecinos recorren los mismos objetos
+            // y texels; con plazo, cada tesela mira antes si ya se acabó el
+            // tiempo
+            let tile = settings.tile_size.max(1);
+            let columns = region.width.div_ceil(tile);
+            let bounds = |index: usize| {
+                let (x, y) = (index % columns * tile, index / columns * tile);
+                (x, y, (x + tile).min(region.width), (y + tile).min(region.height))
+            };
+            let expired = AtomicBool::new(false);
+            let tiles: Vec<Vec<Sample>> = (0..columns * region.height.div_ceil(tile))
+                .into_par_iter()
+                .map(|index| {
+                    if deadline.is_some_and(|deadline| expired.load(Ordering::Relaxed) || Instant::now() >= deadline) {
+                        expired.store(true, Ordering::Relaxed);
+                        return Vec::new();
+                    }
+                    let _chunk = profile::chunk("tesela", Some(index));
+                    let (x0, y0, x1, y1) = bounds(index);
+                    (y0..y1).flat_map(|y| (x0..x1).map(move |x| (x, y))).map(|(x, y)| trace(x, y)).collect()
+                })
+                .collect();
+            if expired.into_inner() {
+                return None;
+            }
+
+            // Copia las teselas al búfer de la región
+            let mut pixel_buffer = vec![Sample::default(); region.width * region.height];
+            for (index, samples) in tiles.iter().enumerate() {
+                let (x0, y0, x1, _) = bounds(index);
+                for (row, line) in samples.chunks(x1 - x0).enumerate() {
+                    let start = (y0 + row) * region.width + x0;
+                    pixel_buffer[start..start + line.len()].copy_from_slice(line);
+                }
+            }
+
+            let rays = pixel_buffer.len() * samples_per_pixel as usize;
+            (pixel_buffer, rays, (0, 0))
+        }
+    };
+
+    drop(frame_chunk);
+
+    // Finalmente, vuelca el pixel_buffer en el framebuffer
+    let _post = profile::phase(Span::Post);
+    // Con recorte, el borde y lo que los efectos de posproceso desborden se
+    // restauran después. Los rayos de sol miran toda la pantalla, así que
+    // dentro del recorte usan lo que quedó afuera del render anterior
+    let outside = crop.map(|_| framebuffer.buffer.clone());
+    let mut glow = vec![Color::black(); framebuffer.width * framebuffer.height];
+    for (index, sample) in pixel_buffer.iter().enumerate() {
+        let x = region.x + index % region.width;
+        let y = region.y + index / region.width;
+        let target = y * framebuffer.width + x;
+        framebuffer.set_current_color(sample.color);
+        framebuffer.point(x, y);
+        framebuffer.set_alpha(x, y, sample.alpha);
+        framebuffer.depth[target] = sample.depth;
+        glow[target] = sample.glow;
+    }
+
+    // Halo de los materiales que lo piden, sumado sobre la imagen final
+    apply_halo(framebuffer, &glow, settings.halo_radius);
+
+    // Rayos de sol, que necesitan la profundidad ya volcada para saber dónde se ve el cielo
+    if let Some(god_rays) = &settings.god_rays {
+        apply_god_rays(framebuffer, camera, lights, camera.perspective_scale(), god_rays);
+    }
+    // El resplandor va último, sobre la imagen con todo lo que brilla
+    if let Some(bloom) = &settings.bloom {
+        apply_bloom(framebuffer, bloom);
+    }
+
+    if let (Some(crop), Some(outside)) = (crop, outside) {
+        for (index, (pixel, previous)) in framebuffer.buffer.iter_mut().zip(outside).enumerate() {
+            if !crop.contains(index % framebuffer.width, index / framebuffer.width) {
+                *pixel = previous;
+            }
+        }
+    }
+
+    Some(RenderStats { primary_rays, reused_pixels, reusable_pixels })
+}
+
+// Cuánto es de día según la altura del sol, entre 0 y 1
+fn daylight(lights: &[SceneLight]) -> f32 {
+    let sun_height = lights.iter().find(|light| light.is_sun).map_or(2.0, |light| light.position.dot(&WORLD_UP));
+    (sun_height + 2.0) / 4.0 // Normaliza entre 0 y 1
+}
+
+// Mezcla entre dos colores: `from` con t = 0 y `to` con t = 1
+fn mix(from: Color, to: Color, t: f32) -> Color {
+    let channel = |from: u8, to: u8| (to as f32 * t + from as f32 * (1.0 - t)) as u8;
+    Color::new(channel(from.red(), to.red()), channel(from.green(), to.green()), channel(from.blue(), to.blue()))
+}
+
+// Color del cielo en el cenit según la altura del sol en el ciclo día/noche;
+// también es el de la luz ambiente
+pub fn sky_color(lights: &[SceneLight]) -> Color {
+    mix(NIGHT_SKY_COLOR, SKYBOX_COLOR, daylight(lights))
+}
+
+// Degradado del cielo en una dirección: de la bruma del horizonte, que
+// también pasa de la de noche a la de día, a `sky_color` en el cenit. La
+// bruma se queda cerca del horizonte y debajo se oscurece hacia abajo
+pub fn gradient_sky(sky_color: Color, lights: &[SceneLight], direction: &Vec3) -> Color {
+    let horizon = mix(NIGHT_HORIZON_COLOR, HORIZON_COLOR, daylight(lights));
+    let up = direction.try_normalize(1e-6).map_or(1.0, |direction| direction.dot(&WORLD_UP));
+    if up >= 0.0 {
+        mix(sky_color, horizon, (1.0 - up).powi(3))
+    } else {
+        horizon * (1.0 - 0.45 * smoothstep(0.0, 0.4, -up))
+    }
+}
+
+// Disco del sol donde la dirección apunta hacia él, con el color de su luz,
+// y de noche la luna en el lado opuesto. Los bordes se suavizan en el último
+// cuarto del radio y cada disco se apaga al pasar bajo el horizonte
+pub fn with_sun_and_moon(sky: Color, lights: &[SceneLight], direction: &Vec3) -> Color {
+    let (Some(sun), Some(direction)) = (lights.iter().find(|light| light.is_sun), direction.try_normalize(1e-6)) else {
+        return sky;
+    };
+    let toward_sun = sun.toward(&Vec3::zeros()).0;
+    let angle = direction.dot(&toward_sun).clamp(-1.0, 1.0).acos();
+    let disc = |radius: f32, angle: f32| 1.0 - smoothstep(radius * 0.75, radius, angle);
+    let sun_height = toward_sun.dot(&WORLD_UP);
+    let sun_amount = disc(SUN_RADIUS, angle) * smoothstep(-0.05, 0.05, sun_height);
+    let moon_amount = disc(MOON_RADIUS, PI - angle) * smoothstep(-0.05, 0.05, -sun_height);
+    mix(mix(sky, sun.color, sun_amount), MOON_COLOR, moon_amount)
+}
+
+fn smoothstep(low: f32, high: f32, x: f32) -> f32 {
+    let t = ((x - low) / (high - low)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
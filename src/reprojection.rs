@@ -0,0 +1,214 @@
+use nalgebra_glm::Vec3;
+
+use crate::adaptive::Sample;
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::light::{LightKind, SceneLight};
+use crate::material::Material;
+use crate::settings::{AmbientOcclusion, RenderSettings, ShadowMapSettings, ShadowQuality, ToneMapping};
+use crate::texture::TextureFilter;
+
+// Reproyección temporal: cada píxel traza solo su rayo primario y, si el
+// punto impactado ya se veía en el frame anterior sobre el mismo objeto, la
+// misma cara y a la misma profundidad, reusa el color ya sombreado en lugar de
+// trazar sombras y rebotes. El historial se descarta al cambiar las luces o
+// las opciones de render, así que solo ayuda mientras se mueve la cámara.
+#[derive(Debug, Clone)]
+pub struct Reprojection {
+    // Diferencia relativa de profundidad tolerada entre los dos frames
+    pub depth_tolerance: f32,
+    // Distancia máxima, en píxeles, entre el punto donde se sombreó el color
+    // y el punto actual; acota el corrimiento de un color reusado varias veces
+    pub max_drift: f32,
+    // Fracción de píxeles que se vuelven a trazar cada frame aunque sean
+    // reusables, para que nada quede viejo indefinidamente
+    pub refresh: f32,
+    history: Option<History>,
+    frame: u32,
+}
+
+impl Default for Reprojection {
+    fn default() -> Self {
+        Reprojection {
+            depth_tolerance: 0.02,
+            max_drift: 0.5,
+            refresh: 0.03,
+            history: None,
+            frame: 0,
+        }
+    }
+}
+
+// Lo que se guarda de cada píxel para el frame siguiente
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct HistoryPixel {
+    pub sample: Sample,
+    pub normal: Vec3,
+    // Punto donde se sombreó el color, que se conserva al reusarlo
+    pub point: Vec3,
+    pub reusable: bool,
+}
+
+impl HistoryPixel {
+    pub fn fixed(sample: Sample) -> Self {
+        HistoryPixel {
+            sample,
+            normal: Vec3::zeros(),
+            point: Vec3::zeros(),
+            reusable: false,
+        }
+    }
+}
+
+// Posición, color, intensidad, tipo, objetivo, atenuación y radio de una luz
+type LightKey = (Vec3, Color, f32, LightKind, Vec3, [f32; 2], f32);
+
+// Todo lo que, si cambia, invalida los colores del historial
+#[derive(Debug, Clone, PartialEq)]
+struct HistoryKey {
+    lights: Vec<LightKey>,
+    near: f32,
+    bias: f32,
+    texture_filter: Option<TextureFilter>,
+    cut_color: Option<Color>,
+    half_res: bool,
+    max_depth: u32,
+    shadow_quality: ShadowQuality,
+    shadow_map: Option<ShadowMapSettings>,
+    ambient_occlusion: Option<AmbientOcclusion>,
+    gamma_correct: bool,
+    exposure: f32,
+    tone_mapping: ToneMapping,
+    // Mientras la caché de luz indirecta se sigue llenando los colores cambian
+    irradiance: Option<usize>,
+}
+
+impl HistoryKey {
+    fn new(lights: &[SceneLight], settings: &RenderSettings) -> Self {
+        HistoryKey {
+            lights: lights
+                .iter()
+                .map(|light| (light.position, light.color, light.intensity, light.kind, light.target, light.falloff, light.radius))
+                .collect(),
+            near: settings.near,
+            bias: settings.bias,
+            texture_filter: settings.texture_filter,
+            cut_color: settings.cut_color,
+            half_res: settings.half_res,
+            max_depth: settings.max_depth,
+            shadow_quality: settings.shadow_quality,
+            shadow_map: settings.shadow_map,
+            ambient_occlusion: settings.ambient_occlusion,
+            gamma_correct: settings.gamma_correct,
+            exposure: settings.exposure,
+            tone_mapping: settings.tone_mapping,
+            irradiance: settings.irradiance.as_ref().map(|cache| cache.len()),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+struct History {
+    camera: Camera,
+    width: usize,
+    height: usize,
+    key: HistoryKey,
+    pixels: Vec<HistoryPixel>,
+}
+
+// Los emisivos, los animados y el agua se vuelven a trazar siempre: son los
+// materiales que cambian de aspecto por sí solos (brillo, halo, cuadros y
+// las cáusticas del fondo)
+pub(crate) fn reusable(material: &Material) -> bool {
+    !material.is_emissive() && !material.shadow_catcher && material.animation.is_none() && !material.caustics
+}
+
+impl Reprojection {
+    pub fn clear(&mut self) {
+        self.history = None;
+    }
+
+    // Descarta el historial si no sirve para el frame que empieza
+    pub(crate) fn prepare(&mut self, width: usize, height: usize, lights: &[SceneLight], settings: &RenderSettings) {
+        let key = HistoryKey::new(lights, settings);
+        let valid = self
+            .history
+            .as_ref()
+            .is_some_and(|history| history.width == width && history.height == height && history.key == key);
+        if !valid {
+            self.history = None;
+        }
+    }
+
+    // Píxeles que este frame se trazan sí o sí; un hash del píxel y del frame
+    // los reparte por toda la pantalla y cambia de frame a frame
+    fn refreshes(&self, x: usize, y: usize) -> bool {
+        let mut hash = (x as u32).wrapping_mul(0x9e37_79b1) ^ (y as u32).wrapping_mul(0x85eb_ca77) ^ self.frame.wrapping_mul(0xc2b2_ae3d);
+        hash ^= hash >> 15;
+        hash = hash.wrapping_mul(0x2c1b_3c6d);
+        hash ^= hash >> 12;
+        (hash % 1024) < (self.refresh * 1024.0) as u32
+    }
+
+    // Busca en el frame anterior el color del punto impactado por el píxel
+    // (x, y); None si hay que sombrearlo de nuevo
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn lookup(
+        &self,
+        x: usize,
+        y: usize,
+        point: &Vec3,
+        normal: &Vec3,
+        object: usize,
+        aspect_ratio: f32,
+    ) -> Option<HistoryPixel> {
+        let history = self.history.as_ref()?;
+        if self.refreshes(x, y) {
+            return None;
+        }
+        let perspective_scale = history.camera.perspective_scale();
+
+        // Inversa de `primary_ray`: el píxel x cubre screen_x = 2x/w - 1
+        let (screen_x, screen_y) = history.camera.project(point, aspect_ratio, perspective_scale)?;
+        let px = ((screen_x + 1.0) * 0.5 * history.width as f32).round();
+        let py = ((1.0 - screen_y) * 0.5 * history.height as f32).round();
+        if px < 0.0 || py < 0.0 || px >= history.width as f32 || py >= history.height as f32 {
+            return None;
+        }
+        let previous = history.pixels[py as usize * history.width + px as usize];
+
+        if !previous.reusable || previous.sample.object != Some(object) || previous.normal.dot(normal) < 0.99 {
+            return None;
+        }
+        let distance = history.camera.ray_distance(point);
+        if (previous.sample.depth - distance).abs() > self.depth_tolerance * distance {
+            return None;
+        }
+        // Tamaño de un píxel en el mundo a esa distancia
+        let footprint = history.camera.pixel_footprint(distance, history.height);
+        if (previous.point - point).magnitude() > self.max_drift * footprint {
+            return None;
+        }
+
+        Some(previous)
+    }
+
+    pub(crate) fn store(
+        &mut self,
+        camera: &Camera,
+        width: usize,
+        height: usize,
+        lights: &[SceneLight],
+        settings: &RenderSettings,
+        pixels: Vec<HistoryPixel>,
+    ) {
+        self.history = Some(History {
+            camera: camera.clone(),
+            width,
+            height,
+            key: HistoryKey::new(lights, settings),
+            pixels,
+        });
+        self.frame = self.frame.wrapping_add(1);
+    }
+}
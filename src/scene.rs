@@ -0,0 +1,1201 @@
+use nalgebra_glm::Vec3;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+use crate::buried::Occupancy;
+use crate::bvh::TraceBvh;
+use crate::camera::Camera;
+use crate::chunks::{ChunkCounts, ChunkSettings, ChunkStream};
+use crate::color::Color;
+use crate::cube::Cube;
+use crate::decal::{Decal, DecalBlend, Face};
+use crate::generate::{self, GenParams};
+use crate::light::{LightKind, SceneLight};
+use crate::material::Material;
+use crate::merge;
+use crate::object::SceneObject;
+use crate::plane::Plane;
+use crate::query::SceneQuery;
+use crate::rail::{CameraRail, RailKey, RailTarget};
+use crate::registry::default_materials;
+use crate::texture::{AnimatedTexture, ProceduralTexture, Texture, TextureCache, TextureFilter, UvMode};
+use crate::renderer::FOV;
+use crate::settings::DEFAULT_BIAS;
+use crate::sky::DEFAULT_TURBIDITY;
+use crate::sphere::Sphere;
+
+// Sesgo de origen por unidad de diagonal de la escena
+const BIAS_PER_UNIT: f32 = 2e-5;
+
+// Distancias mínima y máxima del zoom por unidad de diagonal de la escena, y
+// las que se usan si la escena no tiene objetos acotados
+const ZOOM_PER_UNIT: (f32, f32) = (0.05, 10.0);
+const DEFAULT_ZOOM_RANGE: (f32, f32) = (0.25, 50.0);
+
+pub struct Scene {
+    // Objetos que se trazan
+    pub objects: Vec<SceneObject>,
+    // Objetos tapados por completo que `cull_buried` sacó del trazado; siguen
+    // siendo parte de la escena y vuelven a `objects` si quedan a la vista
+    pub buried: Vec<SceneObject>,
+    pub materials: HashMap<String, Material>,
+    pub lights: Vec<SceneLight>,
+    pub camera: Camera,
+    // Oscurece las esquinas donde se juntan los bloques (ver `cornerao`);
+    // lo pide el archivo de escena
+    pub corner_ao: bool,
+    // Turbiedad del aire para el cielo analítico (ver `sky`): 2 es un día muy
+    // limpio y 10 uno brumoso
+    pub turbidity: f32,
+    // Recorrido de cámara del vuelo cinematográfico (ver `rail`); vacío si el
+    // archivo no lo define
+    pub rail: CameraRail,
+    occupancy: Option<Occupancy>,
+    // Trozos de la escena cuando se traza por cercanía (ver `chunks`); con
+    // ellos `objects` es solo lo que se traza desde la cámara actual
+    chunks: Option<ChunkStream>,
+    // Segundo al que `animate` llevó las texturas animadas
+    time: f32,
+    // Árbol con el que se trazan `objects`; las ediciones con `add_object`,
+    // `move_object` y `remove_object` lo mantienen sin reconstruirlo. Si el
+    // renderer todavía tiene el anterior se copia al editar, que cuesta mucho
+    // menos que construirlo
+    bvh: Arc<TraceBvh>,
+}
+
+// Archivo con el diorama que se abre sin escena en la línea de comandos
+pub const DEFAULT_SCENE: &str = "scenes/portal.ron";
+
+#[derive(Debug)]
+pub enum SceneError {
+    Io(std::io::Error),
+    Parse(ron::error::SpannedError),
+    UnknownMaterial(String),
+    UnknownObject(String),
+}
+
+impl fmt::Display for SceneError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SceneError::Io(e) => write!(f, "no se pudo leer el archivo: {}", e),
+            SceneError::Parse(e) => write!(f, "error de sintaxis: {}", e),
+            SceneError::UnknownMaterial(name) => write!(f, "material desconocido: {}", name),
+            SceneError::UnknownObject(name) => write!(f, "objeto desconocido: {}", name),
+        }
+    }
+}
+
+impl std::error::Error for SceneError {}
+
+// Lo que faltó al cargar un archivo de escena. Una textura que no se puede
+// leer no impide abrir la escena: se reemplaza por un damero magenta y queda
+// anotada acá, para avisar una vez en lugar de fallar o callarse. Cada carga
+// arma su reporte, así que volver a cargar después de agregar el archivo lo
+// deja limpio.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LoadReport {
+    // Rutas que no se pudieron leer, sin repetir y en orden
+    pub missing_textures: Vec<String>,
+    // Materiales que quedaron con el damero, en orden
+    pub substituted_materials: Vec<String>,
+    // Un aviso por cada uso de una textura que falta, con quién la usa y el motivo
+    pub warnings: Vec<String>,
+}
+
+impl LoadReport {
+    pub fn is_clean(&self) -> bool {
+        self.missing_textures.is_empty() && self.warnings.is_empty()
+    }
+
+    // Resumen para la consola o el panel de la ventana; vacío si no faltó nada
+    pub fn lines(&self) -> Vec<String> {
+        if self.is_clean() {
+            return Vec::new();
+        }
+        let mut lines = vec![format!(
+            "Faltan {} texturas; {} materiales usan un damero",
+            self.missing_textures.len(),
+            self.substituted_materials.len()
+        )];
+        lines.extend(self.warnings.iter().map(|warning| format!("  {}", warning)));
+        lines
+    }
+
+    // Los cuadros de `path` o, si no se pueden leer, ninguna animación
+    fn animation(&mut self, desc: &AnimationDesc, user: &str) -> Option<AnimatedTexture> {
+        match AnimatedTexture::load(&desc.path, desc.fps) {
+            Ok(animation) => Some(animation),
+            Err(error) => {
+                if !self.missing_textures.contains(&desc.path) {
+                    self.missing_textures.push(desc.path.clone());
+                }
+                self.warnings.push(format!("{} ({}): {}", desc.path, user, error));
+                None
+            }
+        }
+    }
+
+    // La textura de `path` o, si no se puede leer, el damero en su lugar
+    fn texture(&mut self, textures: &mut TextureCache, path: &str, user: &str) -> Arc<Texture> {
+        let (texture, error) = textures.get_or_load(path);
+        if let Some(error) = error {
+            if !self.missing_textures.iter().any(|missing| missing == path) {
+                self.missing_textures.push(path.to_string());
+            }
+            self.warnings.push(format!("{} ({}): {}", path, user, error));
+        }
+        texture
+    }
+}
+
+// Formato del archivo de escena (RON). Los vectores se escriben como tuplas
+// (x, y, z) y los colores como (r, g, b).
+#[derive(Deserialize)]
+struct SceneFile {
+    #[serde(default)]
+    camera: Option<CameraDesc>,
+    #[serde(default)]
+    materials: HashMap<String, MaterialDesc>,
+    #[serde(default)]
+    lights: Vec<LightDesc>,
+    #[serde(default)]
+    objects: Vec<CubeDesc>,
+    #[serde(default)]
+    spheres: Vec<SphereDesc>,
+    #[serde(default)]
+    planes: Vec<PlaneDesc>,
+    #[serde(default)]
+    up_axis: UpAxis,
+    #[serde(default)]
+    generate: Option<GeneratorDesc>,
+    // Solo para construcciones sobre una grilla de bloques
+    #[serde(default)]
+    corner_ao: bool,
+    #[serde(default = "default_turbidity")]
+    turbidity: f32,
+    #[serde(default)]
+    rail: Vec<RailKeyDesc>,
+}
+
+// Diorama al azar debajo de los objetos del archivo (ver `generate`). Sus
+// cubos van primero y usan los materiales del registro aunque el archivo
+// defina otros con el mismo nombre; la cámara y las luces del archivo, si las
+// hay, reemplazan a las del generador.
+#[derive(Deserialize)]
+struct GeneratorDesc {
+    seed: u64,
+    #[serde(default)]
+    params: GenParams,
+}
+
+// Eje que apunta hacia arriba en el archivo. El renderer, el cielo, el
+// recorrido del sol y la órbita de la cámara trabajan con +Y arriba, así que al
+// cargar se rota toda la escena (cubos, luces y cámara) para llevar ese eje a
+// +Y. Las rotaciones son de 90 o 180 grados: los cubos siguen alineados con
+// los ejes y el render es idéntico al de la misma escena escrita con Y arriba.
+#[derive(Deserialize, Default, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum UpAxis {
+    X,
+    #[default]
+    Y,
+    Z,
+    NegX,
+    NegY,
+    NegZ,
+}
+
+// Arriba en el mundo de la escena ya cargada, al que `UpAxis::to_y_up` lleva
+// el eje de arriba del archivo. El cielo, la altura del sol y las cáusticas
+// lo consultan en lugar de leer la Y
+pub const WORLD_UP: Vec3 = Vec3::new(0.0, 1.0, 0.0);
+
+impl UpAxis {
+    // Lleva un punto o una dirección del archivo al mundo con Y arriba
+    pub fn to_y_up(self, v: Vec3) -> Vec3 {
+        match self {
+            UpAxis::X => Vec3::new(-v.y, v.x, v.z),
+            UpAxis::Y => v,
+            UpAxis::Z => Vec3::new(v.x, v.z, -v.y),
+            UpAxis::NegX => Vec3::new(v.y, -v.x, v.z),
+            UpAxis::NegY => Vec3::new(v.x, -v.y, -v.z),
+            UpAxis::NegZ => Vec3::new(v.x, -v.z, v.y),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+struct CameraDesc {
+    eye: (f32, f32, f32),
+    center: (f32, f32, f32),
+    // Por defecto el eje `up_axis` de la escena
+    #[serde(default)]
+    up: Option<(f32, f32, f32)>,
+}
+
+// Pose del recorrido de cámara. `look_at` mira al objeto con ese nombre en
+// lugar de a `center`, que por defecto es el origen; el campo de visión va en
+// grados
+#[derive(Deserialize)]
+struct RailKeyDesc {
+    time: f32,
+    eye: (f32, f32, f32),
+    #[serde(default)]
+    center: (f32, f32, f32),
+    #[serde(default)]
+    look_at: Option<String>,
+    #[serde(default = "default_fov")]
+    fov: f32,
+    #[serde(default)]
+    ease: bool,
+}
+
+#[derive(Deserialize)]
+struct MaterialDesc {
+    #[serde(default = "default_color")]
+    color: (u8, u8, u8),
+    #[serde(default)]
+    texture: Option<String>,
+    #[serde(default)]
+    pattern: Option<PatternDesc>,
+    #[serde(default)]
+    animation: Option<AnimationDesc>,
+    #[serde(default)]
+    normal_map: Option<String>,
+    #[serde(default)]
+    shininess: f32,
+    #[serde(default)]
+    properties: [f32; 4],
+    #[serde(default = "default_refractive_index")]
+    refractive_index: f32,
+    #[serde(default)]
+    emission: Option<(u8, u8, u8)>,
+    #[serde(default)]
+    emission_map: Option<String>,
+    #[serde(default = "default_emission_strength")]
+    emission_strength: f32,
+    #[serde(default)]
+    planar_reflection: bool,
+    #[serde(default)]
+    halo: f32,
+    #[serde(default)]
+    shadow_catcher: bool,
+    #[serde(default)]
+    receives_shadows: bool,
+    #[serde(default)]
+    unlit: bool,
+    #[serde(default)]
+    filter: FilterDesc,
+    #[serde(default)]
+    edge_bevel: f32,
+    #[serde(default)]
+    uv_mode: UvMode,
+    #[serde(default = "default_texture_scale")]
+    texture_scale: f32,
+    #[serde(default)]
+    use_fresnel: bool,
+    #[serde(default)]
+    absorption: Option<(u8, u8, u8)>,
+    #[serde(default)]
+    absorption_density: f32,
+    // Ancho y alto en texels de la imagen en la que se hornea la textura al
+    // cargar (ver `Material::bake_texture`)
+    #[serde(default)]
+    bake: Option<(u32, u32)>,
+    #[serde(default)]
+    caustics: bool,
+}
+
+// Textura calculada, en lugar de una imagen (ver `ProceduralTexture`)
+#[derive(Deserialize, Clone, Copy)]
+enum PatternDesc {
+    Checker {
+        even: (u8, u8, u8),
+        odd: (u8, u8, u8),
+        #[serde(default = "default_checker_scale")]
+        scale: f32,
+    },
+    Noise {
+        low: (u8, u8, u8),
+        high: (u8, u8, u8),
+        #[serde(default)]
+        seed: u32,
+        #[serde(default = "default_noise_frequency")]
+        frequency: f32,
+    },
+    Gradient {
+        bottom: (u8, u8, u8),
+        top: (u8, u8, u8),
+    },
+}
+
+impl From<PatternDesc> for ProceduralTexture {
+    fn from(desc: PatternDesc) -> Self {
+        match desc {
+            PatternDesc::Checker { even, odd, scale } => ProceduralTexture::Checker { even: color(even), odd: color(odd), scale },
+            PatternDesc::Noise { low, high, seed, frequency } => ProceduralTexture::Noise { low: color(low), high: color(high), seed, frequency },
+            PatternDesc::Gradient { bottom, top } => ProceduralTexture::Gradient { bottom: color(bottom), top: color(top) },
+        }
+    }
+}
+
+// Textura animada: un directorio de cuadros numerados o una tira vertical de
+// cuadros cuadrados
+#[derive(Deserialize)]
+struct AnimationDesc {
+    path: String,
+    #[serde(default = "default_fps")]
+    fps: f32,
+}
+
+#[derive(Deserialize, Default, Clone, Copy)]
+enum FilterDesc {
+    #[default]
+    Nearest,
+    Bilinear,
+    Aniso {
+        max_samples: u32,
+    },
+}
+
+impl From<FilterDesc> for TextureFilter {
+    fn from(desc: FilterDesc) -> Self {
+        match desc {
+            FilterDesc::Nearest => TextureFilter::Nearest,
+            FilterDesc::Bilinear => TextureFilter::Bilinear,
+            FilterDesc::Aniso { max_samples } => TextureFilter::Aniso { max_samples },
+        }
+    }
+}
+
+// También se escribe, para guardar las luces editadas en la ventana
+#[derive(Serialize, Deserialize)]
+struct LightDesc {
+    position: (f32, f32, f32),
+    color: (u8, u8, u8),
+    intensity: f32,
+    #[serde(default, skip_serializing_if = "is_false")]
+    sun: bool,
+    #[serde(default, skip_serializing_if = "is_point")]
+    kind: LightKind,
+    // Por defecto el origen
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    target: Option<(f32, f32, f32)>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    include: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    exclude: Vec<String>,
+    // Coeficientes lineal y cuadrático; por defecto no se atenúa
+    #[serde(default, skip_serializing_if = "is_no_falloff")]
+    falloff: (f32, f32),
+    // Por defecto una luz puntual, sin penumbra
+    #[serde(default, skip_serializing_if = "is_zero")]
+    radius: f32,
+}
+
+fn is_false(value: &bool) -> bool {
+    !value
+}
+
+fn is_point(kind: &LightKind) -> bool {
+    *kind == LightKind::Point
+}
+
+fn is_no_falloff(falloff: &(f32, f32)) -> bool {
+    *falloff == (0.0, 0.0)
+}
+
+fn is_zero(value: &f32) -> bool {
+    *value == 0.0
+}
+
+impl LightDesc {
+    fn into_light(self, up: UpAxis) -> SceneLight {
+        let mut light = SceneLight::new(up.to_y_up(vec3(self.position)), color(self.color), self.intensity);
+        light.is_sun = self.sun;
+        light.kind = self.kind;
+        light.target = self.target.map_or(Vec3::zeros(), |target| up.to_y_up(vec3(target)));
+        light.include = self.include;
+        light.exclude = self.exclude;
+        light.falloff = [self.falloff.0, self.falloff.1];
+        light.radius = self.radius.max(0.0);
+        light
+    }
+}
+
+impl From<&SceneLight> for LightDesc {
+    fn from(light: &SceneLight) -> Self {
+        let tuple = |v: Vec3| (v.x, v.y, v.z);
+        LightDesc {
+            position: tuple(light.position),
+            color: (light.color.red(), light.color.green(), light.color.blue()),
+            intensity: light.intensity,
+            sun: light.is_sun,
+            kind: light.kind,
+            target: (light.target != Vec3::zeros()).then(|| tuple(light.target)),
+            include: light.include.clone(),
+            exclude: light.exclude.clone(),
+            falloff: (light.falloff[0], light.falloff[1]),
+            radius: light.radius,
+        }
+    }
+}
+
+// Bloque `lights` para pegar en un archivo de escena con Y arriba y volver a
+// obtener estas luces
+pub fn lights_entry(lights: &[SceneLight]) -> String {
+    let mut entry = String::from("lights: [\n");
+    for light in lights {
+        let desc = ron::to_string(&LightDesc::from(light)).expect("las luces se pueden escribir en RON");
+        let _ = writeln!(entry, "    {},", desc);
+    }
+    entry.push(']');
+    entry
+}
+
+// Archivo con las luces editadas en la ventana, junto al de la escena:
+// `escena.lights.ron` al lado de `escena.ron`. Al cargar la escena sus luces
+// reemplazan a las del archivo, que no se toca. Está escrito con Y arriba,
+// sea cual sea el `up_axis` de la escena
+#[derive(Deserialize)]
+struct LightsFile {
+    lights: Vec<LightDesc>,
+}
+
+pub fn lights_path(scene_path: &str) -> PathBuf {
+    Path::new(scene_path).with_extension("lights.ron")
+}
+
+// Guarda `lights` en el archivo de luces de la escena y devuelve su ruta
+pub fn save_lights(scene_path: &str, lights: &[SceneLight]) -> std::io::Result<PathBuf> {
+    let path = lights_path(scene_path);
+    std::fs::write(&path, format!("// Luces editadas en la ventana, con Y arriba\n(\n{}\n)\n", lights_entry(lights)))?;
+    Ok(path)
+}
+
+#[derive(Deserialize)]
+struct CubeDesc {
+    min: (f32, f32, f32),
+    max: (f32, f32, f32),
+    material: String,
+    #[serde(default)]
+    group: String,
+    #[serde(default)]
+    name: String,
+    #[serde(default)]
+    decals: Vec<DecalDesc>,
+    // Materiales de algunas caras, en los ejes del archivo; las demás usan `material`
+    #[serde(default)]
+    faces: HashMap<Face, String>,
+}
+
+// Van detrás de los cubos en la lista de objetos
+#[derive(Deserialize)]
+struct SphereDesc {
+    center: (f32, f32, f32),
+    radius: f32,
+    material: String,
+    #[serde(default)]
+    group: String,
+    #[serde(default)]
+    name: String,
+}
+
+// Van detrás de las esferas. La normal se normaliza al cargar
+#[derive(Deserialize)]
+struct PlaneDesc {
+    point: (f32, f32, f32),
+    #[serde(default = "default_plane_normal")]
+    normal: (f32, f32, f32),
+    material: String,
+    // Unidades que abarca una repetición de la textura
+    #[serde(default = "default_tile")]
+    tile: f32,
+    #[serde(default)]
+    group: String,
+    #[serde(default)]
+    name: String,
+}
+
+#[derive(Deserialize)]
+struct DecalDesc {
+    // En los ejes del archivo, antes de aplicar `up_axis`
+    face: Face,
+    #[serde(default = "full_rect")]
+    rect: (f32, f32, f32, f32),
+    texture: String,
+    #[serde(default)]
+    blend: DecalBlend,
+}
+
+fn full_rect() -> (f32, f32, f32, f32) {
+    (0.0, 0.0, 1.0, 1.0)
+}
+
+fn default_color() -> (u8, u8, u8) {
+    (255, 255, 255)
+}
+
+fn default_refractive_index() -> f32 {
+    1.0
+}
+
+fn default_checker_scale() -> f32 {
+    8.0
+}
+
+fn default_noise_frequency() -> f32 {
+    4.0
+}
+
+fn default_fps() -> f32 {
+    8.0
+}
+
+fn default_emission_strength() -> f32 {
+    1.0
+}
+
+fn default_texture_scale() -> f32 {
+    1.0
+}
+
+// Hacia arriba en los ejes del archivo con el `up_axis` por defecto
+fn default_plane_normal() -> (f32, f32, f32) {
+    (0.0, 1.0, 0.0)
+}
+
+fn default_tile() -> f32 {
+    1.0
+}
+
+fn default_turbidity() -> f32 {
+    DEFAULT_TURBIDITY
+}
+
+fn default_fov() -> f32 {
+    FOV.to_degrees()
+}
+
+fn vec3((x, y, z): (f32, f32, f32)) -> Vec3 {
+    Vec3::new(x, y, z)
+}
+
+fn color((r, g, b): (u8, u8, u8)) -> Color {
+    Color::new(r, g, b)
+}
+
+// Sol del diorama, también el de las escenas del builder que no definen luces.
+// Es direccional: a lo largo del día gira la dirección de sus rayos
+pub(crate) fn default_sun() -> SceneLight {
+    let mut sun = SceneLight::sun(Vec3::new(0.75, 0.25, -2.0), Color::new(255, 200, 100), 2.0);
+    sun.kind = LightKind::Directional;
+    sun
+}
+
+pub(crate) fn default_camera() -> Camera {
+    // Inicializa la cámara con una posición más lejana para compensar el aumento de tamaño
+    Camera::new(
+        Vec3::new(0.0, 0.0, 5.5),
+        Vec3::new(0.0, 0.0, 0.0),  // punto al que la cámara está mirando (origen)
+        Vec3::new(0.0, 1.0, 0.0)   // vector hacia arriba del mundo
+    )
+}
+
+// Resumen de la complejidad de la escena, útil antes del primer frame lento
+#[derive(Debug, Clone)]
+pub struct SceneStats {
+    pub object_counts: Vec<(&'static str, usize)>,
+    pub material_count: usize,
+    pub texture_memory: usize,
+    pub total_volume: f32,
+    pub emissive_objects: usize,
+    // Promedio de rayos secundarios (reflexión + refracción) que genera cada impacto
+    pub branching_factor: f32,
+}
+
+impl Scene {
+    pub fn new(objects: Vec<SceneObject>, materials: HashMap<String, Material>, lights: Vec<SceneLight>, camera: Camera) -> Self {
+        Scene {
+            bvh: Arc::new(TraceBvh::new(&objects)),
+            objects,
+            buried: Vec::new(),
+            materials,
+            lights,
+            camera,
+            corner_ao: false,
+            turbidity: DEFAULT_TURBIDITY,
+            rail: CameraRail::default(),
+            occupancy: None,
+            chunks: None,
+            time: 0.0,
+        }
+    }
+
+    // Árbol para `RenderSettings::bvh`: el que mantienen las ediciones o, si
+    // `objects` cambió a mano sin `rebuild_bvh`, uno nuevo
+    pub fn bvh(&self) -> Arc<TraceBvh> {
+        if self.bvh.is_current(&self.objects) {
+            self.bvh.clone()
+        } else {
+            Arc::new(TraceBvh::new(&self.objects))
+        }
+    }
+
+    // Consultas sobre los objetos que se trazan (ver `query`), con el mismo
+    // árbol que el renderer
+    pub fn query(&self) -> SceneQuery<'_> {
+        SceneQuery::new(&self.objects, Some(self.bvh()))
+    }
+
+    // Hace falta después de cambiar `objects` a mano
+    pub fn rebuild_bvh(&mut self) {
+        self.bvh = Arc::new(TraceBvh::new(&self.objects));
+    }
+
+    // Agrega un objeto al trazado y devuelve su índice
+    pub fn add_object(&mut self, object: impl Into<SceneObject>) -> usize {
+        let object = object.into();
+        if let Some(occupancy) = self.occupancy.as_mut() {
+            occupancy.add(&object);
+        }
+        self.objects.push(object);
+        Arc::make_mut(&mut self.bvh).insert(&self.objects);
+        self.objects.len() - 1
+    }
+
+    // Mueve o cambia el tamaño de un objeto a la caja [min, max]; una esfera
+    // queda centrada en la caja con el radio de su lado más corto. Los
+    // enterrados no se vuelven a revisar: un cubo que se mueve puede dejar a la
+    // vista a los que tapaba, así que conviene llamar a `restore_buried` antes
+    // de editar en serio.
+    pub fn move_object(&mut self, index: usize, min: Vec3, max: Vec3) {
+        let object = &mut self.objects[index];
+        if let Some(occupancy) = self.occupancy.as_mut() {
+            occupancy.remove(object);
+        }
+        let (min, max) = (min.inf(&max), min.sup(&max));
+        match object {
+            SceneObject::Cube(cube) => {
+                cube.min = min;
+                cube.max = max;
+            }
+            SceneObject::Sphere(sphere) => {
+                sphere.center = (min + max) * 0.5;
+                sphere.radius = (max - min).min() * 0.5;
+            }
+            // Solo se desplaza: se lleva su punto al centro de la caja
+            SceneObject::Plane(plane) => plane.point = (min + max) * 0.5,
+        }
+        if let Some(occupancy) = self.occupancy.as_mut() {
+            occupancy.add(object);
+        }
+        Arc::make_mut(&mut self.bvh).refit(&self.objects, index);
+    }
+
+    // Saca del trazado los objetos a los que ningún rayo puede llegar y
+    // devuelve cuántos se sacaron
+    pub fn cull_buried(&mut self) -> usize {
+        self.occupancy = Occupancy::new(self.objects.iter().chain(&self.buried));
+        let Some(occupancy) = &self.occupancy else {
+            return 0;
+        };
+        let (buried, exposed): (Vec<SceneObject>, Vec<SceneObject>) =
+            std::mem::take(&mut self.objects).into_iter().partition(|object| occupancy.is_buried(object));
+        let culled = buried.len();
+        self.objects = exposed;
+        self.buried.extend(buried);
+        self.rebuild_bvh();
+        culled
+    }
+
+    // Fusiona en cajas más largas las filas de cubos con el mismo material y
+    // grupo (ver `merge`) y devuelve cuántos objetos menos quedan. Las celdas
+    // ocupadas no cambian, así que la detección de enterrados sigue valiendo.
+    pub fn merge_cubes(&mut self) -> usize {
+        let before = self.objects.len();
+        self.objects = merge::merge_cubes(std::mem::take(&mut self.objects));
+        self.rebuild_bvh();
+        before - self.objects.len()
+    }
+
+    // Vuelve a trazar todos los objetos; hace falta cuando el plano cercano
+    // puede dejar ver el interior de un sólido
+    pub fn restore_buried(&mut self) -> usize {
+        self.occupancy = None;
+        let restored = self.buried.len();
+        match self.chunks.as_mut() {
+            Some(chunks) => chunks.insert(std::mem::take(&mut self.buried)),
+            None => self.objects.append(&mut self.buried),
+        }
+        self.rebuild_bvh();
+        restored
+    }
+
+    // Reparte los objetos en trozos que se trazan según la cámara (ver
+    // `chunks`) y devuelve cuántos quedaron; hasta la primera llamada a
+    // `update_chunks` no se traza nada. Va después de `cull_buried` y
+    // `merge_cubes`, que trabajan sobre `objects`, y las ediciones de objetos
+    // sueltos no llegan a los trozos.
+    pub fn stream_chunks(&mut self, settings: &ChunkSettings) -> usize {
+        let chunks = ChunkStream::new(std::mem::take(&mut self.objects), settings);
+        let count = chunks.len();
+        self.chunks = Some(chunks);
+        self.rebuild_bvh();
+        count
+    }
+
+    // Elige los trozos que se trazan desde la cámara y devuelve si cambiaron,
+    // en cuyo caso los índices de `objects` ya no son los de antes
+    pub fn update_chunks(&mut self, camera: &Camera, aspect_ratio: f32) -> bool {
+        let Some(chunks) = self.chunks.as_mut() else {
+            return false;
+        };
+        if !chunks.update(camera, aspect_ratio) {
+            return false;
+        }
+        self.objects = chunks.objects();
+        self.rebuild_bvh();
+        true
+    }
+
+    // Pasa las texturas animadas al cuadro de los `time` segundos y devuelve
+    // si cambió alguno. Los objetos de los trozos sin trazar se ponen al día
+    // en la llamada siguiente a que vuelvan
+    pub fn animate(&mut self, time: f32) -> bool {
+        self.time = time;
+        let mut changed = false;
+        for material in self.objects.iter_mut().flat_map(SceneObject::materials_mut).chain(self.materials.values_mut()) {
+            changed |= material.animate(time);
+        }
+        changed
+    }
+
+    // Segundo de la animación que se ve; lo que se exporte debe salir de este
+    // mismo cuadro
+    pub fn time(&self) -> f32 {
+        self.time
+    }
+
+    pub fn chunk_counts(&self) -> Option<ChunkCounts> {
+        self.chunks.as_ref().map(ChunkStream::counts)
+    }
+
+    // Quita un objeto de la escena. Los enterrados que lo tocaban se revisan
+    // de nuevo y pasan al trazado si quedaron a la vista.
+    pub fn remove_object(&mut self, index: usize) -> SceneObject {
+        let removed = self.objects.remove(index);
+        Arc::make_mut(&mut self.bvh).remove(&self.objects, index);
+        if let Some(occupancy) = self.occupancy.as_mut() {
+            occupancy.remove(&removed);
+            let touching: HashSet<usize> = SceneQuery::new(&self.buried, None).objects_in_aabb(removed.bounds()).collect();
+            for (index, object) in std::mem::take(&mut self.buried).into_iter().enumerate() {
+                if touching.contains(&index) && !occupancy.is_buried(&object) {
+                    self.objects.push(object);
+                    Arc::make_mut(&mut self.bvh).insert(&self.objects);
+                } else {
+                    self.buried.push(object);
+                }
+            }
+        }
+        removed
+    }
+
+    // Las texturas que faltan se reemplazan sin avisar; `load_with_report`
+    // dice cuáles fueron
+    pub fn load(path: &str) -> Result<Scene, SceneError> {
+        Scene::load_with_report(path).map(|(scene, _)| scene)
+    }
+
+    // Con un archivo de luces junto a la escena (ver `save_lights`) se usan
+    // sus luces; si no se puede leer quedan las de la escena y se avisa
+    pub fn load_with_report(path: &str) -> Result<(Scene, LoadReport), SceneError> {
+        let source = std::fs::read_to_string(path).map_err(SceneError::Io)?;
+        let (mut scene, mut report) = Scene::from_ron_with_report(&source)?;
+        let lights_path = lights_path(path);
+        if lights_path.exists() {
+            let edited = std::fs::read_to_string(&lights_path)
+                .map_err(|e| e.to_string())
+                .and_then(|source| ron::from_str::<LightsFile>(&source).map_err(|e| e.to_string()));
+            match edited {
+                Ok(file) => scene.lights = file.lights.into_iter().map(|desc| desc.into_light(UpAxis::Y)).collect(),
+                Err(error) => report.warnings.push(format!("{}: {}", lights_path.display(), error)),
+            }
+        }
+        Ok((scene, report))
+    }
+
+    pub fn from_ron(source: &str) -> Result<Scene, SceneError> {
+        Scene::from_ron_with_report(source).map(|(scene, _)| scene)
+    }
+
+    pub fn from_ron_with_report(source: &str) -> Result<(Scene, LoadReport), SceneError> {
+        let file: SceneFile = ron::from_str(source).map_err(SceneError::Parse)?;
+        let mut textures = TextureCache::new();
+        let mut report = LoadReport::default();
+
+        // En orden de nombre, así el reporte sale siempre igual
+        let mut descs: Vec<_> = file.materials.into_iter().collect();
+        descs.sort_by(|a, b| a.0.cmp(&b.0));
+        let mut materials = HashMap::new();
+        for (name, desc) in descs {
+            let mut material = match &desc.texture {
+                Some(path) => {
+                    let texture = report.texture(&mut textures, path, &format!("material {}", name));
+                    if report.missing_textures.contains(path) {
+                        report.substituted_materials.push(name.clone());
+                    }
+                    Material::with_texture(texture, desc.shininess, desc.properties, desc.refractive_index)
+                }
+                None => match desc.pattern {
+                    Some(pattern) => Material::with_texture(ProceduralTexture::from(pattern), desc.shininess, desc.properties, desc.refractive_index),
+                    None => Material::new(color(desc.color), desc.shininess, desc.properties, desc.refractive_index),
+                },
+            };
+            if desc.texture.is_some() && desc.pattern.is_some() {
+                report.warnings.push(format!("material {}: tiene texture y pattern, se usa texture", name));
+            }
+            // Con animación el color queda en blanco, como con `texture`
+            if let Some(animation) = desc.animation.as_ref().and_then(|animation| report.animation(animation, &format!("material {}", name))) {
+                material = material.animated(animation);
+            }
+            if let Some(path) = &desc.normal_map {
+                // Sin la imagen la cara queda plana; la textura de reemplazo no sirve de normales
+                let normal_map = report.texture(&mut textures, path, &format!("mapa de normales de {}", name));
+                if !report.missing_textures.contains(path) {
+                    material.normal_map = Some(Texture::clone(&normal_map));
+                }
+            }
+            if let Some(emission) = desc.emission {
+                material.emission = color(emission);
+            }
+            if let Some(path) = &desc.emission_map {
+                // La misma imagen que la animación sigue sus cuadros
+                let animated = material.animation.is_some() && desc.animation.as_ref().is_some_and(|animation| animation.path == *path);
+                material.emission_map = if animated {
+                    material.texture.as_deref().cloned()
+                } else {
+                    Some(Texture::clone(&report.texture(&mut textures, path, &format!("mapa de emisión de {}", name))))
+                };
+            }
+            if let Some((width, height)) = desc.bake {
+                // Al cargar la escena está en el segundo cero
+                if material.bake_texture(width, height, 0.0) {
+                    report.warnings.push(format!("material {}: la animación se hornea en su primer cuadro y queda quieta", name));
+                }
+            }
+            material.emission_strength = desc.emission_strength;
+            material.planar_reflection = desc.planar_reflection;
+            material.halo = desc.halo;
+            material.shadow_catcher = desc.shadow_catcher;
+            material.receives_shadows = desc.receives_shadows;
+            material.unlit = desc.unlit;
+            material.filter = desc.filter.into();
+            material.edge_bevel = desc.edge_bevel;
+            material.uv_mode = desc.uv_mode;
+            material.texture_scale = desc.texture_scale;
+            material.use_fresnel = desc.use_fresnel;
+            if let Some(absorption) = desc.absorption {
+                material.absorption = color(absorption);
+            }
+            material.absorption_density = desc.absorption_density;
+            material.caustics = desc.caustics;
+            material.name = name.clone();
+            materials.insert(name, material);
+        }
+
+        // Con un diorama generado los objetos del archivo también pueden usar
+        // los materiales del registro
+        if file.generate.is_some() {
+            for (name, material) in default_materials() {
+                materials.entry(name).or_insert(material);
+            }
+        }
+
+        let up = file.up_axis;
+        let objects = file
+            .objects
+            .into_iter()
+            .map(|desc| {
+                let material = materials
+                    .get(&desc.material)
+                    .ok_or_else(|| SceneError::UnknownMaterial(desc.material.clone()))?;
+                let (a, b) = (up.to_y_up(vec3(desc.min)), up.to_y_up(vec3(desc.max)));
+                let mut cube = Cube::new(a.inf(&b), a.sup(&b), material).in_group(&desc.group).named(&desc.name);
+                for (face, name) in &desc.faces {
+                    let material = materials.get(name).ok_or_else(|| SceneError::UnknownMaterial(name.clone()))?;
+                    cube = cube.with_face_material(Face::from_normal(&up.to_y_up(face.normal())), material);
+                }
+                for decal in desc.decals {
+                    let texture = report.texture(&mut textures, &decal.texture, "calcomanía");
+                    cube = cube.with_decal(Decal {
+                        face: Face::from_normal(&up.to_y_up(decal.face.normal())),
+                        rect: decal.rect,
+                        texture: Texture::clone(&texture),
+                        blend: decal.blend,
+                    });
+                }
+                Ok(SceneObject::Cube(cube))
+            })
+            .chain(file.spheres.into_iter().map(|desc| {
+                let material = materials
+                    .get(&desc.material)
+                    .ok_or_else(|| SceneError::UnknownMaterial(desc.material.clone()))?;
+                let sphere = Sphere::new(up.to_y_up(vec3(desc.center)), desc.radius, material);
+                Ok(SceneObject::Sphere(sphere.in_group(&desc.group).named(&desc.name)))
+            }))
+            .chain(file.planes.into_iter().map(|desc| {
+                let material = materials
+                    .get(&desc.material)
+                    .ok_or_else(|| SceneError::UnknownMaterial(desc.material.clone()))?;
+                let plane = Plane::new(up.to_y_up(vec3(desc.point)), up.to_y_up(vec3(desc.normal)), material).with_tile(desc.tile);
+                Ok(SceneObject::Plane(plane.in_group(&desc.group).named(&desc.name)))
+            }))
+            .collect::<Result<Vec<_>, SceneError>>()?;
+
+        let lights = file.lights.into_iter().map(|desc| desc.into_light(up)).collect();
+
+        let camera = file.camera.map(|desc| {
+            let camera_up = desc.up.map_or(WORLD_UP, |camera_up| up.to_y_up(vec3(camera_up)));
+            Camera::new(up.to_y_up(vec3(desc.eye)), up.to_y_up(vec3(desc.center)), camera_up)
+        });
+
+        let rail = file
+            .rail
+            .into_iter()
+            .map(|desc| {
+                let target = match desc.look_at {
+                    Some(name) => {
+                        let object = objects.iter().find(|object| object.name() == name);
+                        let fallback = object.ok_or_else(|| SceneError::UnknownObject(name.clone()))?.center();
+                        RailTarget::Object { name, fallback }
+                    }
+                    None => RailTarget::Point(up.to_y_up(vec3(desc.center))),
+                };
+                Ok(RailKey {
+                    time: desc.time,
+                    eye: up.to_y_up(vec3(desc.eye)),
+                    target,
+                    fov: desc.fov.to_radians(),
+                    ease: desc.ease,
+                })
+            })
+            .collect::<Result<Vec<_>, SceneError>>()?;
+
+        let mut scene = match file.generate {
+            None => Scene::new(objects, materials, lights, camera.unwrap_or_else(default_camera)),
+            Some(generator) => {
+                let generated = Scene::generate(generator.seed, &generator.params);
+                Scene::new(
+                    generated.objects.into_iter().chain(objects).collect(),
+                    materials,
+                    if lights.is_empty() { generated.lights } else { lights },
+                    camera.unwrap_or(generated.camera),
+                )
+            }
+        };
+        scene.corner_ao = file.corner_ao;
+        scene.turbidity = file.turbidity;
+        scene.rail = CameraRail::new(rail);
+        Ok((scene, report))
+    }
+
+    // Diorama al azar, el mismo para la misma semilla y parámetros
+    pub fn generate(seed: u64, params: &GenParams) -> Self {
+        generate::generate(seed, params)
+    }
+
+    // Escena integrada: el portal de Minecraft sobre la base de césped
+    pub fn diorama() -> Self {
+        let materials = default_materials();
+        let material = |name: &str| materials[name].clone();
+        let obsidian_material = material("obsidian");
+        let purple_material = material("purple");
+        let grass = material("grass");
+        let stone = material("stone");
+        let lava = material("lava");
+
+        let delta_y = 0.703125;
+        let delta_z = 0.46875;
+
+        let objects = vec![
+            // Base con césped
+            Cube { min: Vec3::new(-1.40625, -0.234375, -1.40625), max: Vec3::new(1.40625, -0.09375, 1.40625), material: grass.clone(), group: "base".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },
+
+            // Lava en las esquinas de la base
+            Cube { min: Vec3::new(-1.5, -0.234375, -1.5), max: Vec3::new(-1.3125, 0.0, -1.3125), material: lava.clone(), group: "base".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },
+            Cube { min: Vec3::new(1.3125, -0.234375, -1.5), max: Vec3::new(1.5, 0.0, -1.3125), material: lava.clone(), group: "base".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },
+            Cube { min: Vec3::new(-1.5, -0.234375, 1.3125), max: Vec3::new(-1.3125, 0.0, 1.5), material: lava.clone(), group: "base".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },
+            Cube { min: Vec3::new(1.3125, -0.234375, 1.3125), max: Vec3::new(1.5, 0.0, 1.5), material: lava.clone(), group: "base".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },
+
+            // Portal (marco)
+            Cube { min: Vec3::new(-0.46875, 0.09375 + delta_y, -0.703125 + delta_z), max: Vec3::new(-0.234375, 1.171875 + delta_y, -0.234375 + delta_z), material: obsidian_material.clone(), group: "portal".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },
+            Cube { min: Vec3::new(0.234375, 0.09375 + delta_y, -0.703125 + delta_z), max: Vec3::new(0.46875, 1.171875 + delta_y, -0.234375 + delta_z), material: obsidian_material.clone(), group: "portal".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },
+            Cube { min: Vec3::new(-0.46875, 1.171875 + delta_y, -0.703125 + delta_z), max: Vec3::new(0.46875, 1.40625 + delta_y, -0.234375 + delta_z), material: obsidian_material.clone(), group: "portal".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },
+            Cube { min: Vec3::new(-0.46875, -0.09375 + delta_y, -0.703125 + delta_z), max: Vec3::new(0.46875, 0.09375 + delta_y, -0.234375 + delta_z), material: obsidian_material.clone(), group: "portal".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },
+
+            // Columnas del portal
+            Cube { 
+                min: Vec3::new(-0.234375, 0.09375 + delta_y, -0.703125 + delta_z), 
+                max: Vec3::new(0.0, 1.171875 + delta_y, -0.234375 + delta_z), 
+                material: purple_material.clone(),
+                group: "portal".to_string(),
+                name: String::new(),
+                decals: Vec::new(),
+                face_materials: Vec::new(),
+                uv_repeat: Vec3::repeat(1.0),
+            },
+            Cube { 
+                min: Vec3::new(0.0, 0.09375 + delta_y, -0.703125 + delta_z), 
+                max: Vec3::new(0.234375, 1.171875 + delta_y, -0.234375 + delta_z), 
+                material: purple_material.clone(),
+                group: "portal".to_string(),
+                name: String::new(),
+                decals: Vec::new(),
+                face_materials: Vec::new(),
+                uv_repeat: Vec3::repeat(1.0),
+            },
+
+            // Gradas
+            Cube { min: Vec3::new(-1.125, -0.140625, -1.125), max: Vec3::new(1.125, -0.046875, 1.453125), material: stone.clone(), group: "gradas".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },
+            Cube { min: Vec3::new(-1.078125, -0.046875, -1.078125), max: Vec3::new(1.078125, 0.046875, 1.359375), material: stone.clone(), group: "gradas".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) }, 
+            Cube { min: Vec3::new(-1.03125, 0.046875, -1.03125), max: Vec3::new(1.03125, 0.140625, 1.265625), material: stone.clone(), group: "gradas".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },  
+            Cube { min: Vec3::new(-0.984375, 0.140625, -0.984375), max: Vec3::new(0.984375, 0.234375, 1.171875), material: stone.clone(), group: "gradas".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },  
+            Cube { min: Vec3::new(-0.9375, 0.234375, -0.9375), max: Vec3::new(0.9375, 0.328125, 1.078125), material: stone.clone(), group: "gradas".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) }, 
+            Cube { min: Vec3::new(-0.890625, 0.328125, -0.890625), max: Vec3::new(0.890625, 0.421875, 0.984375), material: stone.clone(), group: "gradas".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },  
+            Cube { min: Vec3::new(-0.84375, 0.421875, -0.84375), max: Vec3::new(0.84375, 0.515625, 0.890625), material: stone.clone(), group: "gradas".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) }, 
+            Cube { min: Vec3::new(-0.796875, 0.515625, -0.796875), max: Vec3::new(0.796875, 0.609375, 0.75), material: stone.clone(), group: "gradas".to_string(), name: String::new(), decals: Vec::new(), face_materials: Vec::new(), uv_repeat: Vec3::repeat(1.0) },  
+        ];
+
+        // Las mismas luces que `scenes/portal.ron`: el sol, una cálida sobre cada
+        // lava y el resplandor del portal a cada lado del marco
+        let local = |position: Vec3, color: Color, intensity: f32, quadratic: f32| {
+            let mut light = SceneLight::attenuated(position, color, intensity, 0.0, quadratic);
+            light.radius = 0.05;
+            light
+        };
+        let mut lights = vec![default_sun()];
+        for (x, z) in [(-1.40625, -1.40625), (1.40625, -1.40625), (-1.40625, 1.40625), (1.40625, 1.40625)] {
+            lights.push(local(Vec3::new(x, 0.1, z), Color::new(255, 128, 0), 1.2, 4.0));
+        }
+        for z in [0.45, -0.45] {
+            lights.push(local(Vec3::new(0.0, 1.34, z), Color::new(190, 110, 255), 0.8, 6.0));
+        }
+
+        Scene::new(objects.into_iter().map(SceneObject::Cube).collect(), materials, lights, default_camera())
+    }
+
+    // Caja que envuelve todos los objetos acotados, None si no hay ninguno
+    pub fn bounds(&self) -> Option<(Vec3, Vec3)> {
+        let bounds = self.objects.iter().filter(|object| object.is_bounded()).map(SceneObject::bounds).reduce(|bounds, other| bounds.union(&other))?;
+        Some((bounds.min, bounds.max))
+    }
+
+    // Sesgo de origen proporcional a la diagonal de la escena, para que una
+    // escena importada a escala 1 bloque = 1 metro no tenga acné de sombras ni
+    // fugas de luz. Para el diorama da aproximadamente DEFAULT_BIAS.
+    pub fn default_bias(&self) -> f32 {
+        match self.bounds() {
+            Some((min, max)) if max != min => (max - min).magnitude() * BIAS_PER_UNIT,
+            _ => DEFAULT_BIAS,
+        }
+    }
+
+    // Distancias entre las que el zoom deja la cámara del centro de la órbita,
+    // proporcionales a la diagonal de la escena: así el ojo no atraviesa el
+    // centro ni se aleja hasta perderla de vista
+    pub fn zoom_range(&self) -> (f32, f32) {
+        match self.bounds() {
+            Some((min, max)) if max != min => {
+                let diagonal = (max - min).magnitude();
+                (diagonal * ZOOM_PER_UNIT.0, diagonal * ZOOM_PER_UNIT.1)
+            }
+            _ => DEFAULT_ZOOM_RANGE,
+        }
+    }
+
+    pub fn stats(&self) -> SceneStats {
+        let object_count = self.objects.len();
+        let secondary_rays: usize = self
+            .objects
+            .iter()
+            .map(|object| object.material().is_reflective() as usize + object.material().is_transparent() as usize)
+            .sum();
+        let mut object_counts: Vec<(&'static str, usize)> = vec![("cube", 0)];
+        for object in &self.objects {
+            match object_counts.iter_mut().find(|(kind, _)| *kind == object.kind()) {
+                Some((_, count)) => *count += 1,
+                None => object_counts.push((object.kind(), 1)),
+            }
+        }
+
+        SceneStats {
+            object_counts,
+            material_count: self.materials.len(),
+            // Los materiales con la misma imagen comparten sus píxeles
+            texture_memory: self
+                .materials
+                .values()
+                .flat_map(|material| {
+                    let frames = material.animation.iter().flat_map(|animation| animation.frames());
+                    material.texture.as_deref().into_iter().chain(&material.normal_map).chain(&material.emission_map).chain(frames)
+                })
+                .map(|texture| (texture.path(), texture.memory_bytes()))
+                .collect::<HashMap<_, _>>()
+                .values()
+                .sum(),
+            total_volume: self.objects.iter().map(|object| object.volume()).sum(),
+            emissive_objects: self.objects.iter().filter(|object| object.material().is_emissive()).count(),
+            branching_factor: if object_count > 0 {
+                secondary_rays as f32 / object_count as f32
+            } else {
+                0.0
+            },
+        }
+    }
+}
+
+impl SceneStats {
+    pub fn object_count(&self) -> usize {
+        self.object_counts.iter().map(|(_, count)| count).sum()
+    }
+
+    // Estimación (cota alta) asumiendo que todos los rayos primarios impactan:
+    // `samples_per_pixel` rayos primarios por píxel, y cada impacto lanza un
+    // rayo de sombra por luz y en promedio `branching_factor` rayos
+    // secundarios, hasta `max_depth` rebotes
+    pub fn estimated_rays_per_frame(&self, width: usize, height: usize, light_count: usize, max_depth: u32, samples_per_pixel: u32) -> u64 {
+        let rays_per_hit = 1.0 + light_count as f64;
+        let branching = self.branching_factor as f64;
+        let hits_per_sample: f64 = (0..=max_depth).map(|depth| branching.powi(depth as i32)).sum();
+        let samples = (width * height) as f64 * samples_per_pixel.max(1) as f64;
+        (samples * hits_per_sample * rays_per_hit) as u64
+    }
+
+    pub fn report(&self, width: usize, height: usize, light_count: usize, max_depth: u32, samples_per_pixel: u32) -> String {
+        let counts: Vec<String> = self
+            .object_counts
+            .iter()
+            .map(|(kind, count)| format!("{} {}", count, kind))
+            .collect();
+
+        let mut report = String::new();
+        let _ = writeln!(report, "Objetos: {} ({})", self.object_count(), counts.join(", "));
+        let _ = writeln!(report, "Materiales: {}", self.material_count);
+        let _ = writeln!(report, "Memoria de texturas: {:.1} MB", self.texture_memory as f64 / (1024.0 * 1024.0));
+        let _ = writeln!(report, "Volumen AABB total: {:.3}", self.total_volume);
+        let _ = writeln!(report, "Objetos emisivos: {}", self.emissive_objects);
+        let _ = write!(
+            report,
+            "Rayos por frame (est.): {} a {}x{}, {} muestras y {} rebotes",
+            self.estimated_rays_per_frame(width, height, light_count, max_depth, samples_per_pixel),
+            width,
+            height,
+            samples_per_pixel.max(1),
+            max_depth
+        );
+        report
+    }
+}
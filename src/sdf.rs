@@ -0,0 +1,76 @@
+use nalgebra_glm::Vec3;
+
+use crate::object::SceneObject;
+use crate::ray_intersect::RayIntersect;
+
+// Campo de distancias de la escena alrededor de un tramo, para marchar un rayo
+// de sombra. Un objeto a más de `radius` de todo el tramo no puede cortar el
+// rayo ni bajar la penumbra por debajo de 1 cuando radius = largo / k, así
+// que solo se guardan los que están más cerca. La prueba usa la distancia de
+// cada caja a la caja que envuelve el tramo, que nunca es mayor que la real.
+// Los objetos sin caja quedan afuera: un plano de suelo con el sol bajo se
+// oscurecería entero en penumbra por su propia distancia al rayo.
+pub struct SceneDistanceField<'a> {
+    objects: Vec<&'a SceneObject>,
+}
+
+impl<'a> SceneDistanceField<'a> {
+    pub fn around_segment(objects: impl Iterator<Item = &'a SceneObject>, from: &Vec3, to: &Vec3, radius: f32) -> Self {
+        let (low, high) = (from.inf(to), from.sup(to));
+        let objects = objects
+            .filter(|object| {
+                let bounds = object.bounds();
+                let gap = (bounds.min - high).sup(&(low - bounds.max)).sup(&Vec3::zeros());
+                object.is_bounded() && gap.magnitude() < radius
+            })
+            .collect();
+        SceneDistanceField { objects }
+    }
+
+    // Distancia al objeto más cercano; infinita si no quedó ninguno
+    pub fn distance(&self, point: &Vec3) -> f32 {
+        self.objects.iter().map(|object| object.distance(point)).fold(f32::INFINITY, f32::min)
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.objects.is_empty()
+    }
+}
+
+// Pasos máximos de la marcha y avance mínimo por paso como fracción del tramo
+const MAX_STEPS: usize = 96;
+const MIN_STEP: f32 = 1.0 / 256.0;
+
+// Intensidad de sombra con penumbra de campo de distancias, de 0 (iluminado)
+// a 1. Donde el rayo choca se usa la misma caída que la sombra dura según la
+// distancia al oclusor; en la penumbra esa caída se toma en el punto de menor
+// razón. `hit` es la distancia por debajo de la que se considera un choque.
+pub fn soft_shadow(field: &SceneDistanceField, origin: &Vec3, to: &Vec3, k: f32, hit: f32) -> f32 {
+    let length = (to - origin).magnitude();
+    if field.is_empty() || length <= 0.0 {
+        return 0.0;
+    }
+    let direction = (to - origin) / length;
+    let falloff = |t: f32| 1.0 - (t / length).powi(2).min(1.0);
+
+    let mut penumbra: f32 = 1.0;
+    let mut closest = 0.0;
+    let mut t = hit;
+    for _ in 0..MAX_STEPS {
+        if t >= length {
+            break;
+        }
+        let distance = field.distance(&(origin + direction * t));
+        if distance < hit {
+            return falloff(t);
+        }
+        let ratio = k * distance / t;
+        if ratio < penumbra {
+            penumbra = ratio;
+            closest = t;
+        }
+        t += distance.max(MIN_STEP * length);
+    }
+
+    (1.0 - penumbra.clamp(0.0, 1.0)) * falloff(closest)
+}
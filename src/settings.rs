@@ -0,0 +1,566 @@
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+use crate::adaptive::AdaptiveSampling;
+use crate::bvh::TraceBvh;
+use crate::color::{Color, LinearColor};
+use crate::cornerao::CornerOcclusion;
+use crate::irradiance::IrradianceCache;
+use crate::renderer::MAX_RAY_DEPTH;
+use crate::sky::PhysicalSky;
+use crate::skybox::Skybox;
+use crate::texture::{Texture, TextureFilter};
+
+// Sesgo por defecto, pensado para el diorama de unas 3 unidades de lado
+pub const DEFAULT_BIAS: f32 = 1e-4;
+// Luz ambiente por defecto, como fracción del color del cielo; 0 deja las
+// caras a la sombra como siempre
+pub const DEFAULT_AMBIENT: f32 = 0.0;
+
+// Cómo se ajusta la imagen de fondo a la proporción de la pantalla
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BackgroundFit {
+    // Se ve la imagen completa; las franjas sobrantes muestran el cielo
+    Letterbox,
+    // La imagen cubre toda la pantalla recortando lo que sobra
+    Cover,
+}
+
+// Cómo se lleva la luz sumada en un píxel, que puede pasar del blanco, al
+// rango que se guarda. Se aplica una vez por píxel, con todos los rebotes
+// sumados y después de la exposición
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ToneMapping {
+    // Cada canal se satura en el blanco
+    #[default]
+    Clamp,
+    // x / (1 + x) por canal: nunca llega al blanco y oscurece las medias luces
+    Reinhard,
+    // Aproximación de la curva filmica de ACES (Narkowicz): contraste en las
+    // medias luces y los brillos se van aplanando sin recortarse de golpe
+    Aces,
+}
+
+impl ToneMapping {
+    pub const ALL: [ToneMapping; 3] = [ToneMapping::Clamp, ToneMapping::Reinhard, ToneMapping::Aces];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            ToneMapping::Clamp => "clamp",
+            ToneMapping::Reinhard => "reinhard",
+            ToneMapping::Aces => "aces",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<ToneMapping> {
+        ToneMapping::ALL.iter().copied().find(|tone_mapping| tone_mapping.name() == name)
+    }
+
+    pub fn apply(self, color: LinearColor) -> LinearColor {
+        let curve = |x: f32| match self {
+            ToneMapping::Clamp => x,
+            ToneMapping::Reinhard => x / (1.0 + x),
+            ToneMapping::Aces => (x * (2.51 * x + 0.03) / (x * (2.43 * x + 0.59) + 0.14)).min(1.0),
+        };
+        let [r, g, b] = color.channels().map(|channel| curve(channel.max(0.0)));
+        LinearColor::new(r, g, b)
+    }
+}
+
+// Cómo se calculan las sombras de cada luz
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum ShadowQuality {
+    // Un rayo de sombra: borde duro
+    Hard,
+    // Penumbra aproximada con un campo de distancias: el rayo de sombra avanza
+    // a saltos del tamaño de la distancia al objeto más cercano y la penumbra
+    // sale de la menor razón entre esa distancia y lo recorrido, multiplicada
+    // por `k`. Un `k` mayor da bordes más duros. Es una sola marcha por luz,
+    // pero tiene los defectos conocidos de la técnica: la penumbra solo crece
+    // hacia afuera de la sombra dura (la umbra no se achica), las caras
+    // iluminadas casi de canto se oscurecen un poco cerca del terminador por
+    // su propio cubo, y un rayo que roza una cara por mucho trecho puede
+    // agotar los pasos y quedar iluminado.
+    SdfSoft { k: f32 },
+    // Penumbra física de luces con `radius`: `samples` rayos de sombra hacia
+    // puntos del disco de la luz, promediados. Cuesta un rayo por muestra; las
+    // luces sin radio y las direccionales siguen con un solo rayo duro
+    Area { samples: u32 },
+}
+
+impl ShadowQuality {
+    pub fn name(self) -> String {
+        match self {
+            ShadowQuality::Hard => "hard".to_string(),
+            ShadowQuality::SdfSoft { k } => format!("sdf:{}", k),
+            ShadowQuality::Area { samples } => format!("area:{}", samples),
+        }
+    }
+}
+
+// Mapa de sombras del sol (ver `shadowmap`): celdas por lado de cada cara, radio en celdas
+// del filtro PCF, sesgo de la comparación en celdas y cuántos grados puede
+// moverse el sol antes de regenerar el mapa
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct ShadowMapSettings {
+    pub resolution: usize,
+    pub pcf_radius: usize,
+    pub bias_texels: f32,
+    pub refresh_angle: f32,
+}
+
+impl Default for ShadowMapSettings {
+    fn default() -> Self {
+        ShadowMapSettings {
+            resolution: 512,
+            pcf_radius: 1,
+            bias_texels: 2.0,
+            refresh_angle: 2.0,
+        }
+    }
+}
+
+// Rayos de sol en pantalla (ver `godrays`): muestras por píxel hacia el sol,
+// fracción del camino al sol que cubren, cuánto pesa cada muestra respecto de
+// la anterior, radio de la máscara alrededor del sol en alturas de imagen y
+// fuerza con el sol sobre el horizonte
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct GodRays {
+    pub samples: usize,
+    pub length: f32,
+    pub decay: f32,
+    pub radius: f32,
+    pub strength: f32,
+}
+
+impl Default for GodRays {
+    fn default() -> Self {
+        GodRays {
+            samples: 48,
+            length: 0.9,
+            decay: 0.97,
+            radius: 0.35,
+            strength: 1.0,
+        }
+    }
+}
+
+// Resplandor: la luz de los píxeles con una luminancia por encima de
+// `threshold` (de 0 a 1, en luz lineal) se difumina hasta `radius`, en
+// fracciones de la altura de la imagen, y se suma multiplicada por `strength`
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Bloom {
+    pub threshold: f32,
+    pub radius: f32,
+    pub strength: f32,
+}
+
+impl Default for Bloom {
+    fn default() -> Self {
+        Bloom { threshold: 0.8, radius: 0.04, strength: 0.6 }
+    }
+}
+
+// Cáusticas del agua: `time` son los segundos de las olas, que mueven el
+// dibujo, y `strength` cuánto se aparta el sol de su intensidad en las
+// líneas brillantes y entre ellas; con 0 no se ven
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Caustics {
+    pub time: f32,
+    pub strength: f32,
+}
+
+impl Default for Caustics {
+    fn default() -> Self {
+        Caustics { time: 0.0, strength: 1.0 }
+    }
+}
+
+// Oclusión ambiental: rayos por punto en el hemisferio de la normal, con
+// densidad proporcional al coseno, y distancia hasta la que un impacto ocluye
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct AmbientOcclusion {
+    pub samples: u32,
+    pub radius: f32,
+}
+
+impl Default for AmbientOcclusion {
+    fn default() -> Self {
+        AmbientOcclusion { samples: 16, radius: 0.1 }
+    }
+}
+
+// Rectángulo del framebuffer en píxeles al que se limita el trazado
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Crop {
+    pub x: usize,
+    pub y: usize,
+    pub width: usize,
+    pub height: usize,
+}
+
+impl Crop {
+    // La parte que cae dentro de un framebuffer de ese tamaño; None si queda vacía
+    pub fn clamped(self, width: usize, height: usize) -> Option<Crop> {
+        let (x, y) = (self.x.min(width), self.y.min(height));
+        let crop = Crop {
+            x,
+            y,
+            width: self.width.min(width - x),
+            height: self.height.min(height - y),
+        };
+        (crop.width > 0 && crop.height > 0).then_some(crop)
+    }
+
+    // Agrandado `margin` píxeles por lado; puede salirse del framebuffer
+    pub fn expanded(self, margin: usize) -> Crop {
+        let (x, y) = (self.x.saturating_sub(margin), self.y.saturating_sub(margin));
+        Crop {
+            x,
+            y,
+            width: self.width + (self.x - x) + margin,
+            height: self.height + (self.y - y) + margin,
+        }
+    }
+
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        (self.x..self.x + self.width).contains(&x) && (self.y..self.y + self.height).contains(&y)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct RenderSettings {
+    // Placa de fondo en espacio de pantalla para los rayos primarios que no impactan
+    pub background: Option<Texture>,
+    pub background_fit: BackgroundFit,
+    // Muestreo adaptativo de rayos primarios; None traza un rayo por píxel
+    pub adaptive: Option<AdaptiveSampling>,
+    // Distancia mínima de impacto de los rayos primarios; la geometría más
+    // cercana se atraviesa. Las sombras y los rayos secundarios no se recortan.
+    pub near: f32,
+    // Color plano de la sección cortada por el plano cercano; None deja ver
+    // a través de los objetos cortados
+    pub cut_color: Option<Color>,
+    // Radio en píxeles del halo de los materiales con `halo`; 0 lo desactiva
+    pub halo_radius: usize,
+    // Desplazamiento del origen de los rayos de sombra, reflexión y refracción,
+    // y distancia mínima de sus impactos. Depende de la escala de la escena:
+    // ver `Scene::default_bias`
+    pub bias: f32,
+    // Color con el que los materiales `shadow_catcher` muestran la sombra que reciben
+    pub shadow_color: Color,
+    // Salida con fondo transparente: los colores de píxeles con alfa parcial se
+    // guardan sin mezclar con el fondo (alfa directo)
+    pub transparent: bool,
+    // Filtro de textura para todos los materiales; None usa el de cada material
+    pub texture_filter: Option<TextureFilter>,
+    // Calcula las sombras y los reflejos de los rayos primarios a media
+    // resolución y los reescala guiado por profundidad y normales
+    pub half_res: bool,
+    // Fracción de la resolución a la que se trazan los rayos; la imagen se
+    // escala después al tamaño del framebuffer
+    pub render_scale: f32,
+    // Rayos primarios por píxel, repartidos dentro del píxel siempre con el
+    // mismo patrón y promediados en luz lineal; 1 traza uno por píxel. La
+    // reproyección traza siempre uno
+    pub samples_per_pixel: u32,
+    // Lado en píxeles de las teselas en que se reparte el render entre los
+    // hilos; cada tesela se traza fila por fila. No cambia la imagen
+    pub tile_size: usize,
+    // Rebotes de reflexión y refracción tras el rayo primario
+    pub max_depth: u32,
+    // Peso acumulado (el producto de reflectividades y transparencias del
+    // camino) por debajo del cual un rayo ya no rebota; 0 rebota siempre
+    // hasta `max_depth`
+    pub min_throughput: f32,
+    pub shadow_quality: ShadowQuality,
+    // Sombras duras del sol desde un mapa de sombras en lugar de rayos; None
+    // traza siempre
+    pub shadow_map: Option<ShadowMapSettings>,
+    // Rayos de sol como efecto de posproceso; None los desactiva
+    pub god_rays: Option<GodRays>,
+    // Resplandor de lo más brillante de la imagen (ver `bloom`); None lo
+    // desactiva
+    pub bloom: Option<Bloom>,
+    // Cáusticas del sol debajo de los materiales de agua, que mantiene
+    // `caustics::refresh`; None las desactiva
+    pub caustics: Option<Caustics>,
+    // Oscurece la luz de los rayos primarios según cuánto del hemisferio tapa
+    // la geometría cercana; None la desactiva
+    pub ambient_occlusion: Option<AmbientOcclusion>,
+    // Grilla de bloques para oscurecer las esquinas (ver `cornerao`), que
+    // mantiene `cornerao::refresh`; None lo desactiva
+    pub corner_ao: Option<Arc<CornerOcclusion>>,
+    // Árbol de cajas con el que se trazan los rayos, que mantiene
+    // `bvh::refresh`; None prueba cada rayo contra la lista entera
+    pub bvh: Option<Arc<TraceBvh>>,
+    // Cielo analítico con el sol del frame, que mantiene `sky::refresh`; None
+    // usa el color de `sky_color` en todas las direcciones
+    pub sky: Option<PhysicalSky>,
+    // Imagen de cielo alrededor de la escena, que reemplaza al degradado y al
+    // analítico en los rayos que no impactan; None no la usa
+    pub skybox: Option<Skybox>,
+    // Fracción del color del cielo que llega como luz ambiente a los
+    // materiales iluminados, pesada por su coeficiente difuso; 0 deja solo
+    // la luz directa
+    pub ambient: f32,
+    // Rebote de luz difusa entre superficies (ver `irradiance`), que mantiene
+    // `irradiance::refresh`; None lo desactiva
+    pub irradiance: Option<Arc<IrradianceCache>>,
+    // Solo se trazan los píxeles de este rectángulo y el resto del
+    // framebuffer queda como estaba. Dentro se traza siempre a resolución
+    // completa: se ignoran `render_scale`, `half_res` y la reproyección
+    pub crop: Option<Crop>,
+    // Ilumina en luz lineal: los colores y las texturas se decodifican de
+    // sRGB antes de sumar las luces y el resultado se vuelve a codificar al
+    // final. Con false la cuenta se hace sobre los valores guardados
+    pub gamma_correct: bool,
+    // Factor por el que se multiplica la luz de cada píxel antes de
+    // `tone_mapping`; 1 la deja como está
+    pub exposure: f32,
+    pub tone_mapping: ToneMapping,
+}
+
+impl Default for RenderSettings {
+    fn default() -> Self {
+        RenderSettings {
+            background: None,
+            background_fit: BackgroundFit::Cover,
+            adaptive: None,
+            near: 0.0,
+            cut_color: None,
+            halo_radius: 4,
+            bias: DEFAULT_BIAS,
+            shadow_color: Color::black(),
+            transparent: false,
+            texture_filter: None,
+            half_res: false,
+            render_scale: 1.0,
+            samples_per_pixel: 1,
+            tile_size: 32,
+            max_depth: MAX_RAY_DEPTH,
+            min_throughput: 1.0 / 255.0,
+            shadow_quality: ShadowQuality::Hard,
+            shadow_map: None,
+            god_rays: None,
+            bloom: None,
+            caustics: None,
+            ambient_occlusion: None,
+            corner_ao: None,
+            bvh: None,
+            sky: None,
+            skybox: None,
+            ambient: DEFAULT_AMBIENT,
+            irradiance: None,
+            crop: None,
+            gamma_correct: true,
+            exposure: 1.0,
+            tone_mapping: ToneMapping::Clamp,
+        }
+    }
+}
+
+// Conjuntos de opciones de calidad que se eligen de una vez
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Preset {
+    Fast,
+    Balanced,
+    Quality,
+    Cinematic,
+}
+
+// Valores que fija cada preset; el resto de las opciones no cambia
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct PresetKnobs {
+    render_scale: f32,
+    max_depth: u32,
+    adaptive: bool,
+    half_res: bool,
+    texture_filter: Option<TextureFilter>,
+    halo_radius: usize,
+    samples_per_pixel: u32,
+    shadow_quality: ShadowQuality,
+    ambient_occlusion: Option<AmbientOcclusion>,
+    bloom: Option<Bloom>,
+}
+
+// Balanced son las opciones por defecto
+const PRESETS: [(Preset, PresetKnobs); 4] = [
+    (
+        Preset::Fast,
+        PresetKnobs {
+            render_scale: 0.5,
+            max_depth: 2,
+            adaptive: true,
+            half_res: true,
+            texture_filter: Some(TextureFilter::Nearest),
+            halo_radius: 2,
+            samples_per_pixel: 1,
+            shadow_quality: ShadowQuality::Hard,
+            ambient_occlusion: None,
+            bloom: None,
+        },
+    ),
+    (
+        Preset::Balanced,
+        PresetKnobs {
+            render_scale: 1.0,
+            max_depth: MAX_RAY_DEPTH,
+            adaptive: false,
+            half_res: false,
+            texture_filter: None,
+            halo_radius: 4,
+            samples_per_pixel: 1,
+            shadow_quality: ShadowQuality::Hard,
+            ambient_occlusion: None,
+            bloom: None,
+        },
+    ),
+    (
+        Preset::Quality,
+        PresetKnobs {
+            render_scale: 1.0,
+            max_depth: 4,
+            adaptive: false,
+            half_res: false,
+            texture_filter: Some(TextureFilter::Aniso { max_samples: 8 }),
+            halo_radius: 4,
+            samples_per_pixel: 4,
+            shadow_quality: ShadowQuality::SdfSoft { k: 8.0 },
+            ambient_occlusion: Some(AmbientOcclusion { samples: 16, radius: 0.1 }),
+            bloom: None,
+        },
+    ),
+    (
+        Preset::Cinematic,
+        PresetKnobs {
+            render_scale: 1.0,
+            max_depth: 6,
+            adaptive: false,
+            half_res: false,
+            texture_filter: Some(TextureFilter::Aniso { max_samples: 16 }),
+            halo_radius: 6,
+            samples_per_pixel: 16,
+            shadow_quality: ShadowQuality::SdfSoft { k: 8.0 },
+            ambient_occlusion: Some(AmbientOcclusion { samples: 32, radius: 0.1 }),
+            bloom: Some(Bloom { threshold: 0.8, radius: 0.04, strength: 0.6 }),
+        },
+    ),
+];
+
+impl Preset {
+    pub const ALL: [Preset; 4] = [Preset::Fast, Preset::Balanced, Preset::Quality, Preset::Cinematic];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Preset::Fast => "fast",
+            Preset::Balanced => "balanced",
+            Preset::Quality => "quality",
+            Preset::Cinematic => "cinematic",
+        }
+    }
+
+    // Nombre para el panel
+    pub fn label(self) -> &'static str {
+        match self {
+            Preset::Fast => "Fast",
+            Preset::Balanced => "Balanced",
+            Preset::Quality => "Quality",
+            Preset::Cinematic => "Cinematic",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Preset> {
+        Preset::ALL.iter().copied().find(|preset| preset.name() == name)
+    }
+
+    pub fn next(self) -> Preset {
+        let index = Preset::ALL.iter().position(|&preset| preset == self).unwrap_or(0);
+        Preset::ALL[(index + 1) % Preset::ALL.len()]
+    }
+
+    fn knobs(self) -> PresetKnobs {
+        PRESETS.iter().find(|(preset, _)| *preset == self).map(|(_, knobs)| *knobs).expect("cada preset tiene sus valores")
+    }
+}
+
+
+impl RenderSettings {
+    pub fn apply_preset(&mut self, preset: Preset) {
+        let knobs = preset.knobs();
+        self.render_scale = knobs.render_scale;
+        self.max_depth = knobs.max_depth;
+        self.adaptive = knobs.adaptive.then(AdaptiveSampling::default);
+        self.half_res = knobs.half_res;
+        self.texture_filter = knobs.texture_filter;
+        self.halo_radius = knobs.halo_radius;
+        self.samples_per_pixel = knobs.samples_per_pixel;
+        self.shadow_quality = knobs.shadow_quality;
+        self.ambient_occlusion = knobs.ambient_occlusion;
+        self.bloom = knobs.bloom;
+    }
+
+    // Preset que coincide con las opciones actuales; None si alguna se cambió
+    // a mano
+    pub fn preset(&self) -> Option<Preset> {
+        let knobs = PresetKnobs {
+            render_scale: self.render_scale,
+            max_depth: self.max_depth,
+            adaptive: self.adaptive.is_some(),
+            half_res: self.half_res,
+            texture_filter: self.texture_filter,
+            halo_radius: self.halo_radius,
+            samples_per_pixel: self.samples_per_pixel,
+            shadow_quality: self.shadow_quality,
+            ambient_occlusion: self.ambient_occlusion,
+            bloom: self.bloom,
+        };
+        PRESETS.iter().find(|(_, preset)| *preset == knobs).map(|(preset, _)| *preset)
+    }
+
+    // Color guardado en el espacio en el que se suman las luces, según `gamma_correct`
+    pub fn linear(&self, color: Color) -> LinearColor {
+        if self.gamma_correct { LinearColor::from_srgb(color) } else { LinearColor::from_raw(color) }
+    }
+
+    // Vuelta de `linear`, saturando en el blanco
+    pub fn encode(&self, color: LinearColor) -> Color {
+        if self.gamma_correct { color.to_srgb() } else { color.to_raw() }
+    }
+
+    // Color que se muestra para la luz que llega a un píxel: con la
+    // exposición y el mapeo de tonos, y recién después codificado
+    pub fn display(&self, color: LinearColor) -> Color {
+        self.encode(self.tone_mapping.apply(color * self.exposure))
+    }
+
+    // `u`, `v` son coordenadas normalizadas de pantalla con v = 0 arriba
+    pub fn sample_background(&self, u: f32, v: f32, screen_aspect: f32) -> Option<Color> {
+        let texture = self.background.as_ref()?;
+        let image_aspect = texture.width() as f32 / texture.height() as f32;
+
+        // Fracción de la pantalla que ocupa la imagen en cada eje
+        let (scale_u, scale_v) = match self.background_fit {
+            BackgroundFit::Cover if image_aspect > screen_aspect => (screen_aspect / image_aspect, 1.0),
+            BackgroundFit::Cover => (1.0, image_aspect / screen_aspect),
+            BackgroundFit::Letterbox if image_aspect > screen_aspect => (1.0, image_aspect / screen_aspect),
+            BackgroundFit::Letterbox => (screen_aspect / image_aspect, 1.0),
+        };
+
+        let u = 0.5 + (u - 0.5) * scale_u;
+        let v = 0.5 + (v - 0.5) * scale_v;
+        if !(0.0..1.0).contains(&u) || !(0.0..1.0).contains(&v) {
+            return None;
+        }
+
+        Some(texture.get_color(u, 1.0 - v))
+    }
+}
@@ -0,0 +1,283 @@
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::Arc;
+
+use crate::bvh::{Aabb, Bvh};
+use crate::light::{LightKind, SceneLight, DIRECTIONAL_DISTANCE};
+use crate::object::SceneObject;
+use crate::query::SceneQuery;
+use crate::ray_intersect::RayIntersect;
+use crate::settings::ShadowMapSettings;
+
+// Mapa de sombras del sol: lo que ve la luz no depende de la cámara, así que
+// se traza una vez la distancia desde el sol hasta el primer objeto en cada
+// dirección de una cuadrícula, y la prueba de sombra de un punto pasa a ser
+// comparar su distancia a la luz con la del mapa en unas pocas celdas vecinas
+// (PCF) en lugar de trazar un rayo. El sol puntual recorre un arco y puede
+// quedar dentro de la escena (el suelo del diorama lo rodea), así que su mapa
+// es un cubo de seis caras alrededor de la luz, una por eje. El sol
+// direccional tiene rayos paralelos: su mapa es una sola cara ortográfica que
+// mira a lo largo de sus rayos y cubre la caja de los objetos, y la
+// profundidad se mide desde el plano de esa cara más cercano al sol.
+//
+// Defectos conocidos: con pocas celdas por unidad las caras casi paralelas a
+// la luz se sombrean a sí mismas en franjas (acné) salvo con sesgo suficiente,
+// y demasiado sesgo despega la sombra de la base de los objetos (peter
+// panning); los bordes quedan escalonados al tamaño de una celda suavizados
+// por el PCF, que no cruza de una cara del cubo a otra; y la intensidad de la
+// sombra usa el oclusor más cercano a la luz, que puede dar una sombra un poco
+// más clara que el rayo cuando hay varios oclusores en fila. Los planos no
+// entran al mapa porque no tienen caja: un suelo recibe sombra pero no tapa la
+// luz mientras el sol esté sobre él.
+pub struct ShadowMap {
+    projection: Projection,
+    // Centro de los objetos, para medir cuánto se movió la luz vista desde ahí
+    target: Vec3,
+    resolution: usize,
+    // Distancia desde la luz al primer oclusor por celda de cada cara, una
+    // tras otra en el orden de `FACES` en el cubo; infinita si no hay
+    depths: Vec<f32>,
+    pcf_radius: usize,
+    bias_texels: f32,
+    // Con qué se construyó, para saber cuándo regenerarlo
+    scene_key: u64,
+}
+
+// Cómo se proyecta la escena en el mapa
+#[derive(Debug, Clone, Copy)]
+enum Projection {
+    // Seis caras alrededor de la posición de una luz puntual
+    Cube { origin: Vec3 },
+    // Una cara que mira a lo largo de `-direction`, con `u` y `v` como ejes de
+    // la cuadrícula. La celda (0, 0) empieza en `corner` (en u, v), cada una
+    // mide `texel` de lado y la profundidad se mide bajando desde el plano
+    // que está a `top` a lo largo de `direction`
+    Orthographic { direction: Vec3, u: Vec3, v: Vec3, corner: [f32; 2], texel: f32, top: f32 },
+}
+
+// Cara del cubo: eje de la normal, signo, y los dos ejes que la recorren
+const FACES: [(usize, f32, usize, usize); 6] =
+    [(0, 1.0, 2, 1), (0, -1.0, 2, 1), (1, 1.0, 0, 2), (1, -1.0, 0, 2), (2, 1.0, 0, 1), (2, -1.0, 0, 1)];
+
+impl std::fmt::Debug for ShadowMap {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        match self.projection {
+            Projection::Cube { origin } => write!(f, "ShadowMap(6x{}x{} desde {:?})", self.resolution, self.resolution, origin),
+            Projection::Orthographic { direction, .. } => {
+                write!(f, "ShadowMap({}x{} hacia {:?})", self.resolution, self.resolution, -direction)
+            }
+        }
+    }
+}
+
+// Huella de lo que proyecta sombras: cajas, grupos y receptores de sombras
+fn scene_key(objects: &[SceneObject]) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    objects.len().hash(&mut hasher);
+    for object in objects {
+        let bounds = object.bounds();
+        for value in bounds.min.iter().chain(bounds.max.iter()) {
+            value.to_bits().hash(&mut hasher);
+        }
+        object.kind().hash(&mut hasher);
+        object.group().hash(&mut hasher);
+        object.material().shadow_catcher.hash(&mut hasher);
+    }
+    hasher.finish()
+}
+
+impl ShadowMap {
+    // None si ningún objeto proyecta sombras de esta luz
+    pub fn build(light: &SceneLight, objects: &[SceneObject], settings: &ShadowMapSettings) -> Option<ShadowMap> {
+        let casters: Vec<&SceneObject> = SceneQuery::new(objects, None)
+            .objects_lit_by(light)
+            .map(|index| &objects[index])
+            .filter(|object| object.is_bounded() && !object.material().shadow_catcher)
+            .collect();
+        let Aabb { min, max } = casters.iter().map(|object| object.bounds()).reduce(|bounds, other| bounds.union(&other))?;
+        let resolution = settings.resolution.max(1);
+        // Son millones de rayos por mapa, así que se recorren con un BVH propio
+        let mut bvh = Bvh::default();
+        bvh.rebuild(&casters.iter().map(|object| Aabb::of(object)).collect::<Vec<_>>());
+
+        let target = (min + max) * 0.5;
+        let projection = if light.kind == LightKind::Directional {
+            orthographic(light.toward(&target).0, &Aabb { min, max }, resolution)
+        } else {
+            Projection::Cube { origin: light.position }
+        };
+        let faces = if let Projection::Cube { .. } = projection { 6 } else { 1 };
+
+        let mut map = ShadowMap {
+            projection,
+            target,
+            resolution,
+            depths: Vec::new(),
+            pcf_radius: settings.pcf_radius,
+            bias_texels: settings.bias_texels,
+            scene_key: scene_key(objects),
+        };
+        map.depths = (0..faces * resolution * resolution)
+            .into_par_iter()
+            .map(|index| {
+                let cell = index % (resolution * resolution);
+                let (origin, direction) = map.ray(index / (resolution * resolution), cell % resolution, cell / resolution);
+                let mut nearest = f32::INFINITY;
+                bvh.traverse(&origin, &direction, f32::INFINITY, |index| {
+                    let hit = casters[index].ray_intersect(&origin, &direction);
+                    if hit.is_intersecting && hit.distance > 0.0 {
+                        nearest = nearest.min(hit.distance);
+                    }
+                    nearest
+                });
+                nearest
+            })
+            .collect();
+        Some(map)
+    }
+
+    // Rayo que mide la profundidad de la celda (x, y) de una cara
+    fn ray(&self, face: usize, x: usize, y: usize) -> (Vec3, Vec3) {
+        match self.projection {
+            Projection::Cube { origin } => (origin, self.direction(face, x, y)),
+            Projection::Orthographic { direction, u, v, corner, texel, top } => {
+                let along = |start: f32, cell: usize| start + (cell as f32 + 0.5) * texel;
+                (u * along(corner[0], x) + v * along(corner[1], y) + direction * top, -direction)
+            }
+        }
+    }
+
+    fn direction(&self, face: usize, x: usize, y: usize) -> Vec3 {
+        let (axis, sign, u_axis, v_axis) = FACES[face];
+        let to_face = |cell: usize| (cell as f32 + 0.5) / self.resolution as f32 * 2.0 - 1.0;
+        let mut direction = Vec3::zeros();
+        direction[axis] = sign;
+        direction[u_axis] = to_face(x);
+        direction[v_axis] = to_face(y);
+        direction.normalize()
+    }
+
+    // Intensidad de sombra de un punto, de 0 (iluminado) a 1, con la misma
+    // caída que el rayo de sombra duro según la distancia al oclusor. None en
+    // la posición misma de la luz.
+    pub fn shadow(&self, point: &Vec3) -> Option<f32> {
+        let size = self.resolution as f32;
+        match self.projection {
+            Projection::Cube { origin } => {
+                let relative = point - origin;
+                let axis = relative.iamax();
+                let major = relative[axis].abs();
+                if major <= 0.0 {
+                    return None;
+                }
+                let face = axis * 2 + (relative[axis] < 0.0) as usize;
+                let (_, _, u_axis, v_axis) = FACES[face];
+                let to_cell = |along: usize| ((relative[along] / major + 1.0) * 0.5 * size).min(size - 1.0);
+                // El sesgo crece con la distancia porque las celdas se agrandan
+                let distance = relative.magnitude();
+                let texel = 2.0 * distance / size;
+                Some(self.filter(face, (to_cell(u_axis), to_cell(v_axis)), distance, texel, distance))
+            }
+            Projection::Orthographic { direction, u, v, corner, texel, top } => {
+                // Fuera de la cara o por encima de su plano no hay nada que tape
+                // al sol, porque la cara cubre a todos los que dan sombra
+                let to_cell = |axis: &Vec3, start: f32| (point.dot(axis) - start) / texel;
+                let (x, y) = (to_cell(&u, corner[0]), to_cell(&v, corner[1]));
+                let distance = top - point.dot(&direction);
+                if !(0.0..size).contains(&x) || !(0.0..size).contains(&y) || distance <= 0.0 {
+                    return Some(0.0);
+                }
+                Some(self.filter(0, (x, y), distance, texel, DIRECTIONAL_DISTANCE))
+            }
+        }
+    }
+
+    // Promedio de las celdas del radio PCF alrededor de `cell` en la cara
+    // `face`, para un punto a `distance` de la luz (o del plano de la cara) con
+    // celdas de `texel` de lado en ese punto. `light_distance` es hasta dónde
+    // llega el rayo de sombra, para la caída con la distancia al oclusor
+    fn filter(&self, face: usize, (u, v): (f32, f32), distance: f32, texel: f32, light_distance: f32) -> f32 {
+        let size = self.resolution as f32;
+        let depths = &self.depths[face * self.resolution * self.resolution..][..self.resolution * self.resolution];
+        let limit = distance - self.bias_texels * texel;
+        let radius = self.pcf_radius as i64;
+        let (cx, cy) = (u as i64, v as i64);
+        let mut total = 0.0;
+        let mut taps = 0;
+        for y in cy - radius..=cy + radius {
+            for x in cx - radius..=cx + radius {
+                let (x, y) = (x.clamp(0, size as i64 - 1) as usize, y.clamp(0, size as i64 - 1) as usize);
+                let occluder = depths[y * self.resolution + x];
+                if occluder < limit {
+                    let ratio = (distance - occluder) / light_distance;
+                    total += 1.0 - ratio.powi(2).min(1.0);
+                }
+                taps += 1;
+            }
+        }
+        total / taps as f32
+    }
+
+    // Si el mapa sigue valiendo para la luz y la escena: la luz no se movió
+    // más de `refresh_angle` grados vista desde la escena y ningún objeto cambió
+    fn is_fresh(&self, light: &SceneLight, objects: &[SceneObject], settings: &ShadowMapSettings) -> bool {
+        if self.resolution != settings.resolution.max(1)
+            || self.pcf_radius != settings.pcf_radius
+            || self.bias_texels != settings.bias_texels
+        {
+            return false;
+        }
+        let now = light.toward(&self.target).0;
+        let built = match (self.projection, light.kind) {
+            (Projection::Orthographic { direction, .. }, LightKind::Directional) => direction,
+            (Projection::Cube { origin }, kind) if kind != LightKind::Directional => (origin - self.target).normalize(),
+            _ => return false,
+        };
+        built.dot(&now).clamp(-1.0, 1.0).acos().to_degrees() <= settings.refresh_angle
+            && scene_key(objects) == self.scene_key
+    }
+}
+
+// Cara ortográfica que mira a lo largo de los rayos de un sol que llega desde
+// `direction` y cubre toda la caja `bounds`, con un poco de margen arriba
+fn orthographic(direction: Vec3, bounds: &Aabb, resolution: usize) -> Projection {
+    let reference = if direction.y.abs() < 0.9 { Vec3::new(0.0, 1.0, 0.0) } else { Vec3::new(1.0, 0.0, 0.0) };
+    let u = reference.cross(&direction).normalize();
+    let v = direction.cross(&u);
+    let corners = (0..8).map(|corner| {
+        let pick = |axis: usize| if corner >> axis & 1 == 0 { bounds.min[axis] } else { bounds.max[axis] };
+        Vec3::new(pick(0), pick(1), pick(2))
+    });
+    let (mut low, mut high) = ([f32::INFINITY; 3], [f32::NEG_INFINITY; 3]);
+    for corner in corners {
+        for (axis, along) in [u, v, direction].iter().enumerate() {
+            low[axis] = low[axis].min(corner.dot(along));
+            high[axis] = high[axis].max(corner.dot(along));
+        }
+    }
+    // Celdas cuadradas sobre el lado más largo; una caja plana no deja celdas
+    // de tamaño cero
+    let texel = ((high[0] - low[0]).max(high[1] - low[1]) / resolution as f32).max(1e-4);
+    Projection::Orthographic { direction, u, v, corner: [low[0], low[1]], texel, top: high[2] + texel }
+}
+
+// Deja al día los mapas de sombras de los soles: los construye, los regenera
+// si la luz se movió o la escena cambió, o los quita con `settings` en None.
+// Devuelve cuántos se construyeron.
+pub fn refresh(lights: &mut [SceneLight], objects: &[SceneObject], settings: Option<&ShadowMapSettings>) -> usize {
+    let mut built = 0;
+    for light in lights.iter_mut() {
+        let Some(settings) = settings.filter(|_| light.is_sun) else {
+            light.shadow_map = None;
+            continue;
+        };
+        if light.shadow_map.as_ref().is_some_and(|map| map.is_fresh(light, objects, settings)) {
+            continue;
+        }
+        light.shadow_map = ShadowMap::build(light, objects, settings).map(Arc::new);
+        built += light.shadow_map.is_some() as usize;
+    }
+    built
+}
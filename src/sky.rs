@@ -0,0 +1,228 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::color::Color;
+use crate::light::SceneLight;
+use crate::scene::WORLD_UP;
+
+// Cielo analítico de Preetham, Shirley y Smits ("A Practical Analytic Model
+// for Daylight"): con la dirección del sol y la turbiedad del aire da el
+// color del cielo en cualquier dirección, así el horizonte se calienta al
+// amanecer y al atardecer y el cenit queda azul sin ajustar colores a mano.
+// Del mismo modelo salen el color con el que llega el sol, atenuado por el
+// aire que atraviesa, y la luz de cielo que reciben las texturas, así el sol,
+// el cielo y el ambiente cambian juntos a lo largo del día. Con el sol bajo
+// el horizonte el modelo no vale y se funde con el cielo nocturno.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PhysicalSky {
+    // Dirección hacia el sol, unitaria
+    sun: Vec3,
+    pub turbidity: f32,
+    // Ángulo cenital del sol con el que se evalúa el modelo
+    sun_zenith: f32,
+    // Coeficientes de Perez y valor en el cenit para la luminancia Y y las
+    // cromaticidades x, y
+    perez: [[f32; 5]; 3],
+    zenith: [f32; 3],
+    // Cuánto es de día: 0 con el sol bien bajo el horizonte, 1 desde que sale
+    day: f32,
+    sun_color: Color,
+    skylight: [f32; 3],
+}
+
+// Turbiedad de un día despejado; 2 es muy limpio y 10 muy brumoso
+pub const DEFAULT_TURBIDITY: f32 = 3.0;
+
+// El mismo cielo nocturno del degradado
+const NIGHT_SKY_COLOR: Color = Color::new(10, 10, 50);
+// Escala de la luminancia del modelo (kcd/m²) antes de comprimirla a [0, 1)
+const EXPOSURE: f32 = 0.05;
+// Cuánto se oscurece la bruma debajo del horizonte mirando hacia abajo
+const GROUND_DARKENING: f32 = 0.45;
+// Aclarado de la luz de cielo de las texturas
+const SKYLIGHT_GAIN: f32 = 1.15;
+// Luz de cielo mínima de las texturas de noche, como la de la luna
+const NIGHT_SKYLIGHT: f32 = 0.15;
+// Longitudes de onda en micrómetros de los canales rojo, verde y azul
+const WAVELENGTHS: [f32; 3] = [0.680, 0.550, 0.440];
+
+impl PhysicalSky {
+    pub fn new(sun: Vec3, turbidity: f32) -> Self {
+        let mut sky = PhysicalSky::model(sun, turbidity);
+        sky.sun_color = sky.compute_sun_color();
+        sky.skylight = sky.compute_skylight();
+        sky
+    }
+
+    // Solo el modelo, sin el color del sol ni la luz de cielo
+    fn model(sun: Vec3, turbidity: f32) -> Self {
+        let sun = sun.try_normalize(1e-6).unwrap_or(WORLD_UP);
+        let t = turbidity.clamp(1.7, 12.0);
+        // Con el sol bajo el horizonte se usa el cielo de cuando está saliendo
+        let sun_zenith = sun.dot(&WORLD_UP).clamp(-1.0, 1.0).acos().min(PI * 0.5 - 0.01);
+        let perez = [
+            [0.1787 * t - 1.4630, -0.3554 * t + 0.4275, -0.0227 * t + 5.3251, 0.1206 * t - 2.5771, -0.0670 * t + 0.3703],
+            [-0.0193 * t - 0.2592, -0.0665 * t + 0.0008, -0.0004 * t + 0.2125, -0.0641 * t - 0.8989, -0.0033 * t + 0.0452],
+            [-0.0167 * t - 0.2608, -0.0950 * t + 0.0092, -0.0079 * t + 0.2102, -0.0441 * t - 1.6537, -0.0109 * t + 0.0529],
+        ];
+
+        let chi = (4.0 / 9.0 - t / 120.0) * (PI - 2.0 * sun_zenith);
+        let luminance = (4.0453 * t - 4.9710) * chi.tan() - 0.2155 * t + 2.4192;
+        let (z, z2, z3) = (sun_zenith, sun_zenith * sun_zenith, sun_zenith * sun_zenith * sun_zenith);
+        let x = t * t * (0.00166 * z3 - 0.00375 * z2 + 0.00209 * z)
+            + t * (-0.02903 * z3 + 0.06377 * z2 - 0.03202 * z + 0.00394)
+            + (0.11693 * z3 - 0.21196 * z2 + 0.06052 * z + 0.25886);
+        let y = t * t * (0.00275 * z3 - 0.00610 * z2 + 0.00317 * z)
+            + t * (-0.04214 * z3 + 0.08970 * z2 - 0.04153 * z + 0.00516)
+            + (0.15346 * z3 - 0.26756 * z2 + 0.06670 * z + 0.26688);
+
+        PhysicalSky {
+            sun,
+            turbidity: t,
+            sun_zenith,
+            perez,
+            zenith: [luminance.max(0.0), x, y],
+            day: smoothstep(-0.15, 0.05, sun.dot(&WORLD_UP)),
+            sun_color: Color::black(),
+            skylight: [1.0; 3],
+        }
+    }
+
+    // Con el primer sol de la lista, visto desde el origen; sin sol queda en el cenit
+    pub fn from_lights(lights: &[SceneLight], turbidity: f32) -> Self {
+        let sun = lights.iter().find(|light| light.is_sun).map_or(WORLD_UP, |light| light.toward(&Vec3::zeros()).0);
+        PhysicalSky::new(sun, turbidity)
+    }
+
+    // Color del cielo en la dirección `direction`; debajo del horizonte se
+    // repite el del horizonte, un poco más oscuro hacia abajo
+    pub fn radiance(&self, direction: &Vec3) -> Color {
+        to_color(self.linear(direction)) * self.day + NIGHT_SKY_COLOR * (1.0 - self.day)
+    }
+
+    // Radiancia de día en RGB lineal ya expuesta, en [0, 1)
+    fn linear(&self, direction: &Vec3) -> [f32; 3] {
+        let direction = direction.try_normalize(1e-6).unwrap_or(WORLD_UP);
+        let height = direction.dot(&WORLD_UP);
+        let cos_theta = height.max(0.02);
+        let model_sun = (self.sun - WORLD_UP * self.sun.dot(&WORLD_UP))
+            .try_normalize(1e-6)
+            .unwrap_or(Vec3::new(1.0, 0.0, 0.0))
+            * self.sun_zenith.sin()
+            + WORLD_UP * self.sun_zenith.cos();
+        let gamma = direction.dot(&model_sun).clamp(-1.0, 1.0).acos();
+
+        let [luminance, x, y] = [0, 1, 2].map(|channel| {
+            let coefficients = &self.perez[channel];
+            self.zenith[channel] * perez(coefficients, cos_theta, gamma) / perez(coefficients, 1.0, self.sun_zenith)
+        });
+        // Se comprime la luminancia y no cada canal, así el azul no se lava
+        let exposed = (luminance * EXPOSURE).max(1e-6);
+        let scale = (1.0 - (-exposed).exp()) / exposed * EXPOSURE;
+        let ground = 1.0 - GROUND_DARKENING * smoothstep(0.0, 0.4, -height);
+        xyz_to_rgb(x, y, luminance).map(|value| (value * scale * ground).min(1.0))
+    }
+
+    // Color con el que llega el sol: la luz directa atenuada por la
+    // dispersión de Rayleigh y de los aerosoles según cuánto aire atraviesa,
+    // relativa a la de un sol en el cenit con la misma turbiedad
+    pub fn sun_color(&self) -> Color {
+        self.sun_color
+    }
+
+    fn compute_sun_color(&self) -> Color {
+        let zenith = transmittance(0.0, self.turbidity);
+        let now = transmittance(self.sun_zenith, self.turbidity);
+        let [r, g, b] = [0, 1, 2].map(|channel| ((now[channel] / zenith[channel]).powf(1.0 / 2.2) * self.day * 255.0).clamp(0.0, 255.0) as u8);
+        Color::new(r, g, b)
+    }
+
+    // Luz que recibe una superficie que mira hacia arriba, por canal y
+    // relativa a la de un sol en el cenit: el cielo de todo el hemisferio más
+    // el sol. Se aclara un poco, así con el sol alto las texturas quedan como
+    // sin cielo
+    pub fn skylight(&self) -> [f32; 3] {
+        self.skylight
+    }
+
+    fn compute_skylight(&self) -> [f32; 3] {
+        let noon = PhysicalSky::model(WORLD_UP, self.turbidity).irradiance();
+        let now = self.irradiance();
+        [0, 1, 2].map(|channel| ((now[channel] / noon[channel].max(1e-6)).powf(1.0 / 2.2) * SKYLIGHT_GAIN * self.day).clamp(NIGHT_SKYLIGHT, 1.0))
+    }
+
+    // Suma del cielo en una grilla del hemisferio pesada por el coseno, más el sol
+    fn irradiance(&self) -> [f32; 3] {
+        const RINGS: usize = 4;
+        const SEGMENTS: usize = 8;
+        let mut total = [0.0; 3];
+        for ring in 0..RINGS {
+            let elevation = (ring as f32 + 0.5) / RINGS as f32 * PI * 0.5;
+            for segment in 0..SEGMENTS {
+                let azimuth = segment as f32 / SEGMENTS as f32 * 2.0 * PI;
+                let direction = Vec3::new(elevation.cos() * azimuth.cos(), elevation.sin(), elevation.cos() * azimuth.sin());
+                let weight = elevation.sin() * elevation.cos() / (RINGS * SEGMENTS) as f32;
+                for (total, value) in total.iter_mut().zip(self.linear(&direction)) {
+                    *total += value * weight;
+                }
+            }
+        }
+        let sun = transmittance(self.sun_zenith, self.turbidity);
+        [0, 1, 2].map(|channel| total[channel] + sun[channel] * self.sun_zenith.cos())
+    }
+}
+
+// Deja al día el cielo con el sol del frame, o lo quita con `turbidity` en
+// None. Con cielo, los soles toman el color con el que los deja pasar el
+// aire; hay que llamarlo después de avanzar la hora de las luces.
+pub fn refresh(current: &mut Option<PhysicalSky>, lights: &mut [SceneLight], turbidity: Option<f32>) {
+    let Some(turbidity) = turbidity else {
+        *current = None;
+        return;
+    };
+    let sky = PhysicalSky::from_lights(lights, turbidity);
+    for light in lights.iter_mut().filter(|light| light.is_sun) {
+        light.color = sky.sun_color();
+    }
+    *current = Some(sky);
+}
+
+// Función de distribución de Perez
+fn perez([a, b, c, d, e]: &[f32; 5], cos_theta: f32, gamma: f32) -> f32 {
+    (1.0 + a * (b / cos_theta).exp()) * (1.0 + c * (d * gamma).exp() + e * gamma.cos() * gamma.cos())
+}
+
+// Fracción de la luz del sol que llega al suelo por canal
+fn transmittance(sun_zenith: f32, turbidity: f32) -> [f32; 3] {
+    let degrees = sun_zenith.to_degrees().min(93.0);
+    let air_mass = 1.0 / (sun_zenith.cos().max(0.0) + 0.15 * (93.885 - degrees).powf(-1.253));
+    let beta = 0.04608 * turbidity - 0.04586;
+    WAVELENGTHS.map(|lambda| {
+        let rayleigh = 0.008735 * lambda.powf(-4.08);
+        let aerosol = beta * lambda.powf(-1.3);
+        (-(rayleigh + aerosol) * air_mass).exp()
+    })
+}
+
+// Cromaticidad xyY a RGB lineal (primarios de sRGB)
+fn xyz_to_rgb(x: f32, y: f32, luminance: f32) -> [f32; 3] {
+    let y = y.max(1e-4);
+    let big_x = x / y * luminance;
+    let big_z = (1.0 - x - y) / y * luminance;
+    [
+        (3.2406 * big_x - 1.5372 * luminance - 0.4986 * big_z).max(0.0),
+        (-0.9689 * big_x + 1.8758 * luminance + 0.0415 * big_z).max(0.0),
+        (0.0557 * big_x - 0.2040 * luminance + 1.0570 * big_z).max(0.0),
+    ]
+}
+
+// RGB lineal en [0, 1] a un color con corrección gamma
+fn to_color(linear: [f32; 3]) -> Color {
+    let [r, g, b] = linear.map(|value| (value.clamp(0.0, 1.0).powf(1.0 / 2.2) * 255.0).round() as u8);
+    Color::new(r, g, b)
+}
+
+fn smoothstep(edge0: f32, edge1: f32, x: f32) -> f32 {
+    let t = ((x - edge0) / (edge1 - edge0)).clamp(0.0, 1.0);
+    t * t * (3.0 - 2.0 * t)
+}
@@ -0,0 +1,100 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::color::Color;
+use crate::decal::Face;
+use crate::texture::{Footprint, Texture, TextureFilter};
+
+// Cielo de una imagen alrededor de la escena, como los panoramas de Minecraft:
+// seis caras de un cubo o una sola imagen equirrectangular. Da el color que se
+// ve en cualquier dirección. Las coordenadas de las funciones de acá son las
+// de la imagen, con v = 0 en la fila de arriba.
+#[derive(Debug, Clone)]
+pub enum Skybox {
+    // Caras en el orden de `FACES`, cada una vista desde adentro del cubo: las
+    // laterales con el cielo arriba, la de arriba como si se levantara la
+    // vista desde la que mira a -Z y la de abajo como si se bajara
+    Cubemap(Box<[Texture; 6]>),
+    // Longitud a lo ancho, con -Z en el centro y +X a la derecha, y latitud a
+    // lo alto, del cenit en la fila de arriba al nadir en la de abajo
+    Equirectangular(Texture),
+}
+
+// Orden de las caras del cubo y el nombre con el que se buscan sus archivos
+pub const FACES: [(Face, &str); 6] = [
+    (Face::X, "px"),
+    (Face::NegX, "nx"),
+    (Face::Y, "py"),
+    (Face::NegY, "ny"),
+    (Face::Z, "pz"),
+    (Face::NegZ, "nz"),
+];
+
+impl Skybox {
+    // Una ruta con `{}` es un cubo: se carga una cara por cada nombre de
+    // `FACES` puesto en su lugar (`cielo_{}.png` lee `cielo_px.png`, ...).
+    // Cualquier otra es una imagen equirrectangular
+    pub fn load(path: &str) -> Result<Skybox, image::ImageError> {
+        if !path.contains("{}") {
+            return Ok(Skybox::Equirectangular(Texture::new(path)?));
+        }
+        let [px, nx, py, ny, pz, nz] = FACES.map(|(_, name)| Texture::new(&path.replace("{}", name)));
+        Ok(Skybox::Cubemap(Box::new([px?, nx?, py?, ny?, pz?, nz?])))
+    }
+
+    // Color de la imagen en la dirección de un rayo, que no hace falta que
+    // sea unitaria
+    pub fn color(&self, direction: &Vec3) -> Color {
+        match self {
+            Skybox::Cubemap(faces) => {
+                let (face, u, v) = cube_uv(direction);
+                let index = FACES.iter().position(|(other, _)| *other == face).unwrap_or(0);
+                // Sin pasar al borde opuesto de la cara, que en el cubo es otra
+                let texture = &faces[index];
+                sample(texture, u.clamp(half_texel(texture.width()), 1.0 - half_texel(texture.width())), v)
+            }
+            Skybox::Equirectangular(texture) => {
+                // A lo ancho la imagen da la vuelta y se repite; a lo alto no
+                let (u, v) = equirectangular_uv(direction);
+                sample(texture, u, v)
+            }
+        }
+    }
+}
+
+// Cara del cubo hacia la que apunta la dirección, la de su eje más largo, y
+// el punto de esa cara en el que la cruza
+pub fn cube_uv(direction: &Vec3) -> (Face, f32, f32) {
+    let face = Face::from_normal(direction);
+    let (x, y, z) = (direction.x, direction.y, direction.z);
+    let major = direction[direction.iamax()].abs().max(1e-12);
+    // Hacia la derecha y hacia abajo de la imagen, vista desde adentro
+    let (right, down) = match face {
+        Face::X => (z, -y),
+        Face::NegX => (-z, -y),
+        Face::Y => (x, -z),
+        Face::NegY => (x, z),
+        Face::Z => (-x, -y),
+        Face::NegZ => (x, -y),
+    };
+    (face, (right / major + 1.0) * 0.5, (down / major + 1.0) * 0.5)
+}
+
+// Longitud y latitud de la dirección llevadas a [0, 1]
+pub fn equirectangular_uv(direction: &Vec3) -> (f32, f32) {
+    let direction = direction.try_normalize(1e-12).unwrap_or(Vec3::new(0.0, 1.0, 0.0));
+    let u = 0.5 + direction.x.atan2(-direction.z) / (2.0 * PI);
+    let v = direction.y.clamp(-1.0, 1.0).acos() / PI;
+    (u, v)
+}
+
+// Filtrado bilineal con v a medio texel de arriba y de abajo como mucho,
+// para no mezclar la primera fila con la última
+fn sample(texture: &Texture, u: f32, v: f32) -> Color {
+    let half = half_texel(texture.height());
+    texture.sample(u, 1.0 - v.clamp(half, 1.0 - half), TextureFilter::Bilinear, Footprint::ROUND)
+}
+
+fn half_texel(size: u32) -> f32 {
+    0.5 / size as f32
+}
@@ -0,0 +1,87 @@
+use nalgebra_glm::Vec3;
+use std::f32::consts::PI;
+
+use crate::material::Material;
+use crate::ray_intersect::{Intersect, RayIntersect};
+use crate::texture::Footprint;
+
+#[derive(Clone)]
+pub struct Sphere {
+    pub center: Vec3,
+    pub radius: f32,
+    pub material: Material,
+    pub group: String, // Grupo o capa usado por el enlace de luces, como en `Cube`
+    pub name: String,
+}
+
+impl Sphere {
+    pub fn new(center: Vec3, radius: f32, material: &Material) -> Self {
+        Sphere {
+            center,
+            radius,
+            material: material.clone(),
+            group: String::new(),
+            name: String::new(),
+        }
+    }
+
+    pub fn in_group(mut self, group: &str) -> Self {
+        self.group = group.to_string();
+        self
+    }
+
+    pub fn named(mut self, name: &str) -> Self {
+        self.name = name.to_string();
+        self
+    }
+
+    pub fn contains(&self, point: &Vec3) -> bool {
+        (point - self.center).magnitude_squared() <= self.radius * self.radius
+    }
+
+    pub fn volume(&self) -> f32 {
+        4.0 / 3.0 * PI * self.radius.powi(3)
+    }
+
+    // Coordenadas esféricas de un punto de la superficie: u da la vuelta
+    // alrededor del eje Y empezando en +X y v va del polo de abajo (0) al de
+    // arriba (1), así una textura se envuelve una vez con la costura hacia -X
+    pub fn uv(&self, point: &Vec3) -> (f32, f32) {
+        let direction = (point - self.center) / self.radius;
+        let u = 0.5 + direction.z.atan2(direction.x) / (2.0 * PI);
+        let v = 0.5 + direction.y.clamp(-1.0, 1.0).asin() / PI;
+        (u, v)
+    }
+}
+
+impl RayIntersect for Sphere {
+    // Con el origen adentro devuelve la salida, con la normal siempre hacia afuera
+    fn ray_intersect(&self, ray_origin: &Vec3, ray_dir: &Vec3) -> Intersect {
+        let offset = ray_origin - self.center;
+        let a = ray_dir.dot(ray_dir);
+        let b = offset.dot(ray_dir);
+        let c = offset.dot(&offset) - self.radius * self.radius;
+        let discriminant = b * b - a * c;
+        if discriminant < 0.0 || a == 0.0 {
+            return Intersect::empty();
+        }
+
+        let root = discriminant.sqrt();
+        let near = (-b - root) / a;
+        let far = (-b + root) / a;
+        let distance = if near >= 0.0 { near } else { far };
+        if distance < 0.0 {
+            return Intersect::empty();
+        }
+
+        // La textura se envuelve una vez con coordenadas esféricas, sin huella
+        // alargada porque sus ejes no siguen a los de una cara
+        let point = ray_origin + ray_dir * distance;
+        Intersect::new(point, (point - self.center) / self.radius, distance, self.material.clone())
+            .with_uv(self.uv(&point), Footprint::ROUND)
+    }
+
+    fn distance(&self, point: &Vec3) -> f32 {
+        (point - self.center).magnitude() - self.radius
+    }
+}
@@ -0,0 +1,135 @@
+use std::time::{Duration, Instant};
+
+use crate::camera::Camera;
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::light::SceneLight;
+use crate::object::SceneObject;
+use crate::renderer::{render, render_reprojected, RenderStats};
+use crate::reprojection::Reprojection;
+use crate::settings::RenderSettings;
+
+// Vista dividida: el framebuffer se parte en dos vistas lado a lado separadas
+// por una línea, cada una con su cámara. La izquierda es la cámara de siempre
+// y la derecha arranca en la de la escena; mientras está fija no recibe la
+// entrada de cámara, así sirve de vista de referencia mientras se orbita en la
+// otra. Cada vista se traza en su propio framebuffer, así los rayos usan la
+// relación de aspecto de la vista y ninguna de las dos se estira.
+pub struct SplitView {
+    // Cámara de la vista derecha
+    pub camera: Camera,
+    pub locked: bool,
+    // Historial propio de la vista derecha cuando se reproyecta
+    reprojection: Option<Reprojection>,
+    pub views: [Framebuffer; 2],
+    pub costs: [ViewCost; 2],
+}
+
+// Lo que costó trazar una vista en el último frame
+#[derive(Debug, Clone, Copy, Default)]
+pub struct ViewCost {
+    pub stats: RenderStats,
+    pub elapsed: Duration,
+}
+
+// Ancho en píxeles de la línea entre las dos vistas
+pub const DIVIDER: usize = 1;
+pub const DIVIDER_COLOR: Color = Color::new(20, 20, 20);
+
+impl SplitView {
+    pub fn new(camera: Camera, width: usize, height: usize) -> Self {
+        let left = width.saturating_sub(DIVIDER) / 2;
+        let right = width.saturating_sub(DIVIDER + left);
+        SplitView {
+            camera,
+            locked: true,
+            reprojection: None,
+            views: [Framebuffer::new(left.max(1), height), Framebuffer::new(right.max(1), height)],
+            costs: [ViewCost::default(); 2],
+        }
+    }
+
+    // La vista izquierda usa la cámara de siempre
+    pub fn camera_of<'a>(&'a self, view: usize, primary: &'a Camera) -> &'a Camera {
+        if view == 0 { primary } else { &self.camera }
+    }
+
+    // Columna del framebuffer completo donde empieza cada vista
+    pub fn offset(&self, view: usize) -> usize {
+        if view == 0 { 0 } else { self.views[0].width + DIVIDER }
+    }
+
+    // Vista bajo una coordenada horizontal normalizada a [0, 1) del
+    // framebuffer completo, y la coordenada normalizada dentro de esa vista
+    pub fn view_at(&self, u: f32) -> (usize, f32) {
+        let width = (self.offset(1) + self.views[1].width) as f32;
+        let x = u * width;
+        if x < self.offset(1) as f32 - DIVIDER as f32 * 0.5 {
+            (0, (x / self.views[0].width as f32).clamp(0.0, 1.0))
+        } else {
+            (1, ((x - self.offset(1) as f32) / self.views[1].width as f32).clamp(0.0, 1.0))
+        }
+    }
+
+    // Traza las dos vistas con la misma escena y las mismas opciones. Con
+    // `reprojection` la izquierda usa ese historial y la derecha uno propio
+    #[allow(clippy::too_many_arguments)]
+    pub fn render(
+        &mut self,
+        objects: &[SceneObject],
+        camera: &Camera,
+        lights: &[SceneLight],
+        sky_color: Color,
+        settings: &RenderSettings,
+        mut reprojection: Option<&mut Reprojection>,
+    ) {
+        if reprojection.is_none() {
+            self.reprojection = None;
+        } else if self.reprojection.is_none() {
+            self.reprojection = Some(Reprojection::default());
+        }
+        for view in 0..2 {
+            let start = Instant::now();
+            let (camera, history) = if view == 0 {
+                (camera, reprojection.as_deref_mut())
+            } else {
+                (&self.camera, self.reprojection.as_mut())
+            };
+            let framebuffer = &mut self.views[view];
+            let stats = match history {
+                Some(history) => render_reprojected(framebuffer, objects, camera, lights, sky_color, settings, history),
+                None => render(framebuffer, objects, camera, lights, sky_color, settings),
+            };
+            self.costs[view] = ViewCost { stats, elapsed: start.elapsed() };
+        }
+    }
+
+    // Lo que costaron las dos vistas juntas en el último frame
+    pub fn total(&self) -> RenderStats {
+        let [left, right] = self.costs.map(|cost| cost.stats);
+        RenderStats {
+            primary_rays: left.primary_rays + right.primary_rays,
+            reused_pixels: left.reused_pixels + right.reused_pixels,
+            reusable_pixels: left.reusable_pixels + right.reusable_pixels,
+        }
+    }
+
+    // Píxeles que cubren las dos vistas, sin la línea del medio
+    pub fn pixels(&self) -> usize {
+        self.views.iter().map(|view| view.width * view.height).sum()
+    }
+
+    // Pone las dos vistas lado a lado en `target`, con la línea entre ellas
+    pub fn compose(&self, target: &mut Framebuffer) {
+        target.paste(&self.views[0], 0, 0);
+        target.paste(&self.views[1], self.offset(1), 0);
+        for y in 0..target.height {
+            for x in self.views[0].width..self.offset(1).min(target.width) {
+                let index = y * target.width + x;
+                target.buffer[index] = DIVIDER_COLOR;
+                target.alpha[index] = 255;
+                target.depth[index] = f32::INFINITY;
+            }
+        }
+    }
+}
@@ -1,37 +1,358 @@
-use image::{DynamicImage, GenericImageView, Rgba};
+use serde::{Deserialize, Serialize};
+use image::{DynamicImage, Rgba};
+use nalgebra_glm::Vec3;
 use crate::color::Color;
 use std::collections::HashMap;
 use std::io::Error as IoError;
+use std::path::Path;
+use std::sync::Arc;
 
-#[derive(Clone, Debug)]
+// Cómo se filtra una textura al muestrearla
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TextureFilter {
+    Nearest,
+    Bilinear,
+    // Aproximación anisotrópica barata: hasta `max_samples` lecturas
+    // bilineales repartidas a lo largo del eje mayor de la huella en UV
+    Aniso { max_samples: u32 },
+}
+
+impl TextureFilter {
+    pub fn name(self) -> String {
+        match self {
+            TextureFilter::Nearest => "nearest".to_string(),
+            TextureFilter::Bilinear => "bilinear".to_string(),
+            TextureFilter::Aniso { max_samples } => format!("aniso:{}", max_samples),
+        }
+    }
+}
+
+// De dónde salen las coordenadas de textura de un material
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum UvMode {
+    // Las de la forma: por cara en los cubos, esféricas en las esferas, planas en los planos
+    #[default]
+    Face,
+    // Una proyección plana en el mundo sobre el eje más cercano a la normal
+    World,
+    // Tres proyecciones en el mundo (YZ, XZ y XY) mezcladas según la normal;
+    // un `sharpness` más alto angosta la transición entre ellas
+    Triplanar { sharpness: f32 },
+}
+
+// Forma de la huella de un píxel en UV: `axis` es la dirección unitaria de su
+// eje mayor y `elongation` cuántas veces más largo es que el menor (1 cuando
+// la superficie se ve de frente)
+#[derive(Debug, Clone, Copy)]
+pub struct Footprint {
+    pub axis: (f32, f32),
+    pub elongation: f32,
+}
+
+impl Footprint {
+    // Una huella redonda, para superficies cuyos ejes UV no siguen una cara
+    pub const ROUND: Footprint = Footprint { axis: (1.0, 0.0), elongation: 1.0 };
+}
+
+// Un patrón calculado a partir de UV en lugar de leído de una imagen. Todos se
+// repiten en el cuadrado unidad, así las caras vecinas se juntan sin costuras
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProceduralTexture {
+    // `scale` casillas por lado (redondeado a un entero), `even` en UV 0
+    Checker { even: Color, odd: Color, scale: f32 },
+    // Ruido fractal de valores de `low` a `high`: tres octavas sobre una red de
+    // `frequency` celdas por lado (redondeado), cada una el doble de fina y la mitad de fuerte
+    Noise { low: Color, high: Color, seed: u32, frequency: f32 },
+    // De `bottom` en v = 0 a `top` en v = 1
+    Gradient { bottom: Color, top: Color },
+}
+
+impl ProceduralTexture {
+    // RGB de 0 a 255 en (u, v), llevado al cuadrado unidad
+    pub fn color_at(&self, u: f32, v: f32) -> [f32; 3] {
+        let (u, v) = (u.rem_euclid(1.0), v.rem_euclid(1.0));
+        let rgb = |color: Color| [color.red(), color.green(), color.blue()].map(f32::from);
+        let (from, to, t) = match *self {
+            ProceduralTexture::Checker { even, odd, scale } => {
+                let cells = scale.round().max(1.0);
+                let parity = ((u * cells).floor() + (v * cells).floor()) as i64 % 2;
+                return rgb(if parity == 0 { even } else { odd });
+            }
+            ProceduralTexture::Noise { low, high, seed, frequency } => (low, high, fractal_noise(u, v, seed, frequency)),
+            ProceduralTexture::Gradient { bottom, top } => (bottom, top, v),
+        };
+        let (from, to) = (rgb(from), rgb(to));
+        [0, 1, 2].map(|c| from[c] + (to[c] - from[c]) * t)
+    }
+}
+
+// Valor pseudoaleatorio en [0, 1) para un punto de la red, que se repite cada
+// `period` puntos para que el ruido empalme
+fn lattice(x: i64, y: i64, period: i64, seed: u32) -> f32 {
+    let (x, y) = (x.rem_euclid(period) as u64, y.rem_euclid(period) as u64);
+    // Final de splitmix64 sobre las coordenadas empaquetadas
+    let mut hash = (x << 32 | y) ^ (seed as u64).wrapping_mul(0x9e37_79b9_7f4a_7c15);
+    hash = (hash ^ (hash >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    hash = (hash ^ (hash >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    hash ^= hash >> 31;
+    (hash >> 40) as f32 / (1u64 << 24) as f32
+}
+
+// Valores de la red interpolados con suavidad, `period` celdas a lo ancho del cuadrado unidad
+fn value_noise(u: f32, v: f32, period: i64, seed: u32) -> f32 {
+    let (x, y) = (u * period as f32, v * period as f32);
+    let (x0, y0) = (x.floor(), y.floor());
+    let smooth = |t: f32| t * t * (3.0 - 2.0 * t);
+    let (fx, fy) = (smooth(x - x0), smooth(y - y0));
+    let (x0, y0) = (x0 as i64, y0 as i64);
+    let corner = |dx: i64, dy: i64| lattice(x0 + dx, y0 + dy, period, seed);
+    let top = corner(0, 0) + (corner(1, 0) - corner(0, 0)) * fx;
+    let bottom = corner(0, 1) + (corner(1, 1) - corner(0, 1)) * fx;
+    top + (bottom - top) * fy
+}
+
+fn fractal_noise(u: f32, v: f32, seed: u32, frequency: f32) -> f32 {
+    const OCTAVES: u32 = 3;
+    let base = frequency.round().max(1.0) as i64;
+    let (mut sum, mut total) = (0.0, 0.0);
+    for octave in 0..OCTAVES {
+        let amplitude = 0.5f32.powi(octave as i32);
+        sum += value_noise(u, v, base << octave, seed.wrapping_add(octave)) * amplitude;
+        total += amplitude;
+    }
+    sum / total
+}
+
+impl From<ProceduralTexture> for Texture {
+    fn from(pattern: ProceduralTexture) -> Self {
+        Texture::procedural(pattern)
+    }
+}
+
+impl From<ProceduralTexture> for Arc<Texture> {
+    fn from(pattern: ProceduralTexture) -> Self {
+        Arc::new(Texture::procedural(pattern))
+    }
+}
+
+// Los píxeles se decodifican una vez a RGBA8, fila por fila desde arriba, y se
+// indexan directo al muestrear. Los clones comparten el búfer, así clonar un
+// material no copia su imagen. Una textura procedural se evalúa desde UV al
+// muestrearla y guarda solo una vista previa chica en `pixels`, para los
+// promedios y las huellas del filtro.
+#[derive(Clone)]
 pub struct Texture {
-    image: DynamicImage,
+    pixels: Arc<[u8]>,
     width: u32,
     height: u32,
+    path: String,
+    procedural: Option<ProceduralTexture>,
+}
+
+impl std::fmt::Debug for Texture {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(f, "Texture({}, {}x{})", self.path, self.width, self.height)
+    }
 }
 
 impl Texture {
     pub fn new(file_path: &str) -> Result<Texture, image::ImageError> {
-        let img = image::open(file_path)?;
-        let (width, height) = img.dimensions();
-        Ok(Texture { image: img, width, height })
+        Ok(Texture::from_image(image::open(file_path)?, file_path))
+    }
+
+    fn from_image(image: DynamicImage, path: &str) -> Texture {
+        let image = image.into_rgba8();
+        let (width, height) = image.dimensions();
+        Texture { pixels: image.into_raw().into(), width, height, path: path.to_string(), procedural: None }
+    }
+
+    // Un patrón que no necesita archivo de imagen. La ruta describe el patrón,
+    // así dos texturas con el mismo comparten los promedios guardados por ruta
+    pub fn procedural(pattern: ProceduralTexture) -> Texture {
+        const PREVIEW_SIZE: u32 = 64;
+        let preview = rasterize(PREVIEW_SIZE, PREVIEW_SIZE, &format!("procedural:{:?}", pattern), |u, v| pattern.color_at(u, v));
+        Texture { procedural: Some(pattern), ..preview }
+    }
+
+    pub fn pattern(&self) -> Option<&ProceduralTexture> {
+        self.procedural.as_ref()
+    }
+
+    fn pixel(&self, x: u32, y: u32) -> [u8; 4] {
+        let start = (y as usize * self.width as usize + x as usize) * 4;
+        let texel = &self.pixels[start..start + 4];
+        [texel[0], texel[1], texel[2], texel[3]]
+    }
+
+    // Damero magenta y negro en lugar de una imagen que no se pudo cargar, para
+    // que el archivo que falta se note en el render; `path` es el que falta
+    pub fn checkerboard(path: &str) -> Texture {
+        const SIZE: u32 = 64;
+        const SQUARE: u32 = 8;
+        let image = image::RgbaImage::from_fn(SIZE, SIZE, |x, y| {
+            if (x / SQUARE + y / SQUARE).is_multiple_of(2) { Rgba([255, 0, 255, 255]) } else { Rgba([0, 0, 0, 255]) }
+        });
+        Texture::from_image(DynamicImage::ImageRgba8(image), path)
+    }
+
+    // Decodifica una imagen incluida en el binario; `path` solo sirve para identificarla
+    pub fn from_bytes(bytes: &[u8], path: &str) -> Result<Texture, image::ImageError> {
+        Ok(Texture::from_image(image::load_from_memory(bytes)?, path))
     }
 
     pub fn get_color(&self, u: f32, v: f32) -> Color {
-        // Convertir UV a coordenadas de píxeles
-        let x = ((u % 1.0) * self.width as f32) as u32;
-        let y = ((1.0 - (v % 1.0)) * self.height as f32) as u32;
+        if let Some(pattern) = &self.procedural {
+            let [r, g, b] = pattern.color_at(u, v).map(|c| c.round().clamp(0.0, 255.0) as u8);
+            return Color::new(r, g, b);
+        }
+        // Convertir UV a coordenadas de píxeles; rem_euclid repite la textura
+        // también en los valores negativos, sin espejarla
+        let x = (u.rem_euclid(1.0) * self.width as f32) as u32;
+        let y = ((1.0 - v.rem_euclid(1.0)) * self.height as f32) as u32;
         
         // Asegurar que las coordenadas estén dentro de los límites
         let x = x.clamp(0, self.width - 1);
         let y = y.clamp(0, self.height - 1);
         
-        let pixel = self.image.get_pixel(x, y);
-        let Rgba([r, g, b, _a]) = pixel;
+        let [r, g, b, _a] = self.pixel(x, y);
+        Color::new(r, g, b)
+    }
+
+    fn texel(&self, x: i64, y: i64) -> [f32; 3] {
+        let x = x.rem_euclid(self.width as i64) as u32;
+        let y = y.rem_euclid(self.height as i64) as u32;
+        let [r, g, b, _a] = self.pixel(x, y);
+        [r as f32, g as f32, b as f32]
+    }
+
+    // Lectura bilineal que se repite, con la misma orientación que `get_color`
+    fn bilinear(&self, u: f32, v: f32) -> [f32; 3] {
+        if let Some(pattern) = &self.procedural {
+            return pattern.color_at(u, v);
+        }
+        let x = u.rem_euclid(1.0) * self.width as f32 - 0.5;
+        let y = (1.0 - v.rem_euclid(1.0)) * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let (x0, y0) = (x0 as i64, y0 as i64);
+
+        let mix = |a: [f32; 3], b: [f32; 3], t: f32| [0, 1, 2].map(|c| a[c] * (1.0 - t) + b[c] * t);
+        let top = mix(self.texel(x0, y0), self.texel(x0 + 1, y0), fx);
+        let bottom = mix(self.texel(x0, y0 + 1), self.texel(x0 + 1, y0 + 1), fx);
+        mix(top, bottom, fy)
+    }
+
+    pub fn sample(&self, u: f32, v: f32, filter: TextureFilter, footprint: Footprint) -> Color {
+        let rgb = match filter {
+            TextureFilter::Nearest => return self.get_color(u, v),
+            TextureFilter::Bilinear => self.bilinear(u, v),
+            TextureFilter::Aniso { max_samples } => {
+                // Una lectura por texel de alargamiento, repartidas sobre el
+                // eje mayor de la huella y promediadas
+                let taps = (footprint.elongation.ceil() as u32).clamp(1, max_samples.max(1));
+                let step_u = footprint.axis.0 * footprint.elongation / self.width as f32;
+                let step_v = footprint.axis.1 * footprint.elongation / self.height as f32;
+
+                let mut sum = [0.0; 3];
+                for i in 0..taps {
+                    let t = (i as f32 + 0.5) / taps as f32 - 0.5;
+                    let tap = self.bilinear(u + step_u * t, v + step_v * t);
+                    sum = [0, 1, 2].map(|c| sum[c] + tap[c]);
+                }
+                sum.map(|c| c / taps as f32)
+            }
+        };
 
+        let [r, g, b] = rgb.map(|c| c.round().clamp(0.0, 255.0) as u8);
         Color::new(r, g, b)
     }
 
+    // Muestrea con proyecciones en el mundo en lugar de las UV de la forma, con
+    // una repetición cada `scale` unidades. Rotar o estirar la forma no estira
+    // la textura, que queda quieta en el mundo. `Face` se trata como `World`.
+    pub fn sample_world(&self, point: &Vec3, normal: &Vec3, mode: UvMode, scale: f32, filter: TextureFilter) -> Color {
+        let scale = scale.max(1e-6);
+        let project = |axis: usize| {
+            let (u, v) = match axis {
+                0 => (point.z, point.y),
+                1 => (point.x, point.z),
+                _ => (point.x, point.y),
+            };
+            self.sample((u / scale).rem_euclid(1.0), (v / scale).rem_euclid(1.0), filter, Footprint::ROUND)
+        };
+        let UvMode::Triplanar { sharpness } = mode else {
+            return project(normal.iamax());
+        };
+
+        let weights = [normal.x, normal.y, normal.z].map(|c| c.abs().powf(sharpness.max(1.0)));
+        let total: f32 = weights.iter().sum();
+        if total <= 0.0 {
+            return project(normal.iamax());
+        }
+        let mut sum = [0.0; 3];
+        for (axis, weight) in weights.iter().enumerate() {
+            // Las proyecciones que casi no se ven no valen una lectura
+            let weight = weight / total;
+            if weight < 1e-3 {
+                continue;
+            }
+            let color = project(axis);
+            for (channel, value) in sum.iter_mut().zip([color.red(), color.green(), color.blue()]) {
+                *channel += value as f32 * weight;
+            }
+        }
+        let [r, g, b] = sum.map(|c| c.round().clamp(0.0, 255.0) as u8);
+        Color::new(r, g, b)
+    }
+
+    // Lectura bilineal de color y alfa (de 0 a 1) sin repetir, para imágenes
+    // que se estampan una vez en lugar de en mosaico. Las lecturas se mezclan
+    // premultiplicadas para que los texels transparentes no oscurezcan los bordes.
+    pub fn sample_rgba(&self, u: f32, v: f32) -> ([f32; 3], f32) {
+        if let Some(pattern) = &self.procedural {
+            return (pattern.color_at(u.clamp(0.0, 0.999_999), v.clamp(0.0, 0.999_999)), 1.0);
+        }
+        let x = u.clamp(0.0, 1.0) * self.width as f32 - 0.5;
+        let y = (1.0 - v.clamp(0.0, 1.0)) * self.height as f32 - 0.5;
+        let (x0, y0) = (x.floor(), y.floor());
+        let (fx, fy) = (x - x0, y - y0);
+        let texel = |dx: i64, dy: i64| {
+            let tx = (x0 as i64 + dx).clamp(0, self.width as i64 - 1) as u32;
+            let ty = (y0 as i64 + dy).clamp(0, self.height as i64 - 1) as u32;
+            let [r, g, b, a] = self.pixel(tx, ty);
+            let alpha = a as f32 / 255.0;
+            [r as f32 * alpha, g as f32 * alpha, b as f32 * alpha, alpha]
+        };
+
+        let mix = |a: [f32; 4], b: [f32; 4], t: f32| [0, 1, 2, 3].map(|c| a[c] * (1.0 - t) + b[c] * t);
+        let top = mix(texel(0, 0), texel(1, 0), fx);
+        let bottom = mix(texel(0, 1), texel(1, 1), fx);
+        let [r, g, b, alpha] = mix(top, bottom, fy);
+        if alpha <= 0.0 {
+            return ([0.0; 3], 0.0);
+        }
+        ([r / alpha, g / alpha, b / alpha], alpha)
+    }
+
+    // Color promedio de toda la imagen, para cuando una cara queda en un solo color
+    pub fn average_color(&self) -> Color {
+        let mut sum = [0u64; 3];
+        for texel in self.pixels.chunks_exact(4) {
+            sum[0] += texel[0] as u64;
+            sum[1] += texel[1] as u64;
+            sum[2] += texel[2] as u64;
+        }
+        let count = (self.width as u64 * self.height as u64).max(1);
+        let [r, g, b] = sum.map(|channel| (channel / count) as u8);
+        Color::new(r, g, b)
+    }
+
+    // Bytes que ocupa la imagen decodificada, compartidos por todos los clones
+    pub fn memory_bytes(&self) -> usize {
+        self.pixels.len()
+    }
+
     pub fn width(&self) -> u32 {
         self.width
     }
@@ -39,10 +360,149 @@ impl Texture {
     pub fn height(&self) -> u32 {
         self.height
     }
+
+    // Archivo del que se cargó la textura
+    pub fn path(&self) -> &str {
+        &self.path
+    }
+
+    // Si las dos comparten los mismos píxeles decodificados, como los clones de una textura
+    pub fn same_image(&self, other: &Texture) -> bool {
+        Arc::ptr_eq(&self.pixels, &other.pixels)
+    }
+
+    // Las filas `top..top + height` como una textura aparte, con el nombre `path`
+    fn rows(&self, top: u32, height: u32, path: String) -> Texture {
+        let row_bytes = self.width as usize * 4;
+        let start = top as usize * row_bytes;
+        let pixels = self.pixels[start..start + height as usize * row_bytes].into();
+        Texture { pixels, width: self.width, height, path, procedural: None }
+    }
+}
+
+// Cuadros que se muestran en orden a `fps` por segundo y vuelven a empezar
+#[derive(Debug, Clone)]
+pub struct AnimatedTexture {
+    frames: Vec<Texture>,
+    pub fps: f32,
+}
+
+impl AnimatedTexture {
+    // `frames` no puede estar vacío
+    pub fn new(frames: Vec<Texture>, fps: f32) -> Self {
+        assert!(!frames.is_empty(), "an animation needs at least one frame");
+        AnimatedTexture { frames, fps }
+    }
+
+    // Corta una tira vertical de cuadros cuadrados, de arriba abajo, como las
+    // texturas animadas de Minecraft. Una tira que no mide un número entero de
+    // cuadrados tiene los cuadros que entran y descarta las filas que sobran
+    pub fn from_strip(strip: &Texture, fps: f32) -> Self {
+        let size = strip.width.min(strip.height).max(1);
+        let count = (strip.height / size).max(1);
+        let frames = (0..count)
+            .map(|index| strip.rows(index * size, size, format!("{}#{}", strip.path, index)))
+            .collect();
+        AnimatedTexture::new(frames, fps)
+    }
+
+    // Carga un directorio de imágenes ordenadas por el número de su nombre
+    // (cuadro_2 antes que cuadro_10), o corta una sola imagen en tira
+    pub fn load(path: &str, fps: f32) -> Result<Self, IoError> {
+        if !Path::new(path).is_dir() {
+            let strip = Texture::new(path).map_err(IoError::other)?;
+            return Ok(AnimatedTexture::from_strip(&strip, fps));
+        }
+        let mut files: Vec<_> = std::fs::read_dir(path)?
+            .map(|entry| entry.map(|entry| entry.path()))
+            .collect::<Result<_, _>>()?;
+        files.retain(|file| file.is_file());
+        files.sort_by_key(|file| {
+            let name = file.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            let digits: String = name.chars().filter(char::is_ascii_digit).collect();
+            (digits.parse::<u64>().ok(), name)
+        });
+        if files.is_empty() {
+            return Err(IoError::new(std::io::ErrorKind::NotFound, format!("{} has no frames", path)));
+        }
+        let frames = files
+            .iter()
+            .map(|file| Texture::new(&file.to_string_lossy()).map_err(IoError::other))
+            .collect::<Result<_, _>>()?;
+        Ok(AnimatedTexture::new(frames, fps))
+    }
+
+    pub fn frames(&self) -> &[Texture] {
+        &self.frames
+    }
+
+    // Cuadro que se ve a los `time` segundos del inicio
+    pub fn frame_at(&self, time: f32) -> &Texture {
+        let index = (time * self.fps).floor().max(0.0) as usize;
+        &self.frames[index % self.frames.len()]
+    }
+}
+
+// Cualquier cosa que da un color para cada UV y se puede pasar a una imagen
+// común, para los exportadores que solo aceptan píxeles y para los materiales
+// que prefieren leer texels a evaluar un patrón en cada muestra
+pub trait TextureSource {
+    // `width` x `height` texels muestreados en sus centros, como se ve la
+    // fuente a los `time` segundos; solo las animadas dependen de `time`
+    fn bake(&self, width: u32, height: u32, time: f32) -> Texture;
+
+    // Si hornearla deja quieto un solo cuadro de algo que se mueve
+    fn is_animated(&self) -> bool {
+        false
+    }
+}
+
+impl TextureSource for ProceduralTexture {
+    fn bake(&self, width: u32, height: u32, _time: f32) -> Texture {
+        rasterize(width, height, &format!("procedural:{:?}@{}x{}", self, width, height), |u, v| self.color_at(u, v))
+    }
+}
+
+impl TextureSource for Texture {
+    // Un patrón se evalúa de nuevo en el tamaño pedido; una imagen se
+    // remuestrea desde sus texels más cercanos, así hornearla en su tamaño la copia
+    fn bake(&self, width: u32, height: u32, time: f32) -> Texture {
+        if let Some(pattern) = &self.procedural {
+            return pattern.bake(width, height, time);
+        }
+        rasterize(width, height, &format!("{}@{}x{}", self.path, width, height), |u, v| {
+            let color = self.get_color(u, v);
+            [color.red(), color.green(), color.blue()].map(f32::from)
+        })
+    }
+}
+
+impl TextureSource for AnimatedTexture {
+    fn bake(&self, width: u32, height: u32, time: f32) -> Texture {
+        self.frame_at(time).bake(width, height, time)
+    }
+
+    fn is_animated(&self) -> bool {
+        true
+    }
+}
+
+// Imagen opaca con `color` (de 0 a 255 por canal) evaluado en el centro de
+// cada texel, con v = 1 en la fila de arriba como en el resto del archivo
+fn rasterize(width: u32, height: u32, path: &str, color: impl Fn(f32, f32) -> [f32; 3]) -> Texture {
+    let (width, height) = (width.max(1), height.max(1));
+    let image = image::RgbaImage::from_fn(width, height, |x, y| {
+        let u = (x as f32 + 0.5) / width as f32;
+        let v = 1.0 - (y as f32 + 0.5) / height as f32;
+        let [r, g, b] = color(u, v).map(|c| c.round().clamp(0.0, 255.0) as u8);
+        Rgba([r, g, b, 255])
+    });
+    Texture::from_image(DynamicImage::ImageRgba8(image), path)
 }
 
+#[derive(Default)]
 pub struct TextureCache {
-    textures: HashMap<String, Texture>,
+    textures: HashMap<String, Arc<Texture>>,
 }
 
 impl TextureCache {
@@ -52,11 +512,19 @@ impl TextureCache {
         }
     }
 
-    pub fn get_or_load(&mut self, file_path: &str) -> Result<&Texture, std::io::Error> {
+    // Cada ruta se decodifica una vez y todos reciben un clon del mismo Arc.
+    // Un archivo que no se puede leer da el damero en su lugar junto con el
+    // error, para que quien llama avise; el damero no se guarda, así la
+    // próxima vez se vuelve a probar el archivo
+    pub fn get_or_load(&mut self, file_path: &str) -> (Arc<Texture>, Option<IoError>) {
         if !self.textures.contains_key(file_path) {
-            let texture = Texture::new(file_path).map_err(|e| IoError::new(std::io::ErrorKind::Other, e))?;
-            self.textures.insert(file_path.to_string(), texture);
+            match Texture::new(file_path) {
+                Ok(texture) => {
+                    self.textures.insert(file_path.to_string(), Arc::new(texture));
+                }
+                Err(error) => return (Arc::new(Texture::checkerboard(file_path)), Some(IoError::other(error))),
+            }
         }
-        Ok(self.textures.get(file_path).unwrap())
+        (self.textures[file_path].clone(), None)
     }
 }
\ No newline at end of file
@@ -0,0 +1,49 @@
+use image::RgbImage;
+
+use crate::bvh;
+use crate::cornerao;
+use crate::framebuffer::Framebuffer;
+use crate::light::NOON;
+use crate::renderer::{render, sky_color};
+use crate::scene::{LoadReport, Scene, SceneError};
+use crate::settings::RenderSettings;
+
+// Cada píxel de la miniatura promedia una cuadrícula de 2x2 muestras
+const SUPERSAMPLING: usize = 2;
+
+// Miniatura cuadrada de un archivo de escena: la cámara se encuadra sobre la
+// caja de la escena manteniendo su dirección de vista y el sol queda al
+// mediodía. No abre ventana ni depende de estado global.
+pub fn render_thumbnail(scene_path: &str, size: usize) -> Result<RgbImage, SceneError> {
+    render_thumbnail_with_report(scene_path, size).map(|(image, _)| image)
+}
+
+// Igual, con el reporte de carga para marcar las escenas a las que les faltan
+// texturas: la miniatura sale igual, con el damero en su lugar
+pub fn render_thumbnail_with_report(scene_path: &str, size: usize) -> Result<(RgbImage, LoadReport), SceneError> {
+    let (scene, report) = Scene::load_with_report(scene_path)?;
+
+    let mut camera = scene.camera.clone();
+    if let Some((min, max)) = scene.bounds() {
+        camera.frame_bounds(&min, &max);
+    }
+
+    let mut lights = scene.lights.clone();
+    for light in lights.iter_mut().filter(|light| light.is_sun) {
+        light.set_time(NOON);
+    }
+
+    let mut framebuffer = Framebuffer::new(size * SUPERSAMPLING, size * SUPERSAMPLING);
+    let mut settings = RenderSettings {
+        bias: scene.default_bias(),
+        ..RenderSettings::default()
+    };
+    cornerao::refresh(&mut settings.corner_ao, &scene.objects, scene.corner_ao);
+    bvh::refresh(&mut settings.bvh, &scene.objects);
+    render(&mut framebuffer, &scene.objects, &camera, &lights, sky_color(&lights), &settings);
+    let framebuffer = framebuffer.downsample(SUPERSAMPLING, settings.transparent);
+
+    let side = size as u32;
+    let image = RgbImage::from_raw(side, side, framebuffer.to_rgb_bytes()).expect("el búfer tiene el tamaño de la miniatura");
+    Ok((image, report))
+}
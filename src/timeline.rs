@@ -0,0 +1,450 @@
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Write};
+
+// Acciones de entrada que el bucle principal sabe aplicar. Se graban por
+// nombre para que las sesiones sigan siendo legibles y editables a mano.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Action {
+    ZoomIn,
+    ZoomOut,
+    OrbitLeft,
+    OrbitRight,
+    OrbitUp,
+    OrbitDown,
+    ToggleStats,
+    ToggleAdaptive,
+    ClearProbe,
+    NearIn,
+    NearOut,
+    CycleFilter,
+    CaptureTrace,
+    ToggleReprojection,
+    ToggleHalfRes,
+    CyclePreset,
+    ToggleGizmo,
+    Regenerate,
+    ToggleMinimap,
+    CyclePalette,
+    ToggleLightEdit,
+    NextLight,
+    LightUp,
+    LightDown,
+    CycleLightKind,
+    AddLight,
+    DeleteLight,
+    Undo,
+    ToggleGodRays,
+    ClearCrop,
+    ToggleSplit,
+    ToggleViewLock,
+    PlayRail,
+    ToggleMotionView,
+    DismissReport,
+    ToggleAmbientOcclusion,
+    DepthUp,
+    DepthDown,
+    ExposureUp,
+    ExposureDown,
+    CycleSamples,
+    PauseCycle,
+    ToggleFly,
+    MoveLeft,
+    MoveRight,
+    MoveUp,
+    MoveDown,
+    FovDown,
+    FovUp,
+    ToggleOrtho,
+    Screenshot,
+    BeautyShot,
+    Quit,
+}
+
+impl Action {
+    pub const ALL: [Action; 53] = [
+        Action::ZoomIn,
+        Action::ZoomOut,
+        Action::OrbitLeft,
+        Action::OrbitRight,
+        Action::OrbitUp,
+        Action::OrbitDown,
+        Action::ToggleStats,
+        Action::ToggleAdaptive,
+        Action::ClearProbe,
+        Action::NearIn,
+        Action::NearOut,
+        Action::CycleFilter,
+        Action::CaptureTrace,
+        Action::ToggleReprojection,
+        Action::ToggleHalfRes,
+        Action::CyclePreset,
+        Action::ToggleGizmo,
+        Action::Regenerate,
+        Action::ToggleMinimap,
+        Action::CyclePalette,
+        Action::ToggleLightEdit,
+        Action::NextLight,
+        Action::LightUp,
+        Action::LightDown,
+        Action::CycleLightKind,
+        Action::AddLight,
+        Action::DeleteLight,
+        Action::Undo,
+        Action::ToggleGodRays,
+        Action::ClearCrop,
+        Action::ToggleSplit,
+        Action::ToggleViewLock,
+        Action::PlayRail,
+        Action::ToggleMotionView,
+        Action::DismissReport,
+        Action::ToggleAmbientOcclusion,
+        Action::DepthUp,
+        Action::DepthDown,
+        Action::ExposureUp,
+        Action::ExposureDown,
+        Action::CycleSamples,
+        Action::PauseCycle,
+        Action::ToggleFly,
+        Action::MoveLeft,
+        Action::MoveRight,
+        Action::MoveUp,
+        Action::MoveDown,
+        Action::FovDown,
+        Action::FovUp,
+        Action::ToggleOrtho,
+        Action::Screenshot,
+        Action::BeautyShot,
+        Action::Quit,
+    ];
+
+    pub fn name(self) -> &'static str {
+        match self {
+            Action::ZoomIn => "zoom_in",
+            Action::ZoomOut => "zoom_out",
+            Action::OrbitLeft => "orbit_left",
+            Action::OrbitRight => "orbit_right",
+            Action::OrbitUp => "orbit_up",
+            Action::OrbitDown => "orbit_down",
+            Action::ToggleStats => "toggle_stats",
+            Action::ToggleAdaptive => "toggle_adaptive",
+            Action::ClearProbe => "clear_probe",
+            Action::NearIn => "near_in",
+            Action::NearOut => "near_out",
+            Action::CycleFilter => "cycle_filter",
+            Action::CaptureTrace => "capture_trace",
+            Action::ToggleReprojection => "toggle_reprojection",
+            Action::ToggleHalfRes => "toggle_half_res",
+            Action::CyclePreset => "cycle_preset",
+            Action::ToggleGizmo => "toggle_gizmo",
+            Action::Regenerate => "regenerate",
+            Action::ToggleMinimap => "toggle_minimap",
+            Action::CyclePalette => "cycle_palette",
+            Action::ToggleLightEdit => "toggle_light_edit",
+            Action::NextLight => "next_light",
+            Action::LightUp => "light_up",
+            Action::LightDown => "light_down",
+            Action::CycleLightKind => "cycle_light_kind",
+            Action::AddLight => "add_light",
+            Action::DeleteLight => "delete_light",
+            Action::Undo => "undo",
+            Action::ToggleGodRays => "toggle_god_rays",
+            Action::ClearCrop => "clear_crop",
+            Action::ToggleSplit => "toggle_split",
+            Action::ToggleViewLock => "toggle_view_lock",
+            Action::PlayRail => "play_rail",
+            Action::ToggleMotionView => "toggle_motion_view",
+            Action::DismissReport => "dismiss_report",
+            Action::ToggleAmbientOcclusion => "toggle_ambient_occlusion",
+            Action::DepthUp => "depth_up",
+            Action::DepthDown => "depth_down",
+            Action::ExposureUp => "exposure_up",
+            Action::ExposureDown => "exposure_down",
+            Action::CycleSamples => "cycle_samples",
+            Action::PauseCycle => "pause_cycle",
+            Action::ToggleFly => "toggle_fly",
+            Action::MoveLeft => "move_left",
+            Action::MoveRight => "move_right",
+            Action::MoveUp => "move_up",
+            Action::MoveDown => "move_down",
+            Action::FovDown => "fov_down",
+            Action::FovUp => "fov_up",
+            Action::ToggleOrtho => "toggle_ortho",
+            Action::Screenshot => "screenshot",
+            Action::BeautyShot => "beauty_shot",
+            Action::Quit => "quit",
+        }
+    }
+
+    // Las acciones mantenidas se aplican en cada frame con la tecla abajo; el
+    // resto una vez por pulsación
+    pub fn is_held(self) -> bool {
+        matches!(
+            self,
+            Action::ZoomIn
+                | Action::ZoomOut
+                | Action::OrbitLeft
+                | Action::OrbitRight
+                | Action::OrbitUp
+                | Action::OrbitDown
+                | Action::NearIn
+                | Action::NearOut
+                | Action::LightUp
+                | Action::LightDown
+                | Action::MoveLeft
+                | Action::MoveRight
+                | Action::MoveUp
+                | Action::MoveDown
+                | Action::FovDown
+                | Action::FovUp
+                | Action::Quit
+        )
+    }
+
+    pub fn from_name(name: &str) -> Option<Action> {
+        Action::ALL.iter().copied().find(|action| action.name() == name)
+    }
+}
+
+// Entrada de un frame: el paso de tiempo usado para el ciclo día/noche, las
+// acciones mantenidas, las que se dispararon en este frame, el giro de la rueda
+// del mouse, el clic de sonda y el rectángulo de recorte que se terminó de
+// arrastrar (esquinas mínima y máxima), en coordenadas de pantalla
+// normalizadas a [0, 1) para no depender de la resolución, y con la vista
+// dividida la vista que está bajo el mouse (0 la izquierda). En la ventana las
+// acciones salen de `input::InputState`, así las pulsaciones grabadas son las
+// mismas que se aplicaron
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct FrameRecord {
+    pub delta_time: f32,
+    pub held: Vec<Action>,
+    pub pressed: Vec<Action>,
+    pub scroll: f32,
+    pub probe: Option<(f32, f32)>,
+    pub crop: Option<[f32; 4]>,
+    pub view: usize,
+    // Lo que se arrastró el mouse en el frame, en píxeles de la ventana: con
+    // el botón izquierdo orbita y con el del medio desplaza el centro
+    pub orbit: [f32; 2],
+    pub pan: [f32; 2],
+}
+
+impl FrameRecord {
+    pub fn is_held(&self, action: Action) -> bool {
+        self.held.contains(&action)
+    }
+
+    pub fn was_pressed(&self, action: Action) -> bool {
+        self.pressed.contains(&action)
+    }
+
+    // Frame sin teclas, rueda ni clics: no cambia nada de lo que se traza
+    pub fn is_idle(&self) -> bool {
+        self.held.is_empty()
+            && self.pressed.is_empty()
+            && self.scroll == 0.0
+            && self.probe.is_none()
+            && self.crop.is_none()
+            && self.orbit == [0.0; 2]
+            && self.pan == [0.0; 2]
+    }
+
+    // Una línea por frame: `t=<segundos> dt=<segundos> held=a,b pressed=c`,
+    // más `scroll=s` en los frames en que giró la rueda, `probe=u,v` en los
+    // frames con clic, `crop=u0,v0,u1,v1` en los que se soltó un recorte,
+    // `view=n` en los que el mouse está sobre otra vista que la primera y
+    // `orbit=dx,dy` o `pan=dx,dy` en los que se arrastró.
+    // Los f32 se imprimen con la representación más corta que se relee igual,
+    // así la reproducción usa exactamente los mismos pasos de tiempo.
+    fn to_line(&self, elapsed: f64) -> String {
+        let names = |actions: &[Action]| actions.iter().map(|a| a.name()).collect::<Vec<_>>().join(",");
+        let mut line = format!("t={:.4} dt={} held={} pressed={}", elapsed, self.delta_time, names(&self.held), names(&self.pressed));
+        if self.scroll != 0.0 {
+            line.push_str(&format!(" scroll={}", self.scroll));
+        }
+        if let Some((u, v)) = self.probe {
+            line.push_str(&format!(" probe={},{}", u, v));
+        }
+        if let Some([u0, v0, u1, v1]) = self.crop {
+            line.push_str(&format!(" crop={},{},{},{}", u0, v0, u1, v1));
+        }
+        if self.view != 0 {
+            line.push_str(&format!(" view={}", self.view));
+        }
+        for (name, [dx, dy]) in [("orbit", self.orbit), ("pan", self.pan)] {
+            if dx != 0.0 || dy != 0.0 {
+                line.push_str(&format!(" {}={},{}", name, dx, dy));
+            }
+        }
+        line
+    }
+
+    fn parse_line(line: &str) -> Result<FrameRecord, String> {
+        let mut record = FrameRecord::default();
+        let actions = |list: &str| -> Result<Vec<Action>, String> {
+            list.split(',')
+                .filter(|name| !name.is_empty())
+                .map(|name| Action::from_name(name).ok_or_else(|| format!("acción desconocida: {}", name)))
+                .collect()
+        };
+
+        for field in line.split_whitespace() {
+            match field.split_once('=') {
+                Some(("t", _)) => {}
+                Some(("dt", value)) => {
+                    record.delta_time = value.parse().map_err(|_| format!("dt inválido: {}", value))?
+                }
+                Some(("held", list)) => record.held = actions(list)?,
+                Some(("pressed", list)) => record.pressed = actions(list)?,
+                Some(("scroll", value)) => {
+                    record.scroll = value.parse().map_err(|_| format!("scroll inválido: {}", value))?
+                }
+                Some(("probe", value)) => {
+                    let parsed = value
+                        .split_once(',')
+                        .and_then(|(u, v)| Some((u.parse().ok()?, v.parse().ok()?)));
+                    record.probe = Some(parsed.ok_or_else(|| format!("probe inválido: {}", value))?);
+                }
+                Some(("crop", value)) => {
+                    let parsed: Result<Vec<f32>, _> = value.split(',').map(str::parse).collect();
+                    record.crop = match parsed.as_deref() {
+                        Ok(&[u0, v0, u1, v1]) => Some([u0, v0, u1, v1]),
+                        _ => return Err(format!("crop inválido: {}", value)),
+                    };
+                }
+                Some(("view", value)) => {
+                    record.view = value.parse().map_err(|_| format!("view inválido: {}", value))?
+                }
+                Some((name @ ("orbit" | "pan"), value)) => {
+                    let parsed = value
+                        .split_once(',')
+                        .and_then(|(dx, dy)| Some([dx.parse().ok()?, dy.parse().ok()?]))
+                        .ok_or_else(|| format!("{} inválido: {}", name, value))?;
+                    if name == "orbit" {
+                        record.orbit = parsed;
+                    } else {
+                        record.pan = parsed;
+                    }
+                }
+                _ => return Err(format!("campo inválido: {}", field)),
+            }
+        }
+        Ok(record)
+    }
+}
+
+pub struct Recorder {
+    writer: BufWriter<File>,
+    elapsed: f64,
+}
+
+impl Recorder {
+    pub fn create(path: &str) -> std::io::Result<Recorder> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        writeln!(writer, "# sesión de Raycasting_diorama: un frame por línea")?;
+        Ok(Recorder { writer, elapsed: 0.0 })
+    }
+
+    pub fn record(&mut self, frame: &FrameRecord) -> std::io::Result<()> {
+        self.elapsed += frame.delta_time as f64;
+        writeln!(self.writer, "{}", frame.to_line(self.elapsed))
+    }
+
+    pub fn finish(mut self) -> std::io::Result<()> {
+        self.writer.flush()
+    }
+}
+
+pub struct Replay {
+    frames: Vec<FrameRecord>,
+    cursor: usize,
+}
+
+impl Replay {
+    pub fn load(path: &str) -> Result<Replay, String> {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let mut frames = Vec::new();
+        for (number, line) in BufReader::new(file).lines().enumerate() {
+            let line = line.map_err(|e| e.to_string())?;
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+            frames.push(FrameRecord::parse_line(line).map_err(|e| format!("línea {}: {}", number + 1, e))?);
+        }
+        Ok(Replay { frames, cursor: 0 })
+    }
+
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+impl Iterator for Replay {
+    type Item = FrameRecord;
+
+    fn next(&mut self) -> Option<FrameRecord> {
+        let frame = self.frames.get(self.cursor).cloned();
+        self.cursor += 1;
+        frame
+    }
+}
+
+// Reloj del ciclo día/noche, aparte del ritmo de render. Cada frame muestra
+// el sol en la mitad de su intervalo en lugar de al final, así un frame lento
+// no lo hace saltar de golpe: el paso entre dos frames es la media de sus
+// duraciones. El recorrido del sol es analítico, así que interpolar entre
+// pasos fijos de simulación daría lo mismo que el tiempo acumulado. Con
+// `freeze_while_moving` el sol se detiene mientras se mueve la cámara (el mapa
+// de sombras y la reproyección siguen valiendo) y al quedar quieta recupera el
+// atraso de a poco, a `CATCH_UP` veces la velocidad normal. En pausa el sol
+// queda donde está y no se acumula atraso.
+#[derive(Debug, Clone, Default)]
+pub struct DayClock {
+    pub freeze_while_moving: bool,
+    pub paused: bool,
+    // Tiempo del ciclo corrido hasta el final del frame
+    elapsed: f64,
+    // Momento que mostró el frame anterior
+    shown: f64,
+    // Tiempo que el sol pasó detenido y falta recuperar
+    owed: f32,
+}
+
+const CATCH_UP: f32 = 3.0;
+
+impl DayClock {
+    pub fn new(freeze_while_moving: bool) -> Self {
+        DayClock { freeze_while_moving, ..DayClock::default() }
+    }
+
+    // Avanza con la duración de un frame y devuelve cuánto mover el sol en
+    // él; `moving` dice si la cámara se movió en el frame
+    pub fn advance(&mut self, delta_time: f32, moving: bool) -> f32 {
+        if self.paused {
+            return 0.0;
+        }
+        if self.freeze_while_moving && moving {
+            self.owed += delta_time;
+            return 0.0;
+        }
+        let caught_up = self.owed.min(delta_time * (CATCH_UP - 1.0));
+        self.owed -= caught_up;
+        let step = delta_time + caught_up;
+        // Al recuperar, el paso es largo pero parejo; la mitad del frame sigue
+        // quedando después del frame anterior
+        self.elapsed += step as f64;
+        let middle = (self.elapsed - step as f64 * 0.5).max(self.shown);
+        let delta = (middle - self.shown) as f32;
+        self.shown = middle;
+        delta
+    }
+
+    // Atraso pendiente, en segundos del ciclo
+    pub fn owed(&self) -> f32 {
+        self.owed
+    }
+}
@@ -0,0 +1,97 @@
+use crate::color::Palette;
+use crate::diff::{diff_scenes, render_diff, DEFAULT_TOLERANCE};
+use crate::scene::{LoadReport, Scene, SceneError};
+use crate::thumbnail::render_thumbnail_with_report;
+
+// Carga un archivo de escena y avisa por stderr de las texturas que faltan
+pub fn load_scene(path: &str) -> Result<(Scene, LoadReport), SceneError> {
+    let (scene, report) = Scene::load_with_report(path)?;
+    for line in report.lines() {
+        eprintln!("{}: {}", path, line);
+    }
+    Ok((scene, report))
+}
+
+// Compara dos escenas e imprime el reporte. Devuelve el código de salida como
+// diff(1): 0 si son iguales, 1 si difieren y 2 si hubo un error.
+pub fn run_diff(before_path: &str, after_path: &str, image: Option<&str>, width: usize, height: usize, palette: Palette) -> i32 {
+    let load = |path: &str| {
+        load_scene(path).map(|(scene, _)| scene).map_err(|e| eprintln!("Error al cargar la escena {}: {}", path, e))
+    };
+    let (Ok(before), Ok(after)) = (load(before_path), load(after_path)) else {
+        return 2;
+    };
+
+    let diff = diff_scenes(&before, &after, DEFAULT_TOLERANCE);
+    println!("{}", diff.report());
+
+    if let Some(output) = image {
+        let framebuffer = render_diff(&before, &after, &diff, width, height, palette);
+        if let Err(e) = framebuffer.save_png(output) {
+            eprintln!("Error al guardar {}: {}", output, e);
+            return 2;
+        }
+        println!("Imagen guardada en {}", output);
+    }
+
+    if diff.is_empty() { 0 } else { 1 }
+}
+
+// Escribe una miniatura junto a cada escena .ron de la carpeta. Un archivo que
+// falla no detiene al resto, y una escena con texturas que faltan sale con el
+// damero y queda marcada; devuelve si todas se generaron completas.
+pub fn generate_thumbnails(dir: &str, size: usize) -> bool {
+    let entries = match std::fs::read_dir(dir) {
+        Ok(entries) => entries,
+        Err(e) => {
+            eprintln!("Error al leer la carpeta {}: {}", dir, e);
+            return false;
+        }
+    };
+
+    let mut scenes: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .collect();
+    scenes.sort();
+
+    let mut failures = Vec::new();
+    let mut incomplete = Vec::new();
+    for path in &scenes {
+        let name = path.file_stem().unwrap_or_default().to_string_lossy();
+        let output = path.with_file_name(format!("{}.thumb.png", name));
+        let result = render_thumbnail_with_report(&path.to_string_lossy(), size)
+            .map_err(|e| e.to_string())
+            .and_then(|(image, report)| image.save(&output).map(|_| report).map_err(|e| e.to_string()));
+
+        match result {
+            Ok(report) => {
+                println!("{} -> {}", path.display(), output.display());
+                for line in report.lines() {
+                    eprintln!("{}: {}", path.display(), line);
+                }
+                if !report.is_clean() {
+                    incomplete.push(path.display().to_string());
+                }
+            }
+            Err(e) => {
+                eprintln!("Error en {}: {}", path.display(), e);
+                failures.push(path.display().to_string());
+            }
+        }
+    }
+
+    println!(
+        "Miniaturas: {} generadas, {} con error, {} con texturas que faltan",
+        scenes.len() - failures.len(),
+        failures.len(),
+        incomplete.len()
+    );
+    for failure in &failures {
+        println!("  falló: {}", failure);
+    }
+    for scene in &incomplete {
+        println!("  incompleta: {}", scene);
+    }
+    failures.is_empty() && incomplete.is_empty()
+}
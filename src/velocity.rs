@@ -0,0 +1,97 @@
+use image::{ImageError, Rgb32FImage};
+use nalgebra_glm::Vec3;
+use rayon::prelude::*;
+
+use crate::camera::{Camera, Projection};
+use crate::color::Color;
+use crate::framebuffer::Framebuffer;
+use crate::renderer::primary_ray;
+
+// Vectores de movimiento en pantalla: para cada píxel, cuánto se movió desde
+// el frame anterior lo que muestra, en píxeles (positivo hacia la derecha y
+// hacia abajo). Los objetos de la escena no se mueven solos entre frames, así
+// que el movimiento sale solo de la cámara: el punto impactado se reconstruye
+// con la profundidad del rayo primario y se proyecta con la cámara anterior.
+// Los píxeles de cielo están infinitamente lejos y solo los mueve el giro; con
+// la cámara ortográfica todos miran al mismo cielo y quedan quietos. Con
+// la cámara quieta todos los vectores valen cero exacto, sin pasar por la
+// proyección. Sirve para el desenfoque de movimiento, la reproyección o un
+// compositor externo.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MotionVectors {
+    pub width: usize,
+    pub height: usize,
+    // Cero donde el punto quedaba detrás de la cámara anterior
+    pub vectors: Vec<[f32; 2]>,
+}
+
+// Largo en píxeles que se ve con el brillo máximo en la vista de movimiento
+pub const VIEW_SCALE: f32 = 16.0;
+
+// Píxel en que cae un punto visto con `camera` en una imagen de `width` x
+// `height`, en la convención de los rayos primarios: el rayo del píxel (x, y)
+// vuelve a caer en (x, y). None detrás de la cámara
+pub fn pixel_of(camera: &Camera, point: &Vec3, width: usize, height: usize) -> Option<(f32, f32)> {
+    let (screen_x, screen_y) = camera.project(point, width as f32 / height as f32, camera.perspective_scale())?;
+    Some(((screen_x + 1.0) * 0.5 * width as f32, (1.0 - screen_y) * 0.5 * height as f32))
+}
+
+// Desplazamiento del píxel (x, y) entre `previous` y `camera`, para lo que
+// se ve a `depth` de distancia por su rayo primario
+pub fn motion_of(camera: &Camera, previous: &Camera, x: usize, y: usize, depth: f32, width: usize, height: usize) -> [f32; 2] {
+    let (origin, direction) = primary_ray(camera, x, y, width, height);
+    // El cielo se proyecta como una dirección, desde el ojo anterior
+    let point = if depth.is_finite() {
+        origin + direction * depth
+    } else if matches!(camera.projection, Projection::Perspective) && matches!(previous.projection, Projection::Perspective) {
+        previous.eye + direction
+    } else {
+        return [0.0, 0.0];
+    };
+    pixel_of(previous, &point, width, height).map_or([0.0, 0.0], |(from_x, from_y)| [x as f32 - from_x, y as f32 - from_y])
+}
+
+impl MotionVectors {
+    // Vectores del framebuffer recién trazado con `camera`, cuando el frame
+    // anterior se trazó con `previous`
+    pub fn compute(framebuffer: &Framebuffer, camera: &Camera, previous: &Camera) -> Self {
+        let (width, height) = (framebuffer.width, framebuffer.height);
+        let mut vectors = vec![[0.0, 0.0]; width * height];
+        if camera != previous {
+            vectors.par_chunks_mut(width).enumerate().for_each(|(y, row)| {
+                for (x, vector) in row.iter_mut().enumerate() {
+                    *vector = motion_of(camera, previous, x, y, framebuffer.depth[y * width + x], width, height);
+                }
+            });
+        }
+        MotionVectors { width, height, vectors }
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.vectors.iter().all(|vector| *vector == [0.0, 0.0])
+    }
+
+    // Vista de depuración: el tono es la dirección y el brillo el largo, hasta
+    // `scale` píxeles; lo quieto queda negro
+    pub fn visualize(&self, framebuffer: &mut Framebuffer, scale: f32) {
+        for (pixel, [dx, dy]) in framebuffer.buffer.iter_mut().zip(&self.vectors) {
+            let value = ((dx * dx + dy * dy).sqrt() / scale).min(1.0);
+            let hue = (dy.atan2(*dx) / std::f32::consts::TAU).rem_euclid(1.0) * 6.0;
+            let channel = |offset: f32| {
+                let k = (hue + offset) % 6.0;
+                let amount = 1.0 - (k.min(4.0 - k).clamp(0.0, 1.0));
+                ((1.0 - amount) * 255.0 * value) as u8
+            };
+            *pixel = Color::new(channel(5.0), channel(3.0), channel(1.0));
+        }
+    }
+
+    // Guarda los vectores como OpenEXR de punto flotante, con el desplazamiento
+    // en píxeles en R (x) y G (y) y B en cero, para componer afuera
+    pub fn save_exr(&self, path: &str) -> Result<(), ImageError> {
+        let data = self.vectors.iter().flat_map(|[dx, dy]| [*dx, *dy, 0.0]).collect();
+        Rgb32FImage::from_raw(self.width as u32, self.height as u32, data)
+            .expect("hay un vector por píxel")
+            .save(path)
+    }
+}
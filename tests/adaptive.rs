@@ -0,0 +1,75 @@
+use raycasting_diorama::adaptive::{AdaptiveSampling, Sample};
+use raycasting_diorama::color::Color;
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::light::SceneLight;
+use raycasting_diorama::material::Material;
+use raycasting_diorama::renderer::{render, sky_color};
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::{DioramaBuilder, Vec3};
+
+// Mayor diferencia por canal entre dos imágenes
+fn largest_error(a: &[Color], b: &[Color]) -> u8 {
+    a.iter()
+        .zip(b)
+        .flat_map(|(a, b)| [a.red().abs_diff(b.red()), a.green().abs_diff(b.green()), a.blue().abs_diff(b.blue())])
+        .max()
+        .unwrap_or(0)
+}
+
+// Imagen de 100x70 con un cielo en degradado vertical y un disco, otro objeto,
+// iluminado en degradado diagonal
+fn synthetic(x: usize, y: usize) -> Sample {
+    let (fx, fy) = (x as f32 / 100.0, y as f32 / 70.0);
+    let inside = (fx - 0.55).powi(2) + (fy - 0.5).powi(2) < 0.09;
+    if inside {
+        let light = 60.0 + 150.0 * (0.6 * fx + 0.4 * fy);
+        Sample { color: Color::new(light as u8, (light * 0.5) as u8, 40), alpha: 255, object: Some(0), depth: 3.0, ..Sample::default() }
+    } else {
+        Sample { color: Color::new(90 + (60.0 * fy) as u8, 150 + (40.0 * fy) as u8, 230), ..Sample::default() }
+    }
+}
+
+#[test]
+fn interpolated_tiles_stay_within_the_threshold() {
+    let reference: Vec<Color> = (0..70 * 100).map(|index| synthetic(index % 100, index / 100).color).collect();
+    for threshold in [2.0, 8.0, 20.0] {
+        let adaptive = AdaptiveSampling { threshold, ..AdaptiveSampling::default() };
+        let (samples, rays) = adaptive.render(100, 70, &synthetic);
+        let colors: Vec<Color> = samples.iter().map(|sample| sample.color).collect();
+        let error = largest_error(&colors, &reference);
+        assert!(error as f32 <= threshold, "umbral {}: error {}", threshold, error);
+        assert!(rays < 100 * 70, "umbral {}: {} rayos", threshold, rays);
+    }
+}
+
+#[test]
+fn adaptive_renders_stay_within_the_threshold_of_the_reference() {
+    // Toma abierta con mucho cielo y dos cubos lisos, sin texturas ni sombras
+    // dentro de las caras
+    let scene = DioramaBuilder::new()
+        .material("stone", Material::new(Color::new(160, 150, 140), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0))
+        .material("clay", Material::new(Color::new(190, 110, 70), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0))
+        .add_cube(Vec3::new(-1.0, -0.5, -1.0), Vec3::new(0.0, 0.5, 0.0), "stone")
+        .add_cube(Vec3::new(0.6, -0.5, -0.4), Vec3::new(1.2, 0.1, 0.2), "clay")
+        .add_light(SceneLight::sun(Vec3::new(1.0, 3.0, 2.0), Color::white(), 1.0))
+        .camera(Vec3::new(0.0, 0.6, 6.0), Vec3::new(0.0, 0.6, 0.0))
+        .build()
+        .expect("los materiales están registrados");
+    let frame = |adaptive: Option<AdaptiveSampling>| {
+        let settings = RenderSettings { adaptive, bias: scene.default_bias(), ..RenderSettings::default() };
+        let mut framebuffer = Framebuffer::new(160, 120);
+        let stats = render(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, sky_color(&scene.lights), &settings);
+        (framebuffer.buffer, stats.primary_rays)
+    };
+
+    let (reference, full_rays) = frame(None);
+    let mut rays_at = Vec::new();
+    for threshold in [4.0, 8.0] {
+        let (adaptive, rays) = frame(Some(AdaptiveSampling { threshold, ..AdaptiveSampling::default() }));
+        let error = largest_error(&adaptive, &reference);
+        assert!(error as f32 <= threshold, "umbral {}: error {}", threshold, error);
+        rays_at.push(rays);
+    }
+    // Con el umbral por defecto el cielo se llena casi sin rayos
+    assert!(rays_at[0] < full_rays && rays_at[1] * 3 < full_rays, "{:?} de {} rayos", rays_at, full_rays);
+}
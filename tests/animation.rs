@@ -0,0 +1,80 @@
+use raycasting_diorama::bake::{bake, BakeSettings};
+use raycasting_diorama::color::Color;
+use raycasting_diorama::material::Material;
+use raycasting_diorama::texture::{AnimatedTexture, ProceduralTexture, Texture};
+use raycasting_diorama::{DioramaBuilder, Vec3};
+
+// Tira de `frames` cuadros de 2x2, cada uno de un gris más claro que el anterior
+fn strip(frames: u32) -> Texture {
+    let image = image::RgbImage::from_fn(2, 2 * frames, |_, y| image::Rgb([(y / 2 * 10) as u8; 3]));
+    let mut png = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).expect("se codifica en memoria");
+    Texture::from_bytes(&png, "tira.png").expect("es un PNG válido")
+}
+
+#[test]
+fn strip_is_sliced_into_square_frames() {
+    let animation = AnimatedTexture::from_strip(&strip(3), 4.0);
+    let grays: Vec<u8> = animation.frames().iter().map(|frame| frame.get_color(0.5, 0.5).red()).collect();
+    assert_eq!(grays, [0, 10, 20]);
+    assert!(animation.frames().iter().all(|frame| (frame.width(), frame.height()) == (2, 2)));
+    // A 4 cuadros por segundo, a los 0.6 s va el tercero y a los 0.8 s vuelve al primero
+    assert_eq!(animation.frame_at(0.6).get_color(0.5, 0.5).red(), 20);
+    assert_eq!(animation.frame_at(0.8).get_color(0.5, 0.5).red(), 0);
+}
+
+#[test]
+fn directory_frames_follow_their_numbers() {
+    let dir = std::env::temp_dir().join(format!("diorama_cuadros_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("se crea el directorio");
+    for (name, gray) in [("cuadro_10.png", 30), ("cuadro_2.png", 20), ("cuadro_1.png", 10)] {
+        image::RgbImage::from_pixel(2, 2, image::Rgb([gray; 3])).save(dir.join(name)).expect("se guarda el cuadro");
+    }
+    let animation = AnimatedTexture::load(&dir.to_string_lossy(), 8.0).expect("el directorio tiene cuadros");
+    let grays: Vec<u8> = animation.frames().iter().map(|frame| frame.get_color(0.5, 0.5).red()).collect();
+    std::fs::remove_dir_all(&dir).expect("se borra el directorio");
+    assert_eq!(grays, [10, 20, 30]);
+}
+
+#[test]
+fn animating_swaps_the_texture_and_its_emission_map() {
+    let mut material = Material::builder().build().animated(AnimatedTexture::from_strip(&strip(2), 1.0));
+    material.emission_map = material.texture.as_deref().cloned();
+    assert!(!material.animate(0.5), "sigue en el primer cuadro");
+    assert!(material.animate(1.5));
+    let texture = material.texture.as_ref().expect("tiene textura");
+    assert_eq!(texture.get_color(0.5, 0.5), Color::new(10, 10, 10));
+    assert!(material.emission_map.as_ref().is_some_and(|map| map.same_image(texture)));
+}
+
+#[test]
+fn diorama_lava_and_portal_are_animated() {
+    let mut scene = DioramaBuilder::new()
+        .add_cube(Vec3::zeros(), Vec3::repeat(1.0), "lava")
+        .build()
+        .expect("la lava está registrada");
+    assert!(scene.materials["purple"].animation.is_some());
+    assert!(!scene.animate(0.0));
+    assert!(scene.animate(0.5), "a 6 cuadros por segundo la lava ya cambió");
+}
+
+#[test]
+fn the_export_bakes_the_frame_on_screen() {
+    let frames = [Color::new(0, 0, 0), Color::new(255, 255, 255)]
+        .map(|color| Texture::procedural(ProceduralTexture::Gradient { bottom: color, top: color }));
+    let mut scene = DioramaBuilder::new()
+        .material("parpadeo", Material::builder().build().animated(AnimatedTexture::new(frames.to_vec(), 1.0)))
+        .add_cube(Vec3::zeros(), Vec3::repeat(1.0), "parpadeo")
+        .build()
+        .expect("el material está registrado");
+    assert_eq!(scene.time(), 0.0);
+    // A un cuadro por segundo, a los 1.5 s se ve el blanco
+    scene.animate(1.5);
+    assert_eq!(scene.time(), 1.5);
+
+    let brightest = |time: f32| {
+        let settings = BakeSettings { time, ao_rays: 4, spacing: 0.5, ..BakeSettings::default() };
+        bake(&scene.objects, &scene.lights, &settings).iter().map(|face| face.color.red()).max().expect("el cubo tiene caras")
+    };
+    assert!(brightest(scene.time()) > brightest(0.0) + 100, "{} contra {}", brightest(scene.time()), brightest(0.0));
+}
@@ -0,0 +1,40 @@
+use raycasting_diorama::color::Color;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::texture::{Texture, TextureSource};
+
+// Imagen de 4x2 con un color distinto en cada texel
+fn mosaic() -> Texture {
+    let image = image::RgbImage::from_fn(4, 2, |x, y| image::Rgb([x as u8 * 60, y as u8 * 200, 30 + x as u8 * 10]));
+    let mut bytes = Vec::new();
+    image::DynamicImage::ImageRgb8(image)
+        .write_to(&mut std::io::Cursor::new(&mut bytes), image::ImageFormat::Png)
+        .expect("se codifica la imagen");
+    Texture::from_bytes(&bytes, "mosaico").expect("se decodifica la imagen")
+}
+
+#[test]
+fn baking_an_image_at_its_own_size_copies_it() {
+    let texture = mosaic();
+    let baked = texture.bake(4, 2, 0.0);
+    assert_eq!((baked.width(), baked.height()), (4, 2));
+    assert!(!texture.is_animated());
+    // En el centro de cada texel la imagen horneada da lo mismo
+    for (x, y) in (0..4).flat_map(|x| (0..2).map(move |y| (x, y))) {
+        let (u, v) = ((x as f32 + 0.5) / 4.0, 1.0 - (y as f32 + 0.5) / 2.0);
+        assert_eq!(baked.get_color(u, v), texture.get_color(u, v), "texel ({}, {})", x, y);
+    }
+    // Al doble de tamaño cada texel se repite en cuatro
+    let doubled = texture.bake(8, 4, 0.0);
+    assert_eq!(doubled.get_color(0.1, 0.9), Color::new(0, 0, 30));
+    assert_eq!(doubled.get_color(0.9, 0.1), Color::new(180, 200, 60));
+}
+
+#[test]
+fn scenes_can_bake_textures_at_load() {
+    let scene = Scene::from_ron(
+        r#"(materials: {"pasto": (texture: Some("assets/grass.jpg"), bake: Some((16, 8)))}, objects: [])"#,
+    )
+    .expect("la escena es válida");
+    let texture = scene.materials["pasto"].texture.as_ref().expect("queda la imagen horneada");
+    assert_eq!((texture.width(), texture.height()), (16, 8));
+}
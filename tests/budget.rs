@@ -0,0 +1,41 @@
+use std::time::Duration;
+
+use raycasting_diorama::budget::{render_budgeted, LEVELS};
+use raycasting_diorama::color::Color;
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::light::SceneLight;
+use raycasting_diorama::material::Material;
+use raycasting_diorama::renderer::sky_color;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::{DioramaBuilder, Vec3};
+
+fn scene() -> Scene {
+    DioramaBuilder::new()
+        .material("piedra", Material::new(Color::new(150, 140, 130), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0))
+        .add_cube(Vec3::repeat(-0.5), Vec3::repeat(0.5), "piedra")
+        .add_light(SceneLight::sun(Vec3::new(2.0, 4.0, 3.0), Color::white(), 1.0))
+        .camera(Vec3::new(0.0, 0.5, 3.0), Vec3::zeros())
+        .build()
+        .expect("el material está registrado")
+}
+
+#[test]
+fn the_sky_looks_the_same_at_every_level() {
+    let scene = scene();
+    let settings = RenderSettings { bias: scene.default_bias(), ..RenderSettings::default() };
+    let corner = |budget: Duration| {
+        let mut framebuffer = Framebuffer::new(64, 48);
+        let stats = render_budgeted(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, sky_color(&scene.lights), &settings, budget);
+        (stats.level, framebuffer.get_pixel(0, 0))
+    };
+    // Sin tiempo se presenta la pasada gruesa; con tiempo de sobra, la de
+    // 2x2 muestras, cuyo cielo sin alfa no debe promediarse a negro
+    let (coarse_level, coarse) = corner(Duration::ZERO);
+    let (fine_level, fine) = corner(Duration::from_secs(60));
+    assert_eq!((coarse_level, fine_level), (0, LEVELS.len() - 1));
+    assert_ne!(fine, Color::black());
+    for (a, b) in [(coarse.red(), fine.red()), (coarse.green(), fine.green()), (coarse.blue(), fine.blue())] {
+        assert!(a.abs_diff(b) <= 4, "{:?} contra {:?}", coarse, fine);
+    }
+}
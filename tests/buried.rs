@@ -0,0 +1,72 @@
+use raycasting_diorama::color::Color;
+use raycasting_diorama::material::Material;
+use raycasting_diorama::object::SceneObject;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::{DioramaBuilder, Vec3};
+
+const SIDE: i32 = 32;
+// Hueco de 4x4x4 en el medio del bloque
+const CAVITY: std::ops::Range<i32> = 14..18;
+
+fn in_cavity([x, y, z]: [i32; 3]) -> bool {
+    CAVITY.contains(&x) && CAVITY.contains(&y) && CAVITY.contains(&z)
+}
+
+// Un bloque está enterrado si sus seis vecinos por cara son bloques
+fn enclosed(block: [i32; 3]) -> bool {
+    (0..3).all(|axis| {
+        [-1, 1].into_iter().all(|step| {
+            let mut neighbor = block;
+            neighbor[axis] += step;
+            neighbor.iter().all(|&value| (0..SIDE).contains(&value)) && !in_cavity(neighbor)
+        })
+    })
+}
+
+fn block_of(object: &SceneObject) -> [i32; 3] {
+    let min = object.bounds().min;
+    [min.x, min.y, min.z].map(|value| value.round() as i32)
+}
+
+// Bloque macizo de 32x32x32 cubos de lado 1 con un hueco adentro, como una
+// construcción de vóxeles importada
+fn hollowed_block() -> Scene {
+    let stone = Material::new(Color::new(120, 120, 120), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0);
+    let mut builder = DioramaBuilder::new().material("piedra", stone);
+    for x in 0..SIDE {
+        for y in 0..SIDE {
+            for z in (0..SIDE).filter(|&z| !in_cavity([x, y, z])) {
+                let min = Vec3::new(x as f32, y as f32, z as f32);
+                builder = builder.add_cube(min, min + Vec3::repeat(1.0), "piedra");
+            }
+        }
+    }
+    builder.camera(Vec3::new(16.0, 16.0, 80.0), Vec3::repeat(16.0)).build().expect("el material está registrado")
+}
+
+#[test]
+fn a_hollowed_block_culls_only_the_enclosed_cubes() {
+    let mut scene = hollowed_block();
+    let total = scene.objects.len();
+    assert_eq!(total, 32 * 32 * 32 - 4 * 4 * 4);
+
+    // Adentro quedan 30³ bloques; los del hueco no están y los 6 x 4 x 4 que
+    // dan al hueco se ven desde ahí
+    let culled = scene.cull_buried();
+    assert_eq!(culled, 30 * 30 * 30 - 4 * 4 * 4 - 6 * 4 * 4);
+    assert!(culled * 4 > total * 3, "se esperaba sacar la gran mayoría, no {} de {}", culled, total);
+    assert_eq!(scene.objects.len() + scene.buried.len(), total);
+    assert!(scene.buried.iter().all(|object| enclosed(block_of(object))));
+    assert!(scene.objects.iter().all(|object| !enclosed(block_of(object))));
+}
+
+#[test]
+fn removing_a_surface_cube_exposes_the_one_behind_it() {
+    let mut scene = hollowed_block();
+    scene.cull_buried();
+    let buried = scene.buried.len();
+    let surface = scene.objects.iter().position(|object| block_of(object) == [16, 16, 31]).expect("está en la cara de adelante");
+    scene.remove_object(surface);
+    assert_eq!(scene.buried.len(), buried - 1);
+    assert!(scene.objects.iter().any(|object| block_of(object) == [16, 16, 30]));
+}
@@ -0,0 +1,199 @@
+use std::sync::Arc;
+
+use raycasting_diorama::bvh::{self, Aabb, Bvh, TraceBvh};
+use raycasting_diorama::cube::Cube;
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::material::Material;
+use raycasting_diorama::object::SceneObject;
+use raycasting_diorama::query::SceneQuery;
+use raycasting_diorama::renderer::{render, sky_color};
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::{RenderSettings, ShadowQuality};
+use raycasting_diorama::Vec3;
+
+// Generador xorshift con semilla fija, así la secuencia de ediciones es siempre la misma
+struct Rng(u64);
+
+impl Rng {
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 << 13;
+        self.0 ^= self.0 >> 7;
+        self.0 ^= self.0 << 17;
+        self.0
+    }
+
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+
+    fn unit(&mut self) -> f32 {
+        (self.next() >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn point(&mut self, extent: f32) -> Vec3 {
+        Vec3::new(self.unit(), self.unit(), self.unit()) * 2.0 * extent - Vec3::repeat(extent)
+    }
+
+    fn cube(&mut self) -> SceneObject {
+        let min = self.point(10.0);
+        let size = Vec3::new(self.unit(), self.unit(), self.unit()) * 1.5 + Vec3::repeat(0.1);
+        Cube::new(min, min + size, &Material::builder().build()).into()
+    }
+
+    // Rayo desde afuera de la nube de cubos hacia un punto de adentro
+    fn ray(&mut self) -> (Vec3, Vec3) {
+        let origin = self.point(1.0).normalize() * 30.0;
+        (origin, (self.point(9.0) - origin).normalize())
+    }
+}
+
+// Distancia e índice del impacto más cercano de cada rayo
+fn hits(closest: impl Fn(&Vec3, &Vec3) -> Option<(f32, usize)>, rays: &[(Vec3, Vec3)]) -> Vec<Option<(f32, usize)>> {
+    rays.iter().map(|(origin, direction)| closest(origin, direction)).collect()
+}
+
+// Renderiza la escena recorriendo la lista entera y con el árbol
+fn render_both(scene: &Scene, settings: RenderSettings) -> (Framebuffer, Framebuffer) {
+    let sky = sky_color(&scene.lights);
+    let mut settings = RenderSettings { bias: scene.default_bias(), ..settings };
+    let mut linear = Framebuffer::new(160, 120);
+    render(&mut linear, &scene.objects, &scene.camera, &scene.lights, sky, &settings);
+
+    bvh::refresh(&mut settings.bvh, &scene.objects);
+    assert!(settings.bvh.is_some());
+    let mut traced = Framebuffer::new(160, 120);
+    render(&mut traced, &scene.objects, &scene.camera, &scene.lights, sky, &settings);
+    (linear, traced)
+}
+
+fn assert_identical(linear: &Framebuffer, traced: &Framebuffer) {
+    assert!(linear.buffer == traced.buffer, "los colores cambian con el árbol");
+    assert_eq!(linear.alpha, traced.alpha);
+    assert_eq!(linear.depth, traced.depth);
+}
+
+#[test]
+fn diorama_renders_the_same() {
+    let (linear, traced) = render_both(&Scene::diorama(), RenderSettings::default());
+    assert_identical(&linear, &traced);
+}
+
+#[test]
+fn soft_shadows_and_near_cut_render_the_same() {
+    let settings = RenderSettings {
+        shadow_quality: ShadowQuality::SdfSoft { k: 8.0 },
+        near: 2.5,
+        ..RenderSettings::default()
+    };
+    let (linear, traced) = render_both(&Scene::diorama(), settings);
+    assert_identical(&linear, &traced);
+}
+
+#[test]
+fn unbounded_planes_are_still_hit() {
+    let scene = Scene::load("scenes/ground_plane.ron").expect("la escena de ejemplo carga");
+    let (linear, traced) = render_both(&scene, RenderSettings::default());
+    assert_identical(&linear, &traced);
+    // La fila de abajo mira hacia el suelo, que no está en el árbol
+    let bottom = &traced.depth[traced.depth.len() - traced.width..];
+    assert!(bottom.iter().all(|depth| depth.is_finite()));
+}
+
+#[test]
+fn edited_tree_hits_the_same_as_a_fresh_build() {
+    let mut rng = Rng(0x5eed_1234_abcd_0042);
+    let mut objects: Vec<SceneObject> = (0..150).map(|_| rng.cube()).collect();
+    let mut edited = Bvh::build(&objects);
+    let rays: Vec<_> = (0..64).map(|_| rng.ray()).collect();
+
+    for step in 0..400 {
+        match rng.below(3) {
+            0 => {
+                let object = rng.cube();
+                edited.insert(Aabb::of(&object));
+                objects.push(object);
+            }
+            1 if !objects.is_empty() => {
+                let index = rng.below(objects.len());
+                objects[index] = rng.cube();
+                edited.refit(index, Aabb::of(&objects[index]));
+            }
+            _ if !objects.is_empty() => {
+                let index = rng.below(objects.len());
+                objects.remove(index);
+                edited.remove(index);
+            }
+            _ => {}
+        }
+        if edited.needs_rebuild() {
+            edited = Bvh::build(&objects);
+        }
+        assert_eq!(edited.len(), objects.len());
+
+        if step % 20 == 0 {
+            let fresh = Bvh::build(&objects);
+            let closest = |bvh: &Bvh| {
+                hits(|origin, direction| bvh.closest_hit(&objects, origin, direction, 0.0, true).map(|(hit, index)| (hit.distance, index)), &rays)
+            };
+            let expected = closest(&fresh);
+            assert!(expected.iter().filter(|hit| hit.is_some()).count() > 8, "pocos rayos impactan");
+            assert_eq!(closest(&edited), expected, "paso {}", step);
+        }
+    }
+}
+
+#[test]
+fn scene_edits_keep_the_tree_the_renderer_uses() {
+    let mut rng = Rng(0x0bad_cafe_0000_0007);
+    let mut scene = Scene::diorama();
+    let rays: Vec<_> = (0..64).map(|_| rng.ray()).collect();
+    let closest = |bvh: &TraceBvh, objects: &[SceneObject]| {
+        hits(|origin, direction| bvh.closest_hit(objects, origin, direction, 0.0, true).map(|(hit, index)| (hit.distance, index)), &rays)
+    };
+
+    for _ in 0..200 {
+        match rng.below(3) {
+            0 => {
+                scene.add_object(rng.cube());
+            }
+            1 => {
+                let index = rng.below(scene.objects.len());
+                let min = rng.point(10.0);
+                scene.move_object(index, min, min + Vec3::repeat(0.5));
+            }
+            _ => {
+                let index = rng.below(scene.objects.len());
+                scene.remove_object(index);
+            }
+        }
+        let maintained = scene.bvh();
+        assert!(maintained.is_current(&scene.objects));
+        // Mientras no haya otra edición se entrega siempre el mismo árbol
+        assert!(Arc::ptr_eq(&maintained, &scene.bvh()));
+        assert_eq!(closest(&maintained, &scene.objects), closest(&TraceBvh::new(&scene.objects), &scene.objects));
+    }
+}
+
+#[test]
+fn a_stale_tree_is_not_used_for_moved_objects() {
+    // Un cubo del diorama pasa a tapar el centro de la vista sin que cambie la
+    // cantidad de objetos: el árbol de antes ya no sirve y no se usa
+    let scene = Scene::diorama();
+    let sky = sky_color(&scene.lights);
+    let mut settings = RenderSettings { bias: scene.default_bias(), ..RenderSettings::default() };
+    let mut objects = scene.objects.clone();
+    bvh::refresh(&mut settings.bvh, &objects);
+    let center = scene.camera.eye + (scene.camera.center - scene.camera.eye) * 0.5;
+    objects[0] = Cube::new(center - Vec3::repeat(0.2), center + Vec3::repeat(0.2), &Material::builder().build()).into();
+    assert!(!settings.bvh.as_ref().expect("se construyó").is_current(&objects));
+
+    let mut stale = Framebuffer::new(160, 120);
+    render(&mut stale, &objects, &scene.camera, &scene.lights, sky, &settings);
+    let mut linear = Framebuffer::new(160, 120);
+    render(&mut linear, &objects, &scene.camera, &scene.lights, sky, &RenderSettings { bvh: None, ..settings.clone() });
+    assert_identical(&linear, &stale);
+
+    let query = SceneQuery::with_settings(&objects, &settings);
+    let (origin, direction) = (scene.camera.eye, (scene.camera.center - scene.camera.eye).normalize());
+    assert_eq!(query.raycast(&origin, &direction).map(|hit| hit.object), Some(0));
+}
@@ -0,0 +1,165 @@
+use raycasting_diorama::camera::{Camera, Projection, MAX_FOV, MIN_FOV};
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::renderer::{render, sky_color};
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::{DioramaBuilder, Vec3};
+
+const MIN_DISTANCE: f32 = 0.5;
+const MAX_DISTANCE: f32 = 20.0;
+
+fn camera() -> Camera {
+    Camera::new(Vec3::new(2.0, 1.5, 4.0), Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, 1.0, 0.0))
+}
+
+fn distance(camera: &Camera) -> f32 {
+    (camera.eye - camera.center).magnitude()
+}
+
+#[test]
+fn repeated_zoom_in_stops_at_the_minimum() {
+    let mut camera = camera();
+    let (_, _, forward) = camera.basis();
+    for factor in [0.99, 0.9, 0.5, 0.0] {
+        for _ in 0..1000 {
+            camera.zoom(factor, MIN_DISTANCE, MAX_DISTANCE);
+            assert!(distance(&camera) >= MIN_DISTANCE - 1e-5, "la distancia bajó a {}", distance(&camera));
+        }
+    }
+    // El ojo queda del mismo lado del centro, mirando hacia el mismo lado
+    assert!((distance(&camera) - MIN_DISTANCE).abs() < 1e-5);
+    assert!(camera.basis().2.dot(&forward) > 0.9999);
+}
+
+#[test]
+fn repeated_zoom_out_stops_at_the_maximum() {
+    let mut camera = camera();
+    let (_, _, forward) = camera.basis();
+    for _ in 0..1000 {
+        camera.zoom(1.1, MIN_DISTANCE, MAX_DISTANCE);
+        assert!(distance(&camera) <= MAX_DISTANCE + 1e-4);
+    }
+    assert!((distance(&camera) - MAX_DISTANCE).abs() < 1e-4);
+    assert!(camera.basis().2.dot(&forward) > 0.9999);
+}
+
+#[test]
+fn zoom_steps_scale_with_the_distance() {
+    // El mismo factor avanza lo mismo en proporción de cerca y de lejos
+    let mut near = camera();
+    near.zoom(0.2, MIN_DISTANCE, MAX_DISTANCE);
+    let mut far = camera();
+    for camera in [&mut near, &mut far] {
+        let before = distance(camera);
+        camera.zoom(0.9, MIN_DISTANCE, MAX_DISTANCE);
+        assert!((distance(camera) / before - 0.9).abs() < 1e-5);
+    }
+}
+
+#[test]
+fn zoom_range_covers_the_scene_camera() {
+    let scene = Scene::diorama();
+    let (min, max) = scene.zoom_range();
+    let start = distance(&scene.camera);
+    assert!(min < start && start < max, "{} fuera de {}..{}", start, min, max);
+}
+
+#[test]
+fn turning_keeps_the_eye_and_stops_short_of_the_poles() {
+    let mut camera = camera();
+    let (eye, start) = (camera.eye, distance(&camera));
+    let (right, _, _) = camera.basis();
+    camera.rotate(0.1, 0.0);
+    // Un giro positivo lleva la vista hacia la derecha
+    assert!((camera.center - eye).dot(&right) > 0.0);
+    for _ in 0..100 {
+        camera.rotate(0.0, 0.3);
+    }
+    assert_eq!(camera.eye, eye);
+    assert!((distance(&camera) - start).abs() < 1e-4);
+    let (_, _, forward) = camera.basis();
+    assert!(forward.y < 1.0 && forward.y > 0.99, "la vista quedó en {:?}", forward);
+}
+
+#[test]
+fn moving_carries_the_center_along() {
+    let mut camera = camera();
+    let (right, _, forward) = camera.basis();
+    let (eye, view) = (camera.eye, camera.center - camera.eye);
+    camera.move_relative(Vec3::new(1.0, 0.5, 2.0));
+    let expected = eye + right + camera.up * 0.5 + forward * 2.0;
+    assert!((camera.eye - expected).magnitude() < 1e-5);
+    assert!((camera.center - camera.eye - view).magnitude() < 1e-5);
+}
+
+#[test]
+fn field_of_view_stays_within_limits() {
+    let mut camera = camera();
+    for _ in 0..100 {
+        camera.widen(0.1);
+    }
+    assert_eq!(camera.fov, MAX_FOV);
+    for _ in 0..100 {
+        camera.widen(-0.1);
+    }
+    assert_eq!(camera.fov, MIN_FOV);
+}
+
+#[test]
+fn image_corners_follow_the_aspect_ratio_at_any_field_of_view() {
+    // El borde derecho está a `aspecto · tan(fov / 2)` a cada unidad de
+    // distancia y el de arriba a `tan(fov / 2)`, también en los extremos
+    let aspect_ratio = 16.0 / 9.0;
+    for fov in [MIN_FOV, MAX_FOV] {
+        let camera = Camera { fov, ..camera() };
+        let (right, up, forward) = camera.basis();
+        let scale = camera.perspective_scale();
+        let corner = camera.eye + (forward + right * aspect_ratio * scale + up * scale) * 3.0;
+        let (x, y) = camera.project(&corner, aspect_ratio, scale).expect("la esquina está delante");
+        assert!((x - 1.0).abs() < 1e-4 && (y - 1.0).abs() < 1e-4, "({}, {}) con {} grados", x, y, fov.to_degrees());
+    }
+}
+
+#[test]
+fn orthographic_size_does_not_change_with_depth() {
+    let aspect_ratio = 4.0 / 3.0;
+    let camera = Camera { projection: Projection::Orthographic { half_height: 2.0 }, ..camera() };
+    let (right, up, forward) = camera.basis();
+    for depth in [0.5, 3.0, 40.0] {
+        let corner = camera.eye + forward * depth + right * aspect_ratio * 2.0 + up * 2.0;
+        let (x, y) = camera.project(&corner, aspect_ratio, camera.perspective_scale()).expect("la esquina está delante");
+        assert!((x - 1.0).abs() < 1e-4 && (y - 1.0).abs() < 1e-4, "({}, {}) a {} de distancia", x, y, depth);
+    }
+}
+
+#[test]
+fn orthographic_matches_the_perspective_framing_and_follows_the_zoom() {
+    let mut camera = camera();
+    let expected = distance(&camera) * camera.perspective_scale();
+    camera.projection = camera.matching_orthographic();
+    assert_eq!(camera.projection, Projection::Orthographic { half_height: expected });
+    // Acercarse a la mitad de la distancia muestra la mitad de la escena
+    camera.zoom(0.5, MIN_DISTANCE, MAX_DISTANCE);
+    let Projection::Orthographic { half_height } = camera.projection else {
+        panic!("el zoom cambió la proyección");
+    };
+    assert!((half_height - expected * 0.5).abs() < 1e-5);
+}
+
+#[test]
+fn orthographic_rays_reach_a_facing_wall_at_the_same_depth() {
+    // Una pared de frente a la cámara: en perspectiva los bordes quedan más
+    // lejos que el centro, en ortográfica todos los rayos recorren lo mismo
+    let mut scene = DioramaBuilder::new()
+        .add_cube(Vec3::new(-10.0, -10.0, -1.0), Vec3::new(10.0, 10.0, 0.0), "rock")
+        .camera(Vec3::new(0.0, 0.0, 4.0), Vec3::new(0.0, 0.0, 0.0))
+        .build()
+        .expect("la escena se arma");
+    scene.camera.projection = Projection::Orthographic { half_height: 3.0 };
+    let settings = RenderSettings { bias: scene.default_bias(), ..RenderSettings::default() };
+    let mut framebuffer = Framebuffer::new(40, 30);
+    render(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, sky_color(&scene.lights), &settings);
+    for depth in &framebuffer.depth {
+        assert!((depth - 4.0).abs() < 1e-4, "un rayo llegó a {}", depth);
+    }
+}
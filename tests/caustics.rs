@@ -0,0 +1,78 @@
+use raycasting_diorama::caustics::{pattern, water_depth};
+use raycasting_diorama::color::Color;
+use raycasting_diorama::light::SceneLight;
+use raycasting_diorama::material::Material;
+use raycasting_diorama::presets;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::{Caustics, RenderSettings};
+use raycasting_diorama::{DioramaBuilder, Renderer, Vec3};
+
+// Fondo de arena bajo un cubo de agua, visto desde arriba y de costado, con
+// el sol a la altura dada y opcionalmente un techo opaco bajo que lo tapa
+fn pool(caustics: bool, sun_height: f32, roof: bool) -> Scene {
+    let mut water = presets::water();
+    water.caustics = caustics;
+    let builder = DioramaBuilder::new()
+        .material("sand", Material::new(Color::new(214, 196, 140), 10.0, [0.9, 0.0, 0.0, 0.0], 1.0))
+        .material("water", water)
+        .material("roof", Material::new(Color::new(90, 90, 90), 10.0, [0.9, 0.0, 0.0, 0.0], 1.0))
+        .add_cube(Vec3::new(-2.0, -1.2, -2.0), Vec3::new(2.0, -1.0, 2.0), "sand")
+        .add_cube(Vec3::new(-2.0, -1.0, -2.0), Vec3::new(2.0, 0.0, 2.0), "water")
+        .add_light(SceneLight::sun(Vec3::new(0.5, sun_height, 1.0), Color::white(), 1.5))
+        .camera(Vec3::new(0.0, 0.3, 2.2), Vec3::new(0.0, -1.0, 0.0));
+    let builder = if roof { builder.add_cube(Vec3::new(-6.0, 0.4, -6.0), Vec3::new(6.0, 0.6, 6.0), "roof") } else { builder };
+    builder.build().expect("los materiales están registrados")
+}
+
+fn render(scene: &Scene, time: f32) -> Vec<u8> {
+    let settings = RenderSettings { caustics: Some(Caustics { time, ..Caustics::default() }), ..RenderSettings::default() };
+    Renderer::new(settings).size(48, 36).render(scene, &scene.camera).into_raw()
+}
+
+#[test]
+fn the_pattern_keeps_the_light_on_average_and_moves() {
+    let mean = |time: f32, depth: f32| {
+        let samples: Vec<f32> = (0..10_000).map(|i| pattern((i % 100) as f32 * 0.021, (i / 100) as f32 * 0.019, time, depth)).collect();
+        let spread = samples.iter().fold(0.0f32, |spread, value| spread.max((value - 1.0).abs()));
+        (samples.iter().sum::<f32>() / samples.len() as f32, spread)
+    };
+    let (shallow, shallow_spread) = mean(0.0, 0.1);
+    let (deep, deep_spread) = mean(0.0, 3.0);
+    assert!((shallow - 1.0).abs() < 0.05 && (deep - 1.0).abs() < 0.05, "{} y {}", shallow, deep);
+    // Más hondo el dibujo se suaviza
+    assert!(deep_spread < shallow_spread, "{} y {}", deep_spread, shallow_spread);
+    // Con el tiempo de las olas las líneas se corren
+    let moved = (0..100).filter(|&i| (pattern(i as f32 * 0.05, 0.3, 0.0, 0.5) - pattern(i as f32 * 0.05, 0.3, 1.0, 0.5)).abs() > 0.1).count();
+    assert!(moved > 20, "solo cambiaron {}", moved);
+}
+
+#[test]
+fn the_depth_is_measured_from_inside_and_outside_the_water() {
+    let scene = Scene::load("scenes/water.ron").expect("la escena del repositorio carga");
+    let bias = scene.default_bias();
+    // El fondo hondo está dentro del cubo de agua, que llega a y = -0.05
+    let floor = water_depth(&Vec3::new(0.0, -1.2, 0.0), &scene.objects, None, bias).expect("el fondo está bajo el agua");
+    assert!((floor - 1.15).abs() < 1e-3, "{}", floor);
+    let step = water_depth(&Vec3::new(1.0, -0.3, 1.0), &scene.objects, None, bias).expect("el escalón está bajo el agua");
+    assert!((step - 0.25).abs() < 1e-3, "{}", step);
+    // El césped del borde no tiene agua encima
+    assert_eq!(water_depth(&Vec3::new(1.8, 0.0, 1.8), &scene.objects, None, bias), None);
+}
+
+#[test]
+fn caustics_light_the_floor_only_where_the_sun_reaches() {
+    // De día el fondo cambia, y cambia con el tiempo de las olas
+    let day = render(&pool(true, 6.0, false), 0.0);
+    assert_ne!(day, render(&pool(false, 6.0, false), 0.0));
+    assert_ne!(day, render(&pool(true, 6.0, false), 2.0));
+    // De noche no hay cáusticas
+    assert_eq!(render(&pool(true, -6.0, false), 0.0), render(&pool(false, -6.0, false), 0.0));
+    // Bajo un techo opaco casi no llega el sol y las líneas se pierden con
+    // él, mientras que a cielo abierto se notan
+    let largest_change = |roof: bool| {
+        let (with, without) = (render(&pool(true, 6.0, roof), 0.0), render(&pool(false, 6.0, roof), 0.0));
+        with.iter().zip(&without).map(|(a, b)| a.abs_diff(*b)).max().unwrap_or(0)
+    };
+    assert!(largest_change(false) > 20);
+    assert!(largest_change(true) <= 2, "{}", largest_change(true));
+}
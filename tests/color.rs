@@ -0,0 +1,58 @@
+use raycasting_diorama::color::{Color, LinearColor, Palette};
+
+#[test]
+fn every_channel_survives_the_round_trip() {
+    for value in 0..=255 {
+        let color = Color::new(value, 255 - value, value / 2);
+        assert_eq!(LinearColor::from_srgb(color).to_srgb(), color);
+        assert_eq!(LinearColor::from_raw(color).to_raw(), color);
+    }
+}
+
+#[test]
+fn the_srgb_curve_matches_known_values() {
+    let mid = LinearColor::from_srgb(Color::new(0, 128, 255));
+    assert_eq!(mid.r, 0.0);
+    assert!((mid.g - 0.2159).abs() < 1e-4, "{}", mid.g);
+    assert!((mid.b - 1.0).abs() < 1e-6, "{}", mid.b);
+    // Media luz lineal es un 188 guardado, no un 128
+    assert_eq!(LinearColor::new(0.5, 0.5, 0.5).to_srgb(), Color::new(188, 188, 188));
+    // Lo que pasa del blanco se satura al codificar
+    assert_eq!(LinearColor::new(1.7, -0.2, 0.0).to_srgb(), Color::new(255, 0, 0));
+}
+
+#[test]
+fn the_ramps_keep_their_published_endpoints_and_midpoints() {
+    let hex = Color::from_hex;
+    // Viridis tiene once paradas: la del medio cae justo en 0.5
+    assert_eq!(Palette::Viridis.sample(0.0), hex(0x440154));
+    assert_eq!(Palette::Viridis.sample(0.5), hex(0x21918c));
+    assert_eq!(Palette::Viridis.sample(1.0), hex(0xfde725));
+    // Cividis tiene dieciocho: el medio mezcla las paradas 8 y 9 por igual
+    assert_eq!(Palette::Cividis.sample(0.0), hex(0x00204c));
+    assert_eq!(Palette::Cividis.sample(0.5), Color::new(124, 123, 119));
+    assert_eq!(Palette::Cividis.sample(1.0), hex(0xffe945));
+    // Okabe-Ito no se mezcla: cada t elige el color más cercano
+    assert_eq!(Palette::OkabeIto.sample(0.0), hex(0xe69f00));
+    assert_eq!(Palette::OkabeIto.sample(0.5), hex(0xf0e442));
+    assert_eq!(Palette::OkabeIto.sample(1.0), hex(0xcc79a7));
+    // Lo que queda afuera del rango se recorta a las puntas
+    assert_eq!(Palette::Viridis.sample(-1.0), hex(0x440154));
+    assert_eq!(Palette::Cividis.sample(2.0), hex(0xffe945));
+    assert_eq!(Palette::Viridis.sample(f32::NAN), hex(0x440154));
+}
+
+#[test]
+fn categories_pick_fixed_colors() {
+    let fallback = Color::new(1, 2, 3);
+    assert_eq!(Palette::Default.category(4, fallback), fallback);
+    // Okabe-Ito en orden, y vuelve a empezar después del séptimo
+    assert_eq!(Palette::OkabeIto.category(0, fallback), Color::from_hex(0xe69f00));
+    assert_eq!(Palette::OkabeIto.category(4, fallback), Color::from_hex(0x0072b2));
+    assert_eq!(Palette::OkabeIto.category(7, fallback), Color::from_hex(0xe69f00));
+    // Las rampas arrancan en su primer cuarto y saltan a la mitad de lo que queda
+    assert_eq!(Palette::Viridis.category(0, fallback), Color::new(59, 82, 138));
+    assert_eq!(Palette::Viridis.category(1, fallback), Color::new(43, 174, 127));
+    assert_eq!(Palette::Cividis.category(0, fallback), Color::new(64, 77, 107));
+    assert_eq!(Palette::Viridis.category(7, fallback), Palette::Viridis.category(0, fallback));
+}
@@ -0,0 +1,59 @@
+use std::fs;
+use std::path::PathBuf;
+
+use raycasting_diorama::config::{load_settings, Settings};
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("diorama_config_{}_{}", std::process::id(), name));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).expect("se crea el directorio temporal");
+    dir
+}
+
+#[test]
+fn a_corrupt_file_is_set_aside_and_replaced_with_defaults() {
+    let dir = temp_dir("rotas");
+    let path = dir.join("settings.ron");
+    let broken = "(render: (render_scale: 0.5, exposure: ";
+    fs::write(&path, broken).expect("se escribe el archivo");
+
+    assert_eq!(load_settings(Some(&path), false), Settings::default());
+    // Lo que tenía queda aparte, tal cual, y en su lugar hay uno que se lee
+    assert_eq!(fs::read_to_string(dir.join("settings.ron.bad")).expect("se apartó"), broken);
+    assert_eq!(Settings::load(&path).expect("el archivo nuevo se lee"), Settings::default());
+
+    // Un segundo archivo roto no pisa al primero
+    fs::write(&path, "no es RON").expect("se escribe el archivo");
+    assert_eq!(load_settings(Some(&path), false), Settings::default());
+    assert_eq!(fs::read_to_string(dir.join("settings.ron.bad")).expect("sigue aparte"), broken);
+    assert_eq!(fs::read_to_string(dir.join("settings.ron.bad.1")).expect("se apartó"), "no es RON");
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn missing_fields_take_their_defaults() {
+    let dir = temp_dir("viejas");
+    let path = dir.join("settings.ron");
+    // Como lo habría escrito una versión anterior: sin la ventana, sin teclas y
+    // con un campo que esta versión ya no tiene
+    fs::write(&path, "(render: (exposure: 1.5, samples_per_pixel: 4), view: (show_gizmo: true), retired: 3)")
+        .expect("se escribe el archivo");
+
+    let loaded = Settings::load(&path).expect("el archivo se lee");
+    let defaults = Settings::default();
+    assert_eq!(loaded.render.exposure, 1.5);
+    assert_eq!(loaded.render.samples_per_pixel, 4);
+    assert!(loaded.view.show_gizmo);
+    assert_eq!(loaded.render.render_scale, defaults.render.render_scale);
+    assert_eq!(loaded.view.mouse_sensitivity, defaults.view.mouse_sensitivity);
+    assert_eq!(loaded.keys, defaults.keys);
+    assert_eq!(loaded.window, defaults.window);
+    assert_eq!(loaded.last_scene, None);
+
+    // Se lee sin apartarlo y al guardarlo queda completo
+    assert_eq!(load_settings(Some(&path), false), loaded);
+    assert!(!dir.join("settings.ron.bad").exists());
+    loaded.save(&path).expect("se guarda");
+    assert_eq!(Settings::load(&path).expect("el archivo se lee"), loaded);
+    fs::remove_dir_all(&dir).ok();
+}
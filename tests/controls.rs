@@ -0,0 +1,131 @@
+use std::fs;
+
+use raycasting_diorama::camera::Camera;
+use raycasting_diorama::color::{Color, Palette};
+use raycasting_diorama::controls::{apply_frame, crop_of, drag_corners, edit_lights, save_edited_lights, View};
+use raycasting_diorama::light::SceneLight;
+use raycasting_diorama::lightedit::LightEditor;
+use raycasting_diorama::scene::{lights_path, Scene};
+use raycasting_diorama::settings::{Crop, RenderSettings};
+use raycasting_diorama::timeline::{Action, DayClock, FrameRecord};
+use raycasting_diorama::Vec3;
+
+fn view() -> View {
+    View {
+        show_stats: false,
+        show_gizmo: false,
+        show_minimap: false,
+        show_motion: false,
+        load_report: vec!["Falta assets/grass.jpg".to_string()],
+        palette: Palette::Default,
+        light_editor: LightEditor::default(),
+        mouse_sensitivity: 0.01,
+        zoom_range: (0.5, 20.0),
+    }
+}
+
+fn camera() -> Camera {
+    Camera::new(Vec3::new(0.0, 0.0, 5.0), Vec3::zeros(), Vec3::new(0.0, 1.0, 0.0))
+}
+
+fn sun() -> SceneLight {
+    SceneLight::sun(Vec3::new(0.0, 3.0, 0.0), Color::white(), 1.0)
+}
+
+#[test]
+fn a_frame_changes_the_camera_the_view_and_the_settings() {
+    let (mut camera, mut view, mut settings) = (camera(), view(), RenderSettings::default());
+    let mut lights = vec![sun()];
+    let (depth, dawn) = (settings.max_depth, lights[0].time());
+    let frame = FrameRecord {
+        delta_time: 0.5,
+        held: vec![Action::ZoomIn],
+        pressed: vec![Action::ToggleStats, Action::ToggleGizmo, Action::DismissReport, Action::DepthUp],
+        ..FrameRecord::default()
+    };
+    apply_frame(&frame, &mut camera, &mut lights, &mut view, &mut settings, &mut None, &mut DayClock::new(false), false);
+
+    assert!(view.show_stats && view.show_gizmo && !view.show_minimap);
+    assert!(view.load_report.is_empty());
+    assert_eq!(settings.max_depth, depth + 1);
+    assert!((camera.eye - camera.center).magnitude() < 5.0, "W acerca la cámara");
+    // El sol sigue el ciclo: el primer frame lo muestra a la mitad de su paso
+    let shown = lights[0].time();
+    assert!((shown - dawn - 0.25).abs() < 1e-6, "{} después de {}", shown, dawn);
+
+    // En la demostración de órbita el sol queda quieto
+    apply_frame(&frame, &mut camera, &mut lights, &mut view, &mut settings, &mut None, &mut DayClock::new(false), true);
+    assert_eq!(lights[0].time(), shown);
+    assert!(!view.show_stats, "la misma tecla lo vuelve a ocultar");
+}
+
+#[test]
+fn editing_moves_the_selected_light_and_undoes() {
+    let (camera, mut editor) = (camera(), LightEditor::default());
+    let mut lights = vec![sun(), SceneLight::new(Vec3::new(1.0, 1.0, 1.0), Color::white(), 1.0)];
+    let press = |action| FrameRecord { pressed: vec![action], ..FrameRecord::default() };
+
+    // J entra al modo de edición y otra J pasa a la segunda luz
+    edit_lights(&press(Action::NextLight), &mut editor, &mut lights, &camera);
+    edit_lights(&press(Action::NextLight), &mut editor, &mut lights, &camera);
+    assert!(editor.active);
+    assert_eq!(editor.selected, 1);
+
+    let up = FrameRecord { held: vec![Action::LightUp], ..FrameRecord::default() };
+    edit_lights(&up, &mut editor, &mut lights, &camera);
+    assert!((lights[1].position.y - 1.02).abs() < 1e-6);
+    assert_eq!(lights[0].position, Vec3::new(0.0, 3.0, 0.0));
+    edit_lights(&press(Action::Undo), &mut editor, &mut lights, &camera);
+    assert_eq!(lights[1].position, Vec3::new(1.0, 1.0, 1.0));
+
+    // Fuera del modo de edición las flechas no mueven luces
+    assert!(edit_lights(&press(Action::ToggleLightEdit), &mut editor, &mut lights, &camera), "salir avisa que hubo cambios");
+    assert!(!edit_lights(&up, &mut editor, &mut lights, &camera));
+    assert_eq!(lights[1].position, Vec3::new(1.0, 1.0, 1.0));
+}
+
+#[test]
+fn edited_lights_are_saved_next_to_the_scene() {
+    let dir = std::env::temp_dir().join(format!("diorama_luces_{}", std::process::id()));
+    fs::create_dir_all(&dir).expect("se crea el directorio temporal");
+    let scene_path = dir.join("escena.ron").to_string_lossy().into_owned();
+    // Con Z arriba en el archivo: las luces guardadas son las del mundo ya
+    // cargado, con Y arriba, y no se vuelven a rotar
+    let source = r#"(up_axis: Z, lights: [(position: (0.0, 0.0, 3.0), color: (255, 255, 255), intensity: 1.0)], objects: [])"#;
+    fs::write(&scene_path, source).expect("se escribe la escena");
+    let mut lights = Scene::load(&scene_path).expect("la escena carga").lights;
+    assert_eq!(lights[0].position, Vec3::new(0.0, 3.0, 0.0));
+
+    // Sube la luz y agrega otra en la cámara; al salir se guardan
+    let (camera, mut editor) = (camera(), LightEditor::default());
+    let press = |action| FrameRecord { pressed: vec![action], ..FrameRecord::default() };
+    edit_lights(&press(Action::ToggleLightEdit), &mut editor, &mut lights, &camera);
+    edit_lights(&FrameRecord { held: vec![Action::LightUp], ..FrameRecord::default() }, &mut editor, &mut lights, &camera);
+    edit_lights(&press(Action::AddLight), &mut editor, &mut lights, &camera);
+    assert!(edit_lights(&press(Action::ToggleLightEdit), &mut editor, &mut lights, &camera));
+    save_edited_lights(Some(&scene_path), &lights);
+
+    let loaded = Scene::load(&scene_path).expect("la escena carga").lights;
+    assert_eq!(loaded.len(), 2);
+    for (loaded, edited) in loaded.iter().zip(&lights) {
+        assert_eq!((loaded.position, loaded.target, loaded.kind), (edited.position, edited.target, edited.kind));
+        assert_eq!((loaded.color, loaded.intensity), (edited.color, edited.intensity));
+    }
+    assert_eq!(fs::read_to_string(&scene_path).expect("se lee la escena"), source, "el archivo de la escena no se toca");
+
+    // Un archivo de luces roto deja las de la escena y se avisa
+    fs::write(lights_path(&scene_path), "(lights: [").expect("se escribe el archivo de luces");
+    let (scene, report) = Scene::load_with_report(&scene_path).expect("la escena carga igual");
+    assert_eq!(scene.lights[0].position, Vec3::new(0.0, 3.0, 0.0));
+    assert_eq!(report.warnings.len(), 1, "{:?}", report.warnings);
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn a_drag_becomes_a_crop_in_pixels() {
+    let corners = drag_corners((0.75, 0.5), (0.25, 0.1));
+    assert_eq!(corners, [0.25, 0.1, 0.75, 0.5]);
+    assert_eq!(crop_of(corners, 400, 300), Some(Crop { x: 100, y: 30, width: 200, height: 120 }));
+    // Un arrastre que no llega a cubrir un píxel no recorta nada
+    assert_eq!(crop_of(drag_corners((0.5, 0.5), (0.5, 0.9)), 400, 300), None);
+}
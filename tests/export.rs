@@ -0,0 +1,76 @@
+use std::fs;
+use std::path::PathBuf;
+use std::time::{Duration, UNIX_EPOCH};
+
+use raycasting_diorama::cli;
+use raycasting_diorama::export::{export_gltf, export_rail, timestamp};
+use raycasting_diorama::rail::CameraRail;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+
+// Un bloque con luz y un recorrido de cámara de un segundo
+const SCENE: &str = r#"(
+    rail: [
+        (time: 0.0, eye: (0.0, 1.0, 4.0), center: (0.0, 0.0, 0.0)),
+        (time: 1.0, eye: (4.0, 1.0, 0.0), center: (0.0, 0.0, 0.0)),
+    ],
+    materials: {"piedra": (color: (150, 140, 130), shininess: 10.0, properties: (0.9, 0.1, 0.0, 0.0))},
+    lights: [(position: (2.0, 4.0, 3.0), color: (255, 255, 255), intensity: 1.0)],
+    objects: [(min: (-0.5, -0.5, -0.5), max: (0.5, 0.5, 0.5), material: "piedra")],
+)"#;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("diorama_export_{}_{}", std::process::id(), name));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).expect("se crea el directorio temporal");
+    dir
+}
+
+#[test]
+fn timestamps_are_utc_civil_dates() {
+    assert_eq!(timestamp(UNIX_EPOCH), "1970-01-01_00-00-00");
+    // Un 29 de febrero de año bisiesto por siglo
+    assert_eq!(timestamp(UNIX_EPOCH + Duration::from_secs(951_786_061)), "2000-02-29_01-01-01");
+    assert_eq!(timestamp(UNIX_EPOCH + Duration::from_secs(1_704_067_199)), "2023-12-31_23-59-59");
+}
+
+#[test]
+fn the_model_is_written_as_binary_gltf() {
+    let dir = temp_dir("modelo");
+    let path = dir.join("bloque.glb");
+    let scene = Scene::from_ron(SCENE).expect("la escena es válida");
+    assert!(export_gltf(&path.to_string_lossy(), &scene, &scene.lights, &RenderSettings::default()));
+    let bytes = fs::read(&path).expect("se escribió el modelo");
+    assert_eq!(&bytes[..4], b"glTF");
+
+    // Una carpeta que no existe no se crea
+    assert!(!export_gltf(&dir.join("no/existe.glb").to_string_lossy(), &scene, &scene.lights, &RenderSettings::default()));
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn the_rail_is_rendered_frame_by_frame() {
+    let dir = temp_dir("recorrido");
+    let frames = dir.join("cuadros");
+    let flags = ["--rail-frames", &frames.to_string_lossy(), "--width", "16", "--height", "12", "--fps", "2"];
+    let args = cli::parse(flags.iter().map(|flag| flag.to_string())).expect("los argumentos son válidos");
+
+    let mut scene = Scene::from_ron(SCENE).expect("la escena es válida");
+    let mut lights = scene.lights.clone();
+    assert!(export_rail(&frames.to_string_lossy(), &mut scene, &mut lights, &mut RenderSettings::default(), &args));
+    let mut names: Vec<_> = fs::read_dir(&frames)
+        .expect("se creó la carpeta")
+        .map(|entry| entry.expect("se lee la carpeta").file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    // Un segundo a dos cuadros por segundo, con el último incluido
+    assert_eq!(names, ["cuadro_0000.png", "cuadro_0001.png", "cuadro_0002.png"]);
+    let first = image::open(frames.join("cuadro_0000.png")).expect("es un PNG");
+    assert_eq!((first.width(), first.height()), (16, 12));
+
+    // Sin recorrido no hay nada que exportar
+    scene.rail = CameraRail::default();
+    assert!(!export_rail(&dir.join("nada").to_string_lossy(), &mut scene, &mut lights, &mut RenderSettings::default(), &args));
+    assert!(!dir.join("nada").exists());
+    fs::remove_dir_all(&dir).ok();
+}
@@ -0,0 +1,74 @@
+use raycasting_diorama::color::Color;
+use raycasting_diorama::framebuffer::{Framebuffer, PixelFormat};
+
+// Framebuffer de 3x2 con canales distintos en cada píxel y varios alfas
+fn sample() -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(3, 2);
+    framebuffer.buffer = vec![
+        Color::new(255, 0, 0),
+        Color::new(0, 255, 0),
+        Color::new(0, 0, 255),
+        Color::new(0x12, 0x34, 0x56),
+        Color::new(1, 2, 3),
+        Color::new(250, 128, 7),
+    ];
+    framebuffer.alpha = vec![255, 0, 128, 1, 254, 64];
+    framebuffer
+}
+
+#[test]
+fn every_format_round_trips_its_pixels() {
+    let framebuffer = sample();
+    for format in [PixelFormat::Argb32, PixelFormat::Rgba8, PixelFormat::Rgb8] {
+        let bytes = framebuffer.to_bytes(format);
+        assert_eq!(bytes.len(), 6 * format.bytes_per_pixel(), "{:?}", format);
+        let back = Framebuffer::from_pixels(3, 2, &bytes, format);
+        assert_eq!(back.buffer, framebuffer.buffer, "{:?}", format);
+        // Solo RGBA guarda la cobertura; los otros formatos vuelven opacos
+        let alpha = if format == PixelFormat::Rgba8 { framebuffer.alpha.clone() } else { vec![255; 6] };
+        assert_eq!(back.alpha, alpha, "{:?}", format);
+
+        for (&color, &alpha) in framebuffer.buffer.iter().zip(&framebuffer.alpha) {
+            let mut pixel = Vec::new();
+            format.encode(color, alpha, &mut pixel);
+            assert_eq!(pixel.len(), format.bytes_per_pixel());
+            assert_eq!(format.decode(&pixel).0, color, "{:?}", format);
+        }
+    }
+}
+
+#[test]
+fn byte_formats_are_in_channel_order() {
+    let framebuffer = sample();
+    let rgba = framebuffer.to_rgba_bytes();
+    assert_eq!(&rgba[..8], &[255, 0, 0, 255, 0, 255, 0, 0]);
+    assert_eq!(&rgba[12..16], &[0x12, 0x34, 0x56, 1]);
+    assert_eq!(&framebuffer.to_rgb_bytes()[9..12], &[0x12, 0x34, 0x56]);
+
+    // Los valores empaquetados son 0x00RRGGBB sea cual sea el orden de bytes
+    // de la máquina
+    assert_eq!(framebuffer.to_argb_u32()[3], 0x0012_3456);
+    let packed = framebuffer.to_bytes(PixelFormat::Argb32);
+    assert_eq!(u32::from_ne_bytes([packed[12], packed[13], packed[14], packed[15]]), 0x0012_3456);
+}
+
+#[test]
+fn rgba_png_keeps_the_channel_order() {
+    let framebuffer = sample();
+    let path = std::env::temp_dir().join(format!("diorama_rgba_{}.png", std::process::id()));
+    framebuffer.save_png_rgba(&path.to_string_lossy()).expect("se guarda el PNG");
+    let image = image::open(&path).expect("se lee el PNG").to_rgba8();
+    std::fs::remove_file(&path).ok();
+
+    assert_eq!(image.dimensions(), (3, 2));
+    assert_eq!(image.get_pixel(0, 0).0, [255, 0, 0, 255]);
+    assert_eq!(image.get_pixel(2, 0).0, [0, 0, 255, 128]);
+    assert_eq!(image.get_pixel(0, 1).0, [0x12, 0x34, 0x56, 1]);
+    assert_eq!(image.into_raw(), framebuffer.to_rgba_bytes());
+
+    let path = std::env::temp_dir().join(format!("diorama_rgb_{}.png", std::process::id()));
+    framebuffer.save_png(&path.to_string_lossy()).expect("se guarda el PNG");
+    let image = image::open(&path).expect("se lee el PNG").to_rgb8();
+    std::fs::remove_file(&path).ok();
+    assert_eq!(image.get_pixel(2, 1).0, [250, 128, 7]);
+}
@@ -0,0 +1,42 @@
+use raycasting_diorama::generate::GenParams;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::Renderer;
+
+// Lo que define una escena generada: cada objeto con su caja y su material,
+// las luces y la cámara, en texto para poder compararlo
+fn fingerprint(scene: &Scene) -> Vec<String> {
+    let objects = scene.objects.iter().map(|object| {
+        let bounds = object.bounds();
+        format!("{} {} {:?} {:?}", object.kind(), object.material().name, bounds.min.as_slice(), bounds.max.as_slice())
+    });
+    let lights = scene.lights.iter().map(|light| format!("luz {:?} {:?}", light.position.as_slice(), light.color));
+    let camera = format!("cámara {:?} {:?}", scene.camera.eye.as_slice(), scene.camera.center.as_slice());
+    objects.chain(lights).chain([camera]).collect()
+}
+
+#[test]
+fn the_same_seed_generates_the_same_scene() {
+    let params = GenParams { size: 12, ..GenParams::default() };
+    for seed in [0, 42, u64::MAX] {
+        let (first, second) = (Scene::generate(seed, &params), Scene::generate(seed, &params));
+        assert!(!first.objects.is_empty(), "semilla {}", seed);
+        assert_eq!(fingerprint(&first), fingerprint(&second), "semilla {}", seed);
+
+        let render = |scene: &Scene| Renderer::new(RenderSettings::default()).size(48, 36).render(scene, &scene.camera).into_raw();
+        assert!(render(&first) == render(&second), "semilla {}: los renders difieren", seed);
+    }
+}
+
+#[test]
+fn another_seed_generates_another_scene() {
+    let params = GenParams::default();
+    let scenes: Vec<Vec<String>> = (0..4).map(|seed| fingerprint(&Scene::generate(seed, &params))).collect();
+    for (a, first) in scenes.iter().enumerate() {
+        for (b, second) in scenes.iter().enumerate().skip(a + 1) {
+            assert_ne!(first, second, "las semillas {} y {} dan la misma escena", a, b);
+        }
+    }
+    // Los parámetros también cuentan: otro tamaño es otra base
+    assert_ne!(fingerprint(&Scene::generate(7, &GenParams { size: 10, ..params })), fingerprint(&Scene::generate(7, &params)));
+}
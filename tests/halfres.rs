@@ -0,0 +1,54 @@
+use raycasting_diorama::color::Color;
+use raycasting_diorama::light::{LightKind, SceneLight};
+use raycasting_diorama::material::Material;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::{DioramaBuilder, Renderer, Vec3};
+
+fn render(scene: &Scene, half_res: bool) -> Vec<u8> {
+    let settings = RenderSettings { half_res, ..RenderSettings::default() };
+    Renderer::new(settings).size(128, 96).render(scene, &scene.camera).into_raw()
+}
+
+// Error medio por canal y fracción de canales que se alejan más de `limit`
+fn error(reference: &[u8], image: &[u8], limit: u8) -> (f32, f32) {
+    let differences: Vec<u8> = reference.iter().zip(image).map(|(a, b)| a.abs_diff(*b)).collect();
+    let mean = differences.iter().map(|&d| d as f32).sum::<f32>() / differences.len() as f32;
+    let far = differences.iter().filter(|&&d| d > limit).count() as f32 / differences.len() as f32;
+    (mean, far)
+}
+
+// Piso que recibe la sombra dura de un bloque, delante de un espejo que
+// refleja los dos
+fn shadow_and_mirror() -> Scene {
+    let mut sun = SceneLight::new(Vec3::new(2.0, 4.0, 3.0), Color::white(), 1.2);
+    sun.kind = LightKind::Directional;
+    sun.target = Vec3::zeros();
+    DioramaBuilder::new()
+        .material("floor", Material::new(Color::new(200, 200, 190), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0))
+        .material("block", Material::new(Color::new(60, 110, 200), 30.0, [0.8, 0.3, 0.0, 0.0], 1.0))
+        .material("mirror", Material::new(Color::new(20, 20, 20), 80.0, [0.1, 0.5, 0.8, 0.0], 1.0))
+        .add_cube(Vec3::new(-2.0, -0.6, -2.0), Vec3::new(2.0, -0.5, 2.0), "floor")
+        .add_cube(Vec3::new(-0.5, -0.5, -0.5), Vec3::new(0.3, 0.5, 0.3), "block")
+        .add_cube(Vec3::new(-1.8, -0.5, -1.7), Vec3::new(1.8, 1.2, -1.6), "mirror")
+        .add_light(sun)
+        .camera(Vec3::new(0.8, 1.0, 3.2), Vec3::new(0.0, -0.1, 0.0))
+        .build()
+        .expect("los materiales están registrados")
+}
+
+#[test]
+fn half_resolution_stays_close_to_the_full_reference() {
+    // El render trazado entero es la imagen dorada de cada escena
+    let diorama = Scene::load("scenes/light_linking.ron").expect("la escena del repositorio carga");
+    for (name, scene) in [("sombra y espejo", shadow_and_mirror()), ("diorama", diorama)] {
+        let reference = render(&scene, false);
+        let half = render(&scene, true);
+        let (mean, far) = error(&reference, &half, 16);
+        // En promedio no se nota, y un borde de sombra o de reflejo borroneado
+        // se alejaría en muchos píxeles seguidos
+        assert!(mean <= 0.5, "{}: error medio {}", name, mean);
+        assert!(far <= 0.002, "{}: {} de los canales se alejan", name, far);
+        assert_eq!(render(&scene, true), half, "{}: el resultado no cambia de un frame al otro", name);
+    }
+}
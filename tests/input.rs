@@ -0,0 +1,51 @@
+use raycasting_diorama::input::{InputEvent, InputState};
+
+#[test]
+fn press_hold_release() {
+    let mut input = InputState::new();
+    assert_eq!(input.update(['a']), &[InputEvent::Pressed('a')]);
+    assert!(input.was_pressed('a') && input.is_down('a'));
+
+    assert_eq!(input.update(['a']), &[InputEvent::Held('a')]);
+    assert!(!input.was_pressed('a') && input.is_down('a'));
+
+    assert_eq!(input.update([]), &[InputEvent::Released('a')]);
+    assert!(input.was_released('a') && !input.is_down('a'));
+
+    assert!(input.update([]).is_empty());
+    assert!(!input.was_released('a'));
+}
+
+#[test]
+fn holding_does_not_repeat_the_press() {
+    let mut input = InputState::new();
+    let presses = (0..10).filter(|_| {
+        input.update(['f']);
+        input.was_pressed('f')
+    });
+    assert_eq!(presses.count(), 1);
+}
+
+#[test]
+fn pressing_again_after_release() {
+    let mut input = InputState::new();
+    input.update(['f']);
+    input.update([]);
+    input.update(['f']);
+    assert!(input.was_pressed('f'));
+}
+
+#[test]
+fn several_inputs_in_one_frame() {
+    let mut input = InputState::new();
+    input.update(['a', 'b']);
+    assert_eq!(input.update(['b', 'c']), &[InputEvent::Held('b'), InputEvent::Pressed('c'), InputEvent::Released('a')]);
+}
+
+#[test]
+fn repeated_inputs_count_once() {
+    // Dos teclas asignadas a la misma acción
+    let mut input = InputState::new();
+    assert_eq!(input.update(['a', 'a']), &[InputEvent::Pressed('a')]);
+    assert_eq!(input.update(['a']), &[InputEvent::Held('a')]);
+}
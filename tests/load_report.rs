@@ -0,0 +1,44 @@
+use std::sync::Arc;
+
+use raycasting_diorama::scene::Scene;
+
+const SCENE: &str = r#"(
+    materials: {
+        "piedra": (texture: Some("assets/no_existe.png")),
+        "otra": (texture: Some("assets/no_existe.png")),
+        "pasto": (texture: Some("assets/grass.jpg")),
+    },
+    objects: [
+        (min: (0.0, 0.0, 0.0), max: (1.0, 1.0, 1.0), material: "piedra"),
+        (min: (1.0, 0.0, 0.0), max: (2.0, 1.0, 1.0), material: "pasto"),
+    ],
+)"#;
+
+#[test]
+fn missing_textures_are_substituted_and_reported() {
+    let (scene, report) = Scene::from_ron_with_report(SCENE).expect("falta una textura, no la escena");
+    assert_eq!(scene.objects.len(), 2);
+    assert!(scene.materials["piedra"].texture.is_some(), "el damero ocupa el lugar de la textura");
+    assert_eq!(report.missing_textures, ["assets/no_existe.png"]);
+    assert_eq!(report.substituted_materials, ["otra", "piedra"]);
+    assert_eq!(report.warnings.len(), 2);
+    assert!(!report.is_clean());
+
+    let (_, report) = Scene::from_ron_with_report(&SCENE.replace("no_existe.png", "lava.jpg")).expect("la escena carga");
+    assert!(report.is_clean() && report.lines().is_empty());
+}
+
+#[test]
+fn materials_with_the_same_path_share_the_image() {
+    let scene = Scene::from_ron(&SCENE.replace("no_existe.png", "grass.jpg")).expect("la escena carga");
+    let texture = |name: &str| scene.materials[name].texture.clone().expect("tiene textura");
+    assert!(Arc::ptr_eq(&texture("piedra"), &texture("pasto")));
+    assert!(Arc::ptr_eq(&texture("otra"), &texture("pasto")));
+    assert!(scene.objects.iter().all(|object| object.material().texture.as_ref().is_some_and(|t| Arc::ptr_eq(t, &texture("pasto")))));
+
+    // Los cubos del diorama clonan el material sin copiar la textura
+    let diorama = Scene::diorama();
+    let obsidian = diorama.materials["obsidian"].texture.clone().expect("la obsidiana tiene textura");
+    let shared = diorama.objects.iter().filter(|object| object.material().texture.as_ref().is_some_and(|t| Arc::ptr_eq(t, &obsidian))).count();
+    assert!(shared > 1, "solo {} cubos comparten la obsidiana", shared);
+}
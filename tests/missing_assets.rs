@@ -0,0 +1,86 @@
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use raycasting_diorama::color::Color;
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::renderer::{render, sky_color};
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::texture::{Texture, TextureCache};
+
+fn render_small(scene: &Scene) -> Framebuffer {
+    let settings = RenderSettings { bias: scene.default_bias(), ..RenderSettings::default() };
+    let mut framebuffer = Framebuffer::new(80, 60);
+    render(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, sky_color(&scene.lights), &settings);
+    framebuffer
+}
+
+// El directorio de trabajo es de todo el proceso: los tests de este archivo se
+// turnan con este candado, y el que lo cambia lo vuelve a dejar como estaba
+// al terminar, aunque falle
+static SERIAL: Mutex<()> = Mutex::new(());
+
+struct WorkingDir(PathBuf);
+
+impl WorkingDir {
+    fn enter(dir: &Path) -> WorkingDir {
+        let previous = std::env::current_dir().expect("hay un directorio de trabajo");
+        std::env::set_current_dir(dir).expect("se entra al directorio");
+        WorkingDir(previous)
+    }
+}
+
+impl Drop for WorkingDir {
+    fn drop(&mut self) {
+        std::env::set_current_dir(&self.0).ok();
+    }
+}
+
+#[test]
+fn scenes_open_without_the_assets_folder() {
+    let _serial = SERIAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let portal = std::fs::read_to_string(concat!(env!("CARGO_MANIFEST_DIR"), "/scenes/portal.ron")).expect("la escena del repo");
+    let expected = render_small(&Scene::diorama());
+
+    let empty = std::env::temp_dir().join(format!("diorama_sin_assets_{}", std::process::id()));
+    std::fs::create_dir_all(empty.join("assets")).expect("se crea el directorio");
+    let working_dir = WorkingDir::enter(&empty);
+
+    // El diorama integrado lleva sus texturas en el binario
+    let framebuffer = render_small(&Scene::diorama());
+    assert!(framebuffer.buffer == expected.buffer, "el diorama cambia sin la carpeta assets");
+
+    // Un archivo de escena carga con el damero en lugar de cada imagen
+    let (scene, report) = Scene::from_ron_with_report(&portal).expect("faltan texturas, no la escena");
+    assert!(report.missing_textures.iter().any(|path| path.starts_with("assets/")));
+    assert!(!report.is_clean());
+    let framebuffer = render_small(&scene);
+    assert!(framebuffer.buffer.iter().any(|&pixel| pixel != framebuffer.buffer[0]));
+
+    drop(working_dir);
+    std::fs::remove_dir_all(&empty).ok();
+}
+
+#[test]
+fn the_cache_falls_back_to_the_checkerboard() {
+    let _serial = SERIAL.lock().unwrap_or_else(|poisoned| poisoned.into_inner());
+    let missing = std::env::temp_dir().join(format!("diorama_falta_{}.png", std::process::id()));
+    let path = missing.to_string_lossy().into_owned();
+    let mut cache = TextureCache::new();
+    let (placeholder, error) = cache.get_or_load(&path);
+    assert!(error.is_some());
+    let checkerboard = Texture::checkerboard(&path);
+    for (u, v) in [(0.05, 0.95), (0.2, 0.95), (0.6, 0.3)] {
+        assert_eq!(placeholder.get_color(u, v), checkerboard.get_color(u, v));
+    }
+    assert_eq!(placeholder.get_color(0.05, 0.95), Color::new(255, 0, 255));
+    assert_eq!(placeholder.get_color(0.2, 0.95), Color::new(0, 0, 0));
+    assert_eq!(placeholder.path(), path);
+
+    // El damero no queda guardado: cuando aparece el archivo se usa
+    image::RgbImage::from_pixel(2, 2, image::Rgb([10, 200, 30])).save(&missing).expect("se escribe la imagen");
+    let (found, error) = cache.get_or_load(&path);
+    std::fs::remove_file(&missing).ok();
+    assert!(error.is_none());
+    assert_eq!(found.get_color(0.5, 0.5), Color::new(10, 200, 30));
+}
@@ -0,0 +1,69 @@
+use raycasting_diorama::camera::Camera;
+use raycasting_diorama::color::{linear_to_srgb, srgb_to_linear, Color};
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::material::Material;
+use raycasting_diorama::motion::{render_motion_blur, FrameMotion};
+use raycasting_diorama::renderer::{render, sky_color};
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::texture::Texture;
+use raycasting_diorama::{DioramaBuilder, Vec3};
+
+const WIDTH: usize = 40;
+const HEIGHT: usize = 30;
+
+// Cubo blanco sin iluminar a la derecha del origen, sin luces
+fn white_cube() -> Scene {
+    let white = Material { unlit: true, ..Material::new(Color::white(), 1.0, [1.0, 0.0, 0.0, 0.0], 1.0) };
+    DioramaBuilder::new()
+        .material("blanco", white)
+        .add_cube(Vec3::new(0.2, -1.0, -1.0), Vec3::new(3.0, 1.0, 1.0), "blanco")
+        .camera(Vec3::new(0.0, 0.0, 4.0), Vec3::zeros())
+        .build()
+        .expect("el material está registrado")
+}
+
+// Fondo negro liso
+fn black_background() -> Texture {
+    let mut png = Vec::new();
+    image::RgbImage::from_pixel(4, 4, image::Rgb([0, 0, 0]))
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .expect("se codifica en memoria");
+    Texture::from_bytes(&png, "negro.png").expect("es un PNG válido")
+}
+
+// Cámara que mira de frente con el ojo en `x`
+fn camera_at(x: f32) -> Camera {
+    Camera::new(Vec3::new(x, 0.0, 4.0), Vec3::new(x, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0))
+}
+
+fn center(framebuffer: &Framebuffer) -> Color {
+    framebuffer.get_pixel(WIDTH / 2, HEIGHT / 2)
+}
+
+#[test]
+fn the_streak_is_the_linear_average_of_its_instants() {
+    let scene = white_cube();
+    let settings = RenderSettings { background: Some(black_background()), ..RenderSettings::default() };
+    let (start, end) = (camera_at(-2.0), camera_at(2.0));
+    let still = |camera: &Camera| {
+        let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+        render(&mut framebuffer, &scene.objects, camera, &scene.lights, sky_color(&scene.lights), &settings);
+        center(&framebuffer)
+    };
+    let (white, black) = (still(&end), still(&start));
+    assert_eq!(black, Color::black(), "al principio el centro ve el fondo");
+    assert!(white.red() > 200, "al final el centro ve el cubo, no {:?}", white);
+
+    // Con dos muestras y el obturador abierto todo el frame los instantes
+    // caen en 0.75 y 0.44: el ojo en x = 1 ve el cubo y en x = -0.24 el fondo
+    let motion = FrameMotion::new((&start, &scene.lights), (&end, &scene.lights));
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    render_motion_blur(&mut framebuffer, &scene.objects, &motion, 1.0, 2, &settings);
+    let blurred = center(&framebuffer);
+
+    let linear = linear_to_srgb((srgb_to_linear(white.red()) + srgb_to_linear(black.red())) / 2.0);
+    let bytes = ((white.red() as u16 + black.red() as u16) / 2) as u8;
+    assert!(blurred.red().abs_diff(linear) <= 1, "se esperaba el promedio lineal {}, no {}", linear, blurred.red());
+    assert!(blurred.red().abs_diff(bytes) > 20, "{} es el promedio de los bytes", blurred.red());
+}
@@ -0,0 +1,45 @@
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::offline::{OfflineRender, OfflineSettings};
+use raycasting_diorama::renderer::{render, sky_color};
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+
+const WIDTH: usize = 120;
+const HEIGHT: usize = 90;
+
+fn offline_settings(samples: u32) -> OfflineSettings {
+    OfflineSettings { width: WIDTH, height: HEIGHT, samples: Some(samples), max_depth: None }
+}
+
+#[test]
+fn bands_match_a_single_render() {
+    let scene = Scene::diorama();
+    let settings = RenderSettings { bias: scene.default_bias(), ..RenderSettings::default() };
+    let mut expected = Framebuffer::new(WIDTH, HEIGHT);
+    render(&mut expected, &scene.objects, &scene.camera, &scene.lights, sky_color(&scene.lights), &settings);
+
+    // Franjas de siete filas, que no dividen el alto: la última queda corta.
+    // El halo de la lava cruza los bordes de las franjas
+    let mut shot = OfflineRender::new(&scene.objects, &scene.camera, &scene.lights, &settings, &offline_settings(1));
+    let mut steps = 0;
+    while !shot.step(WIDTH * 7) {
+        steps += 1;
+        assert!(shot.progress() < 1.0);
+    }
+    assert_eq!(steps, HEIGHT.div_ceil(7) - 1);
+    assert_eq!(shot.progress(), 1.0);
+    assert!(shot.framebuffer.buffer == expected.buffer, "las franjas no coinciden con el render entero");
+}
+
+#[test]
+fn samples_count_against_the_rays_per_step() {
+    let scene = Scene::diorama();
+    let settings = RenderSettings { bias: scene.default_bias(), ..RenderSettings::default() };
+    let mut shot = OfflineRender::new(&scene.objects, &scene.camera, &scene.lights, &settings, &offline_settings(4));
+    // Con cuatro muestras por píxel los rayos de diez filas alcanzan para dos y media
+    shot.step(WIDTH * 10);
+    assert_eq!(shot.progress(), 2.0 / HEIGHT as f32);
+    // Siempre avanza al menos una fila
+    shot.step(1);
+    assert_eq!(shot.progress(), 3.0 / HEIGHT as f32);
+}
@@ -0,0 +1,69 @@
+use raycasting_diorama::camera::Camera;
+use raycasting_diorama::color::Color;
+use raycasting_diorama::cube::Cube;
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::light::SceneLight;
+use raycasting_diorama::material::Material;
+use raycasting_diorama::renderer::{probe_pixel, render, sky_color, PlanarReflection};
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::DioramaBuilder;
+use raycasting_diorama::Vec3;
+
+fn mirror() -> Material {
+    let mut mirror = Material::new(Color::new(30, 30, 40), 50.0, [0.6, 0.3, 0.15, 0.0], 1.0);
+    mirror.planar_reflection = true;
+    mirror
+}
+
+#[test]
+fn planar_and_traced_reflections_use_the_same_weight() {
+    // Un piso espejado con dos cubos de colores encima, visto en diagonal
+    let scene = DioramaBuilder::new()
+        .material("mirror", mirror())
+        .material("red", Material::new(Color::new(200, 40, 40), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0))
+        .material("green", Material::new(Color::new(40, 180, 60), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0))
+        .add_cube(Vec3::new(-3.0, -0.1, -3.0), Vec3::new(3.0, 0.0, 3.0), "mirror")
+        .add_cube(Vec3::new(-1.0, 0.0, -1.0), Vec3::new(0.0, 1.0, 0.0), "red")
+        .add_cube(Vec3::new(0.5, 0.0, -0.5), Vec3::new(1.2, 0.6, 0.2), "green")
+        .add_light(SceneLight::sun(Vec3::new(0.4, 3.0, 1.0), Color::white(), 1.0))
+        .camera(Vec3::new(0.0, 0.6, 3.0), Vec3::new(0.0, 0.2, 0.0))
+        .build()
+        .expect("los materiales están registrados");
+    let settings = RenderSettings::default();
+    let mut framebuffer = Framebuffer::new(64, 48);
+    render(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, sky_color(&scene.lights), &settings);
+
+    // La sonda traza el reflejo en lugar de muestrear el búfer; donde el
+    // espejo refleja el cielo la resolución del búfer no se nota y, con el
+    // mismo peso en los dos caminos, el color coincide
+    let mut largest = 0;
+    for (x, y) in [(4, 40), (20, 44), (44, 38), (10, 46), (56, 42)] {
+        let path = probe_pixel(x, y, &framebuffer, &scene.objects, &scene.camera, &scene.lights, &settings);
+        let hex = path.log.last().and_then(|line| line.strip_prefix("color final #")).expect("la sonda anota el color final");
+        let traced = u32::from_str_radix(hex, 16).expect("el color está en hexadecimal");
+        let planar = framebuffer.get_pixel(x, y);
+        for (channel, shift) in [(planar.red(), 16), (planar.green(), 8), (planar.blue(), 0)] {
+            largest = largest.max(channel.abs_diff((traced >> shift) as u8));
+        }
+    }
+    assert!(largest <= 2, "el reflejo planar y el trazado difieren en {}", largest);
+}
+
+#[test]
+fn mirrors_outside_the_image_are_not_rendered() {
+    let slab = Cube::new(Vec3::new(-1.0, -0.1, -1.0), Vec3::new(1.0, 0.0, 1.0), &mirror());
+    let aspect_ratio = 4.0 / 3.0;
+    let seen_from = |eye: Vec3, center: Vec3| {
+        let camera = Camera::new(eye, center, Vec3::new(0.0, 1.0, 0.0));
+        PlanarReflection::mirror_plane(&slab, &camera, aspect_ratio, camera.perspective_scale())
+    };
+
+    // Mirando la placa desde arriba y desde abajo se refleja en la cara cercana
+    assert_eq!(seen_from(Vec3::new(0.0, 2.0, 3.0), Vec3::zeros()), Some((1, 0.0)));
+    assert_eq!(seen_from(Vec3::new(0.0, -2.0, 3.0), Vec3::zeros()), Some((1, -0.1)));
+    // Mirando hacia arriba, o hacia un costado, la placa no entra en la imagen
+    assert_eq!(seen_from(Vec3::new(0.0, 2.0, 3.0), Vec3::new(0.0, 6.0, 2.0)), None);
+    assert_eq!(seen_from(Vec3::new(-2.0, 2.0, 6.0), Vec3::new(-1.0, 2.0, 5.8)), None);
+    // Con la cámara sobre la placa hay esquinas detrás y se renderiza igual
+    assert_eq!(seen_from(Vec3::new(0.0, 0.5, 0.0), Vec3::new(0.0, 0.5, -3.0)), Some((1, 0.0)));
+}
@@ -0,0 +1,31 @@
+use raycasting_diorama::color::Color;
+use raycasting_diorama::presets;
+
+#[test]
+fn presets_do_not_add_light() {
+    for material in presets::all() {
+        let weights = [material.diffuse, material.specular, material.reflectivity, material.transparency];
+        assert!(weights.iter().all(|weight| (0.0..=1.0).contains(weight)), "{}", material.name);
+        assert!(material.reflectivity + material.transparency <= 1.0, "{}", material.name);
+        assert!(material.shininess >= 0.0, "{}", material.name);
+    }
+}
+
+#[test]
+fn transparent_presets_bend_light_like_the_real_thing() {
+    assert_eq!(presets::glass().refractive_index, 1.5);
+    assert_eq!(presets::water().refractive_index, 1.33);
+    for material in presets::all().into_iter().filter(|material| material.is_transparent()) {
+        assert!(material.refractive_index >= 1.0, "{}", material.name);
+    }
+    assert!(presets::glowstone().is_emissive());
+    assert!(!presets::mirror().is_transparent());
+}
+
+#[test]
+fn presets_take_another_base() {
+    let tint = Color::new(200, 240, 210);
+    let green = presets::glass().tinted(tint);
+    assert_eq!(green.color, tint);
+    assert_eq!(green.transparency, presets::glass().transparency);
+}
@@ -0,0 +1,101 @@
+use raycasting_diorama::color::Color;
+use raycasting_diorama::material::Material;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::texture::{AnimatedTexture, ProceduralTexture, Texture, TextureSource};
+
+const BLACK: Color = Color::new(0, 0, 0);
+const WHITE: Color = Color::new(255, 255, 255);
+
+#[test]
+fn checker_alternates_its_squares() {
+    let checker = Texture::from(ProceduralTexture::Checker { even: BLACK, odd: WHITE, scale: 4.0 });
+    assert_eq!(checker.get_color(0.1, 0.1), BLACK);
+    assert_eq!(checker.get_color(0.3, 0.1), WHITE);
+    assert_eq!(checker.get_color(0.3, 0.3), BLACK);
+    // Se repite fuera del cuadrado unidad
+    assert_eq!(checker.get_color(1.3, -0.9), WHITE);
+}
+
+#[test]
+fn noise_is_seeded_and_tiles() {
+    let noise = |seed| ProceduralTexture::Noise { low: BLACK, high: WHITE, seed, frequency: 4.0 };
+    let samples: Vec<f32> = (0..64).map(|i| noise(1).color_at(i as f32 / 64.0, 0.37)[0]).collect();
+    assert!(samples.iter().all(|value| (0.0..=255.0).contains(value)));
+    assert!(samples.iter().any(|&value| (value - samples[0]).abs() > 20.0), "el ruido varía");
+    assert_eq!(noise(1).color_at(0.2, 0.7), noise(1).color_at(0.2, 0.7));
+    assert_ne!(noise(1).color_at(0.2, 0.7), noise(2).color_at(0.2, 0.7));
+    // Los bordes opuestos coinciden, así las caras vecinas no muestran costura
+    let [left, right] = [0.0, 0.999_99].map(|u| noise(1).color_at(u, 0.37)[0]);
+    assert!((left - right).abs() < 0.1, "{} y {}", left, right);
+}
+
+#[test]
+fn gradient_goes_from_bottom_to_top() {
+    let gradient = ProceduralTexture::Gradient { bottom: BLACK, top: Color::new(200, 100, 0) };
+    assert_eq!(gradient.color_at(0.5, 0.0), [0.0, 0.0, 0.0]);
+    assert_eq!(gradient.color_at(0.5, 0.5), [100.0, 50.0, 0.0]);
+}
+
+#[test]
+fn materials_take_patterns_like_images() {
+    let pattern = ProceduralTexture::Gradient { bottom: BLACK, top: WHITE };
+    let material = Material::with_texture(pattern, 10.0, [1.0, 0.0, 0.0, 0.0], 1.0);
+    assert_eq!(material.texture.as_deref().and_then(Texture::pattern), Some(&pattern));
+    assert_eq!(material.color, WHITE);
+
+    let scene = Scene::from_ron(
+        r#"(materials: {"piso": (pattern: Some(Checker(even: (0, 0, 0), odd: (255, 255, 255), scale: 2.0)))}, objects: [])"#,
+    )
+    .expect("la escena es válida");
+    let texture = scene.materials["piso"].texture.as_ref().expect("el patrón hace de textura");
+    assert_eq!(texture.get_color(0.75, 0.25), WHITE);
+}
+
+#[test]
+fn baked_patterns_match_what_they_were_baked_from() {
+    let checker = ProceduralTexture::Checker { even: Color::new(20, 40, 60), odd: Color::new(230, 200, 10), scale: 4.0 };
+    let noise = ProceduralTexture::Noise { low: BLACK, high: WHITE, seed: 3, frequency: 4.0 };
+    for pattern in [checker, noise] {
+        let baked = pattern.bake(32, 16, 0.0);
+        assert_eq!((baked.width(), baked.height()), (32, 16));
+        assert!(baked.pattern().is_none(), "horneado ya no es un patrón");
+        // En el centro de cada texel la imagen redondea el patrón
+        for (x, y) in (0..32).flat_map(|x| (0..16).map(move |y| (x, y))) {
+            let (u, v) = ((x as f32 + 0.5) / 32.0, 1.0 - (y as f32 + 0.5) / 16.0);
+            let texel = baked.get_color(u, v);
+            let texel = [texel.red(), texel.green(), texel.blue()];
+            for (channel, expected) in texel.into_iter().zip(pattern.color_at(u, v)) {
+                assert!((channel as f32 - expected).abs() <= 0.5, "{:?} en ({}, {})", pattern, u, v);
+            }
+        }
+    }
+    // Un tablero no tiene tonos intermedios: sale exacto
+    let baked = checker.bake(64, 64, 0.0);
+    assert_eq!(baked.get_color(0.1, 0.1), Color::new(20, 40, 60));
+    assert_eq!(baked.get_color(0.3, 0.1), Color::new(230, 200, 10));
+}
+
+#[test]
+fn baking_a_material_freezes_its_animation() {
+    let frames = [BLACK, WHITE].map(|color| Texture::procedural(ProceduralTexture::Gradient { bottom: color, top: color }));
+    let mut material = Material::black().animated(AnimatedTexture::new(frames.to_vec(), 1.0));
+    assert!(material.bake_texture(8, 8, 1.5), "avisa que la animación queda quieta");
+    assert!(material.animation.is_none());
+    assert_eq!(material.texture.as_ref().map(|texture| texture.get_color(0.5, 0.5)), Some(WHITE));
+    // Un patrón se hornea sin nada que avisar
+    let mut material = Material::with_texture(ProceduralTexture::Gradient { bottom: BLACK, top: WHITE }, 10.0, [1.0, 0.0, 0.0, 0.0], 1.0);
+    assert!(!material.bake_texture(4, 4, 0.0));
+    assert!(material.texture.as_deref().and_then(Texture::pattern).is_none());
+}
+
+#[test]
+fn scenes_can_bake_patterns_at_load() {
+    let scene = Scene::from_ron(
+        r#"(materials: {"piso": (pattern: Some(Checker(even: (0, 0, 0), odd: (255, 255, 255), scale: 2.0)), bake: Some((16, 8)))}, objects: [])"#,
+    )
+    .expect("la escena es válida");
+    let texture = scene.materials["piso"].texture.as_ref().expect("queda la imagen horneada");
+    assert!(texture.pattern().is_none());
+    assert_eq!((texture.width(), texture.height()), (16, 8));
+    assert_eq!(texture.get_color(0.75, 0.25), WHITE);
+}
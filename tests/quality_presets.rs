@@ -0,0 +1,63 @@
+use raycasting_diorama::bloom::apply_bloom;
+use raycasting_diorama::color::Color;
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::settings::{Bloom, Preset, RenderSettings, ShadowQuality};
+
+#[test]
+fn each_preset_is_recognized_after_applying_it() {
+    assert_eq!(RenderSettings::default().preset(), Some(Preset::Balanced));
+    for preset in Preset::ALL {
+        let mut settings = RenderSettings::default();
+        settings.apply_preset(preset);
+        assert_eq!(settings.preset(), Some(preset));
+        // Cualquier opción del preset cambiada a mano lo deja en Custom
+        settings.samples_per_pixel += 1;
+        assert_eq!(settings.preset(), None, "{:?}", preset);
+    }
+}
+
+#[test]
+fn quality_and_cinematic_sample_shadow_and_glow_more() {
+    let with = |preset: Preset| {
+        let mut settings = RenderSettings::default();
+        settings.apply_preset(preset);
+        settings
+    };
+    let fast = with(Preset::Fast);
+    assert_eq!((fast.samples_per_pixel, fast.shadow_quality), (1, ShadowQuality::Hard));
+    assert!(fast.ambient_occlusion.is_none() && fast.bloom.is_none());
+
+    let quality = with(Preset::Quality);
+    assert_eq!(quality.samples_per_pixel, 4);
+    assert_ne!(quality.shadow_quality, ShadowQuality::Hard);
+    assert!(quality.ambient_occlusion.is_some());
+    assert!(quality.bloom.is_none());
+
+    let cinematic = with(Preset::Cinematic);
+    assert!(cinematic.samples_per_pixel > quality.samples_per_pixel);
+    assert!(cinematic.bloom.is_some());
+    assert!(cinematic.max_depth >= quality.max_depth);
+}
+
+#[test]
+fn bloom_spreads_only_what_passes_the_threshold() {
+    let mut framebuffer = Framebuffer::new(40, 40);
+    framebuffer.buffer.fill(Color::new(60, 60, 60));
+    framebuffer.buffer[20 * 40 + 20] = Color::white();
+    let before = framebuffer.buffer.clone();
+    apply_bloom(&mut framebuffer, &Bloom::default());
+
+    // El punto blanco ilumina a sus vecinos, cada vez menos con la distancia
+    let at = |x: usize| framebuffer.buffer[20 * 40 + x].red();
+    assert!(at(22) > 60, "{}", at(22));
+    assert!(at(22) >= at(24) && at(24) >= at(34));
+    assert_eq!(at(1), 60, "lejos no llega");
+
+    // Una imagen sin nada por encima del umbral queda igual
+    let mut dim = Framebuffer::new(16, 16);
+    dim.buffer.fill(Color::new(150, 150, 150));
+    let untouched = dim.buffer.clone();
+    apply_bloom(&mut dim, &Bloom::default());
+    assert_eq!(dim.buffer, untouched);
+    assert_ne!(framebuffer.buffer, before);
+}
@@ -0,0 +1,158 @@
+use raycasting_diorama::bvh::Aabb;
+use raycasting_diorama::object::SceneObject;
+use raycasting_diorama::query::SceneQuery;
+use raycasting_diorama::ray_intersect::RayIntersect;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::{DioramaBuilder, Vec3};
+
+const MATERIALS: [&str; 5] = ["obsidian", "purple", "grass", "rock", "lava"];
+
+// Generador chico con semilla, para que cada corrida pruebe las mismas escenas
+struct Rng(u64);
+
+impl Rng {
+    fn unit(&mut self) -> f32 {
+        self.0 = self.0.wrapping_add(0x9e37_79b9_7f4a_7c15);
+        let mut z = self.0;
+        z = (z ^ (z >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+        ((z ^ (z >> 31)) >> 40) as f32 / (1u64 << 24) as f32
+    }
+
+    fn range(&mut self, low: f32, high: f32) -> f32 {
+        low + (high - low) * self.unit()
+    }
+
+    fn point(&mut self, extent: f32) -> Vec3 {
+        Vec3::new(self.range(-extent, extent), self.range(-extent, extent), self.range(-extent, extent))
+    }
+
+    fn material(&mut self) -> &'static str {
+        MATERIALS[(self.unit() * MATERIALS.len() as f32) as usize % MATERIALS.len()]
+    }
+}
+
+// Cubos y esferas desparramados, algunos superpuestos, y en la mitad de las
+// semillas un plano de suelo
+fn random_scene(seed: u64) -> Scene {
+    let mut rng = Rng(seed);
+    let mut builder = DioramaBuilder::new();
+    for _ in 0..150 {
+        let center = rng.point(4.0);
+        builder = if rng.unit() < 0.7 {
+            let half = Vec3::new(rng.range(0.05, 0.6), rng.range(0.05, 0.6), rng.range(0.05, 0.6));
+            builder.add_cube(center - half, center + half, rng.material())
+        } else {
+            builder.add_sphere(center, rng.range(0.05, 0.5), rng.material())
+        };
+    }
+    if seed.is_multiple_of(2) {
+        builder = builder.add_plane(Vec3::new(0.0, -3.0, 0.0), Vec3::new(0.2, 1.0, 0.1).normalize(), 1.0, "grass");
+    }
+    builder.build().expect("los materiales son del registro")
+}
+
+fn random_box(rng: &mut Rng) -> Aabb {
+    let (a, b) = (rng.point(5.0), rng.point(5.0));
+    let size = rng.range(0.0, 1.0);
+    Aabb { min: a.inf(&b) * size, max: a.sup(&b) * size }
+}
+
+// Referencias por fuerza bruta, escritas sin mirar la implementación
+fn brute_in_aabb(objects: &[SceneObject], bounds: &Aabb) -> Vec<usize> {
+    (0..objects.len())
+        .filter(|&index| match &objects[index] {
+            // La esquina de la caja más metida del lado sólido del plano
+            SceneObject::Plane(plane) => {
+                let deepest = Vec3::from_fn(|axis, _| if plane.normal[axis] >= 0.0 { bounds.min[axis] } else { bounds.max[axis] });
+                (deepest - plane.point).dot(&plane.normal) <= 0.0
+            }
+            object => {
+                let other = object.bounds();
+                (0..3).all(|axis| other.min[axis] <= bounds.max[axis] && bounds.min[axis] <= other.max[axis])
+            }
+        })
+        .collect()
+}
+
+fn brute_nearest(objects: &[SceneObject], point: &Vec3) -> Option<(usize, f32)> {
+    let mut best: Option<(usize, f32)> = None;
+    for (index, object) in objects.iter().enumerate() {
+        let distance = object.distance(point).max(0.0);
+        if best.is_none_or(|(_, best)| distance < best) {
+            best = Some((index, distance));
+        }
+    }
+    best
+}
+
+fn brute_raycast(objects: &[SceneObject], origin: &Vec3, direction: &Vec3) -> Option<(usize, f32)> {
+    let mut best: Option<(usize, f32)> = None;
+    for (index, object) in objects.iter().enumerate() {
+        let hit = object.ray_intersect(origin, direction);
+        if hit.is_intersecting && hit.distance >= 0.0 && best.is_none_or(|(_, best)| hit.distance < best) {
+            best = Some((index, hit.distance));
+        }
+    }
+    best
+}
+
+fn sorted(found: impl Iterator<Item = usize>) -> Vec<usize> {
+    let mut found: Vec<usize> = found.collect();
+    found.sort_unstable();
+    found
+}
+
+#[test]
+fn spatial_queries_match_brute_force() {
+    // Para no pasar con consultas que nunca encuentran nada
+    let (mut overlaps, mut hits) = (0, 0);
+    for seed in 0..6 {
+        let scene = random_scene(seed);
+        let (query, linear) = (scene.query(), SceneQuery::new(&scene.objects, None));
+        let mut rng = Rng(seed + 100);
+        for _ in 0..60 {
+            let bounds = random_box(&mut rng);
+            let expected = brute_in_aabb(&scene.objects, &bounds);
+            assert_eq!(sorted(query.objects_in_aabb(bounds)), expected, "semilla {}, caja {:?}", seed, bounds);
+            assert_eq!(sorted(linear.objects_in_aabb(bounds)), expected);
+            overlaps += expected.len();
+
+            let point = rng.point(6.0);
+            let expected = brute_nearest(&scene.objects, &point);
+            assert_eq!(query.nearest_object(&point), expected, "semilla {}, punto {:?}", seed, point);
+            assert_eq!(linear.nearest_object(&point), expected);
+
+            let (origin, direction) = (rng.point(8.0), rng.point(1.0).normalize());
+            let expected = brute_raycast(&scene.objects, &origin, &direction);
+            let hit = |query: &SceneQuery| query.raycast(&origin, &direction).map(|hit| (hit.object, hit.intersect.distance));
+            assert_eq!(hit(&query), expected, "semilla {}, rayo desde {:?}", seed, origin);
+            assert_eq!(hit(&linear), expected);
+            hits += expected.is_some() as usize;
+        }
+    }
+    assert!(overlaps > 100 && hits > 50, "{} objetos en cajas, {} impactos", overlaps, hits);
+}
+
+#[test]
+fn material_queries_match_a_filter() {
+    let scene = random_scene(7);
+    let query = scene.query();
+    for name in MATERIALS {
+        let expected: Vec<usize> = (0..scene.objects.len()).filter(|&index| scene.objects[index].material().name == name).collect();
+        assert_eq!(query.objects_with_material(name).collect::<Vec<_>>(), expected);
+    }
+    let emissive: Vec<usize> = (0..scene.objects.len()).filter(|&index| scene.objects[index].material().is_emissive()).collect();
+    assert!(!emissive.is_empty(), "la lava brilla");
+    assert_eq!(query.emissive_objects().collect::<Vec<_>>(), emissive);
+}
+
+#[test]
+fn empty_scene_answers_nothing() {
+    let scene = DioramaBuilder::new().build().expect("sin objetos");
+    let query = scene.query();
+    let everything = Aabb { min: Vec3::repeat(-1e3), max: Vec3::repeat(1e3) };
+    assert_eq!(query.objects_in_aabb(everything).count(), 0);
+    assert_eq!(query.nearest_object(&Vec3::zeros()), None);
+    assert!(query.raycast(&Vec3::zeros(), &Vec3::new(0.0, 0.0, -1.0)).is_none());
+}
@@ -0,0 +1,49 @@
+use raycasting_diorama::light::DAY_DURATION;
+use raycasting_diorama::redraw::Redraw;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::Vec3;
+
+#[test]
+fn nothing_changed_needs_no_render() {
+    let scene = Scene::diorama();
+    let redraw = Redraw::new(&scene.camera, &scene.lights);
+    assert!(!redraw.changed(&scene.camera, &scene.lights));
+    // Un mapa de sombras nuevo no cambia cómo ilumina la luz
+    let mut lights = scene.lights.clone();
+    lights[0].shadow_map = None;
+    assert!(!redraw.changed(&scene.camera, &lights));
+}
+
+#[test]
+fn an_edited_light_needs_a_render() {
+    let scene = Scene::diorama();
+    let mut redraw = Redraw::new(&scene.camera, &scene.lights);
+    let mut lights = scene.lights.clone();
+
+    // Como el editor de luces: sube una luz sin que cambie la cámara
+    lights[0].position += Vec3::new(0.0, 0.5, 0.0);
+    assert!(redraw.changed(&scene.camera, &lights));
+    redraw.rendered(&scene.camera, &lights);
+    assert!(!redraw.changed(&scene.camera, &lights));
+
+    lights[0].intensity *= 0.5;
+    assert!(redraw.changed(&scene.camera, &lights));
+    redraw.rendered(&scene.camera, &lights);
+
+    // Una luz nueva, y el sol que avanza con el ciclo
+    lights.push(lights[0].clone());
+    assert!(redraw.changed(&scene.camera, &lights));
+    redraw.rendered(&scene.camera, &lights);
+    let sun = lights.iter_mut().find(|light| light.is_sun).expect("el diorama tiene sol");
+    sun.update(DAY_DURATION / 100.0);
+    assert!(redraw.changed(&scene.camera, &lights));
+}
+
+#[test]
+fn a_moved_camera_needs_a_render() {
+    let scene = Scene::diorama();
+    let redraw = Redraw::new(&scene.camera, &scene.lights);
+    let mut camera = scene.camera.clone();
+    camera.fov *= 0.9;
+    assert!(redraw.changed(&camera, &scene.lights));
+}
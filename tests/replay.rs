@@ -0,0 +1,54 @@
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+// Sesión corta con de todo un poco: la cámara orbita con teclas y con el
+// mouse, el día avanza con pasos de distinto largo, se agrega y se mueve una
+// luz, se deshace un cambio y se regenera el diorama al azar
+const SESSION: &str = "\
+# sesión de Raycasting_diorama: un frame por línea
+t=0.0160 dt=0.016 held=orbit_left pressed=
+t=0.0493 dt=0.0333 held=orbit_left,zoom_in pressed=
+t=0.0653 dt=0.016 held= pressed=add_light
+t=0.1153 dt=0.05 held=light_up pressed= orbit=12,-5
+t=0.1313 dt=0.016 held= pressed=regenerate
+t=0.1813 dt=0.05 held=orbit_up pressed= pan=3,2
+t=0.2313 dt=0.05 held= pressed=next_light
+t=0.2473 dt=0.016 held= pressed=undo scroll=1
+t=1.2473 dt=1 held= pressed=
+";
+
+fn temp(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("diorama_replay_{}_{}", std::process::id(), name))
+}
+
+// Reproduce la sesión sin ventana y devuelve los píxeles del frame final
+fn replay(session: &Path, output: &Path) -> Vec<u8> {
+    let run = Command::new(env!("CARGO_BIN_EXE_Raycasting_diorama"))
+        .arg("scenes/generated.ron")
+        .args(["--replay", &session.to_string_lossy(), "--output", &output.to_string_lossy()])
+        .args(["--width", "64", "--height", "48"])
+        .output()
+        .expect("el binario arranca");
+    assert!(run.status.success(), "{}", String::from_utf8_lossy(&run.stderr));
+    let image = image::open(output).expect("se guardó el PNG").to_rgb8();
+    std::fs::remove_file(output).ok();
+    image.into_raw()
+}
+
+#[test]
+fn replaying_a_session_twice_gives_the_same_frame() {
+    let session = temp("sesion.log");
+    std::fs::write(&session, SESSION).expect("se escribe la sesión");
+    let first = replay(&session, &temp("primera.png"));
+    let second = replay(&session, &temp("segunda.png"));
+
+    // Sin la sesión el frame final es otro: la sesión sí cambió algo
+    let untouched = temp("vacia.log");
+    std::fs::write(&untouched, "# sesión de Raycasting_diorama: un frame por línea\n").expect("se escribe la sesión");
+    let without = replay(&untouched, &temp("sin.png"));
+    std::fs::remove_file(&session).ok();
+    std::fs::remove_file(&untouched).ok();
+
+    assert!(first == second, "las dos reproducciones difieren");
+    assert_ne!(first, without);
+}
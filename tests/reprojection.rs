@@ -0,0 +1,68 @@
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::light::SceneLight;
+use raycasting_diorama::renderer::{render, render_reprojected, sky_color, RenderStats};
+use raycasting_diorama::reprojection::Reprojection;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::Vec3;
+
+const WIDTH: usize = 80;
+const HEIGHT: usize = 60;
+
+fn settings(scene: &Scene) -> RenderSettings {
+    RenderSettings { bias: scene.default_bias(), ..RenderSettings::default() }
+}
+
+fn reprojected(scene: &Scene, lights: &[SceneLight], settings: &RenderSettings, reprojection: &mut Reprojection) -> (Framebuffer, RenderStats) {
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    let stats = render_reprojected(&mut framebuffer, &scene.objects, &scene.camera, lights, sky_color(lights), settings, reprojection);
+    (framebuffer, stats)
+}
+
+fn plain(scene: &Scene, lights: &[SceneLight], settings: &RenderSettings) -> Framebuffer {
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    render(&mut framebuffer, &scene.objects, &scene.camera, lights, sky_color(lights), settings);
+    framebuffer
+}
+
+#[test]
+fn a_still_camera_reuses_the_previous_frame() {
+    let scene = Scene::diorama();
+    let settings = settings(&scene);
+    let mut reprojection = Reprojection::default();
+
+    // El primer frame no tiene historial y se sombrea entero
+    let (first, stats) = reprojected(&scene, &scene.lights, &settings, &mut reprojection);
+    assert_eq!(stats.reused_pixels, 0);
+    assert!(stats.reusable_pixels > 0);
+    assert!(first.buffer == plain(&scene, &scene.lights, &settings).buffer);
+
+    // Con la cámara y las luces quietas se reusa todo salvo el 3% que se
+    // refresca cada frame, y la imagen no cambia
+    let (second, stats) = reprojected(&scene, &scene.lights, &settings, &mut reprojection);
+    assert!(stats.reused_pixels as f32 > stats.reusable_pixels as f32 * 0.9, "{:?}", stats);
+    assert!(second.buffer == first.buffer, "lo reusado debería verse igual");
+}
+
+#[test]
+fn a_moved_light_shades_everything_again() {
+    let scene = Scene::diorama();
+    let settings = settings(&scene);
+    let mut reprojection = Reprojection::default();
+    reprojected(&scene, &scene.lights, &settings, &mut reprojection);
+
+    let mut lights = scene.lights.clone();
+    lights[0].position += Vec3::new(0.5, 0.5, 0.0);
+    let (moved, stats) = reprojected(&scene, &lights, &settings, &mut reprojection);
+    assert_eq!(stats.reused_pixels, 0, "el historial era de la luz anterior");
+    assert!(moved.buffer == plain(&scene, &lights, &settings).buffer);
+
+    // El historial nuevo ya es de la luz movida
+    let (_, stats) = reprojected(&scene, &lights, &settings, &mut reprojection);
+    assert!(stats.reused_pixels > 0);
+
+    // Cambiar una opción de render también lo descarta
+    let brighter = RenderSettings { exposure: settings.exposure + 1.0, ..settings.clone() };
+    let (_, stats) = reprojected(&scene, &lights, &brighter, &mut reprojection);
+    assert_eq!(stats.reused_pixels, 0, "el historial era de otra exposición");
+}
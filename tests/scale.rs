@@ -0,0 +1,49 @@
+use raycasting_diorama::color::Color;
+use raycasting_diorama::light::{LightKind, SceneLight};
+use raycasting_diorama::material::Material;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::{DioramaBuilder, Renderer, Vec3};
+
+// Un piso, un bloque que le hace sombra, una placa de vidrio y un espejo,
+// todo multiplicado por `scale`, también la luz y la cámara
+fn diorama(scale: f32) -> Scene {
+    let at = |x: f32, y: f32, z: f32| Vec3::new(x, y, z) * scale;
+    // Luz direccional: la altura del sol del ciclo día/noche está en unidades
+    // del diorama
+    let mut light = SceneLight::new(at(1.5, 2.5, 1.0), Color::white(), 1.3);
+    light.kind = LightKind::Directional;
+    light.target = Vec3::zeros();
+    DioramaBuilder::new()
+        .material("floor", Material::new(Color::new(120, 160, 90), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0))
+        .material("block", Material::new(Color::new(200, 120, 60), 30.0, [0.8, 0.4, 0.0, 0.0], 1.0))
+        .material("glass", Material::new(Color::new(220, 230, 255), 80.0, [0.1, 0.5, 0.1, 0.7], 1.5))
+        .material("mirror", Material::new(Color::new(20, 20, 20), 80.0, [0.1, 0.5, 0.8, 0.0], 1.0))
+        .add_cube(at(-2.0, -0.6, -2.0), at(2.0, -0.5, 2.0), "floor")
+        .add_cube(at(-1.0, -0.5, -0.6), at(-0.2, 0.4, 0.2), "block")
+        .add_cube(at(0.2, -0.5, 0.4), at(1.0, 0.3, 0.5), "glass")
+        .add_cube(at(-1.5, -0.5, -1.6), at(1.5, 1.0, -1.5), "mirror")
+        .add_light(light)
+        .camera(at(0.4, 1.2, 3.5), at(0.0, -0.1, 0.0))
+        .build()
+        .expect("los materiales están registrados")
+}
+
+#[test]
+fn the_bias_grows_with_the_scene() {
+    let (small, large) = (diorama(1.0).default_bias(), diorama(100.0).default_bias());
+    assert!((large / small - 100.0).abs() < 1e-3, "{} y {}", small, large);
+}
+
+#[test]
+fn a_scene_renders_the_same_at_any_scale() {
+    // El sesgo sale de cada escena: a 100x es 100 veces mayor
+    let render = |scene: &Scene| Renderer::new(RenderSettings::default()).size(96, 72).render(scene, &scene.camera).into_raw();
+    let (small, large) = (render(&diorama(1.0)), render(&diorama(100.0)));
+
+    // Los rayos a 100x se redondean distinto, así que algún píxel de un borde
+    // puede caer del otro lado; el acné de sombras o las fugas de luz
+    // cambiarían zonas enteras
+    let differing = small.chunks(3).zip(large.chunks(3)).filter(|(a, b)| a.iter().zip(b.iter()).any(|(a, b)| a.abs_diff(*b) > 2)).count();
+    assert!(differing <= 96 * 72 / 1000, "{} píxeles difieren", differing);
+}
@@ -0,0 +1,557 @@
+use std::sync::Arc;
+
+use raycasting_diorama::bvh::TraceBvh;
+use raycasting_diorama::color::{srgb_to_linear, Color, LinearColor};
+use raycasting_diorama::cube::Cube;
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::light::{LightKind, SceneLight, DAY_DURATION};
+use raycasting_diorama::material::Material;
+use raycasting_diorama::object::SceneObject;
+use raycasting_diorama::ray_intersect::RayIntersect;
+use raycasting_diorama::renderer::{cast_ray, cast_shadow, offset_origin, reflect, refract, render, render_reprojected, sky_color};
+use raycasting_diorama::reprojection::Reprojection;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::{AmbientOcclusion, RenderSettings, ShadowQuality, ToneMapping};
+use raycasting_diorama::texture::Texture;
+use raycasting_diorama::{DioramaBuilder, Renderer, Vec3};
+
+// Luz direccional blanca que llega a `angle` grados de la normal de la cara
+// que mira a la cámara
+fn light_at(angle: f32, color: Color) -> SceneLight {
+    let direction = Vec3::new(0.0, angle.to_radians().sin(), angle.to_radians().cos());
+    let mut light = SceneLight::new(direction * 10.0, color, 1.0);
+    light.kind = LightKind::Directional;
+    light.target = Vec3::zeros();
+    light
+}
+
+fn cube_scene(material: Material, light: SceneLight) -> Scene {
+    DioramaBuilder::new()
+        .material("mate", material)
+        .add_cube(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0), "mate")
+        .add_light(light)
+        .camera(Vec3::new(0.0, 0.0, 4.0), Vec3::zeros())
+        .build()
+        .expect("el material está registrado")
+}
+
+// Cubo con solo término difuso visto de frente, con la luz a 60° de la
+// normal: el coseno vale 0.5
+fn lit_cube(color: Color, light_color: Color) -> Scene {
+    cube_scene(Material::new(color, 1.0, [1.0, 0.0, 0.0, 0.0], 1.0), light_at(60.0, light_color))
+}
+
+// Color del píxel del centro, que cae en la cara que mira a la cámara
+fn center_pixel(scene: &Scene) -> [u8; 3] {
+    let image = Renderer::new(RenderSettings::default()).size(40, 30).render(scene, &scene.camera);
+    image.get_pixel(20, 15).0
+}
+
+// Las luces se suman en luz lineal: las proporciones se miden ahí
+fn light_of(channel: u8) -> f32 {
+    srgb_to_linear(channel)
+}
+
+#[test]
+fn gray_cube_is_mid_gray() {
+    let [r, g, b] = center_pixel(&lit_cube(Color::new(128, 128, 128), Color::white()));
+    assert!(r == g && g == b, "el gris no debería teñirse: ({}, {}, {})", r, g, b);
+    assert!((light_of(r) - light_of(128) * 0.5).abs() < 0.005, "se esperaba la mitad de la luz del gris, no {}", r);
+}
+
+#[test]
+fn without_gamma_correction_light_scales_the_stored_values() {
+    let scene = lit_cube(Color::new(128, 128, 128), Color::white());
+    let settings = RenderSettings { gamma_correct: false, ..RenderSettings::default() };
+    let [r, _, _] = Renderer::new(settings).size(40, 30).render(&scene, &scene.camera).get_pixel(20, 15).0;
+    assert!((62..=66).contains(&r), "se esperaba un gris de 64, no {}", r);
+}
+
+#[test]
+fn light_color_filters_the_surface() {
+    let [r, g, b] = center_pixel(&lit_cube(Color::white(), Color::new(255, 0, 0)));
+    assert!((light_of(r) - 0.5).abs() < 0.005, "el rojo debería quedar a la mitad, no {}", r);
+    assert_eq!((g, b), (0, 0));
+}
+
+#[test]
+fn textures_are_lit_unless_unlit() {
+    let grass = DioramaBuilder::new().build().expect("sin objetos").materials["grass"].clone();
+    // La luz llega por detrás: la cara que se ve queda sin luz directa
+    let behind = light_at(180.0, Color::white());
+    assert_eq!(center_pixel(&cube_scene(grass.clone(), behind.clone())), [0, 0, 0]);
+
+    let unlit = Material { unlit: true, ..grass };
+    assert_ne!(center_pixel(&cube_scene(unlit, behind)), [0, 0, 0]);
+}
+
+// Imagen de 4x4 de un solo color
+fn solid_texture(texel: [u8; 3]) -> Texture {
+    let mut png = Vec::new();
+    image::RgbImage::from_pixel(4, 4, image::Rgb(texel))
+        .write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png)
+        .expect("se codifica en memoria");
+    Texture::from_bytes(&png, "liso.png").expect("es un PNG válido")
+}
+
+#[test]
+fn normal_map_tilts_the_lit_normal() {
+    let mapped = |texel: [u8; 3]| {
+        let material = Material::builder().color(Color::new(128, 128, 128)).normal_map(solid_texture(texel)).build();
+        center_pixel(&cube_scene(material, light_at(60.0, Color::white())))[0]
+    };
+    // Plano: como sin mapa, con el coseno en 0.5
+    let flat = mapped([128, 128, 255]);
+    assert!((light_of(flat) - light_of(128) * 0.5).abs() < 0.005, "{}", flat);
+    // Inclinada 60° hacia +v, que en la cara de adelante es hacia arriba y
+    // hacia la luz: la recibe de frente
+    assert!(mapped([128, 238, 191]) >= 124, "{}", mapped([128, 238, 191]));
+    // Hacia -v le da la espalda
+    assert!(mapped([128, 18, 191]) <= 4, "{}", mapped([128, 18, 191]));
+}
+
+#[test]
+fn emission_map_replaces_the_flat_emission() {
+    // Negro y la luz por detrás: solo se ve lo que emite
+    let glowing = |material: Material| center_pixel(&cube_scene(material, light_at(180.0, Color::white())));
+    let flat = Material::builder().color(Color::black()).emission(Color::new(255, 128, 0)).build();
+    assert_eq!(glowing(flat.clone()), [255, 128, 0]);
+    // La intensidad escala la luz emitida
+    let mapped = Material { emission_map: Some(solid_texture([40, 200, 100])), emission_strength: 0.5, ..flat };
+    let expected = (LinearColor::from_srgb(Color::new(40, 200, 100)) * 0.5).to_srgb();
+    assert_eq!(glowing(mapped), [expected.red(), expected.green(), expected.blue()]);
+}
+
+#[test]
+fn tone_mapping_rolls_off_bright_emission() {
+    // Naranja cuatro veces más fuerte de lo que se puede guardar
+    let scene = cube_scene(
+        Material::builder().color(Color::black()).emission(Color::new(255, 128, 0)).emission_strength(4.0).build(),
+        light_at(180.0, Color::white()),
+    );
+    let pixel = |tone_mapping, exposure| {
+        let settings = RenderSettings { tone_mapping, exposure, ..RenderSettings::default() };
+        Renderer::new(settings).size(40, 30).render(&scene, &scene.camera).get_pixel(20, 15).0
+    };
+    // Saturado queda casi blanco; con ACES el verde se aplana antes y sigue naranja
+    let clamped = pixel(ToneMapping::Clamp, 1.0);
+    let aces = pixel(ToneMapping::Aces, 1.0);
+    assert!(clamped[0] == 255 && clamped[1] > 230, "{:?}", clamped);
+    assert!(aces[0] > 240 && aces[0] - aces[1] > clamped[0] - clamped[1] + 8, "ACES {:?} contra {:?}", aces, clamped);
+    // Reinhard nunca llega al blanco
+    let reinhard = pixel(ToneMapping::Reinhard, 1.0);
+    assert!(reinhard[0] < 255 && reinhard[0] > reinhard[1], "{:?}", reinhard);
+    // La exposición escala la luz antes de mapearla: un cuarto vuelve al naranja guardado
+    assert_eq!(pixel(ToneMapping::Clamp, 0.25), [255, 128, 0]);
+}
+
+#[test]
+fn tone_curves_keep_black_and_order() {
+    for tone_mapping in ToneMapping::ALL {
+        let curve = |x: f32| tone_mapping.apply(LinearColor::new(x, x, x)).r;
+        assert_eq!(curve(0.0), 0.0, "{}", tone_mapping.name());
+        let samples: Vec<f32> = (1..64).map(|i| curve(i as f32 / 8.0)).collect();
+        assert!(samples.windows(2).all(|pair| pair[1] >= pair[0]) && samples[7] > samples[0], "{} no crece", tone_mapping.name());
+    }
+    assert_eq!(ToneMapping::Reinhard.apply(LinearColor::new(1.0, 3.0, 0.0)), LinearColor::new(0.5, 0.75, 0.0));
+    assert!(ToneMapping::Aces.apply(LinearColor::new(100.0, 0.0, 0.0)).r <= 1.0);
+}
+
+#[test]
+fn attenuated_light_falls_off_with_distance() {
+    let material = Material::new(Color::white(), 1.0, [1.0, 0.0, 0.0, 0.0], 1.0);
+    // Frente a la cara que mira a la cámara, a 1 y a 3 de distancia
+    let brightness = |distance: f32, light: fn(Vec3) -> SceneLight| {
+        let scene = cube_scene(material.clone(), light(Vec3::new(0.0, 0.0, 1.0 + distance)));
+        center_pixel(&scene)[0]
+    };
+    let constant = |position| SceneLight::new(position, Color::white(), 0.5);
+    assert_eq!(brightness(1.0, constant), brightness(3.0, constant));
+
+    // 1 / (1 + d²): la mitad a 1 y un décimo a 3
+    let attenuated = |position| SceneLight::attenuated(position, Color::white(), 1.0, 0.0, 1.0);
+    let (near, far) = (brightness(1.0, attenuated), brightness(3.0, attenuated));
+    assert!((light_of(near) - 0.5).abs() < 0.005, "a 1 debería quedar a la mitad, no {}", near);
+    assert!((light_of(far) - 0.1).abs() < 0.005, "a 3 debería quedar un décimo, no {}", far);
+}
+
+#[test]
+fn ambient_occlusion_darkens_near_geometry_only() {
+    // Un bloque que sobresale de la cara que mira a la cámara a partir de x = 1
+    let pixels = |material: Material, ambient_occlusion| {
+        let scene = DioramaBuilder::new()
+            .material("mate", material)
+            .add_cube(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0), "mate")
+            .add_cube(Vec3::new(1.0, -1.0, -1.0), Vec3::new(2.0, 1.0, 1.3), "mate")
+            .add_light(light_at(0.0, Color::white()))
+            .camera(Vec3::new(0.0, 0.0, 4.0), Vec3::zeros())
+            .build()
+            .expect("el material está registrado");
+        let settings = RenderSettings { ambient_occlusion, ..RenderSettings::default() };
+        let image = Renderer::new(settings).size(160, 120).render(&scene, &scene.camera);
+        // El centro de la cara y un punto a menos de 0.1 del bloque
+        (image.get_pixel(80, 60).0, image.get_pixel(112, 60).0)
+    };
+    let matte = Material::new(Color::white(), 1.0, [1.0, 0.0, 0.0, 0.0], 1.0);
+    let (open, corner) = pixels(matte.clone(), None);
+    let (open_ao, corner_ao) = pixels(matte, Some(AmbientOcclusion::default()));
+    assert_eq!(open, open_ao);
+    assert!(corner_ao[0] < corner[0], "la esquina debería oscurecerse: {:?} contra {:?}", corner_ao, corner);
+
+    // La emisión no se oscurece
+    let glow = Material { emission: Color::new(200, 100, 50), ..Material::new(Color::black(), 1.0, [0.0; 4], 1.0) };
+    assert_eq!(pixels(glow.clone(), None), pixels(glow, Some(AmbientOcclusion::default())));
+}
+
+#[test]
+fn shadow_follows_the_nearest_occluder() {
+    // Suelo bajo una luz puntual con dos bloques en el camino del rayo de
+    // sombra: uno cerca de la luz y otro cerca del suelo
+    let near_light = (Vec3::new(-0.5, 8.0, -0.5), Vec3::new(0.5, 8.5, 0.5));
+    let near_floor = (Vec3::new(-0.5, 1.0, -0.5), Vec3::new(0.5, 1.5, 0.5));
+    let floor_center = |occluders: &[(Vec3, Vec3)], with_bvh: bool| {
+        let mut builder = DioramaBuilder::new()
+            .material("mate", Material::new(Color::white(), 1.0, [1.0, 0.0, 0.0, 0.0], 1.0))
+            .add_cube(Vec3::new(-3.0, -1.0, -3.0), Vec3::new(3.0, 0.0, 3.0), "mate");
+        for &(min, max) in occluders {
+            builder = builder.add_cube(min, max, "mate");
+        }
+        let scene = builder
+            .add_light(SceneLight::new(Vec3::new(0.0, 10.0, 0.0), Color::white(), 1.0))
+            .camera(Vec3::new(0.0, 2.0, 4.0), Vec3::zeros())
+            .build()
+            .expect("el material está registrado");
+        let bvh = with_bvh.then(|| Arc::new(TraceBvh::new(&scene.objects)));
+        let settings = RenderSettings { bvh, ..RenderSettings::default() };
+        let image = Renderer::new(settings).size(40, 30).render(&scene, &scene.camera);
+        image.get_pixel(20, 15).0
+    };
+    for with_bvh in [false, true] {
+        let lit = floor_center(&[], with_bvh);
+        let (far, near) = (floor_center(&[near_light], with_bvh), floor_center(&[near_floor], with_bvh));
+        // Cuanto más cerca del suelo está el oclusor, más oscura la sombra
+        assert!(near[0] < far[0] && far[0] < lit[0], "{:?} < {:?} < {:?}", near, far, lit);
+        // Con los dos manda el más cercano al suelo, en cualquier orden; tomar
+        // el primero de la lista daba la sombra clara del de arriba
+        assert_eq!(floor_center(&[near_light, near_floor], with_bvh), near, "árbol: {}", with_bvh);
+        assert_eq!(floor_center(&[near_floor, near_light], with_bvh), near, "árbol: {}", with_bvh);
+    }
+}
+
+#[test]
+fn transparent_occluders_tint_the_shadow() {
+    // Suelo blanco bajo una luz puntual con una placa encima, pegada al suelo
+    // para que la sombra sea casi plena
+    let floor_center = |plate: Option<Material>| {
+        let mut builder = DioramaBuilder::new()
+            .material("mate", Material::new(Color::white(), 1.0, [1.0, 0.0, 0.0, 0.0], 1.0))
+            .add_cube(Vec3::new(-3.0, -1.0, -3.0), Vec3::new(3.0, 0.0, 3.0), "mate");
+        if let Some(plate) = plate {
+            builder = builder.material("placa", plate).add_cube(Vec3::new(-0.5, 1.0, -0.5), Vec3::new(0.5, 1.1, 0.5), "placa");
+        }
+        let scene = builder
+            .add_light(SceneLight::new(Vec3::new(0.0, 10.0, 0.0), Color::white(), 1.0))
+            .camera(Vec3::new(0.0, 2.0, 4.0), Vec3::zeros())
+            .build()
+            .expect("los materiales están registrados");
+        let settings = RenderSettings::default();
+        let image = Renderer::new(settings).size(40, 30).render(&scene, &scene.camera);
+        image.get_pixel(20, 15).0
+    };
+    let lit = floor_center(None);
+    let opaque = floor_center(Some(Material::new(Color::new(255, 40, 40), 1.0, [1.0, 0.0, 0.0, 0.0], 1.0)));
+    let glass = floor_center(Some(Material::new(Color::new(255, 40, 40), 1.0, [1.0, 0.0, 0.0, 0.8], 1.0)));
+    // El vidrio rojo deja pasar el rojo y frena casi todo el verde y el azul
+    assert!(glass[0] > opaque[0] && glass[0] < lit[0], "rojo: {:?} entre {:?} y {:?}", glass, opaque, lit);
+    assert!(light_of(glass[0]) > 3.0 * light_of(glass[1]) && glass[1] == glass[2], "la sombra debería quedar roja: {:?}", glass);
+    assert!(opaque[0] == opaque[1] && opaque[1] == opaque[2], "la sombra opaca es gris: {:?}", opaque);
+}
+
+#[test]
+fn fresnel_glass_reflects_at_grazing_angles() {
+    // Placa de vidrio negro sobre un suelo rojo que se ve tal cual: lo rojo
+    // del píxel llega refractado y lo demás es el cielo reflejado
+    let plate_center = |eye: Vec3, use_fresnel: bool| {
+        let glass = Material { use_fresnel, ..Material::new(Color::black(), 1.0, [0.0, 0.0, 0.5, 0.5], 1.5) };
+        let floor = Material { unlit: true, ..Material::new(Color::new(255, 0, 0), 1.0, [1.0, 0.0, 0.0, 0.0], 1.0) };
+        let scene = DioramaBuilder::new()
+            .material("vidrio", glass)
+            .material("suelo", floor)
+            .add_cube(Vec3::new(-20.0, -0.05, -20.0), Vec3::new(20.0, 0.05, 20.0), "vidrio")
+            .add_cube(Vec3::new(-50.0, -2.0, -50.0), Vec3::new(50.0, -1.0, 50.0), "suelo")
+            .camera(eye, Vec3::zeros())
+            .build()
+            .expect("los materiales están registrados");
+        let settings = RenderSettings::default();
+        let image = Renderer::new(settings).size(40, 30).render(&scene, &scene.camera);
+        image.get_pixel(20, 15).0
+    };
+    let (above, grazing) = (Vec3::new(0.0, 4.0, 0.5), Vec3::new(0.0, 0.6, 4.0));
+    let fixed = plate_center(above, false);
+    let head_on = plate_center(above, true);
+    let edge = plate_center(grazing, true);
+    // De frente casi todo pasa: más rojo y menos cielo que con pesos fijos
+    assert!(head_on[0] > fixed[0] && head_on[2] < fixed[2], "de frente {:?}, pesos fijos {:?}", head_on, fixed);
+    // En ángulo rasante gana el reflejo del cielo
+    assert!(edge[0] < head_on[0] && edge[2] > head_on[2], "rasante {:?}, de frente {:?}", edge, head_on);
+}
+
+#[test]
+fn absorption_grows_with_depth() {
+    // Agua que a cada unidad deja la mitad del rojo y del verde, sobre un
+    // fondo blanco que se ve tal cual, mirada casi desde arriba
+    let bottom = |depth: f32| {
+        let mut water = Material::new(Color::black(), 1.0, [0.0, 0.0, 0.0, 1.0], 1.0);
+        water.absorption = Color::new(128, 128, 255);
+        water.absorption_density = 1.0;
+        let floor = Material { unlit: true, ..Material::new(Color::white(), 1.0, [1.0, 0.0, 0.0, 0.0], 1.0) };
+        let scene = DioramaBuilder::new()
+            .material("agua", water)
+            .material("fondo", floor)
+            .add_cube(Vec3::new(-5.0, -depth, -5.0), Vec3::new(5.0, 0.0, 5.0), "agua")
+            .add_cube(Vec3::new(-5.0, -depth - 1.0, -5.0), Vec3::new(5.0, -depth, 5.0), "fondo")
+            .camera(Vec3::new(0.0, 4.0, 0.2), Vec3::zeros())
+            .build()
+            .expect("los materiales están registrados");
+        let settings = RenderSettings::default();
+        let image = Renderer::new(settings).size(40, 30).render(&scene, &scene.camera);
+        image.get_pixel(20, 15).0
+    };
+    let (shallow, deep) = (bottom(1.0), bottom(2.0));
+    assert!((light_of(shallow[0]) - 0.5).abs() < 0.02 && shallow[0] == shallow[1], "a una unidad queda la mitad: {:?}", shallow);
+    assert!((light_of(deep[0]) - 0.25).abs() < 0.02 && deep[0] == deep[1], "a dos unidades, un cuarto: {:?}", deep);
+    assert!(shallow[2] > 250 && deep[2] > 250, "el azul pasa entero: {:?} {:?}", shallow, deep);
+}
+
+#[test]
+fn depth_zero_shows_only_the_base_color() {
+    // Espejo gris de frente, con el cielo detrás de la cámara para reflejar
+    let mirror = Material::new(Color::new(128, 128, 128), 1.0, [1.0, 0.0, 0.8, 0.0], 1.0);
+    let matte = Material { diffuse: 1.0, specular: 0.0, reflectivity: 0.0, transparency: 0.0, ..mirror.clone() };
+    let pixel = |material: Material, max_depth: u32| {
+        let scene = cube_scene(material, light_at(60.0, Color::white()));
+        let settings = RenderSettings { max_depth, ..RenderSettings::default() };
+        Renderer::new(settings).size(40, 30).render(&scene, &scene.camera).get_pixel(20, 15).0
+    };
+    assert_ne!(pixel(mirror.clone(), 3), pixel(matte.clone(), 3), "el espejo refleja el cielo");
+    assert_eq!(pixel(mirror, 0), pixel(matte, 0));
+}
+
+#[test]
+fn supersampling_blends_edges_the_same_every_frame() {
+    // Cubo blanco sin luz que se ve tal cual contra el cielo: con un rayo por
+    // píxel cada píxel es cubo o cielo, con varios los del borde quedan entre medio
+    let white = Material { unlit: true, ..Material::new(Color::white(), 1.0, [1.0, 0.0, 0.0, 0.0], 1.0) };
+    let scene = cube_scene(white, light_at(0.0, Color::white()));
+    let render = |samples_per_pixel: u32| {
+        let settings = RenderSettings { samples_per_pixel, ..RenderSettings::default() };
+        Renderer::new(settings).size(40, 30).render(&scene, &scene.camera)
+    };
+    let (single, smooth) = (render(1), render(4));
+    let row = |image: &image::RgbImage| (0..40).map(|x| image.get_pixel(x, 15).0[0]).collect::<Vec<u8>>();
+    let sky = row(&single)[0];
+    assert!(row(&single).iter().all(|&red| red == sky || red == 255), "sin suavizar: {:?}", row(&single));
+    assert!(row(&smooth).iter().any(|&red| red != sky && red != 255), "el borde debería mezclarse: {:?}", row(&smooth));
+    assert_eq!(render(4), smooth, "el patrón de muestras es siempre el mismo");
+}
+
+#[test]
+fn reprojection_keeps_the_supersampled_edges() {
+    // El mismo cubo con cuatro muestras por píxel: el primer frame con
+    // historial se sombrea entero y el segundo lo reusa, y en los dos los
+    // bordes quedan igual de suaves que sin reproyección
+    let white = Material { unlit: true, ..Material::new(Color::white(), 1.0, [1.0, 0.0, 0.0, 0.0], 1.0) };
+    let scene = cube_scene(white, light_at(0.0, Color::white()));
+    let settings = RenderSettings { samples_per_pixel: 4, ..RenderSettings::default() };
+    let sky = sky_color(&scene.lights);
+    let mut plain = Framebuffer::new(40, 30);
+    render(&mut plain, &scene.objects, &scene.camera, &scene.lights, sky, &settings);
+    let edge = (0..40).find(|&x| ![sky, Color::white()].contains(&plain.get_pixel(x, 15))).expect("hay un borde suavizado");
+
+    let mut reprojection = Reprojection::default();
+    for frame in 0..2 {
+        let mut reprojected = Framebuffer::new(40, 30);
+        let stats = render_reprojected(&mut reprojected, &scene.objects, &scene.camera, &scene.lights, sky, &settings, &mut reprojection);
+        assert_eq!(stats.reused_pixels > 0, frame == 1, "frame {}: {:?}", frame, stats);
+        assert_eq!(reprojected.get_pixel(edge, 15), plain.get_pixel(edge, 15), "frame {}", frame);
+        assert_eq!(reprojected.buffer, plain.buffer, "frame {}", frame);
+    }
+}
+
+#[test]
+fn downsampling_weights_colors_by_coverage() {
+    // Un píxel hecho de dos muestras rojas del cubo y dos del cielo
+    // transparente: queda cubierto a medias y rojo, sin el azul del cielo
+    let red = Color::new(200, 30, 30);
+    let mut framebuffer = Framebuffer::new(2, 2);
+    framebuffer.buffer = vec![red, Color::new(90, 150, 230), red, Color::new(90, 150, 230)];
+    framebuffer.alpha = vec![255, 0, 255, 0];
+    let half = framebuffer.downsample(2, true);
+    assert_eq!((half.get_pixel(0, 0), half.alpha[0]), (red, 128));
+
+    // Con todo opaco el promedio es en luz lineal, como al suavizar por píxel
+    framebuffer.buffer = vec![Color::black(), Color::white(), Color::black(), Color::white()];
+    framebuffer.alpha = vec![255; 4];
+    assert_eq!(framebuffer.downsample(2, true).get_pixel(0, 0), Color::new(188, 188, 188));
+
+    // En el borde de un cubo blanco contra el fondo transparente, los
+    // píxeles entre medio tienen alfa fraccionario y el color del cubo
+    let white = Material { unlit: true, ..Material::new(Color::white(), 1.0, [1.0, 0.0, 0.0, 0.0], 1.0) };
+    let scene = cube_scene(white, light_at(0.0, Color::white()));
+    let settings = RenderSettings { transparent: true, ..RenderSettings::default() };
+    let mut large = Framebuffer::new(160, 120);
+    render(&mut large, &scene.objects, &scene.camera, &scene.lights, sky_color(&scene.lights), &settings);
+    let small = large.downsample(4, true);
+    let edges: Vec<usize> = (0..40).map(|x| 15 * 40 + x).filter(|&index| small.alpha[index] > 0 && small.alpha[index] < 255).collect();
+    assert!(!edges.is_empty(), "la fila cruza el borde del cubo");
+    for index in edges {
+        assert_eq!(small.buffer[index], Color::white(), "alfa {}", small.alpha[index]);
+    }
+}
+
+#[test]
+fn opaque_downsampling_keeps_the_sky() {
+    // En un render opaco el cielo se ve aunque no tenga alfa: los píxeles
+    // que son todo cielo quedan del color del cielo, no negros
+    let sky = Color::new(90, 150, 230);
+    let mut framebuffer = Framebuffer::new(4, 2);
+    framebuffer.buffer = vec![sky; 8];
+    framebuffer.alpha = vec![0; 8];
+    framebuffer.buffer[2] = Color::black();
+    framebuffer.buffer[6] = Color::black();
+    framebuffer.alpha[2] = 255;
+    framebuffer.alpha[6] = 255;
+    let half = framebuffer.downsample(2, false);
+    assert_eq!((half.get_pixel(0, 0), half.alpha[0]), (sky, 0));
+    // Media mezcla con el cubo cuenta el cielo como una muestra más
+    assert_ne!(half.get_pixel(1, 0), Color::black());
+    assert_ne!(half.get_pixel(1, 0), sky);
+    assert_eq!(half.alpha[1], 128);
+    // Con fondo transparente el mismo píxel toma solo el color del cubo
+    assert_eq!(framebuffer.downsample(2, true).get_pixel(1, 0), Color::black());
+}
+
+#[test]
+fn tiny_scene_renders_known_pixels() {
+    // Cubo sin iluminar de frente en un framebuffer de 64x64: el centro
+    // muestra su color tal cual a unas 3 unidades del ojo y las esquinas el cielo,
+    // con alfa 0 y sin profundidad
+    let red = Color::new(200, 30, 30);
+    let scene = cube_scene(Material { unlit: true, ..Material::new(red, 1.0, [1.0, 0.0, 0.0, 0.0], 1.0) }, light_at(0.0, Color::white()));
+    let settings = RenderSettings { bias: scene.default_bias(), ..RenderSettings::default() };
+    let mut framebuffer = Framebuffer::new(64, 64);
+    render(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, sky_color(&scene.lights), &settings);
+
+    for (x, y) in [(31, 31), (32, 32), (28, 36)] {
+        assert_eq!(framebuffer.get_pixel(x, y), red, "píxel ({}, {})", x, y);
+        assert_eq!(framebuffer.alpha[y * 64 + x], 255);
+        // La profundidad es el largo del rayo, que se alarga apenas lejos del centro
+        assert!((3.0..3.05).contains(&framebuffer.depth[y * 64 + x]));
+    }
+    for (x, y) in [(0, 0), (63, 0), (0, 63), (63, 63)] {
+        assert_eq!(framebuffer.alpha[y * 64 + x], 0);
+        assert!(framebuffer.depth[y * 64 + x].is_infinite());
+    }
+    // El cielo es un degradado vertical: las esquinas de una misma fila casi
+    // coinciden, salvo por el medio píxel en que los rayos no son simétricos
+    for (left, right) in [((0, 0), (63, 0)), ((0, 63), (63, 63))] {
+        let (left, right) = (framebuffer.get_pixel(left.0, left.1), framebuffer.get_pixel(right.0, right.1));
+        assert!(left.red().abs_diff(right.red()) <= 2 && left.green().abs_diff(right.green()) <= 2 && left.blue().abs_diff(right.blue()) <= 2);
+    }
+}
+
+#[test]
+fn builder_sets_the_weights_by_name() {
+    let built = Material::builder().color(Color::new(10, 20, 30)).reflectivity(0.3).transparency(0.2).build();
+    let positional = Material::new(Color::new(10, 20, 30), 1.0, [1.0, 0.0, 0.3, 0.2], 1.0);
+    assert_eq!(
+        (built.diffuse, built.specular, built.reflectivity, built.transparency),
+        (positional.diffuse, positional.specular, positional.reflectivity, positional.transparency)
+    );
+    assert_eq!((built.color, built.shininess, built.refractive_index), (positional.color, positional.shininess, positional.refractive_index));
+    assert!(built.is_reflective() && built.is_transparent() && !built.is_diffuse());
+    assert!(Material::builder().build().is_diffuse());
+}
+
+#[test]
+fn the_ray_helpers_work_outside_the_renderer() {
+    let close = |a: Vec3, b: Vec3| (a - b).norm() < 1e-5;
+    let down = Vec3::new(1.0, -1.0, 0.0).normalize();
+    let up = Vec3::new(0.0, 1.0, 0.0);
+    assert!(close(reflect(&down, &up), Vec3::new(1.0, 1.0, 0.0).normalize()));
+    // De frente el rayo pasa derecho; en diagonal se acerca a la normal al
+    // entrar y, al salir más allá del ángulo crítico, se refleja
+    assert!(close(refract(&-up, &up, 1.5), -up));
+    let inside = refract(&down, &up, 1.5);
+    assert!((inside.x - down.x / 1.5).abs() < 1e-5 && inside.y < 0.0, "{:?}", inside);
+    let grazing = Vec3::new(1.0, 0.3, 0.0).normalize();
+    assert!(close(refract(&grazing, &up, 1.5), reflect(&grazing, &up)));
+
+    let cube = Cube::new(Vec3::new(-1.0, -1.0, -1.0), Vec3::new(1.0, 1.0, 1.0), &Material::black());
+    let hit = cube.ray_intersect(&Vec3::new(0.0, 3.0, 0.0), &-up);
+    assert!(close(offset_origin(&hit, &up, 0.01), Vec3::new(0.0, 1.01, 0.0)));
+    assert!(close(offset_origin(&hit, &-up, 0.01), Vec3::new(0.0, 0.99, 0.0)));
+
+    // Una placa a un cuarto del camino hacia la luz da una sombra de 1 - 0.25²
+    let light = SceneLight::new(Vec3::new(0.0, 5.0, 0.0), Color::white(), 1.0);
+    let blocker = SceneObject::Cube(Cube::new(Vec3::new(-0.5, 2.0, -0.5), Vec3::new(0.5, 2.1, 0.5), &Material::black()));
+    assert_eq!(cast_shadow(&hit, &light, &[], None, 0.01, ShadowQuality::Hard), 0.0);
+    let shadow = cast_shadow(&hit, &light, &[blocker], None, 0.01, ShadowQuality::Hard);
+    assert!((shadow - 0.9375).abs() < 0.01, "sombra {}", shadow);
+
+    // Un rayo al centro del cubo sin iluminar devuelve su color
+    let red = Color::new(200, 30, 30);
+    let scene = cube_scene(Material { unlit: true, ..Material::new(red, 1.0, [1.0, 0.0, 0.0, 0.0], 1.0) }, light_at(0.0, Color::white()));
+    let settings = RenderSettings { bias: scene.default_bias(), ..RenderSettings::default() };
+    let eye = scene.camera.eye;
+    let color = cast_ray(&eye, &-eye.normalize(), &scene.objects, &scene.lights, 0, 1.0, sky_color(&scene.lights), &[], &settings, None);
+    assert_eq!(color.to_srgb(), red);
+}
+
+#[test]
+fn the_default_sun_is_directional() {
+    let file = Scene::load("scenes/portal.ron").expect("la escena del repositorio carga");
+    let built = DioramaBuilder::new().add_cube(Vec3::zeros(), Vec3::repeat(0.5), "obsidian").build().expect("la obsidiana es del registro");
+    for (name, scene) in [("portal.ron", file), ("integrado", Scene::diorama()), ("builder", built)] {
+        let mut suns: Vec<SceneLight> = scene.lights.into_iter().filter(|light| light.is_sun).collect();
+        assert_eq!(suns.len(), 1, "{}", name);
+        let sun = &mut suns[0];
+        assert_eq!(sun.kind, LightKind::Directional, "{}", name);
+
+        // Los rayos son paralelos en toda la base y a lo largo del día pasan
+        // de venir del este a venir del oeste
+        sun.set_time(DAY_DURATION * 0.1);
+        let (morning, _) = sun.toward(&Vec3::new(-1.4, 0.0, -1.4));
+        assert!((morning - sun.toward(&Vec3::new(1.4, 0.0, 1.4)).0).norm() < 1e-6, "{}", name);
+        sun.set_time(DAY_DURATION * 0.4);
+        let (afternoon, _) = sun.toward(&Vec3::zeros());
+        assert!(morning.x > 0.0 && afternoon.x < 0.0 && morning.y > 0.0 && afternoon.y > 0.0, "{}: {:?} y {:?}", name, morning, afternoon);
+    }
+}
+
+#[test]
+fn lava_and_portal_light_their_corners() {
+    let file = Scene::load("scenes/portal.ron").expect("la escena del repositorio carga");
+    let describe = |scene: &Scene| -> Vec<String> {
+        let local = scene.lights.iter().filter(|light| !light.is_sun);
+        local.map(|light| format!("{:?} {:?} {} {:?} {}", light.position.as_slice(), light.color, light.intensity, light.falloff, light.radius)).collect()
+    };
+    // El diorama integrado tiene las mismas luces que el archivo
+    assert_eq!(describe(&file), describe(&Scene::diorama()));
+
+    // Cada bloque de lava y el portal tienen al lado una luz chica que se apaga
+    // con la distancia
+    let near = |object: &SceneObject| {
+        let bounds = object.bounds();
+        let center = (bounds.min + bounds.max) * 0.5;
+        file.lights.iter().any(|light| {
+            !light.is_sun && light.kind == LightKind::Point && light.falloff[1] > 0.0 && light.radius > 0.0 && light.radius < 0.1
+                && (light.position - center).norm() < 0.6
+        })
+    };
+    let lit = |name: &str| file.objects.iter().filter(|object| object.material().name == name).all(near);
+    assert!(lit("lava") && lit("purple"));
+    // Las de la lava son cálidas
+    let lava: Vec<&SceneLight> = file.lights.iter().filter(|light| (light.position.y - 0.1).abs() < 1e-6).collect();
+    assert!(lava.len() == 4 && lava.iter().all(|light| light.color.red() > light.color.blue()));
+}
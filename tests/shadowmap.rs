@@ -0,0 +1,83 @@
+use raycasting_diorama::color::Color;
+use raycasting_diorama::light::{LightKind, SceneLight};
+use raycasting_diorama::material::Material;
+use raycasting_diorama::ray_intersect::RayIntersect;
+use raycasting_diorama::renderer::cast_shadow;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::{ShadowMapSettings, ShadowQuality};
+use raycasting_diorama::shadowmap::refresh;
+use raycasting_diorama::{DioramaBuilder, Vec3};
+
+// Piso con un bloque encima y un sol que llega en diagonal desde +X
+fn block_on_floor(sun: SceneLight) -> Scene {
+    DioramaBuilder::new()
+        .material("stone", Material::new(Color::new(160, 150, 140), 10.0, [0.9, 0.1, 0.0, 0.0], 1.0))
+        .add_cube(Vec3::new(-3.0, -0.6, -3.0), Vec3::new(3.0, -0.5, 3.0), "stone")
+        .add_cube(Vec3::new(-0.5, -0.5, -0.5), Vec3::new(0.5, 0.5, 0.5), "stone")
+        .add_light(sun)
+        .build()
+        .expect("los materiales están registrados")
+}
+
+fn directional_sun() -> SceneLight {
+    let mut sun = SceneLight::sun(Vec3::new(3.0, 3.0, 0.5), Color::white(), 1.0);
+    sun.kind = LightKind::Directional;
+    sun
+}
+
+// Sombra que da el rayo desde un punto del piso, para comparar con el mapa
+fn traced(scene: &Scene, point: Vec3) -> f32 {
+    let hit = scene.objects[0].ray_intersect(&(point + Vec3::new(0.0, 2.0, 0.0)), &Vec3::new(0.0, -1.0, 0.0));
+    assert!(hit.is_intersecting);
+    let mut light = scene.lights[0].clone();
+    light.shadow_map = None;
+    cast_shadow(&hit, &light, &scene.objects, None, scene.default_bias(), ShadowQuality::Hard)
+}
+
+#[test]
+fn a_directional_sun_gets_an_orthographic_map() {
+    let mut scene = block_on_floor(directional_sun());
+    let settings = ShadowMapSettings { resolution: 256, ..ShadowMapSettings::default() };
+    assert_eq!(refresh(&mut scene.lights, &scene.objects, Some(&settings)), 1);
+    let map = scene.lights[0].shadow_map.clone().expect("el sol direccional tiene mapa");
+
+    // Detrás del bloque, del lado opuesto al sol, el piso queda en sombra; al
+    // frente y a los costados no
+    let behind = Vec3::new(-1.3, -0.5, -0.2);
+    let shadow = map.shadow(&behind).expect("el punto cae en el mapa");
+    assert!(shadow > 0.9, "detrás del bloque: {}", shadow);
+    for point in [Vec3::new(1.5, -0.5, 0.0), Vec3::new(-1.3, -0.5, 2.0), Vec3::new(0.0, 0.5, 0.0)] {
+        assert_eq!(map.shadow(&point), Some(0.0), "{:?}", point);
+    }
+    // Por encima de todo o fuera de la caja de los objetos tampoco hay sombra
+    assert_eq!(map.shadow(&Vec3::new(0.0, 10.0, 0.0)), Some(0.0));
+    assert_eq!(map.shadow(&Vec3::new(40.0, -0.5, 0.0)), Some(0.0));
+
+    // En una línea que cruza el borde de la sombra, hasta el pie del bloque, el
+    // mapa dice lo mismo que el rayo salvo en las celdas del borde
+    let disagree = (0..40)
+        .map(|step| Vec3::new(-2.5 + step as f32 * 0.05, -0.5, 0.1))
+        .filter(|&point| (map.shadow(&point).unwrap_or(0.0) - traced(&scene, point)).abs() > 0.5)
+        .count();
+    assert!(disagree <= 2, "{} puntos no coinciden con el rayo", disagree);
+}
+
+#[test]
+fn the_map_follows_the_sun() {
+    let mut scene = block_on_floor(directional_sun());
+    let settings = ShadowMapSettings { resolution: 128, ..ShadowMapSettings::default() };
+    refresh(&mut scene.lights, &scene.objects, Some(&settings));
+    // Quieto no se regenera; con el sol del otro lado la sombra cambia de lado
+    assert_eq!(refresh(&mut scene.lights, &scene.objects, Some(&settings)), 0);
+    scene.lights[0].position = Vec3::new(-3.0, 3.0, 0.5);
+    assert_eq!(refresh(&mut scene.lights, &scene.objects, Some(&settings)), 1);
+    let map = scene.lights[0].shadow_map.clone().expect("sigue teniendo mapa");
+    assert!(map.shadow(&Vec3::new(1.3, -0.5, -0.2)).unwrap_or(0.0) > 0.9);
+    assert_eq!(map.shadow(&Vec3::new(-1.3, -0.5, -0.2)), Some(0.0));
+
+    // El sol puntual sigue con su cubo, y sin opciones no hay mapa
+    scene.lights[0].kind = LightKind::Point;
+    assert_eq!(refresh(&mut scene.lights, &scene.objects, Some(&settings)), 1);
+    refresh(&mut scene.lights, &scene.objects, None);
+    assert!(scene.lights[0].shadow_map.is_none());
+}
@@ -0,0 +1,52 @@
+use raycasting_diorama::color::Color;
+use raycasting_diorama::light::SceneLight;
+use raycasting_diorama::renderer::{gradient_sky, sky_color, with_sun_and_moon};
+use raycasting_diorama::Vec3;
+
+fn sun_at(position: Vec3) -> Vec<SceneLight> {
+    vec![SceneLight::sun(position, Color::new(255, 240, 200), 1.0)]
+}
+
+fn brightness(color: Color) -> u32 {
+    color.red() as u32 + color.green() as u32 + color.blue() as u32
+}
+
+#[test]
+fn the_zenith_is_the_sky_color_and_the_horizon_is_lighter() {
+    let lights = sun_at(Vec3::new(2.0, 6.0, 3.0));
+    let sky = sky_color(&lights);
+    let zenith = gradient_sky(sky, &lights, &Vec3::new(0.0, 1.0, 0.0));
+    let high = gradient_sky(sky, &lights, &Vec3::new(0.0, 1.0, 1.0));
+    let horizon = gradient_sky(sky, &lights, &Vec3::new(0.0, 0.0, 1.0));
+    let below = gradient_sky(sky, &lights, &Vec3::new(0.0, -1.0, 1.0));
+
+    assert_eq!(zenith, sky);
+    assert!(brightness(zenith) < brightness(high));
+    assert!(brightness(high) < brightness(horizon));
+    // Debajo del horizonte se oscurece
+    assert!(brightness(below) < brightness(horizon));
+}
+
+#[test]
+fn the_sun_disc_shows_only_toward_the_sun() {
+    let lights = sun_at(Vec3::new(0.0, 5.0, 5.0));
+    let toward_sun = Vec3::new(0.0, 1.0, 1.0);
+    let black = Color::black();
+
+    assert_eq!(with_sun_and_moon(black, &lights, &toward_sun), lights[0].color);
+    // Apenas corrido del centro sigue siendo sol, y lejos queda el cielo
+    assert_eq!(with_sun_and_moon(black, &lights, &Vec3::new(0.01, 1.0, 1.0)), lights[0].color);
+    assert_eq!(with_sun_and_moon(black, &lights, &Vec3::new(0.3, 1.0, 1.0)), black);
+    // De día no hay luna en el lado opuesto
+    assert_eq!(with_sun_and_moon(black, &lights, &-toward_sun), black);
+}
+
+#[test]
+fn at_night_the_moon_rises_opposite_the_sun() {
+    let lights = sun_at(Vec3::new(0.0, -5.0, 5.0));
+    let black = Color::black();
+
+    assert_eq!(with_sun_and_moon(black, &lights, &Vec3::new(0.0, -1.0, 1.0)), black);
+    let moon = with_sun_and_moon(black, &lights, &Vec3::new(0.0, 1.0, -1.0));
+    assert!(brightness(moon) > 600, "{:?}", moon);
+}
@@ -0,0 +1,105 @@
+use raycasting_diorama::camera::Camera;
+use raycasting_diorama::color::Color;
+use raycasting_diorama::decal::Face;
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::light::SceneLight;
+use raycasting_diorama::renderer::{render, sky_color};
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::skybox::{cube_uv, equirectangular_uv, Skybox, FACES};
+use raycasting_diorama::texture::Texture;
+use raycasting_diorama::Vec3;
+
+const FACE_COLORS: [[u8; 3]; 6] = [[255, 0, 0], [0, 255, 0], [0, 0, 255], [255, 255, 0], [0, 255, 255], [255, 0, 255]];
+
+fn close(a: (f32, f32), b: (f32, f32)) -> bool {
+    (a.0 - b.0).abs() < 1e-5 && (a.1 - b.1).abs() < 1e-5
+}
+
+// Cubo con cada cara de un color, guardado como `cara_{}.png`
+fn solid_cubemap() -> Skybox {
+    let dir = std::env::temp_dir().join(format!("diorama_skybox_{}", std::process::id()));
+    std::fs::create_dir_all(&dir).expect("se crea el directorio");
+    for ((_, name), color) in FACES.iter().zip(FACE_COLORS) {
+        image::RgbImage::from_pixel(4, 4, image::Rgb(color)).save(dir.join(format!("cara_{}.png", name))).expect("se guarda la cara");
+    }
+    let skybox = Skybox::load(&dir.join("cara_{}.png").to_string_lossy()).expect("están las seis caras");
+    std::fs::remove_dir_all(&dir).ok();
+    skybox
+}
+
+// Imagen equirrectangular con una franja de color por fila: rojo arriba,
+// verde en el horizonte y azul abajo
+fn banded_panorama() -> Skybox {
+    let bands = [[255, 0, 0], [0, 255, 0], [0, 0, 255]];
+    let image = image::RgbImage::from_fn(8, 3, |_, y| image::Rgb(bands[y as usize]));
+    let mut png = Vec::new();
+    image.write_to(&mut std::io::Cursor::new(&mut png), image::ImageFormat::Png).expect("se codifica en memoria");
+    Skybox::Equirectangular(Texture::from_bytes(&png, "panorama.png").expect("es un PNG válido"))
+}
+
+#[test]
+fn directions_map_to_cube_faces() {
+    assert_eq!(cube_uv(&Vec3::new(0.0, 1.0, 0.0)), (Face::Y, 0.5, 0.5));
+    assert_eq!(cube_uv(&Vec3::new(0.0, -3.0, 0.0)), (Face::NegY, 0.5, 0.5));
+    assert_eq!(cube_uv(&Vec3::new(0.0, 0.0, -1.0)), (Face::NegZ, 0.5, 0.5));
+
+    // Mirando a -Z, +X queda a la derecha y +Y arriba
+    let (face, u, v) = cube_uv(&Vec3::new(0.5, 0.5, -1.0));
+    assert_eq!(face, Face::NegZ);
+    assert!(close((u, v), (0.75, 0.25)));
+    // La cara de arriba sigue a la de -Z: su borde de abajo es el de arriba de -Z
+    let (face, u, v) = cube_uv(&Vec3::new(0.0, 1.0, -0.999));
+    assert_eq!(face, Face::Y);
+    assert!(close((u, v), (0.5, 0.9995)));
+    // Mirando a +Z la derecha es -X
+    let (face, u, _) = cube_uv(&Vec3::new(-1.0, 0.0, 2.0));
+    assert_eq!(face, Face::Z);
+    assert!((u - 0.75).abs() < 1e-5);
+}
+
+#[test]
+fn directions_map_to_longitude_and_latitude() {
+    assert!(close(equirectangular_uv(&Vec3::new(0.0, 0.0, -1.0)), (0.5, 0.5)));
+    assert!(close(equirectangular_uv(&Vec3::new(2.0, 0.0, 0.0)), (0.75, 0.5)));
+    assert!(close(equirectangular_uv(&Vec3::new(-1.0, 0.0, 0.0)), (0.25, 0.5)));
+    assert_eq!(equirectangular_uv(&Vec3::new(0.0, 1.0, 0.0)).1, 0.0);
+    assert_eq!(equirectangular_uv(&Vec3::new(0.0, -1.0, 0.0)).1, 1.0);
+}
+
+#[test]
+fn each_direction_samples_its_face() {
+    let skybox = solid_cubemap();
+    let directions = [(1.0, 0.2, 0.3), (-1.0, -0.4, 0.1), (0.1, 1.0, 0.0), (0.3, -1.0, 0.2), (0.0, 0.5, 1.0), (-0.2, 0.0, -1.0)];
+    for ((x, y, z), [r, g, b]) in directions.into_iter().zip(FACE_COLORS) {
+        assert_eq!(skybox.color(&Vec3::new(x, y, z)), Color::new(r, g, b), "dirección ({}, {}, {})", x, y, z);
+    }
+}
+
+#[test]
+fn straight_up_samples_the_top_row_of_the_panorama() {
+    let skybox = banded_panorama();
+    for direction in [Vec3::new(0.0, 1.0, 0.0), Vec3::new(1e-4, 1.0, 0.0), Vec3::new(0.0, 1.0, 1e-4)] {
+        assert_eq!(skybox.color(&direction), Color::new(255, 0, 0));
+    }
+    assert_eq!(skybox.color(&Vec3::new(0.0, 0.0, -1.0)), Color::new(0, 255, 0));
+    assert_eq!(skybox.color(&Vec3::new(0.7, 0.0, 0.7)), Color::new(0, 255, 0));
+    assert_eq!(skybox.color(&Vec3::new(0.0, -1.0, 0.0)), Color::new(0, 0, 255));
+}
+
+#[test]
+fn night_darkens_the_skybox() {
+    let render_sky = |sun_height: f32| {
+        let lights = vec![SceneLight::sun(Vec3::new(0.0, sun_height, 1.0), Color::white(), 1.0)];
+        let camera = Camera::new(Vec3::new(0.0, 0.0, 1.0), Vec3::new(0.0, 0.0, 0.0), Vec3::new(0.0, 1.0, 0.0));
+        let settings = RenderSettings { skybox: Some(banded_panorama()), ..RenderSettings::default() };
+        let mut framebuffer = Framebuffer::new(8, 8);
+        render(&mut framebuffer, &[], &camera, &lights, sky_color(&lights), &settings);
+        framebuffer.get_pixel(4, 4)
+    };
+
+    let day = render_sky(2.0);
+    let night = render_sky(-2.0);
+    // De día la imagen se ve tal cual, sin el disco del sol encima
+    assert_eq!(day, Color::new(0, 255, 0));
+    assert!(night.green() < 80, "{:?}", night);
+}
@@ -0,0 +1,23 @@
+use raycasting_diorama::scene::Scene;
+
+#[test]
+fn the_ray_estimate_follows_the_render_settings() {
+    // El diorama tiene vidrio y espejos, así que cada rebote suma rayos
+    let stats = Scene::diorama().stats();
+    assert!(stats.branching_factor > 0.0);
+    let estimate = |width, height, depth, samples| stats.estimated_rays_per_frame(width, height, 2, depth, samples);
+    let base = estimate(400, 300, 3, 1);
+
+    // El doble de píxeles o de muestras por píxel, el doble de rayos
+    assert!(estimate(800, 300, 3, 1).abs_diff(2 * base) <= 2);
+    assert!(estimate(400, 600, 3, 1).abs_diff(2 * base) <= 2);
+    assert!(estimate(400, 300, 3, 4).abs_diff(4 * base) <= 4);
+    // Más rebotes, más rayos; sin rebotes, solo los primarios y sus sombras
+    assert!(estimate(400, 300, 5, 1) > base && estimate(400, 300, 2, 1) < base);
+    assert_eq!(estimate(400, 300, 0, 1), 400 * 300 * 3);
+    // Cero muestras se cuenta como una, igual que al renderizar
+    assert_eq!(estimate(400, 300, 3, 0), base);
+
+    let report = stats.report(640, 480, 2, 5, 4);
+    assert!(report.ends_with(&format!("Rayos por frame (est.): {} a 640x480, 4 muestras y 5 rebotes", estimate(640, 480, 5, 4))), "{}", report);
+}
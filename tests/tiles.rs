@@ -0,0 +1,49 @@
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::renderer::{render, sky_color};
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::{Crop, RenderSettings};
+
+// Tamaño que no es múltiplo de las teselas, así quedan teselas cortadas en
+// el borde derecho y el de abajo
+const WIDTH: usize = 150;
+const HEIGHT: usize = 97;
+
+fn render_tiles(scene: &Scene, settings: &RenderSettings, tile_size: usize) -> Framebuffer {
+    let settings = RenderSettings { tile_size, ..settings.clone() };
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    render(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, sky_color(&scene.lights), &settings);
+    framebuffer
+}
+
+fn assert_same(expected: &Framebuffer, actual: &Framebuffer, tile_size: usize) {
+    assert!(expected.buffer == actual.buffer, "los colores cambian con teselas de {}", tile_size);
+    assert!(expected.alpha == actual.alpha, "el alfa cambia con teselas de {}", tile_size);
+    let same_depth = expected.depth.iter().zip(&actual.depth).all(|(a, b)| a.to_bits() == b.to_bits());
+    assert!(same_depth, "la profundidad cambia con teselas de {}", tile_size);
+}
+
+#[test]
+fn tiles_match_the_per_pixel_render() {
+    let scene = Scene::diorama();
+    let settings = RenderSettings { bias: scene.default_bias(), ..RenderSettings::default() };
+    // Teselas de un píxel: cada tarea traza un solo píxel
+    let reference = render_tiles(&scene, &settings, 1);
+    for tile_size in [7, 32, 64, WIDTH * 2] {
+        assert_same(&reference, &render_tiles(&scene, &settings, tile_size), tile_size);
+    }
+}
+
+#[test]
+fn tiles_cover_a_crop_with_supersampling() {
+    let scene = Scene::diorama();
+    let settings = RenderSettings {
+        bias: scene.default_bias(),
+        samples_per_pixel: 4,
+        crop: Some(Crop { x: 23, y: 11, width: 61, height: 45 }),
+        ..RenderSettings::default()
+    };
+    let reference = render_tiles(&scene, &settings, 1);
+    for tile_size in [16, 32] {
+        assert_same(&reference, &render_tiles(&scene, &settings, tile_size), tile_size);
+    }
+}
@@ -0,0 +1,75 @@
+use std::fs;
+use std::path::PathBuf;
+
+use raycasting_diorama::color::Palette;
+use raycasting_diorama::renderer::sky_color;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::thumbnail::render_thumbnail;
+use raycasting_diorama::tools::{generate_thumbnails, run_diff};
+
+const SCENE: &str = r#"(
+    materials: {"piedra": (color: (150, 140, 130), shininess: 10.0, properties: (0.9, 0.1, 0.0, 0.0))},
+    lights: [(position: (2.0, 4.0, 3.0), color: (255, 255, 255), intensity: 1.0)],
+    objects: [(min: (-0.5, -0.5, -0.5), max: (0.5, 0.5, 0.5), material: "piedra")],
+)"#;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("diorama_tools_{}_{}", std::process::id(), name));
+    fs::remove_dir_all(&dir).ok();
+    fs::create_dir_all(&dir).expect("se crea el directorio temporal");
+    dir
+}
+
+#[test]
+fn diff_exits_like_diff1() {
+    let dir = temp_dir("diff");
+    let write = |name: &str, text: &str| {
+        let path = dir.join(name);
+        fs::write(&path, text).expect("se escribe la escena");
+        path.to_string_lossy().into_owned()
+    };
+    let before = write("antes.ron", SCENE);
+    let same = write("igual.ron", SCENE);
+    let moved = write("despues.ron", &SCENE.replace("max: (0.5, 0.5, 0.5)", "max: (0.5, 1.5, 0.5)"));
+    let image = dir.join("diff.png");
+
+    assert_eq!(run_diff(&before, &same, None, 16, 12, Palette::Default), 0);
+    assert_eq!(run_diff(&before, &moved, Some(&image.to_string_lossy()), 16, 12, Palette::Default), 1);
+    assert!(image.exists(), "la imagen de las diferencias se guardó");
+    assert_eq!(run_diff(&before, &dir.join("no_existe.ron").to_string_lossy(), None, 16, 12, Palette::Default), 2);
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn thumbnails_skip_broken_scenes_and_report_them() {
+    let dir = temp_dir("miniaturas");
+    fs::write(dir.join("bloque.ron"), SCENE).expect("se escribe la escena");
+    fs::write(dir.join("rota.ron"), "(objects: [").expect("se escribe la escena");
+    fs::write(dir.join("notas.txt"), "no es una escena").expect("se escribe el archivo");
+
+    assert!(!generate_thumbnails(&dir.to_string_lossy(), 16), "una escena rota hace fallar el conjunto");
+    let thumbnail = image::open(dir.join("bloque.thumb.png")).expect("la escena buena tiene miniatura");
+    assert_eq!((thumbnail.width(), thumbnail.height()), (16, 16));
+    assert!(!dir.join("rota.thumb.png").exists() && !dir.join("notas.thumb.png").exists());
+
+    fs::remove_file(dir.join("rota.ron")).expect("se borra la escena rota");
+    assert!(generate_thumbnails(&dir.to_string_lossy(), 16));
+    assert!(!generate_thumbnails(&dir.join("no_existe").to_string_lossy(), 16));
+    fs::remove_dir_all(&dir).ok();
+}
+
+#[test]
+fn thumbnails_keep_the_sky_around_the_framed_scene() {
+    let dir = temp_dir("cielo");
+    let path = dir.join("bloque.ron").to_string_lossy().into_owned();
+    fs::write(&path, SCENE).expect("se escribe la escena");
+    let thumbnail = render_thumbnail(&path, 16).expect("la escena carga");
+    // La esquina es todo cielo: el promedio de sus muestras sin alfa sigue
+    // siendo el cielo, con algo de la bruma del horizonte, y no negro
+    let sky = sky_color(&Scene::load(&path).expect("la escena carga").lights);
+    let corner = thumbnail.get_pixel(0, 0).0;
+    for (channel, expected) in corner.into_iter().zip([sky.red(), sky.green(), sky.blue()]) {
+        assert!(channel.abs_diff(expected) <= 24, "{:?} contra el cielo {:?}", corner, sky);
+    }
+    fs::remove_dir_all(&dir).ok();
+}
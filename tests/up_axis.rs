@@ -0,0 +1,66 @@
+use raycasting_diorama::color::Color;
+use raycasting_diorama::light::SceneLight;
+use raycasting_diorama::renderer::{gradient_sky, sky_color, with_sun_and_moon};
+use raycasting_diorama::scene::{Scene, UpAxis, WORLD_UP};
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::sky;
+use raycasting_diorama::{Renderer, Vec3};
+
+// Las dos escenas del repositorio con la base, la lava y el sol, una escrita
+// con Y arriba y la otra con Z arriba, al segundo `time` del día
+fn pair(time: f32) -> [Scene; 2] {
+    ["scenes/light_linking.ron", "scenes/light_linking_z_up.ron"].map(|path| {
+        let mut scene = Scene::load(path).expect("la escena del repositorio carga");
+        for light in scene.lights.iter_mut().filter(|light| light.is_sun) {
+            light.set_time(time);
+        }
+        scene
+    })
+}
+
+fn render(scene: &mut Scene, physical_sky: bool) -> Vec<u8> {
+    let mut settings = RenderSettings::default();
+    sky::refresh(&mut settings.sky, &mut scene.lights, physical_sky.then_some(3.0));
+    // Un poco por encima de la base, así el cielo ocupa la mitad de arriba
+    let mut camera = scene.camera.clone();
+    camera.center += WORLD_UP * 1.5;
+    Renderer::new(settings).size(64, 48).render(scene, &camera).into_raw()
+}
+
+#[test]
+fn every_up_axis_is_brought_to_the_world_up() {
+    let file_up = [
+        (UpAxis::X, Vec3::new(1.0, 0.0, 0.0)),
+        (UpAxis::Y, Vec3::new(0.0, 1.0, 0.0)),
+        (UpAxis::Z, Vec3::new(0.0, 0.0, 1.0)),
+        (UpAxis::NegX, Vec3::new(-1.0, 0.0, 0.0)),
+        (UpAxis::NegY, Vec3::new(0.0, -1.0, 0.0)),
+        (UpAxis::NegZ, Vec3::new(0.0, 0.0, -1.0)),
+    ];
+    for (axis, up) in file_up {
+        assert_eq!(axis.to_y_up(up), WORLD_UP, "{:?}", axis);
+    }
+
+    // El cielo degradado y el sol se miden contra el mundo: el cenit es el
+    // color del cielo y el sol en lo alto se dibuja
+    let lights = vec![SceneLight::sun(WORLD_UP * 3.0, Color::white(), 1.0)];
+    assert_eq!(gradient_sky(sky_color(&lights), &lights, &WORLD_UP), sky_color(&lights));
+    assert_eq!(with_sun_and_moon(Color::black(), &lights, &WORLD_UP), Color::white());
+}
+
+#[test]
+fn z_up_and_y_up_scenes_render_the_same_sky_and_sun() {
+    // De día, al atardecer y de noche, con el cielo degradado y con el físico
+    for time in [2.5, 4.6, 7.5] {
+        for physical_sky in [false, true] {
+            let [mut y_up, mut z_up] = pair(time);
+            let golden = render(&mut y_up, physical_sky);
+            assert_eq!(render(&mut z_up, physical_sky), golden, "segundo {} (cielo físico: {})", time, physical_sky);
+
+            // De día se ve el degradado del cielo: la fila de arriba no es la
+            // del horizonte
+            let row = |y: usize| &golden[y * 64 * 3..(y + 1) * 64 * 3];
+            assert!(time > 5.0 || row(0) != row(20), "segundo {}", time);
+        }
+    }
+}
@@ -0,0 +1,155 @@
+use raycasting_diorama::color::Color;
+use raycasting_diorama::cube::Cube;
+use raycasting_diorama::light::SceneLight;
+use raycasting_diorama::decal::Face;
+use raycasting_diorama::material::Material;
+use raycasting_diorama::merge::merge_cubes;
+use raycasting_diorama::object::SceneObject;
+use raycasting_diorama::ray_intersect::RayIntersect;
+use raycasting_diorama::registry::default_materials;
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::{DioramaBuilder, Renderer, Vec3};
+
+const FACES: [Face; 6] = [Face::X, Face::NegX, Face::Y, Face::NegY, Face::Z, Face::NegZ];
+
+// Coordenadas de textura donde un rayo que llega de frente a la cara toca el punto
+fn uv_at(cube: &Cube, face: Face, point: Vec3) -> (f32, f32) {
+    let normal = face.normal();
+    let hit = cube.ray_intersect(&(point + normal * 3.0), &-normal);
+    assert!(hit.is_intersecting, "el rayo llega a la cara {:?}", face);
+    assert_eq!(hit.normal, normal);
+    hit.uv
+}
+
+fn assert_near(found: (f32, f32), expected: (f32, f32)) {
+    let close = (found.0 - expected.0).abs() < 1e-4 && (found.1 - expected.1).abs() < 1e-4;
+    assert!(close, "uv {:?}, se esperaba {:?}", found, expected);
+}
+
+#[test]
+fn face_centers_map_to_the_middle_of_the_texture() {
+    // Fuera de la grilla y con lados de largos distintos
+    let cube = Cube::new(Vec3::new(0.3, -0.7, 1.15), Vec3::new(2.1, 0.05, 1.6), &Material::black());
+    let center = (cube.min + cube.max) * 0.5;
+    let half = (cube.max - cube.min) * 0.5;
+    for face in FACES {
+        let point = center + face.normal().component_mul(&half);
+        assert_near(uv_at(&cube, face, point), (0.5, 0.5));
+    }
+}
+
+#[test]
+fn faces_are_read_upright_from_outside() {
+    let cube = Cube::new(Vec3::new(-0.4, 0.2, -1.3), Vec3::new(0.6, 1.7, 0.1), &Material::black());
+    let center = (cube.min + cube.max) * 0.5;
+    let half = (cube.max - cube.min) * 0.5;
+    for face in FACES {
+        // Arriba de la imagen: +Y en los lados, -Z en la cara de arriba y +Z
+        // en la de abajo. La derecha es la de alguien que mira la cara desde afuera
+        let normal = face.normal();
+        let up = match face {
+            Face::Y => Vec3::new(0.0, 0.0, -1.0),
+            Face::NegY => Vec3::new(0.0, 0.0, 1.0),
+            _ => Vec3::new(0.0, 1.0, 0.0),
+        };
+        let right = (-normal).cross(&up);
+        let on_face = center + normal.component_mul(&half);
+        let (u, v) = uv_at(&cube, face, on_face + right * 0.1);
+        assert!(u > 0.5 && (v - 0.5).abs() < 1e-4, "cara {:?}: a la derecha uv ({}, {})", face, u, v);
+        let (u, v) = uv_at(&cube, face, on_face + up * 0.1);
+        assert!(v > 0.5 && (u - 0.5).abs() < 1e-4, "cara {:?}: arriba uv ({}, {})", face, u, v);
+    }
+}
+
+#[test]
+fn merged_rows_repeat_the_texture_once_per_cube() {
+    let material = Material::black().named("bloque");
+    // Fuera de la grilla pero en cuartos exactos, para que las caras coincidan
+    let cubes = (0..4)
+        .map(|i| {
+            let min = Vec3::new(0.25 + i as f32, 0.0, 0.0);
+            SceneObject::Cube(Cube::new(min, min + Vec3::repeat(1.0), &material))
+        })
+        .collect();
+    let merged = merge_cubes(cubes);
+    assert_eq!(merged.len(), 1);
+    let cube = merged[0].as_cube().expect("la fila queda en un cubo");
+    assert_eq!(cube.uv_repeat, Vec3::new(4.0, 1.0, 1.0));
+    for i in 0..4 {
+        let top = Vec3::new(0.75 + i as f32, 1.0, 0.5);
+        let (u, v) = uv_at(cube, Face::Y, top);
+        assert_near((u.fract(), v.fract()), (0.5, 0.5));
+    }
+}
+
+// Altura de la cara de arriba de la base de pasto
+const BASE_TOP: f32 = -0.09375;
+
+// Antes las coordenadas de la cara de arriba eran `(x.abs() % 1.0, z.abs() % 1.0)`
+// y la textura quedaba espejada a los dos lados de x = 0, con una costura en el
+// medio de la base de pasto
+fn old_top_uv(point: Vec3) -> (f32, f32) {
+    (point.x.abs() % 1.0, point.z.abs() % 1.0)
+}
+
+// Cuántos pares de puntos reflejados en x = 0 caen en el mismo texel de la
+// textura del pasto, de cuántos, y si la u crece de un lado al otro en todos
+fn mirrored_texels(uv: &dyn Fn(Vec3) -> (f32, f32)) -> (usize, usize, bool) {
+    let grass = default_materials()["grass"].texture.clone().expect("el pasto tiene textura");
+    let (mut same, mut pairs, mut increasing) = (0, 0, true);
+    for z in [-0.9, -0.3, 0.4, 1.1] {
+        for k in 0..40 {
+            let x = 0.005 + 0.013 * k as f32;
+            let (left, right) = (uv(Vec3::new(-x, BASE_TOP, z)), uv(Vec3::new(x, BASE_TOP, z)));
+            same += (grass.get_color(left.0, left.1) == grass.get_color(right.0, right.1)) as usize;
+            pairs += 1;
+            increasing &= left.0 < right.0;
+        }
+    }
+    (same, pairs, increasing)
+}
+
+#[test]
+fn grass_base_has_no_seam_at_the_origin() {
+    let grass = &default_materials()["grass"];
+    let base = Cube::new(Vec3::new(-1.40625, -0.234375, -1.40625), Vec3::new(1.40625, BASE_TOP, 1.40625), grass);
+    let top_uv = |point: Vec3| uv_at(&base, Face::Y, point);
+
+    // La u sigue de largo por x = 0 y los dos lados muestran texels distintos
+    let (same, pairs, increasing) = mirrored_texels(&top_uv);
+    assert!(increasing, "la u no crece de un lado al otro de x = 0");
+    assert!(same * 5 < pairs, "{} de {} pares reflejados dan el mismo texel", same, pairs);
+    let (left, right) = (top_uv(Vec3::new(-1e-3, BASE_TOP, 0.2)), top_uv(Vec3::new(1e-3, BASE_TOP, 0.2)));
+    assert!((right.0 - left.0).abs() < 1e-3 && (right.1 - left.1).abs() < 1e-4, "salto en x = 0: {:?} y {:?}", left, right);
+
+    // Con la cuenta vieja todos los pares coinciden: la prueba la habría agarrado
+    let (same, pairs, increasing) = mirrored_texels(&old_top_uv);
+    assert!(same == pairs && !increasing, "la cuenta vieja no se refleja: {} de {}", same, pairs);
+}
+
+// Una fila de bloques de pasto se ve igual antes y después de fusionarla: la
+// caja larga repite la textura en cada celda y las caras de cada lado siguen
+// con su material
+#[test]
+fn merged_grass_rows_render_like_the_original_cubes() {
+    let row = || {
+        (0..4)
+            .fold(DioramaBuilder::new(), |builder, i| {
+                let min = Vec3::new(i as f32 - 2.0, -0.5, -0.5);
+                builder.add_cube(min, min + Vec3::repeat(1.0), "grass")
+            })
+            .add_light(SceneLight::sun(Vec3::new(3.0, 6.0, 4.0), Color::white(), 1.5))
+            .camera(Vec3::new(1.2, 1.6, 3.0), Vec3::new(0.0, 0.0, 0.0))
+            .build()
+            .expect("el pasto es del registro")
+    };
+    let blocks = || Scene::load("scenes/blocks.ron").expect("la escena del repositorio carga");
+    let render = |scene: &Scene| Renderer::new(RenderSettings::default()).size(96, 72).render(scene, &scene.camera).into_raw();
+
+    for (name, scene, fewer) in [("fila", &row as &dyn Fn() -> Scene, 3), ("bloques", &blocks, 9)] {
+        let (scene, mut merged) = (scene(), scene());
+        assert_eq!(merged.merge_cubes(), fewer, "{}", name);
+        assert!(render(&scene) == render(&merged), "{}: el render cambia al fusionar", name);
+    }
+}
@@ -0,0 +1,58 @@
+use raycasting_diorama::camera::Camera;
+use raycasting_diorama::framebuffer::Framebuffer;
+use raycasting_diorama::renderer::{render, sky_color};
+use raycasting_diorama::scene::Scene;
+use raycasting_diorama::settings::RenderSettings;
+use raycasting_diorama::velocity::{motion_of, pixel_of, MotionVectors};
+use raycasting_diorama::Vec3;
+
+const WIDTH: usize = 160;
+const HEIGHT: usize = 120;
+
+fn camera_at(eye: Vec3, center: Vec3) -> Camera {
+    Camera::new(eye, center, Vec3::new(0.0, 1.0, 0.0))
+}
+
+fn assert_near(actual: [f32; 2], expected: [f32; 2]) {
+    let error = (actual[0] - expected[0]).abs().max((actual[1] - expected[1]).abs());
+    assert!(error < 1e-3, "se esperaba {:?}, no {:?}", expected, actual);
+}
+
+#[test]
+fn still_camera_gives_exact_zeros() {
+    let scene = Scene::diorama();
+    let settings = RenderSettings { bias: scene.default_bias(), ..RenderSettings::default() };
+    let mut framebuffer = Framebuffer::new(WIDTH, HEIGHT);
+    render(&mut framebuffer, &scene.objects, &scene.camera, &scene.lights, sky_color(&scene.lights), &settings);
+    let motion = MotionVectors::compute(&framebuffer, &scene.camera, &scene.camera.clone());
+    assert!(motion.is_zero());
+    assert_eq!(motion.vectors.len(), WIDTH * HEIGHT);
+}
+
+#[test]
+fn projection_inverts_the_primary_rays() {
+    let camera = camera_at(Vec3::new(1.0, 2.0, 5.0), Vec3::new(0.0, 0.5, 0.0));
+    for (x, y) in [(0, 0), (80, 60), (159, 7), (33, 119)] {
+        for depth in [0.5, 3.0, 40.0, f32::INFINITY] {
+            assert_near(motion_of(&camera, &camera, x, y, depth, WIDTH, HEIGHT), [0.0, 0.0]);
+        }
+    }
+    let behind = pixel_of(&camera, &Vec3::new(2.0, 4.0, 10.0), WIDTH, HEIGHT);
+    assert!(behind.is_none());
+}
+
+#[test]
+fn sideways_move_shifts_by_parallax() {
+    // Antes la cámara estaba `shift` más a la derecha: lo que está a `depth`
+    // se corre a la derecha tanto como ocupa `shift` en la pantalla a esa distancia
+    let (shift, depth) = (0.2, 5.0);
+    let camera = camera_at(Vec3::new(0.0, 0.0, depth), Vec3::zeros());
+    let previous = camera_at(Vec3::new(shift, 0.0, depth), Vec3::new(shift, 0.0, 0.0));
+    let aspect_ratio = WIDTH as f32 / HEIGHT as f32;
+    let expected = shift / (depth * aspect_ratio * camera.perspective_scale()) * 0.5 * WIDTH as f32;
+    let (x, y) = (WIDTH / 2, HEIGHT / 2);
+    assert_near(motion_of(&camera, &previous, x, y, depth, WIDTH, HEIGHT), [expected, 0.0]);
+
+    // El cielo está infinitamente lejos: moverse sin girar no lo corre
+    assert_near(motion_of(&camera, &previous, x, y, f32::INFINITY, WIDTH, HEIGHT), [0.0, 0.0]);
+}